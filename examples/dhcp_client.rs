@@ -50,15 +50,29 @@ fn main() {
     let mut sockets = SocketSet::new(vec![]);
     let dhcp_handle = sockets.add(dhcp_socket);
 
+    // Fall back to a link-local address if no DHCP server answers.
+    let mut link_local_at = Some(Instant::now() + Duration::from_secs(10));
+
     loop {
         let timestamp = Instant::now();
         iface.poll(timestamp, &mut device, &mut sockets);
 
+        if link_local_at.is_some_and(|at| timestamp >= at) {
+            debug!("No DHCP server, configuring a link-local address");
+            iface.set_ipv4_link_local(true);
+            link_local_at = None;
+        }
+        while let Some(event) = iface.acd_event() {
+            debug!("Address conflict detection: {:?}", event);
+        }
+
         let event = sockets.get_mut::<dhcpv4::Socket>(dhcp_handle).poll();
         match event {
             None => {}
             Some(dhcpv4::Event::Configured(config)) => {
                 debug!("DHCP config acquired!");
+                iface.set_ipv4_link_local(false);
+                link_local_at = None;
 
                 debug!("IP address:      {}", config.address);
                 set_ipv4_addr(&mut iface, config.address);
@@ -79,6 +93,7 @@ fn main() {
                 debug!("DHCP lost config!");
                 iface.update_ip_addrs(|addrs| addrs.clear());
                 iface.routes_mut().remove_default_ipv4_route();
+                link_local_at = Some(timestamp + Duration::from_secs(10));
             }
         }
 
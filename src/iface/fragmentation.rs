@@ -165,6 +165,12 @@ impl<K> PacketAssembler<K> {
     /// Get an immutable slice of the underlying packet data, if reassembly complete.
     /// This will mark the assembler as empty, so that it can be reused.
     pub(crate) fn assemble(&mut self) -> Option<&'_ [u8]> {
+        self.assemble_mut().map(|packet| &*packet)
+    }
+
+    /// Get a mutable slice of the underlying packet data, if reassembly complete.
+    /// This will mark the assembler as empty, so that it can be reused.
+    pub(crate) fn assemble_mut(&mut self) -> Option<&'_ mut [u8]> {
         if !self.is_complete() {
             return None;
         }
@@ -172,7 +178,7 @@ impl<K> PacketAssembler<K> {
         // NOTE: we can unwrap because `is_complete` already checks this.
         let total_size = self.total_size.unwrap();
         self.reset();
-        Some(&self.buffer[..total_size])
+        Some(&mut self.buffer[..total_size])
     }
 
     /// Returns `true` when all fragments have been received, otherwise `false`.
@@ -248,6 +254,8 @@ pub(crate) const MAX_DECOMPRESSED_LEN: usize = 1500;
 pub(crate) enum FragKey {
     #[cfg(feature = "proto-ipv4-fragmentation")]
     Ipv4(Ipv4FragKey),
+    #[cfg(feature = "proto-ipv6-fragmentation")]
+    Ipv6(Ipv6FragKey),
     #[cfg(feature = "proto-sixlowpan-fragmentation")]
     Sixlowpan(SixlowpanFragKey),
 }
@@ -284,6 +292,8 @@ pub(crate) struct Fragmenter {
 
     #[cfg(feature = "proto-ipv4-fragmentation")]
     pub ipv4: Ipv4Fragmenter,
+    #[cfg(feature = "proto-ipv6-fragmentation")]
+    pub ipv6: Ipv6Fragmenter,
     #[cfg(feature = "proto-sixlowpan-fragmentation")]
     pub sixlowpan: SixlowpanFragmenter,
 }
@@ -301,6 +311,23 @@ pub(crate) struct Ipv4Fragmenter {
     pub ident: u16,
 }
 
+#[cfg(feature = "proto-ipv6-fragmentation")]
+pub(crate) struct Ipv6Fragmenter {
+    /// The IPv6 representation of the unfragmented packet.
+    pub repr: Ipv6Repr,
+    /// The destination hardware address.
+    #[cfg(feature = "medium-ethernet")]
+    pub dst_hardware_addr: EthernetAddress,
+    /// The identification of the fragmented packet.
+    pub ident: u32,
+    /// The length of the unfragmentable part, which is repeated in every fragment: the IPv6
+    /// header, and the extension headers that are processed on the way to the destination.
+    pub unfragmentable_len: usize,
+    /// The offset of the last extension header of the unfragmentable part, if any. The Fragment
+    /// header is inserted after it.
+    pub last_ext_header: Option<usize>,
+}
+
 #[cfg(feature = "proto-sixlowpan-fragmentation")]
 pub(crate) struct SixlowpanFragmenter {
     /// The datagram size that is used for the fragmentation headers.
@@ -341,6 +368,22 @@ impl Fragmenter {
                 ident: 0,
             },
 
+            #[cfg(feature = "proto-ipv6-fragmentation")]
+            ipv6: Ipv6Fragmenter {
+                repr: Ipv6Repr {
+                    src_addr: Ipv6Address::default(),
+                    dst_addr: Ipv6Address::default(),
+                    next_header: IpProtocol::Unknown(0),
+                    payload_len: 0,
+                    hop_limit: 0,
+                },
+                #[cfg(feature = "medium-ethernet")]
+                dst_hardware_addr: EthernetAddress::default(),
+                ident: 0,
+                unfragmentable_len: 0,
+                last_ext_header: None,
+            },
+
             #[cfg(feature = "proto-sixlowpan-fragmentation")]
             sixlowpan: SixlowpanFragmenter {
                 datagram_size: 0,
@@ -385,6 +428,21 @@ impl Fragmenter {
             }
         }
 
+        #[cfg(feature = "proto-ipv6-fragmentation")]
+        {
+            self.ipv6.repr = Ipv6Repr {
+                src_addr: Ipv6Address::default(),
+                dst_addr: Ipv6Address::default(),
+                next_header: IpProtocol::Unknown(0),
+                payload_len: 0,
+                hop_limit: 0,
+            };
+            #[cfg(feature = "medium-ethernet")]
+            {
+                self.ipv6.dst_hardware_addr = EthernetAddress::default();
+            }
+        }
+
         #[cfg(feature = "proto-sixlowpan-fragmentation")]
         {
             self.sixlowpan.datagram_size = 0;
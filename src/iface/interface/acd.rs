@@ -0,0 +1,453 @@
+// IPv4 Address Conflict Detection, see RFC 5227, and dynamic configuration of IPv4 link-local
+// addresses, see RFC 3927.
+
+use super::*;
+
+use core::mem;
+
+use crate::rand::Rand;
+use heapless::Deque;
+
+/// Maximum random delay before sending the first probe.
+const PROBE_WAIT: Duration = Duration::from_secs(1);
+/// Number of probes sent for an address.
+const PROBE_NUM: u8 = 3;
+/// Minimum delay between two probes.
+const PROBE_MIN: Duration = Duration::from_secs(1);
+/// Maximum delay between two probes.
+const PROBE_MAX: Duration = Duration::from_secs(2);
+/// Delay after the last probe before the address is assigned.
+const ANNOUNCE_WAIT: Duration = Duration::from_secs(2);
+/// Number of announcements sent once an address is assigned.
+const ANNOUNCE_NUM: u8 = 2;
+/// Number of conflicts after which link-local addresses are selected at a limited rate.
+const MAX_CONFLICTS: u16 = 10;
+/// Delay between two attempts to select a link-local address, after `MAX_CONFLICTS` conflicts.
+const RATE_LIMIT_INTERVAL: Duration = Duration::from_secs(60);
+/// Minimum time between two defenses of an address. An address that is used by another node
+/// again within this time is given up.
+const DEFEND_INTERVAL: Duration = Duration::from_secs(10);
+
+/// An event of the Address Conflict Detection of an IPv4 address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AcdEvent {
+    /// No other node uses the address, which is now assigned to the interface.
+    Assigned(Ipv4Address),
+    /// Another node uses the address, which was removed from the interface.
+    Conflict(Ipv4Address),
+}
+
+/// An address that is probed before it is assigned.
+#[derive(Debug, Clone, Copy)]
+struct Tentative {
+    addr: Ipv4Address,
+    /// The number of probes that have been sent.
+    probes: u8,
+    /// When to send the next probe, or to assign the address.
+    next_at: Instant,
+}
+
+/// An assigned address that is defended against other nodes.
+#[derive(Debug, Clone, Copy)]
+struct Assigned {
+    addr: Ipv4Address,
+    /// When the address was last defended.
+    defended_at: Option<Instant>,
+}
+
+/// The state of the dynamic configuration of a link-local address.
+#[derive(Debug)]
+struct LinkLocal {
+    /// Selects the candidate addresses. It is seeded from the hardware address, so that the
+    /// same address is selected every time the interface starts.
+    rand: Rand,
+    /// The number of conflicts that were detected for the selected addresses.
+    conflicts: u16,
+    /// When to select the next address.
+    next_at: Instant,
+}
+
+/// The state of a DHCPv4 client, for which the link-local address is a fallback.
+#[cfg(feature = "socket-dhcpv4")]
+#[derive(Debug, Clone, Copy)]
+enum Dhcpv4 {
+    /// No address was acquired, and a link-local address is selected from the given time.
+    Pending(Instant),
+    /// An address was acquired, and no link-local address is used.
+    Configured,
+}
+
+/// The state of Address Conflict Detection on an interface.
+#[derive(Debug)]
+pub(crate) struct Acd {
+    /// Whether the addresses that are added to the interface are probed.
+    enabled: bool,
+    tentative: Vec<Tentative, IFACE_MAX_ADDR_COUNT>,
+    assigned: Vec<Assigned, IFACE_MAX_ADDR_COUNT>,
+    link_local: Option<LinkLocal>,
+    #[cfg(feature = "socket-dhcpv4")]
+    dhcpv4: Option<Dhcpv4>,
+    /// The addresses used by another node, which are removed from the interface by
+    /// `acd_egress`.
+    conflicts: Vec<Ipv4Address, IFACE_MAX_ADDR_COUNT>,
+    events: Deque<AcdEvent, IFACE_MAX_ADDR_COUNT>,
+}
+
+/// Returns `true` if `cidr` is an IPv4 link-local address.
+fn is_link_local(cidr: &IpCidr) -> bool {
+    matches!(cidr, IpCidr::Ipv4(cidr) if cidr.address().is_link_local())
+}
+
+impl Acd {
+    pub(super) fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            tentative: Vec::new(),
+            assigned: Vec::new(),
+            link_local: None,
+            #[cfg(feature = "socket-dhcpv4")]
+            dhcpv4: None,
+            conflicts: Vec::new(),
+            events: Deque::new(),
+        }
+    }
+
+    /// Returns `true` when the address is probed, or is about to be removed after a conflict.
+    pub(super) fn is_tentative(&self, addr: &Ipv4Address) -> bool {
+        self.tentative.iter().any(|t| t.addr == *addr) || self.conflicts.contains(addr)
+    }
+
+    /// Return the time at which the next probe must be sent, or the next link-local address
+    /// selected.
+    pub(super) fn poll_at(&self, ip_addrs: &[IpCidr]) -> Option<Instant> {
+        self.tentative
+            .iter()
+            .map(|t| t.next_at)
+            .chain(self.link_local_at(ip_addrs))
+            .min()
+    }
+
+    /// Return the time at which a link-local address must be selected, if the interface needs
+    /// one. It is only a fallback while a DHCPv4 client is acquiring an address.
+    fn link_local_at(&self, ip_addrs: &[IpCidr]) -> Option<Instant> {
+        let link_local = self.link_local.as_ref()?;
+        if ip_addrs.iter().any(is_link_local) {
+            return None;
+        }
+
+        #[cfg(feature = "socket-dhcpv4")]
+        match self.dhcpv4 {
+            Some(Dhcpv4::Pending(fallback_at)) => return Some(link_local.next_at.max(fallback_at)),
+            Some(Dhcpv4::Configured) => return None,
+            None => (),
+        }
+        Some(link_local.next_at)
+    }
+
+    /// Forget the addresses that are no longer on the interface.
+    fn retain(&mut self, ip_addrs: &[IpCidr]) {
+        let on_iface = |addr: Ipv4Address| ip_addrs.iter().any(|a| a.address() == addr.into());
+        self.tentative.retain(|t| on_iface(t.addr));
+        self.assigned.retain(|a| on_iface(a.addr));
+        self.conflicts.retain(|addr| on_iface(*addr));
+    }
+
+    fn push_event(&mut self, event: AcdEvent) {
+        if self.events.is_full() {
+            self.events.pop_front();
+        }
+        // NOTE(unwrap): we made room above.
+        self.events.push_back(event).unwrap();
+    }
+}
+
+impl LinkLocal {
+    /// Select a pseudo-random address in 169.254.1.0 - 169.254.254.255, see RFC 3927 § 2.1.
+    fn select(&mut self) -> Ipv4Address {
+        let n = self.rand.rand_u32() % (254 * 256);
+        Ipv4Address::new(169, 254, 1 + (n / 256) as u8, (n % 256) as u8)
+    }
+}
+
+impl Interface {
+    /// Return the next event of the Address Conflict Detection, if any.
+    ///
+    /// Addresses that another node uses are removed from the interface, which is reported with
+    /// [`AcdEvent::Conflict`].
+    pub fn acd_event(&mut self) -> Option<AcdEvent> {
+        self.inner.acd.events.pop_front()
+    }
+
+    /// Enable or disable the dynamic configuration of an IPv4 link-local address.
+    ///
+    /// When enabled, the interface selects an address in 169.254.0.0/16, checks that no other
+    /// node uses it with Address Conflict Detection, and selects another one on conflict. It is
+    /// meant as a fallback for networks without a DHCP server: with a DHCPv4 socket, the address
+    /// is only selected if no address was acquired after
+    /// [`link_local_timeout`](crate::socket::dhcpv4::Socket::link_local_timeout), and it is
+    /// removed once an address is acquired. Otherwise, it should be disabled once a routable
+    /// address is configured.
+    pub fn set_ipv4_link_local(&mut self, enabled: bool) {
+        let inner = &mut self.inner;
+        if inner.caps.medium != Medium::Ethernet || enabled == inner.acd.link_local.is_some() {
+            return;
+        }
+
+        if enabled {
+            let mut seed = [0; 8];
+            seed[..6].copy_from_slice(inner.hardware_addr.as_bytes());
+            inner.acd.link_local = Some(LinkLocal {
+                rand: Rand::new(u64::from_le_bytes(seed)),
+                conflicts: 0,
+                next_at: inner.now,
+            });
+        } else {
+            inner.acd.link_local = None;
+            self.update_ip_addrs(|ip_addrs| ip_addrs.retain(|cidr| !is_link_local(cidr)));
+        }
+    }
+
+    /// Send the probes of the Address Conflict Detection, assign the addresses for which no
+    /// other node was detected, and select a link-local address if needed.
+    pub(super) fn acd_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        let now = self.inner.now;
+        if !self.inner.acd.conflicts.is_empty() {
+            let conflicts = mem::take(&mut self.inner.acd.conflicts);
+            self.update_ip_addrs(|ip_addrs| {
+                ip_addrs.retain(|cidr| match cidr {
+                    IpCidr::Ipv4(cidr) => !conflicts.contains(&cidr.address()),
+                    #[allow(unreachable_patterns)]
+                    _ => true,
+                })
+            });
+        }
+        self.inner.acd.retain(&self.inner.ip_addrs);
+        self.acd_select_link_local();
+
+        let acd = &mut self.inner.acd;
+        while let Some(i) = acd
+            .tentative
+            .iter()
+            .position(|t| t.next_at <= now && t.probes >= PROBE_NUM)
+        {
+            let addr = acd.tentative.swap_remove(i).addr;
+            net_debug!("acd: assigned {}", addr);
+            // NOTE(unwrap): there are at most as many assigned addresses as addresses.
+            acd.assigned
+                .push(Assigned {
+                    addr,
+                    defended_at: None,
+                })
+                .unwrap();
+            acd.push_event(AcdEvent::Assigned(addr));
+            let count = self.inner.announcer.count().max(ANNOUNCE_NUM);
+            self.inner.announcer.start(addr.into(), count, now);
+        }
+
+        let acd = &mut self.inner.acd;
+        let Some(tentative) = acd.tentative.iter_mut().find(|t| t.next_at <= now) else {
+            return false;
+        };
+
+        let Some(tx_token) = device.transmit(now) else {
+            return false;
+        };
+
+        tentative.probes += 1;
+        tentative.next_at = if tentative.probes < PROBE_NUM {
+            let spread = (PROBE_MAX - PROBE_MIN).total_millis();
+            let delay = self.inner.rand.rand_u32() as u64 % spread;
+            now + PROBE_MIN + Duration::from_millis(delay)
+        } else {
+            now + ANNOUNCE_WAIT
+        };
+        let addr = tentative.addr;
+
+        net_trace!("acd: sending ARP probe for {}", addr);
+        let arp_repr = ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Request,
+            source_hardware_addr: self.inner.hardware_addr.ethernet_or_panic(),
+            source_protocol_addr: Ipv4Address::UNSPECIFIED,
+            target_hardware_addr: EthernetAddress::default(),
+            target_protocol_addr: addr,
+        };
+        if let Err(err) =
+            self.inner
+                .dispatch_ethernet(tx_token, arp_repr.buffer_len(), |mut frame| {
+                    frame.set_dst_addr(EthernetAddress::BROADCAST);
+                    frame.set_ethertype(EthernetProtocol::Arp);
+                    arp_repr.emit(&mut ArpPacket::new_unchecked(frame.payload_mut()))
+                })
+        {
+            net_debug!("Failed to send ARP probe: {:?}", err);
+        }
+
+        true
+    }
+
+    /// Select a link-local address if the interface needs one, or remove it once a DHCPv4
+    /// client acquired an address.
+    fn acd_select_link_local(&mut self) {
+        #[cfg(feature = "socket-dhcpv4")]
+        if self.inner.acd.link_local.is_some()
+            && matches!(self.inner.acd.dhcpv4, Some(Dhcpv4::Configured))
+            && self.inner.ip_addrs.iter().any(is_link_local)
+        {
+            net_debug!("acd: removing the link-local address, DHCP acquired an address");
+            self.update_ip_addrs(|ip_addrs| ip_addrs.retain(|cidr| !is_link_local(cidr)));
+            return;
+        }
+
+        let now = self.inner.now;
+        if !matches!(self.inner.acd.link_local_at(&self.inner.ip_addrs), Some(at) if at <= now) {
+            return;
+        }
+        // NOTE(unwrap): a time is only returned while link-local configuration is enabled.
+        let link_local = self.inner.acd.link_local.as_mut().unwrap();
+        let addr = link_local.select();
+        if self.inner.ip_addrs.is_full() {
+            net_debug!("acd: no room for a link-local address");
+            link_local.next_at = now + RATE_LIMIT_INTERVAL;
+            return;
+        }
+
+        net_debug!("acd: selected link-local address {}", addr);
+        self.update_ip_addrs(|ip_addrs| {
+            // NOTE(unwrap): we checked there is room above.
+            ip_addrs
+                .push(IpCidr::Ipv4(Ipv4Cidr::new(addr, 16)))
+                .unwrap();
+        });
+    }
+}
+
+impl InterfaceInner {
+    /// Start the Address Conflict Detection of the IPv4 addresses that are not in `old_addrs`,
+    /// and stop it for the addresses that were removed.
+    pub(super) fn acd_update(&mut self, old_addrs: &[IpCidr]) {
+        self.acd.retain(&self.ip_addrs);
+
+        for i in 0..self.ip_addrs.len() {
+            #[allow(irrefutable_let_patterns)] // if only ipv4 is enabled
+            if let IpCidr::Ipv4(cidr) = self.ip_addrs[i] {
+                if !old_addrs.contains(&self.ip_addrs[i]) {
+                    self.acd_start(cidr.address());
+                }
+            }
+        }
+    }
+
+    /// Start the Address Conflict Detection of a new address. Link-local addresses are always
+    /// probed when they are dynamically configured.
+    fn acd_start(&mut self, addr: Ipv4Address) {
+        let link_local = addr.is_link_local() && self.acd.link_local.is_some();
+        if !(self.acd.enabled || link_local)
+            || self.caps.medium != Medium::Ethernet
+            || !addr.is_unicast()
+            || addr.is_loopback()
+            || self.acd.is_tentative(&addr)
+        {
+            return;
+        }
+
+        let delay = self.rand.rand_u32() as u64 % PROBE_WAIT.total_millis();
+        let tentative = Tentative {
+            addr,
+            probes: 0,
+            next_at: self.now + Duration::from_millis(delay),
+        };
+
+        if self.acd.tentative.push(tentative).is_err() {
+            net_debug!(
+                "acd: unable to perform address conflict detection for {}",
+                addr
+            );
+        }
+    }
+
+    /// Check whether a received ARP packet shows that another node uses one of our addresses.
+    ///
+    /// Returns `true` if the packet must not be processed further.
+    pub(super) fn acd_process_arp(
+        &mut self,
+        source_hardware_addr: EthernetAddress,
+        source_protocol_addr: Ipv4Address,
+        target_protocol_addr: Ipv4Address,
+    ) -> bool {
+        if HardwareAddress::Ethernet(source_hardware_addr) == self.hardware_addr {
+            return false;
+        }
+
+        // While probing, any packet from the address, or probe for it, is a conflict.
+        if let Some(tentative) = self.acd.tentative.iter().find(|t| {
+            t.addr == source_protocol_addr
+                || (source_protocol_addr.is_unspecified() && t.addr == target_protocol_addr)
+        }) {
+            let addr = tentative.addr;
+            self.acd_conflict(addr);
+            return true;
+        }
+
+        // Once assigned, the address is defended once, and given up if another node keeps
+        // using it.
+        let now = self.now;
+        let Some(assigned) = self
+            .acd
+            .assigned
+            .iter_mut()
+            .find(|a| a.addr == source_protocol_addr)
+        else {
+            return false;
+        };
+        let addr = assigned.addr;
+        match assigned.defended_at {
+            Some(defended_at) if now < defended_at + DEFEND_INTERVAL => {
+                self.acd_conflict(addr);
+                true
+            }
+            _ => {
+                net_debug!("acd: defending {}", addr);
+                assigned.defended_at = Some(now);
+                self.announcer.start(addr.into(), 1, now);
+                false
+            }
+        }
+    }
+
+    /// Report the state of a DHCPv4 client: a link-local address is used from `fallback_at`,
+    /// or not at all once the client acquired an address and `fallback_at` is `None`.
+    #[cfg(feature = "socket-dhcpv4")]
+    pub(crate) fn acd_dhcpv4_update(&mut self, fallback_at: Option<Instant>) {
+        self.acd.dhcpv4 = Some(match fallback_at {
+            Some(fallback_at) => Dhcpv4::Pending(fallback_at),
+            None => Dhcpv4::Configured,
+        });
+    }
+
+    /// Give up an address that another node is using. It is no longer used, and is removed
+    /// from the interface by `acd_egress`.
+    fn acd_conflict(&mut self, addr: Ipv4Address) {
+        net_debug!("acd: {} is used by another node", addr);
+        self.acd.tentative.retain(|t| t.addr != addr);
+        self.acd.assigned.retain(|a| a.addr != addr);
+        // NOTE(unwrap): the address was probed or assigned, and there are at most as many
+        // conflicts as addresses.
+        self.acd.conflicts.push(addr).unwrap();
+        self.acd.push_event(AcdEvent::Conflict(addr));
+
+        if let Some(link_local) = &mut self.acd.link_local {
+            if addr.is_link_local() {
+                link_local.conflicts = link_local.conflicts.saturating_add(1);
+                link_local.next_at = if link_local.conflicts >= MAX_CONFLICTS {
+                    self.now + RATE_LIMIT_INTERVAL
+                } else {
+                    self.now
+                };
+            }
+        }
+    }
+}
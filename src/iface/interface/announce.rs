@@ -0,0 +1,177 @@
+// Announcement of the addresses of the interface to its neighbors, with gratuitous ARP
+// (see RFC 5227 § 2.3) and unsolicited Neighbor Advertisements (see RFC 4861 § 7.2.6).
+
+use super::*;
+
+/// Time between two announcements of an address.
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// An address that is being announced.
+#[derive(Debug, Clone, Copy)]
+struct Announcement {
+    addr: IpAddress,
+    /// The number of announcements that remain to be sent.
+    remaining: u8,
+    /// When to send the next announcement.
+    next_at: Instant,
+}
+
+/// The state of the announcements of an interface.
+#[derive(Debug)]
+pub(crate) struct Announcer {
+    /// The number of announcements sent when an address changes. Zero disables them.
+    count: u8,
+    pending: Vec<Announcement, IFACE_MAX_ADDR_COUNT>,
+}
+
+impl Announcer {
+    pub(super) fn new(count: u8) -> Self {
+        Self {
+            count,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Return the number of announcements sent when an address changes.
+    #[cfg(feature = "proto-ipv4")]
+    pub(super) fn count(&self) -> u8 {
+        self.count
+    }
+
+    /// Announce an address `count` times, starting at `now`.
+    pub(super) fn start(&mut self, addr: IpAddress, count: u8, now: Instant) {
+        let is_loopback = match addr {
+            #[cfg(feature = "proto-ipv4")]
+            IpAddress::Ipv4(addr) => addr.is_loopback(),
+            #[cfg(feature = "proto-ipv6")]
+            IpAddress::Ipv6(addr) => addr.is_loopback(),
+        };
+        if count == 0 || !addr.is_unicast() || is_loopback {
+            return;
+        }
+
+        let announcement = Announcement {
+            addr,
+            remaining: count,
+            next_at: now,
+        };
+        if let Some(a) = self.pending.iter_mut().find(|a| a.addr == addr) {
+            *a = announcement;
+        } else if self.pending.push(announcement).is_err() {
+            net_debug!("announce: unable to announce {}", addr);
+        }
+    }
+
+    /// Return the time at which the next announcement must be sent.
+    pub(super) fn poll_at(&self) -> Option<Instant> {
+        self.pending.iter().map(|a| a.next_at).min()
+    }
+}
+
+impl Interface {
+    /// Announce the addresses of the interface to its neighbors, for example after a failover
+    /// to this interface, with gratuitous ARP requests for the IPv4 addresses, and unsolicited
+    /// Neighbor Advertisements overriding the cached hardware address for the IPv6 addresses.
+    ///
+    /// Each address is announced the number of times set by [`Config::announce_count`], or
+    /// once if the automatic announcements are disabled.
+    pub fn announce(&mut self) {
+        let count = self.inner.announcer.count.max(1);
+        self.inner.announce_addrs(&[], count);
+    }
+
+    /// Send the announcements of the addresses of the interface.
+    pub(super) fn announce_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        let now = self.inner.now;
+        let ip_addrs = &self.inner.ip_addrs;
+
+        // Addresses can be removed while they are announced.
+        self.inner
+            .announcer
+            .pending
+            .retain(|a| ip_addrs.iter().any(|cidr| cidr.address() == a.addr));
+
+        // Tentative addresses are announced once they are assigned.
+        let inner = &self.inner;
+        let Some(i) = inner
+            .announcer
+            .pending
+            .iter()
+            .position(|a| a.next_at <= now && !inner.is_tentative(&a.addr))
+        else {
+            return false;
+        };
+
+        let Some(tx_token) = device.transmit(now) else {
+            return false;
+        };
+
+        let announcer = &mut self.inner.announcer;
+        let announcement = &mut announcer.pending[i];
+        let addr = announcement.addr;
+        announcement.remaining -= 1;
+        announcement.next_at = now + ANNOUNCE_INTERVAL;
+        if announcement.remaining == 0 {
+            announcer.pending.swap_remove(i);
+        }
+
+        net_trace!("announce: announcing {}", addr);
+        let result = match addr {
+            #[cfg(feature = "proto-ipv4")]
+            IpAddress::Ipv4(addr) => {
+                let arp_repr = self.inner.announce_arp_repr(addr);
+                self.inner
+                    .dispatch_ethernet(tx_token, arp_repr.buffer_len(), |mut frame| {
+                        frame.set_dst_addr(EthernetAddress::BROADCAST);
+                        frame.set_ethertype(EthernetProtocol::Arp);
+                        arp_repr.emit(&mut ArpPacket::new_unchecked(frame.payload_mut()))
+                    })
+            }
+            #[cfg(feature = "proto-ipv6")]
+            IpAddress::Ipv6(addr) => {
+                let packet = self.inner.announce_advert_packet(addr);
+                self.inner.dispatch_ip(
+                    tx_token,
+                    PacketMeta::default(),
+                    packet,
+                    &mut self.fragmenter,
+                )
+            }
+        };
+
+        if let Err(err) = result {
+            net_debug!("Failed to announce {}: {:?}", addr, err);
+        }
+        true
+    }
+}
+
+impl InterfaceInner {
+    /// Announce the addresses of the interface that are not in `old_addrs` `count` times, and
+    /// stop announcing the addresses that were removed.
+    pub(super) fn announce_addrs(&mut self, old_addrs: &[IpCidr], count: u8) {
+        if self.caps.medium != Medium::Ethernet {
+            return;
+        }
+
+        let ip_addrs = &self.ip_addrs;
+        self.announcer
+            .pending
+            .retain(|a| ip_addrs.iter().any(|cidr| cidr.address() == a.addr));
+
+        for cidr in self.ip_addrs.iter() {
+            if !old_addrs.contains(cidr) {
+                self.announcer.start(cidr.address(), count, self.now);
+            }
+        }
+    }
+
+    /// Announce the addresses of the interface that are not in `old_addrs` the number of times
+    /// set in the configuration.
+    pub(super) fn announce_update(&mut self, old_addrs: &[IpCidr]) {
+        self.announce_addrs(old_addrs, self.announcer.count);
+    }
+}
@@ -0,0 +1,206 @@
+// IPv6 Duplicate Address Detection, see RFC 4862 § 5.4.
+
+use super::*;
+
+use heapless::Deque;
+
+/// Maximum random delay before sending the first Neighbor Solicitation.
+const MAX_SOLICITATION_DELAY: Duration = Duration::from_secs(1);
+/// Time between Neighbor Solicitations, and after the last one before the address is assigned.
+const RETRANS_TIMER: Duration = Duration::from_secs(1);
+
+/// An event of the Duplicate Address Detection of an IPv6 address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DadEvent {
+    /// No other node uses the address, which is now assigned to the interface.
+    Assigned(Ipv6Address),
+    /// Another node uses the address, which was removed from the interface.
+    Duplicate(Ipv6Address),
+}
+
+/// An address on which Duplicate Address Detection is performed.
+#[derive(Debug, Clone, Copy)]
+struct Tentative {
+    addr: Ipv6Address,
+    /// The number of Neighbor Solicitations that have been sent.
+    transmissions: u8,
+    /// When to send the next Neighbor Solicitation, or to assign the address.
+    next_at: Instant,
+}
+
+/// The state of Duplicate Address Detection on an interface.
+#[derive(Debug)]
+pub(crate) struct Dad {
+    /// The number of Neighbor Solicitations sent for an address. Zero disables DAD.
+    transmits: u8,
+    tentative: Vec<Tentative, IFACE_MAX_ADDR_COUNT>,
+    events: Deque<DadEvent, IFACE_MAX_ADDR_COUNT>,
+}
+
+impl Dad {
+    pub(super) fn new(transmits: u8) -> Self {
+        Self {
+            transmits,
+            tentative: Vec::new(),
+            events: Deque::new(),
+        }
+    }
+
+    /// Returns `true` when Duplicate Address Detection is in progress for the address.
+    pub(super) fn is_tentative(&self, addr: &Ipv6Address) -> bool {
+        self.tentative.iter().any(|t| t.addr == *addr)
+    }
+
+    /// Return the time at which the next Neighbor Solicitation must be sent.
+    pub(super) fn poll_at(&self) -> Option<Instant> {
+        self.tentative.iter().map(|t| t.next_at).min()
+    }
+
+    fn push_event(&mut self, event: DadEvent) {
+        if self.events.is_full() {
+            self.events.pop_front();
+        }
+        // NOTE(unwrap): we made room above.
+        self.events.push_back(event).unwrap();
+    }
+}
+
+impl Interface {
+    /// Return the next event of the Duplicate Address Detection, if any.
+    ///
+    /// Addresses for which another node is detected are removed from the interface, which
+    /// is reported with [`DadEvent::Duplicate`].
+    pub fn dad_event(&mut self) -> Option<DadEvent> {
+        self.inner.dad.events.pop_front()
+    }
+
+    /// Check whether Duplicate Address Detection is in progress for the given address. Such a
+    /// tentative address is not used until it is assigned.
+    pub fn is_tentative_addr(&self, addr: &Ipv6Address) -> bool {
+        self.inner.dad.is_tentative(addr)
+    }
+
+    /// Send the Neighbor Solicitations of the Duplicate Address Detection, and assign the
+    /// addresses for which no other node was detected.
+    pub(super) fn dad_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        let now = self.inner.now;
+        let ip_addrs = &self.inner.ip_addrs;
+        let dad = &mut self.inner.dad;
+
+        // Addresses can be removed while tentative, e.g. when their lifetime expires.
+        dad.tentative
+            .retain(|t| ip_addrs.iter().any(|a| a.address() == t.addr.into()));
+
+        while let Some(i) = dad
+            .tentative
+            .iter()
+            .position(|t| t.next_at <= now && t.transmissions >= dad.transmits)
+        {
+            let addr = dad.tentative.swap_remove(i).addr;
+            net_debug!("dad: assigned {}", addr);
+            dad.push_event(DadEvent::Assigned(addr));
+        }
+
+        let Some(tentative) = dad.tentative.iter_mut().find(|t| t.next_at <= now) else {
+            return false;
+        };
+
+        let Some(tx_token) = device.transmit(now) else {
+            return false;
+        };
+
+        tentative.transmissions += 1;
+        tentative.next_at = now + RETRANS_TIMER;
+        let addr = tentative.addr;
+
+        net_trace!("dad: sending Neighbor Solicitation for {}", addr);
+        let pkt = self.inner.dad_solicit_packet(addr);
+
+        // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
+        self.inner
+            .dispatch_ip(tx_token, PacketMeta::default(), pkt, &mut self.fragmenter)
+            .unwrap();
+
+        true
+    }
+}
+
+impl InterfaceInner {
+    /// Start the Duplicate Address Detection of the IPv6 addresses that are not in `old_addrs`,
+    /// and stop it for the addresses that were removed.
+    pub(super) fn dad_update(&mut self, old_addrs: &[IpCidr]) {
+        let ip_addrs = &self.ip_addrs;
+        self.dad
+            .tentative
+            .retain(|t| ip_addrs.iter().any(|a| a.address() == t.addr.into()));
+
+        for i in 0..self.ip_addrs.len() {
+            #[allow(irrefutable_let_patterns)] // if only ipv6 is enabled
+            if let IpCidr::Ipv6(cidr) = self.ip_addrs[i] {
+                if !old_addrs.contains(&self.ip_addrs[i]) {
+                    self.dad_start(cidr.address());
+                }
+            }
+        }
+    }
+
+    /// Start the Duplicate Address Detection of a new address.
+    pub(super) fn dad_start(&mut self, addr: Ipv6Address) {
+        if self.dad.transmits == 0
+            || !addr.is_unicast()
+            || addr.is_loopback()
+            || self.dad.is_tentative(&addr)
+        {
+            return;
+        }
+
+        let delay = self.rand.rand_u32() as u64 % MAX_SOLICITATION_DELAY.total_millis();
+        let tentative = Tentative {
+            addr,
+            transmissions: 0,
+            next_at: self.now + Duration::from_millis(delay),
+        };
+
+        if self.dad.tentative.push(tentative).is_err() {
+            net_debug!(
+                "dad: unable to perform duplicate address detection for {}",
+                addr
+            );
+        }
+    }
+
+    /// Remove an address that another node is using.
+    pub(super) fn dad_duplicate(&mut self, addr: Ipv6Address) {
+        net_debug!("dad: {} is a duplicate address", addr);
+        self.dad.tentative.retain(|t| t.addr != addr);
+        self.ip_addrs
+            .retain(|cidr| cidr.address() != IpAddress::Ipv6(addr));
+        if let Some(slaac) = &mut self.slaac {
+            slaac.remove_address(&addr);
+        }
+        self.dad.push_event(DadEvent::Duplicate(addr));
+    }
+
+    /// Create a Neighbor Solicitation for a tentative address. It is sent from the unspecified
+    /// address, to the solicited-node multicast address of the tentative address.
+    fn dad_solicit_packet(&self, addr: Ipv6Address) -> Packet<'static> {
+        let repr = Icmpv6Repr::Ndisc(NdiscRepr::NeighborSolicit {
+            target_addr: addr,
+            lladdr: None,
+        });
+        Packet::new_ipv6(
+            Ipv6Repr {
+                src_addr: Ipv6Address::UNSPECIFIED,
+                dst_addr: addr.solicited_node(),
+                next_header: IpProtocol::Icmpv6,
+                payload_len: repr.buffer_len(),
+                hop_limit: 0xff,
+            },
+            IpPayload::Icmpv6(repr),
+        )
+    }
+}
@@ -31,6 +31,8 @@ impl InterfaceInner {
             #[cfg(feature = "proto-ipv6")]
             EthernetProtocol::Ipv6 => {
                 let ipv6_packet = check!(Ipv6Packet::new_checked(eth_frame.payload()));
+                #[cfg(feature = "proto-ipv6-fragmentation")]
+                let ipv6_packet = self.process_ipv6_fragment(ipv6_packet, fragments)?;
                 self.process_ipv6(sockets, meta, &ipv6_packet)
                     .map(EthernetPacket::Ip)
             }
@@ -63,3 +65,217 @@ impl InterfaceInner {
         })
     }
 }
+
+impl Interface {
+    /// Set the queue that holds the packets waiting for the discovery of their neighbor.
+    ///
+    /// Without a queue, the packets that are transmitted to a neighbor whose hardware address is
+    /// unknown are dropped while it is discovered.
+    ///
+    /// The storage of the queue must be `'static`, since the interface does not borrow it. With
+    /// the `alloc` feature, `Vec`s can be used; otherwise, it is a `static mut` array, or an
+    /// array that is given a `'static` lifetime by a crate such as `static_cell`.
+    pub fn set_pending_queue(&mut self, queue: PendingQueue<'static>) {
+        self.inner.pending_queue = Some(queue);
+    }
+
+    /// Get the queue that holds the packets waiting for the discovery of their neighbor.
+    pub fn pending_queue(&self) -> Option<&PendingQueue<'static>> {
+        self.inner.pending_queue.as_ref()
+    }
+
+    /// Transmit the queued packets whose neighbor has been discovered, and rediscover the
+    /// neighbors of the other ones.
+    pub(super) fn pending_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        let Some(mut queue) = self.inner.pending_queue.take() else {
+            return false;
+        };
+
+        let mut did_something = false;
+        let mut index = 0;
+        while index < queue.len() {
+            if queue.is_expired(index, self.inner.now) {
+                net_debug!(
+                    "pending: neighbor {} not discovered, dropping packet",
+                    queue.get(index).0.neighbor()
+                );
+                queue.drop_packet(index);
+                continue;
+            }
+
+            let (packet, bytes) = queue.get(index);
+            if let NeighborAnswer::RateLimited = self
+                .inner
+                .neighbor_cache
+                .lookup(&packet.neighbor(), self.inner.now)
+            {
+                index += 1;
+                continue;
+            }
+
+            let Some(tx_token) = device.transmit(self.inner.now) else {
+                break;
+            };
+
+            let (src_addr, dst_addr) = packet.addrs();
+            match self.inner.lookup_hardware_addr(
+                tx_token,
+                &src_addr,
+                &dst_addr,
+                &mut self.fragmenter,
+            ) {
+                Ok((HardwareAddress::Ethernet(dst_hardware_addr), tx_token)) => {
+                    let result =
+                        self.inner
+                            .dispatch_ethernet(tx_token, bytes.len(), |mut frame| {
+                                frame.set_dst_addr(dst_hardware_addr);
+                                frame.set_ethertype(match dst_addr.version() {
+                                    #[cfg(feature = "proto-ipv4")]
+                                    IpVersion::Ipv4 => EthernetProtocol::Ipv4,
+                                    #[cfg(feature = "proto-ipv6")]
+                                    IpVersion::Ipv6 => EthernetProtocol::Ipv6,
+                                });
+                                frame.payload_mut().copy_from_slice(bytes);
+                            });
+                    if let Err(err) = result {
+                        net_debug!("Failed to send pending packet: {:?}", err);
+                    }
+                    queue.remove_sent(index);
+                }
+                #[allow(unreachable_patterns)]
+                Ok(_) => unreachable!(),
+                // A discovery request was sent.
+                Err(DispatchError::NeighborPending) => index += 1,
+                Err(DispatchError::NoRoute) => queue.drop_packet(index),
+            }
+            did_something = true;
+        }
+
+        self.inner.pending_queue = Some(queue);
+        did_something
+    }
+
+    /// Probe the next neighbor whose reachability is being verified.
+    pub(super) fn neighbor_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        let now = self.inner.now;
+        if !matches!(self.inner.neighbor_cache.poll_at(now), Some(at) if at <= now) {
+            return false;
+        }
+
+        let Some(tx_token) = device.transmit(now) else {
+            return false;
+        };
+
+        #[cfg_attr(not(feature = "proto-ipv4"), allow(unused_variables))]
+        let Some((neighbor_addr, hardware_addr)) = self.inner.neighbor_cache.probe(now) else {
+            return false;
+        };
+
+        net_trace!("neighbor: probing {}", neighbor_addr);
+        let result = match neighbor_addr {
+            #[cfg(feature = "proto-ipv4")]
+            IpAddress::Ipv4(dst_addr) => {
+                let Some(src_addr) = self.inner.get_source_address_ipv4(&dst_addr) else {
+                    return false;
+                };
+                let dst_hardware_addr = hardware_addr.ethernet_or_panic();
+                let arp_repr = ArpRepr::EthernetIpv4 {
+                    operation: ArpOperation::Request,
+                    source_hardware_addr: self.inner.hardware_addr.ethernet_or_panic(),
+                    source_protocol_addr: src_addr,
+                    target_hardware_addr: dst_hardware_addr,
+                    target_protocol_addr: dst_addr,
+                };
+                self.inner
+                    .dispatch_ethernet(tx_token, arp_repr.buffer_len(), |mut frame| {
+                        frame.set_dst_addr(dst_hardware_addr);
+                        frame.set_ethertype(EthernetProtocol::Arp);
+                        arp_repr.emit(&mut ArpPacket::new_unchecked(frame.payload_mut()))
+                    })
+            }
+            #[cfg(feature = "proto-ipv6")]
+            IpAddress::Ipv6(dst_addr) => {
+                let solicit = Icmpv6Repr::Ndisc(NdiscRepr::NeighborSolicit {
+                    target_addr: dst_addr,
+                    lladdr: Some(self.inner.hardware_addr.into()),
+                });
+                let packet = Packet::new_ipv6(
+                    Ipv6Repr {
+                        src_addr: self.inner.get_source_address_ipv6(&dst_addr),
+                        dst_addr,
+                        next_header: IpProtocol::Icmpv6,
+                        payload_len: solicit.buffer_len(),
+                        hop_limit: 0xff,
+                    },
+                    IpPayload::Icmpv6(solicit),
+                );
+                self.inner.dispatch_ip(
+                    tx_token,
+                    PacketMeta::default(),
+                    packet,
+                    &mut self.fragmenter,
+                )
+            }
+        };
+
+        if let Err(err) = result {
+            net_debug!("Failed to probe neighbor: {:?}", err);
+        }
+        true
+    }
+}
+
+impl InterfaceInner {
+    /// Queue a packet whose neighbor is being discovered.
+    ///
+    /// Returns an error if the packet cannot be queued, and is dropped.
+    pub(super) fn queue_pending(
+        &mut self,
+        ip_repr: &IpRepr,
+        packet: &Packet,
+    ) -> Result<(), DispatchError> {
+        let dst_addr = ip_repr.dst_addr();
+
+        // Fragmented packets are not queued.
+        let size = ip_repr.buffer_len();
+        if size > self.path_mtu(&dst_addr) {
+            return Err(DispatchError::NeighborPending);
+        }
+
+        let (Some(neighbor), Some(queue)) =
+            (self.route(&dst_addr, self.now), self.pending_queue.as_mut())
+        else {
+            return Err(DispatchError::NeighborPending);
+        };
+
+        net_debug!("pending: queueing packet for neighbor {}", neighbor);
+        let caps = &self.caps;
+        let queued = queue.enqueue(
+            neighbor,
+            (ip_repr.src_addr(), dst_addr),
+            size,
+            self.now,
+            |buffer| {
+                ip_repr.emit(&mut *buffer, &caps.checksum);
+                packet.emit_payload(ip_repr, &mut buffer[ip_repr.header_len()..], caps);
+            },
+        );
+        if !queued {
+            return Err(DispatchError::NeighborPending);
+        }
+        Ok(())
+    }
+
+    /// Return the time at which the queued packets should be transmitted, or dropped.
+    pub(super) fn pending_poll_at(&self) -> Option<Instant> {
+        let queue = self.pending_queue.as_ref()?;
+        let expires_at = queue.expires_at()?;
+        Some(expires_at.min(self.neighbor_cache.silent_until()))
+    }
+}
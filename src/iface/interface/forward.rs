@@ -0,0 +1,519 @@
+// Forwarding of the packets that are not addressed to the interface, and polling of several
+// interfaces that route packets between each other.
+
+use core::cmp::Ordering;
+
+use super::*;
+
+impl Interface {
+    /// Enable or disable the forwarding of packets.
+    ///
+    /// When forwarding is enabled, unicast packets that are not addressed to the interface are
+    /// routed instead of being dropped. Their TTL or hop limit is decremented, and an ICMP Time
+    /// Exceeded message is sent back to the source when it expires. The packets are transmitted
+    /// through this interface, or through another one when the interfaces are polled with
+    /// [`poll_all`](Self::poll_all).
+    ///
+    /// Packets from or to link-local addresses are never forwarded.
+    pub fn set_forwarding(&mut self, forwarding: bool) {
+        self.inner.forwarding = forwarding;
+    }
+
+    /// Get whether the forwarding of packets is enabled.
+    ///
+    /// See [`set_forwarding`](Self::set_forwarding) for details on forwarding.
+    pub fn forwarding(&self) -> bool {
+        self.inner.forwarding
+    }
+
+    /// Transmit packets queued in the given sockets, and receive packets queued in the devices
+    /// of several interfaces, which serve the same sockets. Each interface is given as an
+    /// `(&mut Interface, &mut D)` pair, where every interface may use a different type of device.
+    ///
+    /// Forwarded packets and the packets of the sockets are transmitted through the interface
+    /// with the most specific route to their destination, which is either one of its networks or
+    /// one of its [`routes`](Self::routes). When several interfaces have an equally specific
+    /// route, the first one is used. Packets without a route, such as multicast packets, are
+    /// transmitted through the first interface.
+    ///
+    /// Received packets are only delivered to the sockets when they are addressed to the
+    /// interface that received them. Forwarded packets that do not fit in the MTU of the
+    /// interface they are transmitted through are fragmented, unless they are IPv6 packets or
+    /// IPv4 packets with the Don't Fragment flag: those are dropped, and an ICMP Packet Too Big
+    /// or Fragmentation Required error is sent back to their source.
+    ///
+    /// This function returns a boolean value indicating whether any packets were
+    /// processed or emitted, and thus, whether the readiness of any socket might
+    /// have changed. The next call should happen at the earliest [`poll_at`](Self::poll_at)
+    /// of the interfaces.
+    pub fn poll_all(
+        timestamp: Instant,
+        interfaces: &mut [&mut dyn InterfaceDevice],
+        sockets: &mut SocketSet<'_>,
+    ) -> bool {
+        let mut sent_fragment = false;
+        for port in interfaces.iter_mut() {
+            let iface = port.interface_mut();
+            iface.inner.now = timestamp;
+
+            #[cfg(feature = "_proto-fragmentation")]
+            iface.fragments.assembler.remove_expired(timestamp);
+
+            sent_fragment |= port.fragments_egress();
+        }
+        if sent_fragment {
+            return true;
+        }
+
+        #[cfg(feature = "socket-mdns")]
+        for port in interfaces.iter_mut() {
+            port.socket_multicast_groups(sockets);
+        }
+
+        let mut readiness_may_have_changed = false;
+
+        loop {
+            let mut did_something = false;
+
+            for index in 0..interfaces.len() {
+                let (before, rest) = interfaces.split_at_mut(index);
+                let (port, after) = rest.split_first_mut().unwrap();
+
+                did_something |= port.socket_ingress(sockets, &mut |inner, packet| {
+                    let dst_addr = packet.ip_repr().dst_addr();
+                    let egress = egress_interface(before, inner, after, &dst_addr);
+                    let egress = match egress.cmp(&before.len()) {
+                        Ordering::Less => &mut before[egress],
+                        Ordering::Equal => return Some(packet),
+                        Ordering::Greater => &mut after[egress - before.len() - 1],
+                    };
+
+                    match egress.interface().inner.forward_mtu_exceeded(&packet) {
+                        Some(mtu) => inner.forward_too_big(&packet, mtu),
+                        None => {
+                            egress.transmit_forwarded(packet);
+                            None
+                        }
+                    }
+                });
+
+                did_something |= port.socket_egress(sockets, &|inner, dst_addr| {
+                    egress_interface(before, inner, after, dst_addr) == before.len()
+                });
+
+                did_something |= port.protocols_egress();
+            }
+
+            if did_something {
+                readiness_may_have_changed = true;
+            } else {
+                break;
+            }
+        }
+
+        readiness_may_have_changed
+    }
+
+    /// Transmit a packet that is forwarded from another interface.
+    fn transmit_forwarded<D>(&mut self, device: &mut D, packet: Packet)
+    where
+        D: Device + ?Sized,
+    {
+        let Some(tx_token) = device.transmit(self.inner.now) else {
+            net_debug!("failed to forward packet: device exhausted");
+            return;
+        };
+
+        if let Err(err) = self.inner.dispatch_ip(
+            tx_token,
+            PacketMeta::default(),
+            packet,
+            &mut self.fragmenter,
+        ) {
+            net_debug!("Failed to forward packet: {:?}", err);
+        }
+    }
+}
+
+/// An interface and the device it transmits and receives packets with, which are polled
+/// together by [`Interface::poll_all`].
+///
+/// It is implemented for `(&mut Interface, &mut D)` pairs, for any type of device `D`.
+pub trait InterfaceDevice: sealed::Sealed {}
+
+impl<D: Device + ?Sized> InterfaceDevice for (&mut Interface, &mut D) {}
+
+// The sealed trait cannot be named outside of the crate, so its methods may use its types.
+#[allow(private_interfaces)]
+mod sealed {
+    use super::*;
+
+    /// The operations of [`Interface::poll`] that [`Interface::poll_all`] performs on each
+    /// interface, with its own type of device.
+    pub trait Sealed {
+        fn interface(&self) -> &Interface;
+
+        fn interface_mut(&mut self) -> &mut Interface;
+
+        fn fragments_egress(&mut self) -> bool;
+
+        #[cfg(feature = "socket-mdns")]
+        fn socket_multicast_groups(&mut self, sockets: &SocketSet<'_>);
+
+        fn socket_ingress(
+            &mut self,
+            sockets: &mut SocketSet<'_>,
+            forward: &mut dyn for<'p> FnMut(&InterfaceInner, Packet<'p>) -> Option<Packet<'p>>,
+        ) -> bool;
+
+        fn socket_egress(
+            &mut self,
+            sockets: &mut SocketSet<'_>,
+            routed: &dyn Fn(&InterfaceInner, &IpAddress) -> bool,
+        ) -> bool;
+
+        fn protocols_egress(&mut self) -> bool;
+
+        fn transmit_forwarded(&mut self, packet: Packet);
+    }
+
+    impl<D: Device + ?Sized> Sealed for (&mut Interface, &mut D) {
+        fn interface(&self) -> &Interface {
+            self.0
+        }
+
+        fn interface_mut(&mut self) -> &mut Interface {
+            self.0
+        }
+
+        fn fragments_egress(&mut self) -> bool {
+            self.0.fragments_egress(self.1)
+        }
+
+        #[cfg(feature = "socket-mdns")]
+        fn socket_multicast_groups(&mut self, sockets: &SocketSet<'_>) {
+            self.0.socket_multicast_groups(self.1, sockets)
+        }
+
+        fn socket_ingress(
+            &mut self,
+            sockets: &mut SocketSet<'_>,
+            forward: &mut dyn for<'p> FnMut(&InterfaceInner, Packet<'p>) -> Option<Packet<'p>>,
+        ) -> bool {
+            self.0.socket_ingress(self.1, sockets, forward)
+        }
+
+        fn socket_egress(
+            &mut self,
+            sockets: &mut SocketSet<'_>,
+            routed: &dyn Fn(&InterfaceInner, &IpAddress) -> bool,
+        ) -> bool {
+            self.0.socket_egress(self.1, sockets, routed)
+        }
+
+        fn protocols_egress(&mut self) -> bool {
+            self.0.protocols_egress(self.1)
+        }
+
+        fn transmit_forwarded(&mut self, packet: Packet) {
+            self.0.transmit_forwarded(self.1, packet)
+        }
+    }
+}
+
+/// Return the index of the interface that packets to `addr` are transmitted through: the first
+/// one with the most specific route, or the first one when none has a route. The interface
+/// between `before` and `after` is borrowed as `inner`.
+fn egress_interface(
+    before: &[&mut dyn InterfaceDevice],
+    inner: &InterfaceInner,
+    after: &[&mut dyn InterfaceDevice],
+    addr: &IpAddress,
+) -> usize {
+    let interfaces = before
+        .iter()
+        .map(|port| &port.interface().inner)
+        .chain(core::iter::once(inner))
+        .chain(after.iter().map(|port| &port.interface().inner));
+
+    let mut egress = (0, None);
+    for (index, inner) in interfaces.enumerate() {
+        let prefix_len = inner.route_prefix_len(addr);
+        if prefix_len > egress.1 {
+            egress = (index, prefix_len);
+        }
+    }
+    egress.0
+}
+
+/// Hand a packet that is forwarded over to `forward`, which returns it when it is transmitted
+/// through the interface that received it.
+pub(super) fn forward_packet<'p, F>(
+    inner: &InterfaceInner,
+    packet: Packet<'p>,
+    forward: &mut F,
+) -> Option<Packet<'p>>
+where
+    F: FnMut(&InterfaceInner, Packet<'p>) -> Option<Packet<'p>>,
+{
+    if !matches!(packet.payload(), IpPayload::Forward(..)) {
+        return Some(packet);
+    }
+
+    // The packet is transmitted through the interface that received it.
+    let packet = forward(inner, packet)?;
+    match inner.forward_mtu_exceeded(&packet) {
+        Some(mtu) => inner.forward_too_big(&packet, mtu),
+        None => Some(packet),
+    }
+}
+
+impl InterfaceInner {
+    /// Return the prefix length of the most specific route to `addr` through the interface: one
+    /// of its networks, or one of its routes.
+    fn route_prefix_len(&self, addr: &IpAddress) -> Option<u8> {
+        if !addr.is_unicast() {
+            return None;
+        }
+
+        let network = self
+            .ip_addrs
+            .iter()
+            .filter(|cidr| cidr.contains_addr(addr))
+            .map(|cidr| cidr.prefix_len())
+            .max();
+        let route = self
+            .routes
+            .lookup_route(addr, self.now)
+            .map(|route| route.cidr.prefix_len());
+        network.max(route)
+    }
+
+    /// Return the MTU of the interface when a forwarded packet that is transmitted through it
+    /// is too big, and must not be fragmented. 6LoWPAN carries IPv6 packets of up to 1280 octets,
+    /// see RFC 4944 § 4.
+    fn forward_mtu_exceeded(&self, packet: &Packet) -> Option<usize> {
+        let ip_repr = packet.ip_repr();
+        let mtu = match self.caps.medium {
+            #[cfg(feature = "medium-ieee802154")]
+            Medium::Ieee802154 => IPV6_MIN_MTU,
+            #[allow(unreachable_patterns)]
+            _ => self.path_mtu(&ip_repr.dst_addr()),
+        };
+
+        if ip_repr.buffer_len() > mtu && packet.dont_frag() {
+            net_debug!(
+                "forward: packet to {} too big for MTU {}",
+                ip_repr.dst_addr(),
+                mtu
+            );
+            Some(mtu)
+        } else {
+            None
+        }
+    }
+
+    /// Reply to the source of a forwarded packet that is dropped because it is too big for the
+    /// MTU of the next hop, `mtu`.
+    fn forward_too_big<'p>(&self, packet: &Packet<'p>, mtu: usize) -> Option<Packet<'p>> {
+        let &IpPayload::Forward(_, ip_payload) = packet.payload() else {
+            return None;
+        };
+
+        // The error quotes the packet as it was received.
+        match packet.ip_repr() {
+            #[cfg(feature = "proto-ipv4")]
+            IpRepr::Ipv4(ipv4_repr) => {
+                let ipv4_repr = Ipv4Repr {
+                    hop_limit: ipv4_repr.hop_limit + 1,
+                    ..ipv4_repr
+                };
+                self.forward_error_ipv4(ipv4_repr, ip_payload, |header, data| {
+                    Icmpv4Repr::FragRequired {
+                        mtu: mtu.min(u16::MAX as usize) as u16,
+                        header,
+                        data,
+                    }
+                })
+            }
+            #[cfg(feature = "proto-ipv6")]
+            IpRepr::Ipv6(ipv6_repr) => {
+                let ipv6_repr = Ipv6Repr {
+                    hop_limit: ipv6_repr.hop_limit + 1,
+                    ..ipv6_repr
+                };
+                self.forward_error_ipv6(ipv6_repr, ip_payload, |header, data| {
+                    Icmpv6Repr::PktTooBig {
+                        mtu: mtu as u32,
+                        header,
+                        data,
+                    }
+                })
+            }
+        }
+    }
+
+    /// Forward an IPv4 packet that is not addressed to us. It keeps its Don't Fragment flag,
+    /// `dont_frag`.
+    #[cfg(feature = "proto-ipv4")]
+    pub(super) fn forward_ipv4<'frame>(
+        &self,
+        ipv4_repr: Ipv4Repr,
+        dont_frag: bool,
+        ip_payload: &'frame [u8],
+    ) -> Option<Packet<'frame>> {
+        if !self.is_unicast_v4(ipv4_repr.dst_addr)
+            || !self.is_unicast_v4(ipv4_repr.src_addr)
+            || ipv4_repr.dst_addr.is_link_local()
+            || ipv4_repr.src_addr.is_link_local()
+        {
+            net_trace!("packet IP address not for this interface");
+            return None;
+        }
+
+        match self
+            .route_to(&ipv4_repr.dst_addr.into())
+            .map(|route| route.kind)
+        {
+            Some(RouteKind::Blackhole) => {
+                net_trace!("forward: blackhole route to {}", ipv4_repr.dst_addr);
+                return None;
+            }
+            Some(RouteKind::Unreachable) => {
+                net_debug!("forward: unreachable route to {}", ipv4_repr.dst_addr);
+                return self.forward_error_ipv4(ipv4_repr, ip_payload, |header, data| {
+                    Icmpv4Repr::DstUnreachable {
+                        reason: Icmpv4DstUnreachable::HostUnreachable,
+                        header,
+                        data,
+                    }
+                });
+            }
+            _ => {}
+        }
+
+        if ipv4_repr.hop_limit <= 1 {
+            net_debug!("forward: TTL expired for {}", ipv4_repr.dst_addr);
+            return self.forward_error_ipv4(ipv4_repr, ip_payload, |header, data| {
+                Icmpv4Repr::TimeExceeded {
+                    reason: Icmpv4TimeExceeded::TtlExpired,
+                    header,
+                    data,
+                }
+            });
+        }
+
+        let mut packet = Packet::new_ipv4(
+            Ipv4Repr {
+                payload_len: ip_payload.len(),
+                hop_limit: ipv4_repr.hop_limit - 1,
+                ..ipv4_repr
+            },
+            IpPayload::Forward(ipv4_repr.next_header, ip_payload),
+        );
+        packet.set_dont_frag(dont_frag);
+        Some(packet)
+    }
+
+    /// Forward an IPv6 packet that is not addressed to us, together with its extension headers.
+    #[cfg(feature = "proto-ipv6")]
+    pub(super) fn forward_ipv6<'frame>(
+        &self,
+        ipv6_repr: Ipv6Repr,
+        ip_payload: &'frame [u8],
+    ) -> Option<Packet<'frame>> {
+        if !ipv6_repr.dst_addr.is_unicast()
+            || !ipv6_repr.src_addr.is_unicast()
+            || ipv6_repr.dst_addr.is_link_local()
+            || ipv6_repr.src_addr.is_link_local()
+        {
+            net_trace!("packet IP address not for this interface");
+            return None;
+        }
+
+        match self
+            .route_to(&ipv6_repr.dst_addr.into())
+            .map(|route| route.kind)
+        {
+            Some(RouteKind::Blackhole) => {
+                net_trace!("forward: blackhole route to {}", ipv6_repr.dst_addr);
+                return None;
+            }
+            Some(RouteKind::Unreachable) => {
+                net_debug!("forward: unreachable route to {}", ipv6_repr.dst_addr);
+                return self.forward_error_ipv6(ipv6_repr, ip_payload, |header, data| {
+                    Icmpv6Repr::DstUnreachable {
+                        reason: Icmpv6DstUnreachable::RejectRoute,
+                        header,
+                        data,
+                    }
+                });
+            }
+            _ => {}
+        }
+
+        if ipv6_repr.hop_limit <= 1 {
+            net_debug!("forward: hop limit exceeded for {}", ipv6_repr.dst_addr);
+            return self.forward_error_ipv6(ipv6_repr, ip_payload, |header, data| {
+                Icmpv6Repr::TimeExceeded {
+                    reason: Icmpv6TimeExceeded::HopLimitExceeded,
+                    header,
+                    data,
+                }
+            });
+        }
+
+        // The extension headers are forwarded unchanged (RFC 8200 § 4).
+        Some(Packet::new_ipv6(
+            Ipv6Repr {
+                hop_limit: ipv6_repr.hop_limit - 1,
+                ..ipv6_repr
+            },
+            IpPayload::Forward(ipv6_repr.next_header, ip_payload),
+        ))
+    }
+
+    /// Reply to the source of an IPv4 packet that cannot be forwarded with an ICMPv4 error.
+    #[cfg(feature = "proto-ipv4")]
+    fn forward_error_ipv4<'frame>(
+        &self,
+        ipv4_repr: Ipv4Repr,
+        ip_payload: &'frame [u8],
+        error: impl FnOnce(Ipv4Repr, &'frame [u8]) -> Icmpv4Repr<'frame>,
+    ) -> Option<Packet<'frame>> {
+        let payload_len =
+            icmp_reply_payload_len(ip_payload.len(), IPV4_MIN_MTU, ipv4_repr.buffer_len());
+
+        // The packet was not addressed to us, so we reply from our own address.
+        let src_addr = self.get_source_address_ipv4(&ipv4_repr.src_addr)?;
+        self.icmpv4_reply(
+            Ipv4Repr {
+                dst_addr: src_addr,
+                ..ipv4_repr
+            },
+            error(ipv4_repr, &ip_payload[..payload_len]),
+        )
+    }
+
+    /// Reply to the source of an IPv6 packet that cannot be forwarded with an ICMPv6 error.
+    #[cfg(feature = "proto-ipv6")]
+    fn forward_error_ipv6<'frame>(
+        &self,
+        ipv6_repr: Ipv6Repr,
+        ip_payload: &'frame [u8],
+        error: impl FnOnce(Ipv6Repr, &'frame [u8]) -> Icmpv6Repr<'frame>,
+    ) -> Option<Packet<'frame>> {
+        let payload_len =
+            icmp_reply_payload_len(ip_payload.len(), IPV6_MIN_MTU, ipv6_repr.buffer_len());
+
+        // The packet was not addressed to us, so we reply from our own address.
+        let src_addr = self.get_source_address_ipv6(&ipv6_repr.src_addr);
+        self.icmpv6_reply(
+            Ipv6Repr {
+                dst_addr: src_addr,
+                ..ipv6_repr
+            },
+            error(ipv6_repr, &ip_payload[..payload_len]),
+        )
+    }
+}
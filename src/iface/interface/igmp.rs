@@ -1,5 +1,10 @@
+use super::multicast::{MulticastFilter, MulticastFilterMode, Record, MAX_RECORD_COUNT};
 use super::*;
 
+/// How long a querier of IGMPv1 or IGMPv2 is considered present after its last query,
+/// see RFC 3376 § 8.12.
+const OLDER_VERSION_QUERIER_PRESENT_TIMEOUT: Duration = Duration::from_secs(2 * 125 + 10);
+
 /// Error type for `join_multicast_group`, `leave_multicast_group`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -8,6 +13,8 @@ pub enum MulticastError {
     Exhausted,
     /// The table of joined multicast groups is already full.
     GroupTableFull,
+    /// The list of sources of the multicast group is too long.
+    SourceTableFull,
     /// Cannot join/leave the given multicast group.
     Unaddressable,
 }
@@ -17,6 +24,7 @@ impl core::fmt::Display for MulticastError {
         match self {
             MulticastError::Exhausted => write!(f, "Exhausted"),
             MulticastError::GroupTableFull => write!(f, "GroupTableFull"),
+            MulticastError::SourceTableFull => write!(f, "SourceTableFull"),
             MulticastError::Unaddressable => write!(f, "Unaddressable"),
         }
     }
@@ -25,6 +33,29 @@ impl core::fmt::Display for MulticastError {
 #[cfg(feature = "std")]
 impl std::error::Error for MulticastError {}
 
+/// Build the filter of a multicast group, where `convert` returns the source addresses of
+/// the version of IP of the group.
+fn multicast_filter<A: PartialEq>(
+    mode: MulticastFilterMode,
+    sources: &[IpAddress],
+    convert: impl Fn(IpAddress) -> Option<A>,
+) -> Result<MulticastFilter<A>, MulticastError> {
+    let mut filter = MulticastFilter {
+        mode,
+        sources: Vec::new(),
+    };
+    for src_addr in sources {
+        let src_addr = convert(*src_addr).ok_or(MulticastError::Unaddressable)?;
+        if !filter.sources.contains(&src_addr) {
+            filter
+                .sources
+                .push(src_addr)
+                .map_err(|_| MulticastError::SourceTableFull)?;
+        }
+    }
+    Ok(filter)
+}
+
 impl Interface {
     /// Add an address to a list of subscribed multicast IP addresses.
     ///
@@ -36,68 +67,115 @@ impl Interface {
         addr: T,
         timestamp: Instant,
     ) -> Result<bool, MulticastError>
+    where
+        D: Device + ?Sized,
+    {
+        self.join_multicast_group_with_sources(
+            device,
+            addr,
+            MulticastFilterMode::Exclude,
+            &[],
+            timestamp,
+        )
+    }
+
+    /// Add an address to a list of subscribed multicast IP addresses, receiving its traffic
+    /// from the listed `sources` only (`Include`), or from all but the listed `sources`
+    /// (`Exclude`). The filter of a group that is already joined is replaced, and an
+    /// `Include` filter without sources leaves the group.
+    ///
+    /// Returns `Ok(announce_sent)` if the filter was set successfully, where `announce_sent`
+    /// indicates whether an immediate announcement has been sent.
+    pub fn join_multicast_group_with_sources<D, T: Into<IpAddress>>(
+        &mut self,
+        device: &mut D,
+        addr: T,
+        mode: MulticastFilterMode,
+        sources: &[IpAddress],
+        timestamp: Instant,
+    ) -> Result<bool, MulticastError>
     where
         D: Device + ?Sized,
     {
         self.inner.now = timestamp;
 
+        if mode == MulticastFilterMode::Include && sources.is_empty() {
+            return self.leave_multicast_group(device, addr, timestamp);
+        }
+
         match addr.into() {
             IpAddress::Ipv4(addr) => {
-                let is_not_new = self
-                    .inner
+                #[allow(unreachable_patterns)]
+                let filter = multicast_filter(mode, sources, |src_addr| match src_addr {
+                    IpAddress::Ipv4(src_addr) => Some(src_addr),
+                    _ => None,
+                })?;
+                let old = self.inner.ipv4_multicast_groups.get(&addr).cloned();
+                if old.as_ref() == Some(&filter) {
+                    return Ok(false);
+                }
+                self.inner
                     .ipv4_multicast_groups
-                    .insert(addr, ())
-                    .map_err(|_| MulticastError::GroupTableFull)?
-                    .is_some();
-                if is_not_new {
-                    Ok(false)
-                } else if let Some(pkt) = self.inner.igmp_report_packet(IgmpVersion::Version2, addr)
-                {
-                    // Send initial membership report
-                    let tx_token = device
-                        .transmit(timestamp)
-                        .ok_or(MulticastError::Exhausted)?;
+                    .insert(addr, filter.clone())
+                    .map_err(|_| MulticastError::GroupTableFull)?;
 
-                    // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
-                    self.inner
-                        .dispatch_ip(tx_token, PacketMeta::default(), pkt, &mut self.fragmenter)
-                        .unwrap();
+                match self.inner.igmp_compat_version() {
+                    // Older queriers only learn of the groups, and not of their sources.
+                    Some(_) if old.is_some() => Ok(false),
+                    Some(version) => {
+                        let Some(pkt) = self.inner.igmp_report_packet(version, addr) else {
+                            return Ok(false);
+                        };
+
+                        // Send initial membership report
+                        let tx_token = device
+                            .transmit(timestamp)
+                            .ok_or(MulticastError::Exhausted)?;
 
-                    Ok(true)
-                } else {
-                    Ok(false)
+                        // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
+                        self.inner
+                            .dispatch_ip(tx_token, PacketMeta::default(), pkt, &mut self.fragmenter)
+                            .unwrap();
+
+                        Ok(true)
+                    }
+                    None => {
+                        let records = Record::changes(addr, old.as_ref(), Some(&filter));
+                        self.inner
+                            .igmpv3
+                            .report_change(addr, timestamp, &mut self.inner.rand);
+                        self.igmpv3_send(device, &records)
+                    }
                 }
             }
             #[cfg(feature = "proto-ipv6")]
             IpAddress::Ipv6(addr) => {
-                // Build report packet containing this new address
-                let report_record = &[MldAddressRecordRepr::new(
-                    MldRecordType::ChangeToInclude,
-                    addr,
-                )];
-                let is_not_new = self
-                    .inner
+                let filter = multicast_filter(mode, sources, |src_addr| match src_addr {
+                    IpAddress::Ipv6(src_addr) => Some(src_addr),
+                    _ => None,
+                })?;
+                let old = self.inner.ipv6_multicast_groups.get(&addr).cloned();
+                if old.as_ref() == Some(&filter) {
+                    return Ok(false);
+                }
+                self.inner
                     .ipv6_multicast_groups
-                    .insert(addr, ())
-                    .map_err(|_| MulticastError::GroupTableFull)?
-                    .is_some();
-                if is_not_new {
-                    Ok(false)
-                } else if let Some(pkt) = self.inner.mldv2_report_packet(report_record) {
-                    // Send initial membership report
-                    let tx_token = device
-                        .transmit(timestamp)
-                        .ok_or(MulticastError::Exhausted)?;
+                    .insert(addr, filter.clone())
+                    .map_err(|_| MulticastError::GroupTableFull)?;
 
-                    // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
-                    self.inner
-                        .dispatch_ip(tx_token, PacketMeta::default(), pkt, &mut self.fragmenter)
-                        .unwrap();
+                let records = Record::changes(addr, old.as_ref(), Some(&filter));
+                self.inner
+                    .mld
+                    .report_change(addr, timestamp, &mut self.inner.rand);
 
-                    Ok(true)
-                } else {
-                    Ok(false)
-                }
+                // Send initial membership report
+                let tx_token = device
+                    .transmit(timestamp)
+                    .ok_or(MulticastError::Exhausted)?;
+                self.inner
+                    .mldv2_dispatch(tx_token, &records, &mut self.fragmenter);
+
+                Ok(true)
             }
             #[allow(unreachable_patterns)]
             _ => Err(MulticastError::Unaddressable),
@@ -121,49 +199,53 @@ impl Interface {
 
         match addr.into() {
             IpAddress::Ipv4(addr) => {
-                let was_not_present = self.inner.ipv4_multicast_groups.remove(&addr).is_none();
-                if was_not_present {
-                    Ok(false)
-                } else if let Some(pkt) = self.inner.igmp_leave_packet(addr) {
-                    // Send group leave packet
-                    let tx_token = device
-                        .transmit(timestamp)
-                        .ok_or(MulticastError::Exhausted)?;
-
-                    // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
-                    self.inner
-                        .dispatch_ip(tx_token, PacketMeta::default(), pkt, &mut self.fragmenter)
-                        .unwrap();
+                let Some(old) = self.inner.ipv4_multicast_groups.remove(&addr) else {
+                    return Ok(false);
+                };
 
-                    Ok(true)
-                } else {
-                    Ok(false)
+                if self.inner.igmp_compat_version().is_none() {
+                    let records = Record::changes(addr, Some(&old), None);
+                    self.inner
+                        .igmpv3
+                        .report_change(addr, timestamp, &mut self.inner.rand);
+                    return self.igmpv3_send(device, &records);
                 }
+
+                let Some(pkt) = self.inner.igmp_leave_packet(addr) else {
+                    return Ok(false);
+                };
+
+                // Send group leave packet
+                let tx_token = device
+                    .transmit(timestamp)
+                    .ok_or(MulticastError::Exhausted)?;
+
+                // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
+                self.inner
+                    .dispatch_ip(tx_token, PacketMeta::default(), pkt, &mut self.fragmenter)
+                    .unwrap();
+
+                Ok(true)
             }
             #[cfg(feature = "proto-ipv6")]
             IpAddress::Ipv6(addr) => {
-                let report_record = &[MldAddressRecordRepr::new(
-                    MldRecordType::ChangeToExclude,
-                    addr,
-                )];
-                let was_not_present = self.inner.ipv6_multicast_groups.remove(&addr).is_none();
-                if was_not_present {
-                    Ok(false)
-                } else if let Some(pkt) = self.inner.mldv2_report_packet(report_record) {
-                    // Send group leave packet
-                    let tx_token = device
-                        .transmit(timestamp)
-                        .ok_or(MulticastError::Exhausted)?;
-
-                    // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
-                    self.inner
-                        .dispatch_ip(tx_token, PacketMeta::default(), pkt, &mut self.fragmenter)
-                        .unwrap();
+                let Some(old) = self.inner.ipv6_multicast_groups.remove(&addr) else {
+                    return Ok(false);
+                };
 
-                    Ok(true)
-                } else {
-                    Ok(false)
-                }
+                let records = Record::changes(addr, Some(&old), None);
+                self.inner
+                    .mld
+                    .report_change(addr, timestamp, &mut self.inner.rand);
+
+                // Send group leave packet
+                let tx_token = device
+                    .transmit(timestamp)
+                    .ok_or(MulticastError::Exhausted)?;
+                self.inner
+                    .mldv2_dispatch(tx_token, &records, &mut self.fragmenter);
+
+                Ok(true)
             }
             #[allow(unreachable_patterns)]
             _ => Err(MulticastError::Unaddressable),
@@ -213,7 +295,7 @@ impl Interface {
                     .ipv4_multicast_groups
                     .iter()
                     .nth(next_index)
-                    .map(|(addr, ())| *addr);
+                    .map(|(addr, _)| *addr);
 
                 match addr {
                     Some(addr) => {
@@ -253,14 +335,96 @@ impl Interface {
             _ => false,
         }
     }
+
+    /// Send the IGMPv3 reports that are due: responses to queries, and retransmissions of
+    /// State Change Reports.
+    pub(super) fn igmpv3_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        let now = self.inner.now;
+        let Some((report, records)) = self
+            .inner
+            .igmpv3
+            .due(now, &self.inner.ipv4_multicast_groups)
+        else {
+            return false;
+        };
+
+        if !records.is_empty() && self.igmpv3_send(device, &records).is_err() {
+            return false;
+        }
+
+        self.inner.igmpv3.sent(report, now, &mut self.inner.rand);
+        true
+    }
+
+    /// Send an IGMPv3 report holding `records`.
+    ///
+    /// Returns `Ok(false)` if the interface has no IPv4 address to send it from.
+    fn igmpv3_send<D>(
+        &mut self,
+        device: &mut D,
+        records: &[Record<Ipv4Address>],
+    ) -> Result<bool, MulticastError>
+    where
+        D: Device + ?Sized,
+    {
+        let reprs = records
+            .iter()
+            .map(|record| IgmpGroupRecordRepr {
+                record_type: record.record_type.into(),
+                aux_data_len: 0,
+                num_srcs: record.num_srcs,
+                group_addr: record.group_addr,
+                payload: &record.sources,
+            })
+            .collect::<Vec<_, MAX_RECORD_COUNT>>();
+
+        let Some(pkt) = self.inner.igmpv3_report_packet(&reprs) else {
+            return Ok(false);
+        };
+
+        let tx_token = device
+            .transmit(self.inner.now)
+            .ok_or(MulticastError::Exhausted)?;
+
+        // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
+        self.inner
+            .dispatch_ip(tx_token, PacketMeta::default(), pkt, &mut self.fragmenter)
+            .unwrap();
+
+        Ok(true)
+    }
 }
 
 impl InterfaceInner {
-    /// Host duties of the **IGMPv2** protocol.
+    /// Return the version of IGMP of the oldest querier present, if it is older than
+    /// IGMPv3, see RFC 3376 § 7.2.1.
+    pub(super) fn igmp_compat_version(&self) -> Option<IgmpVersion> {
+        self.igmp_older_querier
+            .filter(|(_, until)| self.now < *until)
+            .map(|(version, _)| version)
+    }
+
+    /// Switch to the reports of an older version of IGMP while queriers of that version
+    /// are present, see RFC 3376 § 7.2.1.
+    fn igmp_older_querier_heard(&mut self, version: IgmpVersion) {
+        match self.igmp_compat_version() {
+            Some(IgmpVersion::Version1) if version == IgmpVersion::Version2 => return,
+            // The pending IGMPv3 reports are cancelled.
+            None => self.igmpv3.clear(),
+            Some(_) => (),
+        }
+        self.igmp_older_querier = Some((version, self.now + OLDER_VERSION_QUERIER_PRESENT_TIMEOUT));
+    }
+
+    /// Host duties of the **IGMPv2** and **IGMPv3** protocols.
     ///
     /// Sets up `igmp_report_state` for responding to IGMP general/specific membership queries.
     /// Membership must not be reported immediately in order to avoid flooding the network
-    /// after a query is broadcasted by a router; this is not currently done.
+    /// after a query is broadcasted by a router; this is not currently done for IGMPv1/v2.
+    /// IGMPv3 queries are answered after a random delay, unless an older querier is present.
     pub(super) fn process_igmp<'frame>(
         &mut self,
         ipv4_repr: Ipv4Repr,
@@ -269,53 +433,82 @@ impl InterfaceInner {
         let igmp_packet = check!(IgmpPacket::new_checked(ip_payload));
         let igmp_repr = check!(IgmpRepr::parse(&igmp_packet));
 
-        // FIXME: report membership after a delay
-        match igmp_repr {
+        let (group_addr, version, max_resp_time) = match igmp_repr {
             IgmpRepr::MembershipQuery {
                 group_addr,
                 version,
                 max_resp_time,
             } => {
-                // General query
-                if group_addr.is_unspecified()
-                    && ipv4_repr.dst_addr == Ipv4Address::MULTICAST_ALL_SYSTEMS
-                {
-                    // Are we member in any groups?
-                    if self.ipv4_multicast_groups.iter().next().is_some() {
-                        let interval = match version {
-                            IgmpVersion::Version1 => Duration::from_millis(100),
-                            IgmpVersion::Version2 => {
-                                // No dependence on a random generator
-                                // (see [#24](https://github.com/m-labs/smoltcp/issues/24))
-                                // but at least spread reports evenly across max_resp_time.
-                                let intervals = self.ipv4_multicast_groups.len() as u32 + 1;
-                                max_resp_time / intervals
-                            }
-                        };
-                        self.igmp_report_state = IgmpReportState::ToGeneralQuery {
-                            version,
-                            timeout: self.now + interval,
-                            interval,
-                            next_index: 0,
-                        };
-                    }
-                } else {
-                    // Group-specific query
-                    if self.has_multicast_group(group_addr) && ipv4_repr.dst_addr == group_addr {
-                        // Don't respond immediately
-                        let timeout = max_resp_time / 4;
-                        self.igmp_report_state = IgmpReportState::ToSpecificQuery {
-                            version,
-                            timeout: self.now + timeout,
-                            group: group_addr,
-                        };
-                    }
-                }
+                self.igmp_older_querier_heard(version);
+                (group_addr, version, max_resp_time)
             }
+            IgmpRepr::MembershipQueryV3 {
+                max_resp_time,
+                group_addr,
+                qrv,
+                ..
+            } => match self.igmp_compat_version() {
+                // Queries are answered like those of the older querier.
+                Some(version) => (group_addr, version, max_resp_time),
+                None => {
+                    let group_addr = if group_addr.is_unspecified() {
+                        None
+                    } else if self.ipv4_multicast_groups.contains_key(&group_addr) {
+                        Some(group_addr)
+                    } else {
+                        return None;
+                    };
+                    self.igmpv3.schedule_response(
+                        group_addr,
+                        max_resp_time,
+                        qrv,
+                        self.now,
+                        &mut self.rand,
+                    );
+                    return None;
+                }
+            },
             // Ignore membership reports
-            IgmpRepr::MembershipReport { .. } => (),
+            IgmpRepr::MembershipReport { .. }
+            | IgmpRepr::MembershipReportV3 { .. }
+            | IgmpRepr::ReportRecordReprs(_) => return None,
             // Ignore hosts leaving groups
-            IgmpRepr::LeaveGroup { .. } => (),
+            IgmpRepr::LeaveGroup { .. } => return None,
+        };
+
+        // FIXME: report membership after a delay
+        // General query
+        if group_addr.is_unspecified() && ipv4_repr.dst_addr == Ipv4Address::MULTICAST_ALL_SYSTEMS {
+            // Are we member in any groups?
+            if self.ipv4_multicast_groups.iter().next().is_some() {
+                let interval = match version {
+                    IgmpVersion::Version1 => Duration::from_millis(100),
+                    IgmpVersion::Version2 => {
+                        // No dependence on a random generator
+                        // (see [#24](https://github.com/m-labs/smoltcp/issues/24))
+                        // but at least spread reports evenly across max_resp_time.
+                        let intervals = self.ipv4_multicast_groups.len() as u32 + 1;
+                        max_resp_time / intervals
+                    }
+                };
+                self.igmp_report_state = IgmpReportState::ToGeneralQuery {
+                    version,
+                    timeout: self.now + interval,
+                    interval,
+                    next_index: 0,
+                };
+            }
+        } else {
+            // Group-specific query
+            if self.has_multicast_group(group_addr) && ipv4_repr.dst_addr == group_addr {
+                // Don't respond immediately
+                let timeout = max_resp_time / 4;
+                self.igmp_report_state = IgmpReportState::ToSpecificQuery {
+                    version,
+                    timeout: self.now + timeout,
+                    group: group_addr,
+                };
+            }
         }
 
         None
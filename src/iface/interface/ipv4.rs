@@ -20,6 +20,12 @@ impl Interface {
             return false;
         }
 
+        // The fragmentation buffer is shared with IPv6.
+        #[cfg(feature = "proto-ipv6-fragmentation")]
+        if IpVersion::of_packet(&self.fragmenter.buffer) != Ok(IpVersion::Ipv4) {
+            return false;
+        }
+
         let pkt = &self.fragmenter;
         if pkt.packet_len > pkt.sent_bytes {
             if let Some(tx_token) = device.transmit(self.inner.now) {
@@ -43,14 +49,27 @@ impl InterfaceInner {
 
     /// Get an IPv4 source address based on a destination address.
     ///
-    /// **NOTE**: unlike for IPv6, no specific selection algorithm is implemented. The first IPv4
-    /// address from the interface is returned.
+    /// **NOTE**: unlike for IPv6, no specific selection algorithm is implemented. The source
+    /// address of the route to the destination is returned if it is one of our addresses, and
+    /// the first IPv4 address from the interface otherwise.
     #[allow(unused)]
-    pub(crate) fn get_source_address_ipv4(&self, _dst_addr: &Ipv4Address) -> Option<Ipv4Address> {
+    pub(crate) fn get_source_address_ipv4(&self, dst_addr: &Ipv4Address) -> Option<Ipv4Address> {
+        // The route to the destination may prefer one of our addresses.
+        if let Some(IpAddress::Ipv4(src_addr)) = self
+            .route_to(&IpAddress::Ipv4(*dst_addr))
+            .and_then(|route| route.src_addr)
+        {
+            if self.has_ip_addr(src_addr) {
+                return Some(src_addr);
+            }
+        }
+
         for cidr in self.ip_addrs.iter() {
             #[allow(irrefutable_let_patterns)] // if only ipv4 is enabled
             if let IpCidr::Ipv4(cidr) = cidr {
-                return Some(cidr.address());
+                if !self.is_tentative(&cidr.address().into()) {
+                    return Some(cidr.address());
+                }
             }
         }
         None
@@ -74,14 +93,14 @@ impl InterfaceInner {
     }
 
     /// Checks if an ipv4 address is unicast, taking into account subnet broadcast addresses
-    fn is_unicast_v4(&self, address: Ipv4Address) -> bool {
+    pub(super) fn is_unicast_v4(&self, address: Ipv4Address) -> bool {
         address.is_unicast() && !self.is_broadcast_v4(address)
     }
 
     /// Get the first IPv4 address of the interface.
     pub fn ipv4_addr(&self) -> Option<Ipv4Address> {
         self.ip_addrs.iter().find_map(|addr| match *addr {
-            IpCidr::Ipv4(cidr) => Some(cidr.address()),
+            IpCidr::Ipv4(cidr) if !self.is_tentative(&addr.address()) => Some(cidr.address()),
             #[allow(unreachable_patterns)]
             _ => None,
         })
@@ -179,6 +198,34 @@ impl InterfaceInner {
             }
         }
 
+        #[cfg(feature = "socket-dhcpv4-server")]
+        {
+            use crate::socket::dhcpv4_server::Socket as Dhcpv4ServerSocket;
+
+            if ipv4_repr.next_header == IpProtocol::Udp
+                && matches!(self.caps.medium, Medium::Ethernet)
+                && (self.has_ip_addr(ipv4_repr.dst_addr)
+                    || self.is_broadcast_v4(ipv4_repr.dst_addr))
+            {
+                let udp_packet = check!(UdpPacket::new_checked(ip_payload));
+                if udp_packet.dst_port() == DHCP_SERVER_PORT {
+                    if let Some(dhcp_socket) = sockets
+                        .items_mut()
+                        .find_map(|i| Dhcpv4ServerSocket::downcast_mut(&mut i.socket))
+                    {
+                        let udp_repr = check!(UdpRepr::parse(
+                            &udp_packet,
+                            &ipv4_repr.src_addr.into(),
+                            &ipv4_repr.dst_addr.into(),
+                            &self.caps.checksum
+                        ));
+                        dhcp_socket.process(self, &ipv4_repr, &udp_repr, udp_packet.payload());
+                        return None;
+                    }
+                }
+            }
+        }
+
         if !self.has_ip_addr(ipv4_repr.dst_addr)
             && !self.has_multicast_group(ipv4_repr.dst_addr)
             && !self.is_broadcast_v4(ipv4_repr.dst_addr)
@@ -192,6 +239,9 @@ impl InterfaceInner {
                     .lookup(&IpAddress::Ipv4(ipv4_repr.dst_addr), self.now)
                     .map_or(true, |router_addr| !self.has_ip_addr(router_addr))
             {
+                if self.forwarding {
+                    return self.forward_ipv4(ipv4_repr, ipv4_packet.dont_frag(), ip_payload);
+                }
                 return None;
             }
         }
@@ -243,8 +293,33 @@ impl InterfaceInner {
                 target_protocol_addr,
                 ..
             } => {
-                // Only process ARP packets for us.
+                // Packets from another node using one of our addresses are not processed.
+                if self.acd_process_arp(
+                    source_hardware_addr,
+                    source_protocol_addr,
+                    target_protocol_addr,
+                ) {
+                    return None;
+                }
+
+                // Only process ARP packets for us, and announcements updating the neighbors we
+                // know.
                 if !self.has_ip_addr(target_protocol_addr) && !self.any_ip {
+                    let source_protocol_addr = IpAddress::Ipv4(source_protocol_addr);
+                    if source_protocol_addr == IpAddress::Ipv4(target_protocol_addr)
+                        && source_protocol_addr.is_unicast()
+                        && source_hardware_addr.is_unicast()
+                        && self
+                            .neighbor_cache
+                            .lookup(&source_protocol_addr, timestamp)
+                            .found()
+                    {
+                        self.neighbor_cache.fill_stale(
+                            source_protocol_addr,
+                            source_hardware_addr.into(),
+                            timestamp,
+                        );
+                    }
                     return None;
                 }
 
@@ -268,12 +343,21 @@ impl InterfaceInner {
                 // Fill the ARP cache from any ARP packet aimed at us (both request or response).
                 // We fill from requests too because if someone is requesting our address they
                 // are probably going to talk to us, so we avoid having to request their address
-                // when we later reply to them.
-                self.neighbor_cache.fill(
-                    source_protocol_addr.into(),
-                    source_hardware_addr.into(),
-                    timestamp,
-                );
+                // when we later reply to them. Only a reply confirms the reachability of the
+                // neighbor.
+                if operation == ArpOperation::Reply {
+                    self.neighbor_cache.fill(
+                        source_protocol_addr.into(),
+                        source_hardware_addr.into(),
+                        timestamp,
+                    );
+                } else {
+                    self.neighbor_cache.fill_stale(
+                        source_protocol_addr.into(),
+                        source_hardware_addr.into(),
+                        timestamp,
+                    );
+                }
 
                 if operation == ArpOperation::Request {
                     let src_hardware_addr = self.hardware_addr.ethernet_or_panic();
@@ -334,6 +418,31 @@ impl InterfaceInner {
             // Ignore any echo replies.
             Icmpv4Repr::EchoReply { .. } => None,
 
+            // Lower the path MTU estimate of a destination we sent a packet to, see RFC 1191.
+            Icmpv4Repr::FragRequired { mtu, header, .. } => {
+                let mtu = mtu as usize;
+                if self.has_ip_addr(header.src_addr) && mtu >= PmtuCache::IPV4_MIN_MTU {
+                    self.pmtu_cache.fill(header.dst_addr.into(), mtu, self.now);
+                }
+                None
+            }
+
+            // Deliver other errors to the socket that sent the packet. Protocol and port
+            // unreachable errors are hard errors, see RFC 1122 § 4.2.3.9.
+            #[cfg(any(feature = "socket-tcp", feature = "socket-udp"))]
+            Icmpv4Repr::DstUnreachable {
+                reason,
+                header,
+                data,
+            } => {
+                let hard = matches!(
+                    reason,
+                    Icmpv4DstUnreachable::ProtoUnreachable | Icmpv4DstUnreachable::PortUnreachable
+                );
+                self.process_icmp_error(_sockets, IpRepr::Ipv4(header), data, hard);
+                None
+            }
+
             // Don't report an error if a packet with unknown type
             // has been handled by an ICMP socket
             #[cfg(feature = "socket-icmp")]
@@ -395,9 +504,12 @@ impl InterfaceInner {
     pub(super) fn dispatch_ipv4_frag<Tx: TxToken>(&mut self, tx_token: Tx, frag: &mut Fragmenter) {
         let caps = self.caps.clone();
 
-        let mtu_max = self.ip_mtu();
-        let ip_len = (frag.packet_len - frag.sent_bytes + frag.ipv4.repr.buffer_len()).min(mtu_max);
-        let payload_len = ip_len - frag.ipv4.repr.buffer_len();
+        // Every fragment, except the last one, carries a multiple of 8 octets.
+        let header_len = frag.ipv4.repr.buffer_len();
+        let mtu_max = self.path_mtu(&frag.ipv4.repr.dst_addr.into());
+        let max_payload_len = (mtu_max - header_len) & !0b111;
+        let payload_len = (frag.packet_len - frag.sent_bytes).min(max_payload_len);
+        let ip_len = header_len + payload_len;
 
         let more_frags = (frag.packet_len - frag.sent_bytes) != payload_len;
         frag.ipv4.repr.payload_len = payload_len;
@@ -481,6 +593,27 @@ impl InterfaceInner {
         Some(pkt)
     }
 
+    #[cfg(feature = "proto-igmp")]
+    pub(super) fn igmpv3_report_packet<'any>(
+        &self,
+        records: &'any [IgmpGroupRecordRepr<'any>],
+    ) -> Option<Packet<'any>> {
+        let iface_addr = self.ipv4_addr()?;
+        let igmp_repr = IgmpRepr::ReportRecordReprs(records);
+        let pkt = Packet::new_ipv4(
+            Ipv4Repr {
+                src_addr: iface_addr,
+                // All IGMPv3 reports are sent to 224.0.0.22, see RFC 3376 § 4.2.14.
+                dst_addr: Ipv4Address::MULTICAST_ALL_IGMPV3_ROUTERS,
+                next_header: IpProtocol::Igmp,
+                payload_len: igmp_repr.buffer_len(),
+                hop_limit: 1,
+            },
+            IpPayload::Igmp(igmp_repr),
+        );
+        Some(pkt)
+    }
+
     #[cfg(feature = "proto-igmp")]
     pub(super) fn igmp_leave_packet<'any>(&self, group_addr: Ipv4Address) -> Option<Packet<'any>> {
         self.ipv4_addr().map(|iface_addr| {
@@ -497,4 +630,17 @@ impl InterfaceInner {
             )
         })
     }
+
+    /// Create a gratuitous ARP request announcing that `addr` is ours, as specified by
+    /// RFC 5227 § 2.3.
+    #[cfg(feature = "medium-ethernet")]
+    pub(super) fn announce_arp_repr(&self, addr: Ipv4Address) -> ArpRepr {
+        ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Request,
+            source_hardware_addr: self.hardware_addr.ethernet_or_panic(),
+            source_protocol_addr: addr,
+            target_hardware_addr: EthernetAddress::default(),
+            target_protocol_addr: addr,
+        }
+    }
 }
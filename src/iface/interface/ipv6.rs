@@ -18,9 +18,55 @@ impl Default for HopByHopResponse<'_> {
     }
 }
 
+impl Interface {
+    /// Process fragments that still need to be sent for IPv6 packets.
+    ///
+    /// This function returns a boolean value indicating whether any packets were
+    /// processed or emitted, and thus, whether the readiness of any socket might
+    /// have changed.
+    #[cfg(feature = "proto-ipv6-fragmentation")]
+    pub(super) fn ipv6_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        // Reset the buffer when we transmitted everything.
+        if self.fragmenter.finished() {
+            self.fragmenter.reset();
+        }
+
+        if self.fragmenter.is_empty() {
+            return false;
+        }
+
+        // The fragmentation buffer is shared with IPv4.
+        if IpVersion::of_packet(&self.fragmenter.buffer) != Ok(IpVersion::Ipv6) {
+            return false;
+        }
+
+        let pkt = &self.fragmenter;
+        if pkt.packet_len > pkt.sent_bytes {
+            if let Some(tx_token) = device.transmit(self.inner.now) {
+                self.inner
+                    .dispatch_ipv6_frag(tx_token, &mut self.fragmenter);
+                return true;
+            }
+        }
+        false
+    }
+}
+
 impl InterfaceInner {
+    /// Get the next IPv6 fragment identification.
+    #[cfg(feature = "proto-ipv6-fragmentation")]
+    pub(super) fn next_ipv6_frag_ident(&mut self) -> u32 {
+        let ipv6_id = self.ipv6_id;
+        self.ipv6_id = self.ipv6_id.wrapping_add(1);
+        ipv6_id
+    }
+
     /// Return the IPv6 address that is a candidate source address for the given destination
-    /// address, based on RFC 6724.
+    /// address: the source address of the route to the destination if it is one of our
+    /// addresses, or an address selected based on RFC 6724.
     ///
     /// # Panics
     /// This function panics if the destination address is unspecified.
@@ -28,6 +74,16 @@ impl InterfaceInner {
     pub(crate) fn get_source_address_ipv6(&self, dst_addr: &Ipv6Address) -> Ipv6Address {
         assert!(!dst_addr.is_unspecified());
 
+        // The route to the destination may prefer one of our addresses.
+        if let Some(IpAddress::Ipv6(src_addr)) = self
+            .route_to(&IpAddress::Ipv6(*dst_addr))
+            .and_then(|route| route.src_addr)
+        {
+            if self.has_ip_addr(src_addr) {
+                return src_addr;
+            }
+        }
+
         // See RFC 6724 Section 4: Candidate source address
         fn is_candidate_source_address(dst_addr: &Ipv6Address, src_addr: &Ipv6Address) -> bool {
             // For all multicast and link-local destination addresses, the candidate address MUST
@@ -72,35 +128,24 @@ impl InterfaceInner {
             bits as usize
         }
 
+        // The IPv6 addresses of the interface, except tentative addresses which cannot be used.
+        let ipv6_addrs = || {
+            self.ip_addrs.iter().filter_map(|a| match a {
+                #[cfg(feature = "proto-ipv4")]
+                IpCidr::Ipv4(_) => None,
+                IpCidr::Ipv6(a) => (!self.is_tentative(&a.address().into())).then_some(a),
+            })
+        };
+
         // If the destination address is a loopback address, or when there are no IPv6 addresses in
         // the interface, then the loopback address is the only candidate source address.
-        if dst_addr.is_loopback()
-            || self
-                .ip_addrs
-                .iter()
-                .filter(|a| matches!(a, IpCidr::Ipv6(_)))
-                .count()
-                == 0
-        {
+        if dst_addr.is_loopback() || ipv6_addrs().next().is_none() {
             return Ipv6Address::LOOPBACK;
         }
 
-        let mut candidate = self
-            .ip_addrs
-            .iter()
-            .find_map(|a| match a {
-                #[cfg(feature = "proto-ipv4")]
-                IpCidr::Ipv4(_) => None,
-                IpCidr::Ipv6(a) => Some(a),
-            })
-            .unwrap(); // NOTE: we check above that there is at least one IPv6 address.
+        let mut candidate = ipv6_addrs().next().unwrap(); // NOTE: we check above that there is at least one IPv6 address.
 
-        for addr in self.ip_addrs.iter().filter_map(|a| match a {
-            #[cfg(feature = "proto-ipv4")]
-            IpCidr::Ipv4(_) => None,
-            #[cfg(feature = "proto-ipv6")]
-            IpCidr::Ipv6(a) => Some(a),
-        }) {
+        for addr in ipv6_addrs() {
             if !is_candidate_source_address(dst_addr, &addr.address()) {
                 continue;
             }
@@ -124,7 +169,24 @@ impl InterfaceInner {
                 candidate = addr;
             }
 
-            // Rule 3: avoid deprecated addresses (TODO)
+            // Rule 3: avoid deprecated addresses.
+            #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+            if let Some(slaac) = &self.slaac {
+                if candidate.address() != *dst_addr {
+                    match (
+                        slaac.is_deprecated(&candidate.address(), self.now),
+                        slaac.is_deprecated(&addr.address(), self.now),
+                    ) {
+                        (true, false) => {
+                            candidate = addr;
+                            continue;
+                        }
+                        (false, true) => continue,
+                        _ => (),
+                    }
+                }
+            }
+
             // Rule 4: prefer home addresses (TODO)
             // Rule 5: prefer outgoing interfaces (TODO)
             // Rule 5.5: prefer addresses in a prefix advertises by the next-hop (TODO).
@@ -167,14 +229,14 @@ impl InterfaceInner {
     }
 
     /// Get the first link-local IPv6 address of the interface, if present.
-    fn link_local_ipv6_address(&self) -> Option<Ipv6Address> {
+    pub(super) fn link_local_ipv6_address(&self) -> Option<Ipv6Address> {
         self.ip_addrs.iter().find_map(|addr| match *addr {
             #[cfg(feature = "proto-ipv4")]
             IpCidr::Ipv4(_) => None,
             #[cfg(feature = "proto-ipv6")]
             IpCidr::Ipv6(cidr) => {
                 let addr = cidr.address();
-                if addr.is_link_local() {
+                if addr.is_link_local() && !self.is_tentative(&addr.into()) {
                     Some(addr)
                 } else {
                     None
@@ -191,8 +253,12 @@ impl InterfaceInner {
     ) -> Option<Packet<'frame>> {
         let ipv6_repr = check!(Ipv6Repr::parse(ipv6_packet));
 
-        if !ipv6_repr.src_addr.is_unicast() {
-            // Discard packets with non-unicast source addresses.
+        // Discard packets with non-unicast source addresses. The unspecified address is used by
+        // nodes that do not have an address yet, for ICMPv6 messages such as the Neighbor
+        // Solicitations of Duplicate Address Detection.
+        if !(ipv6_repr.src_addr.is_unicast()
+            || (ipv6_repr.src_addr.is_unspecified() && ipv6_repr.next_header == IpProtocol::Icmpv6))
+        {
             net_debug!("non-unicast source address");
             return None;
         }
@@ -210,6 +276,11 @@ impl InterfaceInner {
             && !self.has_multicast_group(ipv6_repr.dst_addr)
             && !ipv6_repr.dst_addr.is_loopback()
         {
+            #[cfg(feature = "proto-rpl")]
+            if self.rpl_forwards(&ipv6_repr) {
+                return self.rpl_forward(ipv6_repr, next_header, ip_payload);
+            }
+
             // If AnyIP is enabled, also check if the packet is routed locally.
             if !self.any_ip
                 || !ipv6_repr.dst_addr.is_unicast()
@@ -218,6 +289,9 @@ impl InterfaceInner {
                     .lookup(&IpAddress::Ipv6(ipv6_repr.dst_addr), self.now)
                     .map_or(true, |router_addr| !self.has_ip_addr(router_addr))
             {
+                if self.forwarding {
+                    return self.forward_ipv6(ipv6_repr, ipv6_packet.payload());
+                }
                 net_trace!("packet IP address not for this interface");
                 return None;
             }
@@ -238,6 +312,96 @@ impl InterfaceInner {
         )
     }
 
+    /// Reassemble a fragmented IPv6 packet. Packets without a Fragment header are returned as is.
+    /// When the last missing fragment is received, the reassembled packet is returned, without
+    /// the Fragment header.
+    #[cfg(feature = "proto-ipv6-fragmentation")]
+    pub(super) fn process_ipv6_fragment<'frame>(
+        &mut self,
+        ipv6_packet: Ipv6Packet<&'frame [u8]>,
+        frag: &'frame mut FragmentsBuffer,
+    ) -> Option<Ipv6Packet<&'frame [u8]>> {
+        let ipv6_repr = check!(Ipv6Repr::parse(&ipv6_packet));
+        let total_len = ipv6_packet.total_len();
+        let data = &ipv6_packet.into_inner()[..total_len];
+
+        // The Fragment header follows the headers of the unfragmentable part of the packet. We
+        // remember where the Next Header field that points to the Fragment header is.
+        let mut next_header = ipv6_repr.next_header;
+        let mut next_header_pos = 6;
+        let mut offset = ipv6_repr.buffer_len();
+        loop {
+            match next_header {
+                IpProtocol::HopByHop | IpProtocol::Ipv6Route | IpProtocol::Ipv6Opts => {
+                    let ext_hdr = check!(Ipv6ExtHeader::new_checked(&data[offset..]));
+                    let ext_repr = check!(Ipv6ExtHeaderRepr::parse(&ext_hdr));
+                    next_header = ext_repr.next_header;
+                    next_header_pos = offset;
+                    offset += ext_repr.header_len() + ext_repr.data.len();
+                }
+                IpProtocol::Ipv6Frag => break,
+                _ => return Some(Ipv6Packet::new_unchecked(data)),
+            }
+        }
+
+        let ext_hdr = check!(Ipv6ExtHeader::new_checked(&data[offset..]));
+        let ext_repr = check!(Ipv6ExtHeaderRepr::parse(&ext_hdr));
+        let frag_hdr = check!(Ipv6FragmentHeader::new_checked(ext_repr.data));
+        let frag_repr = check!(Ipv6FragmentRepr::parse(&frag_hdr));
+
+        let unfragmentable_len = offset;
+        let fragment = &data[offset + ext_repr.header_len() + ext_repr.data.len()..];
+        let frag_offset = unfragmentable_len + frag_repr.frag_offset as usize * 8;
+
+        // Every fragment except the last one carries a multiple of 8 octets, see RFC 8200 § 4.5.
+        if frag_repr.more_frags && fragment.len() % 8 != 0 {
+            net_debug!("fragment length is not a multiple of 8 octets");
+            return None;
+        }
+
+        let key = FragKey::Ipv6(frag_hdr.get_key(&ipv6_repr));
+        let f = match frag.assembler.get(&key, self.now + frag.reassembly_timeout) {
+            Ok(f) => f,
+            Err(_) => {
+                net_debug!("No available packet assembler for fragmented packet");
+                return None;
+            }
+        };
+
+        if !frag_repr.more_frags {
+            // This is the last fragment, so we know the total size.
+            let total_size = frag_offset + fragment.len();
+            if total_size - ipv6_repr.buffer_len() > u16::MAX as usize {
+                net_debug!("reassembled packet is too large");
+                f.reset();
+                return None;
+            }
+            check!(f.set_total_size(total_size));
+        }
+
+        // The unfragmentable part of the first fragment is used for the reassembled packet.
+        if frag_repr.frag_offset == 0 {
+            if let Err(e) = f.add(&data[..unfragmentable_len], 0) {
+                net_debug!("fragmentation error: {:?}", e);
+                return None;
+            }
+        }
+
+        if let Err(e) = f.add(fragment, frag_offset) {
+            net_debug!("fragmentation error: {:?}", e);
+            return None;
+        }
+
+        let packet = f.assemble_mut()?;
+
+        // Remove the Fragment header from the chain of headers, and update the payload length.
+        packet[next_header_pos] = ext_repr.next_header.into();
+        let payload_len = (packet.len() - ipv6_repr.buffer_len()) as u16;
+        Ipv6Packet::new_unchecked(&mut *packet).set_payload_len(payload_len);
+
+        Some(check!(Ipv6Packet::new_checked(&*packet)))
+    }
+
     fn process_hopbyhop<'frame>(
         &mut self,
         ipv6_repr: Ipv6Repr,
@@ -306,6 +470,31 @@ impl InterfaceInner {
         handled_by_raw_socket: bool,
         ip_payload: &'frame [u8],
     ) -> Option<Packet<'frame>> {
+        #[cfg(feature = "socket-dhcpv6")]
+        if nxt_hdr == IpProtocol::Udp {
+            use crate::socket::dhcpv6::Socket as Dhcpv6Socket;
+
+            let udp_packet = check!(UdpPacket::new_checked(ip_payload));
+            // First check for source and dest ports, then do `UdpRepr::parse` if they match.
+            if udp_packet.src_port() == DHCPV6_SERVER_PORT
+                && udp_packet.dst_port() == DHCPV6_CLIENT_PORT
+            {
+                if let Some(dhcp_socket) = sockets
+                    .items_mut()
+                    .find_map(|i| Dhcpv6Socket::downcast_mut(&mut i.socket))
+                {
+                    let udp_repr = check!(UdpRepr::parse(
+                        &udp_packet,
+                        &ipv6_repr.src_addr.into(),
+                        &ipv6_repr.dst_addr.into(),
+                        &self.caps.checksum
+                    ));
+                    dhcp_socket.process(self, &ipv6_repr, &udp_repr, udp_packet.payload());
+                    return None;
+                }
+            }
+        }
+
         match nxt_hdr {
             IpProtocol::Icmpv6 => self.process_icmpv6(sockets, ipv6_repr, ip_payload),
 
@@ -321,6 +510,11 @@ impl InterfaceInner {
             #[cfg(feature = "socket-tcp")]
             IpProtocol::Tcp => self.process_tcp(sockets, ipv6_repr.into(), ip_payload),
 
+            #[cfg(feature = "proto-ipv6-routing")]
+            IpProtocol::Ipv6Route => {
+                self.process_routing(sockets, meta, ipv6_repr, handled_by_raw_socket, ip_payload)
+            }
+
             #[cfg(feature = "socket-raw")]
             _ if handled_by_raw_socket => None,
 
@@ -340,6 +534,77 @@ impl InterfaceInner {
         }
     }
 
+    /// Process a Routing header. When there are no segments left, the packet is processed
+    /// further. Otherwise, the packet is forwarded to the next address in the route.
+    #[cfg(feature = "proto-ipv6-routing")]
+    fn process_routing<'frame>(
+        &mut self,
+        sockets: &mut SocketSet,
+        meta: PacketMeta,
+        ipv6_repr: Ipv6Repr,
+        handled_by_raw_socket: bool,
+        ip_payload: &'frame [u8],
+    ) -> Option<Packet<'frame>> {
+        let ext_hdr = check!(Ipv6ExtHeader::new_checked(ip_payload));
+        let ext_repr = check!(Ipv6ExtHeaderRepr::parse(&ext_hdr));
+        let routing_hdr = check!(Ipv6RoutingHeader::new_checked(ext_repr.data));
+        let routing_repr = check!(Ipv6RoutingRepr::parse(&routing_hdr));
+        let payload = &ip_payload[ext_repr.header_len() + ext_repr.data.len()..];
+
+        match routing_repr {
+            Ipv6RoutingRepr::Type2 {
+                segments_left: 0, ..
+            }
+            | Ipv6RoutingRepr::Rpl {
+                segments_left: 0, ..
+            } => self.process_nxt_hdr(
+                sockets,
+                meta,
+                ipv6_repr,
+                ext_repr.next_header,
+                handled_by_raw_socket,
+                payload,
+            ),
+            #[cfg(feature = "proto-rpl")]
+            Ipv6RoutingRepr::Rpl {
+                segments_left,
+                cmpr_i,
+                cmpr_e,
+                pad,
+                addresses,
+            } => {
+                // The addresses are at the end of the header.
+                let addresses = &ext_repr.data[ext_repr.data.len() - addresses.len()..];
+                self.process_rpl_source_route(
+                    ipv6_repr,
+                    ext_repr.next_header,
+                    Ipv6RoutingRepr::Rpl {
+                        segments_left,
+                        cmpr_i,
+                        cmpr_e,
+                        pad,
+                        addresses,
+                    },
+                    ip_payload,
+                    payload,
+                )
+            }
+            _ => {
+                // The routing type is not supported.
+                let payload_len =
+                    icmp_reply_payload_len(ip_payload.len(), IPV6_MIN_MTU, ipv6_repr.buffer_len());
+                let icmp_reply_repr = Icmpv6Repr::ParamProblem {
+                    reason: Icmpv6ParamProblem::ErroneousHdrField,
+                    // The offset of the Routing Type field.
+                    pointer: ipv6_repr.buffer_len() as u32 + 2,
+                    header: ipv6_repr,
+                    data: &ip_payload[0..payload_len],
+                };
+                self.icmpv6_reply(ipv6_repr, icmp_reply_repr)
+            }
+        }
+    }
+
     pub(super) fn process_icmpv6<'frame>(
         &mut self,
         _sockets: &mut SocketSet,
@@ -354,6 +619,16 @@ impl InterfaceInner {
             &self.caps.checksum,
         ));
 
+        // Only NDISC messages can be sent from the unspecified address.
+        #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+        let is_ndisc = matches!(icmp_repr, Icmpv6Repr::Ndisc(_));
+        #[cfg(not(any(feature = "medium-ethernet", feature = "medium-ieee802154")))]
+        let is_ndisc = false;
+        if ip_repr.src_addr.is_unspecified() && !is_ndisc {
+            net_debug!("non-unicast source address");
+            return None;
+        }
+
         #[cfg(feature = "socket-icmp")]
         let mut handled_by_icmp_socket = false;
 
@@ -389,6 +664,26 @@ impl InterfaceInner {
             // Ignore any echo replies.
             Icmpv6Repr::EchoReply { .. } => None,
 
+            // Lower the path MTU estimate of a destination we sent a packet to, see RFC 8201.
+            Icmpv6Repr::PktTooBig { mtu, header, .. } if self.has_ip_addr(header.src_addr) => {
+                let mtu = (mtu as usize).max(IPV6_MIN_MTU);
+                self.pmtu_cache.fill(header.dst_addr.into(), mtu, self.now);
+                None
+            }
+
+            // Deliver destination unreachable errors to the socket that sent the packet. Port
+            // unreachable errors are hard errors, see RFC 1122 § 4.2.3.9.
+            #[cfg(any(feature = "socket-tcp", feature = "socket-udp"))]
+            Icmpv6Repr::DstUnreachable {
+                reason,
+                header,
+                data,
+            } => {
+                let hard = reason == Icmpv6DstUnreachable::PortUnreachable;
+                self.process_icmp_error(_sockets, IpRepr::Ipv6(header), data, hard);
+                None
+            }
+
             // Forward any NDISC packets to the ndisc packet handler
             #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
             Icmpv6Repr::Ndisc(repr) if ip_repr.hop_limit == 0xff => match self.caps.medium {
@@ -400,6 +695,18 @@ impl InterfaceInner {
                 Medium::Ip => None,
             },
 
+            // Schedule the response to Multicast Listener Queries.
+            Icmpv6Repr::Mld(MldRepr::Query {
+                max_resp_code,
+                mcast_addr,
+                qrv,
+                ..
+            }) => self.process_mld_query(ip_repr, max_resp_code, mcast_addr, qrv),
+
+            // Forward any RPL control messages to the RPL routing engine
+            #[cfg(feature = "proto-rpl")]
+            Icmpv6Repr::Rpl(repr) => self.process_rpl(ip_repr, repr),
+
             // Don't report an error if a packet with unknown type
             // has been handled by an ICMP socket
             #[cfg(feature = "socket-icmp")]
@@ -422,18 +729,45 @@ impl InterfaceInner {
                 target_addr,
                 flags,
             } => {
+                if self.dad.is_tentative(&target_addr) {
+                    self.dad_duplicate(target_addr);
+                    return None;
+                }
+
                 let ip_addr = ip_repr.src_addr.into();
                 if let Some(lladdr) = lladdr {
                     let lladdr = check!(lladdr.parse(self.caps.medium));
                     if !lladdr.is_unicast() || !target_addr.is_unicast() {
                         return None;
                     }
-                    if flags.contains(NdiscNeighborFlags::OVERRIDE)
-                        || !self.neighbor_cache.lookup(&ip_addr, self.now).found()
-                    {
-                        self.neighbor_cache.fill(ip_addr, lladdr, self.now)
+                    self.neighbor_cache.fill_advert(
+                        ip_addr,
+                        lladdr,
+                        flags.contains(NdiscNeighborFlags::SOLICITED),
+                        flags.contains(NdiscNeighborFlags::OVERRIDE),
+                        self.now,
+                    );
+                } else if flags.contains(NdiscNeighborFlags::SOLICITED) {
+                    self.neighbor_cache.confirm(&ip_addr, self.now);
+                }
+                None
+            }
+            NdiscRepr::RouterAdvert {
+                router_lifetime,
+                lladdr,
+                prefix_info,
+                ..
+            } => {
+                if let Some(lladdr) = lladdr {
+                    let lladdr = check!(lladdr.parse(self.caps.medium));
+                    if !lladdr.is_unicast() {
+                        return None;
                     }
+                    self.neighbor_cache
+                        .fill_stale(ip_repr.src_addr.into(), lladdr, self.now);
                 }
+
+                self.process_router_advert(ip_repr, router_lifetime, prefix_info);
                 None
             }
             NdiscRepr::NeighborSolicit {
@@ -441,25 +775,50 @@ impl InterfaceInner {
                 lladdr,
                 ..
             } => {
+                if self.dad.is_tentative(&target_addr) {
+                    // Another node performing Duplicate Address Detection for the same address
+                    // sends solicitations from the unspecified address. Other solicitations for
+                    // a tentative address are ignored.
+                    if ip_repr.src_addr.is_unspecified() {
+                        self.dad_duplicate(target_addr);
+                    }
+                    return None;
+                }
+
                 if let Some(lladdr) = lladdr {
+                    // Solicitations from the unspecified address cannot contain a link-layer
+                    // address, see RFC 4861 § 7.1.1.
+                    if ip_repr.src_addr.is_unspecified() {
+                        return None;
+                    }
                     let lladdr = check!(lladdr.parse(self.caps.medium));
                     if !lladdr.is_unicast() || !target_addr.is_unicast() {
                         return None;
                     }
                     self.neighbor_cache
-                        .fill(ip_repr.src_addr.into(), lladdr, self.now);
+                        .fill_stale(ip_repr.src_addr.into(), lladdr, self.now);
                 }
 
                 if self.has_solicited_node(ip_repr.dst_addr) && self.has_ip_addr(target_addr) {
+                    // A solicitation from the unspecified address is sent by a node performing
+                    // Duplicate Address Detection, and is answered to all nodes.
+                    let (flags, dst_addr) = if ip_repr.src_addr.is_unspecified() {
+                        (
+                            NdiscNeighborFlags::empty(),
+                            Ipv6Address::LINK_LOCAL_ALL_NODES,
+                        )
+                    } else {
+                        (NdiscNeighborFlags::SOLICITED, ip_repr.src_addr)
+                    };
                     let advert = Icmpv6Repr::Ndisc(NdiscRepr::NeighborAdvert {
-                        flags: NdiscNeighborFlags::SOLICITED,
+                        flags,
                         target_addr,
                         #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
                         lladdr: Some(self.hardware_addr.into()),
                     });
                     let ip_repr = Ipv6Repr {
                         src_addr: target_addr,
-                        dst_addr: ip_repr.src_addr,
+                        dst_addr,
                         next_header: IpProtocol::Icmpv6,
                         hop_limit: 0xff,
                         payload_len: advert.buffer_len(),
@@ -547,4 +906,130 @@ impl InterfaceInner {
             IpPayload::HopByHopIcmpv6(hbh_repr, Icmpv6Repr::Mld(mld_repr)),
         ))
     }
+
+    #[cfg(feature = "proto-ipv6-fragmentation")]
+    pub(super) fn dispatch_ipv6_frag<Tx: TxToken>(&mut self, tx_token: Tx, frag: &mut Fragmenter) {
+        let unfragmentable_len = frag.ipv6.unfragmentable_len;
+
+        // The Fragment header takes over the Next Header of the last unfragmentable header.
+        let next_header = match frag.ipv6.last_ext_header {
+            Some(offset) => Ipv6ExtHeader::new_unchecked(&frag.buffer[offset..]).next_header(),
+            None => frag.ipv6.repr.next_header,
+        };
+        let ext_header = Ipv6ExtHeaderRepr {
+            next_header,
+            length: 0,
+            data: &[],
+        };
+        let mut frag_repr = Ipv6FragmentRepr {
+            frag_offset: ((frag.sent_bytes - unfragmentable_len) / 8) as u16,
+            more_frags: false,
+            ident: frag.ipv6.ident,
+        };
+        let frag_header_start = unfragmentable_len + ext_header.header_len();
+        let frag_header_end = frag_header_start + frag_repr.buffer_len();
+
+        // Every fragment, except the last one, carries a multiple of 8 octets.
+        let remaining = frag.packet_len - frag.sent_bytes;
+        let path_mtu = self.path_mtu(&frag.ipv6.repr.dst_addr.into());
+        let max_payload_len = (path_mtu - frag_header_end) & !0b111;
+        let payload_len = remaining.min(max_payload_len);
+        frag_repr.more_frags = remaining != payload_len;
+
+        #[allow(unused_mut)]
+        let mut tx_len = frag_header_end + payload_len;
+        #[cfg(feature = "medium-ethernet")]
+        if matches!(self.caps.medium, Medium::Ethernet) {
+            tx_len += EthernetFrame::<&[u8]>::header_len();
+        }
+
+        #[allow(unused_mut)]
+        tx_token.consume(tx_len, |mut tx_buffer| {
+            #[cfg(feature = "medium-ethernet")]
+            if matches!(self.caps.medium, Medium::Ethernet) {
+                let mut frame = EthernetFrame::new_unchecked(&mut tx_buffer);
+                frame.set_src_addr(self.hardware_addr.ethernet_or_panic());
+                frame.set_dst_addr(frag.ipv6.dst_hardware_addr);
+                frame.set_ethertype(EthernetProtocol::Ipv6);
+                tx_buffer = &mut tx_buffer[EthernetFrame::<&[u8]>::header_len()..];
+            }
+
+            tx_buffer[..unfragmentable_len].copy_from_slice(&frag.buffer[..unfragmentable_len]);
+            let mut packet = Ipv6Packet::new_unchecked(&mut tx_buffer[..unfragmentable_len]);
+            packet.set_payload_len((frag_header_end - packet.header_len() + payload_len) as u16);
+            match frag.ipv6.last_ext_header {
+                Some(offset) => Ipv6ExtHeader::new_unchecked(&mut tx_buffer[offset..])
+                    .set_next_header(IpProtocol::Ipv6Frag),
+                None => packet.set_next_header(IpProtocol::Ipv6Frag),
+            }
+
+            ext_header.emit(&mut Ipv6ExtHeader::new_unchecked(
+                &mut tx_buffer[unfragmentable_len..frag_header_start],
+            ));
+            frag_repr.emit(&mut Ipv6FragmentHeader::new_unchecked(
+                &mut tx_buffer[frag_header_start..frag_header_end],
+            ));
+
+            tx_buffer[frag_header_end..][..payload_len]
+                .copy_from_slice(&frag.buffer[frag.sent_bytes..][..payload_len]);
+        });
+
+        frag.sent_bytes += payload_len;
+    }
+
+    /// Create an unsolicited Neighbor Advertisement announcing that `addr` is ours, sent to all
+    /// nodes, and overriding the hardware address in their cache.
+    #[cfg(feature = "medium-ethernet")]
+    pub(super) fn announce_advert_packet(&self, addr: Ipv6Address) -> Packet<'static> {
+        let advert = Icmpv6Repr::Ndisc(NdiscRepr::NeighborAdvert {
+            flags: NdiscNeighborFlags::OVERRIDE,
+            target_addr: addr,
+            lladdr: Some(self.hardware_addr.into()),
+        });
+        Packet::new_ipv6(
+            Ipv6Repr {
+                src_addr: addr,
+                dst_addr: Ipv6Address::LINK_LOCAL_ALL_NODES,
+                next_header: IpProtocol::Icmpv6,
+                payload_len: advert.buffer_len(),
+                hop_limit: 0xff,
+            },
+            IpPayload::Icmpv6(advert),
+        )
+    }
+}
+
+/// Return the length of the unfragmentable part of the IPv6 packet in `buffer`, and the offset
+/// of its last extension header, if any.
+///
+/// The unfragmentable part is the IPv6 header, followed by the extension headers up to the
+/// Routing header, or up to the Hop-by-Hop Options header when there is no Routing header, see
+/// [RFC 8200 § 4.5].
+///
+/// [RFC 8200 § 4.5]: https://datatracker.ietf.org/doc/html/rfc8200#section-4.5
+#[cfg(feature = "proto-ipv6-fragmentation")]
+pub(super) fn unfragmentable_part(buffer: &[u8]) -> (usize, Option<usize>) {
+    let packet = Ipv6Packet::new_unchecked(buffer);
+    let mut next_header = packet.next_header();
+    let mut offset = packet.header_len();
+    let mut unfragmentable = (offset, None);
+
+    while matches!(
+        next_header,
+        IpProtocol::HopByHop | IpProtocol::Ipv6Opts | IpProtocol::Ipv6Route
+    ) {
+        let Ok(header) = Ipv6ExtHeader::new_checked(&buffer[offset..]) else {
+            break;
+        };
+        let start = offset;
+        offset += 8 + header.header_len() as usize * 8;
+
+        // Destination Options are only processed on the way when they precede a Routing header.
+        if next_header != IpProtocol::Ipv6Opts {
+            unfragmentable = (offset, Some(start));
+        }
+        next_header = header.next_header();
+    }
+
+    unfragmentable
 }
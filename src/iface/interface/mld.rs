@@ -0,0 +1,115 @@
+// Multicast Listener Discovery Version 2, see RFC 3810.
+
+use super::multicast::{Record, MAX_RECORD_COUNT};
+use super::*;
+
+/// Decode the Maximum Response Code of a query, see RFC 3810 § 5.1.3.
+fn max_resp_delay(code: u16) -> Duration {
+    let millis = if code < 0x8000 {
+        code as u64
+    } else {
+        let mant = (code & 0x0fff) as u64;
+        let exp = ((code >> 12) & 0x7) as u64;
+        (mant | 0x1000) << (exp + 3)
+    };
+    Duration::from_millis(millis)
+}
+
+impl Interface {
+    /// Send the reports of the Multicast Listener Discovery that are due: responses to
+    /// queries, and retransmissions of State Change Reports.
+    pub(super) fn mld_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        let now = self.inner.now;
+        let Some((report, records)) = self.inner.mld.due(now, &self.inner.ipv6_multicast_groups)
+        else {
+            return false;
+        };
+
+        if !records.is_empty() {
+            let Some(tx_token) = device.transmit(now) else {
+                return false;
+            };
+            net_trace!("mld: sending report with {} records", records.len());
+            self.inner
+                .mldv2_dispatch(tx_token, &records, &mut self.fragmenter);
+        }
+
+        self.inner.mld.sent(report, now, &mut self.inner.rand);
+        true
+    }
+}
+
+impl InterfaceInner {
+    /// Send an MLDv2 report holding `records`.
+    pub(super) fn mldv2_dispatch<Tx: TxToken>(
+        &mut self,
+        tx_token: Tx,
+        records: &[Record<Ipv6Address>],
+        frag: &mut Fragmenter,
+    ) {
+        let reprs = records
+            .iter()
+            .map(|record| MldAddressRecordRepr {
+                record_type: record.record_type.into(),
+                aux_data_len: 0,
+                num_srcs: record.num_srcs,
+                mcast_addr: record.group_addr,
+                payload: &record.sources,
+            })
+            .collect::<Vec<_, MAX_RECORD_COUNT>>();
+
+        // NOTE(unwrap): this always returns Some.
+        let pkt = self.mldv2_report_packet(&reprs).unwrap();
+
+        // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
+        self.dispatch_ip(tx_token, PacketMeta::default(), pkt, frag)
+            .unwrap();
+    }
+
+    /// Schedule the response to a Multicast Listener Query, see RFC 3810 § 6.2.
+    pub(super) fn process_mld_query(
+        &mut self,
+        ip_repr: Ipv6Repr,
+        max_resp_code: u16,
+        mcast_addr: Ipv6Address,
+        qrv: u8,
+    ) -> Option<Packet<'static>> {
+        // Queries must be sent from a link-local address, see RFC 3810 § 5.1.14.
+        if !ip_repr.src_addr.is_link_local() {
+            net_debug!("mld: ignoring query from {}", ip_repr.src_addr);
+            return None;
+        }
+
+        let group_addr = if mcast_addr.is_unspecified() {
+            None
+        } else if self.ipv6_multicast_groups.contains_key(&mcast_addr) {
+            Some(mcast_addr)
+        } else {
+            return None;
+        };
+        self.mld.schedule_response(
+            group_addr,
+            max_resp_delay(max_resp_code),
+            qrv,
+            self.now,
+            &mut self.rand,
+        );
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_max_resp_delay() {
+        assert_eq!(max_resp_delay(10_000), Duration::from_secs(10));
+        assert_eq!(max_resp_delay(0x8000), Duration::from_millis(0x1000 << 3));
+        assert_eq!(max_resp_delay(0xffff), Duration::from_millis(0x1fff << 10));
+    }
+}
@@ -17,15 +17,47 @@ mod ipv6;
 #[cfg(feature = "proto-sixlowpan")]
 mod sixlowpan;
 
+#[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+mod acd;
+#[cfg(feature = "medium-ethernet")]
+mod announce;
+#[cfg(all(
+    feature = "proto-ipv6",
+    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+))]
+mod dad;
+mod forward;
 #[cfg(feature = "proto-igmp")]
 mod igmp;
+#[cfg(feature = "proto-ipv6")]
+mod mld;
+#[cfg(any(feature = "proto-igmp", feature = "proto-ipv6"))]
+mod multicast;
+#[cfg(feature = "proto-rpl")]
+mod rpl;
+#[cfg(all(
+    feature = "proto-ipv6",
+    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+))]
+mod slaac;
 #[cfg(feature = "socket-tcp")]
 mod tcp;
 #[cfg(any(feature = "socket-udp", feature = "socket-dns"))]
 mod udp;
 
+pub use forward::InterfaceDevice;
 #[cfg(feature = "proto-igmp")]
 pub use igmp::MulticastError;
+#[cfg(feature = "proto-igmp")]
+pub use multicast::MulticastFilterMode;
+
+#[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+pub use acd::AcdEvent;
+#[cfg(all(
+    feature = "proto-ipv6",
+    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+))]
+pub use dad::DadEvent;
 
 use super::packet::*;
 
@@ -34,18 +66,25 @@ use heapless::{LinearMap, Vec};
 
 #[cfg(feature = "_proto-fragmentation")]
 use super::fragmentation::FragKey;
-#[cfg(any(feature = "proto-ipv4", feature = "proto-sixlowpan"))]
+#[cfg(feature = "_proto-fragmentation")]
 use super::fragmentation::PacketAssemblerSet;
 use super::fragmentation::{Fragmenter, FragmentsBuffer};
 
+#[cfg(feature = "medium-ethernet")]
+use super::neighbor::PendingQueue;
 #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
-use super::neighbor::{Answer as NeighborAnswer, Cache as NeighborCache};
+use super::neighbor::{
+    Answer as NeighborAnswer, Cache as NeighborCache, NeighborEntry, NeighborError,
+};
+use super::pmtu::Cache as PmtuCache;
+#[cfg(feature = "proto-rpl")]
+use super::rpl::Rpl;
 use super::socket_set::SocketSet;
 use crate::config::{
     IFACE_MAX_ADDR_COUNT, IFACE_MAX_MULTICAST_GROUP_COUNT,
     IFACE_MAX_SIXLOWPAN_ADDRESS_CONTEXT_COUNT,
 };
-use crate::iface::Routes;
+use crate::iface::{Route, RouteKind, Routes};
 use crate::phy::PacketMeta;
 use crate::phy::{ChecksumCapabilities, Device, DeviceCapabilities, Medium, RxToken, TxToken};
 use crate::rand::Rand;
@@ -96,6 +135,9 @@ pub struct InterfaceInner {
 
     #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
     neighbor_cache: NeighborCache,
+    #[cfg(feature = "medium-ethernet")]
+    pending_queue: Option<PendingQueue<'static>>,
+    pmtu_cache: PmtuCache,
     hardware_addr: HardwareAddress,
     #[cfg(feature = "medium-ieee802154")]
     sequence_no: u8,
@@ -103,6 +145,8 @@ pub struct InterfaceInner {
     pan_id: Option<Ieee802154Pan>,
     #[cfg(feature = "proto-ipv4-fragmentation")]
     ipv4_id: u16,
+    #[cfg(feature = "proto-ipv6-fragmentation")]
+    ipv6_id: u32,
     #[cfg(feature = "proto-sixlowpan")]
     sixlowpan_address_context:
         Vec<SixlowpanAddressContext, IFACE_MAX_SIXLOWPAN_ADDRESS_CONTEXT_COUNT>,
@@ -110,14 +154,52 @@ pub struct InterfaceInner {
     tag: u16,
     ip_addrs: Vec<IpCidr, IFACE_MAX_ADDR_COUNT>,
     any_ip: bool,
+    forwarding: bool,
     routes: Routes,
     #[cfg(feature = "proto-igmp")]
-    ipv4_multicast_groups: LinearMap<Ipv4Address, (), IFACE_MAX_MULTICAST_GROUP_COUNT>,
+    ipv4_multicast_groups: LinearMap<
+        Ipv4Address,
+        multicast::MulticastFilter<Ipv4Address>,
+        IFACE_MAX_MULTICAST_GROUP_COUNT,
+    >,
     #[cfg(feature = "proto-ipv6")]
-    ipv6_multicast_groups: LinearMap<Ipv6Address, (), IFACE_MAX_MULTICAST_GROUP_COUNT>,
+    ipv6_multicast_groups: LinearMap<
+        Ipv6Address,
+        multicast::MulticastFilter<Ipv6Address>,
+        IFACE_MAX_MULTICAST_GROUP_COUNT,
+    >,
     /// When to report for (all or) the next multicast group membership via IGMP
     #[cfg(feature = "proto-igmp")]
     igmp_report_state: IgmpReportState,
+    /// The version of the oldest IGMP querier heard, and until when it is considered present.
+    #[cfg(feature = "proto-igmp")]
+    igmp_older_querier: Option<(IgmpVersion, Instant)>,
+    #[cfg(feature = "proto-igmp")]
+    igmpv3: multicast::Reporter<Ipv4Address>,
+    #[cfg(feature = "proto-ipv6")]
+    mld: multicast::Reporter<Ipv6Address>,
+    #[cfg(feature = "proto-rpl")]
+    rpl: Option<Rpl>,
+    #[cfg(all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    slaac: Option<slaac::Slaac>,
+    #[cfg(all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    dad: dad::Dad,
+    #[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+    acd: acd::Acd,
+    #[cfg(feature = "medium-ethernet")]
+    announcer: announce::Announcer,
+    /// Whether an mDNS responder socket exists, whose multicast groups are listened to.
+    #[cfg(feature = "socket-mdns")]
+    mdns_responder: bool,
+    /// The multicast groups joined on behalf of the mDNS responder sockets.
+    #[cfg(all(feature = "socket-mdns", feature = "proto-igmp"))]
+    mdns_joined_groups: Vec<IpAddress, { mdns::GROUP_COUNT }>,
 }
 
 /// Configuration structure used for creating a network interface.
@@ -142,6 +224,58 @@ pub struct Config {
     /// **NOTE**: we use the same PAN ID for destination and source.
     #[cfg(feature = "medium-ieee802154")]
     pub pan_id: Option<Ieee802154Pan>,
+
+    /// Enable the RPL routing protocol on the interface, using the given configuration.
+    ///
+    /// **NOTE**: RPL messages are sent from the link-local address of the interface, which
+    /// should thus be added to the IP addresses of the interface.
+    #[cfg(feature = "proto-rpl")]
+    pub rpl_config: Option<super::RplConfig>,
+
+    /// Enable IPv6 stateless address autoconfiguration (SLAAC).
+    ///
+    /// The interface forms a link-local address from its hardware address, solicits Router
+    /// Advertisements, and forms addresses from the advertised prefixes. The default route is
+    /// installed from the router lifetime. Addresses and routes are removed when their lifetime
+    /// expires.
+    ///
+    /// **NOTE**: only /64 prefixes can be used, since the interface identifier is formed using
+    /// the 64-bit EUI-64 of the hardware address.
+    #[cfg(all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    pub slaac: bool,
+
+    /// The number of Neighbor Solicitations sent for the Duplicate Address Detection (DAD) of
+    /// new IPv6 addresses. Zero, the default, disables DAD.
+    ///
+    /// While DAD is in progress, an address is tentative: it is not used as source address and
+    /// packets sent to it are dropped. When another node uses the address, it is removed from
+    /// the interface. See [`Interface::dad_event`].
+    #[cfg(all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    pub dad_transmits: u8,
+
+    /// The number of times the addresses of the interface are announced to its neighbors when
+    /// they change, or when the hardware address changes. Zero, the default, disables the
+    /// announcements.
+    ///
+    /// IPv4 addresses are announced with gratuitous ARP requests, and IPv6 addresses with
+    /// unsolicited Neighbor Advertisements. See also [`Interface::announce`].
+    #[cfg(feature = "medium-ethernet")]
+    pub announce_count: u8,
+
+    /// Enable the Address Conflict Detection (ACD) of new IPv4 addresses.
+    ///
+    /// While ACD is in progress, an address is tentative: it is not used as source address and
+    /// packets sent to it are dropped. It is assigned after three ARP probes without answer,
+    /// and announced. When another node uses the address, it is removed from the interface.
+    /// See [`Interface::acd_event`].
+    #[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+    pub ipv4_acd: bool,
 }
 
 impl Config {
@@ -151,6 +285,22 @@ impl Config {
             hardware_addr,
             #[cfg(feature = "medium-ieee802154")]
             pan_id: None,
+            #[cfg(feature = "proto-rpl")]
+            rpl_config: None,
+            #[cfg(all(
+                feature = "proto-ipv6",
+                any(feature = "medium-ethernet", feature = "medium-ieee802154")
+            ))]
+            slaac: false,
+            #[cfg(all(
+                feature = "proto-ipv6",
+                any(feature = "medium-ethernet", feature = "medium-ieee802154")
+            ))]
+            dad_transmits: 0,
+            #[cfg(feature = "medium-ethernet")]
+            announce_count: 0,
+            #[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+            ipv4_acd: false,
         }
     }
 }
@@ -206,7 +356,49 @@ impl Interface {
             }
         }
 
-        Interface {
+        #[cfg(feature = "proto-ipv6-fragmentation")]
+        let ipv6_id = rand.rand_u32();
+
+        #[cfg(feature = "proto-rpl")]
+        let rpl = config
+            .rpl_config
+            .map(|rpl_config| Rpl::new(rpl_config, now, &mut rand));
+
+        #[cfg(all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        ))]
+        let slaac = if config.slaac {
+            slaac::Slaac::new(&config.hardware_addr, now, &mut rand)
+        } else {
+            None
+        };
+
+        #[cfg(all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        ))]
+        let dad_transmits = match caps.medium {
+            #[cfg(feature = "medium-ip")]
+            Medium::Ip => 0,
+            #[allow(unreachable_patterns)]
+            _ => config.dad_transmits,
+        };
+
+        #[allow(unused_mut)]
+        let mut ip_addrs = Vec::new();
+        #[cfg(all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        ))]
+        if let Some(slaac) = &slaac {
+            ip_addrs
+                .push(IpCidr::Ipv6(slaac.link_local_address()))
+                .unwrap();
+        }
+
+        #[allow(unused_mut, clippy::let_and_return)]
+        let mut iface = Interface {
             fragments: FragmentsBuffer {
                 #[cfg(feature = "proto-sixlowpan")]
                 decompress_buf: [0u8; sixlowpan::MAX_DECOMPRESSED_LEN],
@@ -221,17 +413,27 @@ impl Interface {
                 now,
                 caps,
                 hardware_addr: config.hardware_addr,
-                ip_addrs: Vec::new(),
+                ip_addrs,
                 any_ip: false,
+                forwarding: false,
                 routes: Routes::new(),
                 #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
                 neighbor_cache: NeighborCache::new(),
+                #[cfg(feature = "medium-ethernet")]
+                pending_queue: None,
+                pmtu_cache: PmtuCache::new(),
                 #[cfg(feature = "proto-igmp")]
                 ipv4_multicast_groups: LinearMap::new(),
                 #[cfg(feature = "proto-ipv6")]
                 ipv6_multicast_groups: LinearMap::new(),
                 #[cfg(feature = "proto-igmp")]
                 igmp_report_state: IgmpReportState::Inactive,
+                #[cfg(feature = "proto-igmp")]
+                igmp_older_querier: None,
+                #[cfg(feature = "proto-igmp")]
+                igmpv3: multicast::Reporter::new(),
+                #[cfg(feature = "proto-ipv6")]
+                mld: multicast::Reporter::new(),
                 #[cfg(feature = "medium-ieee802154")]
                 sequence_no,
                 #[cfg(feature = "medium-ieee802154")]
@@ -240,11 +442,43 @@ impl Interface {
                 tag,
                 #[cfg(feature = "proto-ipv4-fragmentation")]
                 ipv4_id,
+                #[cfg(feature = "proto-ipv6-fragmentation")]
+                ipv6_id,
                 #[cfg(feature = "proto-sixlowpan")]
                 sixlowpan_address_context: Vec::new(),
+                #[cfg(feature = "proto-rpl")]
+                rpl,
+                #[cfg(all(
+                    feature = "proto-ipv6",
+                    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+                ))]
+                slaac,
+                #[cfg(all(
+                    feature = "proto-ipv6",
+                    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+                ))]
+                dad: dad::Dad::new(dad_transmits),
+                #[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+                acd: acd::Acd::new(config.ipv4_acd),
+                #[cfg(feature = "medium-ethernet")]
+                announcer: announce::Announcer::new(config.announce_count),
+                #[cfg(feature = "socket-mdns")]
+                mdns_responder: false,
+                #[cfg(all(feature = "socket-mdns", feature = "proto-igmp"))]
+                mdns_joined_groups: Vec::new(),
                 rand,
             },
-        }
+        };
+
+        #[cfg(all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        ))]
+        iface.inner.dad_update(&[]);
+        #[cfg(feature = "medium-ethernet")]
+        iface.inner.announce_update(&[]);
+
+        iface
     }
 
     /// Get the socket context.
@@ -294,6 +528,9 @@ impl Interface {
 
         InterfaceInner::check_hardware_addr(&addr);
         self.inner.hardware_addr = addr;
+
+        #[cfg(feature = "medium-ethernet")]
+        self.inner.announce_update(&[]);
     }
 
     /// Get the IP addresses of the interface.
@@ -313,22 +550,26 @@ impl Interface {
         self.inner.ipv6_addr()
     }
 
-    /// Get an address from the interface that could be used as source address. For IPv4, this is
-    /// the first IPv4 address from the list of addresses. For IPv6, the address is based on the
-    /// destination address and uses RFC6724 for selecting the source address.
+    /// Get an address from the interface that could be used as source address. The source
+    /// address of the route to the destination is preferred, if it is one of the addresses of the
+    /// interface. Otherwise, for IPv4, this is the first IPv4 address from the list of addresses.
+    /// For IPv6, the address is based on the destination address and uses RFC6724 for selecting
+    /// the source address.
     pub fn get_source_address(&self, dst_addr: &IpAddress) -> Option<IpAddress> {
         self.inner.get_source_address(dst_addr)
     }
 
-    /// Get an address from the interface that could be used as source address. This is the first
-    /// IPv4 address from the list of addresses in the interface.
+    /// Get an address from the interface that could be used as source address. This is the
+    /// source address of the route to the destination, if it is one of the addresses of the
+    /// interface, or the first IPv4 address from the list of addresses in the interface.
     #[cfg(feature = "proto-ipv4")]
     pub fn get_source_address_ipv4(&self, dst_addr: &Ipv4Address) -> Option<Ipv4Address> {
         self.inner.get_source_address_ipv4(dst_addr)
     }
 
-    /// Get an address from the interface that could be used as source address. The selection is
-    /// based on RFC6724.
+    /// Get an address from the interface that could be used as source address. This is the
+    /// source address of the route to the destination, if it is one of the addresses of the
+    /// interface. Otherwise the selection is based on RFC6724.
     #[cfg(feature = "proto-ipv6")]
     pub fn get_source_address_ipv6(&self, dst_addr: &Ipv6Address) -> Ipv6Address {
         self.inner.get_source_address_ipv6(dst_addr)
@@ -339,9 +580,25 @@ impl Interface {
     /// # Panics
     /// This function panics if any of the addresses are not unicast.
     pub fn update_ip_addrs<F: FnOnce(&mut Vec<IpCidr, IFACE_MAX_ADDR_COUNT>)>(&mut self, f: F) {
+        #[cfg(any(
+            feature = "medium-ethernet",
+            all(feature = "proto-ipv6", feature = "medium-ieee802154")
+        ))]
+        let old_addrs = self.inner.ip_addrs.clone();
+
         f(&mut self.inner.ip_addrs);
         InterfaceInner::flush_neighbor_cache(&mut self.inner);
-        InterfaceInner::check_ip_addrs(&self.inner.ip_addrs)
+        InterfaceInner::check_ip_addrs(&self.inner.ip_addrs);
+
+        #[cfg(all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        ))]
+        self.inner.dad_update(&old_addrs);
+        #[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+        self.inner.acd_update(&old_addrs);
+        #[cfg(feature = "medium-ethernet")]
+        self.inner.announce_update(&old_addrs);
     }
 
     /// Check whether the interface has the given IP address assigned.
@@ -349,6 +606,14 @@ impl Interface {
         self.inner.has_ip_addr(addr)
     }
 
+    /// Return the path MTU estimate to a destination.
+    ///
+    /// This is the MTU of the interface, unless an ICMP "fragmentation needed" or "packet too
+    /// big" message reported a smaller MTU on the path to the destination.
+    pub fn path_mtu(&self, dst_addr: IpAddress) -> usize {
+        self.inner.path_mtu(&dst_addr)
+    }
+
     pub fn routes(&self) -> &Routes {
         &self.inner.routes
     }
@@ -357,6 +622,44 @@ impl Interface {
         &mut self.inner.routes
     }
 
+    /// Get the neighbors in the neighbor cache, with their state at the last time the interface
+    /// was polled.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub fn neighbors(&self) -> impl Iterator<Item = NeighborEntry> + '_ {
+        self.inner.neighbor_cache.entries(self.inner.now)
+    }
+
+    /// Add a neighbor that never expires, and whose reachability is never verified.
+    ///
+    /// A static neighbor is still updated by the address resolution packets it sends, unless it
+    /// is `permanent`. Permanent neighbors are also never evicted from the cache, which fails to
+    /// hold more neighbors once it is full of them.
+    ///
+    /// Returns an error if the cache is full of permanent neighbors, or if either address is
+    /// not unicast.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub fn add_static_neighbor(
+        &mut self,
+        protocol_addr: IpAddress,
+        hardware_addr: HardwareAddress,
+        permanent: bool,
+    ) -> Result<(), NeighborError> {
+        if !protocol_addr.is_unicast() || !hardware_addr.is_unicast() {
+            return Err(NeighborError::Unaddressable);
+        }
+        self.inner
+            .neighbor_cache
+            .fill_static(protocol_addr, hardware_addr, permanent)
+    }
+
+    /// Remove a neighbor from the neighbor cache.
+    ///
+    /// Returns `true` if the neighbor was in the cache.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub fn remove_neighbor(&mut self, protocol_addr: &IpAddress) -> bool {
+        self.inner.neighbor_cache.remove(protocol_addr)
+    }
+
     /// Enable or disable the AnyIP capability.
     ///
     /// AnyIP allowins packets to be received
@@ -410,36 +713,20 @@ impl Interface {
         #[cfg(feature = "_proto-fragmentation")]
         self.fragments.assembler.remove_expired(timestamp);
 
-        match self.inner.caps.medium {
-            #[cfg(feature = "medium-ieee802154")]
-            Medium::Ieee802154 =>
-            {
-                #[cfg(feature = "proto-sixlowpan-fragmentation")]
-                if self.sixlowpan_egress(device) {
-                    return true;
-                }
-            }
-            #[cfg(any(feature = "medium-ethernet", feature = "medium-ip"))]
-            _ =>
-            {
-                #[cfg(feature = "proto-ipv4-fragmentation")]
-                if self.ipv4_egress(device) {
-                    return true;
-                }
-            }
+        if self.fragments_egress(device) {
+            return true;
         }
 
+        #[cfg(feature = "socket-mdns")]
+        self.socket_multicast_groups(device, sockets);
+
         let mut readiness_may_have_changed = false;
 
         loop {
             let mut did_something = false;
-            did_something |= self.socket_ingress(device, sockets);
-            did_something |= self.socket_egress(device, sockets);
-
-            #[cfg(feature = "proto-igmp")]
-            {
-                did_something |= self.igmp_egress(device);
-            }
+            did_something |= self.socket_ingress(device, sockets, |_, packet| Some(packet));
+            did_something |= self.socket_egress(device, sockets, |_, _| true);
+            did_something |= self.protocols_egress(device);
 
             if did_something {
                 readiness_may_have_changed = true;
@@ -451,6 +738,47 @@ impl Interface {
         readiness_may_have_changed
     }
 
+    /// Listen to the multicast groups of the mDNS responder sockets while there are any.
+    ///
+    /// With `proto-igmp`, the groups are also joined, so that multicast routers learn of the
+    /// membership, and left once the last responder is removed.
+    #[cfg(feature = "socket-mdns")]
+    #[cfg_attr(not(feature = "proto-igmp"), allow(unused_variables))]
+    fn socket_multicast_groups<D>(&mut self, device: &mut D, sockets: &SocketSet<'_>)
+    where
+        D: Device + ?Sized,
+    {
+        self.inner.mdns_responder = sockets
+            .items()
+            .any(|i| mdns::Socket::downcast(&i.socket).is_some());
+
+        #[cfg(feature = "proto-igmp")]
+        for group in mdns::GROUPS {
+            let joined = self.inner.mdns_joined_groups.contains(group);
+            if self.inner.mdns_responder && !joined {
+                // A group the application joined by itself is left alone.
+                let member = match group {
+                    IpAddress::Ipv4(addr) => self.inner.ipv4_multicast_groups.contains_key(addr),
+                    #[cfg(feature = "proto-ipv6")]
+                    IpAddress::Ipv6(addr) => self.inner.ipv6_multicast_groups.contains_key(addr),
+                };
+                if member {
+                    continue;
+                }
+                match self.join_multicast_group(device, *group, self.inner.now) {
+                    // NOTE(unwrap): there is room for all the groups.
+                    Ok(_) => self.inner.mdns_joined_groups.push(*group).unwrap(),
+                    Err(e) => net_debug!("failed to join the mDNS group {}: {}", group, e),
+                }
+            } else if !self.inner.mdns_responder && joined {
+                if let Err(e) = self.leave_multicast_group(device, *group, self.inner.now) {
+                    net_debug!("failed to leave the mDNS group {}: {}", group, e);
+                }
+                self.inner.mdns_joined_groups.retain(|g| g != group);
+            }
+        }
+    }
+
     /// Return a _soft deadline_ for calling [poll] the next time.
     /// The [Instant] returned is the time at which you should call [poll] next.
     /// It is harmless (but wastes energy) to call it before the [Instant], and
@@ -467,6 +795,55 @@ impl Interface {
             return Some(Instant::from_millis(0));
         }
 
+        #[cfg(feature = "proto-rpl")]
+        let rpl_poll_at = self.inner.rpl.as_ref().map(|rpl| rpl.poll_at());
+        #[cfg(not(feature = "proto-rpl"))]
+        let rpl_poll_at = None;
+
+        #[cfg(all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        ))]
+        let ipv6_addr_poll_at = self
+            .inner
+            .slaac
+            .as_ref()
+            .and_then(|slaac| slaac.poll_at())
+            .into_iter()
+            .chain(self.inner.dad.poll_at())
+            .min();
+        #[cfg(not(all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        )))]
+        let ipv6_addr_poll_at = None;
+
+        #[cfg(feature = "proto-igmp")]
+        let igmpv3_poll_at = self.inner.igmpv3.poll_at();
+        #[cfg(not(feature = "proto-igmp"))]
+        let igmpv3_poll_at = None;
+
+        #[cfg(feature = "proto-ipv6")]
+        let mld_poll_at = self.inner.mld.poll_at();
+        #[cfg(not(feature = "proto-ipv6"))]
+        let mld_poll_at = None;
+
+        #[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+        let acd_poll_at = self.inner.acd.poll_at(&self.inner.ip_addrs);
+        #[cfg(not(all(feature = "proto-ipv4", feature = "medium-ethernet")))]
+        let acd_poll_at = None;
+
+        #[cfg(feature = "medium-ethernet")]
+        let pending_poll_at = self
+            .inner
+            .pending_poll_at()
+            .into_iter()
+            .chain(self.inner.neighbor_cache.poll_at(timestamp))
+            .chain(self.inner.announcer.poll_at())
+            .min();
+        #[cfg(not(feature = "medium-ethernet"))]
+        let pending_poll_at = None;
+
         let inner = &mut self.inner;
 
         sockets
@@ -482,6 +859,12 @@ impl Interface {
                     PollAt::Now => Some(Instant::from_millis(0)),
                 }
             })
+            .chain(rpl_poll_at)
+            .chain(ipv6_addr_poll_at)
+            .chain(igmpv3_poll_at)
+            .chain(mld_poll_at)
+            .chain(pending_poll_at)
+            .chain(acd_poll_at)
             .min()
     }
 
@@ -501,9 +884,95 @@ impl Interface {
         }
     }
 
-    fn socket_ingress<D>(&mut self, device: &mut D, sockets: &mut SocketSet<'_>) -> bool
+    /// Transmit the next fragment of the packet that is being fragmented, if any.
+    #[allow(unused_variables)]
+    fn fragments_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        match self.inner.caps.medium {
+            #[cfg(feature = "medium-ieee802154")]
+            Medium::Ieee802154 =>
+            {
+                #[cfg(feature = "proto-sixlowpan-fragmentation")]
+                if self.sixlowpan_egress(device) {
+                    return true;
+                }
+            }
+            #[cfg(any(feature = "medium-ethernet", feature = "medium-ip"))]
+            _ => {
+                #[cfg(feature = "proto-ipv4-fragmentation")]
+                if self.ipv4_egress(device) {
+                    return true;
+                }
+                #[cfg(feature = "proto-ipv6-fragmentation")]
+                if self.ipv6_egress(device) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Transmit the packets of the protocols run by the interface itself.
+    #[allow(unused_variables)]
+    fn protocols_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        #[allow(unused_mut)]
+        let mut did_something = false;
+
+        #[cfg(feature = "proto-igmp")]
+        {
+            did_something |= self.igmp_egress(device);
+            did_something |= self.igmpv3_egress(device);
+        }
+
+        #[cfg(feature = "proto-ipv6")]
+        {
+            did_something |= self.mld_egress(device);
+        }
+
+        #[cfg(feature = "proto-rpl")]
+        {
+            did_something |= self.rpl_egress(device);
+        }
+
+        #[cfg(all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        ))]
+        {
+            did_something |= self.slaac_egress(device);
+            did_something |= self.dad_egress(device);
+        }
+
+        #[cfg(feature = "medium-ethernet")]
+        {
+            #[cfg(feature = "proto-ipv4")]
+            {
+                did_something |= self.acd_egress(device);
+            }
+            did_something |= self.announce_egress(device);
+            did_something |= self.neighbor_egress(device);
+            did_something |= self.pending_egress(device);
+        }
+
+        did_something
+    }
+
+    /// Receive the packets queued in the device. Forwarded packets are handed over to
+    /// `forward`, which returns them when they are transmitted through this interface.
+    fn socket_ingress<D, F>(
+        &mut self,
+        device: &mut D,
+        sockets: &mut SocketSet<'_>,
+        mut forward: F,
+    ) -> bool
     where
         D: Device + ?Sized,
+        F: for<'p> FnMut(&InterfaceInner, Packet<'p>) -> Option<Packet<'p>>,
     {
         let mut processed_any = false;
 
@@ -517,12 +986,18 @@ impl Interface {
                 match self.inner.caps.medium {
                     #[cfg(feature = "medium-ethernet")]
                     Medium::Ethernet => {
-                        if let Some(packet) = self.inner.process_ethernet(
-                            sockets,
-                            rx_meta,
-                            frame,
-                            &mut self.fragments,
-                        ) {
+                        if let Some(packet) = self
+                            .inner
+                            .process_ethernet(sockets, rx_meta, frame, &mut self.fragments)
+                            .and_then(|packet| match packet {
+                                EthernetPacket::Ip(packet) => {
+                                    forward::forward_packet(&self.inner, packet, &mut forward)
+                                        .map(EthernetPacket::Ip)
+                                }
+                                #[allow(unreachable_patterns)]
+                                packet => Some(packet),
+                            })
+                        {
                             if let Err(err) =
                                 self.inner.dispatch(tx_token, packet, &mut self.fragmenter)
                             {
@@ -532,9 +1007,12 @@ impl Interface {
                     }
                     #[cfg(feature = "medium-ip")]
                     Medium::Ip => {
-                        if let Some(packet) =
-                            self.inner
-                                .process_ip(sockets, rx_meta, frame, &mut self.fragments)
+                        if let Some(packet) = self
+                            .inner
+                            .process_ip(sockets, rx_meta, frame, &mut self.fragments)
+                            .and_then(|packet| {
+                                forward::forward_packet(&self.inner, packet, &mut forward)
+                            })
                         {
                             if let Err(err) = self.inner.dispatch_ip(
                                 tx_token,
@@ -548,12 +1026,13 @@ impl Interface {
                     }
                     #[cfg(feature = "medium-ieee802154")]
                     Medium::Ieee802154 => {
-                        if let Some(packet) = self.inner.process_ieee802154(
-                            sockets,
-                            rx_meta,
-                            frame,
-                            &mut self.fragments,
-                        ) {
+                        if let Some(packet) = self
+                            .inner
+                            .process_ieee802154(sockets, rx_meta, frame, &mut self.fragments)
+                            .and_then(|packet| {
+                                forward::forward_packet(&self.inner, packet, &mut forward)
+                            })
+                        {
                             if let Err(err) = self.inner.dispatch_ip(
                                 tx_token,
                                 PacketMeta::default(),
@@ -572,15 +1051,24 @@ impl Interface {
         processed_any
     }
 
-    fn socket_egress<D>(&mut self, device: &mut D, sockets: &mut SocketSet<'_>) -> bool
+    /// Transmit the packets queued in the sockets, whose destination is `routed` through this
+    /// interface.
+    fn socket_egress<D, R>(
+        &mut self,
+        device: &mut D,
+        sockets: &mut SocketSet<'_>,
+        routed: R,
+    ) -> bool
     where
         D: Device + ?Sized,
+        R: Fn(&InterfaceInner, &IpAddress) -> bool,
     {
         let _caps = device.capabilities();
 
         enum EgressError {
             Exhausted,
             Dispatch,
+            Elsewhere,
         }
 
         let mut emitted_any = false;
@@ -595,6 +1083,10 @@ impl Interface {
             let mut neighbor_addr = None;
             let mut respond = |inner: &mut InterfaceInner, meta: PacketMeta, response: Packet| {
                 neighbor_addr = Some(response.ip_repr().dst_addr());
+                if !routed(inner, &response.ip_repr().dst_addr()) {
+                    return Err(EgressError::Elsewhere);
+                }
+
                 let t = device.transmit(inner.now).ok_or_else(|| {
                     net_debug!("failed to transmit IP: device exhausted");
                     EgressError::Exhausted
@@ -661,6 +1153,26 @@ impl Interface {
                         )
                     })
                 }
+                #[cfg(feature = "socket-dhcpv4-server")]
+                Socket::Dhcpv4Server(socket) => {
+                    socket.dispatch(&mut self.inner, |inner, (ip, udp, dhcp)| {
+                        respond(
+                            inner,
+                            PacketMeta::default(),
+                            Packet::new_ipv4(ip, IpPayload::Dhcpv4(udp, dhcp)),
+                        )
+                    })
+                }
+                #[cfg(feature = "socket-dhcpv6")]
+                Socket::Dhcpv6(socket) => {
+                    socket.dispatch(&mut self.inner, |inner, (ip, udp, dhcp)| {
+                        respond(
+                            inner,
+                            PacketMeta::default(),
+                            Packet::new_ipv6(ip, IpPayload::Dhcpv6(udp, dhcp)),
+                        )
+                    })
+                }
                 #[cfg(feature = "socket-dns")]
                 Socket::Dns(socket) => socket.dispatch(&mut self.inner, |inner, (ip, udp, dns)| {
                     respond(
@@ -669,10 +1181,21 @@ impl Interface {
                         Packet::new(ip, IpPayload::Udp(udp, dns)),
                     )
                 }),
+                #[cfg(feature = "socket-mdns")]
+                Socket::Mdns(socket) => {
+                    socket.dispatch(&mut self.inner, |inner, (ip, udp, mdns)| {
+                        respond(
+                            inner,
+                            PacketMeta::default(),
+                            Packet::new(ip, IpPayload::Udp(udp, mdns)),
+                        )
+                    })
+                }
             };
 
             match result {
                 Err(EgressError::Exhausted) => break, // Device buffer full.
+                Err(EgressError::Elsewhere) => {}     // Transmitted through another interface.
                 Err(EgressError::Dispatch) => {
                     // `NeighborCache` already takes care of rate limiting the neighbor discovery
                     // requests from the socket. However, without an additional rate limiting
@@ -712,11 +1235,38 @@ impl InterfaceInner {
         self.caps.ip_mtu()
     }
 
+    /// Return the path MTU estimate to a destination, which is never larger than the MTU of the
+    /// interface.
+    #[allow(unused)] // unused depending on which sockets are enabled
+    pub(crate) fn path_mtu(&self, dst_addr: &IpAddress) -> usize {
+        // A route MTU below the minimum of the IP version is raised to it, as the path MTU
+        // estimates are.
+        let min_mtu = match dst_addr {
+            #[cfg(feature = "proto-ipv4")]
+            IpAddress::Ipv4(_) => PmtuCache::IPV4_MIN_MTU,
+            #[cfg(feature = "proto-ipv6")]
+            IpAddress::Ipv6(_) => IPV6_MIN_MTU,
+        };
+        let ip_mtu = self.caps.ip_mtu();
+        let ip_mtu = self
+            .route_to(dst_addr)
+            .and_then(|route| route.mtu)
+            .map_or(ip_mtu, |mtu| mtu.max(min_mtu).min(ip_mtu));
+        self.pmtu_cache
+            .lookup(dst_addr, self.now)
+            .map_or(ip_mtu, |mtu| mtu.min(ip_mtu))
+    }
+
     #[allow(unused)] // unused depending on which sockets are enabled, and in tests
     pub(crate) fn rand(&mut self) -> &mut Rand {
         &mut self.rand
     }
 
+    #[allow(unused)] // unused depending on which sockets are enabled
+    pub(crate) fn routes_mut(&mut self) -> &mut Routes {
+        &mut self.routes
+    }
+
     #[allow(unused)] // unused depending on which sockets are enabled
     pub(crate) fn get_source_address(&self, dst_addr: &IpAddress) -> Option<IpAddress> {
         match dst_addr {
@@ -727,6 +1277,21 @@ impl InterfaceInner {
         }
     }
 
+    /// Return the addresses assigned to the interface, leaving out the tentative ones.
+    #[allow(unused)] // unused depending on which sockets are enabled
+    pub(crate) fn ip_addrs(&self) -> impl Iterator<Item = IpAddress> + '_ {
+        self.ip_addrs
+            .iter()
+            .map(|cidr| cidr.address())
+            .filter(|addr| !self.is_tentative(addr))
+    }
+
+    #[cfg(test)]
+    #[allow(unused)] // unused depending on which sockets are enabled
+    pub(crate) fn set_path_mtu(&mut self, dst_addr: IpAddress, mtu: usize) {
+        self.pmtu_cache.fill(dst_addr, mtu, self.now)
+    }
+
     #[cfg(test)]
     #[allow(unused)] // unused depending on which sockets are enabled
     pub(crate) fn set_now(&mut self, now: Instant) {
@@ -751,33 +1316,76 @@ impl InterfaceInner {
     /// Check whether the interface has the given IP address assigned.
     fn has_ip_addr<T: Into<IpAddress>>(&self, addr: T) -> bool {
         let addr = addr.into();
-        self.ip_addrs.iter().any(|probe| probe.address() == addr)
+        self.ip_addrs.iter().any(|probe| probe.address() == addr) && !self.is_tentative(&addr)
+    }
+
+    /// Check whether Duplicate Address Detection, or Address Conflict Detection, is in progress
+    /// for the given IP address.
+    fn is_tentative(&self, addr: &IpAddress) -> bool {
+        match addr {
+            #[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+            IpAddress::Ipv4(addr) => self.acd.is_tentative(addr),
+            #[cfg(all(
+                feature = "proto-ipv6",
+                any(feature = "medium-ethernet", feature = "medium-ieee802154")
+            ))]
+            IpAddress::Ipv6(addr) => self.dad.is_tentative(addr),
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
     }
 
     /// Check whether the interface listens to given destination multicast IP address.
     ///
     /// If built without feature `proto-igmp` this function will
-    /// always return `false` when using IPv4.
+    /// always return `false` when using IPv4, except for the mDNS group while an mDNS
+    /// responder socket exists.
     fn has_multicast_group<T: Into<IpAddress>>(&self, addr: T) -> bool {
-        match addr.into() {
+        let addr = addr.into();
+        #[cfg(feature = "socket-mdns")]
+        if self.mdns_responder && mdns::GROUPS.contains(&addr) {
+            return true;
+        }
+
+        match addr {
             #[cfg(feature = "proto-igmp")]
             IpAddress::Ipv4(key) => {
                 key == Ipv4Address::MULTICAST_ALL_SYSTEMS
                     || self.ipv4_multicast_groups.get(&key).is_some()
             }
+            #[cfg(feature = "proto-rpl")]
+            IpAddress::Ipv6(Ipv6Address::LINK_LOCAL_ALL_RPL_NODES) if self.rpl.is_some() => true,
             #[cfg(feature = "proto-ipv6")]
             IpAddress::Ipv6(key) => {
                 key == Ipv6Address::LINK_LOCAL_ALL_NODES
                     || self.has_solicited_node(key)
                     || self.ipv6_multicast_groups.get(&key).is_some()
             }
-            #[cfg(feature = "proto-rpl")]
-            IpAddress::Ipv6(Ipv6Address::LINK_LOCAL_ALL_RPL_NODES) => true,
             #[allow(unreachable_patterns)]
             _ => false,
         }
     }
 
+    /// Check whether the source filter of a joined multicast group accepts the traffic
+    /// from `src_addr`. The traffic to other destinations is always accepted.
+    #[cfg(any(feature = "socket-udp", feature = "socket-dns"))]
+    fn accepts_multicast_source(&self, dst_addr: IpAddress, src_addr: IpAddress) -> bool {
+        match (dst_addr, src_addr) {
+            #[cfg(feature = "proto-igmp")]
+            (IpAddress::Ipv4(dst_addr), IpAddress::Ipv4(src_addr)) => self
+                .ipv4_multicast_groups
+                .get(&dst_addr)
+                .map_or(true, |filter| filter.accepts(&src_addr)),
+            #[cfg(feature = "proto-ipv6")]
+            (IpAddress::Ipv6(dst_addr), IpAddress::Ipv6(src_addr)) => self
+                .ipv6_multicast_groups
+                .get(&dst_addr)
+                .map_or(true, |filter| filter.accepts(&src_addr)),
+            #[allow(unreachable_patterns)]
+            _ => true,
+        }
+    }
+
     #[cfg(feature = "medium-ip")]
     fn process_ip<'frame>(
         &mut self,
@@ -796,6 +1404,8 @@ impl InterfaceInner {
             #[cfg(feature = "proto-ipv6")]
             Ok(IpVersion::Ipv6) => {
                 let ipv6_packet = check!(Ipv6Packet::new_checked(ip_payload));
+                #[cfg(feature = "proto-ipv6-fragmentation")]
+                let ipv6_packet = self.process_ipv6_fragment(ipv6_packet, frag)?;
                 self.process_ipv6(sockets, meta, &ipv6_packet)
             }
             // Drop all other traffic.
@@ -825,6 +1435,29 @@ impl InterfaceInner {
         handled_by_raw_socket
     }
 
+    /// Deliver an ICMP error to the TCP or UDP socket that sent the packet in error, whose IP
+    /// header and first octets of payload are contained in the error message.
+    #[cfg(any(feature = "socket-tcp", feature = "socket-udp"))]
+    fn process_icmp_error(
+        &mut self,
+        sockets: &mut SocketSet,
+        header: IpRepr,
+        data: &[u8],
+        hard: bool,
+    ) {
+        if !self.has_ip_addr(header.src_addr()) {
+            return;
+        }
+
+        match header.next_header() {
+            #[cfg(feature = "socket-tcp")]
+            IpProtocol::Tcp => self.process_tcp_icmp_error(sockets, &header, data, hard),
+            #[cfg(feature = "socket-udp")]
+            IpProtocol::Udp => self.process_udp_icmp_error(sockets, &header, data, hard),
+            _ => {}
+        }
+    }
+
     /// Checks if an address is broadcast, taking into account ipv4 subnet-local
     /// broadcast addresses.
     pub(crate) fn is_broadcast(&self, address: &IpAddress) -> bool {
@@ -886,6 +1519,36 @@ impl InterfaceInner {
         self.routes.lookup(addr, timestamp)
     }
 
+    /// Return the route to `addr`. Destinations in the same network as the interface are only
+    /// routed by the routes that reject them, of kind [`RouteKind::Unreachable`] or
+    /// [`RouteKind::Blackhole`].
+    fn route_to(&self, addr: &IpAddress) -> Option<&Route> {
+        if !addr.is_unicast() {
+            return None;
+        }
+
+        let route = self.routes.lookup_route(addr, self.now)?;
+        if self.in_same_network(addr)
+            && !matches!(route.kind, RouteKind::Unreachable | RouteKind::Blackhole)
+        {
+            return None;
+        }
+        Some(route)
+    }
+
+    /// Confirm the reachability of the neighbor through which `addr` is reached, because an
+    /// upper layer protocol made forward progress.
+    #[cfg(feature = "socket-tcp")]
+    #[allow(unused_variables)]
+    pub(crate) fn confirm_reachable(&mut self, addr: &IpAddress) {
+        #[cfg(feature = "medium-ethernet")]
+        if self.caps.medium == Medium::Ethernet {
+            if let Some(neighbor) = self.route(addr, self.now) {
+                self.neighbor_cache.confirm(&neighbor, self.now);
+            }
+        }
+    }
+
     fn has_neighbor(&self, addr: &IpAddress) -> bool {
         match self.route(addr, self.now) {
             Some(_routed_addr) => match self.caps.medium {
@@ -967,7 +1630,14 @@ impl InterfaceInner {
             .ok_or(DispatchError::NoRoute)?;
 
         match self.neighbor_cache.lookup(&dst_addr, self.now) {
-            NeighborAnswer::Found(hardware_addr) => return Ok((hardware_addr, tx_token)),
+            NeighborAnswer::Found(hardware_addr) => {
+                // Only the reachability of Ethernet neighbors is verified.
+                #[cfg(feature = "medium-ethernet")]
+                if self.caps.medium == Medium::Ethernet {
+                    self.neighbor_cache.used(&dst_addr, self.now);
+                }
+                return Ok((hardware_addr, tx_token));
+            }
             NeighborAnswer::RateLimited => return Err(DispatchError::NeighborPending),
             _ => (), // XXX
         }
@@ -1061,16 +1731,30 @@ impl InterfaceInner {
         let mut ip_repr = packet.ip_repr();
         assert!(!ip_repr.dst_addr().is_unspecified());
 
+        if let Some(route) = self.route_to(&ip_repr.dst_addr()) {
+            if matches!(route.kind, RouteKind::Blackhole | RouteKind::Unreachable) {
+                net_debug!("no route to {}: rejected by a route", ip_repr.dst_addr());
+                return Err(DispatchError::NoRoute);
+            }
+        }
+
         // Dispatch IEEE802.15.4:
 
         #[cfg(feature = "medium-ieee802154")]
         if matches!(self.caps.medium, Medium::Ieee802154) {
-            let (addr, tx_token) = self.lookup_hardware_addr(
-                tx_token,
-                &ip_repr.src_addr(),
-                &ip_repr.dst_addr(),
-                frag,
-            )?;
+            #[cfg(feature = "proto-rpl")]
+            let mut source_route = [0u8; rpl::SOURCE_ROUTE_BUFFER_LEN];
+            #[cfg(feature = "proto-rpl")]
+            let (packet, neighbor) = self.rpl_route_downwards(packet, &mut source_route);
+
+            let ip_repr = packet.ip_repr();
+            #[cfg(feature = "proto-rpl")]
+            let dst_addr = neighbor.unwrap_or(ip_repr.dst_addr());
+            #[cfg(not(feature = "proto-rpl"))]
+            let dst_addr = ip_repr.dst_addr();
+
+            let (addr, tx_token) =
+                self.lookup_hardware_addr(tx_token, &ip_repr.src_addr(), &dst_addr, frag)?;
             let addr = addr.ieee802154_or_panic();
 
             self.dispatch_ieee802154(addr, tx_token, meta, packet, frag);
@@ -1102,9 +1786,13 @@ impl InterfaceInner {
                     &ip_repr.src_addr(),
                     &ip_repr.dst_addr(),
                     frag,
-                )? {
-                    (HardwareAddress::Ethernet(addr), tx_token) => (addr, tx_token),
-                    (_, _) => unreachable!(),
+                ) {
+                    Ok((HardwareAddress::Ethernet(addr), tx_token)) => (addr, tx_token),
+                    Ok((_, _)) => unreachable!(),
+                    Err(DispatchError::NeighborPending) if self.pending_queue.is_some() => {
+                        return self.queue_pending(&ip_repr, &packet);
+                    }
+                    Err(err) => return Err(err),
                 }
             }
             _ => (EthernetAddress([0; 6]), tx_token),
@@ -1133,26 +1821,43 @@ impl InterfaceInner {
         let emit_ip = |repr: &IpRepr, mut tx_buffer: &mut [u8]| {
             repr.emit(&mut tx_buffer, &self.caps.checksum);
 
+            #[cfg(feature = "proto-ipv4")]
+            if !packet.dont_frag() {
+                let mut ipv4_packet = Ipv4Packet::new_unchecked(&mut *tx_buffer);
+                ipv4_packet.set_dont_frag(false);
+                if self.caps.checksum.ipv4.tx() {
+                    ipv4_packet.fill_checksum();
+                }
+            }
+
             let payload = &mut tx_buffer[repr.header_len()..];
             packet.emit_payload(repr, payload, &caps)
         };
 
         let total_ip_len = ip_repr.buffer_len();
+        let path_mtu = self.path_mtu(&ip_repr.dst_addr());
 
         match &mut ip_repr {
             #[cfg(feature = "proto-ipv4")]
             IpRepr::Ipv4(repr) => {
                 // If we have an IPv4 packet, then we need to check if we need to fragment it.
-                if total_ip_len > self.caps.max_transmission_unit {
+                if total_ip_len > path_mtu {
                     #[cfg(feature = "proto-ipv4-fragmentation")]
                     {
                         net_debug!("start fragmentation");
 
-                        // Calculate how much we will send now (including the Ethernet header).
-                        let tx_len = self.caps.max_transmission_unit;
-
+                        // Every fragment, except the last one, carries a multiple of 8 octets.
                         let ip_header_len = repr.buffer_len();
-                        let first_frag_ip_len = self.caps.ip_mtu();
+                        let first_frag_ip_len =
+                            ip_header_len + ((path_mtu - ip_header_len) & !0b111);
+
+                        // Calculate how much we will send now (including the Ethernet header).
+                        #[allow(unused_mut)]
+                        let mut tx_len = first_frag_ip_len;
+                        #[cfg(feature = "medium-ethernet")]
+                        if matches!(self.caps.medium, Medium::Ethernet) {
+                            tx_len += EthernetFrame::<&[u8]>::header_len();
+                        }
 
                         if frag.buffer.len() < total_ip_len {
                             net_debug!(
@@ -1234,18 +1939,65 @@ impl InterfaceInner {
                     })
                 }
             }
-            // We don't support IPv6 fragmentation yet.
             #[cfg(feature = "proto-ipv6")]
-            IpRepr::Ipv6(_) => tx_token.consume(total_len, |mut tx_buffer| {
-                #[cfg(feature = "medium-ethernet")]
-                if matches!(self.caps.medium, Medium::Ethernet) {
-                    emit_ethernet(&ip_repr, tx_buffer)?;
-                    tx_buffer = &mut tx_buffer[EthernetFrame::<&[u8]>::header_len()..];
-                }
+            IpRepr::Ipv6(_repr) => {
+                // If we have an IPv6 packet, then we need to check if we need to fragment it.
+                if total_ip_len > path_mtu {
+                    #[cfg(feature = "proto-ipv6-fragmentation")]
+                    {
+                        net_debug!("start fragmentation");
 
-                emit_ip(&ip_repr, tx_buffer);
-                Ok(())
-            }),
+                        if frag.buffer.len() < total_ip_len {
+                            net_debug!(
+                                "Fragmentation buffer is too small, at least {} needed. Dropping",
+                                total_ip_len
+                            );
+                            return Ok(());
+                        }
+
+                        #[cfg(feature = "medium-ethernet")]
+                        {
+                            frag.ipv6.dst_hardware_addr = dst_hardware_addr;
+                        }
+
+                        // Save the IP header for the fragments, and emit the whole packet to the
+                        // buffer. The unfragmentable part is copied into every fragment.
+                        frag.ipv6.repr = *_repr;
+                        emit_ip(&ip_repr, &mut frag.buffer[..total_ip_len]);
+
+                        let (unfragmentable_len, last_ext_header) =
+                            ipv6::unfragmentable_part(&frag.buffer[..total_ip_len]);
+                        frag.ipv6.unfragmentable_len = unfragmentable_len;
+                        frag.ipv6.last_ext_header = last_ext_header;
+
+                        frag.packet_len = total_ip_len;
+                        frag.sent_bytes = unfragmentable_len;
+                        frag.ipv6.ident = self.next_ipv6_frag_ident();
+
+                        // Transmit the first fragment.
+                        self.dispatch_ipv6_frag(tx_token, frag);
+                        Ok(())
+                    }
+
+                    #[cfg(not(feature = "proto-ipv6-fragmentation"))]
+                    {
+                        net_debug!("Enable the `proto-ipv6-fragmentation` feature for fragmentation support.");
+                        Ok(())
+                    }
+                } else {
+                    // No fragmentation is required.
+                    tx_token.consume(total_len, |mut tx_buffer| {
+                        #[cfg(feature = "medium-ethernet")]
+                        if matches!(self.caps.medium, Medium::Ethernet) {
+                            emit_ethernet(&ip_repr, tx_buffer)?;
+                            tx_buffer = &mut tx_buffer[EthernetFrame::<&[u8]>::header_len()..];
+                        }
+
+                        emit_ip(&ip_repr, tx_buffer);
+                        Ok(())
+                    })
+                }
+            }
         }
     }
 }
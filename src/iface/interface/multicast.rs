@@ -0,0 +1,483 @@
+// Source filters of the joined multicast groups, the group records of the IGMPv3 and
+// MLDv2 reports that announce them, and the scheduling of these reports, see RFC 3376 and
+// RFC 3810.
+
+// Groups are only joined with source filters through the API of `proto-igmp`.
+#![cfg_attr(not(feature = "proto-igmp"), allow(dead_code))]
+
+use core::fmt;
+
+use super::*;
+use crate::config::IFACE_MAX_MULTICAST_SOURCE_COUNT;
+
+/// Default number of times a State Change Report is sent.
+const DEFAULT_ROBUSTNESS: u8 = 2;
+/// Maximum time between the retransmissions of a State Change Report.
+const UNSOLICITED_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Size of the source addresses of a group record.
+const MAX_SOURCES_LEN: usize = IFACE_MAX_MULTICAST_SOURCE_COUNT * 16;
+
+/// Maximum number of records in a report: one for each group, or two for a change of the
+/// sources of a group.
+pub(crate) const MAX_RECORD_COUNT: usize = if IFACE_MAX_MULTICAST_GROUP_COUNT > 2 {
+    IFACE_MAX_MULTICAST_GROUP_COUNT
+} else {
+    2
+};
+
+/// The filter mode of a multicast group membership, see RFC 3376 § 3.1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MulticastFilterMode {
+    /// Receive the traffic of the group from the listed sources only.
+    Include,
+    /// Receive the traffic of the group from all but the listed sources.
+    Exclude,
+}
+
+/// The sources the traffic of a joined multicast group is received from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MulticastFilter<A> {
+    pub(crate) mode: MulticastFilterMode,
+    pub(crate) sources: Vec<A, IFACE_MAX_MULTICAST_SOURCE_COUNT>,
+}
+
+impl<A: Copy + PartialEq> MulticastFilter<A> {
+    /// The state of a group that is not joined.
+    const fn none() -> Self {
+        Self {
+            mode: MulticastFilterMode::Include,
+            sources: Vec::new(),
+        }
+    }
+
+    /// Return whether the traffic of the group from `src_addr` is received.
+    pub(crate) fn accepts(&self, src_addr: &A) -> bool {
+        let listed = self.sources.contains(src_addr);
+        match self.mode {
+            MulticastFilterMode::Include => listed,
+            MulticastFilterMode::Exclude => !listed,
+        }
+    }
+}
+
+/// The type of a group record, common to IGMPv3 and MLDv2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RecordType {
+    ModeIsInclude,
+    ModeIsExclude,
+    ChangeToInclude,
+    ChangeToExclude,
+    AllowNewSources,
+    BlockOldSources,
+}
+
+#[cfg(feature = "proto-igmp")]
+impl From<RecordType> for IgmpRecordType {
+    fn from(record_type: RecordType) -> Self {
+        match record_type {
+            RecordType::ModeIsInclude => Self::ModeIsInclude,
+            RecordType::ModeIsExclude => Self::ModeIsExclude,
+            RecordType::ChangeToInclude => Self::ChangeToInclude,
+            RecordType::ChangeToExclude => Self::ChangeToExclude,
+            RecordType::AllowNewSources => Self::AllowNewSources,
+            RecordType::BlockOldSources => Self::BlockOldSources,
+        }
+    }
+}
+
+#[cfg(feature = "proto-ipv6")]
+impl From<RecordType> for MldRecordType {
+    fn from(record_type: RecordType) -> Self {
+        match record_type {
+            RecordType::ModeIsInclude => Self::ModeIsInclude,
+            RecordType::ModeIsExclude => Self::ModeIsExclude,
+            RecordType::ChangeToInclude => Self::ChangeToInclude,
+            RecordType::ChangeToExclude => Self::ChangeToExclude,
+            RecordType::AllowNewSources => Self::AllowNewSources,
+            RecordType::BlockOldSources => Self::BlockOldSources,
+        }
+    }
+}
+
+/// A group record of a report, common to IGMPv3 and MLDv2.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Record<A> {
+    pub(crate) record_type: RecordType,
+    pub(crate) group_addr: A,
+    pub(crate) num_srcs: u16,
+    /// The source addresses, in network byte order.
+    pub(crate) sources: Vec<u8, MAX_SOURCES_LEN>,
+}
+
+impl<A: Copy + PartialEq + fmt::Debug + Into<IpAddress>> Record<A> {
+    fn new<'s>(record_type: RecordType, group_addr: A, sources: impl Iterator<Item = &'s A>) -> Self
+    where
+        A: 's,
+    {
+        let mut record = Self {
+            record_type,
+            group_addr,
+            num_srcs: 0,
+            sources: Vec::new(),
+        };
+        for src_addr in sources {
+            // NOTE(unwrap): there are at most IFACE_MAX_MULTICAST_SOURCE_COUNT sources.
+            let src_addr: IpAddress = (*src_addr).into();
+            record
+                .sources
+                .extend_from_slice(src_addr.as_bytes())
+                .unwrap();
+            record.num_srcs += 1;
+        }
+        record
+    }
+
+    /// The record of the current state of a group, sent in response to queries.
+    pub(super) fn current_state(group_addr: A, filter: &MulticastFilter<A>) -> Self {
+        let record_type = match filter.mode {
+            MulticastFilterMode::Include => RecordType::ModeIsInclude,
+            MulticastFilterMode::Exclude => RecordType::ModeIsExclude,
+        };
+        Self::new(record_type, group_addr, filter.sources.iter())
+    }
+
+    /// The record of the state a group has changed to. It is used by retransmissions,
+    /// which must describe the state even if some of the previous reports were lost.
+    pub(super) fn state_change(group_addr: A, filter: Option<&MulticastFilter<A>>) -> Self {
+        let filter = filter.cloned().unwrap_or(MulticastFilter::none());
+        let record_type = match filter.mode {
+            MulticastFilterMode::Include => RecordType::ChangeToInclude,
+            MulticastFilterMode::Exclude => RecordType::ChangeToExclude,
+        };
+        Self::new(record_type, group_addr, filter.sources.iter())
+    }
+
+    /// Return the records that report a change of the filter of a group, where `None`
+    /// stands for a group that is not joined, see RFC 3376 § 5.1.
+    pub(crate) fn changes(
+        group_addr: A,
+        old: Option<&MulticastFilter<A>>,
+        new: Option<&MulticastFilter<A>>,
+    ) -> Vec<Self, 2> {
+        let none = MulticastFilter::none();
+        let old = old.unwrap_or(&none);
+        let new = new.unwrap_or(&none);
+
+        let mut records = Vec::new();
+        if old.mode != new.mode {
+            // NOTE(unwrap): there is room for two records.
+            records
+                .push(Self::state_change(group_addr, Some(new)))
+                .unwrap();
+            return records;
+        }
+
+        let added = new
+            .sources
+            .iter()
+            .filter(|addr| !old.sources.contains(addr));
+        let removed = old
+            .sources
+            .iter()
+            .filter(|addr| !new.sources.contains(addr));
+        let (allow, block) = match new.mode {
+            MulticastFilterMode::Include => (
+                Self::new(RecordType::AllowNewSources, group_addr, added),
+                Self::new(RecordType::BlockOldSources, group_addr, removed),
+            ),
+            MulticastFilterMode::Exclude => (
+                Self::new(RecordType::AllowNewSources, group_addr, removed),
+                Self::new(RecordType::BlockOldSources, group_addr, added),
+            ),
+        };
+        for record in [allow, block] {
+            if record.num_srcs > 0 {
+                // NOTE(unwrap): there is room for two records.
+                records.push(record).unwrap();
+            }
+        }
+        records
+    }
+}
+
+/// A kind of report that is sent after a delay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Report {
+    /// Response to a General Query.
+    General,
+    /// Response to Group-Specific Queries.
+    GroupSpecific,
+    /// Retransmission of State Change Reports.
+    StateChange,
+}
+
+/// The schedule of the delayed reports of IGMPv3 or MLDv2.
+#[derive(Debug)]
+pub(crate) struct Reporter<A: Eq> {
+    /// The number of times a State Change Report is sent, learned from queries.
+    robustness: u8,
+    /// When to report all groups, in response to a General Query.
+    general_response_at: Option<Instant>,
+    /// When to report a group, in response to a Group-Specific Query.
+    group_responses: LinearMap<A, Instant, IFACE_MAX_MULTICAST_GROUP_COUNT>,
+    /// The number of times the state of a group remains to be reported.
+    changes: LinearMap<A, u8, IFACE_MAX_MULTICAST_GROUP_COUNT>,
+    /// When to send the next State Change Report.
+    change_at: Option<Instant>,
+}
+
+impl<A: Copy + Eq + fmt::Debug + Into<IpAddress>> Reporter<A> {
+    pub(super) fn new() -> Self {
+        Self {
+            robustness: DEFAULT_ROBUSTNESS,
+            general_response_at: None,
+            group_responses: LinearMap::new(),
+            changes: LinearMap::new(),
+            change_at: None,
+        }
+    }
+
+    /// Return the time at which the next report must be sent.
+    pub(super) fn poll_at(&self) -> Option<Instant> {
+        self.general_response_at
+            .into_iter()
+            .chain(self.group_responses.values().copied())
+            .chain(self.change_at)
+            .min()
+    }
+
+    /// Cancel all the pending reports.
+    #[cfg(feature = "proto-igmp")]
+    pub(super) fn clear(&mut self) {
+        self.general_response_at = None;
+        self.group_responses.clear();
+        self.changes.clear();
+        self.change_at = None;
+    }
+
+    /// Return the next report that is due, and its records.
+    pub(super) fn due(
+        &self,
+        now: Instant,
+        groups: &LinearMap<A, MulticastFilter<A>, IFACE_MAX_MULTICAST_GROUP_COUNT>,
+    ) -> Option<(Report, Vec<Record<A>, IFACE_MAX_MULTICAST_GROUP_COUNT>)> {
+        let mut records = Vec::new();
+
+        // NOTE(unwrap): there are at most as many records as multicast groups.
+        let report = if self.general_response_at.is_some_and(|at| at <= now) {
+            for (addr, filter) in groups.iter() {
+                records.push(Record::current_state(*addr, filter)).unwrap();
+            }
+            Report::General
+        } else if self.group_responses.values().any(|at| *at <= now) {
+            for (addr, _) in self.group_responses.iter().filter(|(_, at)| **at <= now) {
+                if let Some(filter) = groups.get(addr) {
+                    records.push(Record::current_state(*addr, filter)).unwrap();
+                }
+            }
+            Report::GroupSpecific
+        } else if self.change_at.is_some_and(|at| at <= now) {
+            for (addr, _) in self.changes.iter() {
+                records
+                    .push(Record::state_change(*addr, groups.get(addr)))
+                    .unwrap();
+            }
+            Report::StateChange
+        } else {
+            return None;
+        };
+
+        Some((report, records))
+    }
+
+    /// Update the schedule after a report was sent.
+    pub(super) fn sent(&mut self, report: Report, now: Instant, rand: &mut Rand) {
+        match report {
+            Report::General => {
+                self.general_response_at = None;
+                self.group_responses.clear();
+            }
+            Report::GroupSpecific => {
+                while let Some(addr) = self
+                    .group_responses
+                    .iter()
+                    .find(|(_, at)| **at <= now)
+                    .map(|(addr, _)| *addr)
+                {
+                    self.group_responses.remove(&addr);
+                }
+            }
+            Report::StateChange => {
+                for remaining in self.changes.values_mut() {
+                    *remaining -= 1;
+                }
+                while let Some(addr) = self
+                    .changes
+                    .iter()
+                    .find(|(_, remaining)| **remaining == 0)
+                    .map(|(addr, _)| *addr)
+                {
+                    self.changes.remove(&addr);
+                }
+
+                self.change_at = None;
+                if !self.changes.is_empty() {
+                    self.change_at = Some(now + Self::retransmit_delay(rand));
+                }
+            }
+        }
+    }
+
+    /// Schedule the retransmissions of the State Change Report that was sent for a change
+    /// of the filter of a group, see RFC 3376 § 5.1.
+    #[cfg(feature = "proto-igmp")]
+    pub(super) fn report_change(&mut self, addr: A, now: Instant, rand: &mut Rand) {
+        self.changes.remove(&addr);
+        if self.robustness <= 1 {
+            return;
+        }
+
+        if self.changes.insert(addr, self.robustness - 1).is_err() {
+            net_debug!("unable to retransmit report for {}", addr.into());
+            return;
+        }
+
+        if self.change_at.is_none() {
+            self.change_at = Some(now + Self::retransmit_delay(rand));
+        }
+    }
+
+    /// Schedule the response to a query for `group_addr`, or for all groups if it is
+    /// `None`, see RFC 3376 § 5.2. The caller checks that the group is joined.
+    pub(super) fn schedule_response(
+        &mut self,
+        group_addr: Option<A>,
+        max_resp_time: Duration,
+        qrv: u8,
+        now: Instant,
+        rand: &mut Rand,
+    ) {
+        if qrv != 0 {
+            self.robustness = qrv;
+        }
+
+        let delay = rand.rand_u32() as u64 % (max_resp_time.total_millis() + 1);
+        let response_at = now + Duration::from_millis(delay);
+
+        // A response to a General Query that is sent earlier also answers this query.
+        if self.general_response_at.is_some_and(|at| at <= response_at) {
+            return;
+        }
+
+        match group_addr {
+            None => self.general_response_at = Some(response_at),
+            Some(addr) => {
+                let response_at = match self.group_responses.get(&addr) {
+                    Some(at) => response_at.min(*at),
+                    None => response_at,
+                };
+                if self.group_responses.insert(addr, response_at).is_err() {
+                    net_debug!("unable to respond to query for {}", addr.into());
+                }
+            }
+        }
+    }
+
+    fn retransmit_delay(rand: &mut Rand) -> Duration {
+        let delay = rand.rand_u32() as u64 % UNSOLICITED_REPORT_INTERVAL.total_millis();
+        Duration::from_millis(delay)
+    }
+}
+
+#[cfg(all(test, feature = "proto-ipv4"))]
+mod test {
+    use super::*;
+
+    fn filter(mode: MulticastFilterMode, sources: &[Ipv4Address]) -> MulticastFilter<Ipv4Address> {
+        MulticastFilter {
+            mode,
+            sources: Vec::from_slice(sources).unwrap(),
+        }
+    }
+
+    fn record(
+        record_type: RecordType,
+        group_addr: Ipv4Address,
+        sources: &[Ipv4Address],
+    ) -> Record<Ipv4Address> {
+        Record::new(record_type, group_addr, sources.iter())
+    }
+
+    const GROUP: Ipv4Address = Ipv4Address([232, 1, 1, 1]);
+    const SRC_A: Ipv4Address = Ipv4Address([192, 0, 2, 1]);
+    const SRC_B: Ipv4Address = Ipv4Address([192, 0, 2, 2]);
+    const SRC_C: Ipv4Address = Ipv4Address([192, 0, 2, 3]);
+
+    #[test]
+    fn test_filter_accepts() {
+        let include = filter(MulticastFilterMode::Include, &[SRC_A]);
+        assert!(include.accepts(&SRC_A));
+        assert!(!include.accepts(&SRC_B));
+
+        let exclude = filter(MulticastFilterMode::Exclude, &[SRC_A]);
+        assert!(!exclude.accepts(&SRC_A));
+        assert!(exclude.accepts(&SRC_B));
+
+        assert!(filter(MulticastFilterMode::Exclude, &[]).accepts(&SRC_A));
+        assert!(!MulticastFilter::none().accepts(&SRC_A));
+    }
+
+    #[test]
+    fn test_changes_join_leave() {
+        let include = filter(MulticastFilterMode::Include, &[SRC_A]);
+        let exclude = filter(MulticastFilterMode::Exclude, &[SRC_A]);
+
+        assert_eq!(
+            &Record::changes(GROUP, None, Some(&include))[..],
+            &[record(RecordType::AllowNewSources, GROUP, &[SRC_A])]
+        );
+        assert_eq!(
+            &Record::changes(GROUP, None, Some(&exclude))[..],
+            &[record(RecordType::ChangeToExclude, GROUP, &[SRC_A])]
+        );
+        assert_eq!(
+            &Record::changes(GROUP, Some(&include), None)[..],
+            &[record(RecordType::BlockOldSources, GROUP, &[SRC_A])]
+        );
+        assert_eq!(
+            &Record::changes(GROUP, Some(&exclude), None)[..],
+            &[record(RecordType::ChangeToInclude, GROUP, &[])]
+        );
+    }
+
+    #[test]
+    fn test_changes_sources() {
+        let old = filter(MulticastFilterMode::Include, &[SRC_A, SRC_B]);
+        let new = filter(MulticastFilterMode::Include, &[SRC_B, SRC_C]);
+        assert_eq!(
+            &Record::changes(GROUP, Some(&old), Some(&new))[..],
+            &[
+                record(RecordType::AllowNewSources, GROUP, &[SRC_C]),
+                record(RecordType::BlockOldSources, GROUP, &[SRC_A]),
+            ]
+        );
+
+        let old = filter(MulticastFilterMode::Exclude, &[SRC_A, SRC_B]);
+        let new = filter(MulticastFilterMode::Exclude, &[SRC_B, SRC_C]);
+        assert_eq!(
+            &Record::changes(GROUP, Some(&old), Some(&new))[..],
+            &[
+                record(RecordType::AllowNewSources, GROUP, &[SRC_A]),
+                record(RecordType::BlockOldSources, GROUP, &[SRC_C]),
+            ]
+        );
+
+        let new = filter(MulticastFilterMode::Include, &[SRC_C]);
+        assert_eq!(
+            &Record::changes(GROUP, Some(&old), Some(&new))[..],
+            &[record(RecordType::ChangeToInclude, GROUP, &[SRC_C])]
+        );
+    }
+}
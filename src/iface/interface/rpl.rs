@@ -0,0 +1,1105 @@
+use super::*;
+
+use core::ops::Range;
+
+use crate::config::RPL_RELATIONS_BUFFER_COUNT;
+use crate::iface::rpl::{
+    consts, Dodag, DodagConfiguration, Parent, Rank, Role as RplRole, SequenceCounter,
+};
+
+/// The size of the buffer for the addresses of an RPL Source Routing Header inserted by the root.
+pub(super) const SOURCE_ROUTE_BUFFER_LEN: usize = 128;
+
+/// Return the link-local address with the same interface identifier as `address`.
+///
+/// RPL nodes form their addresses using the interface identifier of their link-local address,
+/// which is used to reach them as a neighbor.
+pub(super) fn link_local_address(address: &Ipv6Address) -> Ipv6Address {
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 0).as_bytes()[..8]);
+    bytes[8..].copy_from_slice(&address.as_bytes()[8..]);
+    Ipv6Address::from_bytes(&bytes)
+}
+
+/// Return the final destination of a packet carrying an RPL source routing header, which is the
+/// last address of the route. Upper-layer checksums are computed using this address.
+pub(super) fn final_destination(
+    dst_addr: &Ipv6Address,
+    routing: &Ipv6RoutingRepr,
+) -> Option<Ipv6Address> {
+    match routing {
+        Ipv6RoutingRepr::Rpl { segments_left, .. } if *segments_left > 0 => {
+            let n = source_route_len(routing)?;
+            Some(source_route_address(dst_addr, routing, n, n))
+        }
+        _ => None,
+    }
+}
+
+/// Return the number of addresses in an RPL Source Routing Header, or `None` when the length of
+/// the header does not match the number of elided octets.
+fn source_route_len(routing: &Ipv6RoutingRepr) -> Option<usize> {
+    let Ipv6RoutingRepr::Rpl {
+        cmpr_i,
+        cmpr_e,
+        pad,
+        addresses,
+        ..
+    } = routing
+    else {
+        return None;
+    };
+
+    let len = addresses
+        .len()
+        .checked_sub(*pad as usize + 16 - *cmpr_e as usize)?;
+    if len % (16 - *cmpr_i as usize) != 0 {
+        return None;
+    }
+    Some(len / (16 - *cmpr_i as usize) + 1)
+}
+
+/// Return the position of the `i`th address of an RPL Source Routing Header with `n` addresses,
+/// counting from 1, and the number of prefix octets that are elided from it.
+fn source_route_range(routing: &Ipv6RoutingRepr, n: usize, i: usize) -> (Range<usize>, usize) {
+    let Ipv6RoutingRepr::Rpl { cmpr_i, cmpr_e, .. } = routing else {
+        unreachable!()
+    };
+    let (cmpr_i, cmpr_e) = (*cmpr_i as usize, *cmpr_e as usize);
+
+    let start = (i - 1) * (16 - cmpr_i);
+    let elided = if i < n { cmpr_i } else { cmpr_e };
+    (start..start + 16 - elided, elided)
+}
+
+/// Return the `i`th address of an RPL Source Routing Header with `n` addresses, counting from 1.
+/// The elided prefix of the address is the prefix of the destination address of the packet.
+fn source_route_address(
+    dst_addr: &Ipv6Address,
+    routing: &Ipv6RoutingRepr,
+    n: usize,
+    i: usize,
+) -> Ipv6Address {
+    let Ipv6RoutingRepr::Rpl { addresses, .. } = routing else {
+        unreachable!()
+    };
+    let (range, elided) = source_route_range(routing, n, i);
+
+    let mut addr = *dst_addr;
+    addr.0[elided..].copy_from_slice(&addresses[range]);
+    addr
+}
+
+/// Write the destination address that is swapped with the `i`th address of an RPL Source Routing
+/// Header into the `addresses` of the header, when the header is forwarded.
+pub(super) fn swap_source_route_address(
+    routing: &Ipv6RoutingRepr,
+    (i, dst_addr): (usize, Ipv6Address),
+    addresses: &mut [u8],
+) {
+    let Some(n) = source_route_len(routing) else {
+        return;
+    };
+    let (range, elided) = source_route_range(routing, n, i);
+    addresses[range].copy_from_slice(&dst_addr.as_bytes()[elided..]);
+}
+
+/// Returns `true` when a packet with `next_header` can be forwarded. Only upper-layer protocols
+/// are supported, since other headers would have to be compressed by 6LoWPAN.
+fn is_forwardable(next_header: IpProtocol) -> bool {
+    matches!(
+        next_header,
+        IpProtocol::Icmpv6 | IpProtocol::Udp | IpProtocol::Tcp
+    )
+}
+
+/// Return an iterator over the RPL Control Message Options in `options`.
+///
+/// Iteration stops at the first malformed option. Options that are not used by the RPL routing
+/// engine are skipped.
+fn rpl_options(mut options: &[u8]) -> impl Iterator<Item = RplOptionRepr<'_>> {
+    core::iter::from_fn(move || loop {
+        let option_len = match options {
+            [] => return None,
+            [0x00, ..] => 1,
+            [_, len, ..] => 2 + *len as usize,
+            [_] => return None,
+        };
+
+        if option_len > options.len() {
+            return None;
+        }
+
+        let (option, rest) = options.split_at(option_len);
+        options = rest;
+
+        let option = RplOptionPacket::new_unchecked(option);
+        match (option.option_type(), option_len) {
+            (RplOptionType::DodagConfiguration, 16)
+            | (RplOptionType::RplTarget, 20)
+            | (RplOptionType::TransitInformation, 6 | 22) => {
+                return RplOptionRepr::parse(&option).ok();
+            }
+            _ => continue,
+        }
+    })
+}
+
+impl Interface {
+    /// Increment the version number of the DODAG, triggering a global repair of the DODAG.
+    ///
+    /// Returns `false` when the interface is not the root of a DODAG.
+    pub fn rpl_global_repair(&mut self) -> bool {
+        let now = self.inner.now;
+
+        let Some(rpl) = &mut self.inner.rpl else {
+            return false;
+        };
+        if !rpl.is_root() {
+            return false;
+        }
+        let Some(dodag) = &mut rpl.dodag else {
+            return false;
+        };
+
+        dodag.version_number.increment();
+        dodag.dtsn.increment();
+        dodag
+            .dio_timer
+            .hear_inconsistency(now, &mut self.inner.rand);
+
+        net_debug!(
+            "rpl: global repair, new version {}",
+            dodag.version_number.value()
+        );
+
+        true
+    }
+
+    /// Transmit the RPL control messages that are due: DIS messages when the node is not part of
+    /// a DODAG, DIO messages when the Trickle timer expires or when a DIO was requested, and DAO
+    /// messages when the node needs to advertise its address to the root.
+    pub(crate) fn rpl_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        self.inner.rpl_maintain_dodag();
+
+        self.rpl_dio_egress(device) || self.rpl_dao_egress(device)
+    }
+
+    fn rpl_dio_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        let now = self.inner.now;
+
+        let Some(rpl) = &mut self.inner.rpl else {
+            return false;
+        };
+
+        let sends_dio = rpl.sends_dio();
+        let dst_addr = match &mut rpl.dodag {
+            None if now >= rpl.dis_expiration => {
+                rpl.dis_expiration = now + consts::DIS_INTERVAL;
+                None
+            }
+            None => return false,
+            Some(_) if !sends_dio => return false,
+            Some(dodag) => match rpl.dio_requested_by.take() {
+                Some(dst_addr) => Some(dst_addr),
+                None if dodag.dio_timer.poll(now, &mut self.inner.rand) => {
+                    Some(Ipv6Address::LINK_LOCAL_ALL_RPL_NODES)
+                }
+                None => return false,
+            },
+        };
+
+        let Some(src_addr) = self.inner.link_local_ipv6_address() else {
+            net_debug!("rpl: no link-local address, not sending control message");
+            return false;
+        };
+
+        let mut options = [0u8; 16];
+        let (dst_addr, rpl_repr) = match dst_addr {
+            None => (
+                Ipv6Address::LINK_LOCAL_ALL_RPL_NODES,
+                RplRepr::DodagInformationSolicitation { options: &[] },
+            ),
+            Some(dst_addr) => {
+                // NOTE(unwrap): we only select a destination for a DIO when we are part of a DODAG.
+                let rpl = self.inner.rpl.as_ref().unwrap();
+                let dodag = rpl.dodag.as_ref().unwrap();
+
+                let option = dodag.configuration.to_option();
+                option.emit(&mut RplOptionPacket::new_unchecked(&mut options[..]));
+
+                (
+                    dst_addr,
+                    RplRepr::DodagInformationObject {
+                        rpl_instance_id: rpl.instance_id,
+                        version_number: dodag.version_number.value(),
+                        rank: dodag.rank.raw_value(),
+                        grounded: dodag.grounded,
+                        mode_of_operation: rpl.mode_of_operation,
+                        dodag_preference: dodag.preference,
+                        dtsn: dodag.dtsn.value(),
+                        dodag_id: dodag.id,
+                        options: &options[..],
+                    },
+                )
+            }
+        };
+
+        let icmp_repr = Icmpv6Repr::Rpl(rpl_repr);
+        let ipv6_repr = Ipv6Repr {
+            src_addr,
+            dst_addr,
+            next_header: IpProtocol::Icmpv6,
+            payload_len: icmp_repr.buffer_len(),
+            hop_limit: 64,
+        };
+
+        net_trace!("rpl: sending {} to {}", rpl_repr, dst_addr);
+
+        let Some(tx_token) = device.transmit(now) else {
+            return false;
+        };
+
+        if let Err(e) = self.inner.dispatch_ip(
+            tx_token,
+            PacketMeta::default(),
+            Packet::new_ipv6(ipv6_repr, IpPayload::Icmpv6(icmp_repr)),
+            &mut self.fragmenter,
+        ) {
+            net_debug!("rpl: failed to dispatch control message: {:?}", e);
+        }
+
+        true
+    }
+
+    /// Transmit a DAO to the root when it is due, and retransmit it when it was not acknowledged.
+    fn rpl_dao_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        let now = self.inner.now;
+
+        let Some(rpl) = &self.inner.rpl else {
+            return false;
+        };
+        if !rpl.sends_dao() {
+            return false;
+        }
+        let Some(dodag) = &rpl.dodag else {
+            return false;
+        };
+        let Some(parent) = dodag.parent else {
+            return false;
+        };
+        if now < dodag.dao_expiration {
+            return false;
+        }
+
+        let src_addr = self.inner.get_source_address_ipv6(&dodag.id);
+
+        // NOTE(unwrap): we checked above that we are part of a DODAG.
+        let rpl = self.inner.rpl.as_mut().unwrap();
+        let dodag = rpl.dodag.as_mut().unwrap();
+
+        if src_addr.is_link_local() || src_addr.is_loopback() {
+            net_debug!("rpl: no routable address, not sending DAO");
+            dodag.dao_expiration = now + consts::DAO_RETRANSMISSION_TIMEOUT;
+            return false;
+        }
+
+        match dodag.dao_transmissions {
+            Some(transmissions) if transmissions > consts::DAO_MAX_RETRANSMISSIONS => {
+                net_debug!(
+                    "rpl: DAO {} was not acknowledged",
+                    dodag.dao_seq_number.value()
+                );
+                dodag.dao_transmissions = None;
+                dodag.dao_expiration = now + dodag.dao_refresh_interval();
+                return false;
+            }
+            Some(transmissions) => dodag.dao_transmissions = Some(transmissions + 1),
+            None => {
+                dodag.dao_seq_number.increment();
+                dodag.dao_transmissions = Some(1);
+            }
+        }
+        dodag.dao_expiration = now + consts::DAO_RETRANSMISSION_TIMEOUT;
+
+        // In non-storing mode, the parent is identified by a routable address. The parent uses the
+        // same prefix as we do, with the interface identifier of its link-local address.
+        let mut parent_address = src_addr;
+        parent_address.0[8..].copy_from_slice(&parent.as_bytes()[8..]);
+
+        let target = RplOptionRepr::RplTarget {
+            prefix_length: 128,
+            prefix: src_addr,
+        };
+        let transit = RplOptionRepr::TransitInformation {
+            external: false,
+            path_control: 0,
+            path_sequence: dodag.dao_seq_number.value(),
+            path_lifetime: dodag.configuration.default_lifetime,
+            parent_address: Some(parent_address),
+        };
+
+        let mut options = [0u8; 42];
+        let (target_option, transit_option) = options.split_at_mut(target.buffer_len());
+        target.emit(&mut RplOptionPacket::new_unchecked(target_option));
+        transit.emit(&mut RplOptionPacket::new_unchecked(transit_option));
+
+        let rpl_repr = RplRepr::DestinationAdvertisementObject {
+            rpl_instance_id: rpl.instance_id,
+            expect_ack: true,
+            sequence: dodag.dao_seq_number.value(),
+            dodag_id: match rpl.instance_id {
+                RplInstanceId::Global(_) => None,
+                RplInstanceId::Local(_) => Some(dodag.id),
+            },
+            options: &options[..],
+        };
+
+        let icmp_repr = Icmpv6Repr::Rpl(rpl_repr);
+        let ipv6_repr = Ipv6Repr {
+            src_addr,
+            dst_addr: dodag.id,
+            next_header: IpProtocol::Icmpv6,
+            payload_len: icmp_repr.buffer_len(),
+            hop_limit: 64,
+        };
+
+        net_trace!("rpl: sending {} to {}", rpl_repr, dodag.id);
+
+        let Some(tx_token) = device.transmit(now) else {
+            return false;
+        };
+
+        if let Err(e) = self.inner.dispatch_ip(
+            tx_token,
+            PacketMeta::default(),
+            Packet::new_ipv6(ipv6_repr, IpPayload::Icmpv6(icmp_repr)),
+            &mut self.fragmenter,
+        ) {
+            net_debug!("rpl: failed to dispatch DAO: {:?}", e);
+        }
+
+        true
+    }
+}
+
+impl InterfaceInner {
+    /// Remove parents that expired, and leave the DODAG when we are without a parent for too long.
+    fn rpl_maintain_dodag(&mut self) {
+        let now = self.now;
+
+        let Some(rpl) = &mut self.rpl else {
+            return;
+        };
+        let is_root = rpl.is_root();
+        let Some(dodag) = &mut rpl.dodag else {
+            return;
+        };
+
+        if is_root {
+            dodag.relations.purge(now);
+            return;
+        }
+
+        if now >= dodag.parent_expiration() {
+            dodag.purge_parents(now);
+            self.rpl_select_parent();
+        }
+
+        // NOTE(unwrap): selecting a parent does not leave the DODAG.
+        let rpl = self.rpl.as_mut().unwrap();
+        let dodag = rpl.dodag.as_mut().unwrap();
+
+        // When we lost our parent, we advertise an infinite Rank for some time, such that our
+        // children can select a new parent. Afterwards, we leave the DODAG and look for a new one.
+        if let Some(without_parent) = dodag.without_parent {
+            if now >= without_parent + dodag.dio_timer.min_expiration() * 2 {
+                net_debug!("rpl: leaving DODAG {}", dodag.id);
+                rpl.dodag = None;
+                rpl.dio_requested_by = None;
+                rpl.dis_expiration = now;
+            }
+        }
+    }
+
+    /// Select the preferred parent and update the default route when the parent changed.
+    ///
+    /// Returns `true` when the preferred parent or the Rank of the node changed.
+    fn rpl_select_parent(&mut self) -> bool {
+        let now = self.now;
+
+        let Some(dodag) = self.rpl.as_mut().and_then(|rpl| rpl.dodag.as_mut()) else {
+            return false;
+        };
+
+        let old_parent = dodag.parent;
+        let old_rank = dodag.rank;
+
+        if !dodag.select_preferred_parent(now) {
+            return dodag.rank != old_rank;
+        }
+
+        if let Some(old_parent) = old_parent {
+            self.routes.update(|routes| {
+                routes.retain(|route| {
+                    route.cidr.prefix_len() != 0 || route.via_router != IpAddress::Ipv6(old_parent)
+                })
+            });
+        }
+
+        match dodag.parent {
+            Some(parent) => {
+                net_debug!(
+                    "rpl: selected {} as preferred parent, {}",
+                    parent,
+                    dodag.rank
+                );
+                if self.routes.add_default_ipv6_route(parent).is_err() {
+                    net_debug!("rpl: unable to add default route via {}", parent);
+                }
+
+                // The root needs to learn about our new parent.
+                dodag.schedule_dao(now);
+            }
+            None => net_debug!("rpl: lost preferred parent"),
+        }
+
+        true
+    }
+
+    pub(super) fn process_rpl<'frame>(
+        &mut self,
+        ip_repr: Ipv6Repr,
+        repr: RplRepr<'frame>,
+    ) -> Option<Packet<'frame>> {
+        match repr {
+            RplRepr::DodagInformationSolicitation { .. } => self.process_rpl_dis(ip_repr),
+            RplRepr::DodagInformationObject {
+                rpl_instance_id,
+                version_number,
+                rank,
+                grounded,
+                mode_of_operation,
+                dodag_preference,
+                dtsn,
+                dodag_id,
+                options,
+            } => {
+                let rpl = self.rpl.as_ref()?;
+                if rpl_instance_id != rpl.instance_id || mode_of_operation != rpl.mode_of_operation
+                {
+                    net_trace!("rpl: ignoring DIO for other RPL instance");
+                    return None;
+                }
+
+                let configuration = rpl_options(options)
+                    .find_map(|option| DodagConfiguration::from_option(&option))
+                    .filter(|configuration| configuration.is_supported());
+
+                self.process_rpl_dio(
+                    ip_repr.src_addr,
+                    Parent::new(
+                        dodag_preference,
+                        // NOTE: the MinHopRankIncrease is corrected when processing the DIO.
+                        Rank::new(rank, consts::DEFAULT_MIN_HOP_RANK_INCREASE),
+                        SequenceCounter::new(version_number),
+                        dodag_id,
+                        self.now,
+                    ),
+                    grounded,
+                    SequenceCounter::new(dtsn),
+                    configuration,
+                )
+            }
+            RplRepr::DestinationAdvertisementObject {
+                rpl_instance_id,
+                expect_ack,
+                sequence,
+                dodag_id,
+                options,
+            } => self.process_rpl_dao(
+                ip_repr,
+                rpl_instance_id,
+                expect_ack,
+                sequence,
+                dodag_id,
+                options,
+            ),
+            RplRepr::DestinationAdvertisementObjectAck {
+                rpl_instance_id,
+                sequence,
+                status,
+                ..
+            } => self.process_rpl_dao_ack(rpl_instance_id, sequence, status),
+        }
+    }
+
+    fn process_rpl_dis<'frame>(&mut self, ip_repr: Ipv6Repr) -> Option<Packet<'frame>> {
+        let rpl = self.rpl.as_mut()?;
+        if !rpl.sends_dio() {
+            return None;
+        }
+        let dodag = rpl.dodag.as_mut()?;
+
+        if ip_repr.dst_addr.is_multicast() {
+            // A multicast DIS resets the Trickle timer, such that a DIO is sent soon.
+            dodag.dio_timer.hear_inconsistency(self.now, &mut self.rand);
+        } else {
+            // A unicast DIS is answered with a unicast DIO.
+            rpl.dio_requested_by = Some(ip_repr.src_addr);
+        }
+
+        None
+    }
+
+    fn process_rpl_dio<'frame>(
+        &mut self,
+        src_addr: Ipv6Address,
+        parent: Parent,
+        grounded: bool,
+        dtsn: SequenceCounter,
+        configuration: Option<DodagConfiguration>,
+    ) -> Option<Packet<'frame>> {
+        let now = self.now;
+        let rpl = self.rpl.as_mut()?;
+        let is_root = rpl.is_root();
+        let version_number = parent.version_number();
+        let dodag_id = parent.dodag_id();
+
+        if rpl.dodag.is_none() {
+            if is_root || parent.rank().raw_value() == Rank::INFINITE.raw_value() {
+                return None;
+            }
+
+            let Some(configuration) = configuration else {
+                net_trace!(
+                    "rpl: not joining DODAG {}, no DODAG configuration",
+                    dodag_id
+                );
+                return None;
+            };
+
+            net_debug!("rpl: joining DODAG {}", dodag_id);
+            rpl.dodag = Some(Dodag::join(
+                &parent,
+                grounded,
+                dtsn,
+                configuration,
+                now,
+                &mut self.rand,
+            ));
+        }
+
+        // NOTE(unwrap): we are part of a DODAG at this point.
+        let dodag = rpl.dodag.as_mut().unwrap();
+
+        if dodag.id != dodag_id {
+            net_trace!("rpl: ignoring DIO for other DODAG {}", dodag_id);
+            return None;
+        }
+
+        if version_number < dodag.version_number {
+            // The neighbor uses an older version of the DODAG, inform it about the new one.
+            dodag.dio_timer.hear_inconsistency(now, &mut self.rand);
+            return None;
+        }
+
+        if is_root {
+            dodag.dio_timer.hear_consistent();
+            return None;
+        }
+
+        if version_number > dodag.version_number {
+            net_debug!(
+                "rpl: new DODAG version {} for {}",
+                version_number.value(),
+                dodag_id
+            );
+            dodag.version_number = version_number;
+            dodag.parent_set.clear();
+            dodag.rank = Rank::INFINITE;
+            dodag.dio_timer.hear_inconsistency(now, &mut self.rand);
+        }
+
+        if let Some(configuration) = configuration {
+            dodag.update_configuration(configuration, now, &mut self.rand);
+        }
+
+        if dodag.parent == Some(src_addr) && dtsn > dodag.dtsn {
+            // The parent asks the nodes in its sub-DODAG to advertise their address again.
+            dodag.dtsn = dtsn;
+            dodag.schedule_dao(now);
+        }
+        dodag.grounded = grounded;
+        dodag.preference = parent.preference();
+
+        let rank = dodag.rank_from_raw(parent.rank().raw_value());
+        let parent = Parent::new(parent.preference(), rank, version_number, dodag_id, now);
+
+        if rank.raw_value() == Rank::INFINITE.raw_value() {
+            // The neighbor poisoned its route, it can no longer be used as parent.
+            dodag.parent_set.remove(&src_addr);
+        } else if dodag.parent == Some(src_addr) || rank < dodag.rank {
+            dodag.parent_set.add(src_addr, parent);
+        } else {
+            // Selecting a neighbor with a higher Rank as parent could create a loop.
+            dodag.parent_set.remove(&src_addr);
+        }
+
+        if self.rpl_select_parent() {
+            // NOTE(unwrap): we just joined or updated the DODAG.
+            let dodag = self
+                .rpl
+                .as_mut()
+                .and_then(|rpl| rpl.dodag.as_mut())
+                .unwrap();
+            dodag.dio_timer.hear_inconsistency(now, &mut self.rand);
+        } else if let Some(dodag) = self.rpl.as_mut().and_then(|rpl| rpl.dodag.as_mut()) {
+            dodag.dio_timer.hear_consistent();
+        }
+
+        None
+    }
+
+    /// Process a DAO. In non-storing mode, the root learns the parent of the nodes in the DODAG
+    /// from the RPL Target and Transit Information options.
+    fn process_rpl_dao<'frame>(
+        &mut self,
+        ip_repr: Ipv6Repr,
+        rpl_instance_id: RplInstanceId,
+        expect_ack: bool,
+        sequence: u8,
+        dodag_id: Option<Ipv6Address>,
+        options: &[u8],
+    ) -> Option<Packet<'frame>> {
+        let now = self.now;
+        let rpl = self.rpl.as_mut()?;
+        if !rpl.is_root()
+            || rpl.mode_of_operation != RplModeOfOperation::NonStoringMode
+            || rpl_instance_id != rpl.instance_id
+        {
+            net_trace!("rpl: ignoring DAO");
+            return None;
+        }
+        let dodag = rpl.dodag.as_mut()?;
+        if dodag_id.is_some_and(|dodag_id| dodag_id != dodag.id) {
+            net_trace!("rpl: ignoring DAO for other DODAG");
+            return None;
+        }
+
+        let lifetime_unit = dodag.configuration.lifetime_unit as u64;
+
+        // The RPL Target options are followed by the Transit Information options that apply to
+        // these targets.
+        let mut targets = Vec::<Ipv6Address, RPL_RELATIONS_BUFFER_COUNT>::new();
+        let mut transit = false;
+        for option in rpl_options(options) {
+            match option {
+                RplOptionRepr::RplTarget { prefix, .. } => {
+                    if transit {
+                        targets.clear();
+                        transit = false;
+                    }
+                    if targets.push(prefix).is_err() {
+                        net_debug!("rpl: too many targets in DAO");
+                    }
+                }
+                RplOptionRepr::TransitInformation {
+                    path_lifetime,
+                    parent_address: Some(parent_address),
+                    ..
+                } => {
+                    transit = true;
+                    for target in &targets {
+                        match path_lifetime {
+                            0 => {
+                                net_trace!("rpl: removing route to {}", target);
+                                dodag.relations.remove_relation(*target);
+                            }
+                            lifetime => {
+                                // A lifetime of 0xff means infinity.
+                                let expiration = if lifetime == 0xff {
+                                    Instant::from_micros(i64::MAX)
+                                } else {
+                                    now + Duration::from_secs(lifetime as u64 * lifetime_unit)
+                                };
+
+                                net_trace!("rpl: {} is reachable via {}", target, parent_address);
+                                dodag
+                                    .relations
+                                    .add_relation(*target, parent_address, expiration);
+                            }
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        if !expect_ack {
+            return None;
+        }
+
+        self.icmpv6_reply(
+            ip_repr,
+            Icmpv6Repr::Rpl(RplRepr::DestinationAdvertisementObjectAck {
+                rpl_instance_id,
+                sequence,
+                status: 0,
+                dodag_id,
+            }),
+        )
+    }
+
+    fn process_rpl_dao_ack<'frame>(
+        &mut self,
+        rpl_instance_id: RplInstanceId,
+        sequence: u8,
+        status: u8,
+    ) -> Option<Packet<'frame>> {
+        let now = self.now;
+        let rpl = self.rpl.as_mut()?;
+        if rpl_instance_id != rpl.instance_id {
+            return None;
+        }
+        let dodag = rpl.dodag.as_mut()?;
+
+        if dodag.dao_transmissions.is_none() || sequence != dodag.dao_seq_number.value() {
+            net_trace!("rpl: ignoring unexpected DAO-ACK {}", sequence);
+            return None;
+        }
+
+        // A status of 128 or higher indicates that the root rejected the DAO. We try again
+        // later.
+        if status >= 128 {
+            net_debug!("rpl: DAO {} rejected with status {}", sequence, status);
+        }
+
+        dodag.dao_transmissions = None;
+        dodag.dao_expiration = now + dodag.dao_refresh_interval();
+
+        None
+    }
+
+    /// Returns `true` when a packet that is not addressed to us should be forwarded through the
+    /// DODAG: upwards towards the root, or downwards when we are the root and know the route.
+    pub(super) fn rpl_forwards(&self, ipv6_repr: &Ipv6Repr) -> bool {
+        let Some(rpl) = &self.rpl else {
+            return false;
+        };
+        let Some(dodag) = &rpl.dodag else {
+            return false;
+        };
+
+        if matches!(rpl.role, RplRole::Leaf)
+            || !ipv6_repr.dst_addr.is_unicast()
+            || ipv6_repr.dst_addr.is_link_local()
+            || ipv6_repr.src_addr.is_link_local()
+        {
+            return false;
+        }
+
+        if rpl.is_root() {
+            dodag.relations.find_next_hop(ipv6_repr.dst_addr).is_some()
+        } else {
+            dodag.parent.is_some()
+        }
+    }
+
+    /// Forward a packet that is not addressed to us. The packet is routed using the default
+    /// route via the preferred parent, or with a source route when we are the root.
+    pub(super) fn rpl_forward<'frame>(
+        &self,
+        ipv6_repr: Ipv6Repr,
+        next_header: IpProtocol,
+        ip_payload: &'frame [u8],
+    ) -> Option<Packet<'frame>> {
+        if !is_forwardable(next_header) {
+            net_debug!(
+                "rpl: unable to forward packet with next header {}",
+                next_header
+            );
+            return None;
+        }
+
+        if ipv6_repr.hop_limit <= 1 {
+            return self.rpl_hop_limit_exceeded(ipv6_repr, ip_payload);
+        }
+
+        Some(Packet::new_ipv6(
+            Ipv6Repr {
+                next_header,
+                payload_len: ip_payload.len(),
+                hop_limit: ipv6_repr.hop_limit - 1,
+                ..ipv6_repr
+            },
+            IpPayload::Forward(next_header, ip_payload),
+        ))
+    }
+
+    /// Process an RPL Source Routing Header with segments left, as described in [RFC 6554 §
+    /// 4.2], and forward the packet to the next address in the route. The next address is
+    /// swapped with the destination address when the header is emitted.
+    ///
+    /// [RFC 6554 § 4.2]: https://datatracker.ietf.org/doc/html/rfc6554#section-4.2
+    pub(super) fn process_rpl_source_route<'frame>(
+        &self,
+        ipv6_repr: Ipv6Repr,
+        next_header: IpProtocol,
+        routing_repr: Ipv6RoutingRepr<'frame>,
+        ip_payload: &'frame [u8],
+        payload: &'frame [u8],
+    ) -> Option<Packet<'frame>> {
+        let Ipv6RoutingRepr::Rpl {
+            segments_left,
+            cmpr_i,
+            cmpr_e,
+            pad,
+            addresses,
+        } = routing_repr
+        else {
+            return None;
+        };
+
+        // Calculate the number of addresses in the route.
+        let Some(n) = source_route_len(&routing_repr) else {
+            net_debug!("rpl: invalid source routing header");
+            return None;
+        };
+
+        if segments_left as usize > n {
+            return self.rpl_param_problem(
+                ipv6_repr,
+                ip_payload,
+                // The offset of the Segments Left field.
+                ipv6_repr.buffer_len() as u32 + 3,
+            );
+        }
+
+        if !is_forwardable(next_header) {
+            net_debug!(
+                "rpl: unable to forward packet with next header {}",
+                next_header
+            );
+            return None;
+        }
+
+        let segments_left = segments_left - 1;
+        let i = n - segments_left as usize;
+        let dst_addr = source_route_address(&ipv6_repr.dst_addr, &routing_repr, n, i);
+
+        if ipv6_repr.dst_addr.is_multicast() || dst_addr.is_multicast() {
+            net_debug!("rpl: multicast address in source routing header");
+            return None;
+        }
+
+        // The route contains a loop when our addresses appear more than once, separated by an
+        // address that is not ours.
+        let mut ours = (1..=n).map(|j| {
+            self.has_ip_addr(source_route_address(
+                &ipv6_repr.dst_addr,
+                &routing_repr,
+                n,
+                j,
+            ))
+        });
+        if ours.by_ref().skip_while(|o| !o).any(|o| !o) && ours.any(|o| o) {
+            net_debug!("rpl: loop in source routing header");
+            return self.rpl_param_problem(
+                ipv6_repr,
+                ip_payload,
+                // The offset of the Addresses field.
+                ipv6_repr.buffer_len() as u32 + 8,
+            );
+        }
+
+        if ipv6_repr.hop_limit <= 1 {
+            return self.rpl_hop_limit_exceeded(ipv6_repr, ip_payload);
+        }
+
+        Some(Packet::Ipv6(PacketV6 {
+            header: Ipv6Repr {
+                dst_addr,
+                next_header,
+                payload_len: payload.len(),
+                hop_limit: ipv6_repr.hop_limit - 1,
+                ..ipv6_repr
+            },
+            hop_by_hop: None,
+            #[cfg(feature = "proto-ipv6-fragmentation")]
+            fragment: None,
+            routing: Some(Ipv6RoutingRepr::Rpl {
+                segments_left,
+                cmpr_i,
+                cmpr_e,
+                pad,
+                addresses,
+            }),
+            source_route_swap: Some((i, ipv6_repr.dst_addr)),
+            payload: IpPayload::Forward(next_header, payload),
+        }))
+    }
+
+    fn rpl_param_problem<'frame>(
+        &self,
+        ipv6_repr: Ipv6Repr,
+        ip_payload: &'frame [u8],
+        pointer: u32,
+    ) -> Option<Packet<'frame>> {
+        let payload_len =
+            icmp_reply_payload_len(ip_payload.len(), IPV6_MIN_MTU, ipv6_repr.buffer_len());
+        self.icmpv6_reply(
+            ipv6_repr,
+            Icmpv6Repr::ParamProblem {
+                reason: Icmpv6ParamProblem::ErroneousHdrField,
+                pointer,
+                header: ipv6_repr,
+                data: &ip_payload[..payload_len],
+            },
+        )
+    }
+
+    fn rpl_hop_limit_exceeded<'frame>(
+        &self,
+        ipv6_repr: Ipv6Repr,
+        ip_payload: &'frame [u8],
+    ) -> Option<Packet<'frame>> {
+        let payload_len =
+            icmp_reply_payload_len(ip_payload.len(), IPV6_MIN_MTU, ipv6_repr.buffer_len());
+
+        // The packet was not addressed to us, so we reply from our own address.
+        let src_addr = self.get_source_address_ipv6(&ipv6_repr.src_addr);
+        self.icmpv6_reply(
+            Ipv6Repr {
+                dst_addr: src_addr,
+                ..ipv6_repr
+            },
+            Icmpv6Repr::TimeExceeded {
+                reason: Icmpv6TimeExceeded::HopLimitExceeded,
+                header: ipv6_repr,
+                data: &ip_payload[..payload_len],
+            },
+        )
+    }
+
+    /// Route a packet that is transmitted by this node downwards through the DODAG.
+    ///
+    /// When we are the root, an RPL Source Routing Header is inserted for destinations that are
+    /// not a child of the root, using `addresses` as buffer. The returned address is the
+    /// link-local address of the neighbor the packet should be transmitted to, when the packet is
+    /// routed downwards.
+    pub(super) fn rpl_route_downwards<'p>(
+        &self,
+        packet: Packet<'p>,
+        addresses: &'p mut [u8],
+    ) -> (Packet<'p>, Option<IpAddress>) {
+        #[allow(clippy::infallible_destructuring_match)]
+        let mut packet = match packet {
+            Packet::Ipv6(packet) => packet,
+            #[cfg(feature = "proto-ipv4")]
+            packet @ Packet::Ipv4(_) => return (packet, None),
+        };
+        let dst_addr = packet.header.dst_addr;
+
+        // The destination of a source routed packet is the next address in the route, which is
+        // a neighbor.
+        if matches!(packet.routing, Some(Ipv6RoutingRepr::Rpl { .. })) {
+            let neighbor = link_local_address(&dst_addr);
+            return (Packet::Ipv6(packet), Some(neighbor.into()));
+        }
+
+        let Some(rpl) = &self.rpl else {
+            return (Packet::Ipv6(packet), None);
+        };
+        let Some(dodag) = &rpl.dodag else {
+            return (Packet::Ipv6(packet), None);
+        };
+        if !rpl.is_root()
+            || rpl.mode_of_operation != RplModeOfOperation::NonStoringMode
+            || !dst_addr.is_unicast()
+            || dst_addr.is_link_local()
+        {
+            return (Packet::Ipv6(packet), None);
+        }
+
+        // Walk up the DODAG, from the destination to the root. The last hop is a child of the
+        // root.
+        let mut hops = Vec::<Ipv6Address, RPL_RELATIONS_BUFFER_COUNT>::new();
+        let mut node = dst_addr;
+        loop {
+            let Some(parent) = dodag.relations.find_next_hop(node) else {
+                if !hops.is_empty() {
+                    net_debug!("rpl: no route to {}, {} has no parent", dst_addr, node);
+                }
+                return (Packet::Ipv6(packet), None);
+            };
+
+            if self.has_ip_addr(parent) {
+                break;
+            }
+
+            if hops.contains(&parent) || hops.push(parent).is_err() {
+                net_debug!("rpl: no loop-free source route to {}", dst_addr);
+                return (Packet::Ipv6(packet), None);
+            }
+
+            node = parent;
+        }
+
+        let Some(&first_hop) = hops.last() else {
+            // The destination is a child of the root.
+            let neighbor = link_local_address(&dst_addr);
+            return (Packet::Ipv6(packet), Some(neighbor.into()));
+        };
+
+        // The route consists of the hops after the first hop, followed by the destination. The
+        // prefix they share with the first hop is elided.
+        let route = || hops.iter().rev().skip(1).chain(core::iter::once(&dst_addr));
+        let cmpr = route()
+            .map(|addr| {
+                addr.as_bytes()
+                    .iter()
+                    .zip(first_hop.as_bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            })
+            .min()
+            .unwrap_or(0)
+            .min(15);
+
+        let segment_len = 16 - cmpr;
+        let len = hops.len() * segment_len;
+        let pad = (8 - len % 8) % 8;
+        if len + pad > addresses.len() {
+            net_debug!("rpl: source route to {} is too long", dst_addr);
+            return (Packet::Ipv6(packet), None);
+        }
+
+        for (i, addr) in route().enumerate() {
+            addresses[i * segment_len..][..segment_len].copy_from_slice(&addr.as_bytes()[cmpr..]);
+        }
+        addresses[len..len + pad].fill(0);
+        let addresses: &'p [u8] = addresses;
+
+        packet.header.dst_addr = first_hop;
+        packet.routing = Some(Ipv6RoutingRepr::Rpl {
+            segments_left: hops.len() as u8,
+            cmpr_i: cmpr as u8,
+            cmpr_e: cmpr as u8,
+            pad: pad as u8,
+            addresses: &addresses[..len + pad],
+        });
+
+        let neighbor = link_local_address(&first_hop);
+        (Packet::Ipv6(packet), Some(neighbor.into()))
+    }
+}
@@ -99,7 +99,31 @@ impl InterfaceInner {
             }
         };
 
-        self.process_ipv6(sockets, meta, &check!(Ipv6Packet::new_checked(payload)))
+        let ipv6_packet = check!(Ipv6Packet::new_checked(payload));
+
+        // RPL neighbors are addressed using their link-local address. Remember their link-layer
+        // address, such that we can reach them without neighbor discovery. Packets with another
+        // source address are only used when they originate from the neighbor itself.
+        #[cfg(feature = "proto-rpl")]
+        if self.rpl.is_some() {
+            let src_addr = ipv6_packet.src_addr();
+            if let Some(ll_src_addr) = ieee802154_repr.src_addr.filter(|a| a.is_unicast()) {
+                let neighbor_addr = if src_addr.is_link_local() {
+                    Some(src_addr)
+                } else {
+                    ll_src_addr
+                        .as_link_local_address()
+                        .filter(|addr| *addr == super::rpl::link_local_address(&src_addr))
+                };
+
+                if let Some(neighbor_addr) = neighbor_addr.filter(|addr| addr.is_unicast()) {
+                    self.neighbor_cache
+                        .fill(neighbor_addr.into(), ll_src_addr.into(), self.now);
+                }
+            }
+        }
+
+        self.process_ipv6(sockets, meta, &ipv6_packet)
     }
 
     #[cfg(feature = "proto-sixlowpan-fragmentation")]
@@ -329,7 +353,14 @@ impl InterfaceInner {
                     return;
                 }
 
-                let payload_length = packet.header.payload_len;
+                #[allow(unused_mut)]
+                let mut payload_length = packet.header.payload_len;
+
+                // The payload length of the header does not include the extension headers.
+                #[cfg(feature = "proto-ipv6-routing")]
+                if let Some(routing) = &packet.routing {
+                    payload_length += 2 + routing.buffer_len();
+                }
 
                 Self::ipv6_to_sixlowpan(
                     &self.checksum_caps(),
@@ -491,7 +522,7 @@ impl InterfaceInner {
         if let Some(routing) = &packet.routing {
             let ext_hdr = SixlowpanExtHeaderRepr {
                 ext_header_id: SixlowpanExtHeaderId::RoutingHeader,
-                next_header,
+                next_header: last_header,
                 length: routing.buffer_len() as u8,
             };
             ext_hdr.emit(&mut SixlowpanExtHeaderPacket::new_unchecked(
@@ -502,14 +533,39 @@ impl InterfaceInner {
             routing.emit(&mut Ipv6RoutingHeader::new_unchecked(
                 &mut buffer[..routing.buffer_len()],
             ));
+
+            // The addresses are at the end of the header.
+            #[cfg(feature = "proto-rpl")]
+            if let (Ipv6RoutingRepr::Rpl { addresses, .. }, Some(swap)) =
+                (routing, packet.source_route_swap)
+            {
+                let end = routing.buffer_len();
+                super::rpl::swap_source_route_address(
+                    routing,
+                    swap,
+                    &mut buffer[end - addresses.len()..end],
+                );
+            }
             buffer = &mut buffer[routing.buffer_len()..];
         }
 
+        // The checksum of a source routed packet is computed using its final destination.
+        #[allow(unused_mut)]
+        let mut dst_addr = packet.header.dst_addr;
+        #[cfg(feature = "proto-rpl")]
+        if let Some(final_dst_addr) = packet
+            .routing
+            .as_ref()
+            .and_then(|routing| super::rpl::final_destination(&dst_addr, routing))
+        {
+            dst_addr = final_dst_addr;
+        }
+
         match &mut packet.payload {
             IpPayload::Icmpv6(icmp_repr) => {
                 icmp_repr.emit(
                     &packet.header.src_addr,
-                    &packet.header.dst_addr,
+                    &dst_addr,
                     &mut Icmpv6Packet::new_unchecked(&mut buffer[..icmp_repr.buffer_len()]),
                     checksum_caps,
                 );
@@ -522,7 +578,7 @@ impl InterfaceInner {
                         &mut buffer[..udp_repr.header_len() + payload.len()],
                     ),
                     &iphc_repr.src_addr,
-                    &iphc_repr.dst_addr,
+                    &dst_addr,
                     payload.len(),
                     |buf| buf.copy_from_slice(payload),
                     checksum_caps,
@@ -533,12 +589,30 @@ impl InterfaceInner {
                 tcp_repr.emit(
                     &mut TcpPacket::new_unchecked(&mut buffer[..tcp_repr.buffer_len()]),
                     &packet.header.src_addr.into(),
-                    &packet.header.dst_addr.into(),
+                    &dst_addr.into(),
+                    checksum_caps,
+                );
+            }
+            #[cfg(feature = "socket-dhcpv6")]
+            IpPayload::Dhcpv6(udp_repr, dhcp_repr) => {
+                udp_repr.emit(
+                    &mut UdpPacket::new_unchecked(
+                        &mut buffer[..udp_repr.header_len() + dhcp_repr.buffer_len()],
+                    ),
+                    &packet.header.src_addr.into(),
+                    &dst_addr.into(),
+                    dhcp_repr.buffer_len(),
+                    |buf| {
+                        dhcp_repr
+                            .emit(&mut Dhcpv6Packet::new_unchecked(buf))
+                            .unwrap()
+                    },
                     checksum_caps,
                 );
             }
             #[cfg(feature = "socket-raw")]
             IpPayload::Raw(_raw) => todo!(),
+            IpPayload::Forward(_, payload) => buffer[..payload.len()].copy_from_slice(payload),
 
             #[allow(unreachable_patterns)]
             _ => unreachable!(),
@@ -618,12 +692,13 @@ impl InterfaceInner {
         if let Some(routing) = &packet.routing {
             let ext_hdr = SixlowpanExtHeaderRepr {
                 ext_header_id: SixlowpanExtHeaderId::RoutingHeader,
-                next_header,
+                next_header: last_header,
                 length: routing.buffer_len() as u8,
             };
             total_size += ext_hdr.buffer_len() + routing.buffer_len();
             compressed_hdr_size += ext_hdr.buffer_len() + routing.buffer_len();
-            uncompressed_hdr_size += routing.buffer_len();
+            // The uncompressed header also contains the next header and length fields.
+            uncompressed_hdr_size += 2 + routing.buffer_len();
         }
 
         match packet.payload {
@@ -850,6 +925,8 @@ mod tests {
             fragment: None,
             #[cfg(feature = "proto-ipv6-routing")]
             routing: None,
+            #[cfg(feature = "proto-rpl")]
+            source_route_swap: None,
             payload: IpPayload::Icmpv6(Icmpv6Repr::Rpl(RplRepr::DestinationAdvertisementObject {
                 rpl_instance_id: RplInstanceId::Global(30),
                 expect_ack: false,
@@ -931,6 +1008,8 @@ mod tests {
             fragment: None,
             #[cfg(feature = "proto-ipv6-routing")]
             routing: None,
+            #[cfg(feature = "proto-rpl")]
+            source_route_swap: None,
             payload: IpPayload::Icmpv6(Icmpv6Repr::Rpl(RplRepr::DestinationAdvertisementObject {
                 rpl_instance_id: RplInstanceId::Global(30),
                 expect_ack: false,
@@ -0,0 +1,329 @@
+// IPv6 Stateless Address Autoconfiguration, see RFC 4862. Router Solicitations and Router
+// Advertisements are described in RFC 4861.
+
+use super::*;
+use crate::iface::{Route, RouteOrigin, Routes};
+
+/// Maximum random delay before sending the first Router Solicitation.
+const MAX_RTR_SOLICITATION_DELAY: Duration = Duration::from_secs(1);
+/// Time between Router Solicitations.
+const RTR_SOLICITATION_INTERVAL: Duration = Duration::from_secs(4);
+/// Maximum number of Router Solicitations that are sent.
+const MAX_RTR_SOLICITATIONS: u8 = 3;
+/// Remaining valid lifetime of an address that cannot be shortened by a Router Advertisement.
+const MIN_REMAINING_LIFETIME: Duration = Duration::from_secs(2 * 60 * 60);
+/// The lifetime value that represents infinity.
+const INFINITE_LIFETIME: Duration = Duration::from_secs(0xffff_ffff);
+
+/// Remove the routes learned from Router Advertisements, leaving the other routes alone.
+fn remove_router_advert_routes(routes: &mut Routes) {
+    routes.update(|storage| storage.retain(|r| r.origin != RouteOrigin::RouterAdvertisement));
+}
+
+/// An address formed from a prefix advertised by a router.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Address {
+    cidr: Ipv6Cidr,
+    preferred_until: Instant,
+    expires_at: Instant,
+}
+
+/// The state of stateless address autoconfiguration on an interface.
+#[derive(Debug)]
+pub(crate) struct Slaac {
+    /// The interface identifier used to form addresses.
+    iid: [u8; 8],
+    /// The addresses formed from advertised prefixes.
+    addresses: Vec<Address, IFACE_MAX_ADDR_COUNT>,
+    /// The default router, and when it stops being the default router.
+    router: Option<(Ipv6Address, Instant)>,
+    /// When to send the next Router Solicitation, if any.
+    solicit_at: Option<Instant>,
+    /// The number of Router Solicitations that have been sent.
+    solicitations: u8,
+}
+
+impl Slaac {
+    /// Create the autoconfiguration state for an interface, forming its interface identifier from
+    /// the hardware address. Returns `None` when no interface identifier can be formed.
+    pub(super) fn new(
+        hardware_addr: &HardwareAddress,
+        now: Instant,
+        rand: &mut Rand,
+    ) -> Option<Self> {
+        let iid = match hardware_addr {
+            #[cfg(feature = "medium-ethernet")]
+            HardwareAddress::Ethernet(addr) => addr.as_eui_64(),
+            #[cfg(feature = "medium-ieee802154")]
+            HardwareAddress::Ieee802154(addr) => addr.as_eui_64()?,
+            #[allow(unreachable_patterns)]
+            _ => return None,
+        };
+
+        let delay = rand.rand_u32() as u64 % MAX_RTR_SOLICITATION_DELAY.total_millis();
+
+        Some(Self {
+            iid,
+            addresses: Vec::new(),
+            router: None,
+            solicit_at: Some(now + Duration::from_millis(delay)),
+            solicitations: 0,
+        })
+    }
+
+    /// Return the link-local address of the interface.
+    pub(super) fn link_local_address(&self) -> Ipv6Cidr {
+        let prefix = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 0);
+        Ipv6Cidr::new(self.address(&prefix), 64)
+    }
+
+    /// Form an address using the first 64 bits of `prefix`, followed by the interface identifier.
+    fn address(&self, prefix: &Ipv6Address) -> Ipv6Address {
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&prefix.as_bytes()[..8]);
+        bytes[8..].copy_from_slice(&self.iid);
+        Ipv6Address::from_bytes(&bytes)
+    }
+
+    /// Returns `true` when the preferred lifetime of an autoconfigured address has passed.
+    pub(super) fn is_deprecated(&self, addr: &Ipv6Address, now: Instant) -> bool {
+        self.addresses
+            .iter()
+            .any(|a| a.cidr.address() == *addr && a.preferred_until <= now)
+    }
+
+    /// Forget an autoconfigured address, e.g. when it is a duplicate.
+    pub(super) fn remove_address(&mut self, addr: &Ipv6Address) {
+        self.addresses.retain(|a| a.cidr.address() != *addr);
+    }
+
+    /// Return the time at which the state must be updated.
+    pub(super) fn poll_at(&self) -> Option<Instant> {
+        self.addresses
+            .iter()
+            .map(|a| a.expires_at)
+            .chain(self.router.map(|(_, expires_at)| expires_at))
+            .chain(self.solicit_at)
+            .min()
+    }
+}
+
+/// Return the end of a lifetime starting `now`.
+fn lifetime_end(now: Instant, lifetime: Duration) -> Instant {
+    if lifetime == INFINITE_LIFETIME {
+        Instant::from_micros(i64::MAX)
+    } else {
+        now + lifetime
+    }
+}
+
+impl Interface {
+    /// Send Router Solicitations, and remove addresses and the default router once their lifetime
+    /// has expired.
+    pub(super) fn slaac_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        self.inner.slaac_expire();
+
+        let now = self.inner.now;
+        let Some(slaac) = &mut self.inner.slaac else {
+            return false;
+        };
+
+        match slaac.solicit_at {
+            Some(solicit_at) if now >= solicit_at => {
+                slaac.solicitations += 1;
+                slaac.solicit_at = (slaac.solicitations < MAX_RTR_SOLICITATIONS)
+                    .then_some(now + RTR_SOLICITATION_INTERVAL);
+            }
+            _ => return false,
+        }
+
+        let Some(tx_token) = device.transmit(now) else {
+            return false;
+        };
+
+        let pkt = self.inner.router_solicit_packet();
+
+        // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
+        self.inner
+            .dispatch_ip(tx_token, PacketMeta::default(), pkt, &mut self.fragmenter)
+            .unwrap();
+
+        true
+    }
+}
+
+impl InterfaceInner {
+    /// Create a Router Solicitation, sent to all routers.
+    fn router_solicit_packet(&self) -> Packet<'static> {
+        // The source link-layer address option is not included when the source address is the
+        // unspecified address, see RFC 4861 § 4.1.
+        let src_addr = self
+            .link_local_ipv6_address()
+            .unwrap_or(Ipv6Address::UNSPECIFIED);
+        let lladdr = (!src_addr.is_unspecified()).then(|| self.hardware_addr.into());
+
+        net_trace!("slaac: sending Router Solicitation");
+
+        let repr = Icmpv6Repr::Ndisc(NdiscRepr::RouterSolicit { lladdr });
+        Packet::new_ipv6(
+            Ipv6Repr {
+                src_addr,
+                dst_addr: Ipv6Address::LINK_LOCAL_ALL_ROUTERS,
+                next_header: IpProtocol::Icmpv6,
+                payload_len: repr.buffer_len(),
+                hop_limit: 0xff,
+            },
+            IpPayload::Icmpv6(repr),
+        )
+    }
+
+    /// Update the default router and the autoconfigured addresses using a Router Advertisement.
+    pub(super) fn process_router_advert(
+        &mut self,
+        ip_repr: Ipv6Repr,
+        router_lifetime: Duration,
+        prefix_info: Option<NdiscPrefixInformation>,
+    ) {
+        let now = self.now;
+        let Some(slaac) = &mut self.slaac else {
+            return;
+        };
+
+        // Routers must use their link-local address as source address, see RFC 4861 § 6.1.2.
+        if !ip_repr.src_addr.is_link_local() {
+            net_debug!(
+                "slaac: ignoring Router Advertisement from {}",
+                ip_repr.src_addr
+            );
+            return;
+        }
+
+        slaac.solicit_at = None;
+
+        let router = ip_repr.src_addr;
+        if router_lifetime == Duration::ZERO {
+            if matches!(slaac.router, Some((addr, _)) if addr == router) {
+                net_debug!("slaac: {} is no longer the default router", router);
+                slaac.router = None;
+                remove_router_advert_routes(&mut self.routes);
+            }
+        } else {
+            let expires_at = now + router_lifetime;
+            if !matches!(slaac.router, Some((addr, _)) if addr == router) {
+                net_debug!("slaac: using {} as default router", router);
+            }
+            let route = Route {
+                expires_at: Some(expires_at),
+                ..Route::new_ipv6_gateway(router).with_origin(RouteOrigin::RouterAdvertisement)
+            };
+            self.routes.update(|storage| {
+                storage.retain(|r| r.origin != RouteOrigin::RouterAdvertisement);
+                if storage.push(route).is_err() {
+                    net_debug!("slaac: unable to add default route, table is full");
+                }
+            });
+            slaac.router = Some((router, expires_at));
+        }
+
+        if let Some(prefix_info) = prefix_info {
+            self.process_prefix_information(prefix_info);
+        }
+    }
+
+    /// Form or update an address using the Prefix Information option of a Router Advertisement,
+    /// see RFC 4862 § 5.5.3.
+    fn process_prefix_information(&mut self, info: NdiscPrefixInformation) {
+        let now = self.now;
+        let Some(slaac) = &mut self.slaac else {
+            return;
+        };
+
+        if !info.flags.contains(NdiscPrefixInfoFlags::ADDRCONF)
+            || info.prefix.is_link_local()
+            || info.preferred_lifetime > info.valid_lifetime
+        {
+            return;
+        }
+
+        // The interface identifier is 64 bits long, thus only /64 prefixes can be used.
+        if info.prefix_len != 64 {
+            net_debug!("slaac: ignoring prefix {}/{}", info.prefix, info.prefix_len);
+            return;
+        }
+
+        let addr = slaac.address(&info.prefix);
+        let preferred_until = lifetime_end(now, info.preferred_lifetime);
+        let valid_until = lifetime_end(now, info.valid_lifetime);
+
+        if let Some(address) = slaac
+            .addresses
+            .iter_mut()
+            .find(|a| a.cidr.address() == addr)
+        {
+            address.preferred_until = preferred_until;
+
+            // The valid lifetime can only be shortened down to two hours, to prevent denial of
+            // service attacks using spoofed advertisements.
+            if info.valid_lifetime > MIN_REMAINING_LIFETIME || valid_until > address.expires_at {
+                address.expires_at = valid_until;
+            } else if address.expires_at > now + MIN_REMAINING_LIFETIME {
+                address.expires_at = now + MIN_REMAINING_LIFETIME;
+            }
+            return;
+        }
+
+        if info.valid_lifetime == Duration::ZERO
+            || self
+                .ip_addrs
+                .iter()
+                .any(|cidr| cidr.address() == addr.into())
+        {
+            return;
+        }
+
+        let address = Address {
+            cidr: Ipv6Cidr::new(addr, info.prefix_len),
+            preferred_until,
+            expires_at: valid_until,
+        };
+
+        if slaac.addresses.push(address).is_err()
+            || self.ip_addrs.push(IpCidr::Ipv6(address.cidr)).is_err()
+        {
+            net_debug!("slaac: unable to add address {}, too many addresses", addr);
+            slaac.addresses.retain(|a| *a != address);
+            return;
+        }
+
+        net_debug!("slaac: added address {}", address.cidr);
+        self.dad_start(addr);
+    }
+
+    /// Remove the addresses and the default router whose lifetime has expired.
+    fn slaac_expire(&mut self) {
+        let now = self.now;
+        let Some(slaac) = &mut self.slaac else {
+            return;
+        };
+
+        if matches!(slaac.router, Some((_, expires_at)) if expires_at <= now) {
+            net_debug!("slaac: default router expired");
+            slaac.router = None;
+            remove_router_advert_routes(&mut self.routes);
+        }
+
+        let ip_addrs = &mut self.ip_addrs;
+        slaac.addresses.retain(|address| {
+            if address.expires_at > now {
+                return true;
+            }
+
+            net_debug!("slaac: address {} expired", address.cidr);
+            ip_addrs.retain(|cidr| *cidr != IpCidr::Ipv6(address.cidr));
+            false
+        });
+    }
+}
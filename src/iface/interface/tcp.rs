@@ -42,4 +42,31 @@ impl InterfaceInner {
             Some(Packet::new(ip, IpPayload::Tcp(tcp)))
         }
     }
+
+    /// Deliver an ICMP error to the socket that sent the segment in error.
+    pub(super) fn process_tcp_icmp_error(
+        &mut self,
+        sockets: &mut SocketSet,
+        header: &IpRepr,
+        data: &[u8],
+        hard: bool,
+    ) {
+        // The ports and the sequence number are in the first eight octets of the segment.
+        if data.len() < 8 {
+            return;
+        }
+        let tcp_packet = TcpPacket::new_unchecked(data);
+        let local = IpEndpoint::new(header.src_addr(), tcp_packet.src_port());
+        let remote = IpEndpoint::new(header.dst_addr(), tcp_packet.dst_port());
+
+        for tcp_socket in sockets
+            .items_mut()
+            .filter_map(|i| Socket::downcast_mut(&mut i.socket))
+        {
+            if tcp_socket.accepts_icmp_error(&local, &remote) {
+                tcp_socket.process_icmp_error(tcp_packet.seq_number(), hard);
+                return;
+            }
+        }
+    }
 }
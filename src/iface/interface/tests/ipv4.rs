@@ -510,6 +510,369 @@ fn test_handle_other_arp_request(#[case] medium: Medium) {
     );
 }
 
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_neighbor_probe(#[case] medium: Medium) {
+    use crate::iface::{NeighborEntry, NeighborState};
+
+    let (mut iface, _, mut device) = setup(medium);
+
+    let local_ip_addr = Ipv4Address::new(192, 168, 1, 1);
+    let remote_ip_addr = Ipv4Address::new(192, 168, 1, 2);
+    let local_hw_addr = EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x02]);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x00]);
+
+    iface
+        .inner
+        .neighbor_cache
+        .fill(remote_ip_addr.into(), remote_hw_addr.into(), Instant::ZERO);
+
+    // Sending a packet to the stale neighbor delays its probe.
+    iface.inner.now = Instant::from_secs(30);
+    let icmp_repr = Icmpv4Repr::EchoRequest {
+        ident: 0x1234,
+        seq_no: 1,
+        data: b"probe",
+    };
+    let ipv4_repr = Ipv4Repr {
+        src_addr: local_ip_addr,
+        dst_addr: remote_ip_addr,
+        next_header: IpProtocol::Icmp,
+        payload_len: icmp_repr.buffer_len(),
+        hop_limit: 64,
+    };
+    let tx_token = device.transmit(iface.inner.now).unwrap();
+    assert_eq!(
+        iface.inner.dispatch_ip(
+            tx_token,
+            PacketMeta::default(),
+            Packet::new_ipv4(ipv4_repr, IpPayload::Icmpv4(icmp_repr)),
+            &mut iface.fragmenter,
+        ),
+        Ok(())
+    );
+    let frame = device.queue.pop_front().unwrap();
+    assert_eq!(
+        EthernetFrame::new_checked(&frame[..]).unwrap().dst_addr(),
+        remote_hw_addr
+    );
+    assert_eq!(
+        iface.neighbors().collect::<std::vec::Vec<_>>(),
+        [NeighborEntry {
+            protocol_addr: remote_ip_addr.into(),
+            hardware_addr: remote_hw_addr.into(),
+            state: NeighborState::Delay,
+        }]
+    );
+    assert!(!iface.neighbor_egress(&mut device));
+
+    // The neighbor is probed with a unicast ARP request.
+    iface.inner.now = Instant::from_secs(35);
+    assert_eq!(
+        iface.poll_at(iface.inner.now, &SocketSet::new(vec![])),
+        Some(iface.inner.now)
+    );
+    assert!(iface.neighbor_egress(&mut device));
+    let frame = device.queue.pop_front().unwrap();
+    let frame = EthernetFrame::new_checked(&frame[..]).unwrap();
+    assert_eq!(frame.dst_addr(), remote_hw_addr);
+    assert_eq!(frame.ethertype(), EthernetProtocol::Arp);
+    assert_eq!(
+        ArpRepr::parse(&ArpPacket::new_checked(frame.payload()).unwrap()),
+        Ok(ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Request,
+            source_hardware_addr: local_hw_addr,
+            source_protocol_addr: local_ip_addr,
+            target_hardware_addr: remote_hw_addr,
+            target_protocol_addr: remote_ip_addr,
+        })
+    );
+
+    // The reply confirms the neighbor.
+    let reply = ArpRepr::EthernetIpv4 {
+        operation: ArpOperation::Reply,
+        source_hardware_addr: remote_hw_addr,
+        source_protocol_addr: remote_ip_addr,
+        target_hardware_addr: local_hw_addr,
+        target_protocol_addr: local_ip_addr,
+    };
+    let mut bytes = vec![0u8; 14 + reply.buffer_len()];
+    let mut frame = EthernetFrame::new_unchecked(&mut bytes[..]);
+    frame.set_dst_addr(local_hw_addr);
+    frame.set_src_addr(remote_hw_addr);
+    frame.set_ethertype(EthernetProtocol::Arp);
+    reply.emit(&mut ArpPacket::new_unchecked(frame.payload_mut()));
+    let frame = EthernetFrame::new_checked(&bytes[..]).unwrap();
+    assert_eq!(iface.inner.process_arp(iface.inner.now, &frame), None);
+    assert_eq!(
+        iface.neighbors().next().map(|entry| entry.state),
+        Some(NeighborState::Reachable)
+    );
+
+    // Neighbors that do not answer are removed.
+    iface.inner.now = Instant::from_secs(65);
+    iface
+        .inner
+        .neighbor_cache
+        .used(&remote_ip_addr.into(), iface.inner.now);
+    for _ in 0..3 {
+        iface.inner.now += Duration::from_secs(5);
+        assert!(iface.neighbor_egress(&mut device));
+        device.queue.pop_front().unwrap();
+    }
+    iface.inner.now += Duration::from_secs(1);
+    assert!(!iface.neighbor_egress(&mut device));
+    assert_eq!(iface.neighbors().next(), None);
+}
+
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_permanent_neighbor(#[case] medium: Medium) {
+    use crate::iface::NeighborState;
+
+    let (mut iface, mut sockets, _) = setup(medium);
+
+    let local_ip_addr = Ipv4Address::new(192, 168, 1, 1);
+    let remote_ip_addr = Ipv4Address::new(192, 168, 1, 2);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x00]);
+    let spoofed_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x01]);
+
+    iface
+        .add_static_neighbor(remote_ip_addr.into(), remote_hw_addr.into(), true)
+        .unwrap();
+    assert_eq!(
+        iface.add_static_neighbor(Ipv4Address::BROADCAST.into(), remote_hw_addr.into(), true),
+        Err(crate::iface::NeighborError::Unaddressable)
+    );
+    assert_eq!(
+        iface.add_static_neighbor(
+            remote_ip_addr.into(),
+            EthernetAddress::BROADCAST.into(),
+            true
+        ),
+        Err(crate::iface::NeighborError::Unaddressable)
+    );
+
+    // A request from another hardware address is answered, but does not update the cache.
+    let repr = ArpRepr::EthernetIpv4 {
+        operation: ArpOperation::Request,
+        source_hardware_addr: spoofed_hw_addr,
+        source_protocol_addr: remote_ip_addr,
+        target_hardware_addr: EthernetAddress::default(),
+        target_protocol_addr: local_ip_addr,
+    };
+    let mut bytes = vec![0u8; 14 + repr.buffer_len()];
+    let mut frame = EthernetFrame::new_unchecked(&mut bytes[..]);
+    frame.set_dst_addr(EthernetAddress::BROADCAST);
+    frame.set_src_addr(spoofed_hw_addr);
+    frame.set_ethertype(EthernetProtocol::Arp);
+    repr.emit(&mut ArpPacket::new_unchecked(frame.payload_mut()));
+    assert!(iface
+        .inner
+        .process_ethernet(
+            &mut sockets,
+            PacketMeta::default(),
+            &bytes,
+            &mut iface.fragments
+        )
+        .is_some());
+
+    let entry = iface.neighbors().next().unwrap();
+    assert_eq!(entry.hardware_addr, remote_hw_addr.into());
+    assert_eq!(entry.state, NeighborState::Permanent);
+
+    assert!(iface.remove_neighbor(&remote_ip_addr.into()));
+    assert_eq!(iface.neighbors().next(), None);
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_announce() {
+    let mut device = crate::tests::TestingDevice::new(Medium::Ethernet);
+    let local_hw_addr = EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x02]);
+    let mut config = Config::new(local_hw_addr.into());
+    config.announce_count = 2;
+    let mut iface = Interface::new(config, &mut device, Instant::ZERO);
+
+    let addr = Ipv4Address::new(192, 168, 1, 1);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs.push(IpCidr::new(addr.into(), 24)).unwrap();
+        ip_addrs
+            .push(IpCidr::new(IpAddress::v4(127, 0, 0, 1), 8))
+            .unwrap();
+    });
+
+    // New addresses are announced with gratuitous ARP requests, except the loopback address.
+    for timestamp in [0, 2] {
+        iface.inner.now = Instant::from_secs(timestamp);
+        assert_eq!(
+            iface.poll_at(iface.inner.now, &SocketSet::new(vec![])),
+            Some(iface.inner.now)
+        );
+        assert!(iface.announce_egress(&mut device));
+        assert!(!iface.announce_egress(&mut device));
+
+        let frame = device.queue.pop_front().unwrap();
+        let frame = EthernetFrame::new_checked(&frame[..]).unwrap();
+        assert_eq!(frame.dst_addr(), EthernetAddress::BROADCAST);
+        assert_eq!(frame.ethertype(), EthernetProtocol::Arp);
+        assert_eq!(
+            ArpRepr::parse(&ArpPacket::new_checked(frame.payload()).unwrap()),
+            Ok(ArpRepr::EthernetIpv4 {
+                operation: ArpOperation::Request,
+                source_hardware_addr: local_hw_addr,
+                source_protocol_addr: addr,
+                target_hardware_addr: EthernetAddress::default(),
+                target_protocol_addr: addr,
+            })
+        );
+    }
+    iface.inner.now = Instant::from_secs(4);
+    assert!(!iface.announce_egress(&mut device));
+
+    // Changing the hardware address announces the addresses again.
+    let new_hw_addr = EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x03]);
+    iface.set_hardware_addr(new_hw_addr.into());
+    assert!(iface.announce_egress(&mut device));
+    let frame = device.queue.pop_front().unwrap();
+    let frame = EthernetFrame::new_checked(&frame[..]).unwrap();
+    assert_eq!(frame.src_addr(), new_hw_addr);
+
+    // Announcements of other nodes update the neighbors we know.
+    let remote_ip_addr = Ipv4Address::new(192, 168, 1, 2);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x00]);
+    let failover_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x01]);
+    iface.inner.neighbor_cache.fill(
+        remote_ip_addr.into(),
+        remote_hw_addr.into(),
+        iface.inner.now,
+    );
+    let repr = ArpRepr::EthernetIpv4 {
+        operation: ArpOperation::Request,
+        source_hardware_addr: failover_hw_addr,
+        source_protocol_addr: remote_ip_addr,
+        target_hardware_addr: EthernetAddress::default(),
+        target_protocol_addr: remote_ip_addr,
+    };
+    let mut bytes = vec![0u8; 14 + repr.buffer_len()];
+    let mut frame = EthernetFrame::new_unchecked(&mut bytes[..]);
+    frame.set_dst_addr(EthernetAddress::BROADCAST);
+    frame.set_src_addr(failover_hw_addr);
+    frame.set_ethertype(EthernetProtocol::Arp);
+    repr.emit(&mut ArpPacket::new_unchecked(frame.payload_mut()));
+    let frame = EthernetFrame::new_checked(&bytes[..]).unwrap();
+    assert_eq!(iface.inner.process_arp(iface.inner.now, &frame), None);
+    assert_eq!(
+        iface
+            .inner
+            .neighbor_cache
+            .lookup(&remote_ip_addr.into(), iface.inner.now),
+        NeighborAnswer::Found(failover_hw_addr.into())
+    );
+}
+
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_pending_queue(#[case] medium: Medium) {
+    let (mut iface, _, mut device) = setup(medium);
+    iface.set_pending_queue(PendingQueue::new(
+        vec![crate::iface::PendingPacket::EMPTY; 4],
+        vec![0; 1500],
+    ));
+
+    let local_ip_addr = Ipv4Address::new(192, 168, 1, 1);
+    let remote_ip_addr = Ipv4Address::new(192, 168, 1, 2);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x00]);
+
+    let icmp_repr = Icmpv4Repr::EchoRequest {
+        ident: 0x1234,
+        seq_no: 1,
+        data: b"pending",
+    };
+    let ipv4_repr = Ipv4Repr {
+        src_addr: local_ip_addr,
+        dst_addr: remote_ip_addr,
+        next_header: IpProtocol::Icmp,
+        payload_len: icmp_repr.buffer_len(),
+        hop_limit: 64,
+    };
+
+    // The packet is queued while the neighbor is discovered.
+    let tx_token = device.transmit(Instant::ZERO).unwrap();
+    assert_eq!(
+        iface.inner.dispatch_ip(
+            tx_token,
+            PacketMeta::default(),
+            Packet::new_ipv4(ipv4_repr, IpPayload::Icmpv4(icmp_repr)),
+            &mut iface.fragmenter,
+        ),
+        Ok(())
+    );
+    let arp_frame = device.queue.pop_front().unwrap();
+    let arp_frame = EthernetFrame::new_checked(&arp_frame[..]).unwrap();
+    assert_eq!(arp_frame.ethertype(), EthernetProtocol::Arp);
+    assert_eq!(iface.pending_queue().unwrap().len(), 1);
+
+    // Nothing is sent until the neighbor is discovered.
+    assert!(!iface.pending_egress(&mut device));
+    assert!(device.queue.is_empty());
+
+    iface
+        .inner
+        .neighbor_cache
+        .fill(remote_ip_addr.into(), remote_hw_addr.into(), Instant::ZERO);
+    assert!(iface.pending_egress(&mut device));
+
+    let frame = device.queue.pop_front().unwrap();
+    let frame = EthernetFrame::new_checked(&frame[..]).unwrap();
+    assert_eq!(frame.dst_addr(), remote_hw_addr);
+    assert_eq!(frame.ethertype(), EthernetProtocol::Ipv4);
+    let packet = Ipv4Packet::new_checked(frame.payload()).unwrap();
+    assert_eq!(
+        Ipv4Repr::parse(&packet, &ChecksumCapabilities::default()),
+        Ok(ipv4_repr)
+    );
+    assert_eq!(
+        Icmpv4Repr::parse(
+            &Icmpv4Packet::new_checked(packet.payload()).unwrap(),
+            &ChecksumCapabilities::default()
+        ),
+        Ok(icmp_repr)
+    );
+
+    let queue = iface.pending_queue().unwrap();
+    assert!(queue.is_empty());
+    assert_eq!(
+        queue.stats(),
+        crate::iface::PendingStats {
+            queued: 1,
+            sent: 1,
+            dropped: 0,
+        }
+    );
+
+    // A packet that does not fit in the queue is dropped.
+    iface.set_pending_queue(PendingQueue::new(
+        vec![crate::iface::PendingPacket::EMPTY; 4],
+        vec![0; 16],
+    ));
+    iface.inner.neighbor_cache.flush();
+    let tx_token = device.transmit(Instant::ZERO).unwrap();
+    assert_eq!(
+        iface.inner.dispatch_ip(
+            tx_token,
+            PacketMeta::default(),
+            Packet::new_ipv4(ipv4_repr, IpPayload::Icmpv4(icmp_repr)),
+            &mut iface.fragmenter,
+        ),
+        Err(DispatchError::NeighborPending)
+    );
+    assert!(iface.pending_queue().unwrap().is_empty());
+}
+
 #[rstest]
 #[case(Medium::Ethernet)]
 #[cfg(feature = "medium-ethernet")]
@@ -657,41 +1020,97 @@ fn test_icmpv4_socket(#[case] medium: Medium) {
     );
 }
 
+/// Receive the IGMP packets sent by the interface, as their IPv4 header and IGMP bytes.
+#[cfg(all(
+    feature = "proto-igmp",
+    feature = "medium-ip",
+    feature = "medium-ethernet"
+))]
+fn recv_igmp(
+    device: &mut crate::tests::TestingDevice,
+    timestamp: Instant,
+) -> Vec<(Ipv4Repr, Vec<u8>)> {
+    let caps = device.capabilities();
+    let checksum_caps = &caps.checksum;
+    recv_all(device, timestamp)
+        .iter()
+        .filter_map(|frame| {
+            let ipv4_packet = match caps.medium {
+                #[cfg(feature = "medium-ethernet")]
+                Medium::Ethernet => {
+                    let eth_frame = EthernetFrame::new_checked(frame).ok()?;
+                    Ipv4Packet::new_checked(eth_frame.payload()).ok()?
+                }
+                #[cfg(feature = "medium-ip")]
+                Medium::Ip => Ipv4Packet::new_checked(&frame[..]).ok()?,
+                #[cfg(feature = "medium-ieee802154")]
+                Medium::Ieee802154 => todo!(),
+            };
+            let ipv4_repr = Ipv4Repr::parse(&ipv4_packet, checksum_caps).ok()?;
+            if ipv4_repr.next_header != IpProtocol::Igmp {
+                return None;
+            }
+            Some((ipv4_repr, ipv4_packet.payload().to_vec()))
+        })
+        .collect::<Vec<_>>()
+}
+
+/// Parse the group records of an IGMPv3 Membership Report.
+#[cfg(all(
+    feature = "proto-igmp",
+    feature = "medium-ip",
+    feature = "medium-ethernet"
+))]
+fn igmpv3_records(payload: &[u8]) -> Vec<(IgmpRecordType, Ipv4Address, Vec<Ipv4Address>)> {
+    let packet = IgmpPacket::new_checked(payload).unwrap();
+    let IgmpRepr::MembershipReportV3 {
+        num_group_records,
+        mut data,
+    } = IgmpRepr::parse(&packet).unwrap()
+    else {
+        panic!("expected an IGMPv3 report");
+    };
+
+    let mut records = Vec::new();
+    for _ in 0..num_group_records {
+        let record = IgmpGroupRecord::new_checked(data).unwrap();
+        let repr = IgmpGroupRecordRepr::parse(&record).unwrap();
+        let sources = repr
+            .payload
+            .chunks(4)
+            .map(Ipv4Address::from_bytes)
+            .collect();
+        records.push((repr.record_type, repr.group_addr, sources));
+        data = &data[record.record_len()..];
+    }
+    records
+}
+
+/// Feed an IGMP packet from a querier on the link to the interface.
+#[cfg(all(
+    feature = "proto-igmp",
+    feature = "medium-ip",
+    feature = "medium-ethernet"
+))]
+fn process_igmp(iface: &mut Interface, dst_addr: Ipv4Address, igmp_repr: &IgmpRepr) {
+    let mut bytes = vec![0; igmp_repr.buffer_len()];
+    igmp_repr.emit(&mut IgmpPacket::new_unchecked(&mut bytes[..]));
+    let ipv4_repr = Ipv4Repr {
+        src_addr: Ipv4Address::new(192, 168, 1, 254),
+        dst_addr,
+        next_header: IpProtocol::Igmp,
+        payload_len: bytes.len(),
+        hop_limit: 1,
+    };
+    assert_eq!(iface.inner.process_igmp(ipv4_repr, &bytes), None);
+}
+
 #[rstest]
 #[case(Medium::Ip)]
 #[cfg(all(feature = "proto-igmp", feature = "medium-ip"))]
 #[case(Medium::Ethernet)]
 #[cfg(all(feature = "proto-igmp", feature = "medium-ethernet"))]
 fn test_handle_igmp(#[case] medium: Medium) {
-    fn recv_igmp(
-        device: &mut crate::tests::TestingDevice,
-        timestamp: Instant,
-    ) -> Vec<(Ipv4Repr, IgmpRepr)> {
-        let caps = device.capabilities();
-        let checksum_caps = &caps.checksum;
-        recv_all(device, timestamp)
-            .iter()
-            .filter_map(|frame| {
-                let ipv4_packet = match caps.medium {
-                    #[cfg(feature = "medium-ethernet")]
-                    Medium::Ethernet => {
-                        let eth_frame = EthernetFrame::new_checked(frame).ok()?;
-                        Ipv4Packet::new_checked(eth_frame.payload()).ok()?
-                    }
-                    #[cfg(feature = "medium-ip")]
-                    Medium::Ip => Ipv4Packet::new_checked(&frame[..]).ok()?,
-                    #[cfg(feature = "medium-ieee802154")]
-                    Medium::Ieee802154 => todo!(),
-                };
-                let ipv4_repr = Ipv4Repr::parse(&ipv4_packet, checksum_caps).ok()?;
-                let ip_payload = ipv4_packet.payload();
-                let igmp_packet = IgmpPacket::new_checked(ip_payload).ok()?;
-                let igmp_repr = IgmpRepr::parse(&igmp_packet).ok()?;
-                Some((ipv4_repr, igmp_repr))
-            })
-            .collect::<Vec<_>>()
-    }
-
     let groups = [
         Ipv4Address::new(224, 0, 0, 22),
         Ipv4Address::new(224, 0, 0, 56),
@@ -710,14 +1129,13 @@ fn test_handle_igmp(#[case] medium: Medium) {
     let reports = recv_igmp(&mut device, timestamp);
     assert_eq!(reports.len(), 2);
     for (i, group_addr) in groups.iter().enumerate() {
-        assert_eq!(reports[i].0.next_header, IpProtocol::Igmp);
-        assert_eq!(reports[i].0.dst_addr, *group_addr);
         assert_eq!(
-            reports[i].1,
-            IgmpRepr::MembershipReport {
-                group_addr: *group_addr,
-                version: IgmpVersion::Version2,
-            }
+            reports[i].0.dst_addr,
+            Ipv4Address::MULTICAST_ALL_IGMPV3_ROUTERS
+        );
+        assert_eq!(
+            igmpv3_records(&reports[i].1),
+            vec![(IgmpRecordType::ChangeToExclude, *group_addr, vec![])]
         );
     }
 
@@ -740,7 +1158,7 @@ fn test_handle_igmp(#[case] medium: Medium) {
     // loopback have been processed, including responses to
     // GENERAL_QUERY_BYTES. Therefore `recv_all()` would return 0
     // pkts that could be checked.
-    iface.socket_ingress(&mut device, &mut sockets);
+    iface.socket_ingress(&mut device, &mut sockets, |_, packet| Some(packet));
 
     // Leave multicast groups
     let timestamp = Instant::ZERO;
@@ -750,65 +1168,94 @@ fn test_handle_igmp(#[case] medium: Medium) {
             .unwrap();
     }
 
+    // The query was an IGMPv3 one, so the leaves are IGMPv3 reports as well.
     let leaves = recv_igmp(&mut device, timestamp);
     assert_eq!(leaves.len(), 2);
     for (i, group_addr) in groups.iter().cloned().enumerate() {
-        assert_eq!(leaves[i].0.next_header, IpProtocol::Igmp);
-        assert_eq!(leaves[i].0.dst_addr, Ipv4Address::MULTICAST_ALL_ROUTERS);
-        assert_eq!(leaves[i].1, IgmpRepr::LeaveGroup { group_addr });
+        assert_eq!(
+            leaves[i].0.dst_addr,
+            Ipv4Address::MULTICAST_ALL_IGMPV3_ROUTERS
+        );
+        assert_eq!(
+            igmpv3_records(&leaves[i].1),
+            vec![(IgmpRecordType::ChangeToInclude, group_addr, vec![])]
+        );
     }
 }
 
 #[rstest]
 #[case(Medium::Ip)]
-#[cfg(all(feature = "socket-raw", feature = "medium-ip"))]
+#[cfg(all(feature = "proto-igmp", feature = "socket-udp", feature = "medium-ip"))]
 #[case(Medium::Ethernet)]
-#[cfg(all(feature = "socket-raw", feature = "medium-ethernet"))]
-fn test_raw_socket_no_reply(#[case] medium: Medium) {
-    use crate::wire::{IpVersion, UdpPacket, UdpRepr};
+#[cfg(all(
+    feature = "proto-igmp",
+    feature = "socket-udp",
+    feature = "medium-ethernet"
+))]
+fn test_igmpv3_source_specific_join(#[case] medium: Medium) {
+    use crate::socket::udp;
+    use crate::wire::{IpEndpoint, UdpPacket, UdpRepr};
 
-    let (mut iface, mut sockets, _) = setup(medium);
+    static UDP_PAYLOAD: [u8; 5] = [0x48, 0x65, 0x6c, 0x6c, 0x6f];
 
-    let packets = 1;
-    let rx_buffer =
-        raw::PacketBuffer::new(vec![raw::PacketMetadata::EMPTY; packets], vec![0; 48 * 1]);
-    let tx_buffer = raw::PacketBuffer::new(
-        vec![raw::PacketMetadata::EMPTY; packets],
-        vec![0; 48 * packets],
-    );
-    let raw_socket = raw::Socket::new(IpVersion::Ipv4, IpProtocol::Udp, rx_buffer, tx_buffer);
-    sockets.add(raw_socket);
+    let group = Ipv4Address::new(232, 1, 1, 1);
+    let allowed = Ipv4Address::new(10, 0, 0, 1);
+    let blocked = Ipv4Address::new(10, 0, 0, 2);
 
-    let src_addr = Ipv4Address([127, 0, 0, 2]);
-    let dst_addr = Ipv4Address([127, 0, 0, 1]);
+    let (mut iface, mut sockets, mut device) = setup(medium);
 
-    const PAYLOAD_LEN: usize = 10;
+    let udp_rx_buffer = udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 2], vec![0; 30]);
+    let udp_tx_buffer = udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 15]);
+    let udp_socket = udp::Socket::new(udp_rx_buffer, udp_tx_buffer);
+    let udp_socket_handle = sockets.add(udp_socket);
+    let socket = sockets.get_mut::<udp::Socket>(udp_socket_handle);
+    assert_eq!(socket.bind(5004), Ok(()));
 
-    let udp_repr = UdpRepr {
-        src_port: 67,
-        dst_port: 68,
-    };
-    let mut bytes = vec![0xff; udp_repr.header_len() + PAYLOAD_LEN];
-    let mut packet = UdpPacket::new_unchecked(&mut bytes[..]);
-    udp_repr.emit(
-        &mut packet,
-        &src_addr.into(),
-        &dst_addr.into(),
-        PAYLOAD_LEN,
-        |buf| fill_slice(buf, 0x2a),
-        &ChecksumCapabilities::default(),
+    let timestamp = Instant::ZERO;
+    assert_eq!(
+        iface.join_multicast_group_with_sources(
+            &mut device,
+            group,
+            MulticastFilterMode::Include,
+            &[allowed.into()],
+            timestamp,
+        ),
+        Ok(true)
     );
-    let ipv4_repr = Ipv4Repr {
-        src_addr,
-        dst_addr,
-        next_header: IpProtocol::Udp,
-        hop_limit: 64,
-        payload_len: udp_repr.header_len() + PAYLOAD_LEN,
-    };
 
-    // Emit to frame
-    let mut bytes = vec![0u8; ipv4_repr.buffer_len() + udp_repr.header_len() + PAYLOAD_LEN];
-    let frame = {
+    let reports = recv_igmp(&mut device, timestamp);
+    assert_eq!(reports.len(), 1);
+    assert_eq!(
+        igmpv3_records(&reports[0].1),
+        vec![(IgmpRecordType::AllowNewSources, group, vec![allowed])]
+    );
+
+    // Joining again with the same filter does not report anything.
+    assert_eq!(
+        iface.join_multicast_group_with_sources(
+            &mut device,
+            group,
+            MulticastFilterMode::Include,
+            &[allowed.into()],
+            timestamp,
+        ),
+        Ok(false)
+    );
+    assert!(recv_igmp(&mut device, timestamp).is_empty());
+
+    for src_addr in [blocked, allowed] {
+        let udp_repr = UdpRepr {
+            src_port: 5004,
+            dst_port: 5004,
+        };
+        let ipv4_repr = Ipv4Repr {
+            src_addr,
+            dst_addr: group,
+            next_header: IpProtocol::Udp,
+            hop_limit: 64,
+            payload_len: udp_repr.header_len() + UDP_PAYLOAD.len(),
+        };
+        let mut bytes = vec![0u8; ipv4_repr.buffer_len() + ipv4_repr.payload_len];
         ipv4_repr.emit(
             &mut Ipv4Packet::new_unchecked(&mut bytes),
             &ChecksumCapabilities::default(),
@@ -816,15 +1263,230 @@ fn test_raw_socket_no_reply(#[case] medium: Medium) {
         udp_repr.emit(
             &mut UdpPacket::new_unchecked(&mut bytes[ipv4_repr.buffer_len()..]),
             &src_addr.into(),
-            &dst_addr.into(),
-            PAYLOAD_LEN,
-            |buf| fill_slice(buf, 0x2a),
+            &group.into(),
+            UDP_PAYLOAD.len(),
+            |buf| buf.copy_from_slice(&UDP_PAYLOAD),
             &ChecksumCapabilities::default(),
         );
-        Ipv4Packet::new_unchecked(&bytes[..])
-    };
-
-    assert_eq!(
+        let frame = Ipv4Packet::new_unchecked(&bytes[..]);
+        assert_eq!(
+            iface.inner.process_ipv4(
+                &mut sockets,
+                PacketMeta::default(),
+                &frame,
+                &mut iface.fragments
+            ),
+            None
+        );
+    }
+
+    // Only the datagram from the subscribed source is delivered.
+    let socket = sockets.get_mut::<udp::Socket>(udp_socket_handle);
+    assert_eq!(
+        socket.recv(),
+        Ok((
+            &UDP_PAYLOAD[..],
+            udp::UdpMetadata {
+                local_address: Some(group.into()),
+                ..IpEndpoint::new(allowed.into(), 5004).into()
+            }
+        ))
+    );
+    assert!(!socket.can_recv());
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(all(feature = "proto-igmp", feature = "medium-ip"))]
+#[case(Medium::Ethernet)]
+#[cfg(all(feature = "proto-igmp", feature = "medium-ethernet"))]
+fn test_igmpv3_general_query(#[case] medium: Medium) {
+    let group = Ipv4Address::new(232, 1, 1, 1);
+    let source = Ipv4Address::new(10, 0, 0, 1);
+
+    let (mut iface, _, mut device) = setup(medium);
+
+    iface
+        .join_multicast_group_with_sources(
+            &mut device,
+            group,
+            MulticastFilterMode::Exclude,
+            &[source.into()],
+            Instant::ZERO,
+        )
+        .unwrap();
+    let reports = recv_igmp(&mut device, Instant::ZERO);
+    assert_eq!(
+        igmpv3_records(&reports[0].1),
+        vec![(IgmpRecordType::ChangeToExclude, group, vec![source])]
+    );
+
+    // The State Change Report is retransmitted once within a second.
+    iface.inner.now = Instant::from_secs(1);
+    assert!(iface.igmpv3_egress(&mut device));
+    assert!(!iface.igmpv3_egress(&mut device));
+    let reports = recv_igmp(&mut device, iface.inner.now);
+    assert_eq!(reports.len(), 1);
+    assert_eq!(
+        igmpv3_records(&reports[0].1),
+        vec![(IgmpRecordType::ChangeToExclude, group, vec![source])]
+    );
+
+    process_igmp(
+        &mut iface,
+        Ipv4Address::MULTICAST_ALL_SYSTEMS,
+        &IgmpRepr::MembershipQueryV3 {
+            max_resp_time: Duration::from_secs(1),
+            group_addr: Ipv4Address::UNSPECIFIED,
+            s_flag: false,
+            qrv: 2,
+            qqic: 125,
+            num_srcs: 0,
+            data: &[],
+        },
+    );
+
+    // The current state of all groups is reported within the Max Response Time.
+    iface.inner.now = Instant::from_secs(2);
+    assert!(iface.igmpv3_egress(&mut device));
+    let reports = recv_igmp(&mut device, iface.inner.now);
+    assert_eq!(reports.len(), 1);
+    assert_eq!(
+        igmpv3_records(&reports[0].1),
+        vec![(IgmpRecordType::ModeIsExclude, group, vec![source])]
+    );
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(all(feature = "proto-igmp", feature = "medium-ip"))]
+#[case(Medium::Ethernet)]
+#[cfg(all(feature = "proto-igmp", feature = "medium-ethernet"))]
+fn test_igmpv2_compatibility_mode(#[case] medium: Medium) {
+    let group = Ipv4Address::new(224, 0, 0, 56);
+
+    let (mut iface, _, mut device) = setup(medium);
+
+    process_igmp(
+        &mut iface,
+        Ipv4Address::MULTICAST_ALL_SYSTEMS,
+        &IgmpRepr::MembershipQuery {
+            max_resp_time: Duration::from_secs(10),
+            group_addr: Ipv4Address::UNSPECIFIED,
+            version: IgmpVersion::Version2,
+        },
+    );
+
+    // An IGMPv2 querier is present, so joins are reported with IGMPv2.
+    iface
+        .join_multicast_group(&mut device, group, Instant::ZERO)
+        .unwrap();
+    let reports = recv_igmp(&mut device, Instant::ZERO);
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].0.dst_addr, group);
+    let packet = IgmpPacket::new_checked(&reports[0].1[..]).unwrap();
+    assert_eq!(
+        IgmpRepr::parse(&packet).unwrap(),
+        IgmpRepr::MembershipReport {
+            group_addr: group,
+            version: IgmpVersion::Version2,
+        }
+    );
+
+    // Source filters cannot be expressed with IGMPv2.
+    assert_eq!(
+        iface.join_multicast_group_with_sources(
+            &mut device,
+            Ipv4Address::new(232, 1, 1, 1),
+            MulticastFilterMode::Include,
+            &[Ipv4Address::new(10, 0, 0, 1).into()],
+            Instant::ZERO,
+        ),
+        Ok(true)
+    );
+    let reports = recv_igmp(&mut device, Instant::ZERO);
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].0.dst_addr, Ipv4Address::new(232, 1, 1, 1));
+
+    // Once the Older Version Querier Present Timeout has expired, IGMPv3 is used again.
+    iface.inner.now = Instant::from_secs(261);
+    iface
+        .leave_multicast_group(&mut device, group, iface.inner.now)
+        .unwrap();
+    let reports = recv_igmp(&mut device, iface.inner.now);
+    assert_eq!(reports.len(), 1);
+    assert_eq!(
+        igmpv3_records(&reports[0].1),
+        vec![(IgmpRecordType::ChangeToInclude, group, vec![])]
+    );
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(all(feature = "socket-raw", feature = "medium-ip"))]
+#[case(Medium::Ethernet)]
+#[cfg(all(feature = "socket-raw", feature = "medium-ethernet"))]
+fn test_raw_socket_no_reply(#[case] medium: Medium) {
+    use crate::wire::{IpVersion, UdpPacket, UdpRepr};
+
+    let (mut iface, mut sockets, _) = setup(medium);
+
+    let packets = 1;
+    let rx_buffer =
+        raw::PacketBuffer::new(vec![raw::PacketMetadata::EMPTY; packets], vec![0; 48 * 1]);
+    let tx_buffer = raw::PacketBuffer::new(
+        vec![raw::PacketMetadata::EMPTY; packets],
+        vec![0; 48 * packets],
+    );
+    let raw_socket = raw::Socket::new(IpVersion::Ipv4, IpProtocol::Udp, rx_buffer, tx_buffer);
+    sockets.add(raw_socket);
+
+    let src_addr = Ipv4Address([127, 0, 0, 2]);
+    let dst_addr = Ipv4Address([127, 0, 0, 1]);
+
+    const PAYLOAD_LEN: usize = 10;
+
+    let udp_repr = UdpRepr {
+        src_port: 67,
+        dst_port: 68,
+    };
+    let mut bytes = vec![0xff; udp_repr.header_len() + PAYLOAD_LEN];
+    let mut packet = UdpPacket::new_unchecked(&mut bytes[..]);
+    udp_repr.emit(
+        &mut packet,
+        &src_addr.into(),
+        &dst_addr.into(),
+        PAYLOAD_LEN,
+        |buf| fill_slice(buf, 0x2a),
+        &ChecksumCapabilities::default(),
+    );
+    let ipv4_repr = Ipv4Repr {
+        src_addr,
+        dst_addr,
+        next_header: IpProtocol::Udp,
+        hop_limit: 64,
+        payload_len: udp_repr.header_len() + PAYLOAD_LEN,
+    };
+
+    // Emit to frame
+    let mut bytes = vec![0u8; ipv4_repr.buffer_len() + udp_repr.header_len() + PAYLOAD_LEN];
+    let frame = {
+        ipv4_repr.emit(
+            &mut Ipv4Packet::new_unchecked(&mut bytes),
+            &ChecksumCapabilities::default(),
+        );
+        udp_repr.emit(
+            &mut UdpPacket::new_unchecked(&mut bytes[ipv4_repr.buffer_len()..]),
+            &src_addr.into(),
+            &dst_addr.into(),
+            PAYLOAD_LEN,
+            |buf| fill_slice(buf, 0x2a),
+            &ChecksumCapabilities::default(),
+        );
+        Ipv4Packet::new_unchecked(&bytes[..])
+    };
+
+    assert_eq!(
         iface.inner.process_ipv4(
             &mut sockets,
             PacketMeta::default(),
@@ -1019,3 +1681,1255 @@ fn test_icmp_reply_size(#[case] medium: Medium) {
         ))
     );
 }
+
+#[rstest]
+#[case::ip(Medium::Ip)]
+#[cfg(all(feature = "medium-ip", feature = "proto-ipv4-fragmentation"))]
+#[case::ethernet(Medium::Ethernet)]
+#[cfg(all(feature = "medium-ethernet", feature = "proto-ipv4-fragmentation"))]
+#[cfg(feature = "socket-udp")]
+fn test_icmp_frag_required(#[case] medium: Medium) {
+    let (mut iface, mut sockets, mut device) = setup(medium);
+
+    let src_addr = Ipv4Address([192, 168, 1, 1]);
+    let dst_addr = Ipv4Address([192, 168, 1, 2]);
+    let router_addr = Ipv4Address([192, 168, 1, 254]);
+
+    #[cfg(feature = "medium-ethernet")]
+    if medium == Medium::Ethernet {
+        iface.inner.neighbor_cache.fill(
+            dst_addr.into(),
+            HardwareAddress::Ethernet(EthernetAddress([0x02, 0, 0, 0, 0, 1])),
+            Instant::ZERO,
+        );
+    }
+
+    let payload = [0x2a; 1172];
+    let udp_repr = UdpRepr {
+        src_port: 67,
+        dst_port: 68,
+    };
+    let ip_repr = Ipv4Repr {
+        src_addr,
+        dst_addr,
+        next_header: IpProtocol::Udp,
+        payload_len: udp_repr.header_len() + payload.len(),
+        hop_limit: 64,
+    };
+
+    // A router on the path reports that the packet is too big.
+    let icmp_repr = Icmpv4Repr::DstUnreachable {
+        reason: Icmpv4DstUnreachable::FragRequired,
+        header: ip_repr,
+        data: &payload[..8],
+    };
+    let mut bytes = vec![0; icmp_repr.buffer_len()];
+    let mut packet = Icmpv4Packet::new_unchecked(&mut bytes[..]);
+    icmp_repr.emit(&mut packet, &ChecksumCapabilities::default());
+    packet.set_next_hop_mtu(1000);
+    packet.fill_checksum();
+
+    let icmp_ip_repr = Ipv4Repr {
+        src_addr: router_addr,
+        dst_addr: src_addr,
+        next_header: IpProtocol::Icmp,
+        payload_len: bytes.len(),
+        hop_limit: 64,
+    };
+
+    assert_eq!(iface.path_mtu(dst_addr.into()), 1500);
+    assert_eq!(
+        iface
+            .inner
+            .process_icmpv4(&mut sockets, icmp_ip_repr, &bytes),
+        None
+    );
+    assert_eq!(iface.path_mtu(dst_addr.into()), 1000);
+    assert_eq!(iface.path_mtu(router_addr.into()), 1500);
+
+    // The packet is now fragmented to the path MTU.
+    let tx_token = device.transmit(Instant::ZERO).unwrap();
+    iface
+        .inner
+        .dispatch_ip(
+            tx_token,
+            PacketMeta::default(),
+            Packet::new_ipv4(ip_repr, IpPayload::Udp(udp_repr, &payload)),
+            &mut iface.fragmenter,
+        )
+        .unwrap();
+    assert!(iface.ipv4_egress(&mut device));
+    assert!(!iface.ipv4_egress(&mut device));
+
+    let mut frag_lens = std::vec::Vec::new();
+    for frame in device.queue.iter() {
+        let packet = match medium {
+            #[cfg(feature = "medium-ethernet")]
+            Medium::Ethernet => &frame[EthernetFrame::<&[u8]>::header_len()..],
+            _ => &frame[..],
+        };
+        let packet = Ipv4Packet::new_checked(packet).unwrap();
+        frag_lens.push((
+            packet.frag_offset(),
+            packet.total_len(),
+            packet.more_frags(),
+        ));
+    }
+    assert_eq!(frag_lens, vec![(0, 996, true), (976, 224, false)]);
+
+    // The estimate ages, such that a larger path MTU is tried again.
+    iface.inner.now = Instant::from_secs(10 * 60);
+    assert_eq!(iface.path_mtu(dst_addr.into()), 1500);
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+#[cfg(feature = "socket-udp")]
+fn test_icmp_error_to_udp_socket(#[case] medium: Medium) {
+    use crate::socket::udp;
+
+    let (mut iface, mut sockets, _device) = setup(medium);
+
+    let rx_buffer = udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 15]);
+    let tx_buffer = udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 15]);
+    let socket_handle = sockets.add(udp::Socket::new(rx_buffer, tx_buffer));
+    let socket = sockets.get_mut::<udp::Socket>(socket_handle);
+    assert_eq!(socket.bind(67), Ok(()));
+    assert_eq!(socket.last_error(), None);
+
+    let local_addr = Ipv4Address([192, 168, 1, 1]);
+    let remote_addr = Ipv4Address([192, 168, 1, 2]);
+    let router_addr = Ipv4Address([192, 168, 1, 254]);
+
+    let udp_repr = UdpRepr {
+        src_port: 67,
+        dst_port: 68,
+    };
+    let mut udp_bytes = vec![0; udp_repr.header_len()];
+    udp_repr.emit(
+        &mut UdpPacket::new_unchecked(&mut udp_bytes[..]),
+        &local_addr.into(),
+        &remote_addr.into(),
+        0,
+        |_| {},
+        &ChecksumCapabilities::default(),
+    );
+
+    let mut icmp_error = |reason, src_addr| {
+        let icmp_repr = Icmpv4Repr::DstUnreachable {
+            reason,
+            header: Ipv4Repr {
+                src_addr: local_addr,
+                dst_addr: remote_addr,
+                next_header: IpProtocol::Udp,
+                payload_len: udp_bytes.len(),
+                hop_limit: 64,
+            },
+            data: &udp_bytes,
+        };
+        let mut bytes = vec![0; icmp_repr.buffer_len()];
+        icmp_repr.emit(
+            &mut Icmpv4Packet::new_unchecked(&mut bytes[..]),
+            &ChecksumCapabilities::default(),
+        );
+        let ip_repr = Ipv4Repr {
+            src_addr,
+            dst_addr: local_addr,
+            next_header: IpProtocol::Icmp,
+            payload_len: bytes.len(),
+            hop_limit: 64,
+        };
+        assert_eq!(
+            iface.inner.process_icmpv4(&mut sockets, ip_repr, &bytes),
+            None
+        );
+        sockets.get_mut::<udp::Socket>(socket_handle).last_error()
+    };
+
+    let remote_endpoint = IpEndpoint::new(remote_addr.into(), 68);
+    assert_eq!(
+        icmp_error(Icmpv4DstUnreachable::HostUnreachable, router_addr),
+        Some(udp::IcmpError::Unreachable(remote_endpoint))
+    );
+    assert_eq!(
+        icmp_error(Icmpv4DstUnreachable::PortUnreachable, remote_addr),
+        Some(udp::IcmpError::PortUnreachable(remote_endpoint))
+    );
+
+    let socket = sockets.get_mut::<udp::Socket>(socket_handle);
+    socket.close();
+    assert_eq!(socket.last_error(), None);
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_forward(#[case] medium: Medium) {
+    static PAYLOAD: [u8; 8] = [0x04, 0xd2, 0x16, 0x2e, 0x00, 0x08, 0x00, 0x00];
+
+    let (mut iface, mut sockets, _) = setup(medium);
+
+    let src_addr = Ipv4Address::new(192, 168, 1, 2);
+    let ipv4_repr = |dst_addr, hop_limit| Ipv4Repr {
+        src_addr,
+        dst_addr,
+        next_header: IpProtocol::Udp,
+        payload_len: PAYLOAD.len(),
+        hop_limit,
+    };
+    let ipv4_packet = |ipv4_repr: Ipv4Repr| {
+        let mut bytes = vec![0; ipv4_repr.buffer_len() + PAYLOAD.len()];
+        ipv4_repr.emit(
+            &mut Ipv4Packet::new_unchecked(&mut bytes[..]),
+            &ChecksumCapabilities::default(),
+        );
+        bytes[ipv4_repr.buffer_len()..].copy_from_slice(&PAYLOAD);
+        bytes
+    };
+
+    let dst_addr = Ipv4Address::new(10, 0, 0, 2);
+    let bytes = ipv4_packet(ipv4_repr(dst_addr, 64));
+
+    // Packets that are not addressed to us are dropped, unless forwarding is enabled.
+    assert_eq!(
+        iface.inner.process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv4Packet::new_unchecked(&bytes[..]),
+            &mut iface.fragments
+        ),
+        None
+    );
+
+    iface.set_forwarding(true);
+    assert_eq!(
+        iface.inner.process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv4Packet::new_unchecked(&bytes[..]),
+            &mut iface.fragments
+        ),
+        Some(Packet::new_ipv4(
+            ipv4_repr(dst_addr, 63),
+            IpPayload::Forward(IpProtocol::Udp, &PAYLOAD)
+        ))
+    );
+
+    // The source is told when the TTL expires.
+    let bytes = ipv4_packet(ipv4_repr(dst_addr, 1));
+    let icmp_repr = Icmpv4Repr::TimeExceeded {
+        reason: Icmpv4TimeExceeded::TtlExpired,
+        header: ipv4_repr(dst_addr, 1),
+        data: &PAYLOAD,
+    };
+    assert_eq!(
+        iface.inner.process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv4Packet::new_unchecked(&bytes[..]),
+            &mut iface.fragments
+        ),
+        Some(Packet::new_ipv4(
+            Ipv4Repr {
+                src_addr: Ipv4Address::new(192, 168, 1, 1),
+                dst_addr: src_addr,
+                next_header: IpProtocol::Icmp,
+                payload_len: icmp_repr.buffer_len(),
+                hop_limit: 64,
+            },
+            IpPayload::Icmpv4(icmp_repr)
+        ))
+    );
+
+    // Link-local destinations are not forwarded.
+    let bytes = ipv4_packet(ipv4_repr(Ipv4Address::new(169, 254, 1, 1), 64));
+    assert_eq!(
+        iface.inner.process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv4Packet::new_unchecked(&bytes[..]),
+            &mut iface.fragments
+        ),
+        None
+    );
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_forward_too_big(#[case] medium: Medium) {
+    static SMALL_PAYLOAD: [u8; 8] = [0x04, 0xd2, 0x16, 0x2e, 0x00, 0x08, 0x00, 0x00];
+    let mut payload = [0x2a; 100];
+    payload[..8].copy_from_slice(&[0x04, 0xd2, 0x16, 0x2e, 0x00, 0x64, 0x00, 0x00]);
+
+    let (mut iface, mut sockets, mut device) = setup(medium);
+    iface.set_forwarding(true);
+
+    let router = Ipv4Address::new(192, 168, 1, 254);
+    iface
+        .routes_mut()
+        .add_route(
+            Route::new(IpCidr::new(IpAddress::v4(10, 0, 0, 0), 24), router.into()).with_mtu(100),
+        )
+        .unwrap();
+    #[cfg(feature = "medium-ethernet")]
+    if medium == Medium::Ethernet {
+        iface.inner.neighbor_cache.fill(
+            router.into(),
+            HardwareAddress::Ethernet(EthernetAddress([0x02, 0, 0, 0, 0, 1])),
+            Instant::ZERO,
+        );
+    }
+
+    let src_addr = Ipv4Address::new(192, 168, 1, 2);
+    let dst_addr = Ipv4Address::new(10, 0, 0, 2);
+    let ipv4_repr = |payload: &[u8], hop_limit| Ipv4Repr {
+        src_addr,
+        dst_addr,
+        next_header: IpProtocol::Udp,
+        payload_len: payload.len(),
+        hop_limit,
+    };
+    let ipv4_packet = |payload: &[u8], dont_frag| {
+        let ipv4_repr = ipv4_repr(payload, 64);
+        let mut bytes = vec![0; ipv4_repr.buffer_len() + payload.len()];
+        let mut packet = Ipv4Packet::new_unchecked(&mut bytes[..]);
+        ipv4_repr.emit(&mut packet, &ChecksumCapabilities::default());
+        packet.set_dont_frag(dont_frag);
+        packet.fill_checksum();
+        bytes[ipv4_repr.buffer_len()..].copy_from_slice(payload);
+        bytes
+    };
+
+    // A packet with the Don't Fragment flag that does not fit in the MTU of the route is
+    // dropped, and the source is told the MTU.
+    let bytes = ipv4_packet(&payload, true);
+    let packet = iface
+        .inner
+        .process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv4Packet::new_unchecked(&bytes[..]),
+            &mut iface.fragments,
+        )
+        .unwrap();
+    let icmp_repr = Icmpv4Repr::FragRequired {
+        mtu: 100,
+        header: ipv4_repr(&payload, 64),
+        data: &payload,
+    };
+    assert_eq!(
+        forward::forward_packet(&iface.inner, packet, &mut |_, packet| Some(packet)),
+        Some(Packet::new_ipv4(
+            Ipv4Repr {
+                src_addr: Ipv4Address::new(192, 168, 1, 1),
+                dst_addr: src_addr,
+                next_header: IpProtocol::Icmp,
+                payload_len: icmp_repr.buffer_len(),
+                hop_limit: 64,
+            },
+            IpPayload::Icmpv4(icmp_repr)
+        ))
+    );
+
+    // Without the flag, the packet is forwarded to be fragmented.
+    let bytes = ipv4_packet(&payload, false);
+    let packet = iface
+        .inner
+        .process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv4Packet::new_unchecked(&bytes[..]),
+            &mut iface.fragments,
+        )
+        .unwrap();
+    let mut expected = Packet::new_ipv4(
+        ipv4_repr(&payload, 63),
+        IpPayload::Forward(IpProtocol::Udp, &payload),
+    );
+    expected.set_dont_frag(false);
+    assert_eq!(
+        forward::forward_packet(&iface.inner, packet, &mut |_, packet| Some(packet)),
+        Some(expected)
+    );
+
+    // A forwarded packet that fits keeps the flag it was received with.
+    for dont_frag in [false, true] {
+        let bytes = ipv4_packet(&SMALL_PAYLOAD, dont_frag);
+        let packet = iface
+            .inner
+            .process_ipv4(
+                &mut sockets,
+                PacketMeta::default(),
+                &Ipv4Packet::new_unchecked(&bytes[..]),
+                &mut iface.fragments,
+            )
+            .unwrap();
+        let packet =
+            forward::forward_packet(&iface.inner, packet, &mut |_, packet| Some(packet)).unwrap();
+
+        let tx_token = device.transmit(Instant::ZERO).unwrap();
+        iface
+            .inner
+            .dispatch_ip(
+                tx_token,
+                PacketMeta::default(),
+                packet,
+                &mut iface.fragmenter,
+            )
+            .unwrap();
+        let frame = device.queue.pop_front().unwrap();
+        let frame = match medium {
+            #[cfg(feature = "medium-ethernet")]
+            Medium::Ethernet => &frame[EthernetFrame::<&[u8]>::header_len()..],
+            _ => &frame[..],
+        };
+        let packet = Ipv4Packet::new_checked(frame).unwrap();
+        assert_eq!(packet.dont_frag(), dont_frag);
+        assert_eq!(packet.hop_limit(), 63);
+        assert!(packet.verify_checksum());
+    }
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_route_mtu_minimum(#[case] medium: Medium) {
+    let (mut iface, _sockets, _device) = setup(medium);
+
+    iface
+        .routes_mut()
+        .add_route(
+            Route::new(
+                IpCidr::new(IpAddress::v4(10, 0, 0, 0), 8),
+                IpAddress::v4(192, 168, 1, 254),
+            )
+            .with_mtu(30),
+        )
+        .unwrap();
+
+    // A route MTU smaller than the minimum IPv4 MTU is raised to it.
+    assert_eq!(iface.path_mtu(IpAddress::v4(10, 0, 0, 2)), 68);
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_route_kind(#[case] medium: Medium) {
+    static PAYLOAD: [u8; 8] = [0x04, 0xd2, 0x16, 0x2e, 0x00, 0x08, 0x00, 0x00];
+
+    let (mut iface, mut sockets, mut device) = setup(medium);
+    iface.set_forwarding(true);
+
+    let router = Ipv4Address::new(192, 168, 1, 254);
+    let routes = iface.routes_mut();
+    routes
+        .add_route(
+            Route::new(IpCidr::new(IpAddress::v4(10, 0, 0, 0), 8), router.into())
+                .with_src_addr(IpAddress::v4(127, 0, 0, 1))
+                .with_mtu(1280),
+        )
+        .unwrap();
+    routes
+        .add_route(Route::new_kind(
+            IpCidr::new(IpAddress::v4(10, 1, 0, 0), 16),
+            RouteKind::Unreachable,
+        ))
+        .unwrap();
+    routes
+        .add_route(Route::new_kind(
+            IpCidr::new(IpAddress::v4(10, 2, 0, 0), 16),
+            RouteKind::Blackhole,
+        ))
+        .unwrap();
+
+    // The route selects the source address and the path MTU.
+    let dst_addr = Ipv4Address::new(10, 0, 0, 2);
+    assert_eq!(
+        iface.get_source_address_ipv4(&dst_addr),
+        Some(Ipv4Address::new(127, 0, 0, 1))
+    );
+    assert_eq!(iface.path_mtu(dst_addr.into()), 1280);
+
+    let src_addr = Ipv4Address::new(192, 168, 1, 2);
+    let ipv4_repr = |dst_addr| Ipv4Repr {
+        src_addr,
+        dst_addr,
+        next_header: IpProtocol::Udp,
+        payload_len: PAYLOAD.len(),
+        hop_limit: 64,
+    };
+    let ipv4_packet = |ipv4_repr: Ipv4Repr| {
+        let mut bytes = vec![0; ipv4_repr.buffer_len() + PAYLOAD.len()];
+        ipv4_repr.emit(
+            &mut Ipv4Packet::new_unchecked(&mut bytes[..]),
+            &ChecksumCapabilities::default(),
+        );
+        bytes[ipv4_repr.buffer_len()..].copy_from_slice(&PAYLOAD);
+        bytes
+    };
+
+    // Packets to an unreachable route are answered with an ICMP error.
+    let dst_addr = Ipv4Address::new(10, 1, 0, 2);
+    let bytes = ipv4_packet(ipv4_repr(dst_addr));
+    let icmp_repr = Icmpv4Repr::DstUnreachable {
+        reason: Icmpv4DstUnreachable::HostUnreachable,
+        header: ipv4_repr(dst_addr),
+        data: &PAYLOAD,
+    };
+    assert_eq!(
+        iface.inner.process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv4Packet::new_unchecked(&bytes[..]),
+            &mut iface.fragments
+        ),
+        Some(Packet::new_ipv4(
+            Ipv4Repr {
+                src_addr: Ipv4Address::new(192, 168, 1, 1),
+                dst_addr: src_addr,
+                next_header: IpProtocol::Icmp,
+                payload_len: icmp_repr.buffer_len(),
+                hop_limit: 64,
+            },
+            IpPayload::Icmpv4(icmp_repr)
+        ))
+    );
+
+    // Packets to a blackhole route are silently dropped.
+    let bytes = ipv4_packet(ipv4_repr(Ipv4Address::new(10, 2, 0, 2)));
+    assert_eq!(
+        iface.inner.process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv4Packet::new_unchecked(&bytes[..]),
+            &mut iface.fragments
+        ),
+        None
+    );
+
+    // Routes that reject packets also apply to the network of the interface.
+    iface
+        .routes_mut()
+        .add_route(Route::new_kind(
+            IpCidr::new(IpAddress::v4(192, 168, 1, 128), 25),
+            RouteKind::Unreachable,
+        ))
+        .unwrap();
+    let dst_addr = Ipv4Address::new(192, 168, 1, 130);
+    let bytes = ipv4_packet(ipv4_repr(dst_addr));
+    let icmp_repr = Icmpv4Repr::DstUnreachable {
+        reason: Icmpv4DstUnreachable::HostUnreachable,
+        header: ipv4_repr(dst_addr),
+        data: &PAYLOAD,
+    };
+    assert_eq!(
+        iface.inner.process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv4Packet::new_unchecked(&bytes[..]),
+            &mut iface.fragments
+        ),
+        Some(Packet::new_ipv4(
+            Ipv4Repr {
+                src_addr: Ipv4Address::new(192, 168, 1, 1),
+                dst_addr: src_addr,
+                next_header: IpProtocol::Icmp,
+                payload_len: icmp_repr.buffer_len(),
+                hop_limit: 64,
+            },
+            IpPayload::Icmpv4(icmp_repr)
+        ))
+    );
+
+    let tx_token = device.transmit(Instant::ZERO).unwrap();
+    assert_eq!(
+        iface.inner.dispatch_ip(
+            tx_token,
+            PacketMeta::default(),
+            Packet::new_ipv4(
+                Ipv4Repr {
+                    src_addr: Ipv4Address::new(192, 168, 1, 1),
+                    ..ipv4_repr(dst_addr)
+                },
+                IpPayload::Forward(IpProtocol::Udp, &PAYLOAD)
+            ),
+            &mut iface.fragmenter,
+        ),
+        Err(DispatchError::NoRoute)
+    );
+}
+
+/// A device that receives the packets queued in `rx`, and queues the packets it transmits in
+/// `tx`.
+#[cfg(feature = "medium-ip")]
+#[derive(Default)]
+struct QueueDevice {
+    rx: std::collections::VecDeque<std::vec::Vec<u8>>,
+    tx: std::vec::Vec<std::vec::Vec<u8>>,
+}
+
+#[cfg(feature = "medium-ip")]
+struct QueueRxToken(std::vec::Vec<u8>);
+
+#[cfg(feature = "medium-ip")]
+impl RxToken for QueueRxToken {
+    fn consume<R, F>(mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        f(&mut self.0)
+    }
+}
+
+#[cfg(feature = "medium-ip")]
+struct QueueTxToken<'a>(&'a mut std::vec::Vec<std::vec::Vec<u8>>);
+
+#[cfg(feature = "medium-ip")]
+impl<'a> TxToken for QueueTxToken<'a> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut buffer = vec![0; len];
+        let result = f(&mut buffer);
+        self.0.push(buffer);
+        result
+    }
+}
+
+#[cfg(feature = "medium-ip")]
+impl Device for QueueDevice {
+    type RxToken<'a> = QueueRxToken;
+    type TxToken<'a> = QueueTxToken<'a>;
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        DeviceCapabilities {
+            medium: Medium::Ip,
+            max_transmission_unit: 1500,
+            ..DeviceCapabilities::default()
+        }
+    }
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let buffer = self.rx.pop_front()?;
+        Some((QueueRxToken(buffer), QueueTxToken(&mut self.tx)))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        Some(QueueTxToken(&mut self.tx))
+    }
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-udp"))]
+fn test_poll_all() {
+    use crate::socket::udp;
+    use crate::wire::IpEndpoint;
+
+    static PAYLOAD: [u8; 8] = [0x04, 0xd2, 0x16, 0x2e, 0x00, 0x08, 0x00, 0x00];
+
+    fn router<D: Device>(device: &mut D, cidr: IpCidr) -> Interface {
+        let mut iface = Interface::new(Config::new(HardwareAddress::Ip), device, Instant::ZERO);
+        iface.update_ip_addrs(|ip_addrs| ip_addrs.push(cidr).unwrap());
+        iface.set_forwarding(true);
+        iface
+    }
+
+    let mut uplink = QueueDevice::default();
+    let mut iface_up = router(&mut uplink, IpCidr::new(IpAddress::v4(192, 168, 1, 1), 24));
+    iface_up
+        .routes_mut()
+        .add_default_ipv4_route(Ipv4Address::new(192, 168, 1, 254))
+        .unwrap();
+    // The interfaces may use different types of devices.
+    let mut downlink = crate::phy::Tracer::new(QueueDevice::default(), |_, _| {});
+    let mut iface_down = router(&mut downlink, IpCidr::new(IpAddress::v4(10, 0, 0, 1), 24));
+
+    let mut sockets = SocketSet::new(vec![]);
+    let udp_socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 64]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 2], vec![0; 64]),
+    );
+    let udp_handle = sockets.add(udp_socket);
+
+    let ipv4_packet = |src_addr, dst_addr, hop_limit| {
+        let ipv4_repr = Ipv4Repr {
+            src_addr,
+            dst_addr,
+            next_header: IpProtocol::Udp,
+            payload_len: PAYLOAD.len(),
+            hop_limit,
+        };
+        let mut bytes = vec![0; ipv4_repr.buffer_len() + PAYLOAD.len()];
+        ipv4_repr.emit(
+            &mut Ipv4Packet::new_unchecked(&mut bytes[..]),
+            &ChecksumCapabilities::default(),
+        );
+        bytes[ipv4_repr.buffer_len()..].copy_from_slice(&PAYLOAD);
+        bytes
+    };
+    let host = Ipv4Address::new(10, 0, 0, 2);
+    let remote = Ipv4Address::new(192, 0, 2, 1);
+
+    // Packets are forwarded through the interface with the most specific route.
+    uplink.rx.push_back(ipv4_packet(remote, host, 64));
+    downlink
+        .get_mut()
+        .rx
+        .push_back(ipv4_packet(host, remote, 64));
+
+    let socket = sockets.get_mut::<udp::Socket>(udp_handle);
+    socket.bind(5000).unwrap();
+    socket
+        .send_slice(b"down", IpEndpoint::new(host.into(), 5000))
+        .unwrap();
+    socket
+        .send_slice(b"up", IpEndpoint::new(remote.into(), 5000))
+        .unwrap();
+
+    assert!(Interface::poll_all(
+        Instant::ZERO,
+        &mut [
+            &mut (&mut iface_up, &mut uplink),
+            &mut (&mut iface_down, &mut downlink)
+        ],
+        &mut sockets,
+    ));
+
+    let received = |packets: &[std::vec::Vec<u8>]| {
+        packets
+            .iter()
+            .map(|bytes| {
+                let packet = Ipv4Packet::new_checked(&bytes[..]).unwrap();
+                (packet.src_addr(), packet.dst_addr(), packet.hop_limit())
+            })
+            .collect::<std::vec::Vec<_>>()
+    };
+    assert_eq!(
+        received(&uplink.tx),
+        vec![
+            (host, remote, 63),
+            (Ipv4Address::new(192, 168, 1, 1), remote, 64)
+        ]
+    );
+    assert_eq!(
+        received(&downlink.get_ref().tx),
+        vec![
+            (remote, host, 63),
+            (Ipv4Address::new(10, 0, 0, 1), host, 64)
+        ]
+    );
+}
+
+#[cfg(feature = "medium-ethernet")]
+fn acd_setup(acd: bool) -> (Interface, SocketSet<'static>, crate::tests::TestingDevice) {
+    let mut device = crate::tests::TestingDevice::new(Medium::Ethernet);
+    let mut config = Config::new(EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x02]).into());
+    config.ipv4_acd = acd;
+    let iface = Interface::new(config, &mut device, Instant::ZERO);
+    (iface, SocketSet::new(vec![]), device)
+}
+
+/// Run the Address Conflict Detection until `until`, and return the transmitted ARP packets.
+#[cfg(feature = "medium-ethernet")]
+fn acd_run(
+    iface: &mut Interface,
+    device: &mut crate::tests::TestingDevice,
+    until: Instant,
+) -> std::vec::Vec<ArpRepr> {
+    let mut arps = std::vec::Vec::new();
+    while iface.inner.now < until {
+        iface.inner.now += Duration::from_millis(100);
+        while iface.acd_egress(device) || iface.announce_egress(device) {
+            let frame = device.queue.pop_front().unwrap();
+            let frame = EthernetFrame::new_checked(&frame[..]).unwrap();
+            assert_eq!(frame.dst_addr(), EthernetAddress::BROADCAST);
+            arps.push(ArpRepr::parse(&ArpPacket::new_checked(frame.payload()).unwrap()).unwrap());
+        }
+    }
+    arps
+}
+
+#[cfg(feature = "medium-ethernet")]
+fn acd_receive(iface: &mut Interface, sockets: &mut SocketSet, repr: ArpRepr) {
+    let mut bytes = vec![0u8; 14 + repr.buffer_len()];
+    let mut frame = EthernetFrame::new_unchecked(&mut bytes[..]);
+    frame.set_dst_addr(EthernetAddress::BROADCAST);
+    frame.set_src_addr(EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x01]));
+    frame.set_ethertype(EthernetProtocol::Arp);
+    repr.emit(&mut ArpPacket::new_unchecked(frame.payload_mut()));
+    iface
+        .inner
+        .process_ethernet(sockets, PacketMeta::default(), &bytes, &mut iface.fragments);
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_acd_assigns_address() {
+    let (mut iface, _, mut device) = acd_setup(true);
+    let local_hw_addr = EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x02]);
+    let addr = Ipv4Address::new(192, 168, 1, 1);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs.push(IpCidr::new(addr.into(), 24)).unwrap();
+    });
+
+    // The address is not used while it is probed.
+    assert!(!iface.has_ip_addr(addr));
+    assert_eq!(iface.ipv4_addr(), None);
+
+    // Three probes are sent. Without answer, the address is assigned and announced twice.
+    let arps = acd_run(&mut iface, &mut device, Instant::from_secs(12));
+    let probe = ArpRepr::EthernetIpv4 {
+        operation: ArpOperation::Request,
+        source_hardware_addr: local_hw_addr,
+        source_protocol_addr: Ipv4Address::UNSPECIFIED,
+        target_hardware_addr: EthernetAddress::default(),
+        target_protocol_addr: addr,
+    };
+    let announcement = ArpRepr::EthernetIpv4 {
+        operation: ArpOperation::Request,
+        source_hardware_addr: local_hw_addr,
+        source_protocol_addr: addr,
+        target_hardware_addr: EthernetAddress::default(),
+        target_protocol_addr: addr,
+    };
+    assert_eq!(arps, [probe, probe, probe, announcement, announcement]);
+    assert_eq!(iface.acd_event(), Some(AcdEvent::Assigned(addr)));
+    assert!(iface.has_ip_addr(addr));
+    assert_eq!(iface.ipv4_addr(), Some(addr));
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_acd_conflict() {
+    let (mut iface, mut sockets, mut device) = acd_setup(true);
+    let addr = Ipv4Address::new(192, 168, 1, 1);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x01]);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs.push(IpCidr::new(addr.into(), 24)).unwrap();
+    });
+    assert_eq!(
+        acd_run(&mut iface, &mut device, Instant::from_secs(1)).len(),
+        1
+    );
+
+    // Another node probing for the same address is a conflict.
+    acd_receive(
+        &mut iface,
+        &mut sockets,
+        ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Request,
+            source_hardware_addr: remote_hw_addr,
+            source_protocol_addr: Ipv4Address::UNSPECIFIED,
+            target_hardware_addr: EthernetAddress::default(),
+            target_protocol_addr: addr,
+        },
+    );
+    assert_eq!(iface.acd_event(), Some(AcdEvent::Conflict(addr)));
+    assert!(!iface.has_ip_addr(addr));
+    assert!(acd_run(&mut iface, &mut device, Instant::from_secs(10)).is_empty());
+    assert!(iface.ip_addrs().is_empty());
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_acd_defend() {
+    let (mut iface, mut sockets, mut device) = acd_setup(true);
+    let addr = Ipv4Address::new(192, 168, 1, 1);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x01]);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs.push(IpCidr::new(addr.into(), 24)).unwrap();
+    });
+    acd_run(&mut iface, &mut device, Instant::from_secs(12));
+    assert_eq!(iface.acd_event(), Some(AcdEvent::Assigned(addr)));
+
+    let claim = ArpRepr::EthernetIpv4 {
+        operation: ArpOperation::Request,
+        source_hardware_addr: remote_hw_addr,
+        source_protocol_addr: addr,
+        target_hardware_addr: EthernetAddress::default(),
+        target_protocol_addr: addr,
+    };
+
+    // The address is defended once with an announcement.
+    acd_receive(&mut iface, &mut sockets, claim);
+    assert_eq!(iface.acd_event(), None);
+    let arps = acd_run(&mut iface, &mut device, Instant::from_secs(13));
+    assert_eq!(arps.len(), 1);
+    assert!(iface.has_ip_addr(addr));
+
+    // It is given up if the other node keeps using it, and removed from the interface like
+    // with `update_ip_addrs`, which flushes the neighbor cache.
+    let neighbor_addr = Ipv4Address::new(192, 168, 1, 2);
+    iface.inner.neighbor_cache.fill(
+        neighbor_addr.into(),
+        HardwareAddress::Ethernet(remote_hw_addr),
+        iface.inner.now,
+    );
+    assert_ne!(iface.neighbors().count(), 0);
+    acd_receive(&mut iface, &mut sockets, claim);
+    assert_eq!(iface.acd_event(), Some(AcdEvent::Conflict(addr)));
+    assert!(!iface.has_ip_addr(addr));
+    assert!(acd_run(&mut iface, &mut device, Instant::from_secs(14)).is_empty());
+    assert!(iface.ip_addrs().is_empty());
+    assert_eq!(iface.neighbors().count(), 0);
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_ipv4_link_local() {
+    let (mut iface, mut sockets, mut device) = acd_setup(false);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x01]);
+    let link_local = |iface: &Interface| {
+        iface.ip_addrs().iter().find_map(|cidr| match cidr {
+            IpCidr::Ipv4(cidr) if cidr.address().is_link_local() => Some(*cidr),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        })
+    };
+
+    // A link-local address is selected and probed.
+    iface.set_ipv4_link_local(true);
+    assert_eq!(
+        acd_run(&mut iface, &mut device, Instant::from_secs(1)).len(),
+        1
+    );
+    let first = link_local(&iface).unwrap();
+    assert_eq!(first.prefix_len(), 16);
+    assert!(first.address().as_bytes()[2] >= 1 && first.address().as_bytes()[2] <= 254);
+    assert!(!iface.has_ip_addr(first.address()));
+
+    // Another address is selected on conflict.
+    acd_receive(
+        &mut iface,
+        &mut sockets,
+        ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Reply,
+            source_hardware_addr: remote_hw_addr,
+            source_protocol_addr: first.address(),
+            target_hardware_addr: EthernetAddress::default(),
+            target_protocol_addr: first.address(),
+        },
+    );
+    assert_eq!(iface.acd_event(), Some(AcdEvent::Conflict(first.address())));
+    acd_run(&mut iface, &mut device, Instant::from_secs(12));
+    let second = link_local(&iface).unwrap();
+    assert_ne!(first, second);
+    assert_eq!(
+        iface.acd_event(),
+        Some(AcdEvent::Assigned(second.address()))
+    );
+    assert!(iface.has_ip_addr(second.address()));
+
+    // The address is removed when link-local configuration is disabled.
+    iface.set_ipv4_link_local(false);
+    assert_eq!(link_local(&iface), None);
+}
+
+#[test]
+#[cfg(feature = "socket-dhcpv4")]
+fn test_ipv4_link_local_dhcpv4_fallback() {
+    use crate::socket::dhcpv4::Socket as Dhcpv4Socket;
+
+    let (mut iface, mut sockets, mut device) = acd_setup(false);
+    let link_local = |iface: &Interface| {
+        iface
+            .ip_addrs()
+            .iter()
+            .any(|cidr| matches!(cidr, IpCidr::Ipv4(cidr) if cidr.address().is_link_local()))
+    };
+    // Run the interface until `until`, and return the transaction ID of the last DHCP message.
+    let run = |iface: &mut Interface,
+               device: &mut crate::tests::TestingDevice,
+               sockets: &mut SocketSet,
+               until: Instant| {
+        let mut transaction_id = None;
+        while iface.inner.now < until {
+            iface.inner.now += Duration::from_millis(100);
+            while iface.socket_egress(device, sockets, |_, _| true) | iface.protocols_egress(device)
+            {
+                while let Some(frame) = device.queue.pop_front() {
+                    let frame = EthernetFrame::new_checked(&frame[..]).unwrap();
+                    if frame.ethertype() != EthernetProtocol::Ipv4 {
+                        continue;
+                    }
+                    let packet = Ipv4Packet::new_checked(frame.payload()).unwrap();
+                    let udp_packet = UdpPacket::new_checked(packet.payload()).unwrap();
+                    let dhcp_packet = DhcpPacket::new_checked(udp_packet.payload()).unwrap();
+                    transaction_id = Some(dhcp_packet.transaction_id());
+                }
+            }
+        }
+        transaction_id
+    };
+    let reply = |iface: &mut Interface,
+                 sockets: &mut SocketSet,
+                 message_type: DhcpMessageType,
+                 transaction_id: u32| {
+        let server_addr = Ipv4Address::new(192, 168, 1, 254);
+        let dhcp_repr = DhcpRepr {
+            message_type,
+            transaction_id,
+            secs: 0,
+            client_hardware_address: EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x02]),
+            client_ip: Ipv4Address::UNSPECIFIED,
+            your_ip: Ipv4Address::new(192, 168, 1, 100),
+            server_ip: server_addr,
+            router: None,
+            subnet_mask: Some(Ipv4Address::new(255, 255, 255, 0)),
+            relay_agent_ip: Ipv4Address::UNSPECIFIED,
+            broadcast: false,
+            requested_ip: None,
+            client_identifier: None,
+            server_identifier: Some(server_addr),
+            parameter_request_list: None,
+            dns_servers: None,
+            max_size: None,
+            lease_duration: Some(3600),
+            renew_duration: None,
+            rebind_duration: None,
+            ntp_servers: None,
+            interface_mtu: None,
+            classless_routes: None,
+            additional_options: &[],
+        };
+        let udp_repr = UdpRepr {
+            src_port: DHCP_SERVER_PORT,
+            dst_port: DHCP_CLIENT_PORT,
+        };
+        let ipv4_repr = Ipv4Repr {
+            src_addr: server_addr,
+            dst_addr: Ipv4Address::BROADCAST,
+            next_header: IpProtocol::Udp,
+            payload_len: udp_repr.header_len() + dhcp_repr.buffer_len(),
+            hop_limit: 64,
+        };
+
+        let mut bytes = vec![0u8; ipv4_repr.buffer_len() + ipv4_repr.payload_len];
+        ipv4_repr.emit(
+            &mut Ipv4Packet::new_unchecked(&mut bytes[..]),
+            &ChecksumCapabilities::default(),
+        );
+        udp_repr.emit(
+            &mut UdpPacket::new_unchecked(&mut bytes[ipv4_repr.buffer_len()..]),
+            &ipv4_repr.src_addr.into(),
+            &ipv4_repr.dst_addr.into(),
+            dhcp_repr.buffer_len(),
+            |buf| dhcp_repr.emit(&mut DhcpPacket::new_unchecked(buf)).unwrap(),
+            &ChecksumCapabilities::default(),
+        );
+        iface.inner.process_ipv4(
+            sockets,
+            PacketMeta::default(),
+            &Ipv4Packet::new_unchecked(&bytes[..]),
+            &mut iface.fragments,
+        );
+    };
+
+    iface.set_ipv4_link_local(true);
+    sockets.add(Dhcpv4Socket::new());
+
+    // No link-local address is selected while the DHCP client is acquiring an address.
+    run(
+        &mut iface,
+        &mut device,
+        &mut sockets,
+        Instant::from_millis(9900),
+    );
+    assert!(!link_local(&iface));
+    assert_eq!(
+        iface.poll_at(iface.inner.now, &sockets),
+        Some(Instant::from_millis(10100))
+    );
+
+    // It is selected once the DHCP client timed out.
+    let transaction_id = run(
+        &mut iface,
+        &mut device,
+        &mut sockets,
+        Instant::from_secs(20),
+    )
+    .unwrap();
+    assert!(link_local(&iface));
+    assert!(matches!(iface.acd_event(), Some(AcdEvent::Assigned(_))));
+
+    // It is removed once the DHCP client acquired an address.
+    reply(
+        &mut iface,
+        &mut sockets,
+        DhcpMessageType::Offer,
+        transaction_id,
+    );
+    let transaction_id = run(
+        &mut iface,
+        &mut device,
+        &mut sockets,
+        Instant::from_millis(20100),
+    )
+    .unwrap();
+    assert!(link_local(&iface));
+    reply(
+        &mut iface,
+        &mut sockets,
+        DhcpMessageType::Ack,
+        transaction_id,
+    );
+    run(
+        &mut iface,
+        &mut device,
+        &mut sockets,
+        Instant::from_millis(20200),
+    );
+    assert!(!link_local(&iface));
+}
+
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(all(feature = "medium-ethernet", feature = "socket-dhcpv4-server"))]
+fn test_dhcpv4_server_discover(#[case] medium: Medium) {
+    use crate::socket::dhcpv4_server::{Config, LeaseState, Socket as Dhcpv4ServerSocket};
+
+    let (mut iface, mut sockets, _) = setup(medium);
+
+    let config = Config {
+        address: Ipv4Cidr::new(Ipv4Address([192, 168, 1, 1]), 24),
+        pool_start: Ipv4Address([192, 168, 1, 100]),
+        pool_end: Ipv4Address([192, 168, 1, 199]),
+        router: None,
+        dns_servers: heapless::Vec::new(),
+        lease_duration: Duration::from_secs(3600),
+    };
+    let handle = sockets.add(Dhcpv4ServerSocket::new(config, vec![None; 4]));
+
+    let dhcp_repr = DhcpRepr {
+        message_type: DhcpMessageType::Discover,
+        transaction_id: 0x12345678,
+        secs: 0,
+        client_hardware_address: EthernetAddress([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]),
+        client_ip: Ipv4Address::UNSPECIFIED,
+        your_ip: Ipv4Address::UNSPECIFIED,
+        server_ip: Ipv4Address::UNSPECIFIED,
+        router: None,
+        subnet_mask: None,
+        relay_agent_ip: Ipv4Address::UNSPECIFIED,
+        broadcast: false,
+        requested_ip: None,
+        client_identifier: None,
+        server_identifier: None,
+        parameter_request_list: None,
+        dns_servers: None,
+        max_size: None,
+        lease_duration: None,
+        renew_duration: None,
+        rebind_duration: None,
+        ntp_servers: None,
+        interface_mtu: None,
+        classless_routes: None,
+        additional_options: &[],
+    };
+    let udp_repr = UdpRepr {
+        src_port: DHCP_CLIENT_PORT,
+        dst_port: DHCP_SERVER_PORT,
+    };
+    let ipv4_repr = Ipv4Repr {
+        src_addr: Ipv4Address::UNSPECIFIED,
+        dst_addr: Ipv4Address::BROADCAST,
+        next_header: IpProtocol::Udp,
+        payload_len: udp_repr.header_len() + dhcp_repr.buffer_len(),
+        hop_limit: 64,
+    };
+
+    let mut bytes = vec![0u8; ipv4_repr.buffer_len() + ipv4_repr.payload_len];
+    ipv4_repr.emit(
+        &mut Ipv4Packet::new_unchecked(&mut bytes[..]),
+        &ChecksumCapabilities::default(),
+    );
+    udp_repr.emit(
+        &mut UdpPacket::new_unchecked(&mut bytes[ipv4_repr.buffer_len()..]),
+        &ipv4_repr.src_addr.into(),
+        &ipv4_repr.dst_addr.into(),
+        dhcp_repr.buffer_len(),
+        |buf| dhcp_repr.emit(&mut DhcpPacket::new_unchecked(buf)).unwrap(),
+        &ChecksumCapabilities::default(),
+    );
+
+    // The DISCOVER is handled by the server socket, which offers an address.
+    assert_eq!(
+        iface.inner.process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv4Packet::new_unchecked(&bytes[..]),
+            &mut iface.fragments
+        ),
+        None
+    );
+
+    let server = sockets.get::<Dhcpv4ServerSocket>(handle);
+    let lease = server.leases().next().unwrap();
+    assert_eq!(lease.address, Ipv4Address([192, 168, 1, 100]));
+    assert_eq!(lease.state, LeaseState::Offered);
+}
+
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(all(feature = "medium-ethernet", feature = "socket-mdns"))]
+fn test_mdns_responder_join(#[case] medium: Medium) {
+    use crate::socket::mdns::Socket as MdnsSocket;
+
+    let (mut iface, mut sockets, mut device) = setup(medium);
+    let handle = sockets.add(MdnsSocket::new("device", &[]));
+
+    // The groups are joined as the interface is polled.
+    let group = Ipv4Address::new(224, 0, 0, 251);
+    #[cfg(feature = "proto-ipv6")]
+    let group_v6 = Ipv6Address::new(0xff02, 0, 0, 0, 0, 0, 0, 0xfb);
+    iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    assert!(iface.inner.has_multicast_group(group));
+    #[cfg(feature = "proto-ipv6")]
+    assert!(iface.inner.has_multicast_group(group_v6));
+    #[cfg(feature = "proto-igmp")]
+    assert!(iface.inner.ipv4_multicast_groups.contains_key(&group));
+
+    // The groups are left along with the last responder.
+    sockets.remove(handle);
+    iface.poll(Instant::from_millis(100), &mut device, &mut sockets);
+    assert!(!iface.inner.has_multicast_group(group));
+    #[cfg(feature = "proto-ipv6")]
+    assert!(!iface.inner.has_multicast_group(group_v6));
+}
+
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(all(
+    feature = "medium-ethernet",
+    feature = "socket-mdns",
+    feature = "proto-igmp"
+))]
+fn test_mdns_responder_keeps_joined_group(#[case] medium: Medium) {
+    use crate::socket::mdns::Socket as MdnsSocket;
+
+    let (mut iface, mut sockets, mut device) = setup(medium);
+    let group = Ipv4Address::new(224, 0, 0, 251);
+    iface
+        .join_multicast_group(&mut device, group, Instant::ZERO)
+        .unwrap();
+
+    // A group the application joined stays joined after the last responder is removed.
+    let handle = sockets.add(MdnsSocket::new("device", &[]));
+    iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    sockets.remove(handle);
+    iface.poll(Instant::from_millis(100), &mut device, &mut sockets);
+    assert!(iface.has_multicast_group(group));
+}
@@ -76,8 +76,13 @@ fn any_ip(#[case] medium: Medium) {
                 via_router: IpAddress::Ipv6(Ipv6Address::from_parts(&[
                     0xfdbe, 0, 0, 0, 0, 0, 0, 0x0001,
                 ])),
+                kind: crate::iface::RouteKind::Gateway,
+                metric: 0,
+                src_addr: None,
+                mtu: None,
                 preferred_until: None,
                 expires_at: None,
+                origin: crate::iface::RouteOrigin::Static,
             })
             .unwrap();
     });
@@ -1244,37 +1249,38 @@ fn get_source_address_empty_interface() {
     );
 }
 
+#[cfg(feature = "proto-igmp")]
+fn recv_icmpv6(
+    device: &mut crate::tests::TestingDevice,
+    timestamp: Instant,
+) -> std::vec::Vec<Ipv6Packet<std::vec::Vec<u8>>> {
+    let caps = device.capabilities();
+    recv_all(device, timestamp)
+        .iter()
+        .filter_map(|frame| {
+            let ipv6_packet = match caps.medium {
+                #[cfg(feature = "medium-ethernet")]
+                Medium::Ethernet => {
+                    let eth_frame = EthernetFrame::new_checked(frame).ok()?;
+                    Ipv6Packet::new_checked(eth_frame.payload()).ok()?
+                }
+                #[cfg(feature = "medium-ip")]
+                Medium::Ip => Ipv6Packet::new_checked(&frame[..]).ok()?,
+                #[cfg(feature = "medium-ieee802154")]
+                Medium::Ieee802154 => todo!(),
+            };
+            let buf = ipv6_packet.into_inner().to_vec();
+            Some(Ipv6Packet::new_unchecked(buf))
+        })
+        .collect::<std::vec::Vec<_>>()
+}
+
 #[rstest]
 #[case(Medium::Ip)]
 #[cfg(feature = "medium-ip")]
 #[case(Medium::Ethernet)]
 #[cfg(feature = "medium-ethernet")]
 fn test_join_ipv6_multicast_group(#[case] medium: Medium) {
-    fn recv_icmpv6(
-        device: &mut crate::tests::TestingDevice,
-        timestamp: Instant,
-    ) -> std::vec::Vec<Ipv6Packet<std::vec::Vec<u8>>> {
-        let caps = device.capabilities();
-        recv_all(device, timestamp)
-            .iter()
-            .filter_map(|frame| {
-                let ipv6_packet = match caps.medium {
-                    #[cfg(feature = "medium-ethernet")]
-                    Medium::Ethernet => {
-                        let eth_frame = EthernetFrame::new_checked(frame).ok()?;
-                        Ipv6Packet::new_checked(eth_frame.payload()).ok()?
-                    }
-                    #[cfg(feature = "medium-ip")]
-                    Medium::Ip => Ipv6Packet::new_checked(&frame[..]).ok()?,
-                    #[cfg(feature = "medium-ieee802154")]
-                    Medium::Ieee802154 => todo!(),
-                };
-                let buf = ipv6_packet.into_inner().to_vec();
-                Some(Ipv6Packet::new_unchecked(buf))
-            })
-            .collect::<std::vec::Vec<_>>()
-    }
-
     let (mut iface, _sockets, mut device) = setup(medium);
 
     let groups = [
@@ -1354,7 +1360,7 @@ fn test_join_ipv6_multicast_group(#[case] medium: Medium) {
             MldAddressRecordRepr {
                 num_srcs: 0,
                 mcast_addr: group_addr,
-                record_type: MldRecordType::ChangeToInclude,
+                record_type: MldRecordType::ChangeToExclude,
                 aux_data_len: 0,
                 payload: &[],
             }
@@ -1366,3 +1372,1433 @@ fn test_join_ipv6_multicast_group(#[case] medium: Medium) {
         assert!(!iface.has_multicast_group(group_addr));
     }
 }
+
+/// Parse the records of the MLDv2 reports sent on `device`.
+#[cfg(feature = "proto-igmp")]
+fn recv_mld_reports(
+    device: &mut crate::tests::TestingDevice,
+    timestamp: Instant,
+) -> std::vec::Vec<std::vec::Vec<(MldRecordType, Ipv6Address)>> {
+    let checksum_caps = device.capabilities().checksum;
+    recv_icmpv6(device, timestamp)
+        .iter()
+        .map(|ipv6_packet| {
+            let ipv6_packet = Ipv6Packet::new_unchecked(ipv6_packet.as_ref());
+            let ip_payload = ipv6_packet.payload();
+            let hbh_header = Ipv6HopByHopHeader::new_checked(&ip_payload[..8]).unwrap();
+            let hbh_repr = Ipv6HopByHopRepr::parse(&hbh_header).unwrap();
+            let icmpv6_packet =
+                Icmpv6Packet::new_checked(&ip_payload[hbh_repr.buffer_len()..]).unwrap();
+            let icmpv6_repr = Icmpv6Repr::parse(
+                &ipv6_packet.src_addr(),
+                &ipv6_packet.dst_addr(),
+                &icmpv6_packet,
+                &checksum_caps,
+            )
+            .unwrap();
+
+            let Icmpv6Repr::Mld(MldRepr::Report {
+                nr_mcast_addr_rcrds,
+                mut data,
+            }) = icmpv6_repr
+            else {
+                panic!("unexpected icmpv6_repr: {:?}", icmpv6_repr);
+            };
+
+            let mut records = std::vec::Vec::new();
+            for _ in 0..nr_mcast_addr_rcrds {
+                let record = MldAddressRecord::new_checked(data).unwrap();
+                let record_repr = MldAddressRecordRepr::parse(&record).unwrap();
+                records.push((record_repr.record_type, record_repr.mcast_addr));
+                data = &data[record_repr.buffer_len()..];
+            }
+            records
+        })
+        .collect()
+}
+
+/// Process a Multicast Listener Query sent by fe80::1.
+#[cfg(feature = "proto-igmp")]
+fn process_mld_query(iface: &mut Interface, mcast_addr: Ipv6Address, max_resp_code: u16, qrv: u8) {
+    let repr = Icmpv6Repr::Mld(MldRepr::Query {
+        max_resp_code,
+        mcast_addr,
+        s_flag: false,
+        qrv,
+        qqic: 125,
+        num_srcs: 0,
+        data: &[],
+    });
+    let ip_repr = Ipv6Repr {
+        src_addr: Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+        dst_addr: Ipv6Address::LINK_LOCAL_ALL_NODES,
+        next_header: IpProtocol::Icmpv6,
+        payload_len: repr.buffer_len(),
+        hop_limit: 1,
+    };
+
+    let mut bytes = vec![0u8; repr.buffer_len()];
+    repr.emit(
+        &ip_repr.src_addr,
+        &ip_repr.dst_addr,
+        &mut Icmpv6Packet::new_unchecked(&mut bytes[..]),
+        &ChecksumCapabilities::default(),
+    );
+
+    let mut sockets = SocketSet::new(vec![]);
+    assert_eq!(
+        iface.inner.process_icmpv6(&mut sockets, ip_repr, &bytes),
+        None
+    );
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(all(feature = "medium-ip", feature = "proto-igmp"))]
+#[case(Medium::Ethernet)]
+#[cfg(all(feature = "medium-ethernet", feature = "proto-igmp"))]
+fn test_mld_state_change_retransmission(#[case] medium: Medium) {
+    let (mut iface, sockets, mut device) = setup(medium);
+    let group = Ipv6Address::from_parts(&[0xff05, 0, 0, 0, 0, 0, 0, 0x00fb]);
+
+    iface
+        .join_multicast_group(&mut device, group, Instant::ZERO)
+        .unwrap();
+    assert_eq!(
+        recv_mld_reports(&mut device, Instant::ZERO),
+        vec![vec![(MldRecordType::ChangeToExclude, group)]]
+    );
+
+    // The report is retransmitted once with the default robustness variable.
+    let retransmit_at = iface.poll_at(Instant::ZERO, &sockets).unwrap();
+    assert!(retransmit_at < Instant::from_secs(1));
+    iface.inner.now = retransmit_at;
+    assert!(iface.mld_egress(&mut device));
+    assert_eq!(
+        recv_mld_reports(&mut device, retransmit_at),
+        vec![vec![(MldRecordType::ChangeToExclude, group)]]
+    );
+
+    iface.inner.now = Instant::from_secs(10);
+    assert!(!iface.mld_egress(&mut device));
+    assert_eq!(iface.poll_at(Instant::from_secs(10), &sockets), None);
+
+    // Leaving is retransmitted the same way.
+    iface
+        .leave_multicast_group(&mut device, group, Instant::from_secs(10))
+        .unwrap();
+    assert_eq!(
+        recv_mld_reports(&mut device, Instant::from_secs(10)),
+        vec![vec![(MldRecordType::ChangeToInclude, group)]]
+    );
+    iface.inner.now = Instant::from_secs(11);
+    assert!(iface.mld_egress(&mut device));
+    assert_eq!(
+        recv_mld_reports(&mut device, Instant::from_secs(11)),
+        vec![vec![(MldRecordType::ChangeToInclude, group)]]
+    );
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(all(feature = "medium-ip", feature = "proto-igmp", feature = "socket-udp"))]
+#[case(Medium::Ethernet)]
+#[cfg(all(
+    feature = "medium-ethernet",
+    feature = "proto-igmp",
+    feature = "socket-udp"
+))]
+fn test_mld_source_specific_join(#[case] medium: Medium) {
+    let (mut iface, _, mut device) = setup(medium);
+    let group = Ipv6Address::from_parts(&[0xff3e, 0, 0, 0, 0, 0, 0, 0x1234]);
+    let source = Ipv6Address::from_parts(&[0x2001, 0xdb8, 0, 0, 0, 0, 0, 1]);
+    let other = Ipv6Address::from_parts(&[0x2001, 0xdb8, 0, 0, 0, 0, 0, 2]);
+
+    iface
+        .join_multicast_group_with_sources(
+            &mut device,
+            group,
+            MulticastFilterMode::Include,
+            &[source.into()],
+            Instant::ZERO,
+        )
+        .unwrap();
+    assert_eq!(
+        recv_mld_reports(&mut device, Instant::ZERO),
+        vec![vec![(MldRecordType::AllowNewSources, group)]]
+    );
+    assert!(iface
+        .inner
+        .accepts_multicast_source(group.into(), source.into()));
+    assert!(!iface
+        .inner
+        .accepts_multicast_source(group.into(), other.into()));
+
+    // Replacing the source list allows the new source and blocks the old one.
+    iface
+        .join_multicast_group_with_sources(
+            &mut device,
+            group,
+            MulticastFilterMode::Include,
+            &[other.into()],
+            Instant::ZERO,
+        )
+        .unwrap();
+    assert_eq!(
+        recv_mld_reports(&mut device, Instant::ZERO),
+        vec![vec![
+            (MldRecordType::AllowNewSources, group),
+            (MldRecordType::BlockOldSources, group)
+        ]]
+    );
+
+    // An INCLUDE filter without sources leaves the group.
+    iface
+        .join_multicast_group_with_sources(
+            &mut device,
+            group,
+            MulticastFilterMode::Include,
+            &[],
+            Instant::ZERO,
+        )
+        .unwrap();
+    assert_eq!(
+        recv_mld_reports(&mut device, Instant::ZERO),
+        vec![vec![(MldRecordType::BlockOldSources, group)]]
+    );
+    assert!(!iface.has_multicast_group(group));
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(all(feature = "medium-ip", feature = "proto-igmp"))]
+#[case(Medium::Ethernet)]
+#[cfg(all(feature = "medium-ethernet", feature = "proto-igmp"))]
+fn test_mld_general_query(#[case] medium: Medium) {
+    let (mut iface, sockets, mut device) = setup(medium);
+    let groups = [
+        Ipv6Address::from_parts(&[0xff05, 0, 0, 0, 0, 0, 0, 0x00fb]),
+        Ipv6Address::from_parts(&[0xff0e, 0, 0, 0, 0, 0, 0, 0x0017]),
+    ];
+    for group in groups {
+        iface
+            .join_multicast_group(&mut device, group, Instant::ZERO)
+            .unwrap();
+    }
+    iface.inner.now = Instant::from_secs(10);
+    while iface.mld_egress(&mut device) {}
+    recv_all(&mut device, Instant::from_secs(10));
+
+    // A general query with a maximum response delay of 2 seconds, and a robustness of 3.
+    process_mld_query(&mut iface, Ipv6Address::UNSPECIFIED, 2000, 3);
+    let response_at = iface.poll_at(Instant::from_secs(10), &sockets).unwrap();
+    assert!(response_at <= Instant::from_secs(12));
+
+    iface.inner.now = response_at;
+    assert!(iface.mld_egress(&mut device));
+    let reports = recv_mld_reports(&mut device, response_at);
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].len(), 2);
+    for group in groups {
+        assert!(reports[0].contains(&(MldRecordType::ModeIsExclude, group)));
+    }
+    assert_eq!(iface.poll_at(response_at, &sockets), None);
+
+    // The robustness variable learned from the query applies to later changes.
+    iface
+        .leave_multicast_group(&mut device, groups[0], Instant::from_secs(20))
+        .unwrap();
+    recv_all(&mut device, Instant::from_secs(20));
+    for secs in [21, 22] {
+        iface.inner.now = Instant::from_secs(secs);
+        assert!(iface.mld_egress(&mut device));
+        assert_eq!(
+            recv_mld_reports(&mut device, Instant::from_secs(secs)),
+            vec![vec![(MldRecordType::ChangeToInclude, groups[0])]]
+        );
+    }
+    iface.inner.now = Instant::from_secs(23);
+    assert!(!iface.mld_egress(&mut device));
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(all(feature = "medium-ip", feature = "proto-igmp"))]
+#[case(Medium::Ethernet)]
+#[cfg(all(feature = "medium-ethernet", feature = "proto-igmp"))]
+fn test_mld_address_specific_query(#[case] medium: Medium) {
+    let (mut iface, sockets, mut device) = setup(medium);
+    let group = Ipv6Address::from_parts(&[0xff05, 0, 0, 0, 0, 0, 0, 0x00fb]);
+    let other = Ipv6Address::from_parts(&[0xff0e, 0, 0, 0, 0, 0, 0, 0x0017]);
+    iface
+        .join_multicast_group(&mut device, group, Instant::ZERO)
+        .unwrap();
+    iface.inner.now = Instant::from_secs(10);
+    while iface.mld_egress(&mut device) {}
+    recv_all(&mut device, Instant::from_secs(10));
+
+    // Queries for groups that are not joined are ignored.
+    process_mld_query(&mut iface, other, 1000, 0);
+    assert_eq!(iface.poll_at(Instant::from_secs(10), &sockets), None);
+
+    process_mld_query(&mut iface, group, 1000, 0);
+    let response_at = iface.poll_at(Instant::from_secs(10), &sockets).unwrap();
+    assert!(response_at <= Instant::from_secs(11));
+
+    iface.inner.now = Instant::from_secs(11);
+    assert!(iface.mld_egress(&mut device));
+    assert_eq!(
+        recv_mld_reports(&mut device, Instant::from_secs(11)),
+        vec![vec![(MldRecordType::ModeIsExclude, group)]]
+    );
+    assert_eq!(iface.poll_at(Instant::from_secs(11), &sockets), None);
+}
+
+#[cfg(feature = "medium-ethernet")]
+fn slaac_setup<'a>() -> (Interface, SocketSet<'a>, crate::tests::TestingDevice) {
+    let mut device = crate::tests::TestingDevice::new(Medium::Ethernet);
+
+    let mut config = Config::new(HardwareAddress::Ethernet(EthernetAddress([
+        0x02, 0x02, 0x02, 0x02, 0x02, 0x02,
+    ])));
+    config.slaac = true;
+
+    let iface = Interface::new(config, &mut device, Instant::ZERO);
+
+    (iface, SocketSet::new(vec![]), device)
+}
+
+/// Create an NDISC packet, sent from `src_addr` to `dst_addr`.
+#[cfg(feature = "medium-ethernet")]
+fn ndisc_packet(
+    src_addr: Ipv6Address,
+    dst_addr: Ipv6Address,
+    repr: NdiscRepr,
+) -> std::vec::Vec<u8> {
+    let repr = Icmpv6Repr::Ndisc(repr);
+    let ip_repr = Ipv6Repr {
+        src_addr,
+        dst_addr,
+        next_header: IpProtocol::Icmpv6,
+        payload_len: repr.buffer_len(),
+        hop_limit: 0xff,
+    };
+
+    let mut bytes = vec![0u8; ip_repr.buffer_len() + repr.buffer_len()];
+    let mut packet = Ipv6Packet::new_unchecked(&mut bytes[..]);
+    ip_repr.emit(&mut packet);
+    repr.emit(
+        &ip_repr.src_addr,
+        &ip_repr.dst_addr,
+        &mut Icmpv6Packet::new_unchecked(packet.payload_mut()),
+        &ChecksumCapabilities::default(),
+    );
+    bytes
+}
+
+/// Create a Router Advertisement sent by fe80::1, advertising the 2001:db8::/64 prefix.
+#[cfg(feature = "medium-ethernet")]
+fn slaac_router_advert(
+    router_lifetime: u64,
+    valid_lifetime: u64,
+    preferred_lifetime: u64,
+) -> std::vec::Vec<u8> {
+    ndisc_packet(
+        Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+        Ipv6Address::LINK_LOCAL_ALL_NODES,
+        NdiscRepr::RouterAdvert {
+            hop_limit: 64,
+            flags: NdiscRouterFlags::empty(),
+            router_lifetime: Duration::from_secs(router_lifetime),
+            reachable_time: Duration::ZERO,
+            retrans_time: Duration::ZERO,
+            lladdr: Some(RawHardwareAddress::from_bytes(&[0, 0, 0, 0, 0, 1])),
+            mtu: None,
+            prefix_info: Some(NdiscPrefixInformation {
+                prefix_len: 64,
+                flags: NdiscPrefixInfoFlags::ON_LINK | NdiscPrefixInfoFlags::ADDRCONF,
+                valid_lifetime: Duration::from_secs(valid_lifetime),
+                preferred_lifetime: Duration::from_secs(preferred_lifetime),
+                prefix: Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0),
+            }),
+        },
+    )
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn slaac_router_solicitation() {
+    let (mut iface, _, mut device) = slaac_setup();
+    let link_local_addr = Ipv6Address::new(0xfe80, 0, 0, 0, 0x0002, 0x02ff, 0xfe02, 0x0202);
+
+    // The link-local address is formed using the hardware address.
+    assert_eq!(
+        iface.ip_addrs(),
+        &[IpCidr::Ipv6(Ipv6Cidr::new(link_local_addr, 64))]
+    );
+
+    // Router Solicitations are sent after a random delay of at most one second, every four
+    // seconds, up to three times.
+    for (i, timestamp) in [1, 5, 9, 13].into_iter().enumerate() {
+        iface.inner.now = Instant::from_secs(timestamp);
+        if i == 3 {
+            assert!(!iface.slaac_egress(&mut device));
+            break;
+        }
+        assert!(iface.slaac_egress(&mut device));
+
+        let frame = device.queue.pop_front().unwrap();
+        let frame = EthernetFrame::new_checked(&frame[..]).unwrap();
+        assert_eq!(
+            frame.dst_addr(),
+            EthernetAddress([0x33, 0x33, 0, 0, 0, 0x02])
+        );
+
+        assert_eq!(
+            parse_ipv6(frame.payload()),
+            Ok(Packet::new_ipv6(
+                Ipv6Repr {
+                    src_addr: link_local_addr,
+                    dst_addr: Ipv6Address::LINK_LOCAL_ALL_ROUTERS,
+                    next_header: IpProtocol::Icmpv6,
+                    payload_len: 16,
+                    hop_limit: 0xff,
+                },
+                IpPayload::Icmpv6(Icmpv6Repr::Ndisc(NdiscRepr::RouterSolicit {
+                    lladdr: Some(RawHardwareAddress::from_bytes(&[0x02; 6])),
+                })),
+            ))
+        );
+    }
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn slaac_router_advertisement() {
+    let (mut iface, mut sockets, mut device) = slaac_setup();
+    let router = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+    let addr = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0x0002, 0x02ff, 0xfe02, 0x0202);
+    let remote = IpAddress::v6(0x2001, 0xdb8, 1, 0, 0, 0, 0, 1);
+
+    let data = slaac_router_advert(1800, 7200, 3600);
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(&data[..]).unwrap()
+        ),
+        None
+    );
+
+    // An address is formed from the prefix, and the router is used as default router.
+    assert!(iface.has_ip_addr(addr));
+    assert_eq!(iface.ip_addrs()[1], IpCidr::Ipv6(Ipv6Cidr::new(addr, 64)));
+    assert_eq!(
+        iface.routes().lookup(&remote, iface.inner.now),
+        Some(router.into())
+    );
+    assert!(iface.inner.has_neighbor(&router.into()));
+
+    // No more Router Solicitations are sent.
+    iface.inner.now = Instant::from_secs(1);
+    assert!(!iface.slaac_egress(&mut device));
+
+    // The address is deprecated once its preferred lifetime has passed.
+    let slaac = iface.inner.slaac.as_ref().unwrap();
+    assert!(!slaac.is_deprecated(&addr, Instant::from_secs(3599)));
+    assert!(slaac.is_deprecated(&addr, Instant::from_secs(3600)));
+
+    // The default router expires after the router lifetime.
+    iface.inner.now = Instant::from_secs(1800);
+    iface.slaac_egress(&mut device);
+    assert_eq!(iface.routes().lookup(&remote, iface.inner.now), None);
+    assert!(iface.has_ip_addr(addr));
+
+    // The address is removed after its valid lifetime.
+    assert_eq!(
+        iface.poll_at(Instant::from_secs(1800), &sockets),
+        Some(Instant::from_secs(7200))
+    );
+    iface.inner.now = Instant::from_secs(7200);
+    iface.slaac_egress(&mut device);
+    assert!(!iface.has_ip_addr(addr));
+    assert_eq!(iface.ip_addrs().len(), 1);
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn slaac_keeps_static_routes() {
+    let (mut iface, mut sockets, mut device) = slaac_setup();
+    let router = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+    let gateway = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 2);
+    let remote = IpAddress::v6(0x2001, 0xdb8, 1, 0, 0, 0, 0, 1);
+
+    // The default route of the application has a higher metric than the advertised one.
+    iface
+        .routes_mut()
+        .add_route(Route::new_ipv6_gateway(gateway).with_metric(10))
+        .unwrap();
+
+    let data = slaac_router_advert(1800, 7200, 3600);
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6Packet::new_checked(&data[..]).unwrap(),
+    );
+    assert_eq!(
+        iface.routes().lookup(&remote, iface.inner.now),
+        Some(router.into())
+    );
+
+    // Another advertisement replaces the advertised route only.
+    iface.inner.now = Instant::from_secs(100);
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6Packet::new_checked(&data[..]).unwrap(),
+    );
+    let mut routes = 0;
+    iface.routes_mut().update(|storage| routes = storage.len());
+    assert_eq!(routes, 2);
+
+    // The route of the application is used once the advertised route expires.
+    iface.inner.now = Instant::from_secs(1900);
+    iface.slaac_egress(&mut device);
+    assert_eq!(
+        iface.routes().lookup(&remote, iface.inner.now),
+        Some(gateway.into())
+    );
+
+    // A router lifetime of zero does not remove it either.
+    let data = slaac_router_advert(0, 7200, 3600);
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6Packet::new_checked(&data[..]).unwrap(),
+    );
+    assert_eq!(
+        iface.routes().lookup(&remote, iface.inner.now),
+        Some(gateway.into())
+    );
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn slaac_valid_lifetime_update() {
+    let (mut iface, mut sockets, mut device) = slaac_setup();
+    let addr = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0x0002, 0x02ff, 0xfe02, 0x0202);
+
+    let mut process = |iface: &mut Interface, data: &[u8]| {
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(data).unwrap(),
+        );
+    };
+
+    process(&mut iface, &slaac_router_advert(0, 3 * 3600, 3600));
+    assert!(iface.has_ip_addr(addr));
+
+    // A short valid lifetime cannot shorten the remaining lifetime to less than two hours.
+    process(&mut iface, &slaac_router_advert(0, 60, 60));
+    iface.inner.now = Instant::from_secs(2 * 3600 - 1);
+    iface.slaac_egress(&mut device);
+    assert!(iface.has_ip_addr(addr));
+    iface.inner.now = Instant::from_secs(2 * 3600);
+    iface.slaac_egress(&mut device);
+    assert!(!iface.has_ip_addr(addr));
+
+    // A valid lifetime of zero does not form a new address.
+    process(&mut iface, &slaac_router_advert(0, 0, 0));
+    assert!(!iface.has_ip_addr(addr));
+}
+
+#[cfg(feature = "medium-ethernet")]
+fn dad_setup<'a>() -> (Interface, SocketSet<'a>, crate::tests::TestingDevice) {
+    let mut device = crate::tests::TestingDevice::new(Medium::Ethernet);
+
+    let mut config = Config::new(HardwareAddress::Ethernet(EthernetAddress([
+        0x02, 0x02, 0x02, 0x02, 0x02, 0x02,
+    ])));
+    config.dad_transmits = 2;
+
+    let mut iface = Interface::new(config, &mut device, Instant::ZERO);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs
+            .push(IpCidr::new(IpAddress::v6(0xfdbe, 0, 0, 0, 0, 0, 0, 1), 64))
+            .unwrap();
+    });
+
+    (iface, SocketSet::new(vec![]), device)
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn dad_assigns_address() {
+    let (mut iface, _, mut device) = dad_setup();
+    let addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1);
+
+    // The address is not used while it is tentative.
+    assert!(iface.is_tentative_addr(&addr));
+    assert!(!iface.has_ip_addr(addr));
+    assert_eq!(
+        iface.get_source_address_ipv6(&Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2)),
+        Ipv6Address::LOOPBACK
+    );
+
+    // Neighbor Solicitations are sent from the unspecified address, one second apart.
+    for timestamp in [1, 2] {
+        iface.inner.now = Instant::from_secs(timestamp);
+        assert!(iface.dad_egress(&mut device));
+
+        let frame = device.queue.pop_front().unwrap();
+        let frame = EthernetFrame::new_checked(&frame[..]).unwrap();
+        assert_eq!(
+            parse_ipv6(frame.payload()),
+            Ok(Packet::new_ipv6(
+                Ipv6Repr {
+                    src_addr: Ipv6Address::UNSPECIFIED,
+                    dst_addr: addr.solicited_node(),
+                    next_header: IpProtocol::Icmpv6,
+                    payload_len: 24,
+                    hop_limit: 0xff,
+                },
+                IpPayload::Icmpv6(Icmpv6Repr::Ndisc(NdiscRepr::NeighborSolicit {
+                    target_addr: addr,
+                    lladdr: None,
+                })),
+            ))
+        );
+        assert_eq!(iface.dad_event(), None);
+    }
+
+    // Without reply, the address is assigned.
+    iface.inner.now = Instant::from_secs(3);
+    assert!(!iface.dad_egress(&mut device));
+    assert_eq!(iface.dad_event(), Some(DadEvent::Assigned(addr)));
+    assert!(iface.has_ip_addr(addr));
+    assert_eq!(
+        iface.get_source_address_ipv6(&Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2)),
+        addr
+    );
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn announce_after_dad() {
+    let (mut iface, _, mut device) = dad_setup();
+    let addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1);
+    iface.announce();
+
+    // Tentative addresses are not announced.
+    assert!(!iface.announce_egress(&mut device));
+
+    for timestamp in [1, 2] {
+        iface.inner.now = Instant::from_secs(timestamp);
+        assert!(iface.dad_egress(&mut device));
+        device.queue.pop_front().unwrap();
+    }
+    iface.inner.now = Instant::from_secs(3);
+    assert!(!iface.dad_egress(&mut device));
+    assert_eq!(iface.dad_event(), Some(DadEvent::Assigned(addr)));
+
+    // Once assigned, the address is announced with an unsolicited Neighbor Advertisement.
+    assert!(iface.announce_egress(&mut device));
+    let frame = device.queue.pop_front().unwrap();
+    let frame = EthernetFrame::new_checked(&frame[..]).unwrap();
+    assert_eq!(
+        frame.dst_addr(),
+        EthernetAddress([0x33, 0x33, 0x00, 0x00, 0x00, 0x01])
+    );
+    assert_eq!(
+        parse_ipv6(frame.payload()),
+        Ok(Packet::new_ipv6(
+            Ipv6Repr {
+                src_addr: addr,
+                dst_addr: Ipv6Address::LINK_LOCAL_ALL_NODES,
+                next_header: IpProtocol::Icmpv6,
+                payload_len: 32,
+                hop_limit: 0xff,
+            },
+            IpPayload::Icmpv6(Icmpv6Repr::Ndisc(NdiscRepr::NeighborAdvert {
+                flags: NdiscNeighborFlags::OVERRIDE,
+                target_addr: addr,
+                lladdr: Some(RawHardwareAddress::from_bytes(&[0x02; 6])),
+            })),
+        ))
+    );
+
+    // Without a configured count, the address is announced once.
+    iface.inner.now = Instant::from_secs(10);
+    assert!(!iface.announce_egress(&mut device));
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn dad_duplicate_advertisement() {
+    let (mut iface, mut sockets, _) = dad_setup();
+    let addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1);
+
+    let data = ndisc_packet(
+        Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 2),
+        Ipv6Address::LINK_LOCAL_ALL_NODES,
+        NdiscRepr::NeighborAdvert {
+            flags: NdiscNeighborFlags::OVERRIDE,
+            target_addr: addr,
+            lladdr: Some(RawHardwareAddress::from_bytes(&[0, 0, 0, 0, 0, 2])),
+        },
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(&data[..]).unwrap()
+        ),
+        None
+    );
+
+    assert_eq!(iface.dad_event(), Some(DadEvent::Duplicate(addr)));
+    assert!(!iface.is_tentative_addr(&addr));
+    assert!(iface.ip_addrs().is_empty());
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn dad_duplicate_solicitation() {
+    let (mut iface, mut sockets, _) = dad_setup();
+    let addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1);
+
+    // A solicitation for address resolution is not answered while the address is tentative.
+    let data = ndisc_packet(
+        Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 2),
+        addr.solicited_node(),
+        NdiscRepr::NeighborSolicit {
+            target_addr: addr,
+            lladdr: Some(RawHardwareAddress::from_bytes(&[0, 0, 0, 0, 0, 2])),
+        },
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(&data[..]).unwrap()
+        ),
+        None
+    );
+    assert!(iface.is_tentative_addr(&addr));
+    assert_eq!(iface.dad_event(), None);
+
+    // A solicitation from another node performing DAD for the same address is a conflict.
+    let data = ndisc_packet(
+        Ipv6Address::UNSPECIFIED,
+        addr.solicited_node(),
+        NdiscRepr::NeighborSolicit {
+            target_addr: addr,
+            lladdr: None,
+        },
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(&data[..]).unwrap()
+        ),
+        None
+    );
+    assert_eq!(iface.dad_event(), Some(DadEvent::Duplicate(addr)));
+    assert!(!iface.has_ip_addr(addr));
+}
+
+#[rstest]
+#[case::ip(Medium::Ip)]
+#[cfg(all(feature = "medium-ip", feature = "proto-ipv6-fragmentation"))]
+#[case::ethernet(Medium::Ethernet)]
+#[cfg(all(feature = "medium-ethernet", feature = "proto-ipv6-fragmentation"))]
+#[cfg(feature = "socket-udp")]
+fn ipv6_fragmentation(#[case] medium: Medium) {
+    let (mut iface, _, mut device) = setup(medium);
+
+    // Lower the MTU, such that the packet fits in the fragmentation buffer.
+    iface.inner.caps.max_transmission_unit = match medium {
+        #[cfg(feature = "medium-ethernet")]
+        Medium::Ethernet => 1014,
+        _ => 1000,
+    };
+
+    let src_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1);
+    let dst_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2);
+
+    #[cfg(feature = "medium-ethernet")]
+    if medium == Medium::Ethernet {
+        iface.inner.neighbor_cache.fill(
+            dst_addr.into(),
+            HardwareAddress::Ethernet(EthernetAddress([0x02, 0, 0, 0, 0, 1])),
+            Instant::ZERO,
+        );
+    }
+
+    let payload = [0x2a; 1400];
+    let udp_repr = UdpRepr {
+        src_port: 67,
+        dst_port: 68,
+    };
+    let ip_repr = Ipv6Repr {
+        src_addr,
+        dst_addr,
+        next_header: IpProtocol::Udp,
+        payload_len: udp_repr.header_len() + payload.len(),
+        hop_limit: 64,
+    };
+
+    let tx_token = device.transmit(Instant::ZERO).unwrap();
+    iface
+        .inner
+        .dispatch_ip(
+            tx_token,
+            PacketMeta::default(),
+            Packet::new_ipv6(ip_repr, IpPayload::Udp(udp_repr, &payload)),
+            &mut iface.fragmenter,
+        )
+        .unwrap();
+
+    // The first fragment is sent right away, the last one by the egress function.
+    assert!(iface.ipv6_egress(&mut device));
+    assert!(!iface.ipv6_egress(&mut device));
+
+    let mut reassembled = std::vec::Vec::new();
+    for (i, frame) in device.queue.iter().enumerate() {
+        let packet = match medium {
+            #[cfg(feature = "medium-ethernet")]
+            Medium::Ethernet => {
+                let frame = EthernetFrame::new_checked(&frame[..]).unwrap();
+                assert_eq!(frame.dst_addr(), EthernetAddress([0x02, 0, 0, 0, 0, 1]));
+                assert_eq!(frame.ethertype(), EthernetProtocol::Ipv6);
+                &frame.into_inner()[EthernetFrame::<&[u8]>::header_len()..]
+            }
+            _ => &frame[..],
+        };
+        assert!(packet.len() <= 1000);
+
+        let packet = Ipv6Packet::new_checked(packet).unwrap();
+        let repr = Ipv6Repr::parse(&packet).unwrap();
+        assert_eq!(repr.src_addr, src_addr);
+        assert_eq!(repr.dst_addr, dst_addr);
+        assert_eq!(repr.next_header, IpProtocol::Ipv6Frag);
+
+        let ext_header = Ipv6ExtHeader::new_checked(packet.payload()).unwrap();
+        let ext_repr = Ipv6ExtHeaderRepr::parse(&ext_header).unwrap();
+        assert_eq!(ext_repr.next_header, IpProtocol::Udp);
+        let frag_repr =
+            Ipv6FragmentRepr::parse(&Ipv6FragmentHeader::new_checked(ext_repr.data).unwrap())
+                .unwrap();
+
+        assert_eq!(frag_repr.frag_offset as usize * 8, reassembled.len());
+        assert_eq!(frag_repr.more_frags, i == 0);
+        reassembled.extend_from_slice(&packet.payload()[8..]);
+    }
+    assert_eq!(device.queue.len(), 2);
+
+    let udp = UdpPacket::new_checked(&reassembled[..]).unwrap();
+    assert!(udp.verify_checksum(&src_addr.into(), &dst_addr.into()));
+    assert_eq!(
+        UdpRepr::parse(
+            &udp,
+            &src_addr.into(),
+            &dst_addr.into(),
+            &ChecksumCapabilities::default()
+        ),
+        Ok(udp_repr)
+    );
+    assert_eq!(udp.payload(), &payload[..]);
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "proto-ipv6-fragmentation"))]
+fn ipv6_fragmentation_unfragmentable_part() {
+    let (mut iface, _, mut device) = setup(Medium::Ip);
+    iface.inner.caps.max_transmission_unit = 1000;
+
+    let src_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1);
+    let dst_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2);
+    let hop_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 3);
+
+    // A Hop-by-Hop Options header with a PadN option, followed by an RPL Source Routing Header.
+    let mut headers = vec![IpProtocol::Ipv6Route.into(), 0, 1, 4, 0, 0, 0, 0];
+    let routing = Ipv6RoutingRepr::Rpl {
+        segments_left: 1,
+        cmpr_i: 0,
+        cmpr_e: 0,
+        pad: 0,
+        addresses: hop_addr.as_bytes(),
+    };
+    let ext_hdr = Ipv6ExtHeaderRepr {
+        next_header: IpProtocol::Udp,
+        length: ((2 + routing.buffer_len()) / 8 - 1) as u8,
+        data: &[],
+    };
+    let start = headers.len();
+    headers.resize(start + ext_hdr.header_len() + routing.buffer_len(), 0);
+    ext_hdr.emit(&mut Ipv6ExtHeader::new_unchecked(&mut headers[start..]));
+    routing.emit(&mut Ipv6RoutingHeader::new_unchecked(
+        &mut headers[start + ext_hdr.header_len()..],
+    ));
+
+    let payload = (0..1400).map(|i| i as u8).collect::<std::vec::Vec<_>>();
+    let mut data = headers.clone();
+    data.extend_from_slice(&payload);
+
+    let ip_repr = Ipv6Repr {
+        src_addr,
+        dst_addr,
+        next_header: IpProtocol::HopByHop,
+        payload_len: data.len(),
+        hop_limit: 64,
+    };
+    let tx_token = device.transmit(Instant::ZERO).unwrap();
+    iface
+        .inner
+        .dispatch_ip(
+            tx_token,
+            PacketMeta::default(),
+            Packet::new_ipv6(ip_repr, IpPayload::Forward(IpProtocol::HopByHop, &data)),
+            &mut iface.fragmenter,
+        )
+        .unwrap();
+    assert!(iface.ipv6_egress(&mut device));
+    assert!(!iface.ipv6_egress(&mut device));
+
+    // Every fragment starts with the extension headers, followed by the Fragment header.
+    let mut reassembled = std::vec::Vec::new();
+    for (i, frame) in device.queue.iter().enumerate() {
+        assert!(frame.len() <= 1000);
+
+        let packet = Ipv6Packet::new_checked(&frame[..]).unwrap();
+        assert_eq!(packet.next_header(), IpProtocol::HopByHop);
+        assert_eq!(packet.payload_len() as usize, packet.payload().len());
+
+        let (hbh, rest) = packet.payload().split_at(8);
+        assert_eq!(hbh[0], u8::from(IpProtocol::Ipv6Route));
+        assert_eq!(&hbh[1..], &headers[1..8]);
+
+        let (routing, rest) = rest.split_at(headers.len() - 8);
+        assert_eq!(routing[0], u8::from(IpProtocol::Ipv6Frag));
+        assert_eq!(&routing[1..], &headers[9..]);
+
+        let ext_header = Ipv6ExtHeader::new_checked(rest).unwrap();
+        let ext_repr = Ipv6ExtHeaderRepr::parse(&ext_header).unwrap();
+        assert_eq!(ext_repr.next_header, IpProtocol::Udp);
+        let frag_repr =
+            Ipv6FragmentRepr::parse(&Ipv6FragmentHeader::new_checked(ext_repr.data).unwrap())
+                .unwrap();
+
+        assert_eq!(frag_repr.frag_offset as usize * 8, reassembled.len());
+        assert_eq!(frag_repr.more_frags, i == 0);
+        reassembled.extend_from_slice(&rest[8..]);
+    }
+    assert_eq!(device.queue.len(), 2);
+    assert_eq!(reassembled, payload);
+
+    // The fragments are reassembled into the original packet.
+    let mut original = vec![0u8; ip_repr.buffer_len() + data.len()];
+    ip_repr.emit(&mut Ipv6Packet::new_unchecked(&mut original[..]));
+    original[ip_repr.buffer_len()..].copy_from_slice(&data);
+
+    let first = device.queue.pop_front().unwrap();
+    let last = device.queue.pop_front().unwrap();
+    assert!(iface
+        .inner
+        .process_ipv6_fragment(
+            Ipv6Packet::new_checked(&first[..]).unwrap(),
+            &mut iface.fragments
+        )
+        .is_none());
+    let packet = iface
+        .inner
+        .process_ipv6_fragment(
+            Ipv6Packet::new_checked(&last[..]).unwrap(),
+            &mut iface.fragments,
+        )
+        .unwrap();
+    assert_eq!(packet.into_inner(), &original[..]);
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-tcp"))]
+fn icmp_error_aborts_tcp_connect() {
+    use crate::socket::tcp;
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let local_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1);
+    let remote_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2);
+    let router_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 0xff);
+
+    let rx_buffer = tcp::SocketBuffer::new(vec![0; 64]);
+    let tx_buffer = tcp::SocketBuffer::new(vec![0; 64]);
+    let socket_handle = sockets.add(tcp::Socket::new(rx_buffer, tx_buffer));
+    sockets
+        .get_mut::<tcp::Socket>(socket_handle)
+        .connect(iface.context(), (remote_addr, 80), (local_addr, 49500))
+        .unwrap();
+
+    // Send the SYN, which is quoted by the ICMP errors.
+    assert!(iface.socket_egress(&mut device, &mut sockets, |_, _| true));
+    let frame = device.queue.pop_front().unwrap();
+    let syn = Ipv6Packet::new_checked(&frame[..]).unwrap();
+    assert_eq!(syn.next_header(), IpProtocol::Tcp);
+    let mut syn_bytes = syn.payload()[..8].to_vec();
+
+    let mut icmp_error = |reason, data: &[u8]| {
+        let icmp_repr = Icmpv6Repr::DstUnreachable {
+            reason,
+            header: Ipv6Repr::parse(&syn).unwrap(),
+            data,
+        };
+        let ip_repr = Ipv6Repr {
+            src_addr: router_addr,
+            dst_addr: local_addr,
+            next_header: IpProtocol::Icmpv6,
+            payload_len: icmp_repr.buffer_len(),
+            hop_limit: 64,
+        };
+        let mut bytes = vec![0; icmp_repr.buffer_len()];
+        icmp_repr.emit(
+            &router_addr,
+            &local_addr,
+            &mut Icmpv6Packet::new_unchecked(&mut bytes[..]),
+            &ChecksumCapabilities::default(),
+        );
+        assert_eq!(
+            iface.inner.process_icmpv6(&mut sockets, ip_repr, &bytes),
+            None
+        );
+        sockets.get_mut::<tcp::Socket>(socket_handle).state()
+    };
+
+    // Soft errors don't abort the connection.
+    assert_eq!(
+        icmp_error(Icmpv6DstUnreachable::AddrUnreachable, &syn_bytes),
+        tcp::State::SynSent
+    );
+
+    // Hard errors must quote the initial sequence number.
+    syn_bytes[4..8].copy_from_slice(&[0; 4]);
+    assert_eq!(
+        icmp_error(Icmpv6DstUnreachable::PortUnreachable, &syn_bytes),
+        tcp::State::SynSent
+    );
+    syn_bytes.copy_from_slice(&syn.payload()[..8]);
+    assert_eq!(
+        icmp_error(Icmpv6DstUnreachable::PortUnreachable, &syn_bytes),
+        tcp::State::Closed
+    );
+}
+
+#[rstest]
+#[case::ip(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case::ethernet(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+#[case::ieee802154(Medium::Ieee802154)]
+#[cfg(feature = "medium-ieee802154")]
+fn icmp_packet_too_big(#[case] medium: Medium) {
+    let (mut iface, mut sockets, _device) = setup(medium);
+
+    let src_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1);
+    let dst_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2);
+    let other_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 3);
+    let router_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 0xff);
+    let ip_mtu = iface.inner.ip_mtu();
+
+    let mut packet_too_big = |iface: &mut Interface, src_addr, dst_addr, mtu| {
+        let icmp_repr = Icmpv6Repr::PktTooBig {
+            mtu,
+            header: Ipv6Repr {
+                src_addr,
+                dst_addr,
+                next_header: IpProtocol::Udp,
+                payload_len: 1400,
+                hop_limit: 64,
+            },
+            data: &[0; 8],
+        };
+        let ip_repr = Ipv6Repr {
+            src_addr: router_addr,
+            dst_addr: src_addr,
+            next_header: IpProtocol::Icmpv6,
+            payload_len: icmp_repr.buffer_len(),
+            hop_limit: 64,
+        };
+        let mut bytes = vec![0; icmp_repr.buffer_len()];
+        icmp_repr.emit(
+            &router_addr,
+            &src_addr,
+            &mut Icmpv6Packet::new_unchecked(&mut bytes[..]),
+            &ChecksumCapabilities::default(),
+        );
+        assert_eq!(
+            iface.inner.process_icmpv6(&mut sockets, ip_repr, &bytes),
+            None
+        );
+    };
+
+    // Packets that we did not send are ignored.
+    packet_too_big(&mut iface, other_addr, dst_addr, 1300);
+    assert_eq!(iface.path_mtu(dst_addr.into()), ip_mtu);
+
+    packet_too_big(&mut iface, src_addr, dst_addr, 1300);
+    assert_eq!(iface.path_mtu(dst_addr.into()), 1300);
+
+    // The estimate is not raised, and never lowered below the minimum IPv6 MTU.
+    packet_too_big(&mut iface, src_addr, dst_addr, 1400);
+    assert_eq!(iface.path_mtu(dst_addr.into()), 1300);
+    packet_too_big(&mut iface, src_addr, dst_addr, 600);
+    assert_eq!(iface.path_mtu(dst_addr.into()), IPV6_MIN_MTU);
+    assert_eq!(iface.path_mtu(other_addr.into()), ip_mtu);
+}
+
+#[rstest]
+#[case::ip(Medium::Ip)]
+#[cfg(all(feature = "medium-ip", feature = "proto-ipv6-fragmentation"))]
+#[case::ethernet(Medium::Ethernet)]
+#[cfg(all(feature = "medium-ethernet", feature = "proto-ipv6-fragmentation"))]
+#[cfg(feature = "socket-udp")]
+fn ipv6_reassembly(#[case] medium: Medium) {
+    use crate::socket::udp;
+
+    let (mut iface, mut sockets, mut device) = setup(medium);
+
+    // Lower the MTU, such that the packet fits in the fragmentation buffer.
+    iface.inner.caps.max_transmission_unit = match medium {
+        #[cfg(feature = "medium-ethernet")]
+        Medium::Ethernet => 1014,
+        _ => 1000,
+    };
+
+    let src_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2);
+    let dst_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1);
+
+    // The fragments are sent to our own hardware address, such that we can process them.
+    #[cfg(feature = "medium-ethernet")]
+    if medium == Medium::Ethernet {
+        iface
+            .inner
+            .neighbor_cache
+            .fill(dst_addr.into(), iface.inner.hardware_addr, Instant::ZERO);
+    }
+
+    let rx_buffer = udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 1500]);
+    let tx_buffer = udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 15]);
+    let socket_handle = sockets.add(udp::Socket::new(rx_buffer, tx_buffer));
+    sockets
+        .get_mut::<udp::Socket>(socket_handle)
+        .bind(68)
+        .unwrap();
+
+    let payload: std::vec::Vec<u8> = (0..1400).map(|i| i as u8).collect();
+    let udp_repr = UdpRepr {
+        src_port: 67,
+        dst_port: 68,
+    };
+    let ip_repr = Ipv6Repr {
+        src_addr,
+        dst_addr,
+        next_header: IpProtocol::Udp,
+        payload_len: udp_repr.header_len() + payload.len(),
+        hop_limit: 64,
+    };
+
+    let tx_token = device.transmit(Instant::ZERO).unwrap();
+    iface
+        .inner
+        .dispatch_ip(
+            tx_token,
+            PacketMeta::default(),
+            Packet::new_ipv6(ip_repr, IpPayload::Udp(udp_repr, &payload)),
+            &mut iface.fragmenter,
+        )
+        .unwrap();
+    assert!(iface.ipv6_egress(&mut device));
+    assert_eq!(device.queue.len(), 2);
+
+    // Process the fragments in reverse order.
+    while let Some(frame) = device.queue.pop_back() {
+        let response = match medium {
+            #[cfg(feature = "medium-ethernet")]
+            Medium::Ethernet => iface
+                .inner
+                .process_ethernet(
+                    &mut sockets,
+                    PacketMeta::default(),
+                    &frame,
+                    &mut iface.fragments,
+                )
+                .is_some(),
+            _ => iface
+                .inner
+                .process_ip(
+                    &mut sockets,
+                    PacketMeta::default(),
+                    &frame,
+                    &mut iface.fragments,
+                )
+                .is_some(),
+        };
+        assert!(!response);
+    }
+
+    let socket = sockets.get_mut::<udp::Socket>(socket_handle);
+    let (data, metadata) = socket.recv().unwrap();
+    assert_eq!(data, &payload[..]);
+    assert_eq!(metadata.endpoint, IpEndpoint::new(src_addr.into(), 67));
+}
+
+#[rstest]
+#[case::ip(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case::ethernet(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+#[case::ieee802154(Medium::Ieee802154)]
+#[cfg(feature = "medium-ieee802154")]
+fn forward(#[case] medium: Medium) {
+    static PAYLOAD: [u8; 8] = [0x04, 0xd2, 0x16, 0x2e, 0x00, 0x08, 0x00, 0x00];
+
+    let (mut iface, mut sockets, _device) = setup(medium);
+
+    let src_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2);
+    let ipv6_repr = |dst_addr, hop_limit| Ipv6Repr {
+        src_addr,
+        dst_addr,
+        next_header: IpProtocol::Udp,
+        payload_len: PAYLOAD.len(),
+        hop_limit,
+    };
+    let ipv6_packet = |ipv6_repr: Ipv6Repr| {
+        let mut bytes = vec![0; ipv6_repr.buffer_len() + PAYLOAD.len()];
+        ipv6_repr.emit(&mut Ipv6Packet::new_unchecked(&mut bytes[..]));
+        bytes[ipv6_repr.buffer_len()..].copy_from_slice(&PAYLOAD);
+        bytes
+    };
+
+    let dst_addr = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2);
+    let bytes = ipv6_packet(ipv6_repr(dst_addr, 64));
+
+    // Packets that are not addressed to us are dropped, unless forwarding is enabled.
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(&bytes[..]).unwrap()
+        ),
+        None
+    );
+
+    iface.set_forwarding(true);
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(&bytes[..]).unwrap()
+        ),
+        Some(Packet::new_ipv6(
+            ipv6_repr(dst_addr, 63),
+            IpPayload::Forward(IpProtocol::Udp, &PAYLOAD)
+        ))
+    );
+
+    // The source is told when the hop limit is exceeded.
+    let bytes = ipv6_packet(ipv6_repr(dst_addr, 1));
+    let icmp_repr = Icmpv6Repr::TimeExceeded {
+        reason: Icmpv6TimeExceeded::HopLimitExceeded,
+        header: ipv6_repr(dst_addr, 1),
+        data: &PAYLOAD,
+    };
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(&bytes[..]).unwrap()
+        ),
+        Some(Packet::new_ipv6(
+            Ipv6Repr {
+                src_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1),
+                dst_addr: src_addr,
+                next_header: IpProtocol::Icmpv6,
+                payload_len: icmp_repr.buffer_len(),
+                hop_limit: 64,
+            },
+            IpPayload::Icmpv6(icmp_repr)
+        ))
+    );
+
+    // Link-local destinations are not forwarded.
+    let bytes = ipv6_packet(ipv6_repr(Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 2), 64));
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(&bytes[..]).unwrap()
+        ),
+        None
+    );
+}
+
+#[rstest]
+#[case::ip(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case::ethernet(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn forward_hop_by_hop(#[case] medium: Medium) {
+    // A Hop-by-Hop Options header with a PadN option, followed by a UDP header.
+    static PAYLOAD: [u8; 16] = [
+        0x11, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00, 0x04, 0xd2, 0x16, 0x2e, 0x00, 0x08, 0x00,
+        0x00,
+    ];
+
+    let (mut iface, mut sockets, _device) = setup(medium);
+    iface.set_forwarding(true);
+
+    let src_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2);
+    let ipv6_repr = |hop_limit| Ipv6Repr {
+        src_addr,
+        dst_addr: Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2),
+        next_header: IpProtocol::HopByHop,
+        payload_len: PAYLOAD.len(),
+        hop_limit,
+    };
+    let ipv6_packet = |ipv6_repr: Ipv6Repr| {
+        let mut bytes = vec![0; ipv6_repr.buffer_len() + PAYLOAD.len()];
+        ipv6_repr.emit(&mut Ipv6Packet::new_unchecked(&mut bytes[..]));
+        bytes[ipv6_repr.buffer_len()..].copy_from_slice(&PAYLOAD);
+        bytes
+    };
+
+    // The Hop-by-Hop Options header is forwarded with the rest of the packet.
+    let bytes = ipv6_packet(ipv6_repr(64));
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(&bytes[..]).unwrap()
+        ),
+        Some(Packet::new_ipv6(
+            ipv6_repr(63),
+            IpPayload::Forward(IpProtocol::HopByHop, &PAYLOAD)
+        ))
+    );
+
+    // The ICMP error quotes the packet as it was received.
+    let bytes = ipv6_packet(ipv6_repr(1));
+    let icmp_repr = Icmpv6Repr::TimeExceeded {
+        reason: Icmpv6TimeExceeded::HopLimitExceeded,
+        header: ipv6_repr(1),
+        data: &PAYLOAD,
+    };
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(&bytes[..]).unwrap()
+        ),
+        Some(Packet::new_ipv6(
+            Ipv6Repr {
+                src_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1),
+                dst_addr: src_addr,
+                next_header: IpProtocol::Icmpv6,
+                payload_len: icmp_repr.buffer_len(),
+                hop_limit: 64,
+            },
+            IpPayload::Icmpv6(icmp_repr)
+        ))
+    );
+}
+
+#[rstest]
+#[case::ip(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case::ethernet(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn route_mtu_minimum(#[case] medium: Medium) {
+    let (mut iface, _sockets, _device) = setup(medium);
+
+    iface
+        .routes_mut()
+        .add_route(
+            Route::new(
+                IpCidr::new(IpAddress::v6(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 64),
+                IpAddress::v6(0xfdbe, 0, 0, 0, 0, 0, 0, 0xff),
+            )
+            .with_mtu(30),
+        )
+        .unwrap();
+
+    // A route MTU smaller than the minimum IPv6 MTU is raised to it.
+    assert_eq!(
+        iface.path_mtu(IpAddress::v6(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2)),
+        IPV6_MIN_MTU
+    );
+}
+
+#[rstest]
+#[case::ip(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case::ethernet(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn forward_too_big(#[case] medium: Medium) {
+    let mut payload = [0x2a; 1300];
+    payload[..8].copy_from_slice(&[0x04, 0xd2, 0x16, 0x2e, 0x05, 0x14, 0x00, 0x00]);
+
+    let (mut iface, mut sockets, _device) = setup(medium);
+    iface.set_forwarding(true);
+    iface
+        .routes_mut()
+        .add_route(
+            Route::new(
+                IpCidr::new(IpAddress::v6(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 64),
+                IpAddress::v6(0xfdbe, 0, 0, 0, 0, 0, 0, 0xff),
+            )
+            .with_mtu(1280),
+        )
+        .unwrap();
+
+    let src_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2);
+    let ipv6_repr = Ipv6Repr {
+        src_addr,
+        dst_addr: Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2),
+        next_header: IpProtocol::Udp,
+        payload_len: payload.len(),
+        hop_limit: 64,
+    };
+    let mut bytes = vec![0; ipv6_repr.buffer_len() + payload.len()];
+    ipv6_repr.emit(&mut Ipv6Packet::new_unchecked(&mut bytes[..]));
+    bytes[ipv6_repr.buffer_len()..].copy_from_slice(&payload);
+
+    // A packet that does not fit in the MTU of the route is dropped, and the source is told
+    // the MTU.
+    let packet = iface
+        .inner
+        .process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6Packet::new_checked(&bytes[..]).unwrap(),
+        )
+        .unwrap();
+    let icmp_repr = Icmpv6Repr::PktTooBig {
+        mtu: 1280,
+        header: ipv6_repr,
+        data: &payload[..1192],
+    };
+    assert_eq!(
+        super::super::forward::forward_packet(&iface.inner, packet, &mut |_, packet| Some(packet)),
+        Some(Packet::new_ipv6(
+            Ipv6Repr {
+                src_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1),
+                dst_addr: src_addr,
+                next_header: IpProtocol::Icmpv6,
+                payload_len: icmp_repr.buffer_len(),
+                hop_limit: 64,
+            },
+            IpPayload::Icmpv6(icmp_repr)
+        ))
+    );
+}
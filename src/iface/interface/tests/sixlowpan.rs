@@ -442,3 +442,814 @@ In at rhoncus tortor. Cras blandit tellus diam, varius vestibulum nibh commodo n
         ]
     );
 }
+
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+use crate::iface::{RplConfig, RplRole, RplRootConfig};
+
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+const RPL_ROOT_LL_ADDR: Ieee802154Address =
+    Ieee802154Address::Extended([0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01]);
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+const RPL_NODE_LL_ADDR: Ieee802154Address =
+    Ieee802154Address::Extended([0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02]);
+
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+const RPL_CHILD_LL_ADDR: Ieee802154Address =
+    Ieee802154Address::Extended([0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03]);
+
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_setup<'a>(
+    ll_addr: Ieee802154Address,
+    role: RplRole,
+) -> (Interface, SocketSet<'a>, crate::tests::TestingDevice) {
+    rpl_setup_with_config(ll_addr, RplConfig::new(role))
+}
+
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_setup_with_config<'a>(
+    ll_addr: Ieee802154Address,
+    rpl_config: RplConfig,
+) -> (Interface, SocketSet<'a>, crate::tests::TestingDevice) {
+    let mut device = crate::tests::TestingDevice::new(Medium::Ieee802154);
+
+    let mut config = Config::new(ll_addr.into());
+    config.pan_id = Some(Ieee802154Pan(0xbeef));
+    config.rpl_config = Some(rpl_config);
+
+    let mut iface = Interface::new(config, &mut device, Instant::ZERO);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs
+            .push(IpCidr::Ipv6(Ipv6Cidr::new(
+                ll_addr.as_link_local_address().unwrap(),
+                64,
+            )))
+            .unwrap();
+    });
+
+    (iface, SocketSet::new(vec![]), device)
+}
+
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_root_setup<'a>() -> (Interface, SocketSet<'a>, crate::tests::TestingDevice) {
+    let dodag_id = RPL_ROOT_LL_ADDR.as_link_local_address().unwrap();
+    rpl_setup(
+        RPL_ROOT_LL_ADDR,
+        RplRole::Root(RplRootConfig::new(dodag_id)),
+    )
+}
+
+/// Transmit the RPL control message that is due on `from`, and let `to` process it.
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_exchange(
+    from: &mut Interface,
+    from_device: &mut crate::tests::TestingDevice,
+    to: &mut Interface,
+    to_sockets: &mut SocketSet,
+    timestamp: Instant,
+) -> bool {
+    from.inner.now = timestamp;
+    to.inner.now = timestamp;
+
+    if !from.rpl_egress(from_device) {
+        return false;
+    }
+
+    let frame = from_device.queue.pop_front().unwrap();
+    to.inner.process_ieee802154(
+        to_sockets,
+        PacketMeta::default(),
+        &frame[..],
+        &mut to.fragments,
+    );
+
+    true
+}
+
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_dodag(iface: &Interface) -> Option<&crate::iface::rpl::Dodag> {
+    iface.inner.rpl.as_ref().unwrap().dodag.as_ref()
+}
+
+#[test]
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_root_creates_dodag() {
+    let (iface, _, _) = rpl_root_setup();
+
+    let dodag = rpl_dodag(&iface).unwrap();
+    assert_eq!(dodag.id, RPL_ROOT_LL_ADDR.as_link_local_address().unwrap());
+    assert_eq!(dodag.rank.raw_value(), 256);
+    assert_eq!(dodag.parent, None);
+}
+
+#[test]
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_router_joins_dodag() {
+    let (mut root, _, mut root_device) = rpl_root_setup();
+    let (mut router, mut router_sockets, _) = rpl_setup(RPL_NODE_LL_ADDR, RplRole::Router);
+    let root_addr = RPL_ROOT_LL_ADDR.as_link_local_address().unwrap();
+
+    // The root does not transmit before the Trickle timer expires.
+    root.inner.now = Instant::ZERO;
+    assert!(!root.rpl_egress(&mut root_device));
+
+    let now = Instant::from_secs(5);
+    assert!(rpl_exchange(
+        &mut root,
+        &mut root_device,
+        &mut router,
+        &mut router_sockets,
+        now
+    ));
+
+    let dodag = rpl_dodag(&router).unwrap();
+    assert_eq!(dodag.id, root_addr);
+    assert_eq!(dodag.parent, Some(root_addr));
+    assert_eq!(dodag.rank.raw_value(), 1024);
+
+    // A default route via the preferred parent is installed.
+    assert_eq!(
+        router.inner.routes.lookup(
+            &IpAddress::v6(0xfd00, 0, 0, 0, 0, 0, 0, 1),
+            Instant::from_secs(5)
+        ),
+        Some(IpAddress::Ipv6(root_addr))
+    );
+}
+
+#[test]
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_router_sends_dis() {
+    let (mut root, mut root_sockets, _) = rpl_root_setup();
+    let (mut router, _, mut router_device) = rpl_setup(RPL_NODE_LL_ADDR, RplRole::Router);
+
+    assert!(rpl_exchange(
+        &mut router,
+        &mut router_device,
+        &mut root,
+        &mut root_sockets,
+        Instant::ZERO
+    ));
+
+    // The next DIS is only sent after the DIS interval.
+    router.inner.now = Instant::from_secs(1);
+    assert!(!router.rpl_egress(&mut router_device));
+    router.inner.now = Instant::from_secs(60);
+    assert!(router.rpl_egress(&mut router_device));
+
+    // The root learned the link-layer address of the router from the DIS.
+    let router_addr = RPL_NODE_LL_ADDR.as_link_local_address().unwrap();
+    assert!(root.inner.has_neighbor(&router_addr.into()));
+}
+
+#[test]
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_unicast_dis_is_answered_with_unicast_dio() {
+    let (mut root, mut root_sockets, mut root_device) = rpl_root_setup();
+    let (mut router, mut router_sockets, mut router_device) =
+        rpl_setup(RPL_NODE_LL_ADDR, RplRole::Router);
+    let root_addr = RPL_ROOT_LL_ADDR.as_link_local_address().unwrap();
+    let router_addr = RPL_NODE_LL_ADDR.as_link_local_address().unwrap();
+
+    // The multicast DIS makes the root learn the link-layer address of the router.
+    assert!(rpl_exchange(
+        &mut router,
+        &mut router_device,
+        &mut root,
+        &mut root_sockets,
+        Instant::ZERO
+    ));
+
+    root.inner.process_rpl(
+        Ipv6Repr {
+            src_addr: router_addr,
+            dst_addr: root_addr,
+            next_header: IpProtocol::Icmpv6,
+            payload_len: 0,
+            hop_limit: 64,
+        },
+        RplRepr::DodagInformationSolicitation { options: &[] },
+    );
+
+    // The DIO is sent immediately, without waiting for the Trickle timer.
+    assert_eq!(
+        root.poll_at(Instant::ZERO, &root_sockets),
+        Some(Instant::ZERO)
+    );
+    assert!(rpl_exchange(
+        &mut root,
+        &mut root_device,
+        &mut router,
+        &mut router_sockets,
+        Instant::ZERO
+    ));
+    assert_eq!(rpl_dodag(&router).unwrap().parent, Some(root_addr));
+}
+
+#[test]
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_global_repair() {
+    let (mut root, _, mut root_device) = rpl_root_setup();
+    let (mut router, mut router_sockets, _) = rpl_setup(RPL_NODE_LL_ADDR, RplRole::Router);
+
+    assert!(rpl_exchange(
+        &mut root,
+        &mut root_device,
+        &mut router,
+        &mut router_sockets,
+        Instant::from_secs(5)
+    ));
+    let version = rpl_dodag(&router).unwrap().version_number;
+
+    assert!(root.rpl_global_repair());
+    assert!(!router.rpl_global_repair());
+
+    assert!(rpl_exchange(
+        &mut root,
+        &mut root_device,
+        &mut router,
+        &mut router_sockets,
+        Instant::from_secs(10)
+    ));
+
+    let dodag = rpl_dodag(&router).unwrap();
+    assert!(dodag.version_number > version);
+    assert_eq!(
+        dodag.version_number,
+        rpl_dodag(&root).unwrap().version_number
+    );
+    assert_eq!(
+        dodag.parent,
+        Some(RPL_ROOT_LL_ADDR.as_link_local_address().unwrap())
+    );
+    assert_eq!(dodag.rank.raw_value(), 1024);
+}
+
+#[test]
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_poisoned_parent() {
+    let (mut root, _, mut root_device) = rpl_root_setup();
+    let (mut router, mut router_sockets, mut router_device) =
+        rpl_setup(RPL_NODE_LL_ADDR, RplRole::Router);
+    let root_addr = RPL_ROOT_LL_ADDR.as_link_local_address().unwrap();
+
+    assert!(rpl_exchange(
+        &mut root,
+        &mut root_device,
+        &mut router,
+        &mut router_sockets,
+        Instant::from_secs(5)
+    ));
+    assert_eq!(rpl_dodag(&router).unwrap().parent, Some(root_addr));
+
+    let root_dodag = rpl_dodag(&root).unwrap();
+    router.inner.process_rpl(
+        Ipv6Repr {
+            src_addr: root_addr,
+            dst_addr: Ipv6Address::LINK_LOCAL_ALL_RPL_NODES,
+            next_header: IpProtocol::Icmpv6,
+            payload_len: 0,
+            hop_limit: 64,
+        },
+        RplRepr::DodagInformationObject {
+            rpl_instance_id: RplInstanceId::Global(30),
+            version_number: root_dodag.version_number.value(),
+            rank: 0xffff,
+            grounded: false,
+            mode_of_operation: RplModeOfOperation::NoDownwardRoutesMaintained,
+            dodag_preference: 0,
+            dtsn: 0,
+            dodag_id: root_addr,
+            options: &[],
+        },
+    );
+
+    let dodag = rpl_dodag(&router).unwrap();
+    assert_eq!(dodag.parent, None);
+    assert_eq!(dodag.rank, crate::iface::rpl::Rank::INFINITE);
+    assert_eq!(
+        router.inner.routes.lookup(
+            &IpAddress::v6(0xfd00, 0, 0, 0, 0, 0, 0, 1),
+            Instant::from_secs(5)
+        ),
+        None
+    );
+
+    // The router leaves the DODAG when it does not find a new parent, and looks for a new DODAG.
+    router.inner.now = Instant::from_secs(15);
+    assert!(router.rpl_egress(&mut router_device));
+    assert!(rpl_dodag(&router).is_none());
+
+    let frame = router_device.queue.pop_back().unwrap();
+    let frame = Ieee802154Frame::new_checked(&frame[..]).unwrap();
+    assert_eq!(frame.src_addr(), Some(RPL_NODE_LL_ADDR));
+}
+
+#[test]
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_leaf_does_not_send_dio() {
+    let (mut root, _, mut root_device) = rpl_root_setup();
+    let (mut leaf, mut leaf_sockets, mut leaf_device) = rpl_setup(RPL_NODE_LL_ADDR, RplRole::Leaf);
+
+    assert!(rpl_exchange(
+        &mut root,
+        &mut root_device,
+        &mut leaf,
+        &mut leaf_sockets,
+        Instant::from_secs(5)
+    ));
+    assert!(rpl_dodag(&leaf).is_some());
+
+    for i in 5..100 {
+        leaf.inner.now = Instant::from_secs(i);
+        assert!(!leaf.rpl_egress(&mut leaf_device));
+    }
+}
+
+/// Return the fd00::/64 address of a node, formed using the interface identifier of its link-local
+/// address.
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_global_address(ll_addr: Ieee802154Address) -> Ipv6Address {
+    let mut addr = ll_addr.as_link_local_address().unwrap();
+    addr.0[..8].copy_from_slice(&[0xfd, 0, 0, 0, 0, 0, 0, 0]);
+    addr
+}
+
+/// Create an interface that is part of a non-storing mode DODAG, with a global address. The root
+/// uses its global address as DODAG ID.
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_non_storing_setup<'a>(
+    ll_addr: Ieee802154Address,
+) -> (Interface, SocketSet<'a>, crate::tests::TestingDevice) {
+    let global_addr = rpl_global_address(ll_addr);
+    let (role, prefix_len) = if ll_addr == RPL_ROOT_LL_ADDR {
+        (RplRole::Root(RplRootConfig::new(global_addr)), 64)
+    } else {
+        (RplRole::Router, 128)
+    };
+
+    let mut rpl_config = RplConfig::new(role);
+    rpl_config.mode_of_operation = RplModeOfOperation::NonStoringMode;
+
+    let (mut iface, sockets, device) = rpl_setup_with_config(ll_addr, rpl_config);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs
+            .push(IpCidr::Ipv6(Ipv6Cidr::new(global_addr, prefix_len)))
+            .unwrap();
+    });
+
+    (iface, sockets, device)
+}
+
+/// Let `iface` receive `frame`, and return the frame it transmits in response.
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_receive(
+    iface: &mut Interface,
+    device: &mut crate::tests::TestingDevice,
+    sockets: &mut SocketSet,
+    frame: &[u8],
+    timestamp: Instant,
+) -> Option<heapless::Vec<u8, 1514>> {
+    iface.inner.now = timestamp;
+    let packet = iface.inner.process_ieee802154(
+        sockets,
+        PacketMeta::default(),
+        frame,
+        &mut iface.fragments,
+    )?;
+    let tx_token = device.transmit(timestamp).unwrap();
+    iface
+        .inner
+        .dispatch_ip(
+            tx_token,
+            PacketMeta::default(),
+            packet,
+            &mut iface.fragmenter,
+        )
+        .unwrap();
+    device.queue.pop_front()
+}
+
+/// Let `iface` transmit everything it has to send at `timestamp`, dropping the frames.
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_egress_all(
+    iface: &mut Interface,
+    device: &mut crate::tests::TestingDevice,
+    timestamp: Instant,
+) {
+    iface.inner.now = timestamp;
+    while iface.rpl_egress(device) {
+        device.queue.clear();
+    }
+}
+
+#[test]
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_dao_is_acknowledged() {
+    let (mut root, mut root_sockets, mut root_device) = rpl_non_storing_setup(RPL_ROOT_LL_ADDR);
+    let (mut router, mut router_sockets, mut router_device) =
+        rpl_non_storing_setup(RPL_NODE_LL_ADDR);
+    let root_addr = rpl_global_address(RPL_ROOT_LL_ADDR);
+    let router_addr = rpl_global_address(RPL_NODE_LL_ADDR);
+
+    let now = Instant::from_secs(5);
+    assert!(rpl_exchange(
+        &mut root,
+        &mut root_device,
+        &mut router,
+        &mut router_sockets,
+        now
+    ));
+
+    // After joining the DODAG, the router advertises its address to the root.
+    assert!(router.rpl_egress(&mut router_device));
+    let dao = router_device.queue.pop_front().unwrap();
+    assert_eq!(
+        rpl_dodag(&router).unwrap().dao_transmissions,
+        Some(1),
+        "the DAO is awaiting an acknowledgement"
+    );
+
+    let dao_ack = rpl_receive(&mut root, &mut root_device, &mut root_sockets, &dao, now).unwrap();
+    assert_eq!(
+        rpl_dodag(&root)
+            .unwrap()
+            .relations
+            .find_next_hop(router_addr),
+        Some(root_addr)
+    );
+
+    // The DAO-ACK is sent directly to the router.
+    let frame = Ieee802154Frame::new_checked(&dao_ack[..]).unwrap();
+    assert_eq!(frame.dst_addr(), Some(RPL_NODE_LL_ADDR));
+
+    rpl_receive(
+        &mut router,
+        &mut router_device,
+        &mut router_sockets,
+        &dao_ack,
+        now,
+    );
+    let dodag = rpl_dodag(&router).unwrap();
+    assert_eq!(dodag.dao_transmissions, None);
+    assert_eq!(dodag.dao_expiration, now + Duration::from_secs(15 * 60));
+
+    // No DAO is sent until it needs to be refreshed.
+    rpl_egress_all(&mut router, &mut router_device, Instant::from_secs(60));
+    assert_eq!(rpl_dodag(&router).unwrap().dao_transmissions, None);
+    rpl_egress_all(
+        &mut router,
+        &mut router_device,
+        now + Duration::from_secs(15 * 60),
+    );
+    assert_eq!(rpl_dodag(&router).unwrap().dao_transmissions, Some(1));
+}
+
+#[test]
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_dao_is_retransmitted() {
+    let (mut root, _, mut root_device) = rpl_non_storing_setup(RPL_ROOT_LL_ADDR);
+    let (mut router, mut router_sockets, mut router_device) =
+        rpl_non_storing_setup(RPL_NODE_LL_ADDR);
+
+    assert!(rpl_exchange(
+        &mut root,
+        &mut root_device,
+        &mut router,
+        &mut router_sockets,
+        Instant::from_secs(5)
+    ));
+
+    let mut now = Instant::from_secs(5);
+    rpl_egress_all(&mut router, &mut router_device, now);
+    assert_eq!(rpl_dodag(&router).unwrap().dao_transmissions, Some(1));
+    let sequence = rpl_dodag(&router).unwrap().dao_seq_number;
+
+    // Without DAO-ACK, the DAO is retransmitted with the same sequence number.
+    for transmissions in 2..=6 {
+        now += Duration::from_secs(5);
+        rpl_egress_all(&mut router, &mut router_device, now);
+
+        let dodag = rpl_dodag(&router).unwrap();
+        assert_eq!(dodag.dao_transmissions, Some(transmissions));
+        assert_eq!(dodag.dao_seq_number, sequence);
+    }
+
+    // Finally, the router gives up until the DAO needs to be refreshed.
+    now += Duration::from_secs(5);
+    rpl_egress_all(&mut router, &mut router_device, now);
+    let dodag = rpl_dodag(&router).unwrap();
+    assert_eq!(dodag.dao_transmissions, None);
+    assert!(dodag.dao_expiration > now + Duration::from_secs(60));
+}
+
+#[test]
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_source_routing() {
+    let (mut root, mut root_sockets, mut root_device) = rpl_non_storing_setup(RPL_ROOT_LL_ADDR);
+    let (mut router, mut router_sockets, mut router_device) =
+        rpl_non_storing_setup(RPL_NODE_LL_ADDR);
+    let (mut child, mut child_sockets, mut child_device) = rpl_non_storing_setup(RPL_CHILD_LL_ADDR);
+    let router_addr = rpl_global_address(RPL_NODE_LL_ADDR);
+    let child_addr = rpl_global_address(RPL_CHILD_LL_ADDR);
+
+    // The router joins the DODAG of the root, and advertises its address.
+    let now = Instant::from_secs(5);
+    assert!(rpl_exchange(
+        &mut root,
+        &mut root_device,
+        &mut router,
+        &mut router_sockets,
+        now
+    ));
+    assert!(rpl_exchange(
+        &mut router,
+        &mut router_device,
+        &mut root,
+        &mut root_sockets,
+        now
+    ));
+    root_device.queue.clear();
+
+    // The child only hears the router, and advertises its address through the router.
+    let now = Instant::from_secs(10);
+    assert!(rpl_exchange(
+        &mut router,
+        &mut router_device,
+        &mut child,
+        &mut child_sockets,
+        now
+    ));
+    assert_eq!(
+        rpl_dodag(&child).unwrap().parent,
+        Some(RPL_NODE_LL_ADDR.as_link_local_address().unwrap())
+    );
+
+    child.inner.now = now;
+    assert!(child.rpl_egress(&mut child_device));
+    let dao = child_device.queue.pop_front().unwrap();
+
+    // The router forwards the DAO to the root.
+    let dao = rpl_receive(
+        &mut router,
+        &mut router_device,
+        &mut router_sockets,
+        &dao,
+        now,
+    )
+    .unwrap();
+    let frame = Ieee802154Frame::new_checked(&dao[..]).unwrap();
+    assert_eq!(frame.dst_addr(), Some(RPL_ROOT_LL_ADDR));
+
+    let dao_ack = rpl_receive(&mut root, &mut root_device, &mut root_sockets, &dao, now).unwrap();
+    assert_eq!(
+        rpl_dodag(&root)
+            .unwrap()
+            .relations
+            .find_next_hop(child_addr),
+        Some(router_addr)
+    );
+
+    // The root source routes the DAO-ACK via the router.
+    let frame = Ieee802154Frame::new_checked(&dao_ack[..]).unwrap();
+    assert_eq!(frame.dst_addr(), Some(RPL_NODE_LL_ADDR));
+
+    let dao_ack = rpl_receive(
+        &mut router,
+        &mut router_device,
+        &mut router_sockets,
+        &dao_ack,
+        now,
+    )
+    .unwrap();
+    let frame = Ieee802154Frame::new_checked(&dao_ack[..]).unwrap();
+    assert_eq!(frame.dst_addr(), Some(RPL_CHILD_LL_ADDR));
+
+    assert_eq!(rpl_dodag(&child).unwrap().dao_transmissions, Some(1));
+    rpl_receive(
+        &mut child,
+        &mut child_device,
+        &mut child_sockets,
+        &dao_ack,
+        now,
+    );
+    assert_eq!(rpl_dodag(&child).unwrap().dao_transmissions, None);
+}
+
+/// Emit an ICMPv6 packet carrying an RPL Source Routing Header to `buffer`, returning its length.
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_source_routed_packet(
+    buffer: &mut [u8],
+    ipv6_repr: Ipv6Repr,
+    routing: Ipv6RoutingRepr,
+    icmp_repr: &Icmpv6Repr,
+) -> usize {
+    let ext_hdr = Ipv6ExtHeaderRepr {
+        next_header: IpProtocol::Icmpv6,
+        length: ((2 + routing.buffer_len()) / 8 - 1) as u8,
+        data: &[],
+    };
+    let ext_len = ext_hdr.header_len() + routing.buffer_len();
+    let len = ipv6_repr.buffer_len() + ext_len + icmp_repr.buffer_len();
+
+    let mut packet = Ipv6Packet::new_unchecked(&mut buffer[..len]);
+    Ipv6Repr {
+        next_header: IpProtocol::Ipv6Route,
+        payload_len: ext_len + icmp_repr.buffer_len(),
+        ..ipv6_repr
+    }
+    .emit(&mut packet);
+
+    let payload = packet.payload_mut();
+    ext_hdr.emit(&mut Ipv6ExtHeader::new_unchecked(&mut payload[..]));
+    routing.emit(&mut Ipv6RoutingHeader::new_unchecked(
+        &mut payload[ext_hdr.header_len()..ext_len],
+    ));
+    icmp_repr.emit(
+        &ipv6_repr.src_addr,
+        &rpl::final_destination(&ipv6_repr.dst_addr, &routing).unwrap_or(ipv6_repr.dst_addr),
+        &mut Icmpv6Packet::new_unchecked(&mut payload[ext_len..]),
+        &ChecksumCapabilities::default(),
+    );
+
+    len
+}
+
+#[test]
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_source_routing_header_is_processed() {
+    let (mut router, mut router_sockets, _) = rpl_non_storing_setup(RPL_NODE_LL_ADDR);
+    let root_addr = rpl_global_address(RPL_ROOT_LL_ADDR);
+    let router_addr = rpl_global_address(RPL_NODE_LL_ADDR);
+    let child_addr = rpl_global_address(RPL_CHILD_LL_ADDR);
+
+    let icmp_repr = Icmpv6Repr::EchoRequest {
+        ident: 1,
+        seq_no: 1,
+        data: b"RPL",
+    };
+    let ipv6_repr = Ipv6Repr {
+        src_addr: root_addr,
+        dst_addr: router_addr,
+        next_header: IpProtocol::Icmpv6,
+        payload_len: icmp_repr.buffer_len(),
+        hop_limit: 64,
+    };
+
+    let mut buffer = [0u8; 128];
+    let emit = |buffer: &mut [u8], segments_left| {
+        let routing = Ipv6RoutingRepr::Rpl {
+            segments_left,
+            cmpr_i: 0,
+            cmpr_e: 8,
+            pad: 0,
+            addresses: &child_addr.as_bytes()[8..],
+        };
+        rpl_source_routed_packet(buffer, ipv6_repr, routing, &icmp_repr)
+    };
+
+    // With segments left, the packet is forwarded to the next address in the route.
+    let len = emit(&mut buffer, 1);
+    let response = router.inner.process_ipv6(
+        &mut router_sockets,
+        PacketMeta::default(),
+        &Ipv6Packet::new_checked(&buffer[..len]).unwrap(),
+    );
+    assert_eq!(
+        response,
+        Some(Packet::Ipv6(PacketV6 {
+            header: Ipv6Repr {
+                src_addr: root_addr,
+                dst_addr: child_addr,
+                next_header: IpProtocol::Icmpv6,
+                payload_len: icmp_repr.buffer_len(),
+                hop_limit: 63,
+            },
+            hop_by_hop: None,
+            #[cfg(feature = "proto-ipv6-fragmentation")]
+            fragment: None,
+            routing: Some(Ipv6RoutingRepr::Rpl {
+                segments_left: 0,
+                cmpr_i: 0,
+                cmpr_e: 8,
+                pad: 0,
+                addresses: &child_addr.as_bytes()[8..],
+            }),
+            source_route_swap: Some((1, router_addr)),
+            payload: IpPayload::Forward(IpProtocol::Icmpv6, &buffer[56..len]),
+        }))
+    );
+
+    // Without segments left, the packet is processed by the router.
+    let mut buffer = [0u8; 128];
+    let len = emit(&mut buffer, 0);
+    let response = router.inner.process_ipv6(
+        &mut router_sockets,
+        PacketMeta::default(),
+        &Ipv6Packet::new_checked(&buffer[..len]).unwrap(),
+    );
+    assert_eq!(
+        response,
+        Some(Packet::new_ipv6(
+            Ipv6Repr {
+                src_addr: router_addr,
+                dst_addr: root_addr,
+                next_header: IpProtocol::Icmpv6,
+                payload_len: icmp_repr.buffer_len(),
+                hop_limit: 64,
+            },
+            IpPayload::Icmpv6(Icmpv6Repr::EchoReply {
+                ident: 1,
+                seq_no: 1,
+                data: b"RPL",
+            }),
+        ))
+    );
+}
+
+#[test]
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+fn rpl_source_routing_header_addresses_are_swapped() {
+    let (mut router, mut router_sockets, _) = rpl_non_storing_setup(RPL_NODE_LL_ADDR);
+    let root_addr = rpl_global_address(RPL_ROOT_LL_ADDR);
+    let router_addr = rpl_global_address(RPL_NODE_LL_ADDR);
+    let child_addr = rpl_global_address(RPL_CHILD_LL_ADDR);
+    let leaf_addr = rpl_global_address(Ieee802154Address::Extended([
+        0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04,
+    ]));
+
+    let icmp_repr = Icmpv6Repr::EchoRequest {
+        ident: 1,
+        seq_no: 1,
+        data: b"RPL",
+    };
+    let ipv6_repr = Ipv6Repr {
+        src_addr: root_addr,
+        dst_addr: router_addr,
+        next_header: IpProtocol::Icmpv6,
+        payload_len: icmp_repr.buffer_len(),
+        hop_limit: 64,
+    };
+
+    // The route to the leaf is via the child.
+    let mut addresses = [0u8; 16];
+    addresses[..8].copy_from_slice(&child_addr.as_bytes()[8..]);
+    addresses[8..].copy_from_slice(&leaf_addr.as_bytes()[8..]);
+    let routing = Ipv6RoutingRepr::Rpl {
+        segments_left: 2,
+        cmpr_i: 8,
+        cmpr_e: 8,
+        pad: 0,
+        addresses: &addresses,
+    };
+
+    let mut buffer = [0u8; 128];
+    let len = rpl_source_routed_packet(&mut buffer, ipv6_repr, routing, &icmp_repr);
+    let Some(Packet::Ipv6(packet)) = router.inner.process_ipv6(
+        &mut router_sockets,
+        PacketMeta::default(),
+        &Ipv6Packet::new_checked(&buffer[..len]).unwrap(),
+    ) else {
+        panic!("the packet is not forwarded");
+    };
+    assert_eq!(packet.header.dst_addr, child_addr);
+    assert_eq!(packet.source_route_swap, Some((1, router_addr)));
+
+    // The address of the child is replaced by the address of the router.
+    let routing = packet.routing.unwrap();
+    let mut swapped = addresses;
+    rpl::swap_source_route_address(&routing, packet.source_route_swap.unwrap(), &mut swapped);
+    assert_eq!(&swapped[..8], &router_addr.as_bytes()[8..]);
+    assert_eq!(&swapped[8..], &leaf_addr.as_bytes()[8..]);
+
+    // A route that visits the router twice is a loop.
+    let mut addresses = [0u8; 24];
+    addresses[..8].copy_from_slice(&router_addr.as_bytes()[8..]);
+    addresses[8..16].copy_from_slice(&child_addr.as_bytes()[8..]);
+    addresses[16..].copy_from_slice(&router_addr.as_bytes()[8..]);
+    let routing = Ipv6RoutingRepr::Rpl {
+        segments_left: 3,
+        cmpr_i: 8,
+        cmpr_e: 8,
+        pad: 0,
+        addresses: &addresses,
+    };
+
+    let mut buffer = [0u8; 128];
+    let len = rpl_source_routed_packet(&mut buffer, ipv6_repr, routing, &icmp_repr);
+    let response = router.inner.process_ipv6(
+        &mut router_sockets,
+        PacketMeta::default(),
+        &Ipv6Packet::new_checked(&buffer[..len]).unwrap(),
+    );
+    assert!(matches!(
+        response,
+        Some(Packet::Ipv6(PacketV6 {
+            payload: IpPayload::Icmpv6(Icmpv6Repr::ParamProblem {
+                reason: Icmpv6ParamProblem::ErroneousHdrField,
+                pointer: 48,
+                ..
+            }),
+            ..
+        }))
+    ));
+}
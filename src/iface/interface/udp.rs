@@ -3,8 +3,11 @@ use super::*;
 #[cfg(feature = "socket-dns")]
 use crate::socket::dns::Socket as DnsSocket;
 
+#[cfg(feature = "socket-mdns")]
+use crate::socket::mdns::Socket as MdnsSocket;
+
 #[cfg(feature = "socket-udp")]
-use crate::socket::udp::Socket as UdpSocket;
+use crate::socket::udp::{IcmpError as UdpIcmpError, Socket as UdpSocket};
 
 impl InterfaceInner {
     pub(super) fn process_udp<'frame>(
@@ -24,6 +27,12 @@ impl InterfaceInner {
             &self.caps.checksum
         ));
 
+        // Drop the traffic from the sources the joined multicast group filters out.
+        if !self.accepts_multicast_source(dst_addr, src_addr) {
+            net_debug!("udp: dropping multicast packet from source {}", src_addr);
+            return None;
+        }
+
         #[cfg(feature = "socket-udp")]
         for udp_socket in sockets
             .items_mut()
@@ -35,6 +44,20 @@ impl InterfaceInner {
             }
         }
 
+        // The mDNS responses are also passed on to the DNS sockets, which send mDNS queries.
+        #[cfg(feature = "socket-mdns")]
+        let mut handled_by_mdns_socket = false;
+        #[cfg(feature = "socket-mdns")]
+        for mdns_socket in sockets
+            .items_mut()
+            .filter_map(|i| MdnsSocket::downcast_mut(&mut i.socket))
+        {
+            if mdns_socket.accepts(&udp_repr) {
+                mdns_socket.process(self, &ip_repr, &udp_repr, udp_packet.payload());
+                handled_by_mdns_socket = true;
+            }
+        }
+
         #[cfg(feature = "socket-dns")]
         for dns_socket in sockets
             .items_mut()
@@ -46,6 +69,11 @@ impl InterfaceInner {
             }
         }
 
+        #[cfg(feature = "socket-mdns")]
+        if handled_by_mdns_socket {
+            return None;
+        }
+
         // The packet wasn't handled by a socket, send an ICMP port unreachable packet.
         match ip_repr {
             #[cfg(feature = "proto-ipv4")]
@@ -76,4 +104,37 @@ impl InterfaceInner {
             }
         }
     }
+
+    /// Deliver an ICMP error to the socket that sent the datagram in error.
+    #[cfg(feature = "socket-udp")]
+    pub(super) fn process_udp_icmp_error(
+        &mut self,
+        sockets: &mut SocketSet,
+        header: &IpRepr,
+        data: &[u8],
+        hard: bool,
+    ) {
+        // The ports are in the first four octets of the datagram.
+        if data.len() < 4 {
+            return;
+        }
+        let udp_packet = UdpPacket::new_unchecked(data);
+        let local = IpEndpoint::new(header.src_addr(), udp_packet.src_port());
+        let remote = IpEndpoint::new(header.dst_addr(), udp_packet.dst_port());
+        let error = if hard {
+            UdpIcmpError::PortUnreachable(remote)
+        } else {
+            UdpIcmpError::Unreachable(remote)
+        };
+
+        for udp_socket in sockets
+            .items_mut()
+            .filter_map(|i| UdpSocket::downcast_mut(&mut i.socket))
+        {
+            if udp_socket.accepts_icmp_error(&local) {
+                udp_socket.process_icmp_error(error);
+                return;
+            }
+        }
+    }
 }
@@ -8,6 +8,7 @@ mod fragmentation;
 mod interface;
 #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
 mod neighbor;
+mod pmtu;
 mod route;
 #[cfg(feature = "proto-rpl")]
 mod rpl;
@@ -16,9 +17,23 @@ mod socket_set;
 
 mod packet;
 
+#[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+pub use self::interface::AcdEvent;
+#[cfg(all(
+    feature = "proto-ipv6",
+    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+))]
+pub use self::interface::DadEvent;
+pub use self::interface::{Config, Interface, InterfaceDevice, InterfaceInner as Context};
 #[cfg(feature = "proto-igmp")]
-pub use self::interface::MulticastError;
-pub use self::interface::{Config, Interface, InterfaceInner as Context};
+pub use self::interface::{MulticastError, MulticastFilterMode};
 
-pub use self::route::{Route, RouteTableFull, Routes};
+#[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+pub use self::neighbor::{NeighborEntry, NeighborError, NeighborState};
+#[cfg(feature = "medium-ethernet")]
+pub use self::neighbor::{PendingPacket, PendingQueue, PendingStats};
+
+pub use self::route::{Route, RouteKind, RouteOrigin, RouteTableFull, Routes};
+#[cfg(feature = "proto-rpl")]
+pub use self::rpl::{Config as RplConfig, Role as RplRole, RootConfig as RplRootConfig};
 pub use self::socket_set::{SocketHandle, SocketSet, SocketStorage};
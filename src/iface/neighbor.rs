@@ -2,11 +2,73 @@
 // the parts of RFC 1122 that discuss ARP.
 
 use heapless::LinearMap;
+#[cfg(feature = "medium-ethernet")]
+use managed::ManagedSlice;
 
 use crate::config::IFACE_NEIGHBOR_CACHE_COUNT;
 use crate::time::{Duration, Instant};
 use crate::wire::{HardwareAddress, IpAddress};
 
+/// The reachability state of a neighbor, as defined by the Neighbor Unreachability Detection
+/// of [RFC 4861 § 7.3.2].
+///
+/// Neighbors whose address is being resolved are not in the cache.
+///
+/// [RFC 4861 § 7.3.2]: https://www.rfc-editor.org/rfc/rfc4861#section-7.3.2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NeighborState {
+    /// The neighbor was recently confirmed to be reachable.
+    Reachable,
+    /// The neighbor is no longer known to be reachable. Its reachability is verified when a
+    /// packet is sent to it.
+    Stale,
+    /// A packet was sent to a stale neighbor, and its reachability may be confirmed by an upper
+    /// layer before it is probed.
+    Delay,
+    /// The reachability of the neighbor is being verified with unicast solicitations.
+    Probe,
+    /// The neighbor was added statically: it never expires, and is never probed.
+    Static,
+    /// The neighbor was added statically, and is never updated by the address resolution
+    /// packets that are received, nor evicted when the cache is full.
+    Permanent,
+}
+
+/// Error type for `add_static_neighbor`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NeighborError {
+    /// The neighbor cache is full of permanent entries.
+    CacheFull,
+    /// The protocol or hardware address of the neighbor is not unicast.
+    Unaddressable,
+}
+
+impl core::fmt::Display for NeighborError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            NeighborError::CacheFull => write!(f, "CacheFull"),
+            NeighborError::Unaddressable => write!(f, "Unaddressable"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NeighborError {}
+
+/// An entry of the neighbor cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NeighborEntry {
+    /// The protocol address of the neighbor.
+    pub protocol_addr: IpAddress,
+    /// The hardware address of the neighbor.
+    pub hardware_addr: HardwareAddress,
+    /// The reachability state of the neighbor.
+    pub state: NeighborState,
+}
+
 /// A cached neighbor.
 ///
 /// A neighbor mapping translates from a protocol address to a hardware address,
@@ -16,6 +78,30 @@ use crate::wire::{HardwareAddress, IpAddress};
 pub struct Neighbor {
     hardware_addr: HardwareAddress,
     expires_at: Instant,
+    state: NeighborState,
+    /// The end of the `Reachable` and `Delay` states, or the time of the next probe.
+    timer: Instant,
+    #[cfg_attr(not(feature = "medium-ethernet"), allow(dead_code))]
+    probes: u8,
+}
+
+impl Neighbor {
+    /// Return the state of the neighbor at `timestamp`.
+    fn state(&self, timestamp: Instant) -> NeighborState {
+        match self.state {
+            NeighborState::Reachable if timestamp >= self.timer => NeighborState::Stale,
+            NeighborState::Delay if timestamp >= self.timer => NeighborState::Probe,
+            state => state,
+        }
+    }
+
+    fn is_static(&self) -> bool {
+        matches!(self.state, NeighborState::Static | NeighborState::Permanent)
+    }
+
+    fn is_expired(&self, timestamp: Instant) -> bool {
+        !self.is_static() && timestamp >= self.expires_at
+    }
 }
 
 /// An answer to a neighbor cache lookup.
@@ -42,6 +128,12 @@ impl Answer {
 }
 
 /// A neighbor cache backed by a map.
+///
+/// The reachability of the neighbors is tracked as specified by the Neighbor Unreachability
+/// Detection of RFC 4861, for both IPv6 and IPv4 neighbors. Confirmed neighbors become stale
+/// after [`REACHABLE_TIME`](Self::REACHABLE_TIME); the first packet sent to a stale neighbor
+/// starts a delay, after which the neighbor is probed with unicast solicitations, and removed
+/// if it does not answer.
 #[derive(Debug)]
 pub struct Cache {
     storage: LinearMap<IpAddress, Neighbor, IFACE_NEIGHBOR_CACHE_COUNT>,
@@ -52,9 +144,25 @@ impl Cache {
     /// Minimum delay between discovery requests, in milliseconds.
     pub(crate) const SILENT_TIME: Duration = Duration::from_millis(1_000);
 
-    /// Neighbor entry lifetime, in milliseconds.
+    /// Neighbor entry lifetime, in milliseconds. Entries that are neither confirmed nor updated
+    /// during this time are removed.
     pub(crate) const ENTRY_LIFETIME: Duration = Duration::from_millis(60_000);
 
+    /// Time during which a neighbor is reachable after a confirmation.
+    pub(crate) const REACHABLE_TIME: Duration = Duration::from_millis(30_000);
+
+    /// Delay before a stale neighbor that was sent a packet is probed.
+    #[cfg(feature = "medium-ethernet")]
+    pub(crate) const DELAY_FIRST_PROBE_TIME: Duration = Duration::from_millis(5_000);
+
+    /// Delay between two probes.
+    #[cfg(feature = "medium-ethernet")]
+    pub(crate) const RETRANS_TIMER: Duration = Duration::from_millis(1_000);
+
+    /// Number of probes sent before a neighbor is considered unreachable.
+    #[cfg(feature = "medium-ethernet")]
+    pub(crate) const MAX_UNICAST_SOLICIT: u8 = 3;
+
     /// Create a cache.
     pub fn new() -> Self {
         Self {
@@ -63,6 +171,7 @@ impl Cache {
         }
     }
 
+    /// Fill the cache with a neighbor whose reachability is confirmed.
     pub fn fill(
         &mut self,
         protocol_addr: IpAddress,
@@ -72,23 +181,194 @@ impl Cache {
         debug_assert!(protocol_addr.is_unicast());
         debug_assert!(hardware_addr.is_unicast());
 
-        let expires_at = timestamp + Self::ENTRY_LIFETIME;
-        self.fill_with_expiration(protocol_addr, hardware_addr, expires_at);
+        if self.is_permanent(&protocol_addr) {
+            return;
+        }
+
+        let _ = self.insert(
+            protocol_addr,
+            Neighbor {
+                hardware_addr,
+                expires_at: timestamp + Self::ENTRY_LIFETIME,
+                state: NeighborState::Reachable,
+                timer: timestamp + Self::REACHABLE_TIME,
+                probes: 0,
+            },
+        );
     }
 
-    pub fn fill_with_expiration(
+    /// Fill the cache with a neighbor whose hardware address was received from the neighbor
+    /// itself, without a confirmation of its reachability.
+    ///
+    /// Known neighbors are only updated if their hardware address changed.
+    pub(crate) fn fill_stale(
         &mut self,
         protocol_addr: IpAddress,
         hardware_addr: HardwareAddress,
-        expires_at: Instant,
+        timestamp: Instant,
     ) {
         debug_assert!(protocol_addr.is_unicast());
         debug_assert!(hardware_addr.is_unicast());
 
-        let neighbor = Neighbor {
-            expires_at,
-            hardware_addr,
+        match self.storage.get(&protocol_addr) {
+            Some(neighbor) if neighbor.state == NeighborState::Permanent => {}
+            Some(neighbor)
+                if neighbor.hardware_addr == hardware_addr && !neighbor.is_expired(timestamp) => {}
+            _ => {
+                let _ = self.insert(
+                    protocol_addr,
+                    Neighbor {
+                        hardware_addr,
+                        expires_at: timestamp + Self::ENTRY_LIFETIME,
+                        state: NeighborState::Stale,
+                        timer: timestamp,
+                        probes: 0,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Fill the cache with the hardware address of a Neighbor Advertisement, as specified by
+    /// RFC 4861 § 7.2.5.
+    #[cfg(feature = "proto-ipv6")]
+    pub(crate) fn fill_advert(
+        &mut self,
+        protocol_addr: IpAddress,
+        hardware_addr: HardwareAddress,
+        solicited: bool,
+        override_: bool,
+        timestamp: Instant,
+    ) {
+        if self.is_permanent(&protocol_addr) {
+            return;
+        }
+
+        let Some(neighbor) = self
+            .storage
+            .get_mut(&protocol_addr)
+            .filter(|neighbor| !neighbor.is_expired(timestamp))
+        else {
+            if solicited {
+                self.fill(protocol_addr, hardware_addr, timestamp);
+            } else {
+                self.fill_stale(protocol_addr, hardware_addr, timestamp);
+            }
+            return;
         };
+
+        if neighbor.hardware_addr != hardware_addr && !override_ {
+            // The advertisement does not update the cache, but the neighbor may have changed.
+            if neighbor.state(timestamp) == NeighborState::Reachable {
+                neighbor.state = NeighborState::Stale;
+            }
+        } else if solicited {
+            self.fill(protocol_addr, hardware_addr, timestamp);
+        } else if neighbor.hardware_addr != hardware_addr {
+            self.fill_stale(protocol_addr, hardware_addr, timestamp);
+        }
+    }
+
+    /// Add a neighbor that never expires, and is never probed. Permanent neighbors are not
+    /// updated by received packets, nor evicted.
+    pub(crate) fn fill_static(
+        &mut self,
+        protocol_addr: IpAddress,
+        hardware_addr: HardwareAddress,
+        permanent: bool,
+    ) -> Result<(), NeighborError> {
+        self.insert(
+            protocol_addr,
+            Neighbor {
+                hardware_addr,
+                expires_at: Instant::ZERO,
+                state: if permanent {
+                    NeighborState::Permanent
+                } else {
+                    NeighborState::Static
+                },
+                timer: Instant::ZERO,
+                probes: 0,
+            },
+        )
+    }
+
+    fn is_permanent(&self, protocol_addr: &IpAddress) -> bool {
+        matches!(
+            self.storage.get(protocol_addr),
+            Some(neighbor) if neighbor.state == NeighborState::Permanent
+        )
+    }
+
+    /// Confirm the reachability of a known neighbor, for example because an upper layer
+    /// protocol made forward progress.
+    #[cfg(any(feature = "proto-ipv6", feature = "socket-tcp", test))]
+    pub(crate) fn confirm(&mut self, protocol_addr: &IpAddress, timestamp: Instant) {
+        if let Some(neighbor) = self.storage.get_mut(protocol_addr) {
+            if !neighbor.is_expired(timestamp) && !neighbor.is_static() {
+                let hardware_addr = neighbor.hardware_addr;
+                self.fill(*protocol_addr, hardware_addr, timestamp);
+            }
+        }
+    }
+
+    /// Record that a packet is sent to a neighbor, which starts the verification of the
+    /// reachability of a stale neighbor.
+    #[cfg(feature = "medium-ethernet")]
+    pub(crate) fn used(&mut self, protocol_addr: &IpAddress, timestamp: Instant) {
+        if let Some(neighbor) = self.storage.get_mut(protocol_addr) {
+            if neighbor.state(timestamp) == NeighborState::Stale {
+                net_trace!("neighbor {} is stale, delaying probe", protocol_addr);
+                neighbor.state = NeighborState::Delay;
+                neighbor.timer = timestamp + Self::DELAY_FIRST_PROBE_TIME;
+            }
+        }
+    }
+
+    /// Return the next neighbor to probe, and record that it is probed. Neighbors that did not
+    /// answer the previous probes are removed.
+    #[cfg(feature = "medium-ethernet")]
+    pub(crate) fn probe(&mut self, timestamp: Instant) -> Option<(IpAddress, HardwareAddress)> {
+        loop {
+            let (protocol_addr, neighbor) = self.storage.iter_mut().find(|(_, neighbor)| {
+                !neighbor.is_expired(timestamp)
+                    && neighbor.state(timestamp) == NeighborState::Probe
+                    && timestamp >= neighbor.timer
+            })?;
+
+            if neighbor.probes >= Self::MAX_UNICAST_SOLICIT {
+                let protocol_addr = *protocol_addr;
+                net_debug!("neighbor {} is unreachable", protocol_addr);
+                self.storage.remove(&protocol_addr);
+                continue;
+            }
+
+            neighbor.state = NeighborState::Probe;
+            neighbor.probes += 1;
+            neighbor.timer = timestamp + Self::RETRANS_TIMER;
+            return Some((*protocol_addr, neighbor.hardware_addr));
+        }
+    }
+
+    /// Return the time at which the next neighbor should be probed.
+    #[cfg(feature = "medium-ethernet")]
+    pub(crate) fn poll_at(&self, timestamp: Instant) -> Option<Instant> {
+        self.storage
+            .values()
+            .filter(|neighbor| {
+                !neighbor.is_expired(timestamp)
+                    && matches!(neighbor.state, NeighborState::Delay | NeighborState::Probe)
+            })
+            .map(|neighbor| neighbor.timer)
+            .min()
+    }
+
+    fn insert(
+        &mut self,
+        protocol_addr: IpAddress,
+        neighbor: Neighbor,
+    ) -> Result<(), NeighborError> {
+        let hardware_addr = neighbor.hardware_addr;
         match self.storage.insert(protocol_addr, neighbor) {
             Ok(Some(old_neighbor)) => {
                 if old_neighbor.hardware_addr != hardware_addr {
@@ -105,12 +385,21 @@ impl Cache {
             }
             Err((protocol_addr, neighbor)) => {
                 // If we're going down this branch, it means the cache is full, and we need to evict an entry.
-                let old_protocol_addr = *self
+                // Static entries are evicted last, and permanent entries are never evicted.
+                let Some((&old_protocol_addr, _)) = self
                     .storage
                     .iter()
-                    .min_by_key(|(_, neighbor)| neighbor.expires_at)
-                    .expect("empty neighbor cache storage")
-                    .0;
+                    .filter(|(_, neighbor)| neighbor.state != NeighborState::Permanent)
+                    .min_by_key(|(_, neighbor)| {
+                        (neighbor.state == NeighborState::Static, neighbor.expires_at)
+                    })
+                else {
+                    net_debug!(
+                        "neighbor cache full of permanent entries, dropping {}",
+                        protocol_addr
+                    );
+                    return Err(NeighborError::CacheFull);
+                };
 
                 let _old_neighbor = self.storage.remove(&old_protocol_addr).unwrap();
                 match self.storage.insert(protocol_addr, neighbor) {
@@ -128,18 +417,15 @@ impl Cache {
                 }
             }
         }
+        Ok(())
     }
 
     pub(crate) fn lookup(&self, protocol_addr: &IpAddress, timestamp: Instant) -> Answer {
         assert!(protocol_addr.is_unicast());
 
-        if let Some(&Neighbor {
-            expires_at,
-            hardware_addr,
-        }) = self.storage.get(protocol_addr)
-        {
-            if timestamp < expires_at {
-                return Answer::Found(hardware_addr);
+        if let Some(neighbor) = self.storage.get(protocol_addr) {
+            if !neighbor.is_expired(timestamp) {
+                return Answer::Found(neighbor.hardware_addr);
             }
         }
 
@@ -150,12 +436,274 @@ impl Cache {
         }
     }
 
+    /// Return the neighbors in the cache.
+    pub(crate) fn entries(&self, timestamp: Instant) -> impl Iterator<Item = NeighborEntry> + '_ {
+        self.storage
+            .iter()
+            .filter(move |(_, neighbor)| !neighbor.is_expired(timestamp))
+            .map(move |(protocol_addr, neighbor)| NeighborEntry {
+                protocol_addr: *protocol_addr,
+                hardware_addr: neighbor.hardware_addr,
+                state: neighbor.state(timestamp),
+            })
+    }
+
+    /// Remove a neighbor from the cache.
+    pub(crate) fn remove(&mut self, protocol_addr: &IpAddress) -> bool {
+        self.storage.remove(protocol_addr).is_some()
+    }
+
     pub(crate) fn limit_rate(&mut self, timestamp: Instant) {
         self.silent_until = timestamp + Self::SILENT_TIME;
     }
 
+    /// Return the time until which no discovery request is sent.
+    #[cfg(feature = "medium-ethernet")]
+    pub(crate) fn silent_until(&self) -> Instant {
+        self.silent_until
+    }
+
+    /// Remove the neighbors from the cache, except the static ones.
     pub(crate) fn flush(&mut self) {
-        self.storage.clear()
+        while let Some(protocol_addr) = self
+            .storage
+            .iter()
+            .find(|(_, neighbor)| !neighbor.is_static())
+            .map(|(protocol_addr, _)| *protocol_addr)
+        {
+            self.storage.remove(&protocol_addr);
+        }
+    }
+}
+
+#[cfg(all(feature = "medium-ethernet", feature = "proto-ipv4"))]
+const UNSPECIFIED: IpAddress = IpAddress::Ipv4(crate::wire::Ipv4Address::UNSPECIFIED);
+#[cfg(all(feature = "medium-ethernet", not(feature = "proto-ipv4")))]
+const UNSPECIFIED: IpAddress = IpAddress::Ipv6(crate::wire::Ipv6Address::UNSPECIFIED);
+
+/// A packet waiting in a [`PendingQueue`] for the hardware address of its neighbor.
+#[cfg(feature = "medium-ethernet")]
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PendingPacket {
+    neighbor: IpAddress,
+    src_addr: IpAddress,
+    dst_addr: IpAddress,
+    size: usize,
+    queued_at: Instant,
+}
+
+#[cfg(feature = "medium-ethernet")]
+impl PendingPacket {
+    /// An empty packet slot, used to build the storage of a [`PendingQueue`].
+    pub const EMPTY: PendingPacket = PendingPacket {
+        neighbor: UNSPECIFIED,
+        src_addr: UNSPECIFIED,
+        dst_addr: UNSPECIFIED,
+        size: 0,
+        queued_at: Instant::ZERO,
+    };
+
+    /// Return the address of the neighbor the packet is transmitted to.
+    pub(crate) fn neighbor(&self) -> IpAddress {
+        self.neighbor
+    }
+
+    /// Return the source and destination addresses of the packet.
+    pub(crate) fn addrs(&self) -> (IpAddress, IpAddress) {
+        (self.src_addr, self.dst_addr)
+    }
+}
+
+/// Counters of a [`PendingQueue`].
+#[cfg(feature = "medium-ethernet")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PendingStats {
+    /// Number of packets that were queued.
+    pub queued: u32,
+    /// Number of queued packets that were transmitted once their neighbor was discovered.
+    pub sent: u32,
+    /// Number of packets that were dropped: because the queue was full, because too many
+    /// packets were waiting for the same neighbor, or because the neighbor was not discovered
+    /// in time.
+    pub dropped: u32,
+}
+
+/// A queue of packets waiting for the discovery of their neighbor.
+///
+/// When the hardware address of a neighbor is unknown, the packets that are transmitted to it
+/// are held in the queue, instead of being dropped, until it is discovered. When the queue is
+/// full, or too many packets wait for the same neighbor, the oldest packets are dropped.
+#[cfg(feature = "medium-ethernet")]
+#[derive(Debug)]
+pub struct PendingQueue<'a> {
+    metadata: ManagedSlice<'a, PendingPacket>,
+    payload: ManagedSlice<'a, u8>,
+    len: usize,
+    neighbor_limit: usize,
+    stats: PendingStats,
+}
+
+#[cfg(feature = "medium-ethernet")]
+impl<'a> PendingQueue<'a> {
+    /// Time after which packets whose neighbor was not discovered are dropped.
+    pub(crate) const TIMEOUT: Duration = Duration::from_millis(3_000);
+
+    /// Create a queue with the given storage for the metadata and the contents of the packets.
+    ///
+    /// The queue holds at most as many packets as `metadata` has slots, and at most 3 packets
+    /// for each neighbor.
+    ///
+    /// An interface only takes a `PendingQueue<'static>`, see
+    /// [`Interface::set_pending_queue`](crate::iface::Interface::set_pending_queue).
+    pub fn new<MS, PS>(metadata: MS, payload: PS) -> Self
+    where
+        MS: Into<ManagedSlice<'a, PendingPacket>>,
+        PS: Into<ManagedSlice<'a, u8>>,
+    {
+        Self {
+            metadata: metadata.into(),
+            payload: payload.into(),
+            len: 0,
+            neighbor_limit: 3,
+            stats: PendingStats::default(),
+        }
+    }
+
+    /// Set the maximum number of packets that wait for the same neighbor.
+    pub fn set_neighbor_limit(&mut self, limit: usize) {
+        self.neighbor_limit = limit;
+    }
+
+    /// Return the counters of the queue.
+    pub fn stats(&self) -> PendingStats {
+        self.stats
+    }
+
+    /// Return the number of packets in the queue.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return whether the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the packet at `index`, and its contents.
+    pub(crate) fn get(&self, index: usize) -> (&PendingPacket, &[u8]) {
+        let offset = self.offset(index);
+        let packet = &self.metadata[index];
+        (packet, &self.payload[offset..offset + packet.size])
+    }
+
+    /// Queue a packet of `size` bytes, which are written by `emit`.
+    ///
+    /// Returns `false` if the packet is dropped because it is larger than the queue, or because
+    /// the queue holds no packets.
+    pub(crate) fn enqueue(
+        &mut self,
+        neighbor: IpAddress,
+        (src_addr, dst_addr): (IpAddress, IpAddress),
+        size: usize,
+        timestamp: Instant,
+        emit: impl FnOnce(&mut [u8]),
+    ) -> bool {
+        if self.metadata.is_empty() {
+            net_debug!(
+                "pending: dropping a packet for {}, no packet slots",
+                neighbor
+            );
+            self.stats.dropped += 1;
+            return false;
+        }
+        if self.neighbor_limit == 0 {
+            net_debug!(
+                "pending: dropping a packet for {}, neighbor limit is 0",
+                neighbor
+            );
+            self.stats.dropped += 1;
+            return false;
+        }
+        if size > self.payload.len() {
+            net_debug!("pending: dropping a packet for {}, too large", neighbor);
+            self.stats.dropped += 1;
+            return false;
+        }
+
+        let mut waiting = self.metadata[..self.len]
+            .iter()
+            .filter(|packet| packet.neighbor == neighbor)
+            .count();
+        while waiting >= self.neighbor_limit {
+            let index = self.metadata[..self.len]
+                .iter()
+                .position(|packet| packet.neighbor == neighbor)
+                .unwrap();
+            net_trace!("pending: dropping the oldest packet for {}", neighbor);
+            self.drop_packet(index);
+            waiting -= 1;
+        }
+
+        while self.len == self.metadata.len() || self.offset(self.len) + size > self.payload.len() {
+            net_trace!("pending: queue full, dropping the oldest packet");
+            self.drop_packet(0);
+        }
+
+        let offset = self.offset(self.len);
+        emit(&mut self.payload[offset..offset + size]);
+        self.metadata[self.len] = PendingPacket {
+            neighbor,
+            src_addr,
+            dst_addr,
+            size,
+            queued_at: timestamp,
+        };
+        self.len += 1;
+        self.stats.queued += 1;
+        true
+    }
+
+    /// Remove the packet at `index`, once it has been transmitted.
+    pub(crate) fn remove_sent(&mut self, index: usize) {
+        self.remove(index);
+        self.stats.sent += 1;
+    }
+
+    /// Drop the packet at `index`.
+    pub(crate) fn drop_packet(&mut self, index: usize) {
+        self.remove(index);
+        self.stats.dropped += 1;
+    }
+
+    /// Return whether the packet at `index` has waited for too long.
+    pub(crate) fn is_expired(&self, index: usize, timestamp: Instant) -> bool {
+        self.metadata[index].queued_at + Self::TIMEOUT <= timestamp
+    }
+
+    /// Return the time at which the oldest packet expires.
+    pub(crate) fn expires_at(&self) -> Option<Instant> {
+        self.metadata[..self.len]
+            .iter()
+            .map(|packet| packet.queued_at + Self::TIMEOUT)
+            .min()
+    }
+
+    fn offset(&self, index: usize) -> usize {
+        self.metadata[..index]
+            .iter()
+            .map(|packet| packet.size)
+            .sum()
+    }
+
+    fn remove(&mut self, index: usize) {
+        let offset = self.offset(index);
+        let size = self.metadata[index].size;
+        let end = self.offset(self.len);
+        self.payload.copy_within(offset + size..end, offset);
+        self.metadata[index..self.len].rotate_left(1);
+        self.len -= 1;
     }
 }
 
@@ -306,4 +854,253 @@ mod test {
             .lookup(&MOCK_IP_ADDR_1.into(), Instant::from_millis(0))
             .found());
     }
+
+    fn state(cache: &Cache, protocol_addr: IpAddress, timestamp: Instant) -> Option<NeighborState> {
+        cache
+            .entries(timestamp)
+            .find(|entry| entry.protocol_addr == protocol_addr)
+            .map(|entry| entry.state)
+    }
+
+    #[test]
+    fn test_reachability() {
+        let mut cache = Cache::new();
+        let addr = MOCK_IP_ADDR_1.into();
+
+        cache.fill(addr, HADDR_A, Instant::ZERO);
+        assert_eq!(
+            state(&cache, addr, Instant::ZERO),
+            Some(NeighborState::Reachable)
+        );
+        let stale_at = Instant::ZERO + Cache::REACHABLE_TIME;
+        assert_eq!(state(&cache, addr, stale_at), Some(NeighborState::Stale));
+
+        // Sending a packet to a stale neighbor delays its probe.
+        cache.used(&addr, stale_at);
+        assert_eq!(state(&cache, addr, stale_at), Some(NeighborState::Delay));
+        assert_eq!(
+            cache.poll_at(stale_at),
+            Some(stale_at + Cache::DELAY_FIRST_PROBE_TIME)
+        );
+        assert_eq!(cache.probe(stale_at), None);
+
+        // An upper layer confirmation makes it reachable again.
+        cache.confirm(&addr, stale_at);
+        assert_eq!(
+            state(&cache, addr, stale_at),
+            Some(NeighborState::Reachable)
+        );
+        assert_eq!(cache.poll_at(stale_at), None);
+
+        // Unsolicited information does not change a known neighbor with the same address.
+        cache.fill_stale(addr, HADDR_A, stale_at);
+        assert_eq!(
+            state(&cache, addr, stale_at),
+            Some(NeighborState::Reachable)
+        );
+        cache.fill_stale(addr, HADDR_B, stale_at);
+        assert_eq!(state(&cache, addr, stale_at), Some(NeighborState::Stale));
+        assert_eq!(cache.lookup(&addr, stale_at), Answer::Found(HADDR_B));
+    }
+
+    #[test]
+    fn test_probe() {
+        let mut cache = Cache::new();
+        let addr = MOCK_IP_ADDR_1.into();
+
+        cache.fill_stale(addr, HADDR_A, Instant::ZERO);
+        cache.used(&addr, Instant::ZERO);
+
+        let mut now = Instant::ZERO + Cache::DELAY_FIRST_PROBE_TIME;
+        assert_eq!(state(&cache, addr, now), Some(NeighborState::Probe));
+        for _ in 0..Cache::MAX_UNICAST_SOLICIT {
+            assert_eq!(cache.poll_at(now), Some(now));
+            assert_eq!(cache.probe(now), Some((addr, HADDR_A)));
+            assert_eq!(cache.probe(now), None);
+            now += Cache::RETRANS_TIMER;
+        }
+
+        // The neighbor did not answer.
+        assert_eq!(cache.probe(now), None);
+        assert_eq!(cache.lookup(&addr, now), Answer::NotFound);
+        assert_eq!(cache.poll_at(now), None);
+    }
+
+    #[cfg(feature = "proto-ipv6")]
+    #[test]
+    fn test_advert() {
+        let mut cache = Cache::new();
+        let addr = MOCK_IP_ADDR_1.into();
+
+        // Unsolicited advertisements create stale entries.
+        cache.fill_advert(addr, HADDR_A, false, true, Instant::ZERO);
+        assert_eq!(
+            state(&cache, addr, Instant::ZERO),
+            Some(NeighborState::Stale)
+        );
+
+        // Solicited advertisements confirm the neighbor.
+        cache.fill_advert(addr, HADDR_A, true, false, Instant::ZERO);
+        assert_eq!(
+            state(&cache, addr, Instant::ZERO),
+            Some(NeighborState::Reachable)
+        );
+
+        // Advertisements without the override flag do not change the address.
+        cache.fill_advert(addr, HADDR_B, true, false, Instant::ZERO);
+        assert_eq!(cache.lookup(&addr, Instant::ZERO), Answer::Found(HADDR_A));
+        assert_eq!(
+            state(&cache, addr, Instant::ZERO),
+            Some(NeighborState::Stale)
+        );
+
+        cache.fill_advert(addr, HADDR_B, true, true, Instant::ZERO);
+        assert_eq!(cache.lookup(&addr, Instant::ZERO), Answer::Found(HADDR_B));
+        assert_eq!(
+            state(&cache, addr, Instant::ZERO),
+            Some(NeighborState::Reachable)
+        );
+    }
+
+    #[test]
+    fn test_static() {
+        let mut cache = Cache::new();
+        let addr = MOCK_IP_ADDR_1.into();
+
+        assert_eq!(cache.fill_static(addr, HADDR_A, false), Ok(()));
+        let later = Instant::ZERO + Cache::ENTRY_LIFETIME * 2;
+        assert_eq!(cache.lookup(&addr, later), Answer::Found(HADDR_A));
+        assert_eq!(state(&cache, addr, later), Some(NeighborState::Static));
+        cache.used(&addr, later);
+        assert_eq!(cache.probe(later), None);
+
+        // Static neighbors are evicted last.
+        cache.fill(MOCK_IP_ADDR_2.into(), HADDR_B, Instant::ZERO);
+        cache.fill(MOCK_IP_ADDR_3.into(), HADDR_C, Instant::ZERO);
+        cache.fill(MOCK_IP_ADDR_4.into(), HADDR_D, Instant::ZERO);
+        assert_eq!(cache.lookup(&addr, later), Answer::Found(HADDR_A));
+
+        cache.flush();
+        assert_eq!(cache.lookup(&addr, later), Answer::Found(HADDR_A));
+        assert_eq!(cache.entries(later).count(), 1);
+
+        assert!(cache.remove(&addr));
+        assert!(!cache.remove(&addr));
+        assert_eq!(cache.lookup(&addr, later), Answer::NotFound);
+    }
+
+    #[test]
+    fn test_permanent() {
+        let mut cache = Cache::new();
+        let addr = MOCK_IP_ADDR_1.into();
+
+        assert_eq!(cache.fill_static(addr, HADDR_A, true), Ok(()));
+        assert_eq!(
+            state(&cache, addr, Instant::ZERO),
+            Some(NeighborState::Permanent)
+        );
+
+        // Received packets do not update permanent neighbors.
+        cache.fill(addr, HADDR_B, Instant::ZERO);
+        cache.fill_stale(addr, HADDR_B, Instant::ZERO);
+        assert_eq!(cache.lookup(&addr, Instant::ZERO), Answer::Found(HADDR_A));
+        #[cfg(feature = "proto-ipv6")]
+        {
+            cache.fill_advert(addr, HADDR_B, true, true, Instant::ZERO);
+            assert_eq!(cache.lookup(&addr, Instant::ZERO), Answer::Found(HADDR_A));
+        }
+
+        // Permanent neighbors are never evicted.
+        assert_eq!(
+            cache.fill_static(MOCK_IP_ADDR_2.into(), HADDR_B, true),
+            Ok(())
+        );
+        assert_eq!(
+            cache.fill_static(MOCK_IP_ADDR_3.into(), HADDR_C, true),
+            Ok(())
+        );
+        assert_eq!(
+            cache.fill_static(MOCK_IP_ADDR_4.into(), HADDR_D, true),
+            Err(NeighborError::CacheFull)
+        );
+        cache.fill(MOCK_IP_ADDR_4.into(), HADDR_D, Instant::ZERO);
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_4.into(), Instant::ZERO),
+            Answer::NotFound
+        );
+        assert_eq!(cache.entries(Instant::ZERO).count(), 3);
+
+        // Only the user removes them.
+        assert!(cache.remove(&addr));
+        assert_eq!(cache.lookup(&addr, Instant::ZERO), Answer::NotFound);
+    }
+
+    #[test]
+    fn test_pending_queue() {
+        let mut metadata = [PendingPacket::EMPTY; 3];
+        let mut payload = [0u8; 8];
+        let mut queue = PendingQueue::new(&mut metadata[..], &mut payload[..]);
+        queue.set_neighbor_limit(2);
+
+        let addrs = (MOCK_IP_ADDR_3.into(), MOCK_IP_ADDR_4.into());
+        let enqueue = |queue: &mut PendingQueue, neighbor: IpAddress, bytes: &[u8]| {
+            queue.enqueue(
+                neighbor,
+                addrs,
+                bytes.len(),
+                Instant::from_millis(0),
+                |buffer| buffer.copy_from_slice(bytes),
+            )
+        };
+        let contents = |queue: &PendingQueue| {
+            (0..queue.len())
+                .map(|index| {
+                    let (packet, bytes) = queue.get(index);
+                    (packet.neighbor(), bytes[0], bytes.len())
+                })
+                .collect::<std::vec::Vec<_>>()
+        };
+
+        // The oldest packet for a neighbor is dropped when it has too many packets.
+        assert!(enqueue(&mut queue, MOCK_IP_ADDR_1.into(), &[1; 2]));
+        assert!(enqueue(&mut queue, MOCK_IP_ADDR_1.into(), &[2; 2]));
+        assert!(enqueue(&mut queue, MOCK_IP_ADDR_1.into(), &[3; 2]));
+        assert_eq!(
+            contents(&queue),
+            [(MOCK_IP_ADDR_1.into(), 2, 2), (MOCK_IP_ADDR_1.into(), 3, 2)]
+        );
+
+        // The oldest packet is dropped when the queue is full.
+        assert!(enqueue(&mut queue, MOCK_IP_ADDR_2.into(), &[4; 4]));
+        assert!(enqueue(&mut queue, MOCK_IP_ADDR_2.into(), &[5; 2]));
+        assert_eq!(
+            contents(&queue),
+            [
+                (MOCK_IP_ADDR_1.into(), 3, 2),
+                (MOCK_IP_ADDR_2.into(), 4, 4),
+                (MOCK_IP_ADDR_2.into(), 5, 2)
+            ]
+        );
+
+        // Packets larger than the queue are dropped.
+        assert!(!enqueue(&mut queue, MOCK_IP_ADDR_2.into(), &[6; 9]));
+
+        queue.remove_sent(1);
+        assert_eq!(
+            contents(&queue),
+            [(MOCK_IP_ADDR_1.into(), 3, 2), (MOCK_IP_ADDR_2.into(), 5, 2)]
+        );
+        assert_eq!(
+            queue.stats(),
+            PendingStats {
+                queued: 5,
+                sent: 1,
+                dropped: 3,
+            }
+        );
+
+        assert_eq!(queue.expires_at(), Some(Instant::from_millis(3_000)));
+        assert!(!queue.is_expired(0, Instant::from_millis(2_999)));
+        assert!(queue.is_expired(0, Instant::from_millis(3_000)));
+    }
 }
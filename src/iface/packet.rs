@@ -34,6 +34,7 @@ impl<'p> Packet<'p> {
     pub(crate) fn new_ipv4(ip_repr: Ipv4Repr, payload: IpPayload<'p>) -> Self {
         Self::Ipv4(PacketV4 {
             header: ip_repr,
+            dont_frag: true,
             payload,
         })
     }
@@ -48,10 +49,32 @@ impl<'p> Packet<'p> {
             fragment: None,
             #[cfg(feature = "proto-ipv6-routing")]
             routing: None,
+            #[cfg(feature = "proto-rpl")]
+            source_route_swap: None,
             payload,
         })
     }
 
+    /// Return whether the packet must not be fragmented on its path: IPv6 packets, and IPv4
+    /// packets with the Don't Fragment flag.
+    pub(crate) fn dont_frag(&self) -> bool {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            Packet::Ipv4(p) => p.dont_frag,
+            #[cfg(feature = "proto-ipv6")]
+            Packet::Ipv6(_) => true,
+        }
+    }
+
+    /// Set the Don't Fragment flag of an IPv4 packet, which is set by default.
+    #[cfg(feature = "proto-ipv4")]
+    pub(crate) fn set_dont_frag(&mut self, dont_frag: bool) {
+        #[allow(irrefutable_let_patterns)] // if only ipv4 is enabled
+        if let Packet::Ipv4(p) = self {
+            p.dont_frag = dont_frag;
+        }
+    }
+
     pub(crate) fn ip_repr(&self) -> IpRepr {
         match self {
             #[cfg(feature = "proto-ipv4")]
@@ -167,7 +190,7 @@ impl<'p> Packet<'p> {
                     &caps.checksum,
                 );
             }
-            #[cfg(feature = "socket-dhcpv4")]
+            #[cfg(any(feature = "socket-dhcpv4", feature = "socket-dhcpv4-server"))]
             IpPayload::Dhcpv4(udp_repr, dhcp_repr) => udp_repr.emit(
                 &mut UdpPacket::new_unchecked(payload),
                 &_ip_repr.src_addr(),
@@ -176,6 +199,20 @@ impl<'p> Packet<'p> {
                 |buf| dhcp_repr.emit(&mut DhcpPacket::new_unchecked(buf)).unwrap(),
                 &caps.checksum,
             ),
+            #[cfg(feature = "socket-dhcpv6")]
+            IpPayload::Dhcpv6(udp_repr, dhcp_repr) => udp_repr.emit(
+                &mut UdpPacket::new_unchecked(payload),
+                &_ip_repr.src_addr(),
+                &_ip_repr.dst_addr(),
+                dhcp_repr.buffer_len(),
+                |buf| {
+                    dhcp_repr
+                        .emit(&mut Dhcpv6Packet::new_unchecked(buf))
+                        .unwrap()
+                },
+                &caps.checksum,
+            ),
+            IpPayload::Forward(_, data) => payload.copy_from_slice(data),
         }
     }
 }
@@ -185,6 +222,8 @@ impl<'p> Packet<'p> {
 #[cfg(feature = "proto-ipv4")]
 pub(crate) struct PacketV4<'p> {
     header: Ipv4Repr,
+    /// The Don't Fragment flag. Forwarded packets keep the flag they were received with.
+    dont_frag: bool,
     payload: IpPayload<'p>,
 }
 
@@ -199,6 +238,10 @@ pub(crate) struct PacketV6<'p> {
     pub(crate) fragment: Option<Ipv6FragmentRepr>,
     #[cfg(feature = "proto-ipv6-routing")]
     pub(crate) routing: Option<Ipv6RoutingRepr<'p>>,
+    /// The index of the address of the RPL Source Routing Header that is swapped with the
+    /// destination address, and the destination address it is swapped with.
+    #[cfg(feature = "proto-rpl")]
+    pub(crate) source_route_swap: Option<(usize, Ipv6Address)>,
     pub(crate) payload: IpPayload<'p>,
 }
 
@@ -208,7 +251,7 @@ pub(crate) enum IpPayload<'p> {
     #[cfg(feature = "proto-ipv4")]
     Icmpv4(Icmpv4Repr<'p>),
     #[cfg(feature = "proto-igmp")]
-    Igmp(IgmpRepr),
+    Igmp(IgmpRepr<'p>),
     #[cfg(feature = "proto-ipv6")]
     Icmpv6(Icmpv6Repr<'p>),
     #[cfg(feature = "proto-ipv6")]
@@ -219,8 +262,12 @@ pub(crate) enum IpPayload<'p> {
     Udp(UdpRepr, &'p [u8]),
     #[cfg(feature = "socket-tcp")]
     Tcp(TcpRepr<'p>),
-    #[cfg(feature = "socket-dhcpv4")]
+    #[cfg(any(feature = "socket-dhcpv4", feature = "socket-dhcpv4-server"))]
     Dhcpv4(UdpRepr, DhcpRepr<'p>),
+    #[cfg(feature = "socket-dhcpv6")]
+    Dhcpv6(UdpRepr, Dhcpv6Repr<'p>),
+    /// A payload that is forwarded unmodified, starting with the given upper-layer protocol.
+    Forward(IpProtocol, &'p [u8]),
 }
 
 impl<'p> IpPayload<'p> {
@@ -229,7 +276,7 @@ impl<'p> IpPayload<'p> {
         match self {
             #[cfg(feature = "proto-ipv4")]
             Self::Icmpv4(_) => unreachable!(),
-            #[cfg(feature = "socket-dhcpv4")]
+            #[cfg(any(feature = "socket-dhcpv4", feature = "socket-dhcpv4-server"))]
             Self::Dhcpv4(..) => unreachable!(),
             #[cfg(feature = "proto-ipv6")]
             Self::Icmpv6(_) => SixlowpanNextHeader::Uncompressed(IpProtocol::Icmpv6),
@@ -241,8 +288,11 @@ impl<'p> IpPayload<'p> {
             Self::Tcp(_) => SixlowpanNextHeader::Uncompressed(IpProtocol::Tcp),
             #[cfg(feature = "socket-udp")]
             Self::Udp(..) => SixlowpanNextHeader::Compressed,
+            #[cfg(feature = "socket-dhcpv6")]
+            Self::Dhcpv6(..) => SixlowpanNextHeader::Uncompressed(IpProtocol::Udp),
             #[cfg(feature = "socket-raw")]
             Self::Raw(_) => todo!(),
+            Self::Forward(next_header, _) => SixlowpanNextHeader::Uncompressed(*next_header),
         }
     }
 }
@@ -0,0 +1,169 @@
+// Heads up! Before working on this file you should read, at least, RFC 1191 and RFC 8201,
+// which describe Path MTU Discovery for IPv4 and IPv6.
+
+use heapless::LinearMap;
+
+use crate::config::IFACE_PMTU_CACHE_COUNT;
+use crate::time::{Duration, Instant};
+use crate::wire::IpAddress;
+
+/// A cached path MTU estimate.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Entry {
+    mtu: usize,
+    expires_at: Instant,
+}
+
+/// A path MTU cache backed by a map.
+///
+/// Estimates are lowered by ICMP "fragmentation needed" and "packet too big" messages, and are
+/// forgotten when they age, such that a larger path MTU is tried again.
+#[derive(Debug)]
+pub(crate) struct Cache {
+    storage: LinearMap<IpAddress, Entry, IFACE_PMTU_CACHE_COUNT>,
+}
+
+impl Cache {
+    /// Path MTU estimate lifetime, see RFC 1191 § 6.3 and RFC 8201 § 4.
+    pub(crate) const ENTRY_LIFETIME: Duration = Duration::from_secs(10 * 60);
+
+    /// Smallest MTU an IPv4 path can have, see RFC 791.
+    #[cfg(feature = "proto-ipv4")]
+    pub(crate) const IPV4_MIN_MTU: usize = 68;
+
+    /// Create a cache.
+    pub(crate) fn new() -> Self {
+        Self {
+            storage: LinearMap::new(),
+        }
+    }
+
+    /// Lower the path MTU estimate for a destination. Estimates larger than the current one are
+    /// ignored, as they would cause packet loss until they age.
+    pub(crate) fn fill(&mut self, dst_addr: IpAddress, mtu: usize, timestamp: Instant) {
+        if let Some(current) = self.lookup(&dst_addr, timestamp) {
+            if mtu >= current {
+                return;
+            }
+        }
+
+        let entry = Entry {
+            mtu,
+            expires_at: timestamp + Self::ENTRY_LIFETIME,
+        };
+
+        if let Err((dst_addr, entry)) = self.storage.insert(dst_addr, entry) {
+            // The cache is full, evict the entry that expires first.
+            let old_dst_addr = *self
+                .storage
+                .iter()
+                .min_by_key(|(_, entry)| entry.expires_at)
+                .expect("empty path MTU cache storage")
+                .0;
+
+            self.storage.remove(&old_dst_addr);
+            // NOTE(unwrap): we made room above.
+            self.storage.insert(dst_addr, entry).unwrap();
+        }
+
+        net_debug!("path MTU to {} is {}", dst_addr, mtu);
+    }
+
+    /// Return the path MTU estimate for a destination, if any.
+    pub(crate) fn lookup(&self, dst_addr: &IpAddress, timestamp: Instant) -> Option<usize> {
+        self.storage
+            .get(dst_addr)
+            .filter(|entry| timestamp < entry.expires_at)
+            .map(|entry| entry.mtu)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(all(feature = "proto-ipv4", not(feature = "proto-ipv6")))]
+    use crate::wire::ipv4::test::{MOCK_IP_ADDR_1, MOCK_IP_ADDR_2, MOCK_IP_ADDR_3, MOCK_IP_ADDR_4};
+    #[cfg(feature = "proto-ipv6")]
+    use crate::wire::ipv6::test::{MOCK_IP_ADDR_1, MOCK_IP_ADDR_2, MOCK_IP_ADDR_3, MOCK_IP_ADDR_4};
+
+    #[test]
+    fn test_fill() {
+        let mut cache = Cache::new();
+        assert_eq!(cache.lookup(&MOCK_IP_ADDR_1.into(), Instant::ZERO), None);
+
+        cache.fill(MOCK_IP_ADDR_1.into(), 1400, Instant::ZERO);
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_1.into(), Instant::ZERO),
+            Some(1400)
+        );
+        assert_eq!(cache.lookup(&MOCK_IP_ADDR_2.into(), Instant::ZERO), None);
+
+        // Estimates are only lowered.
+        cache.fill(MOCK_IP_ADDR_1.into(), 1450, Instant::ZERO);
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_1.into(), Instant::ZERO),
+            Some(1400)
+        );
+        cache.fill(MOCK_IP_ADDR_1.into(), 1300, Instant::ZERO);
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_1.into(), Instant::ZERO),
+            Some(1300)
+        );
+    }
+
+    #[test]
+    fn test_expire() {
+        let mut cache = Cache::new();
+
+        cache.fill(MOCK_IP_ADDR_1.into(), 1400, Instant::ZERO);
+        assert_eq!(
+            cache.lookup(
+                &MOCK_IP_ADDR_1.into(),
+                Instant::ZERO + Cache::ENTRY_LIFETIME
+            ),
+            None
+        );
+
+        // An expired estimate can be raised.
+        cache.fill(
+            MOCK_IP_ADDR_1.into(),
+            1450,
+            Instant::ZERO + Cache::ENTRY_LIFETIME,
+        );
+        assert_eq!(
+            cache.lookup(
+                &MOCK_IP_ADDR_1.into(),
+                Instant::ZERO + Cache::ENTRY_LIFETIME
+            ),
+            Some(1450)
+        );
+    }
+
+    #[test]
+    fn test_evict() {
+        let mut cache = Cache::new();
+
+        cache.fill(MOCK_IP_ADDR_1.into(), 1400, Instant::from_millis(100));
+        cache.fill(MOCK_IP_ADDR_2.into(), 1400, Instant::from_millis(50));
+        cache.fill(MOCK_IP_ADDR_3.into(), 1400, Instant::from_millis(200));
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_2.into(), Instant::from_millis(1000)),
+            Some(1400)
+        );
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_4.into(), Instant::from_millis(1000)),
+            None
+        );
+
+        cache.fill(MOCK_IP_ADDR_4.into(), 1400, Instant::from_millis(300));
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_2.into(), Instant::from_millis(1000)),
+            None
+        );
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_4.into(), Instant::from_millis(1000)),
+            Some(1400)
+        );
+    }
+}
@@ -1,8 +1,10 @@
+use core::cmp::Reverse;
+
 use heapless::Vec;
 
 use crate::config::IFACE_MAX_ROUTE_COUNT;
 use crate::time::Instant;
-use crate::wire::{IpAddress, IpCidr};
+use crate::wire::{IpAddress, IpCidr, IpVersion};
 #[cfg(feature = "proto-ipv4")]
 use crate::wire::{Ipv4Address, Ipv4Cidr};
 #[cfg(feature = "proto-ipv6")]
@@ -21,16 +23,60 @@ impl core::fmt::Display for RouteTableFull {
 #[cfg(feature = "std")]
 impl std::error::Error for RouteTableFull {}
 
+/// What happens to the packets that are routed by a route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RouteKind {
+    /// The packets are transmitted to the router `via_router`.
+    Gateway,
+    /// The destinations are on the link: the packets are transmitted to them directly, and
+    /// `via_router` is ignored.
+    OnLink,
+    /// The packets are silently dropped.
+    Blackhole,
+    /// The packets are dropped, and an ICMP Destination Unreachable message is sent back when
+    /// they are forwarded.
+    Unreachable,
+}
+
+/// Where a route comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RouteOrigin {
+    /// The route was added by the application.
+    Static,
+    /// The route was learned from a Router Advertisement. It is replaced by later Router
+    /// Advertisements, and removed when its lifetime expires.
+    RouterAdvertisement,
+}
+
 /// A prefix of addresses that should be routed via a router
+///
+/// This struct is marked as `#[non_exhaustive]`: it is created with one of its constructors,
+/// and the optional properties are set with the `with_*` methods.
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
 pub struct Route {
     pub cidr: IpCidr,
+    /// The router the packets are transmitted to by a [`RouteKind::Gateway`] route. Routes of
+    /// the other kinds ignore it, and [`Route::new_kind`] sets it to the address of `cidr`.
     pub via_router: IpAddress,
+    pub kind: RouteKind,
+    /// When several routes have the same prefix, the one with the lowest metric is used.
+    pub metric: u32,
+    /// The source address that is preferred for the packets that are routed by this route,
+    /// when it is one of the addresses of the interface.
+    pub src_addr: Option<IpAddress>,
+    /// The maximum size of the IP packets that are routed by this route. `None` means the MTU
+    /// of the interface. It is raised to the minimum MTU of the IP version, 68 octets for IPv4
+    /// and 1280 octets for IPv6, if it is smaller.
+    pub mtu: Option<usize>,
     /// `None` means "forever".
     pub preferred_until: Option<Instant>,
     /// `None` means "forever".
     pub expires_at: Option<Instant>,
+    pub origin: RouteOrigin,
 }
 
 #[cfg(feature = "proto-ipv4")]
@@ -40,30 +86,108 @@ const IPV6_DEFAULT: IpCidr =
     IpCidr::Ipv6(Ipv6Cidr::new(Ipv6Address::new(0, 0, 0, 0, 0, 0, 0, 0), 0));
 
 impl Route {
-    /// Returns a route to 0.0.0.0/0 via the `gateway`, with no expiry.
-    #[cfg(feature = "proto-ipv4")]
-    pub fn new_ipv4_gateway(gateway: Ipv4Address) -> Route {
+    /// Returns a route to `cidr` via the `gateway`, with no expiry.
+    pub fn new(cidr: IpCidr, gateway: IpAddress) -> Route {
         Route {
-            cidr: IPV4_DEFAULT,
-            via_router: gateway.into(),
+            cidr,
+            via_router: gateway,
+            kind: RouteKind::Gateway,
+            metric: 0,
+            src_addr: None,
+            mtu: None,
             preferred_until: None,
             expires_at: None,
+            origin: RouteOrigin::Static,
+        }
+    }
+
+    /// Returns a route to `cidr`, of the given `kind`, with no expiry. The router of the route is
+    /// unused unless `kind` is [`RouteKind::Gateway`].
+    pub fn new_kind(cidr: IpCidr, kind: RouteKind) -> Route {
+        Route {
+            kind,
+            ..Route::new(cidr, cidr.address())
         }
     }
 
+    /// Returns the route with the given `metric`.
+    pub fn with_metric(self, metric: u32) -> Route {
+        Route { metric, ..self }
+    }
+
+    /// Returns the route with the given preferred source address.
+    pub fn with_src_addr(self, src_addr: IpAddress) -> Route {
+        Route {
+            src_addr: Some(src_addr),
+            ..self
+        }
+    }
+
+    /// Returns the route with the given maximum size of the IP packets.
+    pub fn with_mtu(self, mtu: usize) -> Route {
+        Route {
+            mtu: Some(mtu),
+            ..self
+        }
+    }
+
+    /// Returns the route with the given `origin`.
+    pub fn with_origin(self, origin: RouteOrigin) -> Route {
+        Route { origin, ..self }
+    }
+
+    /// Returns a route to 0.0.0.0/0 via the `gateway`, with no expiry.
+    #[cfg(feature = "proto-ipv4")]
+    pub fn new_ipv4_gateway(gateway: Ipv4Address) -> Route {
+        Route::new(IPV4_DEFAULT, gateway.into())
+    }
+
     /// Returns a route to ::/0 via the `gateway`, with no expiry.
     #[cfg(feature = "proto-ipv6")]
     pub fn new_ipv6_gateway(gateway: Ipv6Address) -> Route {
-        Route {
-            cidr: IPV6_DEFAULT,
-            via_router: gateway.into(),
-            preferred_until: None,
-            expires_at: None,
+        Route::new(IPV6_DEFAULT, gateway.into())
+    }
+
+    /// Return the address packets to `addr` are transmitted to, if they are not dropped.
+    fn next_hop(&self, addr: &IpAddress) -> Option<IpAddress> {
+        match self.kind {
+            RouteKind::Gateway => Some(self.via_router),
+            RouteKind::OnLink => Some(*addr),
+            RouteKind::Blackhole | RouteKind::Unreachable => None,
         }
     }
+
+    fn is_expired(&self, timestamp: Instant) -> bool {
+        matches!(self.expires_at, Some(expires_at) if timestamp > expires_at)
+    }
+
+    /// The routes of the table are kept sorted by this key: grouped by IP version and prefix
+    /// length, from the most specific prefix, and then sorted by network and metric.
+    fn sort_key(&self) -> (IpVersion, Reverse<u8>, IpAddress, u32) {
+        let prefix_len = self.cidr.prefix_len();
+        (
+            self.cidr.address().version(),
+            Reverse(prefix_len),
+            network(&self.cidr.address(), prefix_len),
+            self.metric,
+        )
+    }
+}
+
+/// Return the first address of the network of `addr`, with a prefix of `prefix_len` bits.
+fn network(addr: &IpAddress, prefix_len: u8) -> IpAddress {
+    match addr {
+        #[cfg(feature = "proto-ipv4")]
+        IpAddress::Ipv4(addr) => Ipv4Cidr::new(*addr, prefix_len).network().address().into(),
+        #[cfg(feature = "proto-ipv6")]
+        IpAddress::Ipv6(addr) => Ipv6Address::from_bytes(&addr.mask(prefix_len)).into(),
+    }
 }
 
 /// A routing table.
+///
+/// The routes are kept sorted by prefix and metric, so that a lookup does a binary search for
+/// each prefix length in the table instead of scanning every route.
 #[derive(Debug)]
 pub struct Routes {
     storage: Vec<Route, IFACE_MAX_ROUTE_COUNT>,
@@ -80,6 +204,21 @@ impl Routes {
     /// Update the routes of this node.
     pub fn update<F: FnOnce(&mut Vec<Route, IFACE_MAX_ROUTE_COUNT>)>(&mut self, f: F) {
         f(&mut self.storage);
+        self.storage.sort_unstable_by_key(Route::sort_key);
+    }
+
+    /// Add a route (ie. "ip route add `cidr` via `router` metric `metric`").
+    pub fn add_route(&mut self, route: Route) -> Result<(), RouteTableFull> {
+        let key = route.sort_key();
+        let index = self.storage.partition_point(|r| r.sort_key() <= key);
+        self.storage
+            .insert(index, route)
+            .map_err(|_| RouteTableFull)
+    }
+
+    /// Remove the routes to `cidr`.
+    pub fn remove_routes(&mut self, cidr: IpCidr) {
+        self.storage.retain(|route| route.cidr != cidr);
     }
 
     /// Add a default ipv4 gateway (ie. "ip route add 0.0.0.0/0 via `gateway`").
@@ -91,9 +230,7 @@ impl Routes {
         gateway: Ipv4Address,
     ) -> Result<Option<Route>, RouteTableFull> {
         let old = self.remove_default_ipv4_route();
-        self.storage
-            .push(Route::new_ipv4_gateway(gateway))
-            .map_err(|_| RouteTableFull)?;
+        self.add_route(Route::new_ipv4_gateway(gateway))?;
         Ok(old)
     }
 
@@ -106,9 +243,7 @@ impl Routes {
         gateway: Ipv6Address,
     ) -> Result<Option<Route>, RouteTableFull> {
         let old = self.remove_default_ipv6_route();
-        self.storage
-            .push(Route::new_ipv6_gateway(gateway))
-            .map_err(|_| RouteTableFull)?;
+        self.add_route(Route::new_ipv6_gateway(gateway))?;
         Ok(old)
     }
 
@@ -146,23 +281,45 @@ impl Routes {
         }
     }
 
+    /// Return the address packets to `addr` are transmitted to: a router, or `addr` itself for
+    /// an on-link route. Returns `None` if there is no route, or if the packets are dropped.
     pub(crate) fn lookup(&self, addr: &IpAddress, timestamp: Instant) -> Option<IpAddress> {
+        self.lookup_route(addr, timestamp)
+            .and_then(|route| route.next_hop(addr))
+    }
+
+    /// Return the most specific route to `addr`, with the lowest metric.
+    pub(crate) fn lookup_route(&self, addr: &IpAddress, timestamp: Instant) -> Option<&Route> {
         assert!(addr.is_unicast());
 
-        self.storage
-            .iter()
-            // Keep only matching routes
-            .filter(|route| {
-                if let Some(expires_at) = route.expires_at {
-                    if timestamp > expires_at {
-                        return false;
-                    }
-                }
-                route.cidr.contains_addr(addr)
-            })
-            // pick the most specific one (highest prefix_len)
-            .max_by_key(|route| route.cidr.prefix_len())
-            .map(|route| route.via_router)
+        let mut routes = &self.storage[..];
+        while let Some(first) = routes.first() {
+            // The routes with the same version and prefix length as the first one.
+            let group_key =
+                |route: &Route| (route.cidr.address().version(), route.cidr.prefix_len());
+            let group_len = routes.partition_point(|route| group_key(route) == group_key(first));
+            let (group, rest) = routes.split_at(group_len);
+            routes = rest;
+
+            if first.cidr.address().version() != addr.version() {
+                continue;
+            }
+
+            // Groups are sorted by network, and then by metric.
+            let prefix_len = first.cidr.prefix_len();
+            let addr_network = network(addr, prefix_len);
+            let start = group
+                .partition_point(|route| network(&route.cidr.address(), prefix_len) < addr_network);
+            let route = group[start..]
+                .iter()
+                .take_while(|route| network(&route.cidr.address(), prefix_len) == addr_network)
+                .find(|route| !route.is_expired(timestamp));
+            if route.is_some() {
+                return route;
+            }
+        }
+
+        None
     }
 }
 
@@ -244,8 +401,13 @@ mod test {
         let route = Route {
             cidr: cidr_1().into(),
             via_router: ADDR_1A.into(),
+            kind: RouteKind::Gateway,
+            metric: 0,
+            src_addr: None,
+            mtu: None,
             preferred_until: None,
             expires_at: None,
+            origin: RouteOrigin::Static,
         };
         routes.update(|storage| {
             storage.push(route).unwrap();
@@ -275,8 +437,13 @@ mod test {
         let route2 = Route {
             cidr: cidr_2().into(),
             via_router: ADDR_2A.into(),
+            kind: RouteKind::Gateway,
+            metric: 0,
+            src_addr: None,
+            mtu: None,
             preferred_until: Some(Instant::from_millis(10)),
             expires_at: Some(Instant::from_millis(10)),
+            origin: RouteOrigin::Static,
         };
         routes.update(|storage| {
             storage.push(route2).unwrap();
@@ -324,4 +491,113 @@ mod test {
             Some(ADDR_2A.into())
         );
     }
+
+    #[test]
+    fn test_most_specific() {
+        let mut routes = Routes::new();
+
+        let default = IpCidr::new(ADDR_2A.into(), 0);
+        routes
+            .add_route(Route::new(cidr_1().into(), ADDR_1A.into()))
+            .unwrap();
+        routes
+            .add_route(Route::new(default, ADDR_2A.into()))
+            .unwrap();
+
+        assert_eq!(
+            routes.lookup(&ADDR_1B.into(), Instant::from_millis(0)),
+            Some(ADDR_1A.into())
+        );
+        assert_eq!(
+            routes.lookup(&ADDR_2B.into(), Instant::from_millis(0)),
+            Some(ADDR_2A.into())
+        );
+
+        // A more specific route that expired is not used.
+        routes
+            .add_route(Route {
+                expires_at: Some(Instant::from_millis(10)),
+                ..Route::new(
+                    IpCidr::new(ADDR_1B.into(), cidr_1().prefix_len() + 8),
+                    ADDR_1C.into(),
+                )
+            })
+            .unwrap();
+        assert_eq!(
+            routes.lookup(&ADDR_1B.into(), Instant::from_millis(0)),
+            Some(ADDR_1C.into())
+        );
+        assert_eq!(
+            routes.lookup(&ADDR_1B.into(), Instant::from_millis(20)),
+            Some(ADDR_1A.into())
+        );
+
+        routes.remove_routes(cidr_1().into());
+        assert_eq!(
+            routes.lookup(&ADDR_1A.into(), Instant::from_millis(20)),
+            Some(ADDR_2A.into())
+        );
+    }
+
+    #[test]
+    fn test_metric() {
+        let mut routes = Routes::new();
+
+        routes
+            .add_route(Route::new(cidr_1().into(), ADDR_1A.into()).with_metric(10))
+            .unwrap();
+        routes
+            .add_route(Route {
+                expires_at: Some(Instant::from_millis(10)),
+                ..Route::new(cidr_1().into(), ADDR_1B.into()).with_metric(5)
+            })
+            .unwrap();
+
+        assert_eq!(
+            routes.lookup(&ADDR_1C.into(), Instant::from_millis(0)),
+            Some(ADDR_1B.into())
+        );
+        assert_eq!(
+            routes.lookup(&ADDR_1C.into(), Instant::from_millis(20)),
+            Some(ADDR_1A.into())
+        );
+
+        // Routes pushed out of order are sorted.
+        routes.update(|storage| {
+            storage
+                .push(Route::new(cidr_1().into(), ADDR_1C.into()).with_metric(1))
+                .unwrap();
+        });
+        assert_eq!(
+            routes.lookup(&ADDR_1A.into(), Instant::from_millis(0)),
+            Some(ADDR_1C.into())
+        );
+    }
+
+    #[test]
+    fn test_kind() {
+        let mut routes = Routes::new();
+
+        routes
+            .add_route(Route::new_kind(cidr_1().into(), RouteKind::OnLink))
+            .unwrap();
+        routes
+            .add_route(Route::new_kind(cidr_2().into(), RouteKind::Blackhole))
+            .unwrap();
+
+        assert_eq!(
+            routes.lookup(&ADDR_1C.into(), Instant::from_millis(0)),
+            Some(ADDR_1C.into())
+        );
+        assert_eq!(
+            routes.lookup(&ADDR_2B.into(), Instant::from_millis(0)),
+            None
+        );
+        assert_eq!(
+            routes
+                .lookup_route(&ADDR_2B.into(), Instant::from_millis(0))
+                .map(|route| route.kind),
+            Some(RouteKind::Blackhole)
+        );
+    }
 }
@@ -1,8 +1,24 @@
 pub const SEQUENCE_WINDOW: u8 = 16;
 
 pub const DEFAULT_MIN_HOP_RANK_INCREASE: u16 = 256;
+pub const DEFAULT_MAX_RANK_INCREASE: u16 = 7 * DEFAULT_MIN_HOP_RANK_INCREASE;
 
 pub const DEFAULT_DIO_INTERVAL_MIN: u32 = 12;
 pub const DEFAULT_DIO_REDUNDANCY_CONSTANT: usize = 10;
 /// This is 20 in the standard, but in Contiki they use:
 pub const DEFAULT_DIO_INTERVAL_DOUBLINGS: u32 = 8;
+
+/// The default lifetime of routes, expressed in units of [`DEFAULT_LIFETIME_UNIT`]. This is the
+/// value used by Contiki.
+pub const DEFAULT_LIFETIME: u8 = 30;
+/// The default lifetime unit, in seconds.
+pub const DEFAULT_LIFETIME_UNIT: u16 = 60;
+
+/// Interval at which a node that is not part of a DODAG solicits DIOs from its neighbors.
+pub const DIS_INTERVAL: crate::time::Duration = crate::time::Duration::from_secs(60);
+
+/// Time after which a DAO that was not acknowledged by the root is retransmitted. This is the
+/// value used by Contiki.
+pub const DAO_RETRANSMISSION_TIMEOUT: crate::time::Duration = crate::time::Duration::from_secs(5);
+/// Number of times a DAO is retransmitted before giving up.
+pub const DAO_MAX_RETRANSMISSIONS: u8 = 5;
@@ -1,9 +1,445 @@
+//! Implementation of the Routing Protocol for Low-Power and Lossy Networks (RPL), defined in
+//! [RFC 6550].
+//!
+//! This module contains the configuration and the state of the RPL routing engine. The
+//! processing of RPL control messages, the transmission of DIS, DIO and DAO messages and the
+//! forwarding of packets through the DODAG is done by the [`Interface`](crate::iface::Interface).
+//!
+//! [RFC 6550]: https://datatracker.ietf.org/doc/html/rfc6550
+
 #![allow(unused)]
 
-mod consts;
+pub(crate) mod consts;
 mod lollipop;
 mod of0;
 mod parents;
 mod rank;
 mod relations;
 mod trickle;
+
+use crate::rand::Rand;
+use crate::time::{Duration, Instant};
+use crate::wire::{Ipv6Address, RplInstanceId, RplModeOfOperation, RplOptionRepr};
+
+pub(crate) use lollipop::SequenceCounter;
+pub(crate) use of0::{ObjectiveFunction, ObjectiveFunction0};
+pub(crate) use parents::{Parent, ParentSet};
+pub(crate) use rank::Rank;
+pub(crate) use relations::Relations;
+pub(crate) use trickle::TrickleTimer;
+
+/// The role of a node in an RPL network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Role {
+    /// The node is the root of a DODAG.
+    Root(RootConfig),
+    /// The node joins a DODAG and advertises it to its neighbors, such that other nodes can use
+    /// it as a parent.
+    Router,
+    /// The node joins a DODAG, but never advertises it. A leaf can not be used as a parent by
+    /// other nodes.
+    Leaf,
+}
+
+/// Configuration of the DODAG created by an RPL root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub struct RootConfig {
+    /// The DODAG ID, which is a routable IPv6 address of the root.
+    pub dodag_id: Ipv6Address,
+    /// The preference of the DODAG, from 0 (least preferred) to 7 (most preferred).
+    pub preference: u8,
+    /// Whether the DODAG can satisfy the application-defined goal.
+    pub grounded: bool,
+}
+
+impl RootConfig {
+    pub fn new(dodag_id: Ipv6Address) -> Self {
+        Self {
+            dodag_id,
+            preference: 0,
+            grounded: false,
+        }
+    }
+}
+
+/// Configuration of the RPL routing engine of an interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub struct Config {
+    /// The role of the node in the RPL network.
+    pub role: Role,
+    /// The mode of operation of the RPL instance.
+    ///
+    /// Nodes only join DODAGs that use the same mode of operation. In
+    /// [`NonStoringMode`](RplModeOfOperation::NonStoringMode), nodes advertise their address to
+    /// the root using DAO messages, and the root uses source routing to reach them.
+    pub mode_of_operation: RplModeOfOperation,
+    /// The RPL instance ID.
+    pub instance_id: RplInstanceId,
+}
+
+impl Config {
+    pub fn new(role: Role) -> Self {
+        Self {
+            role,
+            mode_of_operation: RplModeOfOperation::NoDownwardRoutesMaintained,
+            instance_id: RplInstanceId::Global(30),
+        }
+    }
+}
+
+/// The parameters from the DODAG Configuration option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(crate) struct DodagConfiguration {
+    pub(crate) authentication_enabled: bool,
+    pub(crate) path_control_size: u8,
+    pub(crate) dio_interval_doublings: u8,
+    pub(crate) dio_interval_min: u8,
+    pub(crate) dio_redundancy_constant: u8,
+    pub(crate) max_rank_increase: u16,
+    pub(crate) minimum_hop_rank_increase: u16,
+    pub(crate) objective_code_point: u16,
+    pub(crate) default_lifetime: u8,
+    pub(crate) lifetime_unit: u16,
+}
+
+impl Default for DodagConfiguration {
+    fn default() -> Self {
+        use consts::*;
+
+        Self {
+            authentication_enabled: false,
+            path_control_size: 0,
+            dio_interval_doublings: DEFAULT_DIO_INTERVAL_DOUBLINGS as u8,
+            dio_interval_min: DEFAULT_DIO_INTERVAL_MIN as u8,
+            dio_redundancy_constant: DEFAULT_DIO_REDUNDANCY_CONSTANT as u8,
+            max_rank_increase: DEFAULT_MAX_RANK_INCREASE,
+            minimum_hop_rank_increase: DEFAULT_MIN_HOP_RANK_INCREASE,
+            objective_code_point: <ObjectiveFunction0 as ObjectiveFunction>::OCP,
+            default_lifetime: DEFAULT_LIFETIME,
+            lifetime_unit: DEFAULT_LIFETIME_UNIT,
+        }
+    }
+}
+
+impl DodagConfiguration {
+    /// Parse the DODAG configuration from a DODAG Configuration option.
+    pub(crate) fn from_option(option: &RplOptionRepr) -> Option<Self> {
+        match *option {
+            RplOptionRepr::DodagConfiguration {
+                authentication_enabled,
+                path_control_size,
+                dio_interval_doublings,
+                dio_interval_min,
+                dio_redundancy_constant,
+                max_rank_increase,
+                minimum_hop_rank_increase,
+                objective_code_point,
+                default_lifetime,
+                lifetime_unit,
+            } => Some(Self {
+                authentication_enabled,
+                path_control_size,
+                dio_interval_doublings,
+                dio_interval_min,
+                dio_redundancy_constant,
+                max_rank_increase,
+                minimum_hop_rank_increase,
+                objective_code_point,
+                default_lifetime,
+                lifetime_unit,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Return the DODAG Configuration option for this configuration.
+    pub(crate) fn to_option(self) -> RplOptionRepr<'static> {
+        RplOptionRepr::DodagConfiguration {
+            authentication_enabled: self.authentication_enabled,
+            path_control_size: self.path_control_size,
+            dio_interval_doublings: self.dio_interval_doublings,
+            dio_interval_min: self.dio_interval_min,
+            dio_redundancy_constant: self.dio_redundancy_constant,
+            max_rank_increase: self.max_rank_increase,
+            minimum_hop_rank_increase: self.minimum_hop_rank_increase,
+            objective_code_point: self.objective_code_point,
+            default_lifetime: self.default_lifetime,
+            lifetime_unit: self.lifetime_unit,
+        }
+    }
+
+    /// Returns `true` when the configuration can be used by this implementation: the objective
+    /// function must be OF0 and the Trickle parameters must be in range.
+    pub(crate) fn is_supported(&self) -> bool {
+        self.objective_code_point == <ObjectiveFunction0 as ObjectiveFunction>::OCP
+            && self.minimum_hop_rank_increase > 0
+            && self.dio_interval_min as u32 + self.dio_interval_doublings as u32 <= 31
+    }
+
+    /// Return the lifetime of routes and parents.
+    pub(crate) fn lifetime(&self) -> Duration {
+        Duration::from_secs(self.default_lifetime as u64 * self.lifetime_unit as u64)
+    }
+
+    /// Create a new DIO Trickle timer using the parameters of this configuration.
+    pub(crate) fn dio_timer(&self, now: Instant, rand: &mut Rand) -> TrickleTimer {
+        TrickleTimer::new(
+            self.dio_interval_min as u32,
+            self.dio_interval_min as u32 + self.dio_interval_doublings as u32,
+            self.dio_redundancy_constant as usize,
+            now,
+            rand,
+        )
+    }
+}
+
+/// The state of the RPL routing engine.
+#[derive(Debug)]
+pub(crate) struct Rpl {
+    pub(crate) role: Role,
+    pub(crate) mode_of_operation: RplModeOfOperation,
+    pub(crate) instance_id: RplInstanceId,
+    /// When the next DIS should be transmitted, when the node is not part of a DODAG.
+    pub(crate) dis_expiration: Instant,
+    /// Neighbor that requested a DIO using a unicast DIS.
+    pub(crate) dio_requested_by: Option<Ipv6Address>,
+    pub(crate) dodag: Option<Dodag>,
+}
+
+/// The state of the DODAG the node is part of.
+#[derive(Debug)]
+pub(crate) struct Dodag {
+    pub(crate) id: Ipv6Address,
+    pub(crate) version_number: SequenceCounter,
+    pub(crate) preference: u8,
+    pub(crate) grounded: bool,
+    pub(crate) dtsn: SequenceCounter,
+    pub(crate) configuration: DodagConfiguration,
+
+    pub(crate) rank: Rank,
+    pub(crate) dio_timer: TrickleTimer,
+
+    pub(crate) parent: Option<Ipv6Address>,
+    pub(crate) parent_set: ParentSet,
+    /// Since when the node is without a preferred parent.
+    pub(crate) without_parent: Option<Instant>,
+
+    /// The sequence number of the last DAO that was transmitted.
+    pub(crate) dao_seq_number: SequenceCounter,
+    /// When the next DAO should be transmitted.
+    pub(crate) dao_expiration: Instant,
+    /// The number of transmissions of the last DAO, when it was not acknowledged yet.
+    pub(crate) dao_transmissions: Option<u8>,
+    /// The parent of every node in the DODAG, as advertised in DAOs. Only used by the root in
+    /// non-storing mode.
+    pub(crate) relations: Relations,
+}
+
+impl Rpl {
+    pub(crate) fn new(config: Config, now: Instant, rand: &mut Rand) -> Self {
+        let dodag = match config.role {
+            Role::Root(root) => {
+                let configuration = DodagConfiguration::default();
+                Some(Dodag {
+                    id: root.dodag_id,
+                    version_number: SequenceCounter::default(),
+                    preference: root.preference,
+                    grounded: root.grounded,
+                    dtsn: SequenceCounter::default(),
+                    rank: Rank::new(
+                        configuration.minimum_hop_rank_increase,
+                        configuration.minimum_hop_rank_increase,
+                    ),
+                    dio_timer: configuration.dio_timer(now, rand),
+                    configuration,
+                    parent: None,
+                    parent_set: ParentSet::default(),
+                    without_parent: None,
+                    dao_seq_number: SequenceCounter::default(),
+                    dao_expiration: now,
+                    dao_transmissions: None,
+                    relations: Relations::default(),
+                })
+            }
+            Role::Router | Role::Leaf => None,
+        };
+
+        Self {
+            role: config.role,
+            mode_of_operation: config.mode_of_operation,
+            instance_id: config.instance_id,
+            dis_expiration: now,
+            dio_requested_by: None,
+            dodag,
+        }
+    }
+
+    /// Returns `true` when the node is the root of the DODAG.
+    pub(crate) fn is_root(&self) -> bool {
+        matches!(self.role, Role::Root(_))
+    }
+
+    /// Returns `true` when the node advertises the DODAG using DIO messages.
+    pub(crate) fn sends_dio(&self) -> bool {
+        !matches!(self.role, Role::Leaf)
+    }
+
+    /// Returns `true` when the node advertises its address to the root using DAO messages.
+    pub(crate) fn sends_dao(&self) -> bool {
+        !self.is_root() && self.mode_of_operation == RplModeOfOperation::NonStoringMode
+    }
+
+    /// Return the Instant at which the RPL routing engine needs to be polled again.
+    pub(crate) fn poll_at(&self) -> Instant {
+        match &self.dodag {
+            Some(_) if self.dio_requested_by.is_some() => Instant::ZERO,
+            Some(dodag) => {
+                let mut poll_at = dodag.parent_expiration();
+                if self.sends_dio() {
+                    poll_at = poll_at.min(dodag.dio_timer.poll_at());
+                }
+
+                if let Some(without_parent) = dodag.without_parent {
+                    poll_at = poll_at.min(without_parent + dodag.dio_timer.min_expiration() * 2);
+                }
+
+                if self.sends_dao() && dodag.parent.is_some() {
+                    poll_at = poll_at.min(dodag.dao_expiration);
+                }
+
+                poll_at
+            }
+            None => self.dis_expiration,
+        }
+    }
+}
+
+impl Dodag {
+    /// Create a new DODAG state for a node joining the DODAG advertised in a DIO by `parent`.
+    pub(crate) fn join(
+        parent: &Parent,
+        grounded: bool,
+        dtsn: SequenceCounter,
+        configuration: DodagConfiguration,
+        now: Instant,
+        rand: &mut Rand,
+    ) -> Self {
+        Self {
+            id: parent.dodag_id(),
+            version_number: parent.version_number(),
+            preference: parent.preference(),
+            grounded,
+            dtsn,
+            rank: Rank::INFINITE,
+            dio_timer: configuration.dio_timer(now, rand),
+            configuration,
+            parent: None,
+            parent_set: ParentSet::default(),
+            without_parent: None,
+            dao_seq_number: SequenceCounter::default(),
+            dao_expiration: now,
+            dao_transmissions: None,
+            relations: Relations::default(),
+        }
+    }
+
+    /// Return the Rank of a DIO, using the `MinHopRankIncrease` of the DODAG.
+    pub(crate) fn rank_from_raw(&self, rank: u16) -> Rank {
+        Rank::new(rank, self.configuration.minimum_hop_rank_increase)
+    }
+
+    /// Update the parameters of the DODAG with the parameters from a DODAG Configuration option.
+    ///
+    /// Returns `true` when the configuration changed.
+    pub(crate) fn update_configuration(
+        &mut self,
+        configuration: DodagConfiguration,
+        now: Instant,
+        rand: &mut Rand,
+    ) -> bool {
+        if self.configuration == configuration {
+            return false;
+        }
+
+        if self.configuration.dio_interval_min != configuration.dio_interval_min
+            || self.configuration.dio_interval_doublings != configuration.dio_interval_doublings
+            || self.configuration.dio_redundancy_constant != configuration.dio_redundancy_constant
+        {
+            self.dio_timer = configuration.dio_timer(now, rand);
+        }
+
+        self.configuration = configuration;
+        true
+    }
+
+    /// Schedule the transmission of a new DAO, for example because the preferred parent changed.
+    pub(crate) fn schedule_dao(&mut self, now: Instant) {
+        self.dao_expiration = now;
+        self.dao_transmissions = None;
+    }
+
+    /// Return the interval at which DAOs are refreshed, such that the downward routes do not
+    /// expire.
+    pub(crate) fn dao_refresh_interval(&self) -> Duration {
+        self.configuration.lifetime() / 2
+    }
+
+    /// Remove the parents that we did not hear from within the lifetime of the DODAG.
+    pub(crate) fn purge_parents(&mut self, now: Instant) {
+        let lifetime = self.configuration.lifetime();
+        self.parent_set
+            .retain(|_, parent| parent.last_heard() + lifetime > now);
+    }
+
+    /// Return the Instant at which the oldest parent in the parent set expires.
+    pub(crate) fn parent_expiration(&self) -> Instant {
+        let lifetime = self.configuration.lifetime();
+        self.parent_set
+            .parents()
+            .map(|(_, parent)| parent.last_heard() + lifetime)
+            .min()
+            .unwrap_or(Instant::from_micros(i64::MAX))
+    }
+
+    /// Select the preferred parent from the parent set, using Objective Function Zero, and
+    /// calculate the new Rank of the node. Parents with a Rank that is not lower than the new
+    /// Rank of the node are removed from the parent set.
+    ///
+    /// Returns `true` when the preferred parent changed.
+    pub(crate) fn select_preferred_parent(&mut self, now: Instant) -> bool {
+        let old_parent = self.parent;
+
+        match ObjectiveFunction0::preferred_parent(&self.parent_set) {
+            Some(preferred_parent) => {
+                let rank = ObjectiveFunction0::rank(self.rank, *preferred_parent.rank());
+                let address = self
+                    .parent_set
+                    .parents()
+                    .find(|(_, p)| *p == preferred_parent)
+                    .map(|(a, _)| *a)
+                    .unwrap();
+
+                self.parent = Some(address);
+                self.rank = rank;
+                self.without_parent = None;
+
+                self.parent_set.retain(|_, p| *p.rank() < rank);
+            }
+            None => {
+                self.parent = None;
+                self.rank = Rank::INFINITE;
+                if self.without_parent.is_none() {
+                    self.without_parent = Some(now);
+                }
+            }
+        }
+
+        old_parent != self.parent
+    }
+}
@@ -54,6 +54,7 @@ impl ObjectiveFunction for ObjectiveFunction0 {
 #[cfg(test)]
 mod tests {
     use crate::iface::rpl::consts::DEFAULT_MIN_HOP_RANK_INCREASE;
+    use crate::time::Instant;
 
     use super::*;
 
@@ -100,7 +101,13 @@ mod tests {
 
         parents.add(
             Ipv6Address::default(),
-            Parent::new(0, Rank::ROOT, Default::default(), Ipv6Address::default()),
+            Parent::new(
+                0,
+                Rank::ROOT,
+                Default::default(),
+                Ipv6Address::default(),
+                Instant::ZERO,
+            ),
         );
 
         let mut address = Ipv6Address::default();
@@ -113,6 +120,7 @@ mod tests {
                 Rank::new(1024, DEFAULT_MIN_HOP_RANK_INCREASE),
                 Default::default(),
                 Ipv6Address::default(),
+                Instant::ZERO,
             ),
         );
 
@@ -123,6 +131,7 @@ mod tests {
                 Rank::ROOT,
                 Default::default(),
                 Ipv6Address::default(),
+                Instant::ZERO,
             ))
         );
     }
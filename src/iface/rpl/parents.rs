@@ -1,3 +1,4 @@
+use crate::time::Instant;
 use crate::wire::Ipv6Address;
 
 use super::{lollipop::SequenceCounter, rank::Rank};
@@ -9,6 +10,7 @@ pub(crate) struct Parent {
     preference: u8,
     version_number: SequenceCounter,
     dodag_id: Ipv6Address,
+    last_heard: Instant,
 }
 
 impl Parent {
@@ -18,12 +20,14 @@ impl Parent {
         rank: Rank,
         version_number: SequenceCounter,
         dodag_id: Ipv6Address,
+        last_heard: Instant,
     ) -> Self {
         Self {
             rank,
             preference,
             version_number,
             dodag_id,
+            last_heard,
         }
     }
 
@@ -31,6 +35,26 @@ impl Parent {
     pub(crate) fn rank(&self) -> &Rank {
         &self.rank
     }
+
+    /// Return the preference of the DODAG, as advertised by the parent.
+    pub(crate) fn preference(&self) -> u8 {
+        self.preference
+    }
+
+    /// Return the version number of the DODAG, as advertised by the parent.
+    pub(crate) fn version_number(&self) -> SequenceCounter {
+        self.version_number
+    }
+
+    /// Return the DODAG ID of the DODAG the parent belongs to.
+    pub(crate) fn dodag_id(&self) -> Ipv6Address {
+        self.dodag_id
+    }
+
+    /// Return the time at which we last received a DIO from the parent.
+    pub(crate) fn last_heard(&self) -> Instant {
+        self.last_heard
+    }
 }
 
 #[derive(Debug, Default)]
@@ -73,6 +97,32 @@ impl ParentSet {
         self.parents.iter()
     }
 
+    /// Remove a parent from the parent set.
+    pub(crate) fn remove(&mut self, address: &Ipv6Address) -> Option<Parent> {
+        self.parents.remove(address)
+    }
+
+    /// Remove all parents from the parent set.
+    pub(crate) fn clear(&mut self) {
+        self.parents.clear();
+    }
+
+    /// Remove all parents for which `f` returns `false`.
+    pub(crate) fn retain(&mut self, mut f: impl FnMut(&Ipv6Address, &Parent) -> bool) {
+        while let Some(address) =
+            self.parents
+                .iter()
+                .find_map(|(a, p)| if f(a, p) { None } else { Some(*a) })
+        {
+            self.parents.remove(&address);
+        }
+    }
+
+    /// Returns `true` when there are no parents in the parent set.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
     /// Find the worst parent that is currently in the parent set.
     fn worst_parent(&self) -> Option<(&Ipv6Address, &Parent)> {
         self.parents.iter().max_by_key(|(k, v)| v.rank.dag_rank())
@@ -88,7 +138,13 @@ mod tests {
         let mut set = ParentSet::default();
         set.add(
             Default::default(),
-            Parent::new(0, Rank::ROOT, Default::default(), Default::default()),
+            Parent::new(
+                0,
+                Rank::ROOT,
+                Default::default(),
+                Default::default(),
+                Instant::ZERO,
+            ),
         );
 
         assert_eq!(
@@ -97,7 +153,8 @@ mod tests {
                 0,
                 Rank::ROOT,
                 Default::default(),
-                Default::default()
+                Default::default(),
+                Instant::ZERO,
             ))
         );
     }
@@ -121,6 +178,7 @@ mod tests {
                     Rank::new(256 * i, DEFAULT_MIN_HOP_RANK_INCREASE),
                     Default::default(),
                     address,
+                    Instant::ZERO,
                 ),
             );
 
@@ -131,6 +189,7 @@ mod tests {
                     Rank::new(256 * i, DEFAULT_MIN_HOP_RANK_INCREASE),
                     Default::default(),
                     address,
+                    Instant::ZERO,
                 ))
             );
         }
@@ -146,6 +205,7 @@ mod tests {
                 Rank::new(256 * 8, DEFAULT_MIN_HOP_RANK_INCREASE),
                 Default::default(),
                 address,
+                Instant::ZERO,
             ),
         );
         assert_eq!(set.find(&address), None);
@@ -160,6 +220,7 @@ mod tests {
                 Rank::new(0, DEFAULT_MIN_HOP_RANK_INCREASE),
                 Default::default(),
                 address,
+                Instant::ZERO,
             ),
         );
         assert_eq!(
@@ -168,7 +229,8 @@ mod tests {
                 0,
                 Rank::new(0, DEFAULT_MIN_HOP_RANK_INCREASE),
                 Default::default(),
-                address
+                address,
+                Instant::ZERO,
             ))
         );
         assert_eq!(set.find(&last_address), None);
@@ -50,7 +50,7 @@ impl Relations {
     }
 
     /// Return the next hop for a specific IPv6 address, if there is one.
-    pub fn find_next_hop(&mut self, destination: Ipv6Address) -> Option<Ipv6Address> {
+    pub fn find_next_hop(&self, destination: Ipv6Address) -> Option<Ipv6Address> {
         self.relations.iter().find_map(|r| {
             if r.destination == destination {
                 Some(r.next_hop)
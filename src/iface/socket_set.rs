@@ -2,6 +2,8 @@ use core::fmt;
 use managed::ManagedSlice;
 
 use super::socket_meta::Meta;
+#[cfg(feature = "socket-tcp")]
+use crate::socket::tcp;
 use crate::socket::{AnySocket, Socket};
 
 /// Opaque struct with space for storing one socket.
@@ -117,6 +119,27 @@ impl<'a> SocketSet<'a> {
         }
     }
 
+    /// Accept an established connection from the backlog of the listening TCP socket
+    /// `listener`, add it to the set, and return its handle.
+    ///
+    /// `socket` must be closed, and takes the place of the connection in the backlog, see
+    /// [`tcp::Socket::accept`]. It is dropped if no connection is accepted; use
+    /// [`tcp::Socket::can_accept`] to check for a connection first.
+    ///
+    /// # Panics
+    /// This function panics if the handle does not refer to a TCP socket of this set, or if
+    /// the storage is fixed-size (not a `Vec`) and is full.
+    #[cfg(feature = "socket-tcp")]
+    pub fn accept(
+        &mut self,
+        listener: SocketHandle,
+        mut socket: tcp::Socket<'a>,
+    ) -> Result<SocketHandle, tcp::AcceptError> {
+        self.get_mut::<tcp::Socket<'a>>(listener)
+            .accept(&mut socket)?;
+        Ok(self.add(socket))
+    }
+
     /// Remove a socket from the set, without changing its state.
     ///
     /// # Panics
@@ -1,12 +1,14 @@
 #[cfg(feature = "async")]
 use core::task::Waker;
 
-use crate::iface::Context;
+use crate::config::IFACE_MAX_ROUTE_COUNT;
+use crate::iface::{Context, Route, RouteKind};
 use crate::time::{Duration, Instant};
 use crate::wire::dhcpv4::field as dhcpv4_field;
 use crate::wire::{
-    DhcpMessageType, DhcpPacket, DhcpRepr, IpAddress, IpProtocol, Ipv4Address, Ipv4Cidr, Ipv4Repr,
-    UdpRepr, DHCP_CLIENT_PORT, DHCP_MAX_DNS_SERVER_COUNT, DHCP_SERVER_PORT, UDP_HEADER_LEN,
+    DhcpClasslessRoute, DhcpDomainSearch, DhcpMessageType, DhcpPacket, DhcpRepr, IpAddress, IpCidr,
+    IpProtocol, Ipv4Address, Ipv4Cidr, Ipv4Repr, UdpRepr, DHCP_CLIENT_PORT,
+    DHCP_MAX_DNS_SERVER_COUNT, DHCP_MAX_NTP_SERVER_COUNT, DHCP_SERVER_PORT, UDP_HEADER_LEN,
 };
 use crate::wire::{DhcpOption, HardwareAddress};
 use heapless::Vec;
@@ -16,12 +18,26 @@ use super::WakerRegistration;
 
 use super::PollAt;
 
+/// The maximum total length of the host name, domain name, domain search list and
+/// vendor-specific information kept from the server's configuration. The options that do not
+/// fit are dropped, except for the domain search list, which keeps the names that fit.
+pub const MAX_CONFIG_OPTIONS_LEN: usize = 128;
+
 const DEFAULT_LEASE_DURATION: Duration = Duration::from_secs(120);
 
+/// How long to wait before restarting the configuration after declining an address,
+/// see RFC 2131 § 3.1.5.
+const DECLINE_RESTART_DELAY: Duration = Duration::from_secs(10);
+
 const DEFAULT_PARAMETER_REQUEST_LIST: &[u8] = &[
     dhcpv4_field::OPT_SUBNET_MASK,
     dhcpv4_field::OPT_ROUTER,
     dhcpv4_field::OPT_DOMAIN_NAME_SERVER,
+    dhcpv4_field::OPT_DOMAIN_NAME,
+    dhcpv4_field::OPT_INTERFACE_MTU,
+    dhcpv4_field::OPT_NTP_SERVERS,
+    dhcpv4_field::OPT_DOMAIN_SEARCH,
+    dhcpv4_field::OPT_CLASSLESS_STATIC_ROUTE,
 ];
 
 /// IPv4 configuration data provided by the DHCP server.
@@ -38,10 +54,63 @@ pub struct Config<'a> {
     pub router: Option<Ipv4Address>,
     /// DNS servers
     pub dns_servers: Vec<Ipv4Address, DHCP_MAX_DNS_SERVER_COUNT>,
+    /// NTP servers
+    pub ntp_servers: Vec<Ipv4Address, DHCP_MAX_NTP_SERVER_COUNT>,
+    /// The MTU to use on the interface.
+    pub interface_mtu: Option<u16>,
+    /// The host name assigned by the server.
+    pub hostname: Option<&'a str>,
+    /// The domain name to use when resolving host names.
+    pub domain_name: Option<&'a str>,
+    /// The domain search list, truncated to fit into [`MAX_CONFIG_OPTIONS_LEN`] octets.
+    pub domain_search: Option<DhcpDomainSearch<'a>>,
+    /// The vendor-specific information (option 43), whose contents are opaque.
+    pub vendor_specific: Option<&'a [u8]>,
+    /// The classless static routes (see RFC 3442), including the default route if the server
+    /// provided one. Unless disabled with [`Socket::set_install_routes`], the routes other than
+    /// the default route are installed into the routing table of the interface.
+    pub routes: Vec<DhcpClasslessRoute, IFACE_MAX_ROUTE_COUNT>,
     /// Received DHCP packet
     pub packet: Option<DhcpPacket<&'a [u8]>>,
 }
 
+/// Variable-length options of the current configuration, which [`Config`] borrows.
+#[derive(Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct ConfigOptions {
+    /// The contents of the options, one after the other.
+    data: Vec<u8, MAX_CONFIG_OPTIONS_LEN>,
+    /// The start and end of each option in `data`.
+    hostname: Option<(u8, u8)>,
+    domain_name: Option<(u8, u8)>,
+    domain_search: Option<(u8, u8)>,
+    vendor_specific: Option<(u8, u8)>,
+}
+
+impl ConfigOptions {
+    /// Append the contents of an option, truncated to `max_len` octets, and return where they
+    /// are stored. Return `None` if the contents do not fit.
+    fn push(&mut self, contents: &[u8], max_len: usize) -> Option<(u8, u8)> {
+        let start = self.data.len();
+        let end = start + contents.len().min(max_len);
+        if start == end {
+            return None;
+        }
+        self.data.extend_from_slice(&contents[..end - start]).ok()?;
+        Some((start as u8, end as u8))
+    }
+
+    fn get(&self, range: Option<(u8, u8)>) -> Option<&[u8]> {
+        range.map(|(start, end)| &self.data[start as usize..end as usize])
+    }
+
+    fn get_str(&self, range: Option<(u8, u8)>) -> Option<&str> {
+        // The contents were checked to be valid UTF-8 when pushed.
+        self.get(range)
+            .and_then(|data| core::str::from_utf8(data).ok())
+    }
+}
+
 /// Information on how to reach a DHCP server.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -73,6 +142,26 @@ struct RequestState {
     requested_ip: Ipv4Address,
 }
 
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct RebootState {
+    /// When to send next request
+    retry_at: Instant,
+    /// How many retries have been done
+    retry: u16,
+    /// IP address of the previous lease, that we're trying to request.
+    requested_ip: Ipv4Address,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct InformState {
+    /// When to send next inform
+    retry_at: Instant,
+    /// Statically configured address.
+    address: Ipv4Cidr,
+}
+
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 struct RenewState {
@@ -107,8 +196,20 @@ enum ClientState {
     Discovering(DiscoverState),
     /// Requesting an address
     Requesting(RequestState),
+    /// Requesting the address of a previous lease, without knowing the server.
+    Rebooting(RebootState),
     /// Having an address, refresh it periodically.
     Renewing(RenewState),
+    /// Declining the acquired address, because another host uses it.
+    Declining(Config<'static>),
+    /// Releasing the acquired address.
+    Releasing(Config<'static>),
+    /// Requesting the configuration parameters of a statically configured address.
+    Informing(InformState),
+    /// Having the configuration parameters of a statically configured address.
+    Informed(Config<'static>),
+    /// Stopped, after the address has been released.
+    Stopped,
 }
 
 /// Timeout and retry configuration.
@@ -182,6 +283,19 @@ pub struct Socket<'a> {
     /// Incoming DHCP packets are copied into this buffer, overwriting the previous.
     receive_packet_buffer: Option<&'a mut [u8]>,
 
+    /// Variable-length options of the current configuration.
+    options: ConfigOptions,
+
+    /// Whether to install the classless static routes into the routing table.
+    install_routes: bool,
+    /// Routes currently installed into the routing table.
+    installed_routes: Vec<DhcpClasslessRoute, IFACE_MAX_ROUTE_COUNT>,
+
+    /// How long to acquire an address before the interface falls back to a link-local address.
+    link_local_timeout: Duration,
+    /// When the client started to acquire an address, if it has none.
+    unconfigured_since: Option<Instant>,
+
     /// Waker registration
     #[cfg(feature = "async")]
     waker: WakerRegistration,
@@ -208,6 +322,11 @@ impl<'a> Socket<'a> {
             outgoing_options: &[],
             parameter_request_list: None,
             receive_packet_buffer: None,
+            options: ConfigOptions::default(),
+            install_routes: true,
+            installed_routes: Vec::new(),
+            link_local_timeout: Duration::from_secs(10),
+            unconfigured_since: None,
             #[cfg(feature = "async")]
             waker: WakerRegistration::new(),
             server_port: DHCP_SERVER_PORT,
@@ -278,6 +397,42 @@ impl<'a> Socket<'a> {
         self.ignore_naks = ignore_naks;
     }
 
+    /// Get whether the classless static routes are installed into the routing table.
+    ///
+    /// See also [`Self::set_install_routes()`]
+    pub fn install_routes(&self) -> bool {
+        self.install_routes
+    }
+
+    /// Set whether the classless static routes are installed into the routing table.
+    ///
+    /// When enabled (the default), the routes of [`Config::routes`], other than the default
+    /// route, are added to the routing table of the interface while the configuration is valid,
+    /// and removed when it is lost. The default route is left to the application, like the
+    /// [`router`](Config::router) it is reported as.
+    pub fn set_install_routes(&mut self, install_routes: bool) {
+        self.install_routes = install_routes;
+    }
+
+    /// Get the time after which the interface falls back to a link-local address.
+    ///
+    /// See also [`Self::set_link_local_timeout()`]
+    pub fn link_local_timeout(&self) -> Duration {
+        self.link_local_timeout
+    }
+
+    /// Set the time after which the interface falls back to a link-local address.
+    ///
+    /// When the dynamic configuration of an IPv4 link-local address is enabled on the interface
+    /// (see [`Interface::set_ipv4_link_local`]), the link-local address is only selected if no
+    /// address was acquired this long after the client started, and it is removed once an
+    /// address is acquired. The default is 10 seconds.
+    ///
+    /// [`Interface::set_ipv4_link_local`]: crate::iface::Interface::set_ipv4_link_local
+    pub fn set_link_local_timeout(&mut self, link_local_timeout: Duration) {
+        self.link_local_timeout = link_local_timeout;
+    }
+
     /// Set the server/client port
     ///
     /// Allows you to specify the ports used by DHCP.
@@ -288,15 +443,23 @@ impl<'a> Socket<'a> {
     }
 
     pub(crate) fn poll_at(&self, _cx: &mut Context) -> PollAt {
+        if self.installed_routes != self.wanted_routes() {
+            return PollAt::Now;
+        }
+
         let t = match &self.state {
             ClientState::Discovering(state) => state.retry_at,
             ClientState::Requesting(state) => state.retry_at,
+            ClientState::Rebooting(state) => state.retry_at,
             ClientState::Renewing(state) => if state.rebinding {
                 state.rebind_at
             } else {
                 state.renew_at.min(state.rebind_at)
             }
             .min(state.expires_at),
+            ClientState::Declining(_) | ClientState::Releasing(_) => return PollAt::Now,
+            ClientState::Informing(state) => state.retry_at,
+            ClientState::Informed(_) | ClientState::Stopped => return PollAt::Ingress,
         };
         PollAt::Time(t)
     }
@@ -384,6 +547,7 @@ impl<'a> Socket<'a> {
                 if let Some((config, renew_at, rebind_at, expires_at)) =
                     Self::parse_ack(cx.now(), &dhcp_repr, self.max_lease_duration, state.server)
                 {
+                    self.options = Self::parse_options(&dhcp_packet);
                     self.state = ClientState::Renewing(RenewState {
                         config,
                         renew_at,
@@ -399,6 +563,30 @@ impl<'a> Socket<'a> {
                     self.reset();
                 }
             }
+            (ClientState::Rebooting(_), DhcpMessageType::Ack) => {
+                let server = ServerInfo {
+                    address: src_ip,
+                    identifier: server_identifier,
+                };
+                if let Some((config, renew_at, rebind_at, expires_at)) =
+                    Self::parse_ack(cx.now(), &dhcp_repr, self.max_lease_duration, server)
+                {
+                    self.options = Self::parse_options(&dhcp_packet);
+                    self.state = ClientState::Renewing(RenewState {
+                        config,
+                        renew_at,
+                        rebind_at,
+                        expires_at,
+                        rebinding: false,
+                    });
+                    self.config_changed();
+                }
+            }
+            (ClientState::Rebooting(_), DhcpMessageType::Nak) => {
+                if !self.ignore_naks {
+                    self.reset();
+                }
+            }
             (ClientState::Renewing(state), DhcpMessageType::Ack) => {
                 if let Some((config, renew_at, rebind_at, expires_at)) = Self::parse_ack(
                     cx.now(),
@@ -415,11 +603,14 @@ impl<'a> Socket<'a> {
                     // will change it, so we indicate that the config has
                     // changed every time if the receive packet buffer is set,
                     // but we only write changes to the rest of the config now.
-                    let config_changed =
-                        state.config != config || self.receive_packet_buffer.is_some();
+                    let options = Self::parse_options(&dhcp_packet);
+                    let config_changed = state.config != config
+                        || self.options != options
+                        || self.receive_packet_buffer.is_some();
                     if state.config != config {
                         state.config = config;
                     }
+                    self.options = options;
                     if config_changed {
                         self.config_changed();
                     }
@@ -430,6 +621,16 @@ impl<'a> Socket<'a> {
                     self.reset();
                 }
             }
+            (ClientState::Informing(state), DhcpMessageType::Ack) => {
+                let server = ServerInfo {
+                    address: src_ip,
+                    identifier: server_identifier,
+                };
+                self.options = Self::parse_options(&dhcp_packet);
+                self.state =
+                    ClientState::Informed(Self::parse_config(&dhcp_repr, server, state.address));
+                self.config_changed();
+            }
             _ => {
                 net_debug!(
                     "DHCP ignoring {:?}: unexpected in current state",
@@ -474,28 +675,8 @@ impl<'a> Socket<'a> {
             lease_duration = lease_duration.min(max_lease_duration);
         }
 
-        // Cleanup the DNS servers list, keeping only unicasts/
-        // TP-Link TD-W8970 sends 0.0.0.0 as second DNS server if there's only one configured :(
-        let mut dns_servers = Vec::new();
-
-        dhcp_repr
-            .dns_servers
-            .iter()
-            .flatten()
-            .filter(|s| s.is_unicast())
-            .for_each(|a| {
-                // This will never produce an error, as both the arrays and `dns_servers`
-                // have length DHCP_MAX_DNS_SERVER_COUNT
-                dns_servers.push(*a).ok();
-            });
-
-        let config = Config {
-            server,
-            address: Ipv4Cidr::new(dhcp_repr.your_ip, prefix_len),
-            router: dhcp_repr.router,
-            dns_servers,
-            packet: None,
-        };
+        let address = Ipv4Cidr::new(dhcp_repr.your_ip, prefix_len);
+        let config = Self::parse_config(dhcp_repr, server, address);
 
         // Set renew and rebind times as per RFC 2131:
         // Times T1 and T2 are configurable by the server through
@@ -537,6 +718,181 @@ impl<'a> Socket<'a> {
         Some((config, renew_at, rebind_at, expires_at))
     }
 
+    /// Return the configuration of `address` provided by a DHCPACK.
+    fn parse_config(
+        dhcp_repr: &DhcpRepr,
+        server: ServerInfo,
+        address: Ipv4Cidr,
+    ) -> Config<'static> {
+        let mut routes = Vec::new();
+        let mut ignored_routes = 0;
+        for route in dhcp_repr.classless_routes.iter().flat_map(|r| r.iter()) {
+            if routes.push(route).is_err() {
+                ignored_routes += 1;
+            }
+        }
+        if ignored_routes > 0 {
+            net_debug!(
+                "DHCP ignoring {} classless routes: at most {} are supported",
+                ignored_routes,
+                IFACE_MAX_ROUTE_COUNT
+            );
+        }
+
+        // RFC 3442: if the classless static route option is present, the router option
+        // must be ignored. The default route is looked up in the whole option, so that it
+        // is kept even if other routes were ignored.
+        let router = match dhcp_repr.classless_routes {
+            Some(classless_routes) => classless_routes
+                .iter()
+                .find(|r| r.destination.prefix_len() == 0 && r.router.is_unicast())
+                .map(|r| r.router),
+            None => dhcp_repr.router,
+        };
+
+        let mut ntp_servers = Vec::new();
+        dhcp_repr
+            .ntp_servers
+            .iter()
+            .flatten()
+            .filter(|s| s.is_unicast())
+            .for_each(|a| {
+                ntp_servers.push(*a).ok();
+            });
+
+        Config {
+            server,
+            address,
+            router,
+            dns_servers: Self::parse_dns_servers(dhcp_repr),
+            ntp_servers,
+            interface_mtu: dhcp_repr.interface_mtu,
+            hostname: None,
+            domain_name: None,
+            domain_search: None,
+            vendor_specific: None,
+            routes,
+            packet: None,
+        }
+    }
+
+    /// Return the variable-length options provided by a DHCPACK, dropping those that do not fit.
+    fn parse_options(dhcp_packet: &DhcpPacket<&[u8]>) -> ConfigOptions {
+        let mut options = ConfigOptions::default();
+        if let Some(hostname) = dhcp_packet.hostname() {
+            options.hostname = options.push(hostname.as_bytes(), usize::MAX);
+        }
+        if let Some(domain_name) = dhcp_packet.domain_name() {
+            options.domain_name = options.push(domain_name.as_bytes(), usize::MAX);
+        }
+        if let Some(domain_search) = dhcp_packet.domain_search() {
+            // Compression pointers only refer to earlier names, so the truncated list
+            // keeps the names that fit whole.
+            let max_len = MAX_CONFIG_OPTIONS_LEN - options.data.len();
+            options.domain_search = options.push(domain_search.as_bytes(), max_len);
+        }
+        if let Some(vendor_specific) = dhcp_packet.vendor_specific() {
+            options.vendor_specific = options.push(vendor_specific, usize::MAX);
+        }
+        options
+    }
+
+    fn parse_dns_servers(dhcp_repr: &DhcpRepr) -> Vec<Ipv4Address, DHCP_MAX_DNS_SERVER_COUNT> {
+        // Cleanup the DNS servers list, keeping only unicasts/
+        // TP-Link TD-W8970 sends 0.0.0.0 as second DNS server if there's only one configured :(
+        let mut dns_servers = Vec::new();
+
+        dhcp_repr
+            .dns_servers
+            .iter()
+            .flatten()
+            .filter(|s| s.is_unicast())
+            .for_each(|a| {
+                // This will never produce an error, as both the arrays and `dns_servers`
+                // have length DHCP_MAX_DNS_SERVER_COUNT
+                dns_servers.push(*a).ok();
+            });
+
+        dns_servers
+    }
+
+    /// Return the classless static routes that should be in the routing table.
+    fn wanted_routes(&self) -> Vec<DhcpClasslessRoute, IFACE_MAX_ROUTE_COUNT> {
+        let config = match &self.state {
+            ClientState::Renewing(state) if self.install_routes => &state.config,
+            ClientState::Informed(config) if self.install_routes => config,
+            _ => return Vec::new(),
+        };
+        config
+            .routes
+            .iter()
+            .filter(|r| r.destination.prefix_len() != 0)
+            .copied()
+            .collect()
+    }
+
+    /// Add the wanted classless static routes to the routing table of the interface, and
+    /// remove those that are no longer wanted.
+    fn sync_routes(&mut self, cx: &mut Context) {
+        let wanted = self.wanted_routes();
+        if self.installed_routes == wanted {
+            return;
+        }
+
+        let installed = &self.installed_routes;
+        cx.routes_mut().update(|routes| {
+            routes.retain(|route| !installed.iter().any(|r| Self::is_classless_route(route, r)))
+        });
+        for route in &wanted {
+            net_debug!(
+                "DHCP adding route to {} via {}",
+                route.destination,
+                route.router
+            );
+            if cx
+                .routes_mut()
+                .add_route(Self::classless_route(route))
+                .is_err()
+            {
+                net_debug!("DHCP failed to add route: routing table full");
+            }
+        }
+        self.installed_routes = wanted;
+    }
+
+    /// Report to the interface whether an address was acquired, so that it falls back to a
+    /// link-local address when none was in time.
+    fn sync_link_local(&mut self, cx: &mut Context) {
+        let fallback_at = match &self.state {
+            ClientState::Renewing(_) | ClientState::Informed(_) => {
+                self.unconfigured_since = None;
+                None
+            }
+            _ => {
+                let since = *self.unconfigured_since.get_or_insert(cx.now());
+                Some(since + self.link_local_timeout)
+            }
+        };
+        cx.acd_dhcpv4_update(fallback_at);
+    }
+
+    /// Return the routing table entry of a classless static route.
+    fn classless_route(route: &DhcpClasslessRoute) -> Route {
+        let cidr = IpCidr::Ipv4(route.destination);
+        if route.router.is_unspecified() {
+            Route::new_kind(cidr, RouteKind::OnLink)
+        } else {
+            Route::new(cidr, route.router.into())
+        }
+    }
+
+    fn is_classless_route(route: &Route, classless: &DhcpClasslessRoute) -> bool {
+        let expected = Self::classless_route(classless);
+        route.cidr == expected.cidr
+            && route.kind == expected.kind
+            && route.via_router == expected.via_router
+    }
+
     #[cfg(not(test))]
     fn random_transaction_id(cx: &mut Context) -> u32 {
         cx.rand().rand_u32()
@@ -557,6 +913,9 @@ impl<'a> Socket<'a> {
             panic!("using DHCPv4 socket with a non-ethernet hardware address.");
         };
 
+        self.sync_routes(cx);
+        self.sync_link_local(cx);
+
         // Worst case biggest IPv4 header length.
         // 0x0f * 4 = 60 bytes.
         const MAX_IPV4_HEADER_LEN: usize = 60;
@@ -588,6 +947,9 @@ impl<'a> Socket<'a> {
             lease_duration: None,
             renew_duration: None,
             rebind_duration: None,
+            ntp_servers: None,
+            interface_mtu: None,
+            classless_routes: None,
             dns_servers: None,
             additional_options: self.outgoing_options,
         };
@@ -656,6 +1018,37 @@ impl<'a> Socket<'a> {
                 self.transaction_id = next_transaction_id;
                 Ok(())
             }
+            ClientState::Rebooting(state) => {
+                if cx.now() < state.retry_at {
+                    return Ok(());
+                }
+
+                if state.retry >= self.retry_config.request_retries {
+                    net_debug!("DHCP reboot retries exceeded, restarting discovery");
+                    self.reset();
+                    return Ok(());
+                }
+
+                // The server is not known: the request has no server identifier.
+                dhcp_repr.message_type = DhcpMessageType::Request;
+                dhcp_repr.requested_ip = Some(state.requested_ip);
+
+                net_debug!(
+                    "DHCP send reboot request to {}: {:?}",
+                    ipv4_repr.dst_addr,
+                    dhcp_repr
+                );
+                ipv4_repr.payload_len = udp_repr.header_len() + dhcp_repr.buffer_len();
+                emit(cx, (ipv4_repr, udp_repr, dhcp_repr))?;
+
+                // Exponential backoff: Double every 2 retries.
+                state.retry_at = cx.now()
+                    + (self.retry_config.initial_request_timeout << (state.retry as u32 / 2));
+                state.retry += 1;
+
+                self.transaction_id = next_transaction_id;
+                Ok(())
+            }
             ClientState::Renewing(state) => {
                 let now = cx.now();
                 if state.expires_at <= now {
@@ -708,6 +1101,70 @@ impl<'a> Socket<'a> {
                 self.transaction_id = next_transaction_id;
                 Ok(())
             }
+            ClientState::Declining(config) => {
+                dhcp_repr.message_type = DhcpMessageType::Decline;
+                dhcp_repr.requested_ip = Some(config.address.address());
+                dhcp_repr.server_identifier = Some(config.server.identifier);
+                dhcp_repr.parameter_request_list = None;
+                dhcp_repr.max_size = None;
+
+                net_debug!(
+                    "DHCP send decline to {}: {:?}",
+                    ipv4_repr.dst_addr,
+                    dhcp_repr
+                );
+                ipv4_repr.payload_len = udp_repr.header_len() + dhcp_repr.buffer_len();
+                emit(cx, (ipv4_repr, udp_repr, dhcp_repr))?;
+
+                self.state = ClientState::Discovering(DiscoverState {
+                    retry_at: cx.now() + DECLINE_RESTART_DELAY,
+                });
+                self.transaction_id = next_transaction_id;
+                Ok(())
+            }
+            ClientState::Releasing(config) => {
+                ipv4_repr.src_addr = config.address.address();
+                ipv4_repr.dst_addr = config.server.address;
+                dhcp_repr.message_type = DhcpMessageType::Release;
+                dhcp_repr.client_ip = config.address.address();
+                dhcp_repr.server_identifier = Some(config.server.identifier);
+                dhcp_repr.parameter_request_list = None;
+                dhcp_repr.max_size = None;
+
+                net_debug!(
+                    "DHCP send release to {}: {:?}",
+                    ipv4_repr.dst_addr,
+                    dhcp_repr
+                );
+                ipv4_repr.payload_len = udp_repr.header_len() + dhcp_repr.buffer_len();
+                emit(cx, (ipv4_repr, udp_repr, dhcp_repr))?;
+
+                self.state = ClientState::Stopped;
+                self.transaction_id = next_transaction_id;
+                Ok(())
+            }
+            ClientState::Informing(state) => {
+                if cx.now() < state.retry_at {
+                    return Ok(());
+                }
+
+                ipv4_repr.src_addr = state.address.address();
+                dhcp_repr.message_type = DhcpMessageType::Inform;
+                dhcp_repr.client_ip = state.address.address();
+
+                net_debug!(
+                    "DHCP send inform to {}: {:?}",
+                    ipv4_repr.dst_addr,
+                    dhcp_repr
+                );
+                ipv4_repr.payload_len = udp_repr.header_len() + dhcp_repr.buffer_len();
+                emit(cx, (ipv4_repr, udp_repr, dhcp_repr))?;
+
+                state.retry_at = cx.now() + self.retry_config.discover_timeout;
+                self.transaction_id = next_transaction_id;
+                Ok(())
+            }
+            ClientState::Informed(_) | ClientState::Stopped => Ok(()),
         }
     }
 
@@ -717,12 +1174,73 @@ impl<'a> Socket<'a> {
     /// network if a link was down and it is now back up.
     pub fn reset(&mut self) {
         net_trace!("DHCP reset");
-        if let ClientState::Renewing(_) = &self.state {
+        self.restart(ClientState::Discovering(DiscoverState {
+            retry_at: Instant::from_millis(0),
+        }));
+    }
+
+    /// Restart in the INIT-REBOOT state, requesting the address of a previous lease.
+    ///
+    /// Use this after a reboot, with the address of a lease that was stored and has
+    /// not expired yet. The request is broadcast to any server; if no server answers
+    /// or a server rejects the address, the discovery phase is restarted.
+    pub fn reboot(&mut self, address: Ipv4Address) {
+        net_trace!("DHCP reboot with {}", address);
+        self.restart(ClientState::Rebooting(RebootState {
+            retry_at: Instant::from_millis(0),
+            retry: 0,
+            requested_ip: address,
+        }));
+    }
+
+    /// Request the configuration parameters for a statically configured address,
+    /// with DHCPINFORM messages.
+    ///
+    /// The address must already be configured on the interface. Once a server
+    /// answers, the parameters are returned by [`Self::poll()`] as a configuration
+    /// with the given address, and no lease.
+    pub fn inform(&mut self, address: Ipv4Cidr) {
+        net_trace!("DHCP inform for {}", address);
+        self.restart(ClientState::Informing(InformState {
+            retry_at: Instant::from_millis(0),
+            address,
+        }));
+    }
+
+    /// Decline the acquired address, because another host uses it.
+    ///
+    /// Use this when the address conflict detection of the interface reports a
+    /// conflict for the address (see [`AcdEvent`]). A DHCPDECLINE is sent to the
+    /// server, and the discovery phase is restarted after 10 seconds.
+    ///
+    /// [`AcdEvent`]: crate::iface::AcdEvent
+    pub fn decline(&mut self) {
+        if let ClientState::Renewing(state) = &self.state {
+            net_trace!("DHCP decline {}", state.config.address);
+            let config = state.config.clone();
+            self.restart(ClientState::Declining(config));
+        }
+    }
+
+    /// Release the acquired address, and stop the client.
+    ///
+    /// Use this when the interface is shut down. A DHCPRELEASE is sent to the
+    /// server if an address was acquired. The client stays stopped until
+    /// [`Self::reset()`] is called.
+    pub fn release(&mut self) {
+        net_trace!("DHCP release");
+        let state = match &self.state {
+            ClientState::Renewing(state) => ClientState::Releasing(state.config.clone()),
+            _ => ClientState::Stopped,
+        };
+        self.restart(state);
+    }
+
+    fn restart(&mut self, state: ClientState) {
+        if let ClientState::Renewing(_) | ClientState::Informed(_) = &self.state {
             self.config_changed();
         }
-        self.state = ClientState::Discovering(DiscoverState {
-            retry_at: Instant::from_millis(0),
-        });
+        self.state = state;
     }
 
     /// Query the socket for configuration changes.
@@ -730,15 +1248,30 @@ impl<'a> Socket<'a> {
     /// The socket has an internal "configuration changed" flag. If
     /// set, this function returns the configuration and resets the flag.
     pub fn poll(&mut self) -> Option<Event> {
+        let config = match &self.state {
+            ClientState::Renewing(state) => Some(&state.config),
+            ClientState::Informed(config) => Some(config),
+            _ => None,
+        };
         if !self.config_changed {
             None
-        } else if let ClientState::Renewing(state) = &self.state {
+        } else if let Some(config) = config {
             self.config_changed = false;
             Some(Event::Configured(Config {
-                server: state.config.server,
-                address: state.config.address,
-                router: state.config.router,
-                dns_servers: state.config.dns_servers.clone(),
+                server: config.server,
+                address: config.address,
+                router: config.router,
+                dns_servers: config.dns_servers.clone(),
+                ntp_servers: config.ntp_servers.clone(),
+                interface_mtu: config.interface_mtu,
+                hostname: self.options.get_str(self.options.hostname),
+                domain_name: self.options.get_str(self.options.domain_name),
+                domain_search: self
+                    .options
+                    .get(self.options.domain_search)
+                    .map(DhcpDomainSearch::new),
+                vendor_specific: self.options.get(self.options.vendor_specific),
+                routes: config.routes.clone(),
                 packet: self
                     .receive_packet_buffer
                     .as_deref()
@@ -782,7 +1315,7 @@ mod test {
     use std::ops::{Deref, DerefMut};
 
     use super::*;
-    use crate::wire::EthernetAddress;
+    use crate::wire::{DhcpClasslessRoutes, EthernetAddress};
 
     // =========================================================================================//
     // Helper functions
@@ -972,6 +1505,9 @@ mod test {
         max_size: None,
         renew_duration: None,
         rebind_duration: None,
+        ntp_servers: None,
+        interface_mtu: None,
+        classless_routes: None,
         lease_duration: None,
         additional_options: &[],
     };
@@ -979,7 +1515,7 @@ mod test {
     const DHCP_DISCOVER: DhcpRepr = DhcpRepr {
         message_type: DhcpMessageType::Discover,
         client_identifier: Some(MY_MAC),
-        parameter_request_list: Some(&[1, 3, 6]),
+        parameter_request_list: Some(&[1, 3, 6, 15, 26, 42, 119, 121]),
         max_size: Some(1432),
         ..DHCP_DEFAULT
     };
@@ -1007,7 +1543,7 @@ mod test {
         max_size: Some(1432),
 
         requested_ip: Some(MY_IP),
-        parameter_request_list: Some(&[1, 3, 6]),
+        parameter_request_list: Some(&[1, 3, 6, 15, 26, 42, 119, 121]),
         ..DHCP_DEFAULT
     };
 
@@ -1042,7 +1578,7 @@ mod test {
         max_size: Some(1432),
 
         requested_ip: None,
-        parameter_request_list: Some(&[1, 3, 6]),
+        parameter_request_list: Some(&[1, 3, 6, 15, 26, 42, 119, 121]),
         ..DHCP_DEFAULT
     };
 
@@ -1054,7 +1590,7 @@ mod test {
         max_size: Some(1432),
 
         requested_ip: None,
-        parameter_request_list: Some(&[1, 3, 6]),
+        parameter_request_list: Some(&[1, 3, 6, 15, 26, 42, 119, 121]),
         ..DHCP_DEFAULT
     };
 
@@ -1097,6 +1633,13 @@ mod test {
                 },
                 address: Ipv4Cidr::new(MY_IP, 24),
                 dns_servers: Vec::from_slice(DNS_IPS).unwrap(),
+                ntp_servers: Vec::new(),
+                interface_mtu: None,
+                hostname: None,
+                domain_name: None,
+                domain_search: None,
+                vendor_specific: None,
+                routes: Vec::new(),
                 router: Some(SERVER_IP),
                 packet: None,
             },
@@ -1132,6 +1675,13 @@ mod test {
                 },
                 address: Ipv4Cidr::new(MY_IP, 24),
                 dns_servers: Vec::from_slice(DNS_IPS).unwrap(),
+                ntp_servers: Vec::new(),
+                interface_mtu: None,
+                hostname: None,
+                domain_name: None,
+                domain_search: None,
+                vendor_specific: None,
+                routes: Vec::new(),
                 router: Some(SERVER_IP),
                 packet: None,
             }))
@@ -1147,6 +1697,181 @@ mod test {
         }
     }
 
+    // 10.0.0.0/8 via 192.168.1.2, 192.168.16.0/20 on-link, and the default route via
+    // 192.168.1.3.
+    const CLASSLESS_ROUTES: &[u8] = &[
+        8, 10, 192, 168, 1, 2, //
+        20, 192, 168, 16, 0, 0, 0, 0, //
+        0, 192, 168, 1, 3,
+    ];
+
+    const ACK_OPTIONS: &[DhcpOption] = &[
+        DhcpOption {
+            kind: dhcpv4_field::OPT_HOST_NAME,
+            data: b"host",
+        },
+        DhcpOption {
+            kind: dhcpv4_field::OPT_DOMAIN_NAME,
+            data: b"example.com",
+        },
+        DhcpOption {
+            kind: dhcpv4_field::OPT_DOMAIN_SEARCH,
+            data: &[
+                7, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 3, b'c', b'o', b'm', 0,
+            ],
+        },
+        DhcpOption {
+            kind: dhcpv4_field::OPT_VENDOR_SPECIFIC_INFO,
+            data: &[1, 2, 0xaa, 0xbb],
+        },
+    ];
+
+    fn dhcp_ack_options() -> DhcpRepr<'static> {
+        DhcpRepr {
+            ntp_servers: Some(Vec::from_slice(&[SERVER_IP]).unwrap()),
+            interface_mtu: Some(1400),
+            classless_routes: Some(DhcpClasslessRoutes::new_unchecked(CLASSLESS_ROUTES)),
+            additional_options: ACK_OPTIONS,
+            ..dhcp_ack()
+        }
+    }
+
+    fn lookup(s: &mut TestSocket, addr: Ipv4Address) -> Option<IpAddress> {
+        let now = s.cx.now();
+        s.cx.routes_mut().lookup(&addr.into(), now)
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_bind_options(#[case] medium: Medium) {
+        let mut s = socket(medium);
+
+        recv!(s, [(IP_BROADCAST, UDP_SEND, DHCP_DISCOVER)]);
+        send!(s, (IP_RECV, UDP_RECV, dhcp_offer()));
+        recv!(s, [(IP_BROADCAST, UDP_SEND, DHCP_REQUEST)]);
+        send!(s, (IP_RECV, UDP_RECV, dhcp_ack_options()));
+
+        {
+            let Some(Event::Configured(config)) = s.poll() else {
+                panic!("Expected a configuration");
+            };
+            assert_eq!(config.ntp_servers, [SERVER_IP]);
+            assert_eq!(config.interface_mtu, Some(1400));
+            assert_eq!(config.hostname, Some("host"));
+            assert_eq!(config.domain_name, Some("example.com"));
+            let domain_search = config.domain_search.unwrap();
+            let mut names = domain_search.iter();
+            assert_eq!(names.next().unwrap().to_string(), "example.com");
+            assert!(names.next().is_none());
+            assert_eq!(config.vendor_specific, Some(&[1, 2, 0xaa, 0xbb][..]));
+            assert_eq!(config.routes.len(), 3);
+            // The router option is ignored in favor of the default classless route.
+            assert_eq!(config.router, Some(Ipv4Address([192, 168, 1, 3])));
+        }
+
+        // The routes other than the default one are installed into the routing table.
+        recv!(s, []);
+        assert_eq!(
+            lookup(&mut s, Ipv4Address([10, 1, 2, 3])),
+            Some(IpAddress::v4(192, 168, 1, 2))
+        );
+        assert_eq!(
+            lookup(&mut s, Ipv4Address([192, 168, 20, 1])),
+            Some(IpAddress::v4(192, 168, 20, 1))
+        );
+        assert_eq!(lookup(&mut s, Ipv4Address([8, 8, 8, 8])), None);
+
+        // The routes are removed along with the configuration.
+        s.reset();
+        assert_eq!(s.poll(), Some(Event::Deconfigured));
+        recv!(s, [(IP_BROADCAST, UDP_SEND, DHCP_DISCOVER)]);
+        assert_eq!(lookup(&mut s, Ipv4Address([10, 1, 2, 3])), None);
+        assert_eq!(lookup(&mut s, Ipv4Address([192, 168, 20, 1])), None);
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_bind_options_truncated(#[case] medium: Medium) {
+        // More routes than fit into the configuration, with the default route last.
+        const ROUTES: &[u8] = &[
+            8, 10, 192, 168, 1, 2, //
+            12, 172, 16, 192, 168, 1, 2, //
+            16, 192, 169, 192, 168, 1, 2, //
+            20, 192, 168, 16, 0, 0, 0, 0, //
+            0, 192, 168, 1, 3,
+        ];
+        let long_name = [b'a'; 110];
+        let options = [
+            DhcpOption {
+                kind: dhcpv4_field::OPT_HOST_NAME,
+                data: &long_name,
+            },
+            DhcpOption {
+                kind: dhcpv4_field::OPT_DOMAIN_SEARCH,
+                data: &[
+                    7, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 3, b'c', b'o', b'm', 0, //
+                    3, b'l', b'a', b'n', 0xc0, 8,
+                ],
+            },
+            DhcpOption {
+                kind: dhcpv4_field::OPT_VENDOR_SPECIFIC_INFO,
+                data: &[0xaa; 32],
+            },
+        ];
+
+        let mut s = socket(medium);
+
+        recv!(s, [(IP_BROADCAST, UDP_SEND, DHCP_DISCOVER)]);
+        send!(s, (IP_RECV, UDP_RECV, dhcp_offer()));
+        recv!(s, [(IP_BROADCAST, UDP_SEND, DHCP_REQUEST)]);
+        send!(
+            s,
+            (
+                IP_RECV,
+                UDP_RECV,
+                DhcpRepr {
+                    classless_routes: Some(DhcpClasslessRoutes::new_unchecked(ROUTES)),
+                    additional_options: &options,
+                    ..dhcp_ack()
+                }
+            )
+        );
+
+        let Some(Event::Configured(config)) = s.poll() else {
+            panic!("Expected a configuration");
+        };
+        assert_eq!(config.routes.len(), IFACE_MAX_ROUTE_COUNT);
+        // The default route is used even though it did not fit.
+        assert_eq!(config.router, Some(Ipv4Address([192, 168, 1, 3])));
+        assert_eq!(config.hostname.map(str::len), Some(110));
+        // The domain search list keeps the names that fit.
+        let domain_search = config.domain_search.unwrap();
+        let names: std::vec::Vec<_> = domain_search.iter().map(|n| n.to_string()).collect();
+        assert_eq!(names, ["example.com"]);
+        // The vendor-specific information does not fit.
+        assert_eq!(config.vendor_specific, None);
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_install_routes_disabled(#[case] medium: Medium) {
+        let mut s = socket(medium);
+
+        recv!(s, [(IP_BROADCAST, UDP_SEND, DHCP_DISCOVER)]);
+        send!(s, (IP_RECV, UDP_RECV, dhcp_offer()));
+        recv!(s, [(IP_BROADCAST, UDP_SEND, DHCP_REQUEST)]);
+        send!(s, (IP_RECV, UDP_RECV, dhcp_ack_options()));
+        recv!(s, []);
+        assert!(lookup(&mut s, Ipv4Address([10, 1, 2, 3])).is_some());
+
+        s.set_install_routes(false);
+        recv!(s, []);
+        assert_eq!(lookup(&mut s, Ipv4Address([10, 1, 2, 3])), None);
+    }
+
     #[rstest]
     #[case::ip(Medium::Ethernet)]
     #[cfg(feature = "medium-ethernet")]
@@ -1170,6 +1895,13 @@ mod test {
                 },
                 address: Ipv4Cidr::new(MY_IP, 24),
                 dns_servers: Vec::from_slice(DNS_IPS).unwrap(),
+                ntp_servers: Vec::new(),
+                interface_mtu: None,
+                hostname: None,
+                domain_name: None,
+                domain_search: None,
+                vendor_specific: None,
+                routes: Vec::new(),
                 router: Some(SERVER_IP),
                 packet: None,
             }))
@@ -1414,4 +2146,163 @@ mod test {
         send!(s, time 500_000, (IP_SERVER_BROADCAST, UDP_RECV, DHCP_NAK));
         recv!(s, time 500_000, [(IP_BROADCAST, UDP_SEND, DHCP_DISCOVER)]);
     }
+
+    #[rstest]
+    #[case::ip(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_release(#[case] medium: Medium) {
+        let mut s = socket_bound(medium);
+
+        s.release();
+        assert_eq!(s.poll(), Some(Event::Deconfigured));
+        recv!(s, time 100_000, [(IP_SEND, UDP_SEND, DhcpRepr {
+            message_type: DhcpMessageType::Release,
+            client_identifier: Some(MY_MAC),
+            client_ip: MY_IP,
+            server_identifier: Some(SERVER_IP),
+            ..DHCP_DEFAULT
+        })]);
+
+        // The client stays stopped.
+        recv!(s, time 500_000, []);
+        recv!(s, time 1_000_000, []);
+        assert_eq!(s.poll(), None);
+
+        s.reset();
+        recv!(s, time 1_000_000, [(IP_BROADCAST, UDP_SEND, DHCP_DISCOVER)]);
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_decline(#[case] medium: Medium) {
+        let mut s = socket_bound(medium);
+
+        s.decline();
+        assert_eq!(s.poll(), Some(Event::Deconfigured));
+        recv!(s, time 1_000, [(IP_BROADCAST, UDP_SEND, DhcpRepr {
+            message_type: DhcpMessageType::Decline,
+            client_identifier: Some(MY_MAC),
+            requested_ip: Some(MY_IP),
+            server_identifier: Some(SERVER_IP),
+            ..DHCP_DEFAULT
+        })]);
+
+        // The discovery restarts after a delay.
+        recv!(s, time 10_000, []);
+        recv!(s, time 11_000, [(IP_BROADCAST, UDP_SEND, DHCP_DISCOVER)]);
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_reboot(#[case] medium: Medium) {
+        let mut s = socket(medium);
+
+        const DHCP_REBOOT: DhcpRepr = DhcpRepr {
+            server_identifier: None,
+            ..DHCP_REQUEST
+        };
+
+        s.reboot(MY_IP);
+        recv!(s, time 0, [(IP_BROADCAST, UDP_SEND, DHCP_REBOOT)]);
+        recv!(s, time 5_000, [(IP_BROADCAST, UDP_SEND, DHCP_REBOOT)]);
+        assert_eq!(s.poll(), None);
+        send!(s, time 5_000, (IP_RECV, UDP_RECV, dhcp_ack()));
+
+        assert_eq!(
+            s.poll(),
+            Some(Event::Configured(Config {
+                server: ServerInfo {
+                    address: SERVER_IP,
+                    identifier: SERVER_IP,
+                },
+                address: Ipv4Cidr::new(MY_IP, 24),
+                dns_servers: Vec::from_slice(DNS_IPS).unwrap(),
+                ntp_servers: Vec::new(),
+                interface_mtu: None,
+                hostname: None,
+                domain_name: None,
+                domain_search: None,
+                vendor_specific: None,
+                routes: Vec::new(),
+                router: Some(SERVER_IP),
+                packet: None,
+            }))
+        );
+
+        match &s.state {
+            ClientState::Renewing(r) => {
+                assert_eq!(r.renew_at, Instant::from_secs(5 + 500));
+                assert_eq!(r.expires_at, Instant::from_secs(5 + 1000));
+            }
+            _ => panic!("Invalid state"),
+        }
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_reboot_nak(#[case] medium: Medium) {
+        let mut s = socket(medium);
+
+        s.reboot(MY_IP);
+        recv!(s, time 0, [(IP_BROADCAST, UDP_SEND, DhcpRepr {
+            server_identifier: None,
+            ..DHCP_REQUEST
+        })]);
+        send!(s, time 0, (IP_SERVER_BROADCAST, UDP_RECV, DHCP_NAK));
+        recv!(s, time 0, [(IP_BROADCAST, UDP_SEND, DHCP_DISCOVER)]);
+    }
+
+    #[rstest]
+    #[case::ip(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_inform(#[case] medium: Medium) {
+        let mut s = socket(medium);
+
+        const DHCP_INFORM: DhcpRepr = DhcpRepr {
+            message_type: DhcpMessageType::Inform,
+            client_identifier: Some(MY_MAC),
+            client_ip: MY_IP,
+            max_size: Some(1432),
+            parameter_request_list: Some(&[1, 3, 6, 15, 26, 42, 119, 121]),
+            ..DHCP_DEFAULT
+        };
+
+        s.inform(Ipv4Cidr::new(MY_IP, 24));
+        recv!(s, time 0, [(IP_BROADCAST_ADDRESSED, UDP_SEND, DHCP_INFORM)]);
+        recv!(s, time 10_000, [(IP_BROADCAST_ADDRESSED, UDP_SEND, DHCP_INFORM)]);
+        assert_eq!(s.poll(), None);
+        send!(s, time 10_000, (IP_RECV, UDP_RECV, DhcpRepr {
+            client_ip: MY_IP,
+            your_ip: Ipv4Address::UNSPECIFIED,
+            lease_duration: None,
+            ..dhcp_ack()
+        }));
+
+        assert_eq!(
+            s.poll(),
+            Some(Event::Configured(Config {
+                server: ServerInfo {
+                    address: SERVER_IP,
+                    identifier: SERVER_IP,
+                },
+                address: Ipv4Cidr::new(MY_IP, 24),
+                dns_servers: Vec::from_slice(DNS_IPS).unwrap(),
+                ntp_servers: Vec::new(),
+                interface_mtu: None,
+                hostname: None,
+                domain_name: None,
+                domain_search: None,
+                vendor_specific: None,
+                routes: Vec::new(),
+                router: Some(SERVER_IP),
+                packet: None,
+            }))
+        );
+
+        // There is no lease to renew.
+        recv!(s, time 1_000_000, []);
+    }
 }
@@ -0,0 +1,1297 @@
+use managed::ManagedSlice;
+
+use crate::iface::Context;
+use crate::time::{Duration, Instant};
+use crate::wire::{
+    DhcpMessageType, DhcpOption, DhcpPacket, DhcpRepr, EthernetAddress, IpProtocol, Ipv4Address,
+    Ipv4Cidr, Ipv4Repr, UdpRepr, DHCP_CLIENT_PORT, DHCP_MAX_DNS_SERVER_COUNT, DHCP_SERVER_PORT,
+};
+use heapless::Vec;
+
+use super::PollAt;
+
+/// How long an offered address is held for the client it was offered to.
+const OFFER_DURATION: Duration = Duration::from_secs(60);
+
+/// Maximum number of replies waiting to be sent. Requests received while the
+/// queue is full are dropped; the clients will retransmit them.
+const MAX_PENDING_REPLIES: usize = 4;
+
+/// Configuration of the DHCP server.
+#[derive(Debug, Eq, PartialEq, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Config {
+    /// Address of the server, and subnet the addresses are handed out in.
+    ///
+    /// The address is used as the server identifier, and must be one of the
+    /// addresses of the interface.
+    pub address: Ipv4Cidr,
+    /// First address of the pool.
+    pub pool_start: Ipv4Address,
+    /// Last address of the pool, inclusive.
+    pub pool_end: Ipv4Address,
+    /// Router address given to the clients, also known as default gateway.
+    pub router: Option<Ipv4Address>,
+    /// DNS servers given to the clients.
+    pub dns_servers: Vec<Ipv4Address, DHCP_MAX_DNS_SERVER_COUNT>,
+    /// Duration of the leases.
+    pub lease_duration: Duration,
+}
+
+/// A static address reservation.
+///
+/// The address is always given to the client with the hardware address,
+/// and never to other clients. It does not need to be in the pool.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Reservation {
+    pub hardware_address: EthernetAddress,
+    pub address: Ipv4Address,
+}
+
+/// State of a lease.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LeaseState {
+    /// The address has been offered to the client, which has not requested it yet.
+    Offered,
+    /// The address is bound to the client.
+    Bound,
+    /// The client declined the address because it is already in use on the
+    /// network. It is not handed out again until the lease expires.
+    Declined,
+}
+
+/// An address lease.
+///
+/// A lease is kept after it expires, so that a client coming back gets the
+/// same address again if nobody else took it in the meantime.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Lease {
+    /// Hardware address of the client.
+    pub hardware_address: EthernetAddress,
+    /// Address leased to the client.
+    pub address: Ipv4Address,
+    /// State of the lease.
+    pub state: LeaseState,
+    /// When the lease expires.
+    pub expires_at: Instant,
+}
+
+impl Lease {
+    fn is_active(&self, now: Instant) -> bool {
+        self.expires_at > now
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Reply {
+    message_type: DhcpMessageType,
+    transaction_id: u32,
+    client_hardware_address: EthernetAddress,
+    client_ip: Ipv4Address,
+    your_ip: Ipv4Address,
+    relay_agent_ip: Ipv4Address,
+    broadcast: bool,
+}
+
+/// DHCP server socket.
+///
+/// The socket hands out addresses from a pool to the clients on the link,
+/// and keeps track of the leases in the storage given at creation. Only one
+/// server socket can be added to an interface.
+///
+/// Replies to clients that do not have an address yet are broadcast.
+#[derive(Debug)]
+pub struct Socket<'a> {
+    config: Config,
+    leases: ManagedSlice<'a, Option<Lease>>,
+    reservations: &'a [Reservation],
+    /// A buffer contains options additional to be added to outgoing DHCP
+    /// packets.
+    outgoing_options: &'a [DhcpOption<'a>],
+    /// Replies waiting to be sent.
+    replies: Vec<Reply, MAX_PENDING_REPLIES>,
+}
+
+impl<'a> Socket<'a> {
+    /// Create a DHCPv4 server socket with the given configuration and lease storage.
+    pub fn new<L>(config: Config, leases: L) -> Self
+    where
+        L: Into<ManagedSlice<'a, Option<Lease>>>,
+    {
+        Socket {
+            config,
+            leases: leases.into(),
+            reservations: &[],
+            outgoing_options: &[],
+            replies: Vec::new(),
+        }
+    }
+
+    /// Return the configuration of the server.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Set the configuration of the server.
+    ///
+    /// The existing leases are kept.
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+    }
+
+    /// Set the static address reservations.
+    pub fn set_reservations(&mut self, reservations: &'a [Reservation]) {
+        self.reservations = reservations;
+    }
+
+    /// Set the outgoing options.
+    pub fn set_outgoing_options(&mut self, options: &'a [DhcpOption<'a>]) {
+        self.outgoing_options = options;
+    }
+
+    /// Return the leases, including the expired ones.
+    pub fn leases(&self) -> impl Iterator<Item = &Lease> {
+        self.leases.iter().flatten()
+    }
+
+    /// Forget all the leases.
+    pub fn clear_leases(&mut self) {
+        for lease in self.leases.iter_mut() {
+            *lease = None;
+        }
+    }
+
+    pub(crate) fn poll_at(&self, _cx: &mut Context) -> PollAt {
+        if self.replies.is_empty() {
+            PollAt::Ingress
+        } else {
+            PollAt::Now
+        }
+    }
+
+    pub(crate) fn process(
+        &mut self,
+        cx: &mut Context,
+        ip_repr: &Ipv4Repr,
+        repr: &UdpRepr,
+        payload: &[u8],
+    ) {
+        let src_ip = ip_repr.src_addr;
+
+        // This is enforced in interface.rs.
+        assert!(repr.dst_port == DHCP_SERVER_PORT);
+
+        let dhcp_packet = match DhcpPacket::new_checked(payload) {
+            Ok(dhcp_packet) => dhcp_packet,
+            Err(e) => {
+                net_debug!("DHCP server invalid pkt from {}: {:?}", src_ip, e);
+                return;
+            }
+        };
+        let dhcp_repr = match DhcpRepr::parse(&dhcp_packet) {
+            Ok(dhcp_repr) => dhcp_repr,
+            Err(e) => {
+                net_debug!("DHCP server error parsing pkt from {}: {:?}", src_ip, e);
+                return;
+            }
+        };
+
+        net_debug!(
+            "DHCP server recv {:?} from {}: {:?}",
+            dhcp_repr.message_type,
+            src_ip,
+            dhcp_repr
+        );
+
+        let now = cx.now();
+        let server_identifier = self.config.address.address();
+        let hardware_address = dhcp_repr.client_hardware_address;
+
+        match dhcp_repr.message_type {
+            DhcpMessageType::Discover => {
+                let Some(address) = self.select_address(now, hardware_address, &dhcp_repr) else {
+                    net_debug!(
+                        "DHCP server has no address to offer to {}",
+                        hardware_address
+                    );
+                    return;
+                };
+                let lease = Lease {
+                    hardware_address,
+                    address,
+                    state: LeaseState::Offered,
+                    expires_at: now + OFFER_DURATION,
+                };
+                if self.insert_lease(now, lease) {
+                    self.queue_reply(&dhcp_repr, DhcpMessageType::Offer, address);
+                } else {
+                    net_debug!("DHCP server lease storage is full");
+                }
+            }
+            DhcpMessageType::Request => {
+                match dhcp_repr.server_identifier {
+                    // The client selected the offer of another server.
+                    Some(identifier) if identifier != server_identifier => {
+                        if let Some(slot) = self.leases.iter_mut().find(|l| {
+                            l.is_some_and(|l| {
+                                l.hardware_address == hardware_address
+                                    && l.state == LeaseState::Offered
+                            })
+                        }) {
+                            *slot = None;
+                        }
+                    }
+                    // SELECTING state: the client requests the address we offered.
+                    Some(_) => {
+                        let offered = self.client_lease(hardware_address).filter(|l| {
+                            l.is_active(now)
+                                && l.state != LeaseState::Declined
+                                && Some(l.address) == dhcp_repr.requested_ip
+                        });
+                        match offered {
+                            Some(lease) => self.bind(now, &dhcp_repr, lease.address),
+                            None => self.queue_nak(&dhcp_repr),
+                        }
+                    }
+                    // INIT-REBOOT, RENEWING or REBINDING state: the client checks
+                    // or extends the lease on an address it had before.
+                    None => {
+                        let address = if dhcp_repr.client_ip.is_unspecified() {
+                            match dhcp_repr.requested_ip {
+                                Some(address) => address,
+                                None => return,
+                            }
+                        } else {
+                            dhcp_repr.client_ip
+                        };
+
+                        let known = self.client_lease(hardware_address).is_some_and(|l| {
+                            l.address == address && l.state != LeaseState::Declined
+                        }) || self.reservation(hardware_address) == Some(address);
+
+                        let available = self.config.address.contains_addr(&address)
+                            && self.is_available(now, hardware_address, address);
+
+                        if known && available {
+                            self.bind(now, &dhcp_repr, address);
+                        } else if known || !available {
+                            self.queue_nak(&dhcp_repr);
+                        } else {
+                            // RFC 2131 § 4.3.2: the server must remain silent if it
+                            // has no record of the client.
+                            net_debug!("DHCP server has no record of {}", hardware_address);
+                        }
+                    }
+                }
+            }
+            DhcpMessageType::Decline => {
+                if dhcp_repr.server_identifier != Some(server_identifier) {
+                    return;
+                }
+                let lease_duration = self.config.lease_duration;
+                if let Some(lease) = self.client_lease_mut(hardware_address) {
+                    if Some(lease.address) == dhcp_repr.requested_ip {
+                        net_debug!(
+                            "DHCP server: {} declined {}",
+                            hardware_address,
+                            lease.address
+                        );
+                        lease.state = LeaseState::Declined;
+                        lease.expires_at = now + lease_duration;
+                    }
+                }
+            }
+            DhcpMessageType::Release => {
+                if dhcp_repr.server_identifier != Some(server_identifier) {
+                    return;
+                }
+                if let Some(lease) = self.client_lease_mut(hardware_address) {
+                    if lease.address == dhcp_repr.client_ip && lease.state == LeaseState::Bound {
+                        net_debug!(
+                            "DHCP server: {} released {}",
+                            hardware_address,
+                            lease.address
+                        );
+                        lease.expires_at = now;
+                    }
+                }
+            }
+            _ => {
+                net_debug!(
+                    "DHCP server ignoring {:?}: unexpected message type",
+                    dhcp_repr.message_type
+                );
+            }
+        }
+    }
+
+    /// Return the address reserved for the client, if any.
+    fn reservation(&self, hardware_address: EthernetAddress) -> Option<Ipv4Address> {
+        self.reservations
+            .iter()
+            .find(|r| r.hardware_address == hardware_address)
+            .map(|r| r.address)
+    }
+
+    /// Return the lease of the client, if any. Declined addresses are
+    /// recorded with the hardware address of the client that declined them,
+    /// but are not considered the lease of that client.
+    fn client_lease(&self, hardware_address: EthernetAddress) -> Option<Lease> {
+        self.leases
+            .iter()
+            .flatten()
+            .find(|l| l.hardware_address == hardware_address && l.state != LeaseState::Declined)
+            .copied()
+    }
+
+    fn client_lease_mut(&mut self, hardware_address: EthernetAddress) -> Option<&mut Lease> {
+        self.leases
+            .iter_mut()
+            .flatten()
+            .find(|l| l.hardware_address == hardware_address && l.state != LeaseState::Declined)
+    }
+
+    /// Return whether the address can be given to the client.
+    fn is_available(
+        &self,
+        now: Instant,
+        hardware_address: EthernetAddress,
+        address: Ipv4Address,
+    ) -> bool {
+        address != self.config.address.address()
+            && !self
+                .reservations
+                .iter()
+                .any(|r| r.address == address && r.hardware_address != hardware_address)
+            && !self.leases.iter().flatten().any(|l| {
+                l.address == address
+                    && l.is_active(now)
+                    && (l.hardware_address != hardware_address || l.state == LeaseState::Declined)
+            })
+    }
+
+    fn in_pool(&self, address: Ipv4Address) -> bool {
+        let address = u32::from_be_bytes(address.0);
+        u32::from_be_bytes(self.config.pool_start.0) <= address
+            && address <= u32::from_be_bytes(self.config.pool_end.0)
+    }
+
+    /// Select the address to offer to a client.
+    fn select_address(
+        &self,
+        now: Instant,
+        hardware_address: EthernetAddress,
+        dhcp_repr: &DhcpRepr,
+    ) -> Option<Ipv4Address> {
+        let available = |address: Ipv4Address| self.is_available(now, hardware_address, address);
+
+        // A reserved address is the only one the client can get.
+        if let Some(address) = self.reservation(hardware_address) {
+            return available(address).then_some(address);
+        }
+
+        // Then the address of the previous lease of the client, the address it
+        // asks for, and the first free address of the pool.
+        if let Some(lease) = self.client_lease(hardware_address) {
+            if available(lease.address) {
+                return Some(lease.address);
+            }
+        }
+        if let Some(address) = dhcp_repr.requested_ip {
+            if self.in_pool(address) && available(address) {
+                return Some(address);
+            }
+        }
+        (u32::from_be_bytes(self.config.pool_start.0)..=u32::from_be_bytes(self.config.pool_end.0))
+            .map(|address| Ipv4Address::from_bytes(&address.to_be_bytes()))
+            .filter(|address| self.config.address.contains_addr(address))
+            .find(|address| available(*address))
+    }
+
+    /// Insert or update the lease of a client. Expired leases are evicted,
+    /// oldest first, when the storage is full.
+    ///
+    /// Returns false if there is no room for the lease.
+    fn insert_lease(&mut self, now: Instant, lease: Lease) -> bool {
+        let hardware_address = lease.hardware_address;
+
+        // Drop the expired leases of other clients on the same address.
+        for slot in self.leases.iter_mut() {
+            if slot.is_some_and(|l| {
+                l.address == lease.address
+                    && l.hardware_address != hardware_address
+                    && !l.is_active(now)
+            }) {
+                *slot = None;
+            }
+        }
+
+        let index = self
+            .leases
+            .iter()
+            .position(|l| {
+                l.is_some_and(|l| {
+                    l.hardware_address == hardware_address && l.state != LeaseState::Declined
+                })
+            })
+            .or_else(|| self.leases.iter().position(|l| l.is_none()))
+            .or_else(|| {
+                self.leases
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, l)| l.filter(|l| !l.is_active(now)).map(|l| (i, l)))
+                    .min_by_key(|(_, l)| l.expires_at)
+                    .map(|(i, _)| i)
+            });
+
+        match index {
+            Some(index) => {
+                self.leases[index] = Some(lease);
+                true
+            }
+            None => match &mut self.leases {
+                ManagedSlice::Borrowed(_) => false,
+                #[cfg(feature = "alloc")]
+                ManagedSlice::Owned(leases) => {
+                    leases.push(Some(lease));
+                    true
+                }
+            },
+        }
+    }
+
+    fn bind(&mut self, now: Instant, dhcp_repr: &DhcpRepr, address: Ipv4Address) {
+        let lease = Lease {
+            hardware_address: dhcp_repr.client_hardware_address,
+            address,
+            state: LeaseState::Bound,
+            expires_at: now + self.config.lease_duration,
+        };
+        if self.insert_lease(now, lease) {
+            net_debug!(
+                "DHCP server: {} bound to {}",
+                dhcp_repr.client_hardware_address,
+                address
+            );
+            self.queue_reply(dhcp_repr, DhcpMessageType::Ack, address);
+        } else {
+            net_debug!("DHCP server lease storage is full");
+        }
+    }
+
+    fn queue_nak(&mut self, dhcp_repr: &DhcpRepr) {
+        self.queue_reply(dhcp_repr, DhcpMessageType::Nak, Ipv4Address::UNSPECIFIED)
+    }
+
+    fn queue_reply(
+        &mut self,
+        dhcp_repr: &DhcpRepr,
+        message_type: DhcpMessageType,
+        your_ip: Ipv4Address,
+    ) {
+        let reply = Reply {
+            message_type,
+            transaction_id: dhcp_repr.transaction_id,
+            client_hardware_address: dhcp_repr.client_hardware_address,
+            client_ip: dhcp_repr.client_ip,
+            your_ip,
+            relay_agent_ip: dhcp_repr.relay_agent_ip,
+            broadcast: dhcp_repr.broadcast,
+        };
+        if self.replies.push(reply).is_err() {
+            net_debug!("DHCP server reply queue is full");
+        }
+    }
+
+    pub(crate) fn dispatch<F, E>(&mut self, cx: &mut Context, emit: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut Context, (Ipv4Repr, UdpRepr, DhcpRepr)) -> Result<(), E>,
+    {
+        let Some(reply) = self.replies.first() else {
+            return Ok(());
+        };
+
+        let is_nak = reply.message_type == DhcpMessageType::Nak;
+        let dhcp_repr = DhcpRepr {
+            message_type: reply.message_type,
+            transaction_id: reply.transaction_id,
+            secs: 0,
+            client_hardware_address: reply.client_hardware_address,
+            client_ip: if is_nak {
+                Ipv4Address::UNSPECIFIED
+            } else {
+                reply.client_ip
+            },
+            your_ip: reply.your_ip,
+            server_ip: Ipv4Address::UNSPECIFIED,
+            router: if is_nak { None } else { self.config.router },
+            subnet_mask: (!is_nak).then_some(self.config.address.netmask()),
+            relay_agent_ip: reply.relay_agent_ip,
+            broadcast: reply.broadcast,
+            requested_ip: None,
+            client_identifier: None,
+            server_identifier: Some(self.config.address.address()),
+            parameter_request_list: None,
+            dns_servers: (!is_nak && !self.config.dns_servers.is_empty())
+                .then(|| self.config.dns_servers.clone()),
+            max_size: None,
+            lease_duration: (!is_nak).then_some(self.config.lease_duration.secs() as u32),
+            renew_duration: None,
+            rebind_duration: None,
+            ntp_servers: None,
+            interface_mtu: None,
+            classless_routes: None,
+            additional_options: if is_nak { &[] } else { self.outgoing_options },
+        };
+
+        // RFC 2131 § 4.1: replies go to the relay agent if there is one, and
+        // NAKs are broadcast. Otherwise the reply is unicast to the client if
+        // it already has an address, and broadcast if it does not.
+        let (dst_addr, dst_port) = if !reply.relay_agent_ip.is_unspecified() {
+            (reply.relay_agent_ip, DHCP_SERVER_PORT)
+        } else if is_nak || reply.client_ip.is_unspecified() {
+            (Ipv4Address::BROADCAST, DHCP_CLIENT_PORT)
+        } else {
+            (reply.client_ip, DHCP_CLIENT_PORT)
+        };
+
+        let udp_repr = UdpRepr {
+            src_port: DHCP_SERVER_PORT,
+            dst_port,
+        };
+
+        let ipv4_repr = Ipv4Repr {
+            src_addr: self.config.address.address(),
+            dst_addr,
+            next_header: IpProtocol::Udp,
+            payload_len: udp_repr.header_len() + dhcp_repr.buffer_len(),
+            hop_limit: 64,
+        };
+
+        net_debug!(
+            "DHCP server send {:?} to {}: {:?}",
+            dhcp_repr.message_type,
+            ipv4_repr.dst_addr,
+            dhcp_repr
+        );
+        emit(cx, (ipv4_repr, udp_repr, dhcp_repr))?;
+
+        // Remove the reply AFTER the packet has been successfully sent.
+        self.replies.remove(0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::ops::{Deref, DerefMut};
+
+    use super::*;
+
+    // =========================================================================================//
+    // Helper functions
+
+    struct TestSocket {
+        socket: Socket<'static>,
+        cx: Context,
+    }
+
+    impl Deref for TestSocket {
+        type Target = Socket<'static>;
+        fn deref(&self) -> &Self::Target {
+            &self.socket
+        }
+    }
+
+    impl DerefMut for TestSocket {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.socket
+        }
+    }
+
+    fn send(
+        s: &mut TestSocket,
+        timestamp: Instant,
+        (ip_repr, udp_repr, dhcp_repr): (Ipv4Repr, UdpRepr, DhcpRepr),
+    ) {
+        s.cx.set_now(timestamp);
+
+        net_trace!("send: {:?}", ip_repr);
+        net_trace!("      {:?}", udp_repr);
+        net_trace!("      {:?}", dhcp_repr);
+
+        let mut payload = vec![0; dhcp_repr.buffer_len()];
+        dhcp_repr
+            .emit(&mut DhcpPacket::new_unchecked(&mut payload))
+            .unwrap();
+
+        s.socket.process(&mut s.cx, &ip_repr, &udp_repr, &payload)
+    }
+
+    fn recv(s: &mut TestSocket, timestamp: Instant, reprs: &[(Ipv4Repr, UdpRepr, DhcpRepr)]) {
+        s.cx.set_now(timestamp);
+
+        let mut i = 0;
+
+        while s.socket.poll_at(&mut s.cx) <= PollAt::Time(timestamp) {
+            let _ = s
+                .socket
+                .dispatch(&mut s.cx, |_, (mut ip_repr, udp_repr, dhcp_repr)| {
+                    assert_eq!(ip_repr.next_header, IpProtocol::Udp);
+                    assert_eq!(
+                        ip_repr.payload_len,
+                        udp_repr.header_len() + dhcp_repr.buffer_len()
+                    );
+
+                    // We validated the payload len, change it to 0 to make equality testing easier
+                    ip_repr.payload_len = 0;
+
+                    net_trace!("recv: {:?}", ip_repr);
+                    net_trace!("      {:?}", udp_repr);
+                    net_trace!("      {:?}", dhcp_repr);
+
+                    let got_repr = (ip_repr, udp_repr, dhcp_repr);
+                    match reprs.get(i) {
+                        Some(want_repr) => assert_eq!(want_repr, &got_repr),
+                        None => panic!("Too many reprs emitted"),
+                    }
+                    i += 1;
+                    Ok::<_, ()>(())
+                });
+        }
+
+        assert_eq!(i, reprs.len());
+    }
+
+    macro_rules! send {
+        ($socket:ident, $repr:expr) =>
+            (send!($socket, time 0, $repr));
+        ($socket:ident, time $time:expr, $repr:expr) =>
+            (send(&mut $socket, Instant::from_millis($time), $repr));
+    }
+
+    macro_rules! recv {
+        ($socket:ident, $reprs:expr) => ({
+            recv!($socket, time 0, $reprs);
+        });
+        ($socket:ident, time $time:expr, $reprs:expr) => ({
+            recv(&mut $socket, Instant::from_millis($time), &$reprs);
+        });
+    }
+
+    // =========================================================================================//
+    // Constants
+
+    const TXID: u32 = 0x12345678;
+
+    const SERVER_IP: Ipv4Address = Ipv4Address([192, 168, 1, 1]);
+    const POOL_IP_1: Ipv4Address = Ipv4Address([192, 168, 1, 100]);
+    const POOL_IP_2: Ipv4Address = Ipv4Address([192, 168, 1, 101]);
+    const POOL_IP_3: Ipv4Address = Ipv4Address([192, 168, 1, 102]);
+    const RESERVED_IP: Ipv4Address = Ipv4Address([192, 168, 1, 50]);
+    const RELAY_IP: Ipv4Address = Ipv4Address([10, 0, 0, 1]);
+    const DNS_IP: Ipv4Address = Ipv4Address([192, 168, 1, 53]);
+
+    const MASK_24: Ipv4Address = Ipv4Address([255, 255, 255, 0]);
+
+    const CLIENT_MAC: EthernetAddress = EthernetAddress([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]);
+    const OTHER_MAC: EthernetAddress = EthernetAddress([0x52, 0x54, 0x00, 0x12, 0x34, 0x57]);
+
+    const IP_CLIENT_BROADCAST: Ipv4Repr = Ipv4Repr {
+        src_addr: Ipv4Address::UNSPECIFIED,
+        dst_addr: Ipv4Address::BROADCAST,
+        next_header: IpProtocol::Udp,
+        payload_len: 0,
+        hop_limit: 64,
+    };
+
+    const IP_CLIENT_UNICAST: Ipv4Repr = Ipv4Repr {
+        src_addr: POOL_IP_1,
+        dst_addr: SERVER_IP,
+        next_header: IpProtocol::Udp,
+        payload_len: 0,
+        hop_limit: 64,
+    };
+
+    const IP_BROADCAST: Ipv4Repr = Ipv4Repr {
+        src_addr: SERVER_IP,
+        dst_addr: Ipv4Address::BROADCAST,
+        next_header: IpProtocol::Udp,
+        payload_len: 0,
+        hop_limit: 64,
+    };
+
+    const IP_UNICAST: Ipv4Repr = Ipv4Repr {
+        src_addr: SERVER_IP,
+        dst_addr: POOL_IP_1,
+        next_header: IpProtocol::Udp,
+        payload_len: 0,
+        hop_limit: 64,
+    };
+
+    const UDP_RECV: UdpRepr = UdpRepr {
+        src_port: DHCP_CLIENT_PORT,
+        dst_port: DHCP_SERVER_PORT,
+    };
+    const UDP_SEND: UdpRepr = UdpRepr {
+        src_port: DHCP_SERVER_PORT,
+        dst_port: DHCP_CLIENT_PORT,
+    };
+
+    const DHCP_DEFAULT: DhcpRepr = DhcpRepr {
+        message_type: DhcpMessageType::Unknown(99),
+        transaction_id: TXID,
+        secs: 0,
+        client_hardware_address: CLIENT_MAC,
+        client_ip: Ipv4Address::UNSPECIFIED,
+        your_ip: Ipv4Address::UNSPECIFIED,
+        server_ip: Ipv4Address::UNSPECIFIED,
+        router: None,
+        subnet_mask: None,
+        relay_agent_ip: Ipv4Address::UNSPECIFIED,
+        broadcast: false,
+        requested_ip: None,
+        client_identifier: None,
+        server_identifier: None,
+        parameter_request_list: None,
+        dns_servers: None,
+        max_size: None,
+        renew_duration: None,
+        rebind_duration: None,
+        ntp_servers: None,
+        interface_mtu: None,
+        classless_routes: None,
+        lease_duration: None,
+        additional_options: &[],
+    };
+
+    const DHCP_DISCOVER: DhcpRepr = DhcpRepr {
+        message_type: DhcpMessageType::Discover,
+        client_identifier: Some(CLIENT_MAC),
+        parameter_request_list: Some(&[1, 3, 6]),
+        max_size: Some(1432),
+        ..DHCP_DEFAULT
+    };
+
+    const DHCP_REQUEST: DhcpRepr = DhcpRepr {
+        message_type: DhcpMessageType::Request,
+        client_identifier: Some(CLIENT_MAC),
+        server_identifier: Some(SERVER_IP),
+        requested_ip: Some(POOL_IP_1),
+        parameter_request_list: Some(&[1, 3, 6]),
+        max_size: Some(1432),
+        ..DHCP_DEFAULT
+    };
+
+    const DHCP_RENEW: DhcpRepr = DhcpRepr {
+        message_type: DhcpMessageType::Request,
+        client_identifier: Some(CLIENT_MAC),
+        client_ip: POOL_IP_1,
+        parameter_request_list: Some(&[1, 3, 6]),
+        max_size: Some(1432),
+        ..DHCP_DEFAULT
+    };
+
+    fn dhcp_offer(your_ip: Ipv4Address) -> DhcpRepr<'static> {
+        DhcpRepr {
+            message_type: DhcpMessageType::Offer,
+            your_ip,
+            server_identifier: Some(SERVER_IP),
+            router: Some(SERVER_IP),
+            subnet_mask: Some(MASK_24),
+            dns_servers: Some(Vec::from_slice(&[DNS_IP]).unwrap()),
+            lease_duration: Some(1000),
+            ..DHCP_DEFAULT
+        }
+    }
+
+    fn dhcp_ack(your_ip: Ipv4Address) -> DhcpRepr<'static> {
+        DhcpRepr {
+            message_type: DhcpMessageType::Ack,
+            ..dhcp_offer(your_ip)
+        }
+    }
+
+    const DHCP_NAK: DhcpRepr = DhcpRepr {
+        message_type: DhcpMessageType::Nak,
+        server_identifier: Some(SERVER_IP),
+        ..DHCP_DEFAULT
+    };
+
+    fn config() -> Config {
+        Config {
+            address: Ipv4Cidr::new(SERVER_IP, 24),
+            pool_start: POOL_IP_1,
+            pool_end: POOL_IP_3,
+            router: Some(SERVER_IP),
+            dns_servers: Vec::from_slice(&[DNS_IP]).unwrap(),
+            lease_duration: Duration::from_secs(1000),
+        }
+    }
+
+    // =========================================================================================//
+    // Tests
+
+    use crate::phy::Medium;
+    use crate::tests::setup;
+    use rstest::*;
+
+    fn socket(medium: Medium) -> TestSocket {
+        let (iface, _, _) = setup(medium);
+        let s = Socket::new(config(), vec![None; 3]);
+        TestSocket {
+            socket: s,
+            cx: iface.inner,
+        }
+    }
+
+    fn socket_bound(medium: Medium) -> TestSocket {
+        let mut s = socket(medium);
+        send!(s, (IP_CLIENT_BROADCAST, UDP_RECV, DHCP_DISCOVER));
+        recv!(s, [(IP_BROADCAST, UDP_SEND, dhcp_offer(POOL_IP_1))]);
+        send!(s, (IP_CLIENT_BROADCAST, UDP_RECV, DHCP_REQUEST));
+        recv!(s, [(IP_BROADCAST, UDP_SEND, dhcp_ack(POOL_IP_1))]);
+        s
+    }
+
+    fn client_lease(s: &TestSocket, hardware_address: EthernetAddress) -> Option<Lease> {
+        s.leases()
+            .find(|l| l.hardware_address == hardware_address)
+            .copied()
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_bind(#[case] medium: Medium) {
+        let mut s = socket(medium);
+
+        recv!(s, []);
+        send!(s, (IP_CLIENT_BROADCAST, UDP_RECV, DHCP_DISCOVER));
+        assert_eq!(
+            client_lease(&s, CLIENT_MAC),
+            Some(Lease {
+                hardware_address: CLIENT_MAC,
+                address: POOL_IP_1,
+                state: LeaseState::Offered,
+                expires_at: Instant::from_secs(60),
+            })
+        );
+        recv!(s, [(IP_BROADCAST, UDP_SEND, dhcp_offer(POOL_IP_1))]);
+
+        send!(s, time 1_000, (IP_CLIENT_BROADCAST, UDP_RECV, DHCP_REQUEST));
+        recv!(s, time 1_000, [(IP_BROADCAST, UDP_SEND, dhcp_ack(POOL_IP_1))]);
+        assert_eq!(
+            client_lease(&s, CLIENT_MAC),
+            Some(Lease {
+                hardware_address: CLIENT_MAC,
+                address: POOL_IP_1,
+                state: LeaseState::Bound,
+                expires_at: Instant::from_secs(1001),
+            })
+        );
+
+        // The client gets the same address again.
+        send!(s, time 2_000, (IP_CLIENT_BROADCAST, UDP_RECV, DHCP_DISCOVER));
+        recv!(s, time 2_000, [(IP_BROADCAST, UDP_SEND, dhcp_offer(POOL_IP_1))]);
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_requested_address(#[case] medium: Medium) {
+        let mut s = socket(medium);
+
+        send!(
+            s,
+            (
+                IP_CLIENT_BROADCAST,
+                UDP_RECV,
+                DhcpRepr {
+                    requested_ip: Some(POOL_IP_3),
+                    ..DHCP_DISCOVER
+                }
+            )
+        );
+        recv!(s, [(IP_BROADCAST, UDP_SEND, dhcp_offer(POOL_IP_3))]);
+
+        // An address out of the pool is not given.
+        send!(
+            s,
+            (
+                IP_CLIENT_BROADCAST,
+                UDP_RECV,
+                DhcpRepr {
+                    client_hardware_address: OTHER_MAC,
+                    requested_ip: Some(Ipv4Address([192, 168, 1, 200])),
+                    ..DHCP_DISCOVER
+                }
+            )
+        );
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    client_hardware_address: OTHER_MAC,
+                    ..dhcp_offer(POOL_IP_1)
+                }
+            )]
+        );
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_request_other_server(#[case] medium: Medium) {
+        let mut s = socket(medium);
+
+        send!(s, (IP_CLIENT_BROADCAST, UDP_RECV, DHCP_DISCOVER));
+        recv!(s, [(IP_BROADCAST, UDP_SEND, dhcp_offer(POOL_IP_1))]);
+        send!(
+            s,
+            (
+                IP_CLIENT_BROADCAST,
+                UDP_RECV,
+                DhcpRepr {
+                    server_identifier: Some(Ipv4Address([192, 168, 1, 2])),
+                    ..DHCP_REQUEST
+                }
+            )
+        );
+        recv!(s, []);
+        assert_eq!(client_lease(&s, CLIENT_MAC), None);
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_request_nak(#[case] medium: Medium) {
+        let mut s = socket(medium);
+
+        // The address was not offered.
+        send!(s, (IP_CLIENT_BROADCAST, UDP_RECV, DHCP_REQUEST));
+        recv!(s, [(IP_BROADCAST, UDP_SEND, DHCP_NAK)]);
+
+        send!(s, (IP_CLIENT_BROADCAST, UDP_RECV, DHCP_DISCOVER));
+        recv!(s, [(IP_BROADCAST, UDP_SEND, dhcp_offer(POOL_IP_1))]);
+        send!(
+            s,
+            (
+                IP_CLIENT_BROADCAST,
+                UDP_RECV,
+                DhcpRepr {
+                    requested_ip: Some(POOL_IP_2),
+                    ..DHCP_REQUEST
+                }
+            )
+        );
+        recv!(s, [(IP_BROADCAST, UDP_SEND, DHCP_NAK)]);
+
+        // The offer expired.
+        send!(s, time 60_000, (IP_CLIENT_BROADCAST, UDP_RECV, DHCP_REQUEST));
+        recv!(s, time 60_000, [(IP_BROADCAST, UDP_SEND, DHCP_NAK)]);
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_renew(#[case] medium: Medium) {
+        let mut s = socket_bound(medium);
+
+        send!(s, time 500_000, (IP_CLIENT_UNICAST, UDP_RECV, DHCP_RENEW));
+        recv!(s, time 500_000, [(IP_UNICAST, UDP_SEND, DhcpRepr {
+            client_ip: POOL_IP_1,
+            ..dhcp_ack(POOL_IP_1)
+        })]);
+        assert_eq!(
+            client_lease(&s, CLIENT_MAC).unwrap().expires_at,
+            Instant::from_secs(1500)
+        );
+
+        // Another client cannot renew the address.
+        send!(s, time 500_000, (IP_CLIENT_UNICAST, UDP_RECV, DhcpRepr {
+            client_hardware_address: OTHER_MAC,
+            ..DHCP_RENEW
+        }));
+        recv!(s, time 500_000, [(IP_BROADCAST, UDP_SEND, DhcpRepr {
+            client_hardware_address: OTHER_MAC,
+            ..DHCP_NAK
+        })]);
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_init_reboot(#[case] medium: Medium) {
+        let mut s = socket_bound(medium);
+
+        const INIT_REBOOT: DhcpRepr = DhcpRepr {
+            server_identifier: None,
+            ..DHCP_REQUEST
+        };
+        send!(s, time 2_000_000, (IP_CLIENT_BROADCAST, UDP_RECV, INIT_REBOOT));
+        recv!(s, time 2_000_000, [(IP_BROADCAST, UDP_SEND, dhcp_ack(POOL_IP_1))]);
+
+        // No record of the client.
+        send!(
+            s,
+            (
+                IP_CLIENT_BROADCAST,
+                UDP_RECV,
+                DhcpRepr {
+                    client_hardware_address: OTHER_MAC,
+                    requested_ip: Some(POOL_IP_2),
+                    ..INIT_REBOOT
+                }
+            )
+        );
+        recv!(s, []);
+
+        // Wrong subnet.
+        send!(
+            s,
+            (
+                IP_CLIENT_BROADCAST,
+                UDP_RECV,
+                DhcpRepr {
+                    client_hardware_address: OTHER_MAC,
+                    requested_ip: Some(RELAY_IP),
+                    ..INIT_REBOOT
+                }
+            )
+        );
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    client_hardware_address: OTHER_MAC,
+                    ..DHCP_NAK
+                }
+            )]
+        );
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_release(#[case] medium: Medium) {
+        let mut s = socket_bound(medium);
+
+        send!(s, time 10_000, (IP_CLIENT_UNICAST, UDP_RECV, DhcpRepr {
+            message_type: DhcpMessageType::Release,
+            client_ip: POOL_IP_1,
+            server_identifier: Some(SERVER_IP),
+            ..DHCP_DEFAULT
+        }));
+        recv!(s, time 10_000, []);
+        assert_eq!(
+            client_lease(&s, CLIENT_MAC).unwrap().expires_at,
+            Instant::from_secs(10)
+        );
+
+        // The address can be given to another client.
+        send!(s, time 10_000, (IP_CLIENT_BROADCAST, UDP_RECV, DhcpRepr {
+            client_hardware_address: OTHER_MAC,
+            ..DHCP_DISCOVER
+        }));
+        recv!(s, time 10_000, [(IP_BROADCAST, UDP_SEND, DhcpRepr {
+            client_hardware_address: OTHER_MAC,
+            ..dhcp_offer(POOL_IP_1)
+        })]);
+        assert_eq!(client_lease(&s, CLIENT_MAC), None);
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_decline(#[case] medium: Medium) {
+        let mut s = socket_bound(medium);
+
+        send!(
+            s,
+            (
+                IP_CLIENT_BROADCAST,
+                UDP_RECV,
+                DhcpRepr {
+                    message_type: DhcpMessageType::Decline,
+                    requested_ip: Some(POOL_IP_1),
+                    server_identifier: Some(SERVER_IP),
+                    ..DHCP_DEFAULT
+                }
+            )
+        );
+        recv!(s, []);
+
+        // The declined address is not given again until the lease expires.
+        send!(s, (IP_CLIENT_BROADCAST, UDP_RECV, DHCP_DISCOVER));
+        recv!(s, [(IP_BROADCAST, UDP_SEND, dhcp_offer(POOL_IP_2))]);
+        send!(
+            s,
+            (
+                IP_CLIENT_BROADCAST,
+                UDP_RECV,
+                DhcpRepr {
+                    client_hardware_address: OTHER_MAC,
+                    ..DHCP_DISCOVER
+                }
+            )
+        );
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    client_hardware_address: OTHER_MAC,
+                    ..dhcp_offer(POOL_IP_3)
+                }
+            )]
+        );
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_pool_exhausted(#[case] medium: Medium) {
+        let mut s = socket(medium);
+
+        for (i, address) in [POOL_IP_1, POOL_IP_2, POOL_IP_3].into_iter().enumerate() {
+            let mac = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, i as u8]);
+            send!(
+                s,
+                (
+                    IP_CLIENT_BROADCAST,
+                    UDP_RECV,
+                    DhcpRepr {
+                        client_hardware_address: mac,
+                        ..DHCP_DISCOVER
+                    }
+                )
+            );
+            recv!(
+                s,
+                [(
+                    IP_BROADCAST,
+                    UDP_SEND,
+                    DhcpRepr {
+                        client_hardware_address: mac,
+                        ..dhcp_offer(address)
+                    }
+                )]
+            );
+        }
+
+        send!(s, (IP_CLIENT_BROADCAST, UDP_RECV, DHCP_DISCOVER));
+        recv!(s, []);
+
+        // The offers expire.
+        send!(s, time 60_000, (IP_CLIENT_BROADCAST, UDP_RECV, DHCP_DISCOVER));
+        recv!(s, time 60_000, [(IP_BROADCAST, UDP_SEND, dhcp_offer(POOL_IP_1))]);
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_reservation(#[case] medium: Medium) {
+        let mut s = socket(medium);
+        s.set_reservations(&[Reservation {
+            hardware_address: CLIENT_MAC,
+            address: RESERVED_IP,
+        }]);
+
+        send!(
+            s,
+            (
+                IP_CLIENT_BROADCAST,
+                UDP_RECV,
+                DhcpRepr {
+                    requested_ip: Some(POOL_IP_2),
+                    ..DHCP_DISCOVER
+                }
+            )
+        );
+        recv!(s, [(IP_BROADCAST, UDP_SEND, dhcp_offer(RESERVED_IP))]);
+        send!(
+            s,
+            (
+                IP_CLIENT_BROADCAST,
+                UDP_RECV,
+                DhcpRepr {
+                    requested_ip: Some(RESERVED_IP),
+                    ..DHCP_REQUEST
+                }
+            )
+        );
+        recv!(s, [(IP_BROADCAST, UDP_SEND, dhcp_ack(RESERVED_IP))]);
+
+        // Other clients cannot get the reserved address.
+        send!(
+            s,
+            (
+                IP_CLIENT_BROADCAST,
+                UDP_RECV,
+                DhcpRepr {
+                    client_hardware_address: OTHER_MAC,
+                    requested_ip: Some(RESERVED_IP),
+                    ..DHCP_DISCOVER
+                }
+            )
+        );
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    client_hardware_address: OTHER_MAC,
+                    ..dhcp_offer(POOL_IP_1)
+                }
+            )]
+        );
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_relay(#[case] medium: Medium) {
+        let mut s = socket(medium);
+
+        send!(
+            s,
+            (
+                IP_CLIENT_BROADCAST,
+                UDP_RECV,
+                DhcpRepr {
+                    relay_agent_ip: RELAY_IP,
+                    ..DHCP_DISCOVER
+                }
+            )
+        );
+        recv!(
+            s,
+            [(
+                Ipv4Repr {
+                    dst_addr: RELAY_IP,
+                    ..IP_BROADCAST
+                },
+                UdpRepr {
+                    src_port: DHCP_SERVER_PORT,
+                    dst_port: DHCP_SERVER_PORT,
+                },
+                DhcpRepr {
+                    relay_agent_ip: RELAY_IP,
+                    ..dhcp_offer(POOL_IP_1)
+                }
+            )]
+        );
+    }
+}
@@ -0,0 +1,1778 @@
+#[cfg(feature = "async")]
+use core::task::Waker;
+
+use crate::iface::Context;
+use crate::time::{Duration, Instant};
+use crate::wire::dhcpv6::field as dhcpv6_field;
+#[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+use crate::wire::HardwareAddress;
+use crate::wire::{
+    Dhcpv6DomainList, Dhcpv6IaAddress, Dhcpv6IaNa, Dhcpv6IaPd, Dhcpv6IaPrefix, Dhcpv6MessageType,
+    Dhcpv6Option, Dhcpv6Packet, Dhcpv6Repr, Dhcpv6StatusCode, IpProtocol, Ipv6Address, Ipv6Cidr,
+    Ipv6Repr, UdpRepr, DHCPV6_ALL_RELAY_AGENTS_AND_SERVERS, DHCPV6_CLIENT_PORT,
+    DHCPV6_MAX_DNS_SERVER_COUNT, DHCPV6_MAX_DUID_LEN, DHCPV6_SERVER_PORT,
+};
+use heapless::Vec;
+
+#[cfg(feature = "async")]
+use super::WakerRegistration;
+
+use super::PollAt;
+
+/// The maximum length of the domain search list kept from the server's configuration. The
+/// names that do not fit are dropped.
+pub const MAX_DOMAIN_LIST_LEN: usize = 64;
+
+/// The maximum length of the DUIDs derived by the client: a DUID-UUID.
+const MAX_DEFAULT_DUID_LEN: usize = 18;
+
+/// The preference with which a server asks to be selected immediately, see RFC 8415 § 18.2.1.
+const MAX_PREFERENCE: u8 = 255;
+
+/// The identifier of the identity associations of the client.
+const IAID: u32 = 1;
+
+const DEFAULT_REQUESTED_OPTIONS: &[u16] = &[
+    dhcpv6_field::OPT_DNS_SERVERS,
+    dhcpv6_field::OPT_DOMAIN_LIST,
+    dhcpv6_field::OPT_INFORMATION_REFRESH_TIME,
+];
+
+/// The information refresh time used when the server does not provide one, see RFC 8415
+/// § 21.23.
+const DEFAULT_INFORMATION_REFRESH_TIME: Duration = Duration::from_secs(86400);
+const MIN_INFORMATION_REFRESH_TIME: Duration = Duration::from_secs(600);
+
+/// How long to wait for a usable link-local address before sending a message.
+const NO_SOURCE_ADDRESS_DELAY: Duration = Duration::from_secs(1);
+
+/// IPv6 configuration data provided by the DHCPv6 server.
+#[derive(Debug, Eq, PartialEq, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Config<'a> {
+    /// Address of the DHCPv6 server that provided the configuration.
+    pub server: Ipv6Address,
+    /// Address assigned to the client. It is `None` in stateless mode.
+    ///
+    /// DHCPv6 does not provide the prefix length of the on-link prefix, which is advertised
+    /// by the routers.
+    pub address: Option<Ipv6Address>,
+    /// Prefix delegated to the client, if prefix delegation is enabled.
+    pub prefix: Option<Ipv6Cidr>,
+    /// DNS servers
+    pub dns_servers: Vec<Ipv6Address, DHCPV6_MAX_DNS_SERVER_COUNT>,
+    /// Domain search list
+    pub domain_list: Dhcpv6DomainList<'a>,
+}
+
+/// The mode of operation of the DHCPv6 client.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Mode {
+    /// Acquire an address from the DHCPv6 servers, with SOLICIT and REQUEST messages, along
+    /// with the other configuration. This is what the "managed address configuration" flag of
+    /// the Router Advertisements asks for.
+    Stateful,
+    /// Only acquire the configuration other than addresses, such as the DNS servers, with
+    /// INFORMATION-REQUEST messages, when the addresses are configured with SLAAC. This is
+    /// what the "other configuration" flag of the Router Advertisements asks for.
+    Stateless,
+}
+
+/// Information on how to reach a DHCPv6 server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct ServerInfo {
+    /// IP address of the server
+    address: Ipv6Address,
+    /// DUID of the server, to use in outgoing packets
+    duid: Vec<u8, DHCPV6_MAX_DUID_LEN>,
+}
+
+/// Configuration acquired from a server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Lease {
+    server: ServerInfo,
+    address: Option<Dhcpv6IaAddress>,
+    prefix: Option<Dhcpv6IaPrefix>,
+    dns_servers: Vec<Ipv6Address, DHCPV6_MAX_DNS_SERVER_COUNT>,
+    domain_list: Vec<u8, MAX_DOMAIN_LIST_LEN>,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct SolicitState {
+    /// When to send next solicit
+    retry_at: Instant,
+    /// How long to wait for an answer to the next solicit
+    timeout: Duration,
+    /// When the first solicit was sent
+    started_at: Instant,
+    /// The server of the most preferred ADVERTISE received so far, with its preference.
+    advertised: Option<(ServerInfo, u8)>,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct RequestState {
+    /// When to send next request
+    retry_at: Instant,
+    /// How many retries have been done
+    retry: u16,
+    /// When the first request was sent
+    started_at: Instant,
+    /// Server we're trying to request from
+    server: ServerInfo,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct RenewState {
+    /// Active network config
+    lease: Lease,
+
+    /// Renew timer. When reached, we will start attempting
+    /// to renew this lease with the DHCPv6 server.
+    ///
+    /// Must be less or equal than `rebind_at`.
+    renew_at: Instant,
+
+    /// Rebind timer. When reached, we will start multicasting to renew
+    /// this lease with any DHCPv6 server.
+    ///
+    /// Must be greater than or equal to `renew_at`, and less than or
+    /// equal to `expires_at`.
+    rebind_at: Instant,
+
+    /// Whether the T2 time has elapsed
+    rebinding: bool,
+
+    /// When the first message of the current renew or rebind exchange was sent.
+    started_at: Option<Instant>,
+
+    /// Expiration timer. When reached, this lease is no longer valid, so it must be
+    /// thrown away and the interface deconfigured.
+    expires_at: Instant,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct InformedState {
+    /// Active network config
+    lease: Lease,
+    /// When to send next information request
+    refresh_at: Instant,
+    /// How long to wait for an answer to the next information request
+    timeout: Duration,
+    /// When the first message of the current refresh was sent.
+    started_at: Option<Instant>,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum ClientState {
+    /// Looking for a DHCPv6 server
+    Soliciting(SolicitState),
+    /// Requesting an address
+    Requesting(RequestState),
+    /// Having an address, refresh it periodically.
+    Renewing(RenewState),
+    /// Requesting the configuration, in stateless mode
+    Informing(SolicitState),
+    /// Having the configuration, refresh it periodically.
+    Informed(InformedState),
+}
+
+/// Timeout and retry configuration.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub struct RetryConfig {
+    /// The SOLICIT and INFORMATION-REQUEST timeout doubles every try.
+    pub initial_solicit_timeout: Duration,
+    /// An upper bound on how long to wait between retrying a SOLICIT or an
+    /// INFORMATION-REQUEST.
+    pub max_solicit_timeout: Duration,
+    /// The REQUEST timeout doubles every try.
+    pub initial_request_timeout: Duration,
+    /// An upper bound on how long to wait between retrying a REQUEST.
+    pub max_request_timeout: Duration,
+    pub request_retries: u16,
+    pub min_renew_timeout: Duration,
+    /// An upper bound on how long to wait between retrying a renew or rebind.
+    ///
+    /// Set this to [`Duration::MAX`] if you don't want to impose an upper bound.
+    pub max_renew_timeout: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        // See RFC 8415 § 7.6.
+        Self {
+            initial_solicit_timeout: Duration::from_secs(1),
+            max_solicit_timeout: Duration::from_secs(3600),
+            initial_request_timeout: Duration::from_secs(1),
+            max_request_timeout: Duration::from_secs(30),
+            request_retries: 10,
+            min_renew_timeout: Duration::from_secs(10),
+            max_renew_timeout: Duration::from_secs(600),
+        }
+    }
+}
+
+/// Return value for the `Dhcpv6Socket::poll` function
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Event<'a> {
+    /// Configuration has been lost (for example, the lease has expired)
+    Deconfigured,
+    /// Configuration has been newly acquired, or modified.
+    Configured(Config<'a>),
+}
+
+#[derive(Debug)]
+pub struct Socket<'a> {
+    /// Mode of the DHCPv6 client.
+    mode: Mode,
+    /// Whether to request a delegated prefix, in stateful mode.
+    prefix_delegation: bool,
+    /// State of the DHCPv6 client.
+    state: ClientState,
+    /// Set to true on config/state change, cleared back to false by the `config` function.
+    config_changed: bool,
+    /// Transaction ID of the current exchange.
+    transaction_id: u32,
+    /// DUID of the client, if set.
+    duid: Option<&'a [u8]>,
+    /// DUID of the client derived from the hardware address of the interface, when none is
+    /// set. It is empty until the first message is sent.
+    default_duid: Vec<u8, MAX_DEFAULT_DUID_LEN>,
+
+    retry_config: RetryConfig,
+
+    /// A buffer contains options additional to be added to outgoing DHCPv6
+    /// packets.
+    outgoing_options: &'a [Dhcpv6Option<'a>],
+    /// The options requested to the server.
+    requested_options: Option<&'a [u16]>,
+
+    /// Waker registration
+    #[cfg(feature = "async")]
+    waker: WakerRegistration,
+}
+
+/// DHCPv6 client socket.
+///
+/// The socket acquires an IPv6 configuration through DHCPv6 autonomously.
+/// You must query the configuration with `.poll()` after every call to `Interface::poll()`,
+/// and apply the configuration to the `Interface`.
+impl<'a> Socket<'a> {
+    /// Create a DHCPv6 socket in stateful mode.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Socket {
+            mode: Mode::Stateful,
+            prefix_delegation: false,
+            state: Self::initial_state(Mode::Stateful),
+            config_changed: true,
+            transaction_id: 1,
+            duid: None,
+            default_duid: Vec::new(),
+            retry_config: RetryConfig::default(),
+            outgoing_options: &[],
+            requested_options: None,
+            #[cfg(feature = "async")]
+            waker: WakerRegistration::new(),
+        }
+    }
+
+    fn initial_state(mode: Mode) -> ClientState {
+        let state = SolicitState {
+            retry_at: Instant::from_millis(0),
+            timeout: Duration::ZERO,
+            started_at: Instant::from_millis(0),
+            advertised: None,
+        };
+        match mode {
+            Mode::Stateful => ClientState::Soliciting(state),
+            Mode::Stateless => ClientState::Informing(state),
+        }
+    }
+
+    /// Return the mode of the client.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Set the mode of the client.
+    ///
+    /// Changing the mode discards the current configuration.
+    pub fn set_mode(&mut self, mode: Mode) {
+        if self.mode != mode {
+            self.mode = mode;
+            self.reset();
+        }
+    }
+
+    /// Return whether the client requests a delegated prefix.
+    pub fn prefix_delegation(&self) -> bool {
+        self.prefix_delegation
+    }
+
+    /// Set whether the client requests a delegated prefix (IA_PD), in stateful mode.
+    ///
+    /// Changing this setting discards the current configuration.
+    pub fn set_prefix_delegation(&mut self, prefix_delegation: bool) {
+        if self.prefix_delegation != prefix_delegation {
+            self.prefix_delegation = prefix_delegation;
+            self.reset();
+        }
+    }
+
+    /// Set the DUID identifying the client.
+    ///
+    /// By default, the DUID is derived from the hardware address of the interface (DUID-LL),
+    /// or random (DUID-UUID) if the interface has none.
+    ///
+    /// # Panics
+    /// This function panics if the DUID is longer than 130 octets.
+    pub fn set_duid(&mut self, duid: &'a [u8]) {
+        assert!(duid.len() <= DHCPV6_MAX_DUID_LEN, "DUID too long");
+        self.duid = Some(duid);
+    }
+
+    /// Set the retry/timeouts configuration.
+    pub fn set_retry_config(&mut self, config: RetryConfig) {
+        self.retry_config = config;
+    }
+
+    /// Gets the current retry/timeouts configuration
+    pub fn get_retry_config(&self) -> RetryConfig {
+        self.retry_config
+    }
+
+    /// Set the outgoing options.
+    pub fn set_outgoing_options(&mut self, options: &'a [Dhcpv6Option<'a>]) {
+        self.outgoing_options = options;
+    }
+
+    /// Set the options requested to the server.
+    ///
+    /// This should contain at least `OPTION_DNS_SERVERS` (`23`) and `OPTION_DOMAIN_LIST`
+    /// (`24`). Only the first 8 options are requested.
+    pub fn set_requested_options(&mut self, requested_options: &'a [u16]) {
+        self.requested_options = Some(requested_options);
+    }
+
+    pub(crate) fn poll_at(&self, _cx: &mut Context) -> PollAt {
+        let t = match &self.state {
+            ClientState::Soliciting(state) => state.retry_at,
+            ClientState::Requesting(state) => state.retry_at,
+            ClientState::Renewing(state) => if state.rebinding {
+                state.rebind_at
+            } else {
+                state.renew_at.min(state.rebind_at)
+            }
+            .min(state.expires_at),
+            ClientState::Informing(state) => state.retry_at,
+            ClientState::Informed(state) => state.refresh_at,
+        };
+        PollAt::Time(t)
+    }
+
+    pub(crate) fn process(
+        &mut self,
+        cx: &mut Context,
+        ip_repr: &Ipv6Repr,
+        repr: &UdpRepr,
+        payload: &[u8],
+    ) {
+        let src_ip = ip_repr.src_addr;
+
+        // This is enforced in interface.rs.
+        assert!(repr.src_port == DHCPV6_SERVER_PORT && repr.dst_port == DHCPV6_CLIENT_PORT);
+
+        let dhcp_packet = match Dhcpv6Packet::new_checked(payload) {
+            Ok(dhcp_packet) => dhcp_packet,
+            Err(e) => {
+                net_debug!("DHCPv6 invalid pkt from {}: {:?}", src_ip, e);
+                return;
+            }
+        };
+        let dhcp_repr = match Dhcpv6Repr::parse(&dhcp_packet) {
+            Ok(dhcp_repr) => dhcp_repr,
+            Err(e) => {
+                net_debug!("DHCPv6 error parsing pkt from {}: {:?}", src_ip, e);
+                return;
+            }
+        };
+
+        if dhcp_repr.transaction_id != self.transaction_id {
+            return;
+        }
+        if dhcp_repr.client_id != Some(self.duid.unwrap_or(&self.default_duid)) {
+            return;
+        }
+        let Some(server_id) = dhcp_repr.server_id else {
+            net_debug!(
+                "DHCPv6 ignoring {:?} because missing server_id",
+                dhcp_repr.message_type
+            );
+            return;
+        };
+        let Ok(server_duid) = Vec::from_slice(server_id) else {
+            net_debug!(
+                "DHCPv6 ignoring {:?} because server_id is too long",
+                dhcp_repr.message_type
+            );
+            return;
+        };
+        let server = ServerInfo {
+            address: src_ip,
+            duid: server_duid,
+        };
+
+        net_debug!(
+            "DHCPv6 recv {:?} from {}: {:?}",
+            dhcp_repr.message_type,
+            src_ip,
+            dhcp_repr
+        );
+
+        match (&mut self.state, dhcp_repr.message_type) {
+            (ClientState::Soliciting(state), Dhcpv6MessageType::Advertise) => {
+                if Self::parse_lease(&dhcp_repr, server.clone(), self.prefix_delegation).is_none() {
+                    return;
+                }
+
+                // Keep the most preferred server. Within the first retransmission timeout,
+                // the other advertisements are awaited unless it has the highest preference.
+                let preference = dhcp_repr.preference.unwrap_or(0);
+                if !matches!(&state.advertised, Some((_, p)) if *p >= preference) {
+                    state.advertised = Some((server, preference));
+                }
+                let first_timeout =
+                    cx.now() < state.started_at + self.retry_config.initial_solicit_timeout;
+                if first_timeout && preference != MAX_PREFERENCE {
+                    state.retry_at = state.started_at + self.retry_config.initial_solicit_timeout;
+                    return;
+                }
+                state.retry_at = cx.now();
+            }
+            (ClientState::Requesting(state), Dhcpv6MessageType::Reply) => {
+                if state.server.duid != server.duid {
+                    return;
+                }
+                if let Some(status) = Self::failure_status(&dhcp_repr) {
+                    net_debug!("DHCPv6 request failed: {:?}", status);
+                    self.reset();
+                    return;
+                }
+                let Some(lease) = Self::parse_lease(&dhcp_repr, server, self.prefix_delegation)
+                else {
+                    return;
+                };
+
+                let (renew_at, rebind_at, expires_at) =
+                    Self::lease_times(cx.now(), &dhcp_repr, &lease);
+                self.state = ClientState::Renewing(RenewState {
+                    lease,
+                    renew_at,
+                    rebind_at,
+                    rebinding: false,
+                    started_at: None,
+                    expires_at,
+                });
+                self.config_changed();
+            }
+            (ClientState::Renewing(state), Dhcpv6MessageType::Reply) => {
+                let ia_status = |status: Option<Dhcpv6StatusCode>| {
+                    status.unwrap_or(Dhcpv6StatusCode::Success) != Dhcpv6StatusCode::Success
+                };
+                let ia_statuses = [
+                    dhcp_repr.ia_na.and_then(|ia| ia.status),
+                    dhcp_repr.ia_pd.and_then(|ia| ia.status),
+                ];
+                if ia_statuses.contains(&Some(Dhcpv6StatusCode::NoBinding)) {
+                    // The server has no binding for an identity association, which is
+                    // requested again, see RFC 8415 § 18.2.10.1.
+                    net_debug!("DHCPv6 lease not found by the server, requesting it");
+                    self.state = ClientState::Requesting(RequestState {
+                        retry_at: cx.now(),
+                        retry: 0,
+                        started_at: cx.now(),
+                        server,
+                    });
+                    self.config_changed();
+                    return;
+                }
+                if ia_statuses.into_iter().any(ia_status) {
+                    net_debug!("DHCPv6 lease not renewed, restarting solicitation");
+                    self.reset();
+                    return;
+                }
+                if let Some(status) = Self::failure_status(&dhcp_repr) {
+                    net_debug!("DHCPv6 renew failed: {:?}", status);
+                    return;
+                }
+                let Some(lease) = Self::parse_lease(&dhcp_repr, server, self.prefix_delegation)
+                else {
+                    return;
+                };
+
+                let (renew_at, rebind_at, expires_at) =
+                    Self::lease_times(cx.now(), &dhcp_repr, &lease);
+                state.renew_at = renew_at;
+                state.rebind_at = rebind_at;
+                state.rebinding = false;
+                state.started_at = None;
+                state.expires_at = expires_at;
+                if state.lease != lease {
+                    state.lease = lease;
+                    self.config_changed();
+                }
+            }
+            (ClientState::Informing(_), Dhcpv6MessageType::Reply) => {
+                if let Some(status) = Self::failure_status(&dhcp_repr) {
+                    net_debug!("DHCPv6 information request failed: {:?}", status);
+                    return;
+                }
+
+                self.state = ClientState::Informed(InformedState {
+                    lease: Self::parse_information(&dhcp_repr, server),
+                    refresh_at: cx.now() + Self::refresh_time(&dhcp_repr),
+                    timeout: Duration::ZERO,
+                    started_at: None,
+                });
+                self.config_changed();
+            }
+            (ClientState::Informed(state), Dhcpv6MessageType::Reply) => {
+                if state.started_at.is_none() {
+                    return;
+                }
+                if let Some(status) = Self::failure_status(&dhcp_repr) {
+                    net_debug!("DHCPv6 information request failed: {:?}", status);
+                    return;
+                }
+
+                let lease = Self::parse_information(&dhcp_repr, server);
+                state.refresh_at = cx.now() + Self::refresh_time(&dhcp_repr);
+                state.timeout = Duration::ZERO;
+                state.started_at = None;
+                if state.lease != lease {
+                    state.lease = lease;
+                    self.config_changed();
+                }
+            }
+            _ => {
+                net_debug!(
+                    "DHCPv6 ignoring {:?}: unexpected in current state",
+                    dhcp_repr.message_type
+                );
+            }
+        }
+    }
+
+    /// Return the status of a message if it is not a success.
+    fn failure_status(dhcp_repr: &Dhcpv6Repr) -> Option<Dhcpv6StatusCode> {
+        dhcp_repr
+            .status
+            .filter(|status| *status != Dhcpv6StatusCode::Success)
+    }
+
+    /// Parse the configuration of an ADVERTISE or REPLY message to a SOLICIT, REQUEST, RENEW or
+    /// REBIND message. The message is ignored if it does not carry any of the requested
+    /// identity associations.
+    fn parse_lease(dhcp_repr: &Dhcpv6Repr, server: ServerInfo, prefix: bool) -> Option<Lease> {
+        if let Some(status) = Self::failure_status(dhcp_repr) {
+            net_debug!(
+                "DHCPv6 ignoring {:?} because of status {:?}",
+                dhcp_repr.message_type,
+                status
+            );
+            return None;
+        }
+
+        let address = dhcp_repr
+            .ia_na
+            .filter(|ia| ia.iaid == IAID && ia.status.is_none())
+            .and_then(|ia| ia.address)
+            .filter(|a| a.address.is_unicast() && a.valid_lifetime != 0);
+        let prefix = dhcp_repr
+            .ia_pd
+            .filter(|ia| prefix && ia.iaid == IAID && ia.status.is_none())
+            .and_then(|ia| ia.prefix)
+            .filter(|p| p.valid_lifetime != 0);
+
+        if address.is_none() && prefix.is_none() {
+            net_debug!(
+                "DHCPv6 ignoring {:?} because no address or prefix is available",
+                dhcp_repr.message_type
+            );
+            return None;
+        }
+
+        Some(Lease {
+            address,
+            prefix,
+            ..Self::parse_information(dhcp_repr, server)
+        })
+    }
+
+    /// Parse the configuration other than addresses of a REPLY message.
+    fn parse_information(dhcp_repr: &Dhcpv6Repr, server: ServerInfo) -> Lease {
+        let mut dns_servers = Vec::new();
+        dhcp_repr
+            .dns_servers
+            .iter()
+            .flatten()
+            .filter(|s| s.is_unicast())
+            .for_each(|a| {
+                // This will never produce an error, as both the arrays and `dns_servers`
+                // have length DHCPV6_MAX_DNS_SERVER_COUNT
+                dns_servers.push(*a).ok();
+            });
+
+        // Keep the domain names that fit in the buffer.
+        let mut domain_list = Vec::new();
+        for name in dhcp_repr.domain_list.iter().flat_map(|list| list.iter()) {
+            if domain_list.extend_from_slice(name.as_bytes()).is_err() {
+                break;
+            }
+        }
+
+        Lease {
+            server,
+            address: None,
+            prefix: None,
+            dns_servers,
+            domain_list,
+        }
+    }
+
+    /// Return the times at which a lease must be renewed, rebound, and at which it expires.
+    fn lease_times(
+        now: Instant,
+        dhcp_repr: &Dhcpv6Repr,
+        lease: &Lease,
+    ) -> (Instant, Instant, Instant) {
+        let ias = [
+            lease.address.zip(dhcp_repr.ia_na).map(|(address, ia)| {
+                (
+                    ia.t1,
+                    ia.t2,
+                    address.preferred_lifetime,
+                    address.valid_lifetime,
+                )
+            }),
+            lease.prefix.zip(dhcp_repr.ia_pd).map(|(prefix, ia)| {
+                (
+                    ia.t1,
+                    ia.t2,
+                    prefix.preferred_lifetime,
+                    prefix.valid_lifetime,
+                )
+            }),
+        ];
+
+        // Set renew and rebind times as per RFC 8415 § 14.2: T1 and T2
+        // are set by the server, or default to 0.5 and 0.8 times the
+        // shortest preferred lifetime when they are zero.
+        let (renew, rebind, valid_lifetime) = ias.into_iter().flatten().fold(
+            (u32::MAX, u32::MAX, u32::MAX),
+            |(renew, rebind, valid), (t1, t2, preferred_lifetime, valid_lifetime)| {
+                let t1 = if t1 == 0 { preferred_lifetime / 2 } else { t1 };
+                let t2 = if t2 == 0 {
+                    (preferred_lifetime as u64 * 4 / 5) as u32
+                } else {
+                    t2
+                };
+                (renew.min(t1), rebind.min(t2), valid.min(valid_lifetime))
+            },
+        );
+
+        let expires_at = now + Duration::from_secs(valid_lifetime as u64);
+        let rebind_at = (now + Duration::from_secs(rebind as u64)).min(expires_at);
+        let renew_at = (now + Duration::from_secs(renew as u64)).min(rebind_at);
+        (renew_at, rebind_at, expires_at)
+    }
+
+    /// Return the time after which the configuration of a stateless client is refreshed.
+    fn refresh_time(dhcp_repr: &Dhcpv6Repr) -> Duration {
+        dhcp_repr
+            .information_refresh_time
+            .map(|t| Duration::from_secs(t as u64).max(MIN_INFORMATION_REFRESH_TIME))
+            .unwrap_or(DEFAULT_INFORMATION_REFRESH_TIME)
+    }
+
+    #[cfg(not(test))]
+    fn random_transaction_id(cx: &mut Context) -> u32 {
+        cx.rand().rand_u32() & 0xff_ffff
+    }
+
+    #[cfg(test)]
+    fn random_transaction_id(_cx: &mut Context) -> u32 {
+        0x123456
+    }
+
+    /// Derive the DUID of the client from the hardware address of the interface.
+    fn default_duid(cx: &mut Context) -> Vec<u8, MAX_DEFAULT_DUID_LEN> {
+        let mut duid = Vec::new();
+
+        // DUID-LL, see RFC 8415 § 11.4.
+        #[cfg_attr(
+            not(any(feature = "medium-ethernet", feature = "medium-ieee802154")),
+            allow(unused)
+        )]
+        let mut link_layer = |hardware_type: u16, addr: &[u8]| {
+            duid.extend_from_slice(&[0, 3]).unwrap();
+            duid.extend_from_slice(&hardware_type.to_be_bytes())
+                .unwrap();
+            duid.extend_from_slice(addr).unwrap();
+        };
+        #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+        match cx.hardware_addr() {
+            #[cfg(feature = "medium-ethernet")]
+            HardwareAddress::Ethernet(addr) => link_layer(1, addr.as_bytes()),
+            #[cfg(feature = "medium-ieee802154")]
+            HardwareAddress::Ieee802154(addr) => {
+                if let Some(eui64) = addr.as_eui_64() {
+                    link_layer(27, &eui64)
+                }
+            }
+            #[allow(unreachable_patterns)]
+            _ => {}
+        }
+
+        if duid.is_empty() {
+            // DUID-UUID, see RFC 6355.
+            duid.extend_from_slice(&[0, 4]).unwrap();
+            for _ in 0..4 {
+                duid.extend_from_slice(&cx.rand().rand_u32().to_be_bytes())
+                    .unwrap();
+            }
+        }
+
+        duid
+    }
+
+    pub(crate) fn dispatch<F, E>(&mut self, cx: &mut Context, emit: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut Context, (Ipv6Repr, UdpRepr, Dhcpv6Repr)) -> Result<(), E>,
+    {
+        if self.duid.is_none() && self.default_duid.is_empty() {
+            self.default_duid = Self::default_duid(cx);
+        }
+
+        let now = cx.now();
+        let retry_config = self.retry_config;
+
+        // Messages are sent from a link-local address, which may not be available yet.
+        let src_addr = cx.get_source_address_ipv6(&DHCPV6_ALL_RELAY_AGENTS_AND_SERVERS);
+        let has_src_addr = src_addr.is_link_local();
+
+        let mut requested_options = Vec::new();
+        for code in self
+            .requested_options
+            .unwrap_or(DEFAULT_REQUESTED_OPTIONS)
+            .iter()
+            .filter(|code| {
+                self.mode == Mode::Stateless || **code != dhcpv6_field::OPT_INFORMATION_REFRESH_TIME
+            })
+        {
+            if requested_options.push(*code).is_err() {
+                break;
+            }
+        }
+
+        let ia_na = Dhcpv6IaNa {
+            iaid: IAID,
+            t1: 0,
+            t2: 0,
+            address: None,
+            status: None,
+        };
+        let ia_pd = Dhcpv6IaPd {
+            iaid: IAID,
+            t1: 0,
+            t2: 0,
+            prefix: None,
+            status: None,
+        };
+
+        let mut dhcp_repr = Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::Solicit,
+            transaction_id: self.transaction_id,
+            client_id: Some(self.duid.unwrap_or(&self.default_duid)),
+            server_id: None,
+            ia_na: Some(ia_na),
+            ia_pd: self.prefix_delegation.then_some(ia_pd),
+            elapsed_time: Some(0),
+            requested_options: Some(requested_options),
+            status: None,
+            preference: None,
+            rapid_commit: false,
+            dns_servers: None,
+            domain_list: None,
+            information_refresh_time: None,
+            additional_options: self.outgoing_options,
+        };
+
+        let udp_repr = UdpRepr {
+            src_port: DHCPV6_CLIENT_PORT,
+            dst_port: DHCPV6_SERVER_PORT,
+        };
+
+        let mut ipv6_repr = Ipv6Repr {
+            src_addr,
+            dst_addr: DHCPV6_ALL_RELAY_AGENTS_AND_SERVERS,
+            next_header: IpProtocol::Udp,
+            payload_len: 0, // filled right before emit
+            hop_limit: 64,
+        };
+
+        // The elapsed time option is expressed in hundredths of a second.
+        let elapsed_time = |started_at: Instant| {
+            ((now - started_at).total_millis() / 10).min(u16::MAX as u64) as u16
+        };
+
+        match &mut self.state {
+            ClientState::Soliciting(state) | ClientState::Informing(state) => {
+                if now < state.retry_at {
+                    return Ok(());
+                }
+                if let Some((server, _)) = state.advertised.take() {
+                    // Request the most preferred server that advertised itself.
+                    self.state = ClientState::Requesting(RequestState {
+                        retry_at: now,
+                        retry: 0,
+                        started_at: now,
+                        server,
+                    });
+                    return self.dispatch(cx, emit);
+                }
+                if !has_src_addr {
+                    state.retry_at = now + NO_SOURCE_ADDRESS_DELAY;
+                    return Ok(());
+                }
+
+                let first = state.timeout == Duration::ZERO;
+                if first {
+                    dhcp_repr.transaction_id = Self::random_transaction_id(cx);
+                } else {
+                    dhcp_repr.elapsed_time = Some(elapsed_time(state.started_at));
+                }
+                if self.mode == Mode::Stateless {
+                    dhcp_repr.message_type = Dhcpv6MessageType::InformationRequest;
+                    dhcp_repr.ia_na = None;
+                    dhcp_repr.ia_pd = None;
+                }
+
+                // send packet
+                net_debug!(
+                    "DHCPv6 send {:?} to {}: {:?}",
+                    dhcp_repr.message_type,
+                    ipv6_repr.dst_addr,
+                    dhcp_repr
+                );
+                ipv6_repr.payload_len = udp_repr.header_len() + dhcp_repr.buffer_len();
+                let transaction_id = dhcp_repr.transaction_id;
+                emit(cx, (ipv6_repr, udp_repr, dhcp_repr))?;
+
+                // Update state AFTER the packet has been successfully sent.
+                if first {
+                    state.started_at = now;
+                    state.timeout = retry_config.initial_solicit_timeout;
+                } else {
+                    state.timeout = (state.timeout * 2).min(retry_config.max_solicit_timeout);
+                }
+                state.retry_at = now + state.timeout;
+                self.transaction_id = transaction_id;
+                Ok(())
+            }
+            ClientState::Requesting(state) => {
+                if now < state.retry_at {
+                    return Ok(());
+                }
+
+                if state.retry >= retry_config.request_retries {
+                    net_debug!("DHCPv6 request retries exceeded, restarting solicitation");
+                    self.reset();
+                    return Ok(());
+                }
+
+                if !has_src_addr {
+                    state.retry_at = now + NO_SOURCE_ADDRESS_DELAY;
+                    return Ok(());
+                }
+
+                if state.retry == 0 {
+                    dhcp_repr.transaction_id = Self::random_transaction_id(cx);
+                }
+                dhcp_repr.message_type = Dhcpv6MessageType::Request;
+                dhcp_repr.server_id = Some(&state.server.duid);
+                dhcp_repr.elapsed_time = Some(elapsed_time(state.started_at));
+
+                net_debug!(
+                    "DHCPv6 send request to {}: {:?}",
+                    ipv6_repr.dst_addr,
+                    dhcp_repr
+                );
+                ipv6_repr.payload_len = udp_repr.header_len() + dhcp_repr.buffer_len();
+                let transaction_id = dhcp_repr.transaction_id;
+                emit(cx, (ipv6_repr, udp_repr, dhcp_repr))?;
+
+                // Exponential backoff: Double every try.
+                if state.retry == 0 {
+                    state.started_at = now;
+                }
+                state.retry_at = now
+                    + (retry_config.initial_request_timeout << state.retry.min(16) as u32)
+                        .min(retry_config.max_request_timeout);
+                state.retry += 1;
+
+                self.transaction_id = transaction_id;
+                Ok(())
+            }
+            ClientState::Renewing(state) => {
+                if state.expires_at <= now {
+                    net_debug!("DHCPv6 lease expired");
+                    self.reset();
+                    // return Ok so we get polled again
+                    return Ok(());
+                }
+
+                if now < state.renew_at || state.rebinding && now < state.rebind_at {
+                    return Ok(());
+                }
+
+                if !has_src_addr {
+                    state.renew_at = now + NO_SOURCE_ADDRESS_DELAY;
+                    state.rebind_at = state.rebind_at.max(state.renew_at);
+                    return Ok(());
+                }
+
+                // Rebinding starts a new exchange.
+                let rebinding = now >= state.rebind_at;
+                let started_at = match state.started_at {
+                    Some(started_at) if rebinding == state.rebinding => started_at,
+                    _ => {
+                        dhcp_repr.transaction_id = Self::random_transaction_id(cx);
+                        now
+                    }
+                };
+                state.rebinding = rebinding;
+
+                // Renewing is sent to the original server, rebinding to any server.
+                if state.rebinding {
+                    dhcp_repr.message_type = Dhcpv6MessageType::Rebind;
+                } else {
+                    dhcp_repr.message_type = Dhcpv6MessageType::Renew;
+                    dhcp_repr.server_id = Some(&state.lease.server.duid);
+                }
+                dhcp_repr.ia_na = state.lease.address.map(|address| Dhcpv6IaNa {
+                    address: Some(address),
+                    ..ia_na
+                });
+                dhcp_repr.ia_pd = state.lease.prefix.map(|prefix| Dhcpv6IaPd {
+                    prefix: Some(prefix),
+                    ..ia_pd
+                });
+                dhcp_repr.elapsed_time = Some(elapsed_time(started_at));
+
+                net_debug!(
+                    "DHCPv6 send {:?} to {}: {:?}",
+                    dhcp_repr.message_type,
+                    ipv6_repr.dst_addr,
+                    dhcp_repr
+                );
+                ipv6_repr.payload_len = udp_repr.header_len() + dhcp_repr.buffer_len();
+                let transaction_id = dhcp_repr.transaction_id;
+                emit(cx, (ipv6_repr, udp_repr, dhcp_repr))?;
+
+                // Wait one-half of the remaining time until T2 (when renewing) or
+                // until the end of the lease (when rebinding), but at least the
+                // minimum renew timeout, before retransmitting.
+                state.started_at = Some(started_at);
+                if state.rebinding {
+                    state.rebind_at = now
+                        + retry_config
+                            .min_renew_timeout
+                            .max((state.expires_at - now) / 2)
+                            .min(retry_config.max_renew_timeout);
+                } else {
+                    state.renew_at = now
+                        + retry_config
+                            .min_renew_timeout
+                            .max((state.rebind_at - now) / 2)
+                            .min(state.rebind_at - now)
+                            .min(retry_config.max_renew_timeout);
+                }
+
+                self.transaction_id = transaction_id;
+                Ok(())
+            }
+            ClientState::Informed(state) => {
+                if now < state.refresh_at {
+                    return Ok(());
+                }
+                if !has_src_addr {
+                    state.refresh_at = now + NO_SOURCE_ADDRESS_DELAY;
+                    return Ok(());
+                }
+
+                let started_at = match state.started_at {
+                    Some(started_at) => started_at,
+                    None => {
+                        dhcp_repr.transaction_id = Self::random_transaction_id(cx);
+                        now
+                    }
+                };
+                dhcp_repr.message_type = Dhcpv6MessageType::InformationRequest;
+                dhcp_repr.ia_na = None;
+                dhcp_repr.ia_pd = None;
+                dhcp_repr.elapsed_time = Some(elapsed_time(started_at));
+
+                net_debug!(
+                    "DHCPv6 send refresh to {}: {:?}",
+                    ipv6_repr.dst_addr,
+                    dhcp_repr
+                );
+                ipv6_repr.payload_len = udp_repr.header_len() + dhcp_repr.buffer_len();
+                let transaction_id = dhcp_repr.transaction_id;
+                emit(cx, (ipv6_repr, udp_repr, dhcp_repr))?;
+
+                state.started_at = Some(started_at);
+                state.timeout = if state.timeout == Duration::ZERO {
+                    retry_config.initial_solicit_timeout
+                } else {
+                    (state.timeout * 2).min(retry_config.max_solicit_timeout)
+                };
+                state.refresh_at = now + state.timeout;
+
+                self.transaction_id = transaction_id;
+                Ok(())
+            }
+        }
+    }
+
+    /// Reset state and restart the solicitation, or the information request in
+    /// stateless mode.
+    ///
+    /// Use this to speed up acquisition of a configuration in a new
+    /// network if a link was down and it is now back up.
+    pub fn reset(&mut self) {
+        net_trace!("DHCPv6 reset");
+        if let ClientState::Renewing(_) | ClientState::Informed(_) = &self.state {
+            self.config_changed();
+        }
+        self.state = Self::initial_state(self.mode);
+    }
+
+    /// Query the socket for configuration changes.
+    ///
+    /// The socket has an internal "configuration changed" flag. If
+    /// set, this function returns the configuration and resets the flag.
+    pub fn poll(&mut self) -> Option<Event<'_>> {
+        if !self.config_changed {
+            return None;
+        }
+
+        self.config_changed = false;
+        let lease = match &self.state {
+            ClientState::Renewing(state) => &state.lease,
+            ClientState::Informed(state) => &state.lease,
+            _ => return Some(Event::Deconfigured),
+        };
+        Some(Event::Configured(Config {
+            server: lease.server.address,
+            address: lease.address.map(|a| a.address),
+            prefix: lease.prefix.map(|p| p.prefix),
+            dns_servers: lease.dns_servers.clone(),
+            domain_list: Dhcpv6DomainList::new(&lease.domain_list),
+        }))
+    }
+
+    /// This function _must_ be called when the configuration provided to the
+    /// interface, by this DHCPv6 socket, changes. It will update the `config_changed` field
+    /// so that a subsequent call to `poll` will yield an event, and wake a possible waker.
+    pub(crate) fn config_changed(&mut self) {
+        self.config_changed = true;
+        #[cfg(feature = "async")]
+        self.waker.wake();
+    }
+
+    /// Register a waker.
+    ///
+    /// The waker is woken on state changes that might affect the return value
+    /// of `poll` method calls, which indicates a new state in the DHCPv6 configuration
+    /// provided by this DHCPv6 socket.
+    ///
+    /// Notes:
+    ///
+    /// - Only one waker can be registered at a time. If another waker was previously registered,
+    ///   it is overwritten and will no longer be woken.
+    /// - The Waker is woken only once. Once woken, you must register it again to receive more wakes.
+    #[cfg(feature = "async")]
+    pub fn register_waker(&mut self, waker: &Waker) {
+        self.waker.register(waker)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::ops::{Deref, DerefMut};
+
+    use super::*;
+
+    // =========================================================================================//
+    // Helper functions
+
+    struct TestSocket {
+        socket: Socket<'static>,
+        cx: Context,
+    }
+
+    impl Deref for TestSocket {
+        type Target = Socket<'static>;
+        fn deref(&self) -> &Self::Target {
+            &self.socket
+        }
+    }
+
+    impl DerefMut for TestSocket {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.socket
+        }
+    }
+
+    fn send(
+        s: &mut TestSocket,
+        timestamp: Instant,
+        (ip_repr, udp_repr, dhcp_repr): (Ipv6Repr, UdpRepr, Dhcpv6Repr),
+    ) {
+        s.cx.set_now(timestamp);
+
+        net_trace!("send: {:?}", ip_repr);
+        net_trace!("      {:?}", udp_repr);
+        net_trace!("      {:?}", dhcp_repr);
+
+        let mut payload = vec![0; dhcp_repr.buffer_len()];
+        dhcp_repr
+            .emit(&mut Dhcpv6Packet::new_unchecked(&mut payload))
+            .unwrap();
+
+        s.socket.process(&mut s.cx, &ip_repr, &udp_repr, &payload)
+    }
+
+    fn recv(s: &mut TestSocket, timestamp: Instant, reprs: &[(Ipv6Repr, UdpRepr, Dhcpv6Repr)]) {
+        s.cx.set_now(timestamp);
+
+        let mut i = 0;
+
+        while s.socket.poll_at(&mut s.cx) <= PollAt::Time(timestamp) {
+            let _ = s
+                .socket
+                .dispatch(&mut s.cx, |_, (mut ip_repr, udp_repr, dhcp_repr)| {
+                    assert_eq!(ip_repr.next_header, IpProtocol::Udp);
+                    assert_eq!(
+                        ip_repr.payload_len,
+                        udp_repr.header_len() + dhcp_repr.buffer_len()
+                    );
+
+                    // We validated the payload len, change it to 0 to make equality testing easier
+                    ip_repr.payload_len = 0;
+
+                    net_trace!("recv: {:?}", ip_repr);
+                    net_trace!("      {:?}", udp_repr);
+                    net_trace!("      {:?}", dhcp_repr);
+
+                    let got_repr = (ip_repr, udp_repr, dhcp_repr);
+                    match reprs.get(i) {
+                        Some(want_repr) => assert_eq!(want_repr, &got_repr),
+                        None => panic!("Too many reprs emitted"),
+                    }
+                    i += 1;
+                    Ok::<_, ()>(())
+                });
+        }
+
+        assert_eq!(i, reprs.len());
+    }
+
+    macro_rules! send {
+        ($socket:ident, $repr:expr) =>
+            (send!($socket, time 0, $repr));
+        ($socket:ident, time $time:expr, $repr:expr) =>
+            (send(&mut $socket, Instant::from_millis($time), $repr));
+    }
+
+    macro_rules! recv {
+        ($socket:ident, $reprs:expr) => ({
+            recv!($socket, time 0, $reprs);
+        });
+        ($socket:ident, time $time:expr, $reprs:expr) => ({
+            recv(&mut $socket, Instant::from_millis($time), &$reprs);
+        });
+    }
+
+    // =========================================================================================//
+    // Constants
+
+    const TXID: u32 = 0x123456;
+
+    const MY_LL: Ipv6Address = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+    const SERVER_LL: Ipv6Address = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 2);
+    const MY_IP: Ipv6Address = Ipv6Address::new(0xfd00, 0, 0, 0, 0, 0, 0, 0x42);
+    const MY_PREFIX: Ipv6Cidr =
+        Ipv6Cidr::new(Ipv6Address::new(0xfd00, 0, 0, 0x1200, 0, 0, 0, 0), 56);
+    const DNS_IP: Ipv6Address = Ipv6Address::new(0xfd00, 0, 0, 0, 0, 0, 0, 1);
+    const DOMAIN_LIST: &[u8] = b"\x07example\x03com\x00";
+
+    const CLIENT_DUID: &[u8] = &[0x00, 0x03, 0x00, 0x01, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02];
+    const OTHER_DUID: &[u8] = &[0x00, 0x03, 0x00, 0x01, 0x02, 0x02, 0x02, 0x02, 0x02, 0x03];
+    const SERVER_DUID: &[u8] = &[0x00, 0x03, 0x00, 0x01, 0x52, 0x54, 0x00, 0x12, 0x34, 0x56];
+    const OTHER_SERVER_DUID: &[u8] = &[0x00, 0x03, 0x00, 0x01, 0x52, 0x54, 0x00, 0x12, 0x34, 0x57];
+
+    const IP_SEND: Ipv6Repr = Ipv6Repr {
+        src_addr: MY_LL,
+        dst_addr: DHCPV6_ALL_RELAY_AGENTS_AND_SERVERS,
+        next_header: IpProtocol::Udp,
+        payload_len: 0,
+        hop_limit: 64,
+    };
+
+    const IP_RECV: Ipv6Repr = Ipv6Repr {
+        src_addr: SERVER_LL,
+        dst_addr: MY_LL,
+        next_header: IpProtocol::Udp,
+        payload_len: 0,
+        hop_limit: 64,
+    };
+
+    const UDP_SEND: UdpRepr = UdpRepr {
+        src_port: DHCPV6_CLIENT_PORT,
+        dst_port: DHCPV6_SERVER_PORT,
+    };
+    const UDP_RECV: UdpRepr = UdpRepr {
+        src_port: DHCPV6_SERVER_PORT,
+        dst_port: DHCPV6_CLIENT_PORT,
+    };
+
+    const IA_ADDRESS: Dhcpv6IaAddress = Dhcpv6IaAddress {
+        address: MY_IP,
+        preferred_lifetime: 600,
+        valid_lifetime: 900,
+    };
+
+    const IA_PREFIX: Dhcpv6IaPrefix = Dhcpv6IaPrefix {
+        prefix: MY_PREFIX,
+        preferred_lifetime: 600,
+        valid_lifetime: 900,
+    };
+
+    const IA_NA: Dhcpv6IaNa = Dhcpv6IaNa {
+        iaid: IAID,
+        t1: 0,
+        t2: 0,
+        address: None,
+        status: None,
+    };
+
+    const IA_PD: Dhcpv6IaPd = Dhcpv6IaPd {
+        iaid: IAID,
+        t1: 0,
+        t2: 0,
+        prefix: None,
+        status: None,
+    };
+
+    const DHCP_DEFAULT: Dhcpv6Repr = Dhcpv6Repr {
+        message_type: Dhcpv6MessageType::Unknown(99),
+        transaction_id: TXID,
+        client_id: Some(CLIENT_DUID),
+        server_id: None,
+        ia_na: None,
+        ia_pd: None,
+        elapsed_time: None,
+        requested_options: None,
+        status: None,
+        preference: None,
+        rapid_commit: false,
+        dns_servers: None,
+        domain_list: None,
+        information_refresh_time: None,
+        additional_options: &[],
+    };
+
+    fn dhcp_solicit(elapsed_time: u16) -> Dhcpv6Repr<'static> {
+        Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::Solicit,
+            ia_na: Some(IA_NA),
+            elapsed_time: Some(elapsed_time),
+            requested_options: Some(Vec::from_slice(&[23, 24]).unwrap()),
+            ..DHCP_DEFAULT
+        }
+    }
+
+    fn dhcp_advertise() -> Dhcpv6Repr<'static> {
+        Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::Advertise,
+            server_id: Some(SERVER_DUID),
+            ia_na: Some(Dhcpv6IaNa {
+                t1: 300,
+                t2: 480,
+                address: Some(IA_ADDRESS),
+                ..IA_NA
+            }),
+            dns_servers: Some(Vec::from_slice(&[DNS_IP]).unwrap()),
+            domain_list: Some(Dhcpv6DomainList::new(DOMAIN_LIST)),
+            ..DHCP_DEFAULT
+        }
+    }
+
+    fn dhcp_request(elapsed_time: u16) -> Dhcpv6Repr<'static> {
+        Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::Request,
+            server_id: Some(SERVER_DUID),
+            ..dhcp_solicit(elapsed_time)
+        }
+    }
+
+    fn dhcp_reply() -> Dhcpv6Repr<'static> {
+        Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::Reply,
+            ..dhcp_advertise()
+        }
+    }
+
+    fn dhcp_renew(elapsed_time: u16) -> Dhcpv6Repr<'static> {
+        Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::Renew,
+            server_id: Some(SERVER_DUID),
+            ia_na: Some(Dhcpv6IaNa {
+                address: Some(IA_ADDRESS),
+                ..IA_NA
+            }),
+            ..dhcp_solicit(elapsed_time)
+        }
+    }
+
+    fn dhcp_rebind(elapsed_time: u16) -> Dhcpv6Repr<'static> {
+        Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::Rebind,
+            server_id: None,
+            ..dhcp_renew(elapsed_time)
+        }
+    }
+
+    fn dhcp_information_request(elapsed_time: u16) -> Dhcpv6Repr<'static> {
+        Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::InformationRequest,
+            elapsed_time: Some(elapsed_time),
+            requested_options: Some(Vec::from_slice(&[23, 24, 32]).unwrap()),
+            ..DHCP_DEFAULT
+        }
+    }
+
+    fn config() -> Config<'static> {
+        Config {
+            server: SERVER_LL,
+            address: Some(MY_IP),
+            prefix: None,
+            dns_servers: Vec::from_slice(&[DNS_IP]).unwrap(),
+            domain_list: Dhcpv6DomainList::new(DOMAIN_LIST),
+        }
+    }
+
+    // =========================================================================================//
+    // Tests
+
+    use crate::phy::Medium;
+    use crate::tests::setup;
+    use rstest::*;
+
+    fn socket(medium: Medium) -> TestSocket {
+        let (iface, _, _) = setup(medium);
+        let mut s = Socket::new();
+        assert_eq!(s.poll(), Some(Event::Deconfigured));
+        TestSocket {
+            socket: s,
+            cx: iface.inner,
+        }
+    }
+
+    fn socket_bound(medium: Medium) -> TestSocket {
+        let mut s = socket(medium);
+        s.transaction_id = TXID;
+        s.set_duid(CLIENT_DUID);
+        s.state = ClientState::Renewing(RenewState {
+            lease: Lease {
+                server: ServerInfo {
+                    address: SERVER_LL,
+                    duid: Vec::from_slice(SERVER_DUID).unwrap(),
+                },
+                address: Some(IA_ADDRESS),
+                prefix: None,
+                dns_servers: Vec::from_slice(&[DNS_IP]).unwrap(),
+                domain_list: Vec::from_slice(DOMAIN_LIST).unwrap(),
+            },
+            renew_at: Instant::from_secs(300),
+            rebind_at: Instant::from_secs(480),
+            rebinding: false,
+            started_at: None,
+            expires_at: Instant::from_secs(900),
+        });
+
+        s
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_bind(#[case] medium: Medium) {
+        let mut s = socket(medium);
+
+        recv!(s, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        assert_eq!(s.poll(), None);
+        send!(s, (IP_RECV, UDP_RECV, dhcp_advertise()));
+        assert_eq!(s.poll(), None);
+        // Other advertisements are awaited until the first retransmission timeout.
+        recv!(s, time 500, []);
+        recv!(s, time 1_000, [(IP_SEND, UDP_SEND, dhcp_request(0))]);
+        assert_eq!(s.poll(), None);
+        send!(s, time 1_000, (IP_RECV, UDP_RECV, dhcp_reply()));
+
+        assert_eq!(s.poll(), Some(Event::Configured(config())));
+
+        match &s.state {
+            ClientState::Renewing(r) => {
+                assert_eq!(r.renew_at, Instant::from_secs(301));
+                assert_eq!(r.rebind_at, Instant::from_secs(481));
+                assert_eq!(r.expires_at, Instant::from_secs(901));
+            }
+            _ => panic!("Invalid state"),
+        }
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_solicit_retransmit(#[case] medium: Medium) {
+        let mut s = socket(medium);
+
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        recv!(s, time 500, []);
+        recv!(s, time 1_000, [(IP_SEND, UDP_SEND, dhcp_solicit(100))]);
+        recv!(s, time 2_000, []);
+        recv!(s, time 3_000, [(IP_SEND, UDP_SEND, dhcp_solicit(300))]);
+        recv!(s, time 7_000, [(IP_SEND, UDP_SEND, dhcp_solicit(700))]);
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_ignore_other_client(#[case] medium: Medium) {
+        let mut s = socket(medium);
+
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        send!(s, time 0, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            client_id: Some(OTHER_DUID),
+            ..dhcp_advertise()
+        }));
+        send!(s, time 0, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            transaction_id: TXID + 1,
+            ..dhcp_advertise()
+        }));
+        recv!(s, time 1_000, [(IP_SEND, UDP_SEND, dhcp_solicit(100))]);
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_ignore_no_address(#[case] medium: Medium) {
+        let mut s = socket(medium);
+
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        send!(s, time 0, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            ia_na: Some(Dhcpv6IaNa {
+                status: Some(Dhcpv6StatusCode::NoAddrsAvail),
+                ..IA_NA
+            }),
+            ..dhcp_advertise()
+        }));
+        recv!(s, time 1_000, [(IP_SEND, UDP_SEND, dhcp_solicit(100))]);
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_advertise_preference(#[case] medium: Medium) {
+        let mut s = socket(medium);
+        let request = Dhcpv6Repr {
+            server_id: Some(OTHER_SERVER_DUID),
+            ..dhcp_request(0)
+        };
+
+        // The most preferred server is requested after the first retransmission timeout.
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        send!(s, time 0, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            preference: Some(10),
+            ..dhcp_advertise()
+        }));
+        send!(s, time 500, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            server_id: Some(OTHER_SERVER_DUID),
+            preference: Some(20),
+            ..dhcp_advertise()
+        }));
+        send!(s, time 600, (IP_RECV, UDP_RECV, dhcp_advertise()));
+        recv!(s, time 999, []);
+        recv!(s, time 1_000, [(IP_SEND, UDP_SEND, request.clone())]);
+
+        // A server with the highest preference is requested immediately.
+        s.reset();
+        recv!(s, time 2_000, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        send!(s, time 2_000, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            server_id: Some(OTHER_SERVER_DUID),
+            preference: Some(255),
+            ..dhcp_advertise()
+        }));
+        recv!(s, time 2_000, [(IP_SEND, UDP_SEND, request.clone())]);
+
+        // After the first retransmission timeout, the first server is requested immediately.
+        s.reset();
+        recv!(s, time 3_000, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        recv!(s, time 4_000, [(IP_SEND, UDP_SEND, dhcp_solicit(100))]);
+        send!(s, time 4_500, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            server_id: Some(OTHER_SERVER_DUID),
+            ..dhcp_advertise()
+        }));
+        recv!(s, time 4_500, [(IP_SEND, UDP_SEND, request)]);
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_request_timeout(#[case] medium: Medium) {
+        let mut s = socket(medium);
+
+        s.set_retry_config(RetryConfig {
+            request_retries: 3,
+            ..Default::default()
+        });
+
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        send!(s, time 0, (IP_RECV, UDP_RECV, dhcp_advertise()));
+        recv!(s, time 1_000, [(IP_SEND, UDP_SEND, dhcp_request(0))]);
+        recv!(s, time 2_000, [(IP_SEND, UDP_SEND, dhcp_request(100))]);
+        recv!(s, time 4_000, [(IP_SEND, UDP_SEND, dhcp_request(300))]);
+        // The solicitation restarts after the last request.
+        recv!(s, time 8_000, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_renew(#[case] medium: Medium) {
+        let mut s = socket_bound(medium);
+
+        recv!(s, []);
+        assert_eq!(s.poll(), None);
+        recv!(s, time 300_000, [(IP_SEND, UDP_SEND, dhcp_renew(0))]);
+        recv!(s, time 390_000, [(IP_SEND, UDP_SEND, dhcp_renew(9_000))]);
+        assert_eq!(s.poll(), None);
+
+        send!(s, time 400_000, (IP_RECV, UDP_RECV, dhcp_reply()));
+        // The configuration did not change.
+        assert_eq!(s.poll(), None);
+
+        match &s.state {
+            ClientState::Renewing(r) => {
+                assert_eq!(r.renew_at, Instant::from_secs(400 + 300));
+                assert_eq!(r.rebind_at, Instant::from_secs(400 + 480));
+                assert_eq!(r.expires_at, Instant::from_secs(400 + 900));
+            }
+            _ => panic!("Invalid state"),
+        }
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_rebind(#[case] medium: Medium) {
+        let mut s = socket_bound(medium);
+
+        recv!(s, time 480_000, [(IP_SEND, UDP_SEND, dhcp_rebind(0))]);
+        recv!(s, time 690_000, [(IP_SEND, UDP_SEND, dhcp_rebind(21_000))]);
+
+        send!(s, time 700_000, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            dns_servers: None,
+            ..dhcp_reply()
+        }));
+        assert_eq!(
+            s.poll(),
+            Some(Event::Configured(Config {
+                dns_servers: Vec::new(),
+                ..config()
+            }))
+        );
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_expire(#[case] medium: Medium) {
+        let mut s = socket_bound(medium);
+
+        recv!(s, time 300_000, [(IP_SEND, UDP_SEND, dhcp_renew(0))]);
+        recv!(s, time 480_000, [(IP_SEND, UDP_SEND, dhcp_rebind(0))]);
+        recv!(s, time 690_000, [(IP_SEND, UDP_SEND, dhcp_rebind(21_000))]);
+        recv!(s, time 800_000, [(IP_SEND, UDP_SEND, dhcp_rebind(32_000))]);
+        assert_eq!(s.poll(), None);
+        recv!(s, time 900_000, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        assert_eq!(s.poll(), Some(Event::Deconfigured));
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_renew_no_binding(#[case] medium: Medium) {
+        let mut s = socket_bound(medium);
+
+        recv!(s, time 300_000, [(IP_SEND, UDP_SEND, dhcp_renew(0))]);
+        send!(s, time 300_000, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            ia_na: Some(Dhcpv6IaNa {
+                status: Some(Dhcpv6StatusCode::NoBinding),
+                ..IA_NA
+            }),
+            ..dhcp_reply()
+        }));
+        assert_eq!(s.poll(), Some(Event::Deconfigured));
+        // The lease is requested again, instead of restarting the solicitation.
+        recv!(s, time 300_000, [(IP_SEND, UDP_SEND, dhcp_request(0))]);
+        send!(s, time 300_000, (IP_RECV, UDP_RECV, dhcp_reply()));
+        assert_eq!(s.poll(), Some(Event::Configured(config())));
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_prefix_delegation(#[case] medium: Medium) {
+        let mut s = socket(medium);
+        s.set_prefix_delegation(true);
+
+        recv!(
+            s,
+            [(
+                IP_SEND,
+                UDP_SEND,
+                Dhcpv6Repr {
+                    ia_pd: Some(IA_PD),
+                    ..dhcp_solicit(0)
+                }
+            )]
+        );
+        // The server only delegates a prefix.
+        let ia_na = Dhcpv6IaNa {
+            status: Some(Dhcpv6StatusCode::NoAddrsAvail),
+            ..IA_NA
+        };
+        let ia_pd = Dhcpv6IaPd {
+            t1: 300,
+            t2: 480,
+            prefix: Some(IA_PREFIX),
+            ..IA_PD
+        };
+        send!(
+            s,
+            (
+                IP_RECV,
+                UDP_RECV,
+                Dhcpv6Repr {
+                    ia_na: Some(ia_na),
+                    ia_pd: Some(ia_pd),
+                    ..dhcp_advertise()
+                }
+            )
+        );
+        recv!(
+            s,
+            time 1_000,
+            [(
+                IP_SEND,
+                UDP_SEND,
+                Dhcpv6Repr {
+                    ia_pd: Some(IA_PD),
+                    ..dhcp_request(0)
+                }
+            )]
+        );
+        send!(
+            s,
+            time 1_000,
+            (
+                IP_RECV,
+                UDP_RECV,
+                Dhcpv6Repr {
+                    ia_na: Some(ia_na),
+                    ia_pd: Some(ia_pd),
+                    ..dhcp_reply()
+                }
+            )
+        );
+
+        assert_eq!(
+            s.poll(),
+            Some(Event::Configured(Config {
+                address: None,
+                prefix: Some(MY_PREFIX),
+                ..config()
+            }))
+        );
+
+        // Only the delegated prefix is renewed.
+        recv!(s, time 301_000, [(IP_SEND, UDP_SEND, Dhcpv6Repr {
+            ia_na: None,
+            ia_pd: Some(Dhcpv6IaPd {
+                prefix: Some(IA_PREFIX),
+                ..IA_PD
+            }),
+            ..dhcp_renew(0)
+        })]);
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_stateless(#[case] medium: Medium) {
+        let mut s = socket(medium);
+        s.set_mode(Mode::Stateless);
+
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_information_request(0))]);
+        recv!(s, time 1_000, [(IP_SEND, UDP_SEND, dhcp_information_request(100))]);
+        send!(s, time 1_000, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::Reply,
+            server_id: Some(SERVER_DUID),
+            dns_servers: Some(Vec::from_slice(&[DNS_IP]).unwrap()),
+            domain_list: Some(Dhcpv6DomainList::new(DOMAIN_LIST)),
+            information_refresh_time: Some(3600),
+            ..DHCP_DEFAULT
+        }));
+
+        assert_eq!(
+            s.poll(),
+            Some(Event::Configured(Config {
+                address: None,
+                ..config()
+            }))
+        );
+
+        // The configuration is refreshed after the information refresh time.
+        recv!(s, time 3_600_000, []);
+        recv!(s, time 3_601_000, [(IP_SEND, UDP_SEND, dhcp_information_request(0))]);
+        recv!(s, time 3_602_000, [(IP_SEND, UDP_SEND, dhcp_information_request(100))]);
+        assert_eq!(s.poll(), None);
+    }
+}
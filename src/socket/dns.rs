@@ -1,7 +1,8 @@
+use core::fmt::{self, Write};
 #[cfg(feature = "async")]
 use core::task::Waker;
 
-use heapless::Vec;
+use heapless::{String, Vec};
 use managed::ManagedSlice;
 
 use crate::config::{DNS_MAX_NAME_SIZE, DNS_MAX_RESULT_COUNT, DNS_MAX_SERVER_COUNT};
@@ -126,7 +127,98 @@ pub enum MulticastDns {
 
 #[derive(Debug)]
 struct CompletedQuery {
-    addresses: Vec<IpAddress, DNS_MAX_RESULT_COUNT>,
+    records: Vec<QueryRecord, DNS_MAX_RESULT_COUNT>,
+}
+
+/// A record of the result of a query.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum QueryRecord {
+    /// The address of an `A` or `AAAA` record.
+    Address(IpAddress),
+    /// The name a `PTR` record points to.
+    Ptr(Name),
+    /// A mail exchange of an `MX` record.
+    Mx { preference: u16, exchange: Name },
+    /// The character-strings of a `TXT` record.
+    Txt(Txt),
+    /// The location of a service of an `SRV` record (see RFC 2782).
+    Srv {
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: Name,
+    },
+}
+
+impl QueryRecord {
+    /// Return whether the record answers a query of the given type. Address queries accept
+    /// both `A` and `AAAA` records.
+    fn answers(&self, type_: Type) -> bool {
+        match self {
+            QueryRecord::Address(_) => matches!(type_, Type::A | Type::Aaaa | Type::Any),
+            QueryRecord::Ptr(_) => matches!(type_, Type::Ptr | Type::Any),
+            QueryRecord::Mx { .. } => matches!(type_, Type::Mx | Type::Any),
+            QueryRecord::Txt(_) => matches!(type_, Type::Txt | Type::Any),
+            QueryRecord::Srv { .. } => matches!(type_, Type::Srv | Type::Any),
+        }
+    }
+}
+
+/// A domain name of a query result, in its uncompressed wire format.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Name {
+    raw: Vec<u8, DNS_MAX_NAME_SIZE>,
+}
+
+impl Name {
+    /// Return the wire format of the name, such as `b"\x09rust-lang\x03org\x00"`.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /// Return an iterator over the labels of the name.
+    pub fn labels(&self) -> impl Iterator<Item = &[u8]> {
+        string_iter(&self.raw).take_while(|label| !label.is_empty())
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, label) in self.labels().enumerate() {
+            if i != 0 {
+                write!(f, ".")?;
+            }
+            for &c in label {
+                write!(f, "{}", c as char)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The character-strings of a `TXT` record.
+///
+/// The character-strings are kept up to `DNS_MAX_NAME_SIZE` octets; the first one that does
+/// not fit and those that follow it are dropped.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Txt {
+    raw: Vec<u8, DNS_MAX_NAME_SIZE>,
+}
+
+impl Txt {
+    /// Return the wire format of the record data, where each character-string is prefixed
+    /// with its length.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /// Return an iterator over the character-strings of the record.
+    pub fn strings(&self) -> impl Iterator<Item = &[u8]> {
+        string_iter(&self.raw)
+    }
 }
 
 /// A handle to an in-progress DNS query.
@@ -276,6 +368,39 @@ impl<'a> Socket<'a> {
         self.start_query_raw(cx, &raw_name, query_type, mdns)
     }
 
+    /// Start a reverse query of the name of `addr`.
+    ///
+    /// The `PTR` record of the name under `in-addr.arpa` or `ip6.arpa` is queried, and the
+    /// result is available with [`get_query_records`](Self::get_query_records).
+    pub fn start_reverse_query(
+        &mut self,
+        cx: &mut Context,
+        addr: IpAddress,
+    ) -> Result<QueryHandle, StartQueryError> {
+        let mut name: String<DNS_MAX_NAME_SIZE> = String::new();
+        match addr {
+            #[cfg(feature = "proto-ipv4")]
+            IpAddress::Ipv4(addr) => {
+                for octet in addr.as_bytes().iter().rev() {
+                    write!(name, "{octet}.").map_err(|_| StartQueryError::NameTooLong)?;
+                }
+                name.push_str("in-addr.arpa")
+                    .map_err(|_| StartQueryError::NameTooLong)?;
+            }
+            #[cfg(feature = "proto-ipv6")]
+            IpAddress::Ipv6(addr) => {
+                for octet in addr.as_bytes().iter().rev() {
+                    write!(name, "{:x}.{:x}.", octet & 0xf, octet >> 4)
+                        .map_err(|_| StartQueryError::NameTooLong)?;
+                }
+                name.push_str("ip6.arpa")
+                    .map_err(|_| StartQueryError::NameTooLong)?;
+            }
+        }
+
+        self.start_query(cx, &name, Type::Ptr)
+    }
+
     /// Start a query with a raw (wire-format) DNS name.
     /// `b"\x09rust-lang\x03org\x00"`
     ///
@@ -309,7 +434,9 @@ impl<'a> Socket<'a> {
 
     /// Get the result of a query.
     ///
-    /// If the query is completed, the query slot is automatically freed.
+    /// If the query is completed, the query slot is automatically freed. Only the addresses
+    /// of the result are returned, use [`get_query_records`](Self::get_query_records) for
+    /// the other record types.
     ///
     /// # Panics
     /// Panics if the QueryHandle corresponds to a free slot.
@@ -317,6 +444,26 @@ impl<'a> Socket<'a> {
         &mut self,
         handle: QueryHandle,
     ) -> Result<Vec<IpAddress, DNS_MAX_RESULT_COUNT>, GetQueryResultError> {
+        let records = self.get_query_records(handle)?;
+        Ok(records
+            .iter()
+            .filter_map(|record| match record {
+                QueryRecord::Address(addr) => Some(*addr),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Get the records of the result of a query.
+    ///
+    /// If the query is completed, the query slot is automatically freed.
+    ///
+    /// # Panics
+    /// Panics if the QueryHandle corresponds to a free slot.
+    pub fn get_query_records(
+        &mut self,
+        handle: QueryHandle,
+    ) -> Result<Vec<QueryRecord, DNS_MAX_RESULT_COUNT>, GetQueryResultError> {
         let slot = &mut self.queries[handle.0];
         let q = slot.as_mut().unwrap();
         match &mut q.state {
@@ -324,7 +471,7 @@ impl<'a> Socket<'a> {
             State::Pending(_) => Err(GetQueryResultError::Pending),
             // Query is done
             State::Completed(q) => {
-                let res = q.addresses.clone();
+                let res = core::mem::take(&mut q.records);
                 *slot = None; // Free up the slot for recycling.
                 Ok(res)
             }
@@ -452,7 +599,7 @@ impl<'a> Socket<'a> {
                     }
                 }
 
-                let mut addresses = Vec::new();
+                let mut records = Vec::new();
 
                 for _ in 0..p.answer_record_count() {
                     let (payload2, r) = match Record::parse(payload) {
@@ -476,20 +623,16 @@ impl<'a> Socket<'a> {
                         }
                     }
 
-                    match r.data {
+                    let record = match r.data {
                         #[cfg(feature = "proto-ipv4")]
                         RecordData::A(addr) => {
                             net_trace!("A: {:?}", addr);
-                            if addresses.push(addr.into()).is_err() {
-                                net_trace!("too many addresses in response, ignoring {:?}", addr);
-                            }
+                            QueryRecord::Address(addr.into())
                         }
                         #[cfg(feature = "proto-ipv6")]
                         RecordData::Aaaa(addr) => {
                             net_trace!("AAAA: {:?}", addr);
-                            if addresses.push(addr.into()).is_err() {
-                                net_trace!("too many addresses in response, ignoring {:?}", addr);
-                            }
+                            QueryRecord::Address(addr.into())
                         }
                         RecordData::Cname(name) => {
                             net_trace!("CNAME: {:?}", name);
@@ -505,17 +648,78 @@ impl<'a> Socket<'a> {
                                 net_trace!("dns answer cname malformed");
                                 return;
                             }
+                            continue;
+                        }
+                        RecordData::Ptr(name) => {
+                            net_trace!("PTR: {:?}", name);
+                            match parse_name(&p, name) {
+                                Ok(name) => QueryRecord::Ptr(name),
+                                Err(_) => {
+                                    net_trace!("dns answer ptr malformed");
+                                    continue;
+                                }
+                            }
+                        }
+                        RecordData::Mx {
+                            preference,
+                            exchange,
+                        } => {
+                            net_trace!("MX: {} {:?}", preference, exchange);
+                            match parse_name(&p, exchange) {
+                                Ok(exchange) => QueryRecord::Mx {
+                                    preference,
+                                    exchange,
+                                },
+                                Err(_) => {
+                                    net_trace!("dns answer mx malformed");
+                                    continue;
+                                }
+                            }
+                        }
+                        RecordData::Txt(data) => {
+                            net_trace!("TXT: {:?}", data);
+                            QueryRecord::Txt(parse_txt(data))
+                        }
+                        RecordData::Srv {
+                            priority,
+                            weight,
+                            port,
+                            target,
+                        } => {
+                            net_trace!("SRV: {} {} {} {:?}", priority, weight, port, target);
+                            match parse_name(&p, target) {
+                                Ok(target) => QueryRecord::Srv {
+                                    priority,
+                                    weight,
+                                    port,
+                                    target,
+                                },
+                                Err(_) => {
+                                    net_trace!("dns answer srv malformed");
+                                    continue;
+                                }
+                            }
                         }
                         RecordData::Other(type_, data) => {
-                            net_trace!("unknown: {:?} {:?}", type_, data)
+                            net_trace!("unknown: {:?} {:?}", type_, data);
+                            continue;
                         }
+                    };
+
+                    if !record.answers(pq.type_) {
+                        net_trace!("answer type mismatch: {:?}", record);
+                        continue;
+                    }
+
+                    if let Err(record) = records.push(record) {
+                        net_trace!("too many records in response, ignoring {:?}", record);
                     }
                 }
 
-                q.set_state(if addresses.is_empty() {
+                q.set_state(if records.is_empty() {
                     State::Failure
                 } else {
-                    State::Completed(CompletedQuery { addresses })
+                    State::Completed(CompletedQuery { records })
                 });
 
                 // If we get here, packet matched the current query, stop processing.
@@ -699,3 +903,260 @@ fn copy_name<'a, const N: usize>(
 
     Ok(())
 }
+
+/// Decompress a name of a record of `packet`.
+fn parse_name(packet: &Packet<&[u8]>, name: &[u8]) -> Result<Name, wire::Error> {
+    let mut res = Name::default();
+    copy_name(&mut res.raw, packet.parse_name(name))?;
+    Ok(res)
+}
+
+/// Copy the character-strings of a `TXT` record, until one does not fit.
+fn parse_txt(data: &[u8]) -> Txt {
+    let mut res = Txt::default();
+    for string in string_iter(data) {
+        if res.raw.capacity() - res.raw.len() < 1 + string.len() {
+            break;
+        }
+        res.raw.push(string.len() as u8).unwrap();
+        res.raw.extend_from_slice(string).unwrap();
+    }
+    res
+}
+
+/// Return an iterator over a sequence of strings, each prefixed with its length.
+///
+/// The iteration stops at the first truncated string.
+fn string_iter(mut data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    core::iter::from_fn(move || {
+        let len = *data.first()? as usize;
+        let string = data.get(1..1 + len)?;
+        data = &data[1 + len..];
+        Some(string)
+    })
+}
+
+#[cfg(feature = "proto-ipv4")] // tests assume ipv4
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::phy::Medium;
+    use crate::tests::setup;
+    use crate::wire::Ipv4Address;
+    use rstest::*;
+
+    const SERVER: IpAddress = IpAddress::Ipv4(Ipv4Address([192, 168, 1, 254]));
+
+    fn socket(medium: Medium) -> (Socket<'static>, Context) {
+        let (iface, _, _) = setup(medium);
+        (Socket::new(&[SERVER], vec![]), iface.inner)
+    }
+
+    /// Dispatch the pending query, and return its UDP source port and payload.
+    fn dispatch(s: &mut Socket, cx: &mut Context) -> (u16, std::vec::Vec<u8>) {
+        let mut sent = None;
+        s.dispatch(cx, |_, (ip_repr, udp_repr, payload)| {
+            assert_eq!(ip_repr.dst_addr(), SERVER);
+            assert_eq!(udp_repr.dst_port, DNS_PORT);
+            sent = Some((udp_repr.src_port, payload.to_vec()));
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+        sent.unwrap()
+    }
+
+    /// Answer `query` with a single answer record, whose name is the question name.
+    fn respond(
+        s: &mut Socket,
+        cx: &mut Context,
+        sent: (u16, std::vec::Vec<u8>),
+        type_: Type,
+        data: &[u8],
+    ) {
+        respond_records(s, cx, sent, &[(&[0xc0, 0x0c], type_, data)]);
+    }
+
+    /// Answer `query` with the given answer records.
+    fn respond_records(
+        s: &mut Socket,
+        cx: &mut Context,
+        (port, query): (u16, std::vec::Vec<u8>),
+        records: &[(&[u8], Type, &[u8])],
+    ) {
+        let mut payload = query;
+        let mut packet = Packet::new_unchecked(&mut payload);
+        packet.set_flags(Flags::RESPONSE | Flags::RECURSION_DESIRED);
+        packet.set_answer_record_count(records.len() as u16);
+
+        for (name, type_, data) in records {
+            payload.extend_from_slice(name);
+            payload.extend_from_slice(&u16::from(*type_).to_be_bytes());
+            payload.extend_from_slice(&[0x00, 0x01, 0x00, 0x00, 0x00, 0x3c]);
+            payload.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            payload.extend_from_slice(data);
+        }
+
+        let src_addr = cx.get_source_address(&SERVER).unwrap();
+        let ip_repr = IpRepr::new(SERVER, src_addr, IpProtocol::Udp, 0, 64);
+        let udp_repr = UdpRepr {
+            src_port: DNS_PORT,
+            dst_port: port,
+        };
+        s.process(cx, &ip_repr, &udp_repr, &payload);
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_reverse_query(#[case] medium: Medium) {
+        let (mut s, mut cx) = socket(medium);
+
+        let handle = s
+            .start_reverse_query(&mut cx, IpAddress::v4(192, 168, 1, 42))
+            .unwrap();
+        let sent = dispatch(&mut s, &mut cx);
+        let (_, question) = Question::parse(&sent.1[12..]).unwrap();
+        assert_eq!(
+            question.name,
+            b"\x0242\x011\x03168\x03192\x07in-addr\x04arpa\x00"
+        );
+        assert_eq!(question.type_, Type::Ptr);
+
+        respond(&mut s, &mut cx, sent, Type::Ptr, b"\x04host\x07example\x00");
+        let records = s.get_query_records(handle).unwrap();
+        let QueryRecord::Ptr(name) = &records[0] else {
+            panic!("Expected a PTR record");
+        };
+        assert_eq!(name.to_string(), "host.example");
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(all(feature = "medium-ethernet", feature = "proto-ipv6"))]
+    fn test_reverse_query_ipv6(#[case] medium: Medium) {
+        let (mut s, mut cx) = socket(medium);
+
+        s.start_reverse_query(&mut cx, IpAddress::v6(0xfe80, 0, 0, 0, 0, 0, 0, 1))
+            .unwrap();
+        let (_, query) = dispatch(&mut s, &mut cx);
+        let (_, question) = Question::parse(&query[12..]).unwrap();
+        assert_eq!(question.name.len(), 32 * 2 + 4 + 5 + 1);
+        assert!(question.name.starts_with(b"\x011\x010\x010\x010\x010"));
+        assert!(question
+            .name
+            .ends_with(b"\x010\x018\x01e\x01f\x03ip6\x04arpa\x00"));
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_srv_query(#[case] medium: Medium) {
+        let (mut s, mut cx) = socket(medium);
+
+        let handle = s
+            .start_query(&mut cx, "_http._tcp.example", Type::Srv)
+            .unwrap();
+        let sent = dispatch(&mut s, &mut cx);
+
+        // The target is compressed, pointing to the "example" label of the question.
+        respond(
+            &mut s,
+            &mut cx,
+            sent,
+            Type::Srv,
+            &[
+                0x00, 0x0a, 0x00, 0x05, 0x1f, 0x90, 0x03, b'w', b'w', b'w', 0xc0, 0x17,
+            ],
+        );
+        let records = s.get_query_records(handle).unwrap();
+        let QueryRecord::Srv {
+            priority,
+            weight,
+            port,
+            target,
+        } = &records[0]
+        else {
+            panic!("Expected an SRV record");
+        };
+        assert_eq!((*priority, *weight, *port), (10, 5, 8080));
+        assert_eq!(target.to_string(), "www.example");
+        assert_eq!(target.as_bytes(), b"\x03www\x07example\x00");
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_txt_query(#[case] medium: Medium) {
+        let (mut s, mut cx) = socket(medium);
+
+        let handle = s.start_query(&mut cx, "example", Type::Txt).unwrap();
+        let sent = dispatch(&mut s, &mut cx);
+        respond(&mut s, &mut cx, sent, Type::Txt, b"\x03k=v\x03abc");
+
+        let records = s.get_query_records(handle).unwrap();
+        let QueryRecord::Txt(txt) = &records[0] else {
+            panic!("Expected a TXT record");
+        };
+        let strings: std::vec::Vec<_> = txt.strings().collect();
+        assert_eq!(strings, [&b"k=v"[..], &b"abc"[..]]);
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_query_result_addresses_only(#[case] medium: Medium) {
+        let (mut s, mut cx) = socket(medium);
+
+        let handle = s.start_query(&mut cx, "example", Type::Txt).unwrap();
+        let sent = dispatch(&mut s, &mut cx);
+        respond(&mut s, &mut cx, sent, Type::Txt, b"\x03k=v");
+
+        assert_eq!(s.get_query_result(handle), Ok(Vec::new()));
+    }
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(all(feature = "medium-ethernet", feature = "proto-ipv4"))]
+    fn test_query_answer_type(#[case] medium: Medium) {
+        let (mut s, mut cx) = socket(medium);
+
+        // The CNAME record points to "alias.example", whose name starts at offset 37: after
+        // the header, the question for "example" and the fixed fields of the CNAME record.
+        let cname: (&[u8], Type, &[u8]) = (&[0xc0, 0x0c], Type::Cname, b"\x05alias\xc0\x0c");
+        let alias: &[u8] = &[0xc0, 37];
+
+        // An address query is answered by the address of the canonical name.
+        let handle = s.start_query(&mut cx, "example", Type::A).unwrap();
+        let sent = dispatch(&mut s, &mut cx);
+        respond_records(
+            &mut s,
+            &mut cx,
+            sent,
+            &[cname, (alias, Type::A, &[192, 168, 1, 1])],
+        );
+        assert_eq!(
+            s.get_query_result(handle),
+            Ok(Vec::from_slice(&[IpAddress::v4(192, 168, 1, 1)]).unwrap())
+        );
+
+        // An address query fails without an address, even if the canonical name has other
+        // records.
+        let handle = s.start_query(&mut cx, "example", Type::A).unwrap();
+        let sent = dispatch(&mut s, &mut cx);
+        respond_records(
+            &mut s,
+            &mut cx,
+            sent,
+            &[cname, (alias, Type::Txt, b"\x03k=v")],
+        );
+        assert_eq!(s.get_query_result(handle), Err(GetQueryResultError::Failed));
+
+        // Other queries fail without a record of their own type.
+        let handle = s.start_query(&mut cx, "example", Type::Txt).unwrap();
+        let sent = dispatch(&mut s, &mut cx);
+        respond(&mut s, &mut cx, sent, Type::A, &[192, 168, 1, 1]);
+        assert_eq!(
+            s.get_query_records(handle),
+            Err(GetQueryResultError::Failed)
+        );
+    }
+}
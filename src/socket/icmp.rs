@@ -430,6 +430,7 @@ impl<'a> Socket<'a> {
             (
                 &Endpoint::Udp(endpoint),
                 &Icmpv4Repr::DstUnreachable { data, header, .. }
+                | &Icmpv4Repr::FragRequired { data, header, .. }
                 | &Icmpv4Repr::TimeExceeded { data, header, .. },
             ) if endpoint.addr.is_none() || endpoint.addr == Some(ip_repr.dst_addr.into()) => {
                 let packet = UdpPacket::new_unchecked(data);
@@ -0,0 +1,1701 @@
+use core::iter;
+#[cfg(feature = "async")]
+use core::task::Waker;
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+use crate::socket::{Context, PollAt};
+use crate::time::{Duration, Instant};
+use crate::wire::dns::{Flags, Opcode, Packet, Question, Record, RecordData, Type};
+#[cfg(feature = "proto-ipv4")]
+use crate::wire::Ipv4Address;
+#[cfg(feature = "proto-ipv6")]
+use crate::wire::Ipv6Address;
+use crate::wire::{IpAddress, IpEndpoint, IpProtocol, IpRepr, UdpRepr};
+
+#[cfg(feature = "async")]
+use super::WakerRegistration;
+
+const MDNS_PORT: u16 = 5353;
+
+#[cfg(feature = "proto-ipv4")]
+const IPV4_GROUP: IpAddress = IpAddress::Ipv4(Ipv4Address([224, 0, 0, 251]));
+#[cfg(feature = "proto-ipv6")]
+const IPV6_GROUP: IpAddress = IpAddress::Ipv6(Ipv6Address([
+    0xff, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfb,
+]));
+
+/// The multicast groups the responder joins, and sends its messages to.
+pub(crate) const GROUPS: &[IpAddress] = &[
+    #[cfg(feature = "proto-ipv4")]
+    IPV4_GROUP,
+    #[cfg(feature = "proto-ipv6")]
+    IPV6_GROUP,
+];
+pub(crate) const GROUP_COUNT: usize = GROUPS.len();
+
+/// Maximum random delay before the first probe, and interval between the probes.
+const PROBE_WAIT: Duration = Duration::from_millis(250);
+const PROBE_COUNT: u8 = 3;
+/// How long probing is deferred after losing a simultaneous probe tiebreak (RFC 6762 § 8.2).
+const PROBE_DEFER: Duration = Duration::from_millis(1_000);
+const ANNOUNCE_COUNT: u8 = 2;
+const ANNOUNCE_INTERVAL: Duration = Duration::from_millis(1_000);
+/// How long probing waits for the interface to get an address.
+const ADDRESS_WAIT: Duration = Duration::from_millis(1_000);
+/// Bounds of the random delay of the responses that contain shared records (RFC 6762 § 6).
+const RESPONSE_DELAY_MIN: u64 = 20;
+const RESPONSE_DELAY_MAX: u64 = 120;
+
+/// TTL of the records that contain a host name, and of the other records (RFC 6762 § 10).
+const HOST_NAME_TTL: u32 = 120;
+const OTHER_TTL: u32 = 4500;
+/// Maximum TTL of the records sent in replies to legacy unicast queries (RFC 6762 § 6.7).
+const LEGACY_TTL: u32 = 10;
+
+const CLASS_IN: u16 = 1;
+/// Bit of the class of a question that asks for a unicast response (RFC 6762 § 5.4).
+const UNICAST_RESPONSE: u16 = 0x8000;
+/// Bit of the class of a unique record that tells to flush the other records from the caches
+/// (RFC 6762 § 10.2).
+const CACHE_FLUSH: u16 = 0x8000;
+
+/// Maximum length of the messages sent, which fits the minimum IPv6 MTU.
+const MAX_PAYLOAD_LEN: usize = 1232;
+const HEADER_LEN: usize = 12;
+const MAX_LABEL_LEN: usize = 63;
+
+/// Name of the service type enumeration of DNS-SD (RFC 6763 § 9).
+const SERVICES_NAME: &str = "_services._dns-sd._udp";
+/// TXT record of the services without any key/value pairs (RFC 6763 § 6.1).
+const EMPTY_TXT: &[&str] = &[""];
+
+/// Maximum number of services advertised by the responder; the other services are ignored.
+pub const MAX_SERVICE_COUNT: usize = 4;
+// The record sets of the services must fit in `RecordSets`.
+const _: () = assert!(2 + 4 * MAX_SERVICE_COUNT <= 32);
+
+/// A service instance advertised with DNS-SD (RFC 6763).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Service<'a> {
+    /// Name of the instance, a single label that may contain spaces, e.g. `Office Printer`.
+    pub instance: &'a str,
+    /// Type of the service and its transport protocol, e.g. `_ipp._tcp`.
+    pub service: &'a str,
+    /// Port of the service on this host.
+    pub port: u16,
+    /// Key/value pairs of the TXT record, e.g. `["txtvers=1", "rp=ipp/print"]`.
+    pub txt: &'a [&'a str],
+}
+
+impl<'a> Service<'a> {
+    /// Return the data of the TXT record.
+    fn txt_data(&self) -> impl Iterator<Item = u8> + 'a {
+        let txt = if self.txt.is_empty() {
+            EMPTY_TXT
+        } else {
+            self.txt
+        };
+        txt.iter().flat_map(|string| {
+            let string = &string.as_bytes()[..string.len().min(255)];
+            iter::once(string.len() as u8).chain(string.iter().copied())
+        })
+    }
+}
+
+/// An event of the responder, returned by [`Socket::poll`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Event {
+    /// The host name and the instance names have been probed and announced.
+    Announced,
+    /// Another host on the link uses the host name.
+    HostnameConflict,
+    /// Another host on the link uses the instance name of the service at the given index.
+    ServiceConflict(usize),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum State {
+    /// Checking that no other host uses the names, before sending the given probe.
+    Probing(u8),
+    /// Announcing the records, before sending the given announcement.
+    Announcing(u8),
+    /// Answering the queries.
+    Running,
+    /// A name is used by another host, and the responder is stopped.
+    Conflict,
+}
+
+/// A name owned by the responder.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum Name {
+    /// `<hostname>.local`
+    Host,
+    /// `<service>.local`
+    Service(usize),
+    /// `<instance>.<service>.local`
+    Instance(usize),
+    /// `_services._dns-sd._udp.local`
+    Enumeration,
+}
+
+/// A set of records of the responder, with the same name and type.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum RecordSet {
+    /// The IPv4 addresses of the host.
+    #[cfg(feature = "proto-ipv4")]
+    A,
+    /// The IPv6 addresses of the host.
+    #[cfg(feature = "proto-ipv6")]
+    Aaaa,
+    /// The instance of the service at the given index.
+    Ptr(usize),
+    Srv(usize),
+    Txt(usize),
+    /// The type of the service at the given index, in the service type enumeration.
+    Enumeration(usize),
+}
+
+impl RecordSet {
+    fn name(self) -> Name {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            RecordSet::A => Name::Host,
+            #[cfg(feature = "proto-ipv6")]
+            RecordSet::Aaaa => Name::Host,
+            RecordSet::Ptr(index) => Name::Service(index),
+            RecordSet::Srv(index) | RecordSet::Txt(index) => Name::Instance(index),
+            RecordSet::Enumeration(_) => Name::Enumeration,
+        }
+    }
+
+    fn type_(self) -> Type {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            RecordSet::A => Type::A,
+            #[cfg(feature = "proto-ipv6")]
+            RecordSet::Aaaa => Type::Aaaa,
+            RecordSet::Ptr(_) | RecordSet::Enumeration(_) => Type::Ptr,
+            RecordSet::Srv(_) => Type::Srv,
+            RecordSet::Txt(_) => Type::Txt,
+        }
+    }
+
+    /// Whether the records are unique to this host, as opposed to shared between the hosts.
+    fn is_unique(self) -> bool {
+        !matches!(self, RecordSet::Ptr(_) | RecordSet::Enumeration(_))
+    }
+
+    fn ttl(self) -> u32 {
+        match self {
+            RecordSet::Ptr(_) | RecordSet::Txt(_) | RecordSet::Enumeration(_) => OTHER_TTL,
+            _ => HOST_NAME_TTL,
+        }
+    }
+
+    /// Whether the records of `other` are added to the responses that contain these records
+    /// (RFC 6763 § 12).
+    fn implies(self, other: RecordSet) -> bool {
+        match (self, other) {
+            (RecordSet::Ptr(index), RecordSet::Srv(other) | RecordSet::Txt(other)) => {
+                index == other
+            }
+            (RecordSet::Ptr(_) | RecordSet::Srv(_), other) => other.name() == Name::Host,
+            _ => false,
+        }
+    }
+}
+
+impl RecordSet {
+    fn bit(self) -> u32 {
+        let index = match self {
+            #[cfg(feature = "proto-ipv4")]
+            RecordSet::A => 0,
+            #[cfg(feature = "proto-ipv6")]
+            RecordSet::Aaaa => 1,
+            RecordSet::Ptr(index) => 2 + 4 * index,
+            RecordSet::Srv(index) => 3 + 4 * index,
+            RecordSet::Txt(index) => 4 + 4 * index,
+            RecordSet::Enumeration(index) => 5 + 4 * index,
+        };
+        1 << index
+    }
+}
+
+/// A bitmap of record sets.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct RecordSets(u32);
+
+impl RecordSets {
+    fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    fn contains(self, set: RecordSet) -> bool {
+        self.0 & set.bit() != 0
+    }
+
+    fn insert(&mut self, set: RecordSet) {
+        self.0 |= set.bit()
+    }
+
+    fn remove(&mut self, set: RecordSet) {
+        self.0 &= !set.bit()
+    }
+}
+
+/// Records waiting to be multicast in a response.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Pending {
+    answers: RecordSets,
+    at: Instant,
+}
+
+/// A reply to a legacy unicast query, which is not sent from the mDNS port.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct LegacyReply {
+    endpoint: IpEndpoint,
+    transaction_id: u16,
+    name: Name,
+    type_: Type,
+    answers: RecordSets,
+}
+
+/// Multicast DNS responder socket.
+///
+/// The socket claims a host name, and the instance names of the given services, in the
+/// `.local` domain as described in RFC 6762. It probes the names, announces its records, and
+/// then answers the `A`, `AAAA`, `PTR`, `SRV` and `TXT` queries for them. The services can be
+/// browsed with DNS-SD (RFC 6763), including the service type enumeration.
+///
+/// The interface listens to the mDNS multicast groups while the socket exists, and joins them
+/// with IGMP and MLD reports if built with `proto-igmp`. The records are not announced again
+/// when the addresses of the interface change; call [`reset`] then.
+///
+/// [`reset`]: Socket::reset
+#[derive(Debug)]
+pub struct Socket<'a> {
+    hostname: &'a str,
+    services: &'a [Service<'a>],
+    state: State,
+    /// When the next probe or announcement is sent, or `None` if probing has yet to start.
+    step_at: Option<Instant>,
+    /// Number of groups the next probe or announcement has already been sent to.
+    step_groups: usize,
+    /// Responses waiting to be sent, to each of the groups.
+    pending: [Pending; GROUP_COUNT],
+    legacy: Option<LegacyReply>,
+    event: Option<Event>,
+    #[cfg(feature = "async")]
+    waker: WakerRegistration,
+}
+
+impl<'a> Socket<'a> {
+    /// Create an mDNS responder socket for the given host name, without the `.local` suffix,
+    /// advertising the given services.
+    pub fn new(hostname: &'a str, services: &'a [Service<'a>]) -> Self {
+        let mut socket = Socket {
+            hostname,
+            services: &[],
+            state: State::Probing(0),
+            step_at: None,
+            step_groups: 0,
+            pending: core::array::from_fn(|_| Pending {
+                answers: RecordSets::default(),
+                at: Instant::ZERO,
+            }),
+            legacy: None,
+            event: None,
+            #[cfg(feature = "async")]
+            waker: WakerRegistration::new(),
+        };
+        socket.set_services(services);
+        socket
+    }
+
+    /// Return the host name.
+    pub fn hostname(&self) -> &'a str {
+        self.hostname
+    }
+
+    /// Set the host name, and probe the names again.
+    pub fn set_hostname(&mut self, hostname: &'a str) {
+        self.hostname = hostname;
+        self.reset();
+    }
+
+    /// Return the advertised services.
+    pub fn services(&self) -> &'a [Service<'a>] {
+        self.services
+    }
+
+    /// Set the advertised services, and probe the names again.
+    ///
+    /// Only the first [`MAX_SERVICE_COUNT`] services are advertised.
+    pub fn set_services(&mut self, services: &'a [Service<'a>]) {
+        if services.len() > MAX_SERVICE_COUNT {
+            net_debug!("mdns: too many services, advertising the first ones only");
+        }
+        self.services = &services[..services.len().min(MAX_SERVICE_COUNT)];
+        self.reset();
+    }
+
+    /// Probe the names, and announce the records again.
+    ///
+    /// This must be called when the addresses of the interface change.
+    pub fn reset(&mut self) {
+        net_trace!("mdns: probing");
+        self.state = State::Probing(0);
+        self.step_at = None;
+        self.step_groups = 0;
+        for pending in self.pending.iter_mut() {
+            pending.answers = RecordSets::default();
+        }
+        self.legacy = None;
+    }
+
+    /// Query the socket for events.
+    pub fn poll(&mut self) -> Option<Event> {
+        self.event.take()
+    }
+
+    /// Register a waker.
+    ///
+    /// The waker is woken on events that might affect the return value of `poll`.
+    ///
+    /// Notes:
+    ///
+    /// - Only one waker can be registered at a time. If another waker was previously registered,
+    ///   it is overwritten and will no longer be woken.
+    /// - The Waker is woken only once. Once woken, you must register it again to receive more wakes.
+    #[cfg(feature = "async")]
+    pub fn register_waker(&mut self, waker: &Waker) {
+        self.waker.register(waker)
+    }
+
+    fn set_event(&mut self, event: Event) {
+        self.event = Some(event);
+        #[cfg(feature = "async")]
+        self.waker.wake();
+    }
+
+    /// Return the labels of a name, without the root label.
+    fn labels(&self, name: Name) -> impl Iterator<Item = &'a [u8]> {
+        let (first, rest) = match name {
+            Name::Host => (Some(self.hostname), ""),
+            Name::Service(index) => (None, self.services[index].service),
+            Name::Instance(index) => (
+                Some(self.services[index].instance),
+                self.services[index].service,
+            ),
+            Name::Enumeration => (None, SERVICES_NAME),
+        };
+        first
+            .into_iter()
+            .chain(rest.split('.').filter(|label| !label.is_empty()))
+            .chain(iter::once("local"))
+            .map(|label| &label.as_bytes()[..label.len().min(MAX_LABEL_LEN)])
+    }
+
+    /// Return the unique names, which are probed.
+    fn unique_names(&self) -> impl Iterator<Item = Name> {
+        iter::once(Name::Host).chain((0..self.services.len()).map(Name::Instance))
+    }
+
+    /// Return the sets of records of the responder.
+    fn record_sets(&self) -> impl Iterator<Item = RecordSet> + 'a {
+        let services = self.services;
+        let host = [
+            #[cfg(feature = "proto-ipv4")]
+            RecordSet::A,
+            #[cfg(feature = "proto-ipv6")]
+            RecordSet::Aaaa,
+        ];
+        host.into_iter()
+            .chain((0..services.len()).flat_map(move |index| {
+                // The service types are enumerated once.
+                let first = !services[..index]
+                    .iter()
+                    .any(|other| other.service.eq_ignore_ascii_case(services[index].service));
+                [
+                    Some(RecordSet::Ptr(index)),
+                    Some(RecordSet::Srv(index)),
+                    Some(RecordSet::Txt(index)),
+                    first.then_some(RecordSet::Enumeration(index)),
+                ]
+                .into_iter()
+                .flatten()
+            }))
+    }
+
+    /// Whether the set contains records, which is not the case of the address records of
+    /// the versions of IP the interface has no address for.
+    fn has_records(cx: &Context, set: RecordSet) -> bool {
+        match set {
+            #[cfg(feature = "proto-ipv4")]
+            RecordSet::A => ipv4_addrs(cx).next().is_some(),
+            #[cfg(feature = "proto-ipv6")]
+            RecordSet::Aaaa => ipv6_addrs(cx).next().is_some(),
+            _ => true,
+        }
+    }
+
+    pub(crate) fn accepts(&self, udp_repr: &UdpRepr) -> bool {
+        udp_repr.dst_port == MDNS_PORT
+    }
+
+    pub(crate) fn process(
+        &mut self,
+        cx: &mut Context,
+        ip_repr: &IpRepr,
+        udp_repr: &UdpRepr,
+        payload: &[u8],
+    ) {
+        debug_assert!(self.accepts(udp_repr));
+
+        let packet = match Packet::new_checked(payload) {
+            Ok(packet) => packet,
+            Err(_) => {
+                net_trace!("mdns: packet malformed");
+                return;
+            }
+        };
+        if packet.opcode() != Opcode::Query || self.state == State::Conflict {
+            return;
+        }
+
+        if !packet.flags().contains(Flags::RESPONSE) {
+            self.process_query(cx, ip_repr, udp_repr, &packet);
+        } else if udp_repr.src_port == MDNS_PORT {
+            self.process_response(cx, &packet);
+        } else {
+            net_trace!("mdns: ignoring response from port {}", udp_repr.src_port);
+        }
+    }
+
+    fn process_query(
+        &mut self,
+        cx: &mut Context,
+        ip_repr: &IpRepr,
+        udp_repr: &UdpRepr,
+        packet: &Packet<&[u8]>,
+    ) {
+        let mut answers = RecordSets::default();
+        let mut question = None;
+
+        let mut rest = packet.payload();
+        for _ in 0..packet.question_count() {
+            let Ok((next, q)) = Question::parse(rest) else {
+                net_trace!("mdns: question malformed");
+                return;
+            };
+            rest = next;
+
+            for set in self.record_sets() {
+                if (q.type_ == Type::Any || q.type_ == set.type_())
+                    && Self::has_records(cx, set)
+                    && eq_name(packet, q.name, self.labels(set.name()))
+                {
+                    question.get_or_insert((set.name(), q.type_));
+                    answers.insert(set);
+                }
+            }
+        }
+
+        // The probes of other hosts carry their records in the authority section, which is
+        // used to break the tie when both hosts probe for the same names (RFC 6762 § 8.2).
+        let mut theirs: [Option<(u16, &[u8])>; 1 + MAX_SERVICE_COUNT] = Default::default();
+        let record_count = packet.answer_record_count() + packet.authority_record_count();
+        for index in 0..record_count {
+            let Ok((next, record, type_, data)) = parse_record(rest) else {
+                break;
+            };
+            rest = next;
+
+            if index < packet.answer_record_count() {
+                for set in self.record_sets() {
+                    if self.is_known_answer(packet, set, &record) {
+                        answers.remove(set);
+                    }
+                }
+            } else if let Some(slot) = self
+                .unique_names()
+                .position(|name| eq_name(packet, record.name, self.labels(name)))
+            {
+                if theirs[slot].map_or(true, |lowest| (type_, data) < lowest) {
+                    theirs[slot] = Some((type_, data));
+                }
+            }
+        }
+
+        if let State::Probing(_) = self.state {
+            let lost = self
+                .unique_names()
+                .zip(theirs)
+                .any(|(name, theirs)| theirs.is_some_and(|theirs| self.loses(cx, name, theirs)));
+            if lost {
+                net_debug!("mdns: lost a simultaneous probe tiebreak, deferring probing");
+                self.reset();
+                self.step_at = Some(cx.now() + PROBE_DEFER);
+            }
+            return;
+        }
+
+        if answers.is_empty() {
+            return;
+        }
+
+        if udp_repr.src_port != MDNS_PORT {
+            // Legacy unicast queries are answered with the question, and short TTLs.
+            let Some((name, type_)) = question.filter(|_| packet.question_count() == 1) else {
+                return;
+            };
+            self.legacy = Some(LegacyReply {
+                endpoint: IpEndpoint::new(ip_repr.src_addr(), udp_repr.src_port),
+                transaction_id: packet.transaction_id(),
+                name,
+                type_,
+                answers,
+            });
+            return;
+        }
+
+        let Some(group) = GROUPS
+            .iter()
+            .position(|group| group.version() == ip_repr.version())
+        else {
+            return;
+        };
+
+        // Only the answers made of unique records are sent without delay (RFC 6762 § 6).
+        let delay = if self
+            .record_sets()
+            .all(|set| set.is_unique() || !answers.contains(set))
+        {
+            Duration::ZERO
+        } else {
+            let range = RESPONSE_DELAY_MAX - RESPONSE_DELAY_MIN + 1;
+            Duration::from_millis(RESPONSE_DELAY_MIN + cx.rand().rand_u32() as u64 % range)
+        };
+
+        let pending = &mut self.pending[group];
+        let at = cx.now() + delay;
+        if pending.answers.is_empty() || at < pending.at {
+            pending.at = at;
+        }
+        pending.answers.0 |= answers.0;
+    }
+
+    /// Whether `record`, from the answers of a query, is a shared record of `set` the querier
+    /// already knows of (RFC 6762 § 7.1).
+    fn is_known_answer(&self, packet: &Packet<&[u8]>, set: RecordSet, record: &Record) -> bool {
+        let target = match set {
+            RecordSet::Ptr(index) => Name::Instance(index),
+            RecordSet::Enumeration(index) => Name::Service(index),
+            _ => return false,
+        };
+        match record.data {
+            RecordData::Ptr(data) => {
+                record.ttl >= set.ttl() / 2
+                    && eq_name(packet, record.name, self.labels(set.name()))
+                    && eq_name(packet, data, self.labels(target))
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the probe of another host for `name`, whose lowest record has the given type and
+    /// data, wins over ours.
+    fn loses(&self, cx: &Context, name: Name, (type_, data): (u16, &[u8])) -> bool {
+        match name {
+            Name::Host => {
+                #[cfg(feature = "proto-ipv4")]
+                if let Some(addr) = ipv4_addrs(cx).min() {
+                    return (u16::from(Type::A), addr.as_bytes()) < (type_, data);
+                }
+                #[cfg(feature = "proto-ipv6")]
+                if let Some(addr) = ipv6_addrs(cx).min() {
+                    return (u16::from(Type::Aaaa), addr.as_bytes()) < (type_, data);
+                }
+                false
+            }
+            Name::Instance(index) => {
+                let ours = u16::from(Type::Txt);
+                ours < type_
+                    || (ours == type_ && self.services[index].txt_data().lt(data.iter().copied()))
+            }
+            Name::Service(_) | Name::Enumeration => false,
+        }
+    }
+
+    fn process_response(&mut self, cx: &mut Context, packet: &Packet<&[u8]>) {
+        let mut rest = packet.payload();
+        for _ in 0..packet.question_count() {
+            let Ok((next, _)) = Question::parse(rest) else {
+                net_trace!("mdns: question malformed");
+                return;
+            };
+            rest = next;
+        }
+
+        let record_count = packet.answer_record_count() as usize
+            + packet.authority_record_count() as usize
+            + packet.additional_record_count() as usize;
+        for _ in 0..record_count {
+            let Ok((next, record, _, _)) = parse_record(rest) else {
+                return;
+            };
+            rest = next;
+
+            let Some(name) = self.conflicting_name(cx, packet, &record) else {
+                continue;
+            };
+            match self.state {
+                State::Probing(_) => {
+                    net_debug!("mdns: {:?} is used by another host", name);
+                    self.reset();
+                    self.state = State::Conflict;
+                    self.set_event(match name {
+                        Name::Instance(index) => Event::ServiceConflict(index),
+                        _ => Event::HostnameConflict,
+                    });
+                }
+                // A conflict after probing is resolved by probing again (RFC 6762 § 9).
+                _ => {
+                    net_debug!("mdns: conflicting record for {:?}", name);
+                    self.reset();
+                }
+            }
+            return;
+        }
+    }
+
+    /// Return the unique name of `record`, from a response of another host, if its data
+    /// differs from our records.
+    fn conflicting_name(
+        &self,
+        cx: &Context,
+        packet: &Packet<&[u8]>,
+        record: &Record,
+    ) -> Option<Name> {
+        let name = self
+            .unique_names()
+            .find(|name| eq_name(packet, record.name, self.labels(*name)))?;
+        let ours = match (name, &record.data) {
+            #[cfg(feature = "proto-ipv4")]
+            (Name::Host, RecordData::A(addr)) => ipv4_addrs(cx).any(|ours| ours == *addr),
+            #[cfg(feature = "proto-ipv6")]
+            (Name::Host, RecordData::Aaaa(addr)) => ipv6_addrs(cx).any(|ours| ours == *addr),
+            (Name::Instance(index), RecordData::Srv { port, target, .. }) => {
+                *port == self.services[index].port
+                    && eq_name(packet, target, self.labels(Name::Host))
+            }
+            (Name::Instance(index), RecordData::Txt(data)) => {
+                self.services[index].txt_data().eq(data.iter().copied())
+            }
+            _ => false,
+        };
+        (!ours).then_some(name)
+    }
+
+    pub(crate) fn dispatch<F, E>(&mut self, cx: &mut Context, emit: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut Context, (IpRepr, UdpRepr, &[u8])) -> Result<(), E>,
+    {
+        let mut buffer = [0u8; MAX_PAYLOAD_LEN];
+
+        if let Some(legacy) = self.legacy.take() {
+            let Some(src_addr) = cx.get_source_address(&legacy.endpoint.addr) else {
+                return Ok(());
+            };
+            let mut writer = Writer::new(&mut buffer);
+            let _ = writer.question(self.labels(legacy.name), legacy.type_, CLASS_IN);
+            self.write_answers(cx, &mut writer, legacy.answers, LEGACY_TTL, false);
+            let len = writer.finish(
+                legacy.transaction_id,
+                Flags::RESPONSE | Flags::AUTHORITATIVE,
+            );
+            return send(cx, emit, src_addr, legacy.endpoint, &buffer[..len]);
+        }
+
+        if let State::Probing(_) | State::Announcing(_) = self.state {
+            let Some(step_at) = self.step_at else {
+                // Probing starts after a random delay (RFC 6762 § 8.1).
+                let delay = cx.rand().rand_u32() as u64 % PROBE_WAIT.total_millis();
+                self.step_at = Some(cx.now() + Duration::from_millis(delay));
+                return Ok(());
+            };
+
+            if step_at <= cx.now() {
+                if self.step_groups == 0
+                    && GROUPS
+                        .iter()
+                        .all(|group| source_address(cx, group).is_none())
+                {
+                    net_trace!("mdns: waiting for an address");
+                    self.step_at = Some(cx.now() + ADDRESS_WAIT);
+                    return Ok(());
+                }
+
+                while let Some(group) = GROUPS.get(self.step_groups) {
+                    let Some(src_addr) = source_address(cx, group) else {
+                        self.step_groups += 1;
+                        continue;
+                    };
+
+                    let mut writer = Writer::new(&mut buffer);
+                    let len = match self.state {
+                        State::Probing(count) => self.write_probe(cx, &mut writer, count == 0),
+                        _ => self.write_announcement(cx, &mut writer),
+                    };
+                    let dst = IpEndpoint::new(*group, MDNS_PORT);
+                    send(cx, emit, src_addr, dst, &buffer[..len])?;
+                    self.step_groups += 1;
+                    return Ok(());
+                }
+
+                self.next_step(cx.now());
+            }
+        }
+
+        for (index, group) in GROUPS.iter().enumerate() {
+            let pending = &mut self.pending[index];
+            if pending.answers.is_empty() || pending.at > cx.now() {
+                continue;
+            }
+            let answers = core::mem::take(&mut pending.answers);
+            let Some(src_addr) = source_address(cx, group) else {
+                continue;
+            };
+
+            let mut writer = Writer::new(&mut buffer);
+            self.write_answers(cx, &mut writer, answers, u32::MAX, true);
+            let len = writer.finish(0, Flags::RESPONSE | Flags::AUTHORITATIVE);
+            let dst = IpEndpoint::new(*group, MDNS_PORT);
+            return send(cx, emit, src_addr, dst, &buffer[..len]);
+        }
+
+        Ok(())
+    }
+
+    /// Move on to the next probe or announcement, once the current one is sent to all groups.
+    fn next_step(&mut self, now: Instant) {
+        self.step_groups = 0;
+        match self.state {
+            State::Probing(count) if count + 1 < PROBE_COUNT => {
+                self.state = State::Probing(count + 1);
+                self.step_at = Some(now + PROBE_WAIT);
+            }
+            State::Probing(_) => {
+                net_trace!("mdns: announcing");
+                self.state = State::Announcing(0);
+                self.step_at = Some(now + PROBE_WAIT);
+            }
+            State::Announcing(count) if count + 1 < ANNOUNCE_COUNT => {
+                self.state = State::Announcing(count + 1);
+                self.step_at = Some(now + ANNOUNCE_INTERVAL);
+            }
+            State::Announcing(_) => {
+                self.state = State::Running;
+                self.set_event(Event::Announced);
+            }
+            State::Running | State::Conflict => {}
+        }
+    }
+
+    /// Write a probe, which asks for any record of the unique names, and carries our records
+    /// in the authority section.
+    fn write_probe(&self, cx: &Context, writer: &mut Writer, first: bool) -> usize {
+        // Only the first probe asks for unicast responses (RFC 6762 § 8.1).
+        let class = if first {
+            CLASS_IN | UNICAST_RESPONSE
+        } else {
+            CLASS_IN
+        };
+        for name in self.unique_names() {
+            let _ = writer.question(self.labels(name), Type::Any, class);
+        }
+        writer.section = AUTHORITY;
+        for set in self.record_sets().filter(|set| set.is_unique()) {
+            let _ = self.write_record_set(cx, writer, set, u32::MAX, false);
+        }
+        writer.finish(0, Flags::empty())
+    }
+
+    fn write_announcement(&self, cx: &Context, writer: &mut Writer) -> usize {
+        writer.section = ANSWER;
+        for set in self.record_sets() {
+            let _ = self.write_record_set(cx, writer, set, u32::MAX, true);
+        }
+        writer.finish(0, Flags::RESPONSE | Flags::AUTHORITATIVE)
+    }
+
+    /// Write the answers, followed by the additional records that come with them.
+    fn write_answers(
+        &self,
+        cx: &Context,
+        writer: &mut Writer,
+        answers: RecordSets,
+        max_ttl: u32,
+        cache_flush: bool,
+    ) {
+        writer.section = ANSWER;
+        for set in self.record_sets().filter(|set| answers.contains(*set)) {
+            let _ = self.write_record_set(cx, writer, set, max_ttl, cache_flush);
+        }
+        writer.section = ADDITIONAL;
+        for set in self.record_sets().filter(|set| {
+            !answers.contains(*set)
+                && self
+                    .record_sets()
+                    .any(|answer| answers.contains(answer) && answer.implies(*set))
+        }) {
+            let _ = self.write_record_set(cx, writer, set, max_ttl, cache_flush);
+        }
+    }
+
+    /// Write the records of a set, as long as they fit.
+    fn write_record_set(
+        &self,
+        cx: &Context,
+        writer: &mut Writer,
+        set: RecordSet,
+        max_ttl: u32,
+        cache_flush: bool,
+    ) -> Option<()> {
+        let name = self.labels(set.name());
+        let type_ = set.type_();
+        let ttl = set.ttl().min(max_ttl);
+        let class = if cache_flush && set.is_unique() {
+            CLASS_IN | CACHE_FLUSH
+        } else {
+            CLASS_IN
+        };
+
+        match set {
+            #[cfg(feature = "proto-ipv4")]
+            RecordSet::A => {
+                for addr in ipv4_addrs(cx) {
+                    writer.record(self.labels(Name::Host), type_, class, ttl, |writer| {
+                        writer.bytes(addr.as_bytes())
+                    })?;
+                }
+                Some(())
+            }
+            #[cfg(feature = "proto-ipv6")]
+            RecordSet::Aaaa => {
+                for addr in ipv6_addrs(cx) {
+                    writer.record(self.labels(Name::Host), type_, class, ttl, |writer| {
+                        writer.bytes(addr.as_bytes())
+                    })?;
+                }
+                Some(())
+            }
+            RecordSet::Ptr(index) => writer.record(name, type_, class, ttl, |writer| {
+                writer.name(self.labels(Name::Instance(index)))
+            }),
+            RecordSet::Srv(index) => writer.record(name, type_, class, ttl, |writer| {
+                // Priority and weight.
+                writer.bytes(&[0, 0, 0, 0])?;
+                writer.bytes(&self.services[index].port.to_be_bytes())?;
+                writer.name(self.labels(Name::Host))
+            }),
+            RecordSet::Txt(index) => writer.record(name, type_, class, ttl, |writer| {
+                self.services[index]
+                    .txt_data()
+                    .try_for_each(|byte| writer.bytes(&[byte]))
+            }),
+            RecordSet::Enumeration(index) => writer.record(name, type_, class, ttl, |writer| {
+                writer.name(self.labels(Name::Service(index)))
+            }),
+        }
+    }
+
+    pub(crate) fn poll_at(&self, _cx: &mut Context) -> PollAt {
+        if self.legacy.is_some() {
+            return PollAt::Now;
+        }
+        let step = match self.state {
+            State::Probing(_) | State::Announcing(_) => {
+                Some(self.step_at.map_or(PollAt::Now, PollAt::Time))
+            }
+            State::Running | State::Conflict => None,
+        };
+        let pending = self
+            .pending
+            .iter()
+            .filter(|pending| !pending.answers.is_empty())
+            .map(|pending| PollAt::Time(pending.at));
+        step.into_iter()
+            .chain(pending)
+            .min()
+            .unwrap_or(PollAt::Ingress)
+    }
+}
+
+/// Return the IPv4 addresses of the host, leaving out the loopback ones.
+#[cfg(feature = "proto-ipv4")]
+fn ipv4_addrs(cx: &Context) -> impl Iterator<Item = Ipv4Address> + '_ {
+    cx.ip_addrs().filter_map(|addr| match addr {
+        IpAddress::Ipv4(addr) if !addr.is_loopback() => Some(addr),
+        #[allow(unreachable_patterns)]
+        _ => None,
+    })
+}
+
+/// Return the IPv6 addresses of the host, leaving out the loopback one.
+#[cfg(feature = "proto-ipv6")]
+fn ipv6_addrs(cx: &Context) -> impl Iterator<Item = Ipv6Address> + '_ {
+    cx.ip_addrs().filter_map(|addr| match addr {
+        IpAddress::Ipv6(addr) if !addr.is_loopback() => Some(addr),
+        #[allow(unreachable_patterns)]
+        _ => None,
+    })
+}
+
+/// Return the source address of the messages sent to `group`, if the interface has an address
+/// of its version of IP.
+fn source_address(cx: &Context, group: &IpAddress) -> Option<IpAddress> {
+    cx.get_source_address(group)
+        .filter(|addr| !addr.is_unspecified())
+}
+
+fn send<F, E>(
+    cx: &mut Context,
+    emit: F,
+    src_addr: IpAddress,
+    dst: IpEndpoint,
+    payload: &[u8],
+) -> Result<(), E>
+where
+    F: FnOnce(&mut Context, (IpRepr, UdpRepr, &[u8])) -> Result<(), E>,
+{
+    let udp_repr = UdpRepr {
+        src_port: MDNS_PORT,
+        dst_port: dst.port,
+    };
+    // The hop limit tells that the messages come from the link (RFC 6762 § 11).
+    let ip_repr = IpRepr::new(
+        src_addr,
+        dst.addr,
+        IpProtocol::Udp,
+        udp_repr.header_len() + payload.len(),
+        255,
+    );
+    net_trace!("mdns: sending {} octets to {}", payload.len(), dst);
+    emit(cx, (ip_repr, udp_repr, payload))
+}
+
+/// Compare a name of `packet`, which may be compressed, with the given labels, ignoring case.
+fn eq_name<'p, 'l>(
+    packet: &'p Packet<&[u8]>,
+    name: &'p [u8],
+    mut labels: impl Iterator<Item = &'l [u8]>,
+) -> bool {
+    let mut name = packet.parse_name(name);
+    loop {
+        match (name.next(), labels.next()) {
+            (Some(Ok(a)), Some(b)) if a.eq_ignore_ascii_case(b) => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+/// Parse a record, and return it with its raw type and data.
+fn parse_record(bytes: &[u8]) -> crate::wire::Result<(&[u8], Record<'_>, u16, &[u8])> {
+    let (rest, record) = Record::parse(bytes)?;
+    let header = &bytes[record.name.len()..bytes.len() - rest.len()];
+    let type_ = NetworkEndian::read_u16(&header[0..2]);
+    Ok((rest, record, type_, &header[10..]))
+}
+
+const QUESTION: usize = 0;
+const ANSWER: usize = 1;
+const AUTHORITY: usize = 2;
+const ADDITIONAL: usize = 3;
+
+/// Writes the questions and records of a message, without compressing the names.
+struct Writer<'b> {
+    buffer: &'b mut [u8],
+    len: usize,
+    counts: [u16; 4],
+    /// Section the records are written to.
+    section: usize,
+}
+
+impl<'b> Writer<'b> {
+    fn new(buffer: &'b mut [u8]) -> Self {
+        Writer {
+            buffer,
+            len: HEADER_LEN,
+            counts: [0; 4],
+            section: QUESTION,
+        }
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) -> Option<()> {
+        let end = self.len + bytes.len();
+        self.buffer.get_mut(self.len..end)?.copy_from_slice(bytes);
+        self.len = end;
+        Some(())
+    }
+
+    fn name<'l>(&mut self, labels: impl Iterator<Item = &'l [u8]>) -> Option<()> {
+        for label in labels {
+            self.bytes(&[label.len() as u8])?;
+            self.bytes(label)?;
+        }
+        self.bytes(&[0])
+    }
+
+    /// Write a question, or nothing if it does not fit.
+    fn question<'l>(
+        &mut self,
+        name: impl Iterator<Item = &'l [u8]>,
+        type_: Type,
+        class: u16,
+    ) -> Option<()> {
+        self.rollback(|writer| {
+            writer.name(name)?;
+            writer.bytes(&u16::from(type_).to_be_bytes())?;
+            writer.bytes(&class.to_be_bytes())
+        })?;
+        self.counts[QUESTION] += 1;
+        Some(())
+    }
+
+    /// Write a record to the current section, or nothing if it does not fit.
+    fn record<'l>(
+        &mut self,
+        name: impl Iterator<Item = &'l [u8]>,
+        type_: Type,
+        class: u16,
+        ttl: u32,
+        data: impl FnOnce(&mut Self) -> Option<()>,
+    ) -> Option<()> {
+        self.rollback(|writer| {
+            writer.name(name)?;
+            writer.bytes(&u16::from(type_).to_be_bytes())?;
+            writer.bytes(&class.to_be_bytes())?;
+            writer.bytes(&ttl.to_be_bytes())?;
+            let len_at = writer.len;
+            writer.bytes(&[0, 0])?;
+            data(writer)?;
+            let len = (writer.len - len_at - 2) as u16;
+            NetworkEndian::write_u16(&mut writer.buffer[len_at..len_at + 2], len);
+            Some(())
+        })?;
+        self.counts[self.section] += 1;
+        Some(())
+    }
+
+    fn rollback(&mut self, f: impl FnOnce(&mut Self) -> Option<()>) -> Option<()> {
+        let len = self.len;
+        let result = f(self);
+        if result.is_none() {
+            self.len = len;
+        }
+        result
+    }
+
+    /// Write the header, and return the length of the message.
+    fn finish(&mut self, transaction_id: u16, flags: Flags) -> usize {
+        let mut packet = Packet::new_unchecked(&mut self.buffer[..]);
+        packet.set_transaction_id(transaction_id);
+        packet.set_flags(flags);
+        packet.set_opcode(Opcode::Query);
+        packet.set_question_count(self.counts[QUESTION]);
+        packet.set_answer_record_count(self.counts[ANSWER]);
+        packet.set_authority_record_count(self.counts[AUTHORITY]);
+        packet.set_additional_record_count(self.counts[ADDITIONAL]);
+        self.len
+    }
+}
+
+#[cfg(feature = "proto-ipv4")] // tests assume ipv4
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::phy::Medium;
+    use crate::tests::setup;
+    use heapless::Vec;
+    use rstest::*;
+    use std::string::{String, ToString};
+
+    const PEER: IpAddress = IpAddress::Ipv4(Ipv4Address([192, 168, 1, 2]));
+
+    const SERVICES: &[Service<'static>] = &[
+        Service {
+            instance: "Office Printer",
+            service: "_ipp._tcp",
+            port: 631,
+            txt: &["txtvers=1"],
+        },
+        Service {
+            instance: "Web",
+            service: "_http._tcp",
+            port: 80,
+            txt: &[],
+        },
+        Service {
+            instance: "Admin",
+            service: "_http._tcp",
+            port: 8080,
+            txt: &["path=/admin"],
+        },
+    ];
+
+    fn socket(medium: Medium) -> (Socket<'static>, Context) {
+        let (iface, _, _) = setup(medium);
+        (Socket::new("device", SERVICES), iface.inner)
+    }
+
+    /// Run the socket until all the probes and announcements are sent.
+    fn announced(medium: Medium) -> (Socket<'static>, Context) {
+        let (mut s, mut cx) = socket(medium);
+        run(&mut s, &mut cx, Duration::from_millis(3_000));
+        assert_eq!(s.poll(), Some(Event::Announced));
+        (s, cx)
+    }
+
+    /// Dispatch the messages of the socket for `duration`, and return their destinations and
+    /// payloads.
+    fn run(
+        s: &mut Socket,
+        cx: &mut Context,
+        duration: Duration,
+    ) -> std::vec::Vec<(IpEndpoint, Vec<u8, 1500>)> {
+        let until = cx.now() + duration;
+        let mut sent = std::vec::Vec::new();
+        loop {
+            for _ in 0..8 {
+                s.dispatch(cx, |_, (ip_repr, udp_repr, payload)| {
+                    assert_eq!(udp_repr.src_port, MDNS_PORT);
+                    let dst = IpEndpoint::new(ip_repr.dst_addr(), udp_repr.dst_port);
+                    sent.push((dst, Vec::from_slice(payload).unwrap()));
+                    Ok::<_, ()>(())
+                })
+                .unwrap();
+            }
+            if cx.now() >= until {
+                return sent;
+            }
+            cx.set_now(cx.now() + Duration::from_millis(10));
+        }
+    }
+
+    fn encode_name(name: &str, buffer: &mut std::vec::Vec<u8>) {
+        for label in name.split('.') {
+            buffer.push(label.len() as u8);
+            buffer.extend_from_slice(label.as_bytes());
+        }
+        buffer.push(0);
+    }
+
+    /// Build a message with the given questions, and records in the answer and authority
+    /// sections.
+    fn message(
+        flags: Flags,
+        questions: &[(&str, Type)],
+        answers: &[(&str, Type, &[u8])],
+        authority: &[(&str, Type, &[u8])],
+    ) -> std::vec::Vec<u8> {
+        let mut payload = std::vec![0; HEADER_LEN];
+        let mut packet = Packet::new_unchecked(&mut payload);
+        packet.set_flags(flags);
+        packet.set_question_count(questions.len() as u16);
+        packet.set_answer_record_count(answers.len() as u16);
+        packet.set_authority_record_count(authority.len() as u16);
+
+        for (name, type_) in questions {
+            encode_name(name, &mut payload);
+            payload.extend_from_slice(&u16::from(*type_).to_be_bytes());
+            payload.extend_from_slice(&CLASS_IN.to_be_bytes());
+        }
+        for (name, type_, data) in answers.iter().chain(authority) {
+            encode_name(name, &mut payload);
+            payload.extend_from_slice(&u16::from(*type_).to_be_bytes());
+            payload.extend_from_slice(&CLASS_IN.to_be_bytes());
+            payload.extend_from_slice(&OTHER_TTL.to_be_bytes());
+            payload.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            payload.extend_from_slice(data);
+        }
+        payload
+    }
+
+    fn receive(s: &mut Socket, cx: &mut Context, src_port: u16, payload: &[u8]) {
+        let ip_repr = IpRepr::new(PEER, IPV4_GROUP, IpProtocol::Udp, 0, 255);
+        let udp_repr = UdpRepr {
+            src_port,
+            dst_port: MDNS_PORT,
+        };
+        s.process(cx, &ip_repr, &udp_repr, payload);
+    }
+
+    fn name_string(packet: &Packet<&[u8]>, name: &[u8]) -> String {
+        packet
+            .parse_name(name)
+            .map(|label| core::str::from_utf8(label.unwrap()).unwrap())
+            .collect::<std::vec::Vec<_>>()
+            .join(".")
+    }
+
+    /// A record of a message, with its name, class and data decoded.
+    #[derive(Debug, PartialEq, Eq)]
+    struct Rr {
+        name: String,
+        class: u16,
+        ttl: u32,
+        data: String,
+    }
+
+    /// Return the questions, and the records of all the sections of a message.
+    fn parse(payload: &[u8]) -> (std::vec::Vec<(String, Type, u16)>, std::vec::Vec<Rr>) {
+        let packet = Packet::new_checked(payload).unwrap();
+        let mut rest = packet.payload();
+
+        let mut questions = std::vec::Vec::new();
+        for _ in 0..packet.question_count() {
+            let (next, question) = Question::parse(rest).unwrap();
+            let class = NetworkEndian::read_u16(&rest[question.name.len() + 2..]);
+            questions.push((name_string(&packet, question.name), question.type_, class));
+            rest = next;
+        }
+
+        let mut records = std::vec::Vec::new();
+        let count = packet.answer_record_count()
+            + packet.authority_record_count()
+            + packet.additional_record_count();
+        for _ in 0..count {
+            let (next, record, _, _) = parse_record(rest).unwrap();
+            let class = NetworkEndian::read_u16(&rest[record.name.len() + 2..]);
+            let data = match record.data {
+                RecordData::A(addr) => addr.to_string(),
+                #[cfg(feature = "proto-ipv6")]
+                RecordData::Aaaa(addr) => addr.to_string(),
+                RecordData::Ptr(name) => name_string(&packet, name),
+                RecordData::Srv { port, target, .. } => {
+                    std::format!("{} {}", port, name_string(&packet, target))
+                }
+                RecordData::Txt(data) => std::format!("{:?}", data),
+                data => panic!("unexpected record data {:?}", data),
+            };
+            records.push(Rr {
+                name: name_string(&packet, record.name),
+                class,
+                ttl: record.ttl,
+                data,
+            });
+            rest = next;
+        }
+        (questions, records)
+    }
+
+    fn rr(name: &str, class: u16, ttl: u32, data: &str) -> Rr {
+        Rr {
+            name: name.to_string(),
+            class,
+            ttl,
+            data: data.to_string(),
+        }
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_probe_announce(#[case] medium: Medium) {
+        let (mut s, mut cx) = socket(medium);
+
+        let sent = run(&mut s, &mut cx, Duration::from_millis(3_000));
+        assert_eq!(s.poll(), Some(Event::Announced));
+
+        let to_ipv4: std::vec::Vec<_> = sent
+            .iter()
+            .filter(|(dst, _)| *dst == IpEndpoint::new(IPV4_GROUP, MDNS_PORT))
+            .map(|(_, payload)| payload)
+            .collect();
+        assert_eq!(to_ipv4.len(), (PROBE_COUNT + ANNOUNCE_COUNT) as usize);
+        #[cfg(feature = "proto-ipv6")]
+        assert_eq!(sent.len(), 2 * to_ipv4.len());
+
+        // The probes ask for any record of the unique names, and propose our records.
+        let (questions, records) = parse(to_ipv4[0]);
+        assert_eq!(
+            questions,
+            std::vec![
+                (
+                    "device.local".to_string(),
+                    Type::Any,
+                    CLASS_IN | UNICAST_RESPONSE
+                ),
+                (
+                    "Office Printer._ipp._tcp.local".to_string(),
+                    Type::Any,
+                    CLASS_IN | UNICAST_RESPONSE
+                ),
+                (
+                    "Web._http._tcp.local".to_string(),
+                    Type::Any,
+                    CLASS_IN | UNICAST_RESPONSE
+                ),
+                (
+                    "Admin._http._tcp.local".to_string(),
+                    Type::Any,
+                    CLASS_IN | UNICAST_RESPONSE
+                ),
+            ]
+        );
+        assert_eq!(
+            records[0],
+            rr("device.local", CLASS_IN, HOST_NAME_TTL, "192.168.1.1")
+        );
+        assert!(records.iter().all(|record| record.class == CLASS_IN));
+        assert!(records.contains(&rr(
+            "Office Printer._ipp._tcp.local",
+            CLASS_IN,
+            HOST_NAME_TTL,
+            "631 device.local"
+        )));
+        let (questions, _) = parse(to_ipv4[1]);
+        assert_eq!(questions[0].2, CLASS_IN);
+
+        // The announcements carry all the records.
+        let (questions, records) = parse(to_ipv4[3]);
+        assert!(questions.is_empty());
+        let flush = CLASS_IN | CACHE_FLUSH;
+        for record in [
+            rr("device.local", flush, HOST_NAME_TTL, "192.168.1.1"),
+            rr(
+                "_ipp._tcp.local",
+                CLASS_IN,
+                OTHER_TTL,
+                "Office Printer._ipp._tcp.local",
+            ),
+            rr(
+                "Web._http._tcp.local",
+                flush,
+                HOST_NAME_TTL,
+                "80 device.local",
+            ),
+            rr("Web._http._tcp.local", flush, OTHER_TTL, "[0]"),
+            rr(
+                "Admin._http._tcp.local",
+                flush,
+                OTHER_TTL,
+                "[11, 112, 97, 116, 104, 61, 47, 97, 100, 109, 105, 110]",
+            ),
+            rr(
+                "_services._dns-sd._udp.local",
+                CLASS_IN,
+                OTHER_TTL,
+                "_ipp._tcp.local",
+            ),
+            rr(
+                "_services._dns-sd._udp.local",
+                CLASS_IN,
+                OTHER_TTL,
+                "_http._tcp.local",
+            ),
+        ] {
+            assert!(records.contains(&record), "missing {:?}", record);
+        }
+        // Each service type is enumerated once.
+        assert_eq!(
+            records
+                .iter()
+                .filter(|record| record.name == "_services._dns-sd._udp.local")
+                .count(),
+            2
+        );
+        assert_eq!(to_ipv4[3], to_ipv4[4]);
+
+        // Nothing is sent afterwards.
+        assert!(run(&mut s, &mut cx, Duration::from_millis(5_000)).is_empty());
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_browse(#[case] medium: Medium) {
+        let (mut s, mut cx) = announced(medium);
+
+        let query = message(Flags::empty(), &[("_http._tcp.local", Type::Ptr)], &[], &[]);
+        receive(&mut s, &mut cx, MDNS_PORT, &query);
+
+        // The shared records are answered after a random delay.
+        let sent = run(&mut s, &mut cx, Duration::ZERO);
+        assert!(sent.is_empty());
+        let sent = run(&mut s, &mut cx, Duration::from_millis(RESPONSE_DELAY_MAX));
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, IpEndpoint::new(IPV4_GROUP, MDNS_PORT));
+
+        let packet = Packet::new_checked(&sent[0].1[..]).unwrap();
+        assert_eq!(packet.transaction_id(), 0);
+        assert_eq!(packet.flags(), Flags::RESPONSE | Flags::AUTHORITATIVE);
+        assert_eq!(packet.answer_record_count(), 2);
+
+        let (questions, records) = parse(&sent[0].1);
+        assert!(questions.is_empty());
+        assert_eq!(
+            records[..2],
+            [
+                rr(
+                    "_http._tcp.local",
+                    CLASS_IN,
+                    OTHER_TTL,
+                    "Web._http._tcp.local"
+                ),
+                rr(
+                    "_http._tcp.local",
+                    CLASS_IN,
+                    OTHER_TTL,
+                    "Admin._http._tcp.local"
+                ),
+            ]
+        );
+        // The additional records resolve the instances.
+        let flush = CLASS_IN | CACHE_FLUSH;
+        for record in [
+            rr(
+                "Web._http._tcp.local",
+                flush,
+                HOST_NAME_TTL,
+                "80 device.local",
+            ),
+            rr(
+                "Admin._http._tcp.local",
+                flush,
+                HOST_NAME_TTL,
+                "8080 device.local",
+            ),
+            rr("device.local", flush, HOST_NAME_TTL, "192.168.1.1"),
+        ] {
+            assert!(records.contains(&record), "missing {:?}", record);
+        }
+        assert!(!records
+            .iter()
+            .any(|record| record.name.starts_with("Office")));
+
+        // The service types are enumerated.
+        let query = message(
+            Flags::empty(),
+            &[("_services._dns-sd._udp.local", Type::Ptr)],
+            &[],
+            &[],
+        );
+        receive(&mut s, &mut cx, MDNS_PORT, &query);
+        let sent = run(&mut s, &mut cx, Duration::from_millis(RESPONSE_DELAY_MAX));
+        let (_, records) = parse(&sent[0].1);
+        assert_eq!(
+            records,
+            [
+                rr(
+                    "_services._dns-sd._udp.local",
+                    CLASS_IN,
+                    OTHER_TTL,
+                    "_ipp._tcp.local"
+                ),
+                rr(
+                    "_services._dns-sd._udp.local",
+                    CLASS_IN,
+                    OTHER_TTL,
+                    "_http._tcp.local"
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_known_answer(#[case] medium: Medium) {
+        let (mut s, mut cx) = announced(medium);
+
+        let mut known = std::vec::Vec::new();
+        encode_name("Web._http._tcp.local", &mut known);
+        let query = message(
+            Flags::empty(),
+            &[("_http._tcp.local", Type::Ptr)],
+            &[("_http._tcp.local", Type::Ptr, &known)],
+            &[],
+        );
+        receive(&mut s, &mut cx, MDNS_PORT, &query);
+
+        let sent = run(&mut s, &mut cx, Duration::from_millis(RESPONSE_DELAY_MAX));
+        let (_, records) = parse(&sent[0].1);
+        assert_eq!(
+            records[0],
+            rr(
+                "_http._tcp.local",
+                CLASS_IN,
+                OTHER_TTL,
+                "Admin._http._tcp.local"
+            )
+        );
+        assert!(!records.iter().any(|record| record.name.starts_with("Web")));
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_unique_answer(#[case] medium: Medium) {
+        let (mut s, mut cx) = announced(medium);
+
+        // The unique records are answered without delay.
+        let query = message(Flags::empty(), &[("DEVICE.local", Type::A)], &[], &[]);
+        receive(&mut s, &mut cx, MDNS_PORT, &query);
+        let sent = run(&mut s, &mut cx, Duration::ZERO);
+        assert_eq!(sent.len(), 1);
+        let (_, records) = parse(&sent[0].1);
+        assert_eq!(
+            records,
+            [rr(
+                "device.local",
+                CLASS_IN | CACHE_FLUSH,
+                HOST_NAME_TTL,
+                "192.168.1.1"
+            )]
+        );
+
+        // Names that are not ours are not answered.
+        let query = message(Flags::empty(), &[("other.local", Type::A)], &[], &[]);
+        receive(&mut s, &mut cx, MDNS_PORT, &query);
+        assert!(run(&mut s, &mut cx, Duration::from_millis(1_000)).is_empty());
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_legacy_query(#[case] medium: Medium) {
+        let (mut s, mut cx) = announced(medium);
+
+        let mut query = message(
+            Flags::empty(),
+            &[("Office Printer._ipp._tcp.local", Type::Srv)],
+            &[],
+            &[],
+        );
+        Packet::new_unchecked(&mut query).set_transaction_id(0x1234);
+        receive(&mut s, &mut cx, 49152, &query);
+
+        let sent = run(&mut s, &mut cx, Duration::ZERO);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, IpEndpoint::new(PEER, 49152));
+
+        let packet = Packet::new_checked(&sent[0].1[..]).unwrap();
+        assert_eq!(packet.transaction_id(), 0x1234);
+        let (questions, records) = parse(&sent[0].1);
+        assert_eq!(
+            questions,
+            [(
+                "Office Printer._ipp._tcp.local".to_string(),
+                Type::Srv,
+                CLASS_IN
+            )]
+        );
+        assert_eq!(
+            records[0],
+            rr(
+                "Office Printer._ipp._tcp.local",
+                CLASS_IN,
+                LEGACY_TTL,
+                "631 device.local"
+            )
+        );
+        assert!(records
+            .iter()
+            .all(|record| record.class == CLASS_IN && record.ttl == LEGACY_TTL));
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_probe_conflict(#[case] medium: Medium) {
+        let (mut s, mut cx) = socket(medium);
+        assert!(!run(&mut s, &mut cx, Duration::from_millis(300)).is_empty());
+
+        let response = message(
+            Flags::RESPONSE | Flags::AUTHORITATIVE,
+            &[],
+            &[("device.local", Type::A, &[192, 168, 1, 2])],
+            &[],
+        );
+        receive(&mut s, &mut cx, MDNS_PORT, &response);
+        assert_eq!(s.poll(), Some(Event::HostnameConflict));
+
+        // The responder stops until the name is changed.
+        assert!(run(&mut s, &mut cx, Duration::from_millis(3_000)).is_empty());
+
+        s.set_hostname("device-2");
+        assert!(!run(&mut s, &mut cx, Duration::from_millis(3_000)).is_empty());
+        assert_eq!(s.poll(), Some(Event::Announced));
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_probe_tiebreak(#[case] medium: Medium) {
+        let (mut s, mut cx) = socket(medium);
+        assert!(!run(&mut s, &mut cx, Duration::from_millis(300)).is_empty());
+
+        // A probe with a lower address loses.
+        assert!(matches!(s.state, State::Probing(count) if count > 0));
+        let probe = message(
+            Flags::empty(),
+            &[("device.local", Type::Any)],
+            &[],
+            &[("device.local", Type::A, &[192, 168, 1, 0])],
+        );
+        let state = s.state;
+        receive(&mut s, &mut cx, MDNS_PORT, &probe);
+        assert_eq!(s.state, state);
+
+        // A probe with a higher address wins, and probing is deferred.
+        let probe = message(
+            Flags::empty(),
+            &[("device.local", Type::Any)],
+            &[],
+            &[("device.local", Type::A, &[192, 168, 1, 2])],
+        );
+        receive(&mut s, &mut cx, MDNS_PORT, &probe);
+        assert_eq!(s.state, State::Probing(0));
+        assert!(run(&mut s, &mut cx, PROBE_DEFER - Duration::from_millis(10)).is_empty());
+        run(&mut s, &mut cx, Duration::from_millis(3_000));
+        assert_eq!(s.poll(), Some(Event::Announced));
+    }
+
+    #[rstest]
+    #[case::ethernet(Medium::Ethernet)]
+    #[cfg(feature = "medium-ethernet")]
+    fn test_conflict_after_announce(#[case] medium: Medium) {
+        let (mut s, mut cx) = announced(medium);
+
+        // Our own records do not conflict.
+        let response = message(
+            Flags::RESPONSE | Flags::AUTHORITATIVE,
+            &[],
+            &[("device.local", Type::A, &[192, 168, 1, 1])],
+            &[],
+        );
+        receive(&mut s, &mut cx, MDNS_PORT, &response);
+        assert_eq!(s.state, State::Running);
+
+        let response = message(
+            Flags::RESPONSE | Flags::AUTHORITATIVE,
+            &[],
+            &[("Web._http._tcp.local", Type::Txt, b"\x03a=b")],
+            &[],
+        );
+        receive(&mut s, &mut cx, MDNS_PORT, &response);
+        assert_eq!(s.state, State::Probing(0));
+    }
+}
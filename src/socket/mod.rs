@@ -16,10 +16,16 @@ use crate::time::Instant;
 
 #[cfg(feature = "socket-dhcpv4")]
 pub mod dhcpv4;
+#[cfg(feature = "socket-dhcpv4-server")]
+pub mod dhcpv4_server;
+#[cfg(feature = "socket-dhcpv6")]
+pub mod dhcpv6;
 #[cfg(feature = "socket-dns")]
 pub mod dns;
 #[cfg(feature = "socket-icmp")]
 pub mod icmp;
+#[cfg(feature = "socket-mdns")]
+pub mod mdns;
 #[cfg(feature = "socket-raw")]
 pub mod raw;
 #[cfg(feature = "socket-tcp")]
@@ -67,8 +73,14 @@ pub enum Socket<'a> {
     Tcp(tcp::Socket<'a>),
     #[cfg(feature = "socket-dhcpv4")]
     Dhcpv4(dhcpv4::Socket<'a>),
+    #[cfg(feature = "socket-dhcpv4-server")]
+    Dhcpv4Server(dhcpv4_server::Socket<'a>),
+    #[cfg(feature = "socket-dhcpv6")]
+    Dhcpv6(dhcpv6::Socket<'a>),
     #[cfg(feature = "socket-dns")]
     Dns(dns::Socket<'a>),
+    #[cfg(feature = "socket-mdns")]
+    Mdns(mdns::Socket<'a>),
 }
 
 impl<'a> Socket<'a> {
@@ -84,8 +96,14 @@ impl<'a> Socket<'a> {
             Socket::Tcp(s) => s.poll_at(cx),
             #[cfg(feature = "socket-dhcpv4")]
             Socket::Dhcpv4(s) => s.poll_at(cx),
+            #[cfg(feature = "socket-dhcpv4-server")]
+            Socket::Dhcpv4Server(s) => s.poll_at(cx),
+            #[cfg(feature = "socket-dhcpv6")]
+            Socket::Dhcpv6(s) => s.poll_at(cx),
             #[cfg(feature = "socket-dns")]
             Socket::Dns(s) => s.poll_at(cx),
+            #[cfg(feature = "socket-mdns")]
+            Socket::Mdns(s) => s.poll_at(cx),
         }
     }
 }
@@ -137,5 +155,11 @@ from_socket!(udp::Socket<'a>, Udp);
 from_socket!(tcp::Socket<'a>, Tcp);
 #[cfg(feature = "socket-dhcpv4")]
 from_socket!(dhcpv4::Socket<'a>, Dhcpv4);
+#[cfg(feature = "socket-dhcpv4-server")]
+from_socket!(dhcpv4_server::Socket<'a>, Dhcpv4Server);
+#[cfg(feature = "socket-dhcpv6")]
+from_socket!(dhcpv6::Socket<'a>, Dhcpv6);
 #[cfg(feature = "socket-dns")]
 from_socket!(dns::Socket<'a>, Dns);
+#[cfg(feature = "socket-mdns")]
+from_socket!(mdns::Socket<'a>, Mdns);
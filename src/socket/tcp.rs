@@ -6,6 +6,7 @@ use core::fmt::Display;
 #[cfg(feature = "async")]
 use core::task::Waker;
 use core::{cmp, fmt, mem};
+use managed::ManagedSlice;
 
 #[cfg(feature = "async")]
 use crate::socket::WakerRegistration;
@@ -101,6 +102,29 @@ impl Display for RecvError {
 #[cfg(feature = "std")]
 impl std::error::Error for RecvError {}
 
+/// Error returned by [`Socket::accept`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AcceptError {
+    /// The socket is not listening with a backlog, or the socket given to take the place of
+    /// the connection is not closed.
+    InvalidState,
+    /// No connection of the backlog is established yet.
+    Exhausted,
+}
+
+impl Display for AcceptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AcceptError::InvalidState => write!(f, "invalid state"),
+            AcceptError::Exhausted => write!(f, "no established connection"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AcceptError {}
+
 /// A TCP socket ring buffer.
 pub type SocketBuffer<'a> = RingBuffer<'a, u8>;
 
@@ -408,9 +432,9 @@ pub enum CongestionControl {
 /// A Transmission Control Protocol socket.
 ///
 /// A TCP socket may passively listen for connections or actively connect to another endpoint.
-/// Note that, for listening sockets, there is no "backlog"; to be able to simultaneously
-/// accept several connections, as many sockets must be allocated, or any new connection
-/// attempts will be reset.
+/// A socket listening with [listen](#method.listen) becomes the connection itself; to be able
+/// to simultaneously accept several connections, either as many sockets must be allocated,
+/// or a backlog must be provided with [listen_with_backlog](#method.listen_with_backlog).
 #[derive(Debug)]
 pub struct Socket<'a> {
     state: State,
@@ -429,6 +453,9 @@ pub struct Socket<'a> {
     /// Address passed to listen(). Listen address is set when listen() is called and
     /// used every time the socket is reset back to the LISTEN state.
     listen_endpoint: IpListenEndpoint,
+    /// Sockets that complete the handshakes of incoming connections while listening, until
+    /// they are accepted. Empty if the socket becomes the connection itself.
+    backlog: ManagedSlice<'a, Socket<'a>>,
     /// Current 4-tuple (local and remote endpoints).
     tuple: Option<Tuple>,
     /// The sequence number corresponding to the beginning of the transmit buffer.
@@ -455,6 +482,12 @@ pub struct Socket<'a> {
     remote_win_scale: Option<u8>,
     /// Whether or not the remote supports selective ACK as described in RFC 2018.
     remote_has_sack: bool,
+    /// The ranges that the remote has selectively acknowledged, relative to local_seq_no.
+    /// This is the sender-side scoreboard described in RFC 6675.
+    remote_sacked: Assembler,
+    /// The last sequence number sent when loss recovery started, if in progress.
+    /// Until it is acknowledged, only holes in the scoreboard are retransmitted.
+    recovery_point: Option<TcpSeqNumber>,
     /// The maximum number of data octets that the remote side may receive.
     remote_mss: usize,
     /// The timestamp of the last packet received.
@@ -527,6 +560,7 @@ impl<'a> Socket<'a> {
             keep_alive: None,
             hop_limit: None,
             listen_endpoint: IpListenEndpoint::default(),
+            backlog: ManagedSlice::Borrowed(&mut []),
             tuple: None,
             local_seq_no: TcpSeqNumber::default(),
             remote_seq_no: TcpSeqNumber::default(),
@@ -537,6 +571,8 @@ impl<'a> Socket<'a> {
             remote_win_shift: rx_cap_log2.saturating_sub(16) as u8,
             remote_win_scale: None,
             remote_has_sack: false,
+            remote_sacked: Assembler::new(),
+            recovery_point: None,
             remote_mss: DEFAULT_MSS,
             remote_last_ts: None,
             local_rx_last_ack: None,
@@ -806,6 +842,9 @@ impl<'a> Socket<'a> {
         self.rx_buffer.clear();
         self.rx_fin_received = false;
         self.listen_endpoint = IpListenEndpoint::default();
+        for socket in self.backlog.iter_mut() {
+            socket.reset();
+        }
         self.tuple = None;
         self.local_seq_no = TcpSeqNumber::default();
         self.remote_seq_no = TcpSeqNumber::default();
@@ -815,6 +854,8 @@ impl<'a> Socket<'a> {
         self.remote_win_len = 0;
         self.remote_win_scale = None;
         self.remote_win_shift = rx_cap_log2.saturating_sub(16) as u8;
+        self.remote_sacked.clear();
+        self.recovery_point = None;
         self.remote_mss = DEFAULT_MSS;
         self.remote_last_ts = None;
         self.ack_delay_timer = AckDelayTimer::Idle;
@@ -859,11 +900,77 @@ impl<'a> Socket<'a> {
 
         self.reset();
         self.listen_endpoint = local_endpoint;
+        self.backlog = ManagedSlice::Borrowed(&mut []);
         self.tuple = None;
         self.set_state(State::Listen);
         Ok(())
     }
 
+    /// Start listening on the given endpoint, completing the handshakes of incoming
+    /// connections with the sockets of the backlog.
+    ///
+    /// Unlike with [listen](#method.listen), the socket stays in the `LISTEN` state, and
+    /// established connections are handed out with [accept](#method.accept). Connection
+    /// attempts are ignored while every socket of the backlog is in use. The buffers and
+    /// options of the backlog sockets are those of the accepted connections.
+    ///
+    /// This function returns the same errors as [listen](#method.listen).
+    pub fn listen_with_backlog<T, B>(
+        &mut self,
+        local_endpoint: T,
+        backlog: B,
+    ) -> Result<(), ListenError>
+    where
+        T: Into<IpListenEndpoint>,
+        B: Into<ManagedSlice<'a, Socket<'a>>>,
+    {
+        let local_endpoint = local_endpoint.into();
+        self.listen(local_endpoint)?;
+
+        self.backlog = backlog.into();
+        for socket in self.backlog.iter_mut() {
+            socket.reset();
+            socket.listen(local_endpoint)?;
+        }
+        Ok(())
+    }
+
+    /// Accept an established connection from the backlog.
+    ///
+    /// The connection is moved into `socket`, which must be closed and takes its place in
+    /// the backlog. The socket can then be added to a socket set; when the listening socket is
+    /// itself in a socket set, [`SocketSet::accept`](crate::iface::SocketSet::accept) does
+    /// both.
+    ///
+    /// This function returns `Err(AcceptError::InvalidState)` if the socket isn't listening
+    /// with a backlog or `socket` isn't closed, and `Err(AcceptError::Exhausted)` if no
+    /// connection is established yet.
+    pub fn accept(&mut self, socket: &mut Socket<'a>) -> Result<(), AcceptError> {
+        if self.state != State::Listen || self.backlog.is_empty() || socket.state != State::Closed {
+            return Err(AcceptError::InvalidState);
+        }
+
+        let Some(connection) = self.backlog.iter_mut().find(|s| s.is_acceptable()) else {
+            return Err(AcceptError::Exhausted);
+        };
+
+        mem::swap(connection, socket);
+        connection.reset();
+        // NOTE(unwrap): the socket is closed, and the endpoint has a port.
+        connection.listen(self.listen_endpoint).unwrap();
+        Ok(())
+    }
+
+    /// Return whether a connection can be accepted from the backlog.
+    pub fn can_accept(&self) -> bool {
+        self.state == State::Listen && self.backlog.iter().any(|s| s.is_acceptable())
+    }
+
+    /// Return whether the socket completed a handshake and waits to be accepted.
+    fn is_acceptable(&self) -> bool {
+        matches!(self.state, State::Established | State::CloseWait)
+    }
+
     /// Connect to a given endpoint.
     ///
     /// The local port must be provided explicitly. Assuming `fn get_ephemeral_port() -> u16`
@@ -976,8 +1083,14 @@ impl<'a> Socket<'a> {
     /// data and would like to reuse the socket right away, use [abort](#method.abort).
     pub fn close(&mut self) {
         match self.state {
-            // In the LISTEN state there is no established connection.
-            State::Listen => self.set_state(State::Closed),
+            // In the LISTEN state there is no established connection, and connections that
+            // were not accepted are dropped.
+            State::Listen => {
+                for socket in self.backlog.iter_mut() {
+                    socket.reset();
+                }
+                self.set_state(State::Closed)
+            }
             // In the SYN-SENT state the remote endpoint is not yet synchronized and, upon
             // receiving an RST, will abort the connection.
             State::SynSent => self.set_state(State::Closed),
@@ -1005,6 +1118,9 @@ impl<'a> Socket<'a> {
     /// In terms of the TCP state machine, the socket may be in any state and is moved to
     /// the `CLOSED` state.
     pub fn abort(&mut self) {
+        for socket in self.backlog.iter_mut() {
+            socket.reset();
+        }
         self.set_state(State::Closed);
     }
 
@@ -1428,6 +1544,11 @@ impl<'a> Socket<'a> {
             return false;
         }
 
+        // Segments of the connections in the backlog are processed by the listening socket.
+        if self.state == State::Listen && self.backlog_connection(ip_repr, repr).is_some() {
+            return true;
+        }
+
         // If we're still listening for SYNs and the packet has an ACK, it cannot
         // be destined to this socket, but another one may well listen on the same
         // local endpoint.
@@ -1451,6 +1572,65 @@ impl<'a> Socket<'a> {
         }
     }
 
+    /// Check whether the socket sent a segment from `local` to `remote`, which an ICMP error
+    /// message was received for.
+    pub(crate) fn accepts_icmp_error(&self, local: &IpEndpoint, remote: &IpEndpoint) -> bool {
+        self.tuple
+            .is_some_and(|tuple| tuple.local == *local && tuple.remote == *remote)
+    }
+
+    /// Process an ICMP error for a segment sent by the socket. Hard errors abort a connection
+    /// that is being opened, while soft errors are ignored, see RFC 1122 § 4.2.3.9 and RFC 5461.
+    pub(crate) fn process_icmp_error(&mut self, seq_number: TcpSeqNumber, hard: bool) {
+        // Only the initial SYN can be in error, which makes forging such messages harder.
+        if hard && self.state == State::SynSent && seq_number == self.local_seq_no {
+            tcp_trace!("connection refused by ICMP error");
+            self.set_state(State::Closed);
+            self.tuple = None;
+        }
+    }
+
+    /// Return the index of the connection in the backlog that a segment belongs to.
+    fn backlog_connection(&self, ip_repr: &IpRepr, repr: &TcpRepr) -> Option<usize> {
+        self.backlog.iter().position(|s| {
+            s.state != State::Closed
+                && s.tuple.is_some_and(|tuple| {
+                    ip_repr.dst_addr() == tuple.local.addr
+                        && repr.dst_port == tuple.local.port
+                        && ip_repr.src_addr() == tuple.remote.addr
+                        && repr.src_port == tuple.remote.port
+                })
+        })
+    }
+
+    /// Process a segment for a connection in the backlog, or a connection attempt.
+    fn process_backlog(
+        &mut self,
+        cx: &mut Context,
+        ip_repr: &IpRepr,
+        repr: &TcpRepr,
+    ) -> Option<(IpRepr, TcpRepr<'static>)> {
+        // Prefer the connection the segment belongs to, over a socket that is still listening.
+        let index = self.backlog_connection(ip_repr, repr).or_else(|| {
+            self.backlog
+                .iter()
+                .position(|s| s.state == State::Listen && s.tuple.is_none())
+        });
+        let Some(index) = index else {
+            net_debug!("backlog is full, ignoring connection attempt");
+            return None;
+        };
+
+        let socket = &mut self.backlog[index];
+        let reply = socket.process(cx, ip_repr, repr);
+        if socket.is_acceptable() {
+            // The connection can be accepted, wake the waiting task if any.
+            #[cfg(feature = "async")]
+            self.rx_waker.wake();
+        }
+        reply
+    }
+
     pub(crate) fn process(
         &mut self,
         cx: &mut Context,
@@ -1459,6 +1639,10 @@ impl<'a> Socket<'a> {
     ) -> Option<(IpRepr, TcpRepr<'static>)> {
         debug_assert!(self.accepts(cx, ip_repr, repr));
 
+        if self.state == State::Listen && !self.backlog.is_empty() {
+            return self.process_backlog(cx, ip_repr, repr);
+        }
+
         // Consider how much the sequence number space differs from the transmit buffer space.
         let (sent_syn, sent_fin) = match self.state {
             // In SYN-SENT or SYN-RECEIVED, we've just sent a SYN.
@@ -1788,6 +1972,7 @@ impl<'a> Socket<'a> {
                 self.remote_seq_no = repr.seq_number + 1;
                 self.remote_last_seq = self.local_seq_no + 1;
                 self.remote_last_ack = Some(repr.seq_number);
+                self.remote_has_sack = repr.sack_permitted;
                 self.remote_win_scale = repr.window_scale;
                 // Remote doesn't support window scaling, don't do it.
                 if self.remote_win_scale.is_none() {
@@ -1973,6 +2158,11 @@ impl<'a> Socket<'a> {
             };
             // We've processed everything in the incoming segment, so advance the local
             // sequence number past it.
+            if ack_number > self.local_seq_no {
+                self.remote_sacked.advance(ack_number - self.local_seq_no);
+                // New data was acknowledged, so the remote end is reachable.
+                cx.confirm_reachable(&ip_repr.src_addr());
+            }
             self.local_seq_no = ack_number;
             // During retransmission, if an earlier segment got lost but later was
             // successfully received, self.local_seq_no can move past self.remote_last_seq.
@@ -1982,6 +2172,15 @@ impl<'a> Socket<'a> {
             if self.remote_last_seq < self.local_seq_no {
                 self.remote_last_seq = self.local_seq_no
             }
+
+            if self.remote_has_sack {
+                self.process_sack_ranges(&repr.sack_ranges);
+            }
+            if matches!(self.recovery_point, Some(point) if point <= self.local_seq_no) {
+                tcp_trace!("loss recovery finished");
+                self.recovery_point = None;
+            }
+            self.skip_sacked();
         }
 
         // update last remote tsval
@@ -2082,16 +2281,69 @@ impl<'a> Socket<'a> {
         }
     }
 
+    /// Return the highest sequence number sent so far, which is further than the next sequence
+    /// number to send while retransmitting during loss recovery.
+    fn high_data(&self) -> TcpSeqNumber {
+        match self.recovery_point {
+            Some(point) if point > self.remote_last_seq => point,
+            _ => self.remote_last_seq,
+        }
+    }
+
+    /// Add the ranges that the remote has selectively acknowledged to the scoreboard.
+    fn process_sack_ranges(&mut self, sack_ranges: &[Option<(u32, u32)>]) {
+        let high_data = self.high_data();
+        let buffer_end = self.local_seq_no + self.tx_buffer.len();
+        for &(left, right) in sack_ranges.iter().flatten() {
+            let (left, right) = (TcpSeqNumber(left as i32), TcpSeqNumber(right as i32));
+            // Ignore ranges that were already acknowledged (RFC 2883), and ranges covering data
+            // that was never sent.
+            if left <= self.local_seq_no || right <= left || right > high_data || right > buffer_end
+            {
+                continue;
+            }
+            let offset = left - self.local_seq_no;
+            if self.remote_sacked.add(offset, right - left).is_err() {
+                net_debug!(
+                    "too many holes in SACK scoreboard, ignoring {}..{}",
+                    left,
+                    right
+                );
+            }
+        }
+    }
+
+    /// Advance the next sequence number to send past the ranges that the remote has selectively
+    /// acknowledged. During loss recovery, once all holes are retransmitted, continue with the
+    /// data that was never sent.
+    fn skip_sacked(&mut self) {
+        let mut offset = self.remote_last_seq - self.local_seq_no;
+        let mut highest_sacked = 0;
+        for (left, right) in self.remote_sacked.iter_data(0) {
+            if left <= offset && offset < right {
+                offset = right;
+            }
+            highest_sacked = right;
+        }
+        if let Some(point) = self.recovery_point {
+            if offset >= highest_sacked {
+                offset = offset.max(point - self.local_seq_no);
+            }
+        }
+        self.remote_last_seq = self.local_seq_no + offset;
+    }
+
     fn seq_to_transmit(&self, cx: &mut Context) -> bool {
-        let ip_header_len = match self.tuple.unwrap().local.addr {
+        let tuple = self.tuple.unwrap();
+        let ip_header_len = match tuple.local.addr {
             #[cfg(feature = "proto-ipv4")]
             IpAddress::Ipv4(_) => crate::wire::IPV4_HEADER_LEN,
             #[cfg(feature = "proto-ipv6")]
             IpAddress::Ipv6(_) => crate::wire::IPV6_HEADER_LEN,
         };
 
-        // Max segment size we're able to send due to MTU limitations.
-        let local_mss = cx.ip_mtu() - ip_header_len - TCP_HEADER_LEN;
+        // Max segment size we're able to send due to path MTU limitations.
+        let local_mss = cx.path_mtu(&tuple.remote.addr) - ip_header_len - TCP_HEADER_LEN;
 
         // The effective max segment size, taking into account our and remote's limits.
         let effective_mss = local_mss.min(self.remote_mss);
@@ -2177,6 +2429,17 @@ impl<'a> Socket<'a> {
     }
 
     pub(crate) fn dispatch<F, E>(&mut self, cx: &mut Context, emit: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut Context, (IpRepr, TcpRepr)) -> Result<(), E>,
+    {
+        if self.state == State::Listen && !self.backlog.is_empty() {
+            self.dispatch_backlog(cx, emit)
+        } else {
+            self.dispatch_connection(cx, emit)
+        }
+    }
+
+    fn dispatch_connection<F, E>(&mut self, cx: &mut Context, emit: F) -> Result<(), E>
     where
         F: FnOnce(&mut Context, (IpRepr, TcpRepr)) -> Result<(), E>,
     {
@@ -2209,10 +2472,24 @@ impl<'a> Socket<'a> {
                 // If a retransmit timer expired, we should resend data starting at the last ACK.
                 net_debug!("retransmitting at t+{}", retransmit_delta);
 
+                if self.timer == Timer::FastRetransmit && !self.remote_sacked.is_empty() {
+                    // RFC 6675: use the scoreboard to only retransmit the holes up to the
+                    // highest octet sent so far.
+                    let high_data = self.high_data();
+                    tcp_trace!("loss recovery until {}", high_data);
+                    self.recovery_point = Some(high_data);
+                } else {
+                    // RFC 6675 § 5.1: after a retransmission timeout, the SACK information
+                    // must be discarded, as the remote may have dropped SACKed data.
+                    self.remote_sacked.clear();
+                    self.recovery_point = None;
+                }
+
                 // Rewind "last sequence number sent", as if we never
                 // had sent them. This will cause all data in the queue
-                // to be sent again.
+                // to be sent again, except the ranges acknowledged by SACK.
                 self.remote_last_seq = self.local_seq_no;
+                self.skip_sacked();
 
                 // Clear the `should_retransmit` state. If we can't retransmit right
                 // now for whatever reason (like zero window), this avoids an
@@ -2342,12 +2619,22 @@ impl<'a> Socket<'a> {
                 // Maximum size we're allowed to send. This can be limited by 3 factors:
                 // 1. remote window
                 // 2. MSS the remote is willing to accept, probably determined by their MTU
-                // 3. MSS we can send, determined by our MTU.
-                let size = win_limit
-                    .min(self.remote_mss)
-                    .min(cx.ip_mtu() - ip_repr.header_len() - TCP_HEADER_LEN);
+                // 3. MSS we can send, determined by the path MTU.
+                let local_mss =
+                    cx.path_mtu(&ip_repr.dst_addr()) - ip_repr.header_len() - TCP_HEADER_LEN;
+                let size = win_limit.min(self.remote_mss).min(local_mss);
 
                 let offset = self.remote_last_seq - self.local_seq_no;
+
+                // Stop at the next range that the remote has selectively acknowledged.
+                let size = match self
+                    .remote_sacked
+                    .iter_data(0)
+                    .find(|&(left, _)| left > offset)
+                {
+                    Some((left, _)) => size.min(left - offset),
+                    None => size,
+                };
                 repr.payload = self.tx_buffer.get_allocated(offset, size);
 
                 // If we've sent everything we had in the buffer, follow it with the PSH or FIN
@@ -2448,15 +2735,18 @@ impl<'a> Socket<'a> {
         self.remote_last_ack = repr.ack_number;
         self.remote_last_win = repr.window_len;
 
-        if repr.segment_len() > 0 {
-            self.rtte
-                .on_send(cx.now(), repr.seq_number + repr.segment_len());
+        let segment_len = repr.segment_len();
+        if segment_len > 0 {
+            self.rtte.on_send(cx.now(), self.remote_last_seq);
             self.congestion_controller
                 .inner_mut()
-                .post_transmit(cx.now(), repr.segment_len());
+                .post_transmit(cx.now(), segment_len);
         }
 
-        if !self.seq_to_transmit(cx) && repr.segment_len() > 0 {
+        // Don't retransmit what follows if the remote has selectively acknowledged it.
+        self.skip_sacked();
+
+        if !self.seq_to_transmit(cx) && segment_len > 0 {
             // If we've transmitted all data we could (and there was something at all,
             // data or flag, to transmit, not just an ACK), wind up the retransmit timer.
             self.timer
@@ -2476,10 +2766,40 @@ impl<'a> Socket<'a> {
         Ok(())
     }
 
+    /// Dispatch a segment of the first connection in the backlog that has one to send.
+    fn dispatch_backlog<F, E>(&mut self, cx: &mut Context, emit: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut Context, (IpRepr, TcpRepr)) -> Result<(), E>,
+    {
+        let mut emit = Some(emit);
+        for socket in self.backlog.iter_mut() {
+            // NOTE(unwrap): we stop once a segment was emitted.
+            socket.dispatch_connection(cx, |cx, packet| (emit.take().unwrap())(cx, packet))?;
+
+            // Connections that were reset or timed out make room for new ones.
+            if socket.state == State::Closed && socket.tuple.is_none() {
+                socket.reset();
+                // NOTE(unwrap): the socket is closed, and the endpoint has a port.
+                socket.listen(self.listen_endpoint).unwrap();
+            }
+
+            if emit.is_none() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
     #[allow(clippy::if_same_then_else)]
     pub(crate) fn poll_at(&self, cx: &mut Context) -> PollAt {
         // The logic here mirrors the beginning of dispatch() closely.
-        if self.tuple.is_none() {
+        if self.state == State::Listen && !self.backlog.is_empty() {
+            self.backlog
+                .iter()
+                .map(|s| s.poll_at(cx))
+                .min()
+                .unwrap_or(PollAt::Ingress)
+        } else if self.tuple.is_none() {
             // No one to talk to, nothing to transmit.
             PollAt::Ingress
         } else if self.remote_last_ts.is_none() {
@@ -3122,6 +3442,211 @@ mod test {
         assert_eq!(s.state, State::Closed);
     }
 
+    fn socket_listen_with_backlog(backlog_len: usize) -> TestSocket {
+        let mut s = socket();
+        let backlog: Vec<_> = (0..backlog_len).map(|_| socket().socket).collect();
+        assert_eq!(s.listen_with_backlog(LISTEN_END, backlog), Ok(()));
+        s
+    }
+
+    #[test]
+    fn test_listen_backlog_accept() {
+        let mut s = socket_listen_with_backlog(2);
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: REMOTE_SEQ,
+                ack_number: None,
+                ..SEND_TEMPL
+            }
+        );
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                src_port: REMOTE_PORT + 1,
+                seq_number: REMOTE_SEQ,
+                ack_number: None,
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.state, State::Listen);
+        recv!(
+            s,
+            [
+                TcpRepr {
+                    control: TcpControl::Syn,
+                    seq_number: LOCAL_SEQ,
+                    ack_number: Some(REMOTE_SEQ + 1),
+                    max_seg_size: Some(BASE_MSS),
+                    ..RECV_TEMPL
+                },
+                TcpRepr {
+                    control: TcpControl::Syn,
+                    dst_port: REMOTE_PORT + 1,
+                    seq_number: LOCAL_SEQ,
+                    ack_number: Some(REMOTE_SEQ + 1),
+                    max_seg_size: Some(BASE_MSS),
+                    ..RECV_TEMPL
+                }
+            ]
+        );
+
+        let mut connection = socket().socket;
+        assert!(!s.can_accept());
+        assert_eq!(s.accept(&mut connection), Err(AcceptError::Exhausted));
+
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abc"[..],
+                ..SEND_TEMPL
+            }
+        );
+        assert!(s.can_accept());
+        assert_eq!(s.accept(&mut connection), Ok(()));
+        assert_eq!(s.state, State::Listen);
+        assert_eq!(connection.state, State::Established);
+        assert_eq!(connection.tuple, Some(TUPLE));
+        assert_eq!(connection.recv_slice(&mut [0; 4]), Ok(3));
+
+        // The other connection is still being established.
+        let mut other = socket().socket;
+        assert_eq!(s.accept(&mut other), Err(AcceptError::Exhausted));
+        assert_eq!(s.accept(&mut connection), Err(AcceptError::InvalidState));
+        assert_eq!(socket().accept(&mut other), Err(AcceptError::InvalidState));
+    }
+
+    #[test]
+    fn test_listen_backlog_accept_socket_set() {
+        let mut s = socket_listen_with_backlog(1);
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: REMOTE_SEQ,
+                ack_number: None,
+                ..SEND_TEMPL
+            }
+        );
+        recv!(
+            s,
+            [TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: LOCAL_SEQ,
+                ack_number: Some(REMOTE_SEQ + 1),
+                max_seg_size: Some(BASE_MSS),
+                ..RECV_TEMPL
+            }]
+        );
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                ..SEND_TEMPL
+            }
+        );
+
+        let mut sockets = crate::iface::SocketSet::new(Vec::new());
+        let listener = sockets.add(s.socket);
+        let handle = sockets.accept(listener, socket().socket).unwrap();
+        assert_eq!(sockets.get::<Socket>(handle).state, State::Established);
+        assert_eq!(sockets.get::<Socket>(handle).tuple, Some(TUPLE));
+        assert_eq!(sockets.get::<Socket>(listener).state, State::Listen);
+        assert_eq!(
+            sockets.accept(listener, socket().socket),
+            Err(AcceptError::Exhausted)
+        );
+    }
+
+    #[test]
+    fn test_listen_backlog_full() {
+        let mut s = socket_listen_with_backlog(1);
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: REMOTE_SEQ,
+                ack_number: None,
+                ..SEND_TEMPL
+            }
+        );
+        // The connection attempt is ignored rather than reset, so that it is retried.
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                src_port: REMOTE_PORT + 1,
+                seq_number: REMOTE_SEQ,
+                ack_number: None,
+                ..SEND_TEMPL
+            }
+        );
+        recv!(
+            s,
+            [TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: LOCAL_SEQ,
+                ack_number: Some(REMOTE_SEQ + 1),
+                max_seg_size: Some(BASE_MSS),
+                ..RECV_TEMPL
+            }]
+        );
+
+        // A reset connection makes room for a new one.
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Rst,
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: None,
+                ..SEND_TEMPL
+            }
+        );
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                src_port: REMOTE_PORT + 1,
+                seq_number: REMOTE_SEQ,
+                ack_number: None,
+                ..SEND_TEMPL
+            }
+        );
+        recv!(
+            s,
+            [TcpRepr {
+                control: TcpControl::Syn,
+                dst_port: REMOTE_PORT + 1,
+                seq_number: LOCAL_SEQ,
+                ack_number: Some(REMOTE_SEQ + 1),
+                max_seg_size: Some(BASE_MSS),
+                ..RECV_TEMPL
+            }]
+        );
+    }
+
+    #[test]
+    fn test_listen_backlog_close() {
+        let mut s = socket_listen_with_backlog(1);
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: REMOTE_SEQ,
+                ack_number: None,
+                ..SEND_TEMPL
+            }
+        );
+        s.close();
+        assert_eq!(s.state, State::Closed);
+        assert_eq!(s.backlog[0].state, State::Closed);
+    }
+
     // =========================================================================================//
     // Tests for the SYN-RECEIVED state.
     // =========================================================================================//
@@ -3500,6 +4025,37 @@ mod test {
         sanity!(s, socket_established());
     }
 
+    #[test]
+    fn test_syn_sent_syn_ack_sack_permitted() {
+        let mut s = socket_syn_sent();
+        recv!(
+            s,
+            [TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: LOCAL_SEQ,
+                ack_number: None,
+                max_seg_size: Some(BASE_MSS),
+                window_scale: Some(0),
+                sack_permitted: true,
+                ..RECV_TEMPL
+            }]
+        );
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: REMOTE_SEQ,
+                ack_number: Some(LOCAL_SEQ + 1),
+                max_seg_size: Some(BASE_MSS - 80),
+                window_scale: Some(0),
+                sack_permitted: true,
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.state, State::Established);
+        assert!(s.remote_has_sack);
+    }
+
     #[test]
     fn test_syn_sent_syn_ack_not_incremented() {
         let mut s = socket_syn_sent();
@@ -5757,6 +6313,165 @@ mod test {
         });
     }
 
+    #[test]
+    fn test_fast_retransmit_sack_holes() {
+        let mut s = socket_established();
+        s.remote_mss = 6;
+        s.remote_has_sack = true;
+        let sack = |left: usize, right: usize| {
+            Some(((LOCAL_SEQ + left).0 as u32, (LOCAL_SEQ + right).0 as u32))
+        };
+
+        s.send_slice(b"xxxxxxyyyyyywwwwwwzzzzzz").unwrap();
+        // The first and the third packets are lost
+        recv!(s, time 1000, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"xxxxxx"[..],
+            ..RECV_TEMPL
+        }));
+        recv!(s, time 1005, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 6,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"yyyyyy"[..],
+            ..RECV_TEMPL
+        }));
+        recv!(s, time 1010, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + (6 * 2),
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"wwwwww"[..],
+            ..RECV_TEMPL
+        }));
+        recv!(s, time 1015, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + (6 * 3),
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"zzzzzz"[..],
+            ..RECV_TEMPL
+        }));
+
+        send!(s, time 1050, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1),
+            ..SEND_TEMPL
+        });
+        // Duplicate ACKs reporting the received packets
+        send!(s, time 1055, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1),
+            sack_ranges: [sack(7, 13), None, None],
+            ..SEND_TEMPL
+        });
+        send!(s, time 1060, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1),
+            sack_ranges: [sack(19, 25), sack(7, 13), None],
+            ..SEND_TEMPL
+        });
+        send!(s, time 1065, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1),
+            sack_ranges: [sack(19, 25), sack(7, 13), None],
+            ..SEND_TEMPL
+        });
+        // Duplicated ranges and ranges that were never sent are ignored
+        send!(s, time 1070, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1),
+            sack_ranges: [sack(0, 1), sack(25, 31), sack(7, 13)],
+            ..SEND_TEMPL
+        });
+        assert_eq!(
+            s.remote_sacked.iter_data(0).collect::<Vec<_>>(),
+            vec![(6, 12), (18, 24)]
+        );
+
+        // Only the holes are retransmitted
+        recv!(s, time 1100, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"xxxxxx"[..],
+            ..RECV_TEMPL
+        }));
+        recv!(s, time 1105, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + (6 * 2),
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"wwwwww"[..],
+            ..RECV_TEMPL
+        }));
+        recv_nothing!(s, time 1110);
+        assert_eq!(s.recovery_point, Some(LOCAL_SEQ + 1 + (6 * 4)));
+
+        // The first hole is filled
+        send!(s, time 1120, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1 + (6 * 2)),
+            sack_ranges: [sack(19, 25), None, None],
+            ..SEND_TEMPL
+        });
+        assert_eq!(
+            s.remote_sacked.iter_data(0).collect::<Vec<_>>(),
+            vec![(6, 12)]
+        );
+        recv_nothing!(s, time 1125);
+
+        // All data is acknowledged, which ends the loss recovery
+        send!(s, time 1130, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1 + (6 * 4)),
+            ..SEND_TEMPL
+        });
+        assert!(s.remote_sacked.is_empty());
+        assert_eq!(s.recovery_point, None);
+        assert_eq!(s.remote_last_seq, LOCAL_SEQ + 1 + (6 * 4));
+    }
+
+    #[test]
+    fn test_retransmit_timeout_clears_sack() {
+        let mut s = socket_established();
+        s.remote_mss = 6;
+        s.remote_has_sack = true;
+
+        s.send_slice(b"xxxxxxyyyyyy").unwrap();
+        recv!(s, time 1000, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"xxxxxx"[..],
+            ..RECV_TEMPL
+        }));
+        recv!(s, time 1005, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 6,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"yyyyyy"[..],
+            ..RECV_TEMPL
+        }));
+        send!(s, time 1010, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1),
+            sack_ranges: [
+                Some(((LOCAL_SEQ + 7).0 as u32, (LOCAL_SEQ + 13).0 as u32)),
+                None,
+                None,
+            ],
+            ..SEND_TEMPL
+        });
+        assert!(!s.remote_sacked.is_empty());
+
+        // The remote may have discarded the SACKed data, so everything is retransmitted.
+        recv!(s, time 2000, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"xxxxxx"[..],
+            ..RECV_TEMPL
+        }));
+        assert!(s.remote_sacked.is_empty());
+        recv!(s, time 2005, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 6,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"yyyyyy"[..],
+            ..RECV_TEMPL
+        }));
+    }
+
     #[test]
     fn test_fast_retransmit_duplicate_detection_with_data() {
         let mut s = socket_established();
@@ -6110,6 +6825,29 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_path_mtu_segment_size() {
+        let mut s = socket_established();
+        s.tx_buffer = SocketBuffer::new(vec![0; 32767]);
+        s.remote_mss = usize::from(BASE_MSS);
+        s.remote_win_len = 32767;
+
+        // The path MTU leaves room for 800 octets of payload.
+        let path_mtu = s.cx.ip_mtu() - usize::from(BASE_MSS) + 800;
+        s.cx.set_path_mtu(REMOTE_ADDR.into(), path_mtu);
+
+        s.send_slice(&[0; 1200][..]).unwrap();
+        recv!(
+            s,
+            Ok(TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                payload: &[0; 800][..],
+                ..RECV_TEMPL
+            })
+        );
+    }
+
     #[test]
     fn test_close_wait_no_window_update() {
         let mut s = socket_established();
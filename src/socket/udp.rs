@@ -110,6 +110,29 @@ impl core::fmt::Display for RecvError {
 #[cfg(feature = "std")]
 impl std::error::Error for RecvError {}
 
+/// Error reported by the network for a datagram sent to a remote endpoint, see
+/// [`Socket::last_error`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum IcmpError {
+    /// The remote network or host cannot be reached.
+    Unreachable(IpEndpoint),
+    /// The remote host does not accept datagrams on the port.
+    PortUnreachable(IpEndpoint),
+}
+
+impl core::fmt::Display for IcmpError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            IcmpError::Unreachable(endpoint) => write!(f, "{endpoint} unreachable"),
+            IcmpError::PortUnreachable(endpoint) => write!(f, "port {endpoint} unreachable"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IcmpError {}
+
 /// A User Datagram Protocol socket.
 ///
 /// A UDP socket is bound to a specific endpoint, and owns transmit and receive
@@ -121,6 +144,8 @@ pub struct Socket<'a> {
     tx_buffer: PacketBuffer<'a>,
     /// The time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     hop_limit: Option<u8>,
+    /// The last error reported by an ICMP message.
+    last_error: Option<IcmpError>,
     #[cfg(feature = "async")]
     rx_waker: WakerRegistration,
     #[cfg(feature = "async")]
@@ -135,6 +160,7 @@ impl<'a> Socket<'a> {
             rx_buffer,
             tx_buffer,
             hop_limit: None,
+            last_error: None,
             #[cfg(feature = "async")]
             rx_waker: WakerRegistration::new(),
             #[cfg(feature = "async")]
@@ -210,6 +236,14 @@ impl<'a> Socket<'a> {
         self.hop_limit = hop_limit
     }
 
+    /// Return the last error reported by the network for a datagram sent by the socket, if any.
+    ///
+    /// Such errors are reported by ICMP messages, e.g. when the remote host does not listen on
+    /// the port a datagram was sent to. The error is cleared when the socket is closed.
+    pub fn last_error(&self) -> Option<IcmpError> {
+        self.last_error
+    }
+
     /// Bind the socket to the given endpoint.
     ///
     /// This function returns `Err(Error::Illegal)` if the socket was open
@@ -245,6 +279,8 @@ impl<'a> Socket<'a> {
         self.tx_buffer.reset();
         self.rx_buffer.reset();
 
+        self.last_error = None;
+
         #[cfg(feature = "async")]
         {
             self.rx_waker.wake();
@@ -474,6 +510,28 @@ impl<'a> Socket<'a> {
         true
     }
 
+    /// Check whether the socket sent a datagram from `local_endpoint`, which an ICMP error
+    /// message was received for.
+    pub(crate) fn accepts_icmp_error(&self, local_endpoint: &IpEndpoint) -> bool {
+        self.endpoint.port == local_endpoint.port
+            && self
+                .endpoint
+                .addr
+                .map_or(true, |addr| addr == local_endpoint.addr)
+    }
+
+    pub(crate) fn process_icmp_error(&mut self, error: IcmpError) {
+        net_trace!("udp:{}: {}", self.endpoint, error);
+
+        self.last_error = Some(error);
+
+        #[cfg(feature = "async")]
+        {
+            self.rx_waker.wake();
+            self.tx_waker.wake();
+        }
+    }
+
     pub(crate) fn process(
         &mut self,
         cx: &mut Context,
@@ -295,6 +295,25 @@ impl Assembler {
         }
     }
 
+    /// Remove `size` octets from the front of the assembler, whether they are data or hole,
+    /// so that the offsets of the remaining ranges are relative to the new front.
+    pub fn advance(&mut self, mut size: usize) {
+        while size != 0 && self.front().has_data() {
+            let front = &mut self.contigs[0];
+            if size < front.hole_size {
+                front.shrink_hole_by(size);
+                return;
+            }
+            if size < front.total_size() {
+                front.data_size = front.total_size() - size;
+                front.hole_size = 0;
+                return;
+            }
+            size -= front.total_size();
+            self.remove_contig_at(0);
+        }
+    }
+
     /// Add a segment, then remove_front.
     ///
     /// This is equivalent to calling `add` then `remove_front` individually,
@@ -534,6 +553,29 @@ mod test {
         assert_eq!(assr, exp_assr);
     }
 
+    #[test]
+    fn test_advance_within_hole() {
+        let mut assr = contigs![(4, 4), (2, 2)];
+        assr.advance(3);
+        assert_eq!(assr, contigs![(1, 4), (2, 2)]);
+    }
+
+    #[test]
+    fn test_advance_within_data() {
+        let mut assr = contigs![(4, 4), (2, 2)];
+        assr.advance(6);
+        assert_eq!(assr, contigs![(0, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn test_advance_past_contigs() {
+        let mut assr = contigs![(4, 4), (2, 2)];
+        assr.advance(9);
+        assert_eq!(assr, contigs![(1, 2)]);
+        assr.advance(100);
+        assert_eq!(assr, contigs![]);
+    }
+
     #[test]
     fn test_shrink_next_hole() {
         let mut assr = Assembler::new();
@@ -2,16 +2,17 @@
 
 use bitflags::bitflags;
 use byteorder::{ByteOrder, NetworkEndian};
-use core::iter;
+use core::{fmt, iter};
 use heapless::Vec;
 
 use super::{Error, Result};
 use crate::wire::arp::Hardware;
-use crate::wire::{EthernetAddress, Ipv4Address};
+use crate::wire::{EthernetAddress, Ipv4Address, Ipv4Cidr};
 
 pub const SERVER_PORT: u16 = 67;
 pub const CLIENT_PORT: u16 = 68;
 pub const MAX_DNS_SERVER_COUNT: usize = 3;
+pub const MAX_NTP_SERVER_COUNT: usize = 3;
 
 const DHCP_MAGIC_NUMBER: u32 = 0x63825363;
 
@@ -229,6 +230,12 @@ pub(crate) mod field {
     pub const OPT_REBINDING_TIME_VALUE: u8 = 59;
     pub const OPT_VENDOR_CLASS_ID: u8 = 60;
     pub const OPT_CLIENT_ID: u8 = 61;
+
+    // RFC 3397: Dynamic Host Configuration Protocol (DHCP) Domain Search Option
+    pub const OPT_DOMAIN_SEARCH: u8 = 119;
+
+    // RFC 3442: The Classless Static Route Option for DHCPv4
+    pub const OPT_CLASSLESS_STATIC_ROUTE: u8 = 121;
 }
 
 impl<T: AsRef<[u8]>> Packet<T> {
@@ -415,6 +422,38 @@ impl<T: AsRef<[u8]>> Packet<T> {
         let data = core::str::from_utf8(&data[..len]).map_err(|_| Error)?;
         Ok(data)
     }
+
+    /// Return the data of the first option of the given kind.
+    fn option(&self, kind: u8) -> Option<&[u8]> {
+        self.options()
+            .find(|option| option.kind == kind)
+            .map(|option| option.data)
+    }
+
+    /// Return the "host name" option. It can be used by clients to announce their name, or by
+    /// servers to assign one.
+    pub fn hostname(&self) -> Option<&str> {
+        let data = self.option(field::OPT_HOST_NAME)?;
+        core::str::from_utf8(data).ok().filter(|s| !s.is_empty())
+    }
+
+    /// Return the domain name the client should use when resolving hostnames.
+    pub fn domain_name(&self) -> Option<&str> {
+        let data = self.option(field::OPT_DOMAIN_NAME)?;
+        core::str::from_utf8(data).ok().filter(|s| !s.is_empty())
+    }
+
+    /// Return the domain search list (see RFC 3397).
+    pub fn domain_search(&self) -> Option<DomainSearch<'_>> {
+        self.option(field::OPT_DOMAIN_SEARCH)
+            .filter(|data| !data.is_empty())
+            .map(DomainSearch::new)
+    }
+
+    /// Return the "vendor specific information" option, whose contents are opaque.
+    pub fn vendor_specific(&self) -> Option<&[u8]> {
+        self.option(field::OPT_VENDOR_SPECIFIC_INFO)
+    }
 }
 
 impl<T: AsRef<[u8]> + AsMut<[u8]>> Packet<T> {
@@ -539,6 +578,203 @@ impl<'a, T: AsRef<[u8]> + AsMut<[u8]> + ?Sized> Packet<&'a mut T> {
     }
 }
 
+/// A domain search list, as carried by the domain search option (see RFC 3397).
+///
+/// The domain names are encoded as sequences of labels (see RFC 1035 § 3.1), and may be
+/// compressed with pointers relative to the start of the list.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DomainSearch<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DomainSearch<'a> {
+    /// Create a domain search list from its encoded representation.
+    pub const fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Return the encoded representation of the list.
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Return an iterator over the domain names of the list.
+    ///
+    /// The iteration stops at the first malformed name.
+    pub fn iter(&self) -> impl Iterator<Item = DomainSearchName<'a>> {
+        let data = self.data;
+        let mut pos = 0;
+        iter::from_fn(move || {
+            let start = pos;
+            loop {
+                let len = *data.get(pos)? as usize;
+                match len {
+                    0 => {
+                        pos += 1;
+                        break;
+                    }
+                    1..=63 => {
+                        pos += 1 + len;
+                        if pos > data.len() {
+                            return None;
+                        }
+                    }
+                    0xc0..=0xff => {
+                        let low = *data.get(pos + 1)? as usize;
+                        // Pointers may only refer to names earlier in the list.
+                        if ((len & 0x3f) << 8 | low) >= start {
+                            return None;
+                        }
+                        pos += 2;
+                        break;
+                    }
+                    _ => return None,
+                }
+            }
+            Some(DomainSearchName { data, start })
+        })
+    }
+}
+
+/// A domain name of a [`DomainSearch`] list.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DomainSearchName<'a> {
+    data: &'a [u8],
+    start: usize,
+}
+
+impl<'a> DomainSearchName<'a> {
+    /// Return an iterator over the labels of the name, following compression pointers.
+    ///
+    /// The iteration stops at the first malformed label.
+    pub fn labels(&self) -> impl Iterator<Item = &'a [u8]> {
+        let data = self.data;
+        let mut pos = self.start;
+        iter::from_fn(move || loop {
+            let len = *data.get(pos)? as usize;
+            match len {
+                0 => return None,
+                1..=63 => {
+                    let label = data.get(pos + 1..pos + 1 + len)?;
+                    pos += 1 + len;
+                    return Some(label);
+                }
+                0xc0..=0xff => {
+                    let target = (len & 0x3f) << 8 | *data.get(pos + 1)? as usize;
+                    // Only following pointers backwards guarantees termination.
+                    if target >= pos {
+                        return None;
+                    }
+                    pos = target;
+                }
+                _ => return None,
+            }
+        })
+    }
+}
+
+impl<'a> fmt::Display for DomainSearchName<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, label) in self.labels().enumerate() {
+            if i != 0 {
+                write!(f, ".")?;
+            }
+            for &c in label {
+                write!(f, "{}", c as char)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A route of the classless static route option (see RFC 3442).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ClasslessRoute {
+    /// The destination prefix. A prefix length of zero designates the default route.
+    pub destination: Ipv4Cidr,
+    /// The router to forward the packets to. The unspecified address designates a destination
+    /// that is on the link.
+    pub router: Ipv4Address,
+}
+
+impl ClasslessRoute {
+    /// Return the length of the encoded representation of the route.
+    pub const fn buffer_len(&self) -> usize {
+        1 + (self.destination.prefix_len() as usize).div_ceil(8) + 4
+    }
+
+    /// Emit the encoded representation of the route into a buffer of
+    /// [`buffer_len`](Self::buffer_len) octets.
+    pub fn emit(&self, buffer: &mut [u8]) {
+        let prefix_len = self.destination.prefix_len();
+        let significant = (prefix_len as usize).div_ceil(8);
+        buffer[0] = prefix_len;
+        buffer[1..1 + significant]
+            .copy_from_slice(&self.destination.network().address().as_bytes()[..significant]);
+        buffer[1 + significant..5 + significant].copy_from_slice(self.router.as_bytes());
+    }
+}
+
+/// A list of routes, as carried by the classless static route option (see RFC 3442).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ClasslessRoutes<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ClasslessRoutes<'a> {
+    /// Create a route list from its encoded representation.
+    pub const fn new_unchecked(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Create a route list from its encoded representation, and ensure that every route
+    /// is well-formed.
+    pub fn new_checked(data: &'a [u8]) -> Result<Self> {
+        let routes = Self::new_unchecked(data);
+        let len: usize = routes.iter().map(|route| route.buffer_len()).sum();
+        if len != data.len() {
+            return Err(Error);
+        }
+        Ok(routes)
+    }
+
+    /// Return the encoded representation of the list.
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Return an iterator over the routes of the list.
+    ///
+    /// The iteration stops at the first malformed route.
+    pub fn iter(&self) -> impl Iterator<Item = ClasslessRoute> + 'a {
+        let mut buf = self.data;
+        iter::from_fn(move || {
+            let prefix_len = *buf.first()?;
+            if prefix_len > 32 {
+                return None;
+            }
+            let significant = (prefix_len as usize).div_ceil(8);
+            if buf.len() < 1 + significant + 4 {
+                return None;
+            }
+
+            let mut destination = [0; 4];
+            destination[..significant].copy_from_slice(&buf[1..1 + significant]);
+            let router = Ipv4Address::from_bytes(&buf[1 + significant..5 + significant]);
+            buf = &buf[5 + significant..];
+
+            Some(ClasslessRoute {
+                destination: Ipv4Cidr::new(Ipv4Address(destination), prefix_len).network(),
+                router,
+            })
+        })
+    }
+}
+
 /// A high-level representation of a Dynamic Host Configuration Protocol packet.
 ///
 /// DHCP messages have the following layout (see [RFC 2131](https://tools.ietf.org/html/rfc2131)
@@ -655,6 +891,12 @@ pub struct Repr<'a> {
     pub renew_duration: Option<u32>,
     /// The DHCP IP rebind duration (T2 interval), in seconds, if specified in the packet.
     pub rebind_duration: Option<u32>,
+    /// NTP servers
+    pub ntp_servers: Option<Vec<Ipv4Address, MAX_NTP_SERVER_COUNT>>,
+    /// The MTU to use on the interface.
+    pub interface_mtu: Option<u16>,
+    /// The classless static routes. When present, the "router" option should be ignored.
+    pub classless_routes: Option<ClasslessRoutes<'a>>,
     /// When returned from [`Repr::parse`], this field will be `None`.
     /// However, when calling [`Repr::emit`], this field should contain only
     /// additional DHCP options not known to smoltcp.
@@ -695,6 +937,16 @@ impl<'a> Repr<'a> {
         if let Some(list) = self.parameter_request_list {
             len += list.len() + 2;
         }
+        if let Some(ntp_servers) = &self.ntp_servers {
+            len += 2;
+            len += ntp_servers.iter().count() * core::mem::size_of::<u32>();
+        }
+        if self.interface_mtu.is_some() {
+            len += 4;
+        }
+        if let Some(routes) = &self.classless_routes {
+            len += routes.as_bytes().len() + 2;
+        }
         for opt in self.additional_options {
             len += 2 + opt.data.len()
         }
@@ -742,6 +994,9 @@ impl<'a> Repr<'a> {
         let mut lease_duration = None;
         let mut renew_duration = None;
         let mut rebind_duration = None;
+        let mut ntp_servers = None;
+        let mut interface_mtu = None;
+        let mut classless_routes = None;
 
         for option in packet.options() {
             let data = option.data;
@@ -802,6 +1057,27 @@ impl<'a> Repr<'a> {
                         net_trace!("DHCP domain name servers contained invalid address");
                     }
                 }
+                (field::OPT_NTP_SERVERS, _) => {
+                    let mut servers = Vec::new();
+                    let mut addrs = data.chunks_exact(4);
+                    for chunk in &mut addrs {
+                        servers.push(Ipv4Address::from_bytes(chunk)).ok();
+                    }
+                    ntp_servers = Some(servers);
+
+                    if !addrs.remainder().is_empty() {
+                        net_trace!("DHCP NTP servers contained invalid address");
+                    }
+                }
+                (field::OPT_INTERFACE_MTU, 2) => {
+                    interface_mtu = Some(u16::from_be_bytes([data[0], data[1]]));
+                }
+                (field::OPT_CLASSLESS_STATIC_ROUTE, _) => {
+                    match ClasslessRoutes::new_checked(data) {
+                        Ok(routes) => classless_routes = Some(routes),
+                        Err(_) => net_trace!("DHCP classless static routes were malformed"),
+                    }
+                }
                 _ => {}
             }
         }
@@ -828,6 +1104,9 @@ impl<'a> Repr<'a> {
             lease_duration,
             renew_duration,
             rebind_duration,
+            ntp_servers,
+            interface_mtu,
+            classless_routes,
             message_type: message_type?,
             additional_options: &[],
         })
@@ -940,6 +1219,30 @@ impl<'a> Repr<'a> {
                 })?;
             }
 
+            if let Some(ntp_servers) = &self.ntp_servers {
+                const IP_SIZE: usize = core::mem::size_of::<u32>();
+                let mut servers = [0; MAX_NTP_SERVER_COUNT * IP_SIZE];
+                for (i, ip) in ntp_servers.iter().enumerate() {
+                    servers[(i * IP_SIZE)..((i + 1) * IP_SIZE)].copy_from_slice(ip.as_bytes());
+                }
+                options.emit(DhcpOption {
+                    kind: field::OPT_NTP_SERVERS,
+                    data: &servers[..ntp_servers.len() * IP_SIZE],
+                })?;
+            }
+            if let Some(val) = &self.interface_mtu {
+                options.emit(DhcpOption {
+                    kind: field::OPT_INTERFACE_MTU,
+                    data: &val.to_be_bytes(),
+                })?;
+            }
+            if let Some(val) = &self.classless_routes {
+                options.emit(DhcpOption {
+                    kind: field::OPT_CLASSLESS_STATIC_ROUTE,
+                    data: val.as_bytes(),
+                })?;
+            }
+
             for option in self.additional_options {
                 options.emit(*option)?;
             }
@@ -1170,6 +1473,9 @@ mod test {
             max_size: None,
             renew_duration: None,
             rebind_duration: None,
+            ntp_servers: None,
+            interface_mtu: None,
+            classless_routes: None,
             lease_duration: Some(0xffff_ffff), // Infinite lease
             additional_options: &[],
         }
@@ -1191,6 +1497,9 @@ mod test {
             max_size: Some(DHCP_SIZE),
             renew_duration: None,
             rebind_duration: None,
+            ntp_servers: None,
+            interface_mtu: None,
+            classless_routes: None,
             lease_duration: None,
             requested_ip: Some(IP_NULL),
             client_identifier: Some(CLIENT_MAC),
@@ -1313,4 +1622,132 @@ mod test {
         // duration of 598s.
         assert_eq!(repr.lease_duration, Some(598));
     }
+
+    // RFC 3397 § 3: "eng.apple.com" and "marketing.apple.com", the second one compressed.
+    static DOMAIN_SEARCH_BYTES: &[u8] = &[
+        0x03, b'e', b'n', b'g', 0x05, b'a', b'p', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00,
+        0x09, b'm', b'a', b'r', b'k', b'e', b't', b'i', b'n', b'g', 0xc0, 0x04,
+    ];
+
+    // 10.0.0.0/8 via 192.168.1.2, 192.168.16.0/20 on-link, and the default route via
+    // 192.168.1.1.
+    static CLASSLESS_ROUTES_BYTES: &[u8] = &[
+        8, 10, 192, 168, 1, 2, //
+        20, 192, 168, 16, 0, 0, 0, 0, //
+        0, 192, 168, 1, 1,
+    ];
+
+    #[test]
+    fn test_emit_offer_options() {
+        let repr = {
+            let mut repr = offer_repr();
+            repr.ntp_servers = Some(Vec::from_slice(&[Ipv4Address([192, 168, 1, 3])]).unwrap());
+            repr.interface_mtu = Some(1400);
+            repr.classless_routes = Some(ClasslessRoutes::new_unchecked(CLASSLESS_ROUTES_BYTES));
+            repr
+        };
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        repr.emit(&mut packet).unwrap();
+
+        let packet = Packet::new_unchecked(&bytes);
+        assert_eq!(Repr::parse(&packet).unwrap(), repr);
+    }
+
+    #[test]
+    fn test_parse_offer_text_options() {
+        let options = [
+            DhcpOption {
+                kind: field::OPT_HOST_NAME,
+                data: b"host",
+            },
+            DhcpOption {
+                kind: field::OPT_DOMAIN_NAME,
+                data: b"example.com",
+            },
+            DhcpOption {
+                kind: field::OPT_DOMAIN_SEARCH,
+                data: DOMAIN_SEARCH_BYTES,
+            },
+            DhcpOption {
+                kind: field::OPT_VENDOR_SPECIFIC_INFO,
+                data: &[1, 2, 0xaa, 0xbb],
+            },
+        ];
+        let repr = Repr {
+            additional_options: &options,
+            ..offer_repr()
+        };
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        repr.emit(&mut packet).unwrap();
+
+        let packet = Packet::new_unchecked(&bytes);
+        assert_eq!(packet.hostname(), Some("host"));
+        assert_eq!(packet.domain_name(), Some("example.com"));
+        assert_eq!(
+            packet.domain_search(),
+            Some(DomainSearch::new(DOMAIN_SEARCH_BYTES))
+        );
+        assert_eq!(packet.vendor_specific(), Some(&[1, 2, 0xaa, 0xbb][..]));
+
+        let packet = Packet::new_unchecked(DISCOVER_BYTES);
+        assert_eq!(packet.hostname(), None);
+        assert_eq!(packet.domain_search(), None);
+    }
+
+    #[test]
+    fn test_domain_search() {
+        let list = DomainSearch::new(DOMAIN_SEARCH_BYTES);
+        let names: std::vec::Vec<_> = list.iter().map(|name| name.to_string()).collect();
+        assert_eq!(names, ["eng.apple.com", "marketing.apple.com"]);
+
+        // The list stops at a pointer that does not refer to an earlier name.
+        let list = DomainSearch::new(&[0x01, b'a', 0x00, 0x01, b'b', 0xc0, 0x03]);
+        let names: std::vec::Vec<_> = list.iter().map(|name| name.to_string()).collect();
+        assert_eq!(names, ["a"]);
+
+        // The list stops at a truncated name.
+        let list = DomainSearch::new(&DOMAIN_SEARCH_BYTES[..20]);
+        assert_eq!(list.iter().count(), 1);
+    }
+
+    #[test]
+    fn test_classless_routes() {
+        let routes = ClasslessRoutes::new_checked(CLASSLESS_ROUTES_BYTES).unwrap();
+        let routes: std::vec::Vec<_> = routes.iter().collect();
+        assert_eq!(
+            routes,
+            [
+                ClasslessRoute {
+                    destination: Ipv4Cidr::new(Ipv4Address([10, 0, 0, 0]), 8),
+                    router: Ipv4Address([192, 168, 1, 2]),
+                },
+                ClasslessRoute {
+                    destination: Ipv4Cidr::new(Ipv4Address([192, 168, 16, 0]), 20),
+                    router: Ipv4Address::UNSPECIFIED,
+                },
+                ClasslessRoute {
+                    destination: Ipv4Cidr::new(Ipv4Address::UNSPECIFIED, 0),
+                    router: Ipv4Address([192, 168, 1, 1]),
+                },
+            ]
+        );
+
+        let mut bytes = [0; 19];
+        let mut offset = 0;
+        for route in &routes {
+            route.emit(&mut bytes[offset..offset + route.buffer_len()]);
+            offset += route.buffer_len();
+        }
+        assert_eq!(&bytes[..], CLASSLESS_ROUTES_BYTES);
+    }
+
+    #[test]
+    fn test_classless_routes_malformed() {
+        // Prefix length larger than 32.
+        assert!(ClasslessRoutes::new_checked(&[33, 10, 0, 0, 0, 0, 192, 168, 1, 2]).is_err());
+        // Truncated router.
+        assert!(ClasslessRoutes::new_checked(&[8, 10, 192, 168, 1]).is_err());
+    }
 }
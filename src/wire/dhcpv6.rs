@@ -0,0 +1,1043 @@
+// See https://tools.ietf.org/html/rfc8415 for the DHCPv6 specification.
+
+use byteorder::{ByteOrder, NetworkEndian};
+use core::fmt;
+use core::iter;
+use heapless::Vec;
+
+use super::{Error, Result};
+use crate::wire::{Ipv6Address, Ipv6Cidr};
+
+pub const SERVER_PORT: u16 = 547;
+pub const CLIENT_PORT: u16 = 546;
+pub const MAX_DNS_SERVER_COUNT: usize = 3;
+pub const MAX_REQUESTED_OPTION_COUNT: usize = 8;
+/// The maximum length of a DUID: a 2-octet type code followed by at most 128 octets.
+pub const MAX_DUID_LEN: usize = 130;
+
+/// The link-scoped multicast address used by a client to communicate with the neighboring
+/// relay agents and servers.
+pub const ALL_RELAY_AGENTS_AND_SERVERS: Ipv6Address = Ipv6Address::new(0xff02, 0, 0, 0, 0, 0, 1, 2);
+
+enum_with_unknown! {
+    /// The possible message types of a DHCPv6 packet.
+    pub enum MessageType(u8) {
+        Solicit = 1,
+        Advertise = 2,
+        Request = 3,
+        Confirm = 4,
+        Renew = 5,
+        Rebind = 6,
+        Reply = 7,
+        Release = 8,
+        Decline = 9,
+        Reconfigure = 10,
+        InformationRequest = 11,
+        RelayForw = 12,
+        RelayRepl = 13,
+    }
+}
+
+enum_with_unknown! {
+    /// The possible status codes of a DHCPv6 status code option.
+    pub enum StatusCode(u16) {
+        Success = 0,
+        UnspecFail = 1,
+        NoAddrsAvail = 2,
+        NoBinding = 3,
+        NotOnLink = 4,
+        UseMulticast = 5,
+        NoPrefixAvail = 6,
+    }
+}
+
+/// A buffer for DHCPv6 options.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DhcpOptionWriter<'a> {
+    /// The underlying buffer, directly from the DHCPv6 packet representation.
+    buffer: &'a mut [u8],
+}
+
+impl<'a> DhcpOptionWriter<'a> {
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Self { buffer }
+    }
+
+    /// Emit a [`DhcpOption`] into a [`DhcpOptionWriter`].
+    pub fn emit(&mut self, option: DhcpOption<'_>) -> Result<()> {
+        self.emit_with(option.kind, option.data.len(), |buf| {
+            buf.copy_from_slice(option.data)
+        })
+    }
+
+    /// Emit an option of `len` octets, whose data is written by `f`.
+    fn emit_with(&mut self, kind: u16, len: usize, f: impl FnOnce(&mut [u8])) -> Result<()> {
+        if len > u16::MAX as _ {
+            return Err(Error);
+        }
+
+        let total_len = 4 + len;
+        if self.buffer.len() < total_len {
+            return Err(Error);
+        }
+
+        let (buf, rest) = core::mem::take(&mut self.buffer).split_at_mut(total_len);
+        self.buffer = rest;
+
+        NetworkEndian::write_u16(&mut buf[0..2], kind);
+        NetworkEndian::write_u16(&mut buf[2..4], len as u16);
+        f(&mut buf[4..]);
+
+        Ok(())
+    }
+}
+
+/// A representation of a single DHCPv6 option.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DhcpOption<'a> {
+    pub kind: u16,
+    pub data: &'a [u8],
+}
+
+/// Return an iterator over the options encoded in `buf`, such as the options of a message or
+/// the options encapsulated in another option.
+fn options(mut buf: &[u8]) -> impl Iterator<Item = DhcpOption<'_>> + '_ {
+    iter::from_fn(move || {
+        if buf.len() < 4 {
+            return None;
+        }
+
+        let kind = NetworkEndian::read_u16(&buf[0..2]);
+        let len = NetworkEndian::read_u16(&buf[2..4]) as usize;
+        if buf.len() < 4 + len {
+            return None;
+        }
+
+        let opt = DhcpOption {
+            kind,
+            data: &buf[4..4 + len],
+        };
+        buf = &buf[4 + len..];
+        Some(opt)
+    })
+}
+
+/// A read/write wrapper around a DHCPv6 packet buffer.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Packet<T: AsRef<[u8]>> {
+    buffer: T,
+}
+
+pub(crate) mod field {
+    #![allow(non_snake_case)]
+    #![allow(unused)]
+
+    use crate::wire::field::*;
+
+    pub const MSG_TYPE: usize = 0;
+    pub const TRANSACTION_ID: Field = 1..4;
+    pub const OPTIONS: Rest = 4..;
+
+    // Options, see RFC 8415 § 21.
+    pub const OPT_CLIENTID: u16 = 1;
+    pub const OPT_SERVERID: u16 = 2;
+    pub const OPT_IA_NA: u16 = 3;
+    pub const OPT_IA_TA: u16 = 4;
+    pub const OPT_IAADDR: u16 = 5;
+    pub const OPT_ORO: u16 = 6;
+    pub const OPT_PREFERENCE: u16 = 7;
+    pub const OPT_ELAPSED_TIME: u16 = 8;
+    pub const OPT_RELAY_MSG: u16 = 9;
+    pub const OPT_AUTH: u16 = 11;
+    pub const OPT_UNICAST: u16 = 12;
+    pub const OPT_STATUS_CODE: u16 = 13;
+    pub const OPT_RAPID_COMMIT: u16 = 14;
+    pub const OPT_USER_CLASS: u16 = 15;
+    pub const OPT_VENDOR_CLASS: u16 = 16;
+    pub const OPT_VENDOR_OPTS: u16 = 17;
+    pub const OPT_INTERFACE_ID: u16 = 18;
+    pub const OPT_RECONF_MSG: u16 = 19;
+    pub const OPT_RECONF_ACCEPT: u16 = 20;
+    pub const OPT_DNS_SERVERS: u16 = 23;
+    pub const OPT_DOMAIN_LIST: u16 = 24;
+    pub const OPT_IA_PD: u16 = 25;
+    pub const OPT_IAPREFIX: u16 = 26;
+    pub const OPT_INFORMATION_REFRESH_TIME: u16 = 32;
+    pub const OPT_SOL_MAX_RT: u16 = 82;
+    pub const OPT_INF_MAX_RT: u16 = 83;
+
+    // IA_NA and IA_PD options, see RFC 8415 § 21.4 and § 21.21.
+    pub const IA_IAID: Field = 0..4;
+    pub const IA_T1: Field = 4..8;
+    pub const IA_T2: Field = 8..12;
+    pub const IA_OPTIONS: Rest = 12..;
+
+    // IA Address option, see RFC 8415 § 21.6.
+    pub const IAADDR_ADDRESS: Field = 0..16;
+    pub const IAADDR_PREFERRED: Field = 16..20;
+    pub const IAADDR_VALID: Field = 20..24;
+    pub const IAADDR_OPTIONS: Rest = 24..;
+
+    // IA Prefix option, see RFC 8415 § 21.22.
+    pub const IAPREFIX_PREFERRED: Field = 0..4;
+    pub const IAPREFIX_VALID: Field = 4..8;
+    pub const IAPREFIX_PREFIX_LEN: usize = 8;
+    pub const IAPREFIX_PREFIX: Field = 9..25;
+    pub const IAPREFIX_OPTIONS: Rest = 25..;
+}
+
+impl<T: AsRef<[u8]>> Packet<T> {
+    /// Imbue a raw octet buffer with DHCPv6 packet structure.
+    pub const fn new_unchecked(buffer: T) -> Packet<T> {
+        Packet { buffer }
+    }
+
+    /// Shorthand for a combination of [new_unchecked] and [check_len].
+    ///
+    /// [new_unchecked]: #method.new_unchecked
+    /// [check_len]: #method.check_len
+    pub fn new_checked(buffer: T) -> Result<Packet<T>> {
+        let packet = Self::new_unchecked(buffer);
+        packet.check_len()?;
+        Ok(packet)
+    }
+
+    /// Ensure that no accessor method will panic if called.
+    /// Returns `Err(Error)` if the buffer is too short.
+    pub fn check_len(&self) -> Result<()> {
+        let len = self.buffer.as_ref().len();
+        if len < field::OPTIONS.start {
+            Err(Error)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Consume the packet, returning the underlying buffer.
+    pub fn into_inner(self) -> T {
+        self.buffer
+    }
+
+    /// Returns the message type.
+    pub fn message_type(&self) -> MessageType {
+        let data = self.buffer.as_ref();
+        MessageType::from(data[field::MSG_TYPE])
+    }
+
+    /// Returns the 24-bit transaction ID chosen by the client.
+    pub fn transaction_id(&self) -> u32 {
+        let field = &self.buffer.as_ref()[field::TRANSACTION_ID];
+        NetworkEndian::read_u24(field)
+    }
+
+    /// Return an iterator over the options.
+    #[inline]
+    pub fn options(&self) -> impl Iterator<Item = DhcpOption<'_>> + '_ {
+        options(&self.buffer.as_ref()[field::OPTIONS])
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> Packet<T> {
+    /// Sets the message type.
+    pub fn set_message_type(&mut self, value: MessageType) {
+        let data = self.buffer.as_mut();
+        data[field::MSG_TYPE] = value.into();
+    }
+
+    /// Sets the 24-bit transaction ID.
+    pub fn set_transaction_id(&mut self, value: u32) {
+        let field = &mut self.buffer.as_mut()[field::TRANSACTION_ID];
+        NetworkEndian::write_u24(field, value)
+    }
+
+    /// Return a writer for the options.
+    #[inline]
+    pub fn options_mut(&mut self) -> DhcpOptionWriter<'_> {
+        DhcpOptionWriter::new(&mut self.buffer.as_mut()[field::OPTIONS])
+    }
+}
+
+/// An address of an identity association for non-temporary addresses.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IaAddress {
+    pub address: Ipv6Address,
+    /// The preferred lifetime of the address, in seconds.
+    pub preferred_lifetime: u32,
+    /// The valid lifetime of the address, in seconds.
+    pub valid_lifetime: u32,
+}
+
+/// An identity association for non-temporary addresses (IA_NA).
+///
+/// Only the first address of the association is represented.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IaNa {
+    /// The identifier of the association, chosen by the client.
+    pub iaid: u32,
+    /// The time after which the client contacts the server that assigned the addresses to
+    /// extend their lifetimes, in seconds.
+    pub t1: u32,
+    /// The time after which the client contacts any server to extend the lifetimes of the
+    /// addresses, in seconds.
+    pub t2: u32,
+    pub address: Option<IaAddress>,
+    /// The status of the association, such as `NoAddrsAvail`.
+    pub status: Option<StatusCode>,
+}
+
+/// A prefix of an identity association for prefix delegation.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IaPrefix {
+    pub prefix: Ipv6Cidr,
+    /// The preferred lifetime of the prefix, in seconds.
+    pub preferred_lifetime: u32,
+    /// The valid lifetime of the prefix, in seconds.
+    pub valid_lifetime: u32,
+}
+
+/// An identity association for prefix delegation (IA_PD), see RFC 8415 § 21.21.
+///
+/// Only the first prefix of the association is represented.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IaPd {
+    /// The identifier of the association, chosen by the client.
+    pub iaid: u32,
+    /// The time after which the client contacts the server that delegated the prefixes to
+    /// extend their lifetimes, in seconds.
+    pub t1: u32,
+    /// The time after which the client contacts any server to extend the lifetimes of the
+    /// prefixes, in seconds.
+    pub t2: u32,
+    pub prefix: Option<IaPrefix>,
+    /// The status of the association, such as `NoPrefixAvail`.
+    pub status: Option<StatusCode>,
+}
+
+/// A list of domain names, each encoded as an uncompressed sequence of labels (see RFC 1035
+/// § 3.1), as carried by the domain search list option.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DomainList<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DomainList<'a> {
+    /// Create a domain list from its encoded representation.
+    pub const fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Return the encoded representation of the list.
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Return an iterator over the domain names of the list.
+    ///
+    /// The iteration stops at the first malformed name.
+    pub fn iter(&self) -> impl Iterator<Item = DomainName<'a>> {
+        let mut buf = self.data;
+        iter::from_fn(move || {
+            let mut len = 0;
+            loop {
+                let label_len = *buf.get(len)? as usize;
+                if label_len > 63 {
+                    return None;
+                }
+                len += 1 + label_len;
+                if label_len == 0 {
+                    break;
+                }
+            }
+            if len > buf.len() {
+                return None;
+            }
+
+            let (name, rest) = buf.split_at(len);
+            buf = rest;
+            Some(DomainName { data: name })
+        })
+    }
+}
+
+/// A domain name of a [`DomainList`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DomainName<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DomainName<'a> {
+    /// Return the encoded representation of the name, including its final empty label.
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Return an iterator over the labels of the name.
+    pub fn labels(&self) -> impl Iterator<Item = &'a [u8]> {
+        let mut buf = self.data;
+        iter::from_fn(move || {
+            let len = *buf.first()? as usize;
+            if len == 0 {
+                return None;
+            }
+            let label = &buf[1..1 + len];
+            buf = &buf[1 + len..];
+            Some(label)
+        })
+    }
+}
+
+impl<'a> fmt::Display for DomainName<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, label) in self.labels().enumerate() {
+            if i != 0 {
+                write!(f, ".")?;
+            }
+            for &c in label {
+                write!(f, "{}", c as char)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A high-level representation of a DHCPv6 client/server message.
+///
+/// Relay agent messages are not supported.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Repr<'a> {
+    pub message_type: MessageType,
+    /// The 24-bit transaction ID, chosen by the client, that associates the messages of an
+    /// exchange.
+    pub transaction_id: u32,
+    /// The DUID of the client.
+    pub client_id: Option<&'a [u8]>,
+    /// The DUID of the server.
+    pub server_id: Option<&'a [u8]>,
+    /// The identity association for non-temporary addresses.
+    pub ia_na: Option<IaNa>,
+    /// The identity association for prefix delegation.
+    pub ia_pd: Option<IaPd>,
+    /// The time elapsed since the client began the exchange, in hundredths of a second.
+    pub elapsed_time: Option<u16>,
+    /// The option request option informs the server about which options the client is
+    /// interested in.
+    pub requested_options: Option<Vec<u16, MAX_REQUESTED_OPTION_COUNT>>,
+    /// The status of the message.
+    pub status: Option<StatusCode>,
+    /// The preference of the server, used by the client to select a server.
+    pub preference: Option<u8>,
+    /// Whether the client accepts a REPLY to its SOLICIT, or whether the server commits the
+    /// assignment in its REPLY.
+    pub rapid_commit: bool,
+    /// DNS servers
+    pub dns_servers: Option<Vec<Ipv6Address, MAX_DNS_SERVER_COUNT>>,
+    /// The domain search list.
+    pub domain_list: Option<DomainList<'a>>,
+    /// The time after which the client refreshes the information obtained through an
+    /// INFORMATION-REQUEST, in seconds.
+    pub information_refresh_time: Option<u32>,
+    /// When returned from [`Repr::parse`], this field will be empty.
+    /// However, when calling [`Repr::emit`], this field should contain only
+    /// additional DHCPv6 options not known to smoltcp.
+    pub additional_options: &'a [DhcpOption<'a>],
+}
+
+impl<'a> Repr<'a> {
+    /// Return the length of a packet that will be emitted from this high-level representation.
+    pub fn buffer_len(&self) -> usize {
+        let mut len = field::OPTIONS.start;
+        if let Some(id) = self.client_id {
+            len += 4 + id.len();
+        }
+        if let Some(id) = self.server_id {
+            len += 4 + id.len();
+        }
+        if let Some(ia_na) = &self.ia_na {
+            len += 4 + Self::ia_na_len(ia_na);
+        }
+        if let Some(ia_pd) = &self.ia_pd {
+            len += 4 + Self::ia_pd_len(ia_pd);
+        }
+        if self.elapsed_time.is_some() {
+            len += 6;
+        }
+        if let Some(list) = &self.requested_options {
+            len += 4 + list.len() * 2;
+        }
+        if self.status.is_some() {
+            len += 6;
+        }
+        if self.preference.is_some() {
+            len += 5;
+        }
+        if self.rapid_commit {
+            len += 4;
+        }
+        if let Some(dns_servers) = &self.dns_servers {
+            len += 4 + dns_servers.len() * 16;
+        }
+        if let Some(list) = &self.domain_list {
+            len += 4 + list.as_bytes().len();
+        }
+        if self.information_refresh_time.is_some() {
+            len += 8;
+        }
+        for opt in self.additional_options {
+            len += 4 + opt.data.len()
+        }
+
+        len
+    }
+
+    fn ia_na_len(ia_na: &IaNa) -> usize {
+        let mut len = field::IA_OPTIONS.start;
+        if ia_na.address.is_some() {
+            len += 4 + field::IAADDR_OPTIONS.start;
+        }
+        if ia_na.status.is_some() {
+            len += 6;
+        }
+        len
+    }
+
+    fn ia_pd_len(ia_pd: &IaPd) -> usize {
+        let mut len = field::IA_OPTIONS.start;
+        if ia_pd.prefix.is_some() {
+            len += 4 + field::IAPREFIX_OPTIONS.start;
+        }
+        if ia_pd.status.is_some() {
+            len += 6;
+        }
+        len
+    }
+
+    /// Parse the status of an option, ignoring the status message.
+    fn parse_status(data: &[u8]) -> Option<StatusCode> {
+        (data.len() >= 2).then(|| StatusCode::from(NetworkEndian::read_u16(data)))
+    }
+
+    fn parse_ia_na(data: &[u8]) -> Result<IaNa> {
+        if data.len() < field::IA_OPTIONS.start {
+            return Err(Error);
+        }
+
+        let mut ia_na = IaNa {
+            iaid: NetworkEndian::read_u32(&data[field::IA_IAID]),
+            t1: NetworkEndian::read_u32(&data[field::IA_T1]),
+            t2: NetworkEndian::read_u32(&data[field::IA_T2]),
+            address: None,
+            status: None,
+        };
+        for option in options(&data[field::IA_OPTIONS]) {
+            let data = option.data;
+            match option.kind {
+                field::OPT_IAADDR
+                    if ia_na.address.is_none() && data.len() >= field::IAADDR_OPTIONS.start =>
+                {
+                    ia_na.address = Some(IaAddress {
+                        address: Ipv6Address::from_bytes(&data[field::IAADDR_ADDRESS]),
+                        preferred_lifetime: NetworkEndian::read_u32(&data[field::IAADDR_PREFERRED]),
+                        valid_lifetime: NetworkEndian::read_u32(&data[field::IAADDR_VALID]),
+                    });
+                }
+                field::OPT_STATUS_CODE => ia_na.status = Self::parse_status(data),
+                _ => {}
+            }
+        }
+        Ok(ia_na)
+    }
+
+    fn parse_ia_pd(data: &[u8]) -> Result<IaPd> {
+        if data.len() < field::IA_OPTIONS.start {
+            return Err(Error);
+        }
+
+        let mut ia_pd = IaPd {
+            iaid: NetworkEndian::read_u32(&data[field::IA_IAID]),
+            t1: NetworkEndian::read_u32(&data[field::IA_T1]),
+            t2: NetworkEndian::read_u32(&data[field::IA_T2]),
+            prefix: None,
+            status: None,
+        };
+        for option in options(&data[field::IA_OPTIONS]) {
+            let data = option.data;
+            match option.kind {
+                field::OPT_IAPREFIX
+                    if ia_pd.prefix.is_none()
+                        && data.len() >= field::IAPREFIX_OPTIONS.start
+                        && data[field::IAPREFIX_PREFIX_LEN] <= 128 =>
+                {
+                    ia_pd.prefix = Some(IaPrefix {
+                        prefix: Ipv6Cidr::new(
+                            Ipv6Address::from_bytes(&data[field::IAPREFIX_PREFIX]),
+                            data[field::IAPREFIX_PREFIX_LEN],
+                        ),
+                        preferred_lifetime: NetworkEndian::read_u32(
+                            &data[field::IAPREFIX_PREFERRED],
+                        ),
+                        valid_lifetime: NetworkEndian::read_u32(&data[field::IAPREFIX_VALID]),
+                    });
+                }
+                field::OPT_STATUS_CODE => ia_pd.status = Self::parse_status(data),
+                _ => {}
+            }
+        }
+        Ok(ia_pd)
+    }
+
+    /// Parse a DHCPv6 packet and return a high-level representation.
+    pub fn parse<T>(packet: &'a Packet<&'a T>) -> Result<Self>
+    where
+        T: AsRef<[u8]> + ?Sized,
+    {
+        packet.check_len()?;
+
+        let message_type = packet.message_type();
+        if matches!(
+            message_type,
+            MessageType::RelayForw | MessageType::RelayRepl
+        ) {
+            // Relay agent messages have a different format.
+            return Err(Error);
+        }
+
+        let mut repr = Repr {
+            message_type,
+            transaction_id: packet.transaction_id(),
+            client_id: None,
+            server_id: None,
+            ia_na: None,
+            ia_pd: None,
+            elapsed_time: None,
+            requested_options: None,
+            status: None,
+            preference: None,
+            rapid_commit: false,
+            dns_servers: None,
+            domain_list: None,
+            information_refresh_time: None,
+            additional_options: &[],
+        };
+
+        for option in packet.options() {
+            let data = option.data;
+            match (option.kind, data.len()) {
+                (field::OPT_CLIENTID, _) => repr.client_id = Some(data),
+                (field::OPT_SERVERID, _) => repr.server_id = Some(data),
+                (field::OPT_IA_NA, _) => repr.ia_na = Some(Self::parse_ia_na(data)?),
+                (field::OPT_IA_PD, _) => repr.ia_pd = Some(Self::parse_ia_pd(data)?),
+                (field::OPT_ELAPSED_TIME, 2) => {
+                    repr.elapsed_time = Some(NetworkEndian::read_u16(data));
+                }
+                (field::OPT_ORO, _) => {
+                    let mut list = Vec::new();
+                    for code in data.chunks_exact(2) {
+                        // We ignore push failures, the codes that do not fit are not
+                        // understood by smoltcp anyway.
+                        list.push(NetworkEndian::read_u16(code)).ok();
+                    }
+                    repr.requested_options = Some(list);
+                }
+                (field::OPT_STATUS_CODE, _) => repr.status = Self::parse_status(data),
+                (field::OPT_PREFERENCE, 1) => repr.preference = Some(data[0]),
+                (field::OPT_RAPID_COMMIT, 0) => repr.rapid_commit = true,
+                (field::OPT_DNS_SERVERS, _) => {
+                    let mut servers = Vec::new();
+                    let mut addrs = data.chunks_exact(16);
+                    for chunk in &mut addrs {
+                        // We ignore push failures because that will only happen
+                        // if we attempt to push more than 3 addresses, and the only
+                        // solution to that is to support more addresses.
+                        servers.push(Ipv6Address::from_bytes(chunk)).ok();
+                    }
+                    repr.dns_servers = Some(servers);
+
+                    if !addrs.remainder().is_empty() {
+                        net_trace!("DHCPv6 DNS servers contained invalid address");
+                    }
+                }
+                (field::OPT_DOMAIN_LIST, _) => repr.domain_list = Some(DomainList::new(data)),
+                (field::OPT_INFORMATION_REFRESH_TIME, 4) => {
+                    repr.information_refresh_time = Some(NetworkEndian::read_u32(data));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(repr)
+    }
+
+    /// Emit a high-level representation into a DHCPv6 packet.
+    pub fn emit<T>(&self, packet: &mut Packet<&mut T>) -> Result<()>
+    where
+        T: AsRef<[u8]> + AsMut<[u8]> + ?Sized,
+    {
+        packet.set_message_type(self.message_type);
+        packet.set_transaction_id(self.transaction_id);
+
+        let mut options = packet.options_mut();
+
+        if let Some(id) = self.client_id {
+            options.emit(DhcpOption {
+                kind: field::OPT_CLIENTID,
+                data: id,
+            })?;
+        }
+        if let Some(id) = self.server_id {
+            options.emit(DhcpOption {
+                kind: field::OPT_SERVERID,
+                data: id,
+            })?;
+        }
+        if let Some(ia_na) = &self.ia_na {
+            options.emit_with(field::OPT_IA_NA, Self::ia_na_len(ia_na), |buf| {
+                Self::emit_ia(buf, ia_na.iaid, ia_na.t1, ia_na.t2);
+                let mut options = DhcpOptionWriter::new(&mut buf[field::IA_OPTIONS]);
+                if let Some(address) = &ia_na.address {
+                    let len = field::IAADDR_OPTIONS.start;
+                    options
+                        .emit_with(field::OPT_IAADDR, len, |buf| {
+                            buf[field::IAADDR_ADDRESS].copy_from_slice(address.address.as_bytes());
+                            NetworkEndian::write_u32(
+                                &mut buf[field::IAADDR_PREFERRED],
+                                address.preferred_lifetime,
+                            );
+                            NetworkEndian::write_u32(
+                                &mut buf[field::IAADDR_VALID],
+                                address.valid_lifetime,
+                            );
+                        })
+                        .unwrap();
+                }
+                if let Some(status) = ia_na.status {
+                    Self::emit_status(&mut options, status);
+                }
+            })?;
+        }
+        if let Some(ia_pd) = &self.ia_pd {
+            options.emit_with(field::OPT_IA_PD, Self::ia_pd_len(ia_pd), |buf| {
+                Self::emit_ia(buf, ia_pd.iaid, ia_pd.t1, ia_pd.t2);
+                let mut options = DhcpOptionWriter::new(&mut buf[field::IA_OPTIONS]);
+                if let Some(prefix) = &ia_pd.prefix {
+                    let len = field::IAPREFIX_OPTIONS.start;
+                    options
+                        .emit_with(field::OPT_IAPREFIX, len, |buf| {
+                            NetworkEndian::write_u32(
+                                &mut buf[field::IAPREFIX_PREFERRED],
+                                prefix.preferred_lifetime,
+                            );
+                            NetworkEndian::write_u32(
+                                &mut buf[field::IAPREFIX_VALID],
+                                prefix.valid_lifetime,
+                            );
+                            buf[field::IAPREFIX_PREFIX_LEN] = prefix.prefix.prefix_len();
+                            buf[field::IAPREFIX_PREFIX]
+                                .copy_from_slice(prefix.prefix.address().as_bytes());
+                        })
+                        .unwrap();
+                }
+                if let Some(status) = ia_pd.status {
+                    Self::emit_status(&mut options, status);
+                }
+            })?;
+        }
+        if let Some(elapsed_time) = self.elapsed_time {
+            options.emit(DhcpOption {
+                kind: field::OPT_ELAPSED_TIME,
+                data: &elapsed_time.to_be_bytes(),
+            })?;
+        }
+        if let Some(list) = &self.requested_options {
+            options.emit_with(field::OPT_ORO, list.len() * 2, |buf| {
+                for (code, buf) in list.iter().zip(buf.chunks_exact_mut(2)) {
+                    NetworkEndian::write_u16(buf, *code);
+                }
+            })?;
+        }
+        if let Some(status) = self.status {
+            Self::emit_status(&mut options, status);
+        }
+        if let Some(preference) = self.preference {
+            options.emit(DhcpOption {
+                kind: field::OPT_PREFERENCE,
+                data: &[preference],
+            })?;
+        }
+        if self.rapid_commit {
+            options.emit(DhcpOption {
+                kind: field::OPT_RAPID_COMMIT,
+                data: &[],
+            })?;
+        }
+        if let Some(dns_servers) = &self.dns_servers {
+            options.emit_with(field::OPT_DNS_SERVERS, dns_servers.len() * 16, |buf| {
+                for (addr, buf) in dns_servers.iter().zip(buf.chunks_exact_mut(16)) {
+                    buf.copy_from_slice(addr.as_bytes());
+                }
+            })?;
+        }
+        if let Some(list) = &self.domain_list {
+            options.emit(DhcpOption {
+                kind: field::OPT_DOMAIN_LIST,
+                data: list.as_bytes(),
+            })?;
+        }
+        if let Some(time) = self.information_refresh_time {
+            options.emit(DhcpOption {
+                kind: field::OPT_INFORMATION_REFRESH_TIME,
+                data: &time.to_be_bytes(),
+            })?;
+        }
+        for option in self.additional_options {
+            options.emit(*option)?;
+        }
+
+        Ok(())
+    }
+
+    fn emit_ia(buf: &mut [u8], iaid: u32, t1: u32, t2: u32) {
+        NetworkEndian::write_u32(&mut buf[field::IA_IAID], iaid);
+        NetworkEndian::write_u32(&mut buf[field::IA_T1], t1);
+        NetworkEndian::write_u32(&mut buf[field::IA_T2], t2);
+    }
+
+    fn emit_status(options: &mut DhcpOptionWriter, status: StatusCode) {
+        // The buffer length accounts for the status code option.
+        options
+            .emit(DhcpOption {
+                kind: field::OPT_STATUS_CODE,
+                data: &u16::from(status).to_be_bytes(),
+            })
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CLIENT_DUID: &[u8] = &[0x00, 0x03, 0x00, 0x01, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02];
+    const SERVER_DUID: &[u8] = &[0x00, 0x03, 0x00, 0x01, 0x52, 0x54, 0x00, 0x12, 0x34, 0x56];
+
+    static SOLICIT_BYTES: &[u8] = &[
+        0x01, 0x12, 0x34, 0x56, // SOLICIT, transaction ID
+        0x00, 0x01, 0x00, 0x0a, 0x00, 0x03, 0x00, 0x01, 0x02, 0x02, 0x02, 0x02, 0x02,
+        0x02, // client ID
+        0x00, 0x03, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, // IA_NA
+        0x00, 0x08, 0x00, 0x02, 0x00, 0x00, // elapsed time
+        0x00, 0x06, 0x00, 0x04, 0x00, 0x17, 0x00, 0x18, // ORO
+    ];
+
+    static REPLY_BYTES: &[u8] = &[
+        0x07, 0x12, 0x34, 0x56, // REPLY, transaction ID
+        0x00, 0x01, 0x00, 0x0a, 0x00, 0x03, 0x00, 0x01, 0x02, 0x02, 0x02, 0x02, 0x02,
+        0x02, // client ID
+        0x00, 0x02, 0x00, 0x0a, 0x00, 0x03, 0x00, 0x01, 0x52, 0x54, 0x00, 0x12, 0x34,
+        0x56, // server ID
+        0x00, 0x03, 0x00, 0x28, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x00, 0x01,
+        0xe0, // IA_NA, T1 300, T2 480
+        0x00, 0x05, 0x00, 0x18, 0xfd, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x42, 0x00, 0x00, 0x02, 0x58, 0x00, 0x00, 0x03,
+        0x84, // IAADDR fd00::42, preferred 600, valid 900
+        0x00, 0x19, 0x00, 0x29, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x00, 0x01,
+        0xe0, // IA_PD, T1 300, T2 480
+        0x00, 0x1a, 0x00, 0x19, 0x00, 0x00, 0x02, 0x58, 0x00, 0x00, 0x03, 0x84, 0x38, 0xfd, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x12, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, // IAPREFIX fd00:0:0:1200::/56, preferred 600, valid 900
+        0x00, 0x17, 0x00, 0x10, 0xfd, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x01, // DNS servers
+        0x00, 0x18, 0x00, 0x0d, 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o',
+        b'm', 0x00, // domain list
+    ];
+
+    fn solicit_repr() -> Repr<'static> {
+        Repr {
+            message_type: MessageType::Solicit,
+            transaction_id: 0x123456,
+            client_id: Some(CLIENT_DUID),
+            server_id: None,
+            ia_na: Some(IaNa {
+                iaid: 1,
+                t1: 0,
+                t2: 0,
+                address: None,
+                status: None,
+            }),
+            ia_pd: None,
+            elapsed_time: Some(0),
+            requested_options: Some(
+                Vec::from_slice(&[field::OPT_DNS_SERVERS, field::OPT_DOMAIN_LIST]).unwrap(),
+            ),
+            status: None,
+            preference: None,
+            rapid_commit: false,
+            dns_servers: None,
+            domain_list: None,
+            information_refresh_time: None,
+            additional_options: &[],
+        }
+    }
+
+    fn reply_repr() -> Repr<'static> {
+        Repr {
+            message_type: MessageType::Reply,
+            transaction_id: 0x123456,
+            client_id: Some(CLIENT_DUID),
+            server_id: Some(SERVER_DUID),
+            ia_na: Some(IaNa {
+                iaid: 1,
+                t1: 300,
+                t2: 480,
+                address: Some(IaAddress {
+                    address: Ipv6Address::new(0xfd00, 0, 0, 0, 0, 0, 0, 0x42),
+                    preferred_lifetime: 600,
+                    valid_lifetime: 900,
+                }),
+                status: None,
+            }),
+            ia_pd: Some(IaPd {
+                iaid: 2,
+                t1: 300,
+                t2: 480,
+                prefix: Some(IaPrefix {
+                    prefix: Ipv6Cidr::new(Ipv6Address::new(0xfd00, 0, 0, 0x1200, 0, 0, 0, 0), 56),
+                    preferred_lifetime: 600,
+                    valid_lifetime: 900,
+                }),
+                status: None,
+            }),
+            elapsed_time: None,
+            requested_options: None,
+            status: None,
+            preference: None,
+            rapid_commit: false,
+            dns_servers: Some(
+                Vec::from_slice(&[Ipv6Address::new(0xfd00, 0, 0, 0, 0, 0, 0, 1)]).unwrap(),
+            ),
+            domain_list: Some(DomainList::new(&REPLY_BYTES[REPLY_BYTES.len() - 13..])),
+            information_refresh_time: None,
+            additional_options: &[],
+        }
+    }
+
+    #[test]
+    fn test_deconstruct_solicit() {
+        let packet = Packet::new_unchecked(SOLICIT_BYTES);
+        assert_eq!(packet.message_type(), MessageType::Solicit);
+        assert_eq!(packet.transaction_id(), 0x123456);
+
+        let mut options = packet.options();
+        assert_eq!(
+            options.next(),
+            Some(DhcpOption {
+                kind: field::OPT_CLIENTID,
+                data: CLIENT_DUID,
+            })
+        );
+        assert_eq!(options.next().map(|o| o.kind), Some(field::OPT_IA_NA));
+        assert_eq!(
+            options.next(),
+            Some(DhcpOption {
+                kind: field::OPT_ELAPSED_TIME,
+                data: &[0, 0],
+            })
+        );
+        assert_eq!(options.next().map(|o| o.kind), Some(field::OPT_ORO));
+        assert_eq!(options.next(), None);
+    }
+
+    #[test]
+    fn test_parse_solicit() {
+        let packet = Packet::new_unchecked(SOLICIT_BYTES);
+        assert_eq!(Repr::parse(&packet), Ok(solicit_repr()));
+    }
+
+    #[test]
+    fn test_emit_solicit() {
+        let repr = solicit_repr();
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        repr.emit(&mut packet).unwrap();
+        assert_eq!(&*packet.into_inner(), SOLICIT_BYTES);
+    }
+
+    #[test]
+    fn test_parse_reply() {
+        let packet = Packet::new_unchecked(REPLY_BYTES);
+        assert_eq!(Repr::parse(&packet), Ok(reply_repr()));
+    }
+
+    #[test]
+    fn test_emit_reply() {
+        let repr = reply_repr();
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        repr.emit(&mut packet).unwrap();
+        assert_eq!(&*packet.into_inner(), REPLY_BYTES);
+    }
+
+    #[test]
+    fn test_emit_status() {
+        let repr = Repr {
+            message_type: MessageType::Reply,
+            status: Some(StatusCode::NoBinding),
+            ia_na: Some(IaNa {
+                iaid: 1,
+                t1: 0,
+                t2: 0,
+                address: None,
+                status: Some(StatusCode::NoAddrsAvail),
+            }),
+            ..solicit_repr()
+        };
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        repr.emit(&mut packet).unwrap();
+
+        let packet = Packet::new_unchecked(&bytes);
+        assert_eq!(Repr::parse(&packet), Ok(repr));
+    }
+
+    #[test]
+    fn test_parse_relay() {
+        let mut bytes = SOLICIT_BYTES.to_vec();
+        bytes[0] = MessageType::RelayForw.into();
+        let packet = Packet::new_unchecked(&bytes);
+        assert_eq!(Repr::parse(&packet), Err(Error));
+    }
+
+    #[test]
+    fn test_parse_truncated_ia() {
+        let bytes = [
+            0x07, 0x12, 0x34, 0x56, 0x00, 0x03, 0x00, 0x04, 0x00, 0x00, 0x00, 0x01,
+        ];
+        let packet = Packet::new_unchecked(&bytes);
+        assert_eq!(Repr::parse(&packet), Err(Error));
+    }
+
+    #[test]
+    fn test_domain_list() {
+        let list = DomainList::new(&[
+            7, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 3, b'c', b'o', b'm', 0, 3, b'l', b'a',
+            b'n', 0, 3, b'b', b'a', b'd',
+        ]);
+        let mut names = list.iter();
+        assert_eq!(names.next().unwrap().to_string(), "example.com");
+        let name = names.next().unwrap();
+        assert_eq!(name.as_bytes(), &[3, b'l', b'a', b'n', 0]);
+        assert_eq!(name.to_string(), "lan");
+        // The last name is truncated.
+        assert_eq!(names.next(), None);
+    }
+}
@@ -42,7 +42,12 @@ enum_with_unknown! {
         Ns    = 0x0002,
         Cname = 0x0005,
         Soa   = 0x0006,
+        Ptr   = 0x000c,
+        Mx    = 0x000f,
+        Txt   = 0x0010,
         Aaaa  = 0x001c,
+        Srv   = 0x0021,
+        Any   = 0x00ff,
     }
 }
 
@@ -74,6 +79,9 @@ mod field {
 
 // DNS class IN (Internet)
 const CLASS_IN: u16 = 1;
+// The top bit of the class is the unicast-response bit of mDNS questions, and the cache-flush
+// bit of mDNS records (see RFC 6762 § 18.12 and § 10.2).
+const CLASS_MASK: u16 = 0x7fff;
 
 /// A read/write wrapper around a DNS packet buffer.
 #[derive(Debug, PartialEq, Eq)]
@@ -300,7 +308,7 @@ impl<'a> Question<'a> {
             return Err(Error);
         }
         let type_ = NetworkEndian::read_u16(&rest[0..2]).into();
-        let class = NetworkEndian::read_u16(&rest[2..4]);
+        let class = NetworkEndian::read_u16(&rest[2..4]) & CLASS_MASK;
         let rest = &rest[4..];
 
         if class != CLASS_IN {
@@ -350,6 +358,28 @@ impl<'a> RecordData<'a> {
                 Ok(RecordData::Aaaa(Ipv6Address::from_bytes(data)))
             }
             Type::Cname => Ok(RecordData::Cname(data)),
+            Type::Ptr => Ok(RecordData::Ptr(data)),
+            Type::Mx => {
+                if data.len() < 3 {
+                    return Err(Error);
+                }
+                Ok(RecordData::Mx {
+                    preference: NetworkEndian::read_u16(&data[0..2]),
+                    exchange: &data[2..],
+                })
+            }
+            Type::Txt => Ok(RecordData::Txt(data)),
+            Type::Srv => {
+                if data.len() < 7 {
+                    return Err(Error);
+                }
+                Ok(RecordData::Srv {
+                    priority: NetworkEndian::read_u16(&data[0..2]),
+                    weight: NetworkEndian::read_u16(&data[2..4]),
+                    port: NetworkEndian::read_u16(&data[4..6]),
+                    target: &data[6..],
+                })
+            }
             x => Ok(RecordData::Other(x, data)),
         }
     }
@@ -363,6 +393,22 @@ pub enum RecordData<'a> {
     #[cfg(feature = "proto-ipv6")]
     Aaaa(Ipv6Address),
     Cname(&'a [u8]),
+    /// The name the record points to, which may be compressed.
+    Ptr(&'a [u8]),
+    /// A mail exchange. The name of the exchange may be compressed.
+    Mx {
+        preference: u16,
+        exchange: &'a [u8],
+    },
+    /// The character-strings of the record, each prefixed with its length.
+    Txt(&'a [u8]),
+    /// The location of a service (see RFC 2782). The name of the target may be compressed.
+    Srv {
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: &'a [u8],
+    },
     Other(Type, &'a [u8]),
 }
 
@@ -375,7 +421,7 @@ impl<'a> Record<'a> {
             return Err(Error);
         }
         let type_ = NetworkEndian::read_u16(&rest[0..2]).into();
-        let class = NetworkEndian::read_u16(&rest[2..4]);
+        let class = NetworkEndian::read_u16(&rest[2..4]) & CLASS_MASK;
         let ttl = NetworkEndian::read_u32(&rest[4..8]);
         let len = NetworkEndian::read_u16(&rest[8..10]) as usize;
         let rest = &rest[10..];
@@ -762,6 +808,88 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_parse_response_srv_txt_ptr_mx() {
+        let bytes = &[
+            0x12, 0x34, 0x81, 0x80, 0x00, 0x01, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x02, 0x5f,
+            0x78, 0x04, 0x5f, 0x74, 0x63, 0x70, 0x01, 0x61, 0x00, 0x00, 0x21, 0x00, 0x01, 0xc0,
+            0x0c, 0x00, 0x21, 0x00, 0x01, 0x00, 0x00, 0x00, 0x3c, 0x00, 0x0a, 0x00, 0x0a, 0x00,
+            0x05, 0x1f, 0x90, 0x01, 0x62, 0xc0, 0x14, 0xc0, 0x0c, 0x00, 0x10, 0x00, 0x01, 0x00,
+            0x00, 0x00, 0x3c, 0x00, 0x08, 0x03, 0x6b, 0x3d, 0x76, 0x03, 0x61, 0x62, 0x63, 0xc0,
+            0x0c, 0x00, 0x0c, 0x00, 0x01, 0x00, 0x00, 0x00, 0x3c, 0x00, 0x02, 0xc0, 0x0c, 0xc0,
+            0x0c, 0x00, 0x0f, 0x00, 0x01, 0x00, 0x00, 0x00, 0x3c, 0x00, 0x04, 0x00, 0x14, 0xc0,
+            0x14,
+        ];
+        let p = Parsed::parse(bytes).unwrap();
+
+        assert_eq!(p.questions[0].type_, Type::Srv);
+        assert_eq!(p.answers.len(), 4);
+
+        // _x._tcp.a SRV 10 5 8080 b.a
+        assert_eq!(
+            p.answers[0].data,
+            RecordData::Srv {
+                priority: 10,
+                weight: 5,
+                port: 8080,
+                target: &[0x01, 0x62, 0xc0, 0x14],
+            }
+        );
+        let RecordData::Srv { target, .. } = p.answers[0].data else {
+            unreachable!()
+        };
+        let target: Result<Vec<_>> = p.packet.parse_name(target).collect();
+        assert_eq!(target, Ok(vec![&b"b"[..], &b"a"[..]]));
+
+        // _x._tcp.a TXT "k=v" "abc"
+        assert_eq!(
+            p.answers[1].data,
+            RecordData::Txt(&[0x03, 0x6b, 0x3d, 0x76, 0x03, 0x61, 0x62, 0x63])
+        );
+
+        // _x._tcp.a PTR _x._tcp.a
+        assert_eq!(p.answers[2].data, RecordData::Ptr(&[0xc0, 0x0c]));
+
+        // _x._tcp.a MX 20 a
+        assert_eq!(
+            p.answers[3].data,
+            RecordData::Mx {
+                preference: 20,
+                exchange: &[0xc0, 0x14],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_record_data_truncated() {
+        assert_eq!(
+            RecordData::parse(Type::Srv, &[0x00, 0x0a, 0x00, 0x05, 0x1f, 0x90]),
+            Err(Error)
+        );
+        assert_eq!(RecordData::parse(Type::Mx, &[0x00, 0x14]), Err(Error));
+    }
+
+    #[test]
+    fn test_parse_mdns_class() {
+        // A question asking for a unicast response, and a record with the cache-flush bit.
+        let question = &[
+            0x06, 0x64, 0x65, 0x76, 0x69, 0x63, 0x65, 0x05, 0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x00,
+            0x00, 0xff, 0x80, 0x01,
+        ];
+        let (rest, question) = Question::parse(question).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(question.type_, Type::Any);
+
+        let record = &[
+            0xc0, 0x0c, 0x00, 0x01, 0x80, 0x01, 0x00, 0x00, 0x00, 0x78, 0x00, 0x04, 0xc0, 0xa8,
+            0x01, 0x01,
+        ];
+        let (rest, record) = Record::parse(record).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(record.ttl, 120);
+        assert_eq!(record.data, RecordData::A(Ipv4Address::new(192, 168, 1, 1)));
+    }
+
     #[test]
     fn test_emit() {
         let name = &[
@@ -65,6 +65,15 @@ impl Address {
     pub const fn is_local(&self) -> bool {
         self.0[0] & 0x02 != 0
     }
+
+    /// Convert the address to a modified Extended Unique Identifier (EUI-64), as used for IPv6
+    /// interface identifiers. See [RFC 2464 § 4].
+    ///
+    /// [RFC 2464 § 4]: https://tools.ietf.org/html/rfc2464#section-4
+    pub const fn as_eui_64(&self) -> [u8; 8] {
+        let b = self.0;
+        [b[0] ^ 0x02, b[1], b[2], 0xff, 0xfe, b[3], b[4], b[5]]
+    }
 }
 
 impl fmt::Display for Address {
@@ -319,6 +328,15 @@ mod test {
         assert!(Address::BROADCAST.is_multicast());
         assert!(Address::BROADCAST.is_local());
     }
+
+    #[test]
+    fn test_eui_64() {
+        let addr = Address([0x00, 0x1b, 0x21, 0x3c, 0x4d, 0x5e]);
+        assert_eq!(
+            addr.as_eui_64(),
+            [0x02, 0x1b, 0x21, 0xff, 0xfe, 0x3c, 0x4d, 0x5e]
+        );
+    }
 }
 
 #[cfg(test)]
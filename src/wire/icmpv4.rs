@@ -4,7 +4,7 @@ use core::{cmp, fmt};
 use super::{Error, Result};
 use crate::phy::ChecksumCapabilities;
 use crate::wire::ip::checksum;
-use crate::wire::{Ipv4Packet, Ipv4Repr};
+use crate::wire::{Ipv4Packet, Ipv4Repr, IPV4_HEADER_LEN};
 
 enum_with_unknown! {
     /// Internet protocol control message type.
@@ -179,6 +179,8 @@ mod field {
     pub const ECHO_IDENT: Field = 4..6;
     pub const ECHO_SEQNO: Field = 6..8;
 
+    pub const NEXT_HOP_MTU: Field = 6..8;
+
     pub const HEADER_END: usize = 8;
 }
 
@@ -259,6 +261,13 @@ impl<T: AsRef<[u8]>> Packet<T> {
         NetworkEndian::read_u16(&data[field::ECHO_SEQNO])
     }
 
+    /// Return the next-hop MTU field (for fragmentation required messages, see RFC 1191).
+    #[inline]
+    pub fn next_hop_mtu(&self) -> u16 {
+        let data = self.buffer.as_ref();
+        NetworkEndian::read_u16(&data[field::NEXT_HOP_MTU])
+    }
+
     /// Return the header length.
     /// The result depends on the value of the message type field.
     pub fn header_len(&self) -> usize {
@@ -335,6 +344,13 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> Packet<T> {
         NetworkEndian::write_u16(&mut data[field::ECHO_SEQNO], value)
     }
 
+    /// Set the next-hop MTU field (for fragmentation required messages, see RFC 1191).
+    #[inline]
+    pub fn set_next_hop_mtu(&mut self, value: u16) {
+        let data = self.buffer.as_mut();
+        NetworkEndian::write_u16(&mut data[field::NEXT_HOP_MTU], value)
+    }
+
     /// Compute and fill in the header checksum.
     pub fn fill_checksum(&mut self) {
         self.set_checksum(0);
@@ -382,6 +398,13 @@ pub enum Repr<'a> {
         header: Ipv4Repr,
         data: &'a [u8],
     },
+    /// A Destination Unreachable message with the Fragmentation Required code, which carries
+    /// the MTU of the next hop, see RFC 1191 § 4.
+    FragRequired {
+        mtu: u16,
+        header: Ipv4Repr,
+        data: &'a [u8],
+    },
     TimeExceeded {
         reason: TimeExceeded,
         header: Ipv4Repr,
@@ -399,6 +422,23 @@ impl<'a> Repr<'a> {
     where
         T: AsRef<[u8]> + ?Sized,
     {
+        fn original_packet<'a, T>(packet: &Packet<&'a T>) -> Result<Ipv4Packet<&'a [u8]>>
+        where
+            T: AsRef<[u8]> + ?Sized,
+        {
+            // The original datagram is usually truncated, thus only check whether its IPv4
+            // header is present.
+            let ip_packet = Ipv4Packet::new_unchecked(packet.data());
+            let header_len = ip_packet.header_len() as usize;
+            if packet.data().len() < IPV4_HEADER_LEN
+                || header_len < IPV4_HEADER_LEN
+                || packet.data().len() < header_len
+            {
+                return Err(Error);
+            }
+            Ok(ip_packet)
+        }
+
         packet.check_len()?;
 
         // Valid checksum is expected.
@@ -420,7 +460,7 @@ impl<'a> Repr<'a> {
             }),
 
             (Message::DstUnreachable, code) => {
-                let ip_packet = Ipv4Packet::new_checked(packet.data())?;
+                let ip_packet = original_packet(packet)?;
 
                 let payload = &packet.data()[ip_packet.header_len() as usize..];
                 // RFC 792 requires exactly eight bytes to be returned.
@@ -429,21 +469,29 @@ impl<'a> Repr<'a> {
                     return Err(Error);
                 }
 
-                Ok(Repr::DstUnreachable {
-                    reason: DstUnreachable::from(code),
-                    header: Ipv4Repr {
-                        src_addr: ip_packet.src_addr(),
-                        dst_addr: ip_packet.dst_addr(),
-                        next_header: ip_packet.next_header(),
-                        payload_len: payload.len(),
-                        hop_limit: ip_packet.hop_limit(),
-                    },
-                    data: payload,
-                })
+                let header = Ipv4Repr {
+                    src_addr: ip_packet.src_addr(),
+                    dst_addr: ip_packet.dst_addr(),
+                    next_header: ip_packet.next_header(),
+                    payload_len: payload.len(),
+                    hop_limit: ip_packet.hop_limit(),
+                };
+                match DstUnreachable::from(code) {
+                    DstUnreachable::FragRequired => Ok(Repr::FragRequired {
+                        mtu: packet.next_hop_mtu(),
+                        header,
+                        data: payload,
+                    }),
+                    reason => Ok(Repr::DstUnreachable {
+                        reason,
+                        header,
+                        data: payload,
+                    }),
+                }
             }
 
             (Message::TimeExceeded, code) => {
-                let ip_packet = Ipv4Packet::new_checked(packet.data())?;
+                let ip_packet = original_packet(packet)?;
 
                 let payload = &packet.data()[ip_packet.header_len() as usize..];
                 // RFC 792 requires exactly eight bytes to be returned.
@@ -476,6 +524,7 @@ impl<'a> Repr<'a> {
                 field::ECHO_SEQNO.end + data.len()
             }
             &Repr::DstUnreachable { header, data, .. }
+            | &Repr::FragRequired { header, data, .. }
             | &Repr::TimeExceeded { header, data, .. } => {
                 field::UNUSED.end + header.buffer_len() + data.len()
             }
@@ -530,6 +579,18 @@ impl<'a> Repr<'a> {
                 payload.copy_from_slice(data)
             }
 
+            Repr::FragRequired { mtu, header, data } => {
+                packet.set_msg_type(Message::DstUnreachable);
+                packet.set_msg_code(DstUnreachable::FragRequired.into());
+                packet.buffer.as_mut()[field::UNUSED.start..field::NEXT_HOP_MTU.start].fill(0);
+                packet.set_next_hop_mtu(mtu);
+
+                let mut ip_packet = Ipv4Packet::new_unchecked(packet.data_mut());
+                header.emit(&mut ip_packet, checksum_caps);
+                let payload = &mut ip_packet.into_inner()[header.buffer_len()..];
+                payload.copy_from_slice(data)
+            }
+
             Repr::TimeExceeded {
                 reason,
                 header,
@@ -604,6 +665,13 @@ impl<'a> fmt::Display for Repr<'a> {
             Repr::DstUnreachable { reason, .. } => {
                 write!(f, "ICMPv4 destination unreachable ({reason})")
             }
+            Repr::FragRequired { mtu, .. } => {
+                write!(
+                    f,
+                    "ICMPv4 destination unreachable ({}) mtu={mtu}",
+                    DstUnreachable::FragRequired
+                )
+            }
             Repr::TimeExceeded { reason, .. } => {
                 write!(f, "ICMPv4 time exceeded ({reason})")
             }
@@ -638,6 +706,7 @@ impl<T: AsRef<[u8]>> PrettyPrint for Packet<T> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::wire::{IpProtocol, Ipv4Address};
 
     static ECHO_PACKET_BYTES: [u8; 12] = [
         0x08, 0x00, 0x8e, 0xfe, 0x12, 0x34, 0xab, 0xcd, 0xaa, 0x00, 0x00, 0xff,
@@ -694,6 +763,43 @@ mod test {
         assert_eq!(&packet.into_inner()[..], &ECHO_PACKET_BYTES[..]);
     }
 
+    #[test]
+    fn test_next_hop_mtu() {
+        let mut bytes = [0x03, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let mut packet = Packet::new_unchecked(&mut bytes[..]);
+        packet.set_next_hop_mtu(1280);
+        assert_eq!(packet.msg_type(), Message::DstUnreachable);
+        assert_eq!(packet.next_hop_mtu(), 1280);
+        assert_eq!(&bytes[4..], &[0x00, 0x00, 0x05, 0x00]);
+    }
+
+    #[test]
+    fn test_frag_required_emit_parse() {
+        let repr = Repr::FragRequired {
+            mtu: 1280,
+            header: Ipv4Repr {
+                src_addr: Ipv4Address::new(192, 168, 1, 2),
+                dst_addr: Ipv4Address::new(10, 0, 0, 2),
+                next_header: IpProtocol::Udp,
+                payload_len: 8,
+                hop_limit: 63,
+            },
+            data: &[0x04, 0xd2, 0x16, 0x2e, 0x00, 0x08, 0x00, 0x00],
+        };
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        repr.emit(&mut packet, &ChecksumCapabilities::default());
+        assert_eq!(&bytes[..2], &[0x03, 0x04]);
+        assert_eq!(&bytes[4..8], &[0x00, 0x00, 0x05, 0x00]);
+
+        let packet = Packet::new_checked(&bytes[..]).unwrap();
+        assert!(packet.verify_checksum());
+        assert_eq!(
+            Repr::parse(&packet, &ChecksumCapabilities::default()),
+            Ok(repr)
+        );
+    }
+
     #[test]
     fn test_check_len() {
         let bytes = [0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
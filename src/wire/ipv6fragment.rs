@@ -1,8 +1,17 @@
-use super::{Error, Result};
+use super::{Error, Ipv6Address, Ipv6Repr, Result};
 use core::fmt;
 
 use byteorder::{ByteOrder, NetworkEndian};
 
+/// Key used for identifying all the fragments that belong to the same packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Key {
+    pub(crate) src_addr: Ipv6Address,
+    pub(crate) dst_addr: Ipv6Address,
+    pub(crate) ident: u32,
+}
+
 /// A read/write wrapper around an IPv6 Fragment Header.
 #[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -86,6 +95,15 @@ impl<T: AsRef<[u8]>> Header<T> {
         let data = self.buffer.as_ref();
         NetworkEndian::read_u32(&data[field::IDENT])
     }
+
+    /// Returns the key for identifying the packet it belongs to.
+    pub fn get_key(&self, ipv6_repr: &Ipv6Repr) -> Key {
+        Key {
+            src_addr: ipv6_repr.src_addr,
+            dst_addr: ipv6_repr.dst_addr,
+            ident: self.ident(),
+        }
+    }
 }
 
 impl<T: AsRef<[u8]> + AsMut<[u8]>> Header<T> {
@@ -322,19 +322,27 @@ impl<'a> AddressRecordRepr<'a> {
     where
         T: AsRef<[u8]> + ?Sized,
     {
+        // The payload holds the source addresses, followed by the auxiliary data and
+        // the next records.
+        let srcs_len = record.num_srcs() as usize * 16;
+        let payload = record.payload();
+        if payload.len() < srcs_len {
+            return Err(Error);
+        }
+
         Ok(Self {
             num_srcs: record.num_srcs(),
             mcast_addr: record.mcast_addr(),
             record_type: record.record_type(),
             aux_data_len: record.aux_data_len(),
-            payload: record.payload(),
+            payload: &payload[..srcs_len],
         })
     }
 
     /// Return the length of a record that will be emitted from this high-level
-    /// representation, not including any payload data.
+    /// representation, including the source addresses but not the auxiliary data.
     pub fn buffer_len(&self) -> usize {
-        field::RECORD_MCAST_ADDR.end
+        field::RECORD_MCAST_ADDR.end + self.payload.len()
     }
 
     /// Emit a high-level representation into an MLDv2 address record.
@@ -343,6 +351,7 @@ impl<'a> AddressRecordRepr<'a> {
         record.set_aux_data_len(self.aux_data_len);
         record.set_num_srcs(self.num_srcs);
         record.set_mcast_addr(self.mcast_addr);
+        record.payload_mut()[..self.payload.len()].copy_from_slice(self.payload);
     }
 }
 
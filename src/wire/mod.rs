@@ -81,6 +81,8 @@ pub mod pretty_print;
 mod arp;
 #[cfg(feature = "proto-dhcpv4")]
 pub(crate) mod dhcpv4;
+#[cfg(feature = "proto-dhcpv6")]
+pub(crate) mod dhcpv6;
 #[cfg(feature = "proto-dns")]
 pub(crate) mod dns;
 #[cfg(feature = "medium-ethernet")]
@@ -155,8 +157,9 @@ pub use self::arp::{
 #[cfg(feature = "proto-rpl")]
 pub use self::rpl::{
     data::HopByHopOption as RplHopByHopRepr, data::Packet as RplHopByHopPacket,
-    options::Packet as RplOptionPacket, options::Repr as RplOptionRepr,
-    InstanceId as RplInstanceId, Repr as RplRepr,
+    options::OptionType as RplOptionType, options::Packet as RplOptionPacket,
+    options::Repr as RplOptionRepr, InstanceId as RplInstanceId,
+    ModeOfOperation as RplModeOfOperation, Repr as RplRepr,
 };
 
 #[cfg(all(feature = "proto-sixlowpan", feature = "medium-ieee802154"))]
@@ -208,7 +211,9 @@ pub use self::ipv6option::{
 pub use self::ipv6ext_header::{Header as Ipv6ExtHeader, Repr as Ipv6ExtHeaderRepr};
 
 #[cfg(feature = "proto-ipv6")]
-pub use self::ipv6fragment::{Header as Ipv6FragmentHeader, Repr as Ipv6FragmentRepr};
+pub use self::ipv6fragment::{
+    Header as Ipv6FragmentHeader, Key as Ipv6FragKey, Repr as Ipv6FragmentRepr,
+};
 
 #[cfg(feature = "proto-ipv6")]
 pub use self::ipv6hbh::{Header as Ipv6HopByHopHeader, Repr as Ipv6HopByHopRepr};
@@ -226,7 +231,10 @@ pub use self::icmpv4::{
 };
 
 #[cfg(feature = "proto-igmp")]
-pub use self::igmp::{IgmpVersion, Packet as IgmpPacket, Repr as IgmpRepr};
+pub use self::igmp::{
+    GroupRecord as IgmpGroupRecord, GroupRecordRepr as IgmpGroupRecordRepr, IgmpVersion,
+    Packet as IgmpPacket, RecordType as IgmpRecordType, Repr as IgmpRepr,
+};
 
 #[cfg(feature = "proto-ipv6")]
 pub use self::icmpv6::{
@@ -270,9 +278,26 @@ pub use self::tcp::{
 
 #[cfg(feature = "proto-dhcpv4")]
 pub use self::dhcpv4::{
-    DhcpOption, DhcpOptionWriter, Flags as DhcpFlags, MessageType as DhcpMessageType,
-    OpCode as DhcpOpCode, Packet as DhcpPacket, Repr as DhcpRepr, CLIENT_PORT as DHCP_CLIENT_PORT,
-    MAX_DNS_SERVER_COUNT as DHCP_MAX_DNS_SERVER_COUNT, SERVER_PORT as DHCP_SERVER_PORT,
+    ClasslessRoute as DhcpClasslessRoute, ClasslessRoutes as DhcpClasslessRoutes, DhcpOption,
+    DhcpOptionWriter, DomainSearch as DhcpDomainSearch, DomainSearchName as DhcpDomainSearchName,
+    Flags as DhcpFlags, MessageType as DhcpMessageType, OpCode as DhcpOpCode, Packet as DhcpPacket,
+    Repr as DhcpRepr, CLIENT_PORT as DHCP_CLIENT_PORT,
+    MAX_DNS_SERVER_COUNT as DHCP_MAX_DNS_SERVER_COUNT,
+    MAX_NTP_SERVER_COUNT as DHCP_MAX_NTP_SERVER_COUNT, SERVER_PORT as DHCP_SERVER_PORT,
+};
+
+#[cfg(feature = "proto-dhcpv6")]
+pub use self::dhcpv6::{
+    DhcpOption as Dhcpv6Option, DhcpOptionWriter as Dhcpv6OptionWriter,
+    DomainList as Dhcpv6DomainList, DomainName as Dhcpv6DomainName, IaAddress as Dhcpv6IaAddress,
+    IaNa as Dhcpv6IaNa, IaPd as Dhcpv6IaPd, IaPrefix as Dhcpv6IaPrefix,
+    MessageType as Dhcpv6MessageType, Packet as Dhcpv6Packet, Repr as Dhcpv6Repr,
+    StatusCode as Dhcpv6StatusCode,
+    ALL_RELAY_AGENTS_AND_SERVERS as DHCPV6_ALL_RELAY_AGENTS_AND_SERVERS,
+    CLIENT_PORT as DHCPV6_CLIENT_PORT, MAX_DNS_SERVER_COUNT as DHCPV6_MAX_DNS_SERVER_COUNT,
+    MAX_DUID_LEN as DHCPV6_MAX_DUID_LEN,
+    MAX_REQUESTED_OPTION_COUNT as DHCPV6_MAX_REQUESTED_OPTION_COUNT,
+    SERVER_PORT as DHCPV6_SERVER_PORT,
 };
 
 #[cfg(feature = "proto-dns")]